@@ -5,11 +5,19 @@ Development server for Cribl Health Check API.
 Quick start script to run the FastAPI application locally for development.
 """
 
+import os
 import sys
 import argparse
+import asyncio
+import functools
+import multiprocessing
+import threading
+import time
 import uvicorn
 import socket
 
+APP = "cribl_hc.api.app:app"
+
 def find_free_port():
     """Find a free port on the system."""
     with socket.socket(socket.AF_INET, socket.SOCK_STREAM) as s:
@@ -18,26 +26,132 @@ def find_free_port():
         port = s.getsockname()[1]
     return port
 
+def announce_ready(host, port, timeout=30.0):
+    """Print READY once the server accepts connections; the desktop app waits for it."""
+    probe_host = {"0.0.0.0": "127.0.0.1", "": "127.0.0.1", "::": "::1"}.get(host, host)
+    deadline = time.monotonic() + timeout
+    while time.monotonic() < deadline:
+        try:
+            with socket.create_connection((probe_host, port), timeout=1):
+                print("READY", flush=True)
+                return
+        except OSError:
+            time.sleep(0.1)
+
+def _socket_accepts(path):
+    """Whether something is serving the Unix socket or named pipe at path."""
+    try:
+        if sys.platform == "win32":
+            with open(path, "r+b", buffering=0):
+                return True
+        with socket.socket(socket.AF_UNIX, socket.SOCK_STREAM) as s:
+            s.settimeout(1)
+            s.connect(path)
+            return True
+    except OSError:
+        return False
+
+def announce_socket_ready(path, timeout=30.0):
+    """Like announce_ready, for a server on a Unix socket or named pipe."""
+    deadline = time.monotonic() + timeout
+    while time.monotonic() < deadline:
+        if _socket_accepts(path):
+            print("READY", flush=True)
+            return
+        time.sleep(0.1)
+
+class _PipeServers:
+    """Gives the pipe servers asyncio hands back the close/wait_closed uvicorn expects."""
+
+    def __init__(self, servers):
+        self._servers = servers
+
+    def close(self):
+        for server in self._servers:
+            server.close()
+
+    async def wait_closed(self):
+        pass
+
+class PipeServer(uvicorn.Server):
+    """Uvicorn serving a Windows named pipe, which it has no option for."""
+
+    def __init__(self, config, pipe):
+        super().__init__(config)
+        self.pipe = pipe
+
+    async def startup(self, sockets=None):
+        await self.lifespan.startup()
+        if self.lifespan.should_exit:
+            self.should_exit = True
+            return
+
+        create_protocol = functools.partial(
+            self.config.http_protocol_class,
+            config=self.config,
+            server_state=self.server_state,
+            app_state=self.lifespan.state,
+        )
+        # Only the proactor loop, the default on Windows, serves pipes
+        loop = asyncio.get_running_loop()
+        self.servers = [_PipeServers(await loop.start_serving_pipe(create_protocol, self.pipe))]
+        self.started = True
+
+def serve_socket(path, workers):
+    """Serve on a Unix socket, or on Windows a named pipe, instead of a TCP port."""
+    if sys.platform == "win32":
+        # Pipe servers live in this process, so there are no workers to share them with
+        config = uvicorn.Config(APP, log_level="info")
+        PipeServer(config, path).run()
+        return
+
+    if os.path.exists(path):
+        os.remove(path)
+    uvicorn.run(APP, uds=path, workers=workers, log_level="info")
+
 if __name__ == "__main__":
+    # Worker processes re-launch the frozen sidecar; this lets them start as workers
+    multiprocessing.freeze_support()
+
     parser = argparse.ArgumentParser(description='Run Cribl Health Check API server')
     parser.add_argument('--port', type=int, default=8080, help='Port to run on (0 for auto-assign)')
     parser.add_argument('--host', type=str, default='0.0.0.0', help='Host to bind to')
     parser.add_argument('--reload', action='store_true', help='Enable auto-reload')
+    parser.add_argument('--workers', type=int, default=1, help='Number of worker processes (ignored with --reload)')
+    parser.add_argument('--socket', type=str, help='Unix socket path, or on Windows a \\\\.\\pipe\\ name, to serve on instead of a port')
     args = parser.parse_args()
 
+    if args.socket:
+        # Announced like PORT: so the desktop app knows which transport it got
+        print(f"SOCKET:{args.socket}", flush=True)
+        threading.Thread(target=announce_socket_ready, args=(args.socket,), daemon=True).start()
+        serve_socket(args.socket, args.workers)
+        sys.exit(0)
+
     # Auto-assign port if 0
     port = args.port
     if port == 0:
         port = find_free_port()
+    else:
+        # A taken port would still answer the READY probe, so check before announcing it
+        try:
+            with socket.socket(socket.AF_INET, socket.SOCK_STREAM) as s:
+                s.bind((args.host, port))
+        except OSError as e:
+            print(f"Cannot bind {args.host}:{port}: {e.strerror}", file=sys.stderr, flush=True)
+            sys.exit(1)
 
     # Print port for Tauri to capture (must be first output line)
     print(f"PORT:{port}", flush=True)
     sys.stdout.flush()
 
+    threading.Thread(target=announce_ready, args=(args.host, port), daemon=True).start()
+
     uvicorn.run(
-        "cribl_hc.api.app:app",
+        APP,
         host=args.host,
         port=port,
         reload=args.reload,
+        workers=args.workers,
         log_level="info",
     )
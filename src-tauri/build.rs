@@ -1,3 +1,21 @@
+use sha2::{Digest, Sha256};
+use std::{env, fs};
+
 fn main() {
+  embed_backend_hash();
   tauri_build::build()
 }
+
+/// Bakes the SHA-256 of the bundled backend into the binary so the app can
+/// tell when an installer left a stale backend behind.
+fn embed_backend_hash() {
+  let target = env::var("TARGET").unwrap_or_default();
+  let extension = if target.contains("windows") { ".exe" } else { "" };
+  let path = format!("binaries/cribl-hc-backend-{}{}", target, extension);
+  println!("cargo:rerun-if-changed={}", path);
+
+  let hash = fs::read(&path)
+    .map(|bytes| format!("{:x}", Sha256::digest(&bytes)))
+    .unwrap_or_default();
+  println!("cargo:rustc-env=CRIBL_HC_BACKEND_SHA256={}", hash);
+}
@@ -0,0 +1,358 @@
+//! Live analysis updates as Tauri events, so the UI doesn't have to poll.
+//!
+//! `subscribe_analysis_progress` follows the backend's `/api/v1/analysis/ws/<id>`
+//! WebSocket and re-emits what it sends as `analysis-progress`, `analysis-stage`
+//! and, once the analysis ends either way, `analysis-complete`, along with a
+//! notification (see `notifications`).
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader, ReadHalf, WriteHalf};
+use tokio_util::sync::CancellationToken;
+
+use crate::notifications::{self, NotificationCategory};
+use crate::transport::{self, Endpoint};
+use crate::{gateway, PythonBackend};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Larger messages are a protocol error rather than a progress update.
+const MAX_MESSAGE_BYTES: usize = 16 * 1024 * 1024;
+
+const OPCODE_CONTINUATION: u8 = 0x0;
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_BINARY: u8 = 0x2;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+/// Open subscriptions by analysis id.
+#[derive(Default)]
+pub struct ProgressSubscriptions {
+    active: Mutex<HashMap<String, CancellationToken>>,
+}
+
+#[derive(Clone, Serialize)]
+struct AnalysisProgress {
+    analysis_id: String,
+    percent: Option<f64>,
+    /// The analyzer running now, if any.
+    step: Option<String>,
+    completed: Option<u64>,
+    total: Option<u64>,
+}
+
+#[derive(Clone, Serialize)]
+struct AnalysisStage {
+    analysis_id: String,
+    objective: String,
+    /// `running`, `completed` or `failed`.
+    status: String,
+}
+
+#[derive(Clone, Serialize)]
+struct AnalysisComplete {
+    analysis_id: String,
+    /// `completed` or `failed`.
+    status: String,
+    health_score: Option<f64>,
+    error: Option<String>,
+}
+
+struct Frame {
+    fin: bool,
+    opcode: u8,
+    payload: Vec<u8>,
+}
+
+async fn read_frame(reader: &mut (impl AsyncRead + Unpin), limit: usize) -> std::io::Result<Frame> {
+    let mut head = [0u8; 2];
+    reader.read_exact(&mut head).await?;
+
+    let len = match head[1] & 0x7f {
+        126 => reader.read_u16().await? as u64,
+        127 => reader.read_u64().await?,
+        n => n as u64,
+    };
+    if len > limit as u64 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("WebSocket message of {} bytes is too large", len),
+        ));
+    }
+
+    let mut mask = None;
+    if head[1] & 0x80 != 0 {
+        let mut key = [0u8; 4];
+        reader.read_exact(&mut key).await?;
+        mask = Some(key);
+    }
+    let mut payload = vec![0u8; len as usize];
+    reader.read_exact(&mut payload).await?;
+    if let Some(key) = mask {
+        for (i, byte) in payload.iter_mut().enumerate() {
+            *byte ^= key[i % 4];
+        }
+    }
+
+    Ok(Frame {
+        fin: head[0] & 0x80 != 0,
+        opcode: head[0] & 0x0f,
+        payload,
+    })
+}
+
+/// Client frames must be masked (RFC 6455 section 5.3).
+async fn write_frame(writer: &mut (impl AsyncWrite + Unpin), opcode: u8, payload: &[u8]) -> std::io::Result<()> {
+    let mut key = [0u8; 4];
+    getrandom::getrandom(&mut key).map_err(|e| std::io::Error::other(e.to_string()))?;
+
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        n if n < 126 => frame.push(0x80 | n as u8),
+        n if n <= u16::MAX as usize => {
+            frame.push(0x80 | 126);
+            frame.extend_from_slice(&(n as u16).to_be_bytes());
+        }
+        n => {
+            frame.push(0x80 | 127);
+            frame.extend_from_slice(&(n as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(&key);
+    frame.extend(payload.iter().enumerate().map(|(i, byte)| byte ^ key[i % 4]));
+    writer.write_all(&frame).await
+}
+
+type Connection = (
+    BufReader<ReadHalf<Box<dyn transport::Stream>>>,
+    WriteHalf<Box<dyn transport::Stream>>,
+);
+
+/// Opens the WebSocket for `analysis_id` straight to the backend, with the token
+/// the gateway would have added.
+async fn connect(endpoint: &Endpoint, token: Option<String>, analysis_id: &str) -> Result<Connection, String> {
+    let stream = tokio::time::timeout(CONNECT_TIMEOUT, transport::connect(endpoint))
+        .await
+        .map_err(|_| "Timed out connecting to backend".to_string())?
+        .map_err(|e| format!("Failed to connect to backend: {}", e))?;
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut reader = BufReader::new(reader);
+
+    let mut key = [0u8; 16];
+    getrandom::getrandom(&mut key).map_err(|e| format!("Failed to generate WebSocket key: {}", e))?;
+    let token_header = token.map_or_else(String::new, |t| format!("{}: {}\r\n", gateway::TOKEN_HEADER, t));
+    let request = format!(
+        "GET /api/v1/analysis/ws/{} HTTP/1.1\r\nHost: {}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Key: {}\r\nSec-WebSocket-Version: 13\r\n{}\r\n",
+        analysis_id,
+        endpoint.host(),
+        base64::Engine::encode(&base64::engine::general_purpose::STANDARD, key),
+        token_header
+    );
+    writer
+        .write_all(request.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to subscribe to analysis progress: {}", e))?;
+
+    let handshake = async {
+        let mut status = String::new();
+        reader.read_line(&mut status).await?;
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header).await? == 0 || header.trim().is_empty() {
+                return Ok::<_, std::io::Error>(status);
+            }
+        }
+    };
+    let status = tokio::time::timeout(CONNECT_TIMEOUT, handshake)
+        .await
+        .map_err(|_| "Timed out subscribing to analysis progress".to_string())?
+        .map_err(|e| format!("Failed to subscribe to analysis progress: {}", e))?;
+    if status.split_whitespace().nth(1) != Some("101") {
+        return Err(format!("Backend refused the progress stream: {}", status.trim()));
+    }
+
+    Ok((reader, writer))
+}
+
+fn emit(app_handle: &tauri::AppHandle, event: &str, payload: impl Serialize + Clone) {
+    if let Err(e) = app_handle.emit(event, payload) {
+        log::warn!("Failed to emit {}: {}", event, e);
+    }
+}
+
+fn complete(app_handle: &tauri::AppHandle, analysis_id: &str, status: &str, message: &Value) {
+    let payload = AnalysisComplete {
+        analysis_id: analysis_id.to_string(),
+        status: status.to_string(),
+        health_score: message.get("health_score").and_then(Value::as_f64),
+        error: message.get("error").and_then(Value::as_str).map(str::to_string),
+    };
+    match (&*payload.status, payload.health_score) {
+        ("completed", Some(score)) => notifications::notify(
+            app_handle,
+            NotificationCategory::AnalysisComplete,
+            "Analysis complete",
+            &format!("Health score: {:.0}/100", score),
+        ),
+        ("completed", None) => notifications::notify(
+            app_handle,
+            NotificationCategory::AnalysisComplete,
+            "Analysis complete",
+            "Results are ready to view",
+        ),
+        _ => notifications::notify(
+            app_handle,
+            NotificationCategory::AnalysisFailed,
+            "Analysis failed",
+            payload.error.as_deref().unwrap_or("The analysis did not finish"),
+        ),
+    }
+    emit(app_handle, "analysis-complete", payload);
+}
+
+/// Re-emits one backend message; returns whether the analysis has ended.
+fn relay(app_handle: &tauri::AppHandle, analysis_id: &str, message: &Value) -> bool {
+    let text = |key: &str| message.get(key).and_then(Value::as_str).map(str::to_string);
+
+    match message.get("type").and_then(Value::as_str) {
+        Some("progress") => {
+            let payload = AnalysisProgress {
+                analysis_id: analysis_id.to_string(),
+                percent: message.get("percent").and_then(Value::as_f64),
+                step: text("step"),
+                completed: message.get("completed").and_then(Value::as_u64),
+                total: message.get("total").and_then(Value::as_u64),
+            };
+            emit(app_handle, "analysis-progress", payload);
+        }
+        Some("stage") => {
+            let payload = AnalysisStage {
+                analysis_id: analysis_id.to_string(),
+                objective: text("objective").unwrap_or_default(),
+                status: text("status").unwrap_or_default(),
+            };
+            emit(app_handle, "analysis-stage", payload);
+        }
+        Some("complete") => {
+            complete(app_handle, analysis_id, "completed", message);
+            return true;
+        }
+        Some("error") => {
+            complete(app_handle, analysis_id, "failed", message);
+            return true;
+        }
+        // Sent on connect; the analysis may have ended before we subscribed
+        Some("status") => {
+            if let Some(status @ ("completed" | "failed")) = message.get("status").and_then(Value::as_str) {
+                complete(app_handle, analysis_id, status, message);
+                return true;
+            }
+        }
+        _ => {}
+    }
+    false
+}
+
+async fn follow(
+    app_handle: &tauri::AppHandle,
+    analysis_id: &str,
+    (mut reader, mut writer): Connection,
+    cancel: &CancellationToken,
+) -> std::io::Result<()> {
+    let mut message = Vec::new();
+
+    loop {
+        let frame = tokio::select! {
+            frame = read_frame(&mut reader, MAX_MESSAGE_BYTES - message.len()) => frame?,
+            _ = cancel.cancelled() => break,
+        };
+
+        match frame.opcode {
+            // uvicorn drops clients that stop answering its pings
+            OPCODE_PING => write_frame(&mut writer, OPCODE_PONG, &frame.payload).await?,
+            OPCODE_CLOSE => return Ok(()),
+            OPCODE_TEXT | OPCODE_BINARY | OPCODE_CONTINUATION => {
+                message.extend_from_slice(&frame.payload);
+                if !frame.fin {
+                    continue;
+                }
+                let done = match serde_json::from_slice(&message) {
+                    Ok(value) => relay(app_handle, analysis_id, &value),
+                    Err(e) => {
+                        log::debug!("Ignoring unparseable progress message: {}", e);
+                        false
+                    }
+                };
+                message.clear();
+                if done {
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // 1000 is a normal closure
+    write_frame(&mut writer, OPCODE_CLOSE, &1000u16.to_be_bytes()).await
+}
+
+/// Starts relaying `analysis_id`'s progress as events until it completes or
+/// `unsubscribe_analysis_progress` is called. Subscribing twice is a no-op.
+#[tauri::command]
+pub async fn subscribe_analysis_progress(app_handle: tauri::AppHandle, analysis_id: String) -> Result<(), String> {
+    if analysis_id.is_empty() || !analysis_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(format!("Invalid analysis id: {:?}", analysis_id));
+    }
+    let subscriptions = app_handle.state::<ProgressSubscriptions>();
+    if subscriptions.active.lock().unwrap().contains_key(&analysis_id) {
+        return Ok(());
+    }
+
+    let endpoint = app_handle
+        .state::<PythonBackend>()
+        .endpoint
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or("Backend not started yet")?;
+    let connection = connect(&endpoint, gateway::token(&app_handle), &analysis_id).await?;
+
+    let cancel = CancellationToken::new();
+    {
+        let mut active = subscriptions.active.lock().unwrap();
+        if active.contains_key(&analysis_id) {
+            return Ok(());
+        }
+        active.insert(analysis_id.clone(), cancel.clone());
+    }
+
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = follow(&app_handle, &analysis_id, connection, &cancel).await {
+            log::warn!("Progress stream for analysis {} ended: {}", analysis_id, e);
+        }
+        // If cancelled, unsubscribe already removed us, and a newer subscription may be listed
+        if !cancel.is_cancelled() {
+            let subscriptions = app_handle.state::<ProgressSubscriptions>();
+            subscriptions.active.lock().unwrap().remove(&analysis_id);
+        }
+    });
+    Ok(())
+}
+
+/// Stops relaying `analysis_id`'s progress; returns whether it was subscribed.
+#[tauri::command]
+pub fn unsubscribe_analysis_progress(app_handle: tauri::AppHandle, analysis_id: String) -> bool {
+    let subscriptions = app_handle.state::<ProgressSubscriptions>();
+    let token = subscriptions.active.lock().unwrap().remove(&analysis_id);
+    match token {
+        Some(token) => {
+            token.cancel();
+            true
+        }
+        None => false,
+    }
+}
@@ -0,0 +1,137 @@
+use serde::Serialize;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::sidecar;
+
+/// A cold start slower than this usually means a scanner inspected the binary first.
+const SLOW_SPAWN: Duration = Duration::from_secs(10);
+const SPAWN_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Serialize)]
+pub struct AntivirusDiagnosis {
+    binary_path: String,
+    binary_present: bool,
+    /// `None` when no reference hash was bundled.
+    binary_unmodified: Option<bool>,
+    spawn_latency_ms: Option<f64>,
+    /// Windows Defender detections mentioning the backend; `None` where not queryable.
+    defender_detections: Option<u32>,
+    findings: Vec<String>,
+    hints: Vec<String>,
+}
+
+/// Times a full cold start of the backend that exits straight away.
+fn measure_spawn(path: &std::path::Path) -> Result<Duration, String> {
+    let started = Instant::now();
+    let mut child = Command::new(path)
+        .arg("--help")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to start backend: {}", e))?;
+
+    loop {
+        match child.try_wait() {
+            Ok(Some(_)) => return Ok(started.elapsed()),
+            Ok(None) if started.elapsed() > SPAWN_TIMEOUT => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("Backend did not exit within {}s", SPAWN_TIMEOUT.as_secs()));
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(50)),
+            Err(e) => return Err(format!("Failed to wait for backend: {}", e)),
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn defender_detections() -> Option<u32> {
+    let script = "@(Get-MpThreat -ErrorAction Stop | Where-Object { $_.Resources -like '*cribl-hc-backend*' }).Count";
+    let output = Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", script])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+#[cfg(not(target_os = "windows"))]
+fn defender_detections() -> Option<u32> {
+    None
+}
+
+/// Best-effort checks for a scanner quarantining, altering or slowing the backend.
+/// Every check is optional; failures become findings rather than errors.
+#[tauri::command]
+pub async fn diagnose_antivirus(app_handle: tauri::AppHandle) -> Result<AntivirusDiagnosis, String> {
+    let path = sidecar::resolve_path(&app_handle)?;
+    let file = sidecar::file_on_disk(&path);
+    let install_dir = file.parent().map(|p| p.display().to_string()).unwrap_or_default();
+
+    let mut findings = Vec::new();
+    let mut hints = Vec::new();
+    let exclusion_hint = format!("Add an antivirus exclusion for {}", install_dir);
+
+    let binary_present = file.is_file();
+    if !binary_present {
+        findings.push("Backend binary is missing; it may have been quarantined".to_string());
+        hints.push("Restore the file from quarantine or reinstall the app".to_string());
+        hints.push(exclusion_hint.clone());
+    }
+
+    let binary_unmodified = match (binary_present, sidecar::expected_sha256(&app_handle)) {
+        (true, Some(expected)) => match sidecar::sha256_file(&path) {
+            Ok(actual) => Some(actual == expected),
+            Err(e) => {
+                findings.push(e);
+                None
+            }
+        },
+        _ => None,
+    };
+    if binary_unmodified == Some(false) {
+        findings.push("Backend binary differs from the one shipped with this version".to_string());
+        hints.push("Reinstall the app; a scanner may have altered or replaced the file".to_string());
+    }
+
+    let spawn_latency = if binary_present {
+        match measure_spawn(&path) {
+            Ok(latency) => Some(latency),
+            Err(e) => {
+                findings.push(e);
+                hints.push(exclusion_hint.clone());
+                None
+            }
+        }
+    } else {
+        None
+    };
+    if spawn_latency.is_some_and(|latency| latency > SLOW_SPAWN) {
+        findings.push("Backend takes unusually long to start, which often means it is scanned on launch".to_string());
+        hints.push(exclusion_hint.clone());
+    }
+
+    let defender_detections = defender_detections();
+    if defender_detections.is_some_and(|count| count > 0) {
+        findings.push("Windows Defender has flagged the backend".to_string());
+        hints.push("Allow the detection in Windows Security > Protection history".to_string());
+        hints.push(exclusion_hint);
+    }
+
+    hints.dedup();
+
+    Ok(AntivirusDiagnosis {
+        binary_path: file.to_string_lossy().to_string(),
+        binary_present,
+        binary_unmodified,
+        spawn_latency_ms: spawn_latency.map(|d| d.as_secs_f64() * 1000.0),
+        defender_detections,
+        findings,
+        hints,
+    })
+}
@@ -0,0 +1,178 @@
+//! What the running backend says about itself on `/health`. A launch counts as
+//! started only once that answers, since a `PORT:` line just means the socket is bound.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+use crate::startup::{StartupError, StartupErrorKind};
+use crate::transport::{self, Endpoint};
+use crate::PythonBackend;
+
+/// What every build of the API reports as `service`, to tell it apart from
+/// anything else that might answer on the port.
+const SERVICE: &str = "cribl-health-check";
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+const RETRY_INTERVAL: Duration = Duration::from_millis(200);
+
+/// The parts of the `/health` response we rely on.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Health {
+    pub status: String,
+    pub version: String,
+    pub service: String,
+}
+
+/// GETs `path` straight from the backend, with the gateway's token when there is
+/// one, and parses the JSON it answers with.
+pub fn get<T: DeserializeOwned>(endpoint: &Endpoint, path: &str, token: Option<&str>) -> Result<T, String> {
+    let token_header = token.map_or_else(String::new, |t| format!("{}: {}\r\n", crate::gateway::TOKEN_HEADER, t));
+    let request = format!(
+        "GET {} HTTP/1.1\r\nHost: {}\r\n{}Connection: close\r\n\r\n",
+        path,
+        endpoint.host(),
+        token_header
+    );
+    let response = transport::exchange(endpoint, &request, PROBE_TIMEOUT)?;
+    let status = response.lines().next().unwrap_or_default();
+    if !status.starts_with("HTTP/1.1 200") {
+        return Err(format!("Unexpected response {:?}", status));
+    }
+
+    let body = response.split_once("\r\n\r\n").map_or("", |(_, body)| body);
+    serde_json::from_str(body).map_err(|e| format!("Failed to parse response: {}", e))
+}
+
+/// `token` is the one the backend was started with; it rejects `/health` without it.
+fn probe(endpoint: &Endpoint, token: Option<&str>) -> Result<Health, String> {
+    let health: Health = get(endpoint, "/health", token)?;
+    if health.service != SERVICE {
+        return Err(format!("{} is served by {:?}, not the backend", endpoint, health.service));
+    }
+    Ok(health)
+}
+
+/// Polls `/health` until it answers or `max_wait` runs out.
+pub fn wait_until_healthy(endpoint: &Endpoint, token: Option<&str>, max_wait: Duration) -> Result<Health, StartupError> {
+    let deadline = Instant::now() + max_wait;
+    loop {
+        let error = match probe(endpoint, token) {
+            Ok(health) => return Ok(health),
+            Err(e) => e,
+        };
+        if Instant::now() + RETRY_INTERVAL >= deadline {
+            return Err(StartupError::new(
+                StartupErrorKind::HealthCheckFailed,
+                format!("Backend on {} did not pass its health check: {}", endpoint, error),
+            ));
+        }
+        log::debug!("Backend not healthy yet: {}", error);
+        std::thread::sleep(RETRY_INTERVAL);
+    }
+}
+
+/// One probe, for a backend that should already be up (the dev backend).
+pub fn check(port: u16, token: Option<&str>) -> Result<Health, StartupError> {
+    probe(&Endpoint::Tcp(port), token).map_err(|e| {
+        StartupError::new(
+            StartupErrorKind::HealthCheckFailed,
+            format!("Nothing responding on dev port {} — start the backend manually ({})", port, e),
+        )
+    })
+}
+
+#[derive(Serialize)]
+pub struct BackendInfo {
+    pub version: String,
+    pub app_version: String,
+    /// False when the backend and the app come from different releases.
+    pub matches_app: bool,
+    /// Set when the backend listens on TCP.
+    pub port: Option<u16>,
+    /// Set when it listens on a Unix socket or named pipe instead.
+    pub socket: Option<String>,
+}
+
+#[tauri::command]
+pub fn get_backend_info(app_handle: tauri::AppHandle) -> Result<BackendInfo, String> {
+    let state = app_handle.state::<PythonBackend>();
+    let endpoint = state.endpoint.lock().unwrap().clone().ok_or("Backend not started yet")?;
+    let health = state.health.lock().unwrap().clone().ok_or("Backend not started yet")?;
+
+    let app_version = app_handle.package_info().version.to_string();
+    Ok(BackendInfo {
+        matches_app: health.version == app_version,
+        version: health.version,
+        app_version,
+        port: endpoint.port(),
+        socket: match endpoint {
+            Endpoint::Socket(path) => Some(path.display().to_string()),
+            Endpoint::Tcp(_) => None,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    const TOKEN: &str = "launch-token";
+
+    /// Answers `/health` like the backend started with `TOKEN`: 401 without the header.
+    fn protected_backend(connections: usize) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().take(connections) {
+                let mut stream = stream.unwrap();
+                let mut authorized = false;
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap() == 0 || line == "\r\n" {
+                        break;
+                    }
+                    let header = format!("{}: {}", crate::gateway::TOKEN_HEADER, TOKEN);
+                    authorized |= line.trim_end().eq_ignore_ascii_case(&header);
+                }
+                let response = if authorized {
+                    let body = r#"{"status":"healthy","version":"0.4.0","service":"cribl-health-check"}"#;
+                    format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}", body.len(), body)
+                } else {
+                    "HTTP/1.1 401 Unauthorized\r\nContent-Length: 0\r\n\r\n".to_string()
+                };
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        port
+    }
+
+    #[test]
+    fn probe_sends_the_launch_token() {
+        let endpoint = Endpoint::Tcp(protected_backend(1));
+        let health = probe(&endpoint, Some(TOKEN)).unwrap();
+        assert_eq!(health.status, "healthy");
+        assert_eq!(health.version, "0.4.0");
+    }
+
+    #[test]
+    fn probe_without_the_token_is_refused() {
+        let endpoint = Endpoint::Tcp(protected_backend(1));
+        let error = probe(&endpoint, None).unwrap_err();
+        assert!(error.contains("401"), "{}", error);
+    }
+
+    #[test]
+    fn health_gate_passes_a_protected_backend() {
+        let endpoint = Endpoint::Tcp(protected_backend(1));
+        let health = wait_until_healthy(&endpoint, Some(TOKEN), Duration::from_secs(2)).unwrap();
+        assert_eq!(health.service, SERVICE);
+
+        let endpoint = Endpoint::Tcp(protected_backend(100));
+        let error = wait_until_healthy(&endpoint, None, Duration::from_millis(500)).unwrap_err();
+        assert_eq!(error.kind, StartupErrorKind::HealthCheckFailed);
+    }
+}
@@ -0,0 +1,261 @@
+//! Golden baselines: one saved run per profile that later runs are measured
+//! against, so a deployment drifting from a known-good state is noticed.
+//!
+//! Every run saved for a profile's deployment (the one its credentials are for)
+//! is compared with that profile's baseline as it is saved. The drift report
+//! lists findings the baseline didn't have, findings whose severity got worse,
+//! and the change in health score (see `scoring`). It is sent as a
+//! `baseline-drift` event, and raises a notification when the score dropped or
+//! new findings grew past the thresholds in settings. Baseline runs are never
+//! pruned from the history to make room for newer ones.
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{Emitter, Manager};
+
+use crate::settings::{self, SettingsStore};
+
+const SEVERITIES: &[&str] = &["info", "low", "medium", "high", "critical"];
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Baseline {
+    pub run_id: String,
+    /// Milliseconds since the Unix epoch.
+    pub set_at_ms: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BaselineSettings {
+    /// By profile name.
+    pub baselines: BTreeMap<String, Baseline>,
+    /// Notify when a run scores at least this many points below its baseline.
+    pub max_score_drop: f64,
+    /// Notify when a run has more than this many findings its baseline didn't.
+    pub max_new_findings: usize,
+}
+
+impl Default for BaselineSettings {
+    fn default() -> Self {
+        Self {
+            baselines: BTreeMap::new(),
+            max_score_drop: 5.0,
+            max_new_findings: 5,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RegressedCheck {
+    pub worker_group: String,
+    pub finding_id: Option<String>,
+    pub title: String,
+    pub baseline_severity: String,
+    pub severity: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DriftReport {
+    pub profile: String,
+    pub baseline_run_id: String,
+    pub run_id: String,
+    pub baseline_score: f64,
+    pub score: f64,
+    /// `score` minus `baseline_score`; negative when the run is worse.
+    pub score_delta: f64,
+    /// Findings the baseline didn't have, most severe first.
+    pub new_findings: Vec<Value>,
+    /// Findings in both runs whose severity got worse.
+    pub regressed_checks: Vec<RegressedCheck>,
+    /// Baseline findings this run no longer has.
+    pub resolved_count: usize,
+    /// Whether the drift is past a threshold in settings.
+    pub exceeded: bool,
+    pub generated_at: String,
+}
+
+fn baseline_settings(app_handle: &tauri::AppHandle) -> BaselineSettings {
+    app_handle.state::<SettingsStore>().settings.lock().unwrap().baselines.clone()
+}
+
+/// Runs that are some profile's baseline, which history pruning keeps.
+pub fn run_ids(app_handle: &tauri::AppHandle) -> HashSet<String> {
+    baseline_settings(app_handle)
+        .baselines
+        .into_values()
+        .map(|baseline| baseline.run_id)
+        .collect()
+}
+
+fn rank(finding: &Value) -> usize {
+    let severity = severity(finding);
+    SEVERITIES.iter().position(|s| *s == severity).unwrap_or(0)
+}
+
+fn severity(finding: &Value) -> String {
+    finding
+        .get("severity")
+        .and_then(Value::as_str)
+        .unwrap_or("info")
+        .trim()
+        .to_ascii_lowercase()
+}
+
+/// Compares `result` with `baseline` group by group, the way `compare_analyses` matches findings.
+fn drift(profile: &str, baseline: &Value, result: &Value, thresholds: &BaselineSettings) -> DriftReport {
+    let id = |result: &Value| result.get("analysis_id").and_then(Value::as_str).unwrap_or_default().to_string();
+    let before = crate::history::group_findings(baseline);
+    let baseline_count: usize = before.values().map(BTreeMap::len).sum();
+    let mut new_findings = Vec::new();
+    let mut regressed_checks = Vec::new();
+    let mut unchanged = 0;
+
+    for (group, findings) in crate::history::group_findings(result) {
+        for (key, finding) in findings {
+            let Some(was) = before.get(&group).and_then(|g| g.get(&key)) else {
+                new_findings.push(finding);
+                continue;
+            };
+            unchanged += 1;
+            if rank(&finding) > rank(was) {
+                regressed_checks.push(RegressedCheck {
+                    worker_group: group.clone(),
+                    finding_id: finding.get("id").and_then(Value::as_str).map(str::to_string),
+                    title: finding.get("title").and_then(Value::as_str).unwrap_or_default().to_string(),
+                    baseline_severity: severity(was),
+                    severity: severity(&finding),
+                });
+            }
+        }
+    }
+    new_findings.sort_by_key(|f| std::cmp::Reverse(rank(f)));
+
+    let baseline_score = crate::scoring::compute(baseline).overall;
+    let score = crate::scoring::compute(result).overall;
+    let score_delta = ((score - baseline_score) * 10.0).round() / 10.0;
+    let exceeded = -score_delta >= thresholds.max_score_drop || new_findings.len() > thresholds.max_new_findings;
+
+    DriftReport {
+        profile: profile.to_string(),
+        baseline_run_id: id(baseline),
+        run_id: id(result),
+        baseline_score,
+        score,
+        score_delta,
+        new_findings,
+        regressed_checks,
+        resolved_count: baseline_count - unchanged,
+        exceeded,
+        generated_at: Local::now().to_rfc3339(),
+    }
+}
+
+/// The profile with a baseline whose credentials `result` was run with.
+fn profile_for(app_handle: &tauri::AppHandle, result: &Value, baselines: &BaselineSettings) -> Option<String> {
+    let deployment = result.get("deployment_name").and_then(Value::as_str)?;
+    crate::profiles::list_profiles(app_handle.clone())
+        .profiles
+        .into_iter()
+        .find(|p| p.credential.as_deref() == Some(deployment) && baselines.baselines.contains_key(&p.name))
+        .map(|p| p.name)
+}
+
+fn report_for(app_handle: &tauri::AppHandle, result: &Value) -> Result<Option<DriftReport>, String> {
+    let settings = baseline_settings(app_handle);
+    let Some(profile) = profile_for(app_handle, result, &settings) else {
+        return Ok(None);
+    };
+    let baseline_id = settings.baselines[&profile].run_id.clone();
+    if result.get("analysis_id").and_then(Value::as_str) == Some(baseline_id.as_str()) {
+        return Ok(None);
+    }
+    let baseline = crate::history::get_analysis_run(app_handle.clone(), baseline_id)
+        .map_err(|e| format!("Baseline for {} can't be read: {}", profile, e))?;
+    Ok(Some(drift(&profile, &baseline, result, &settings)))
+}
+
+/// Compares a run that was just saved with its profile's baseline, if it has
+/// one, and reports the drift.
+pub fn check(app_handle: &tauri::AppHandle, result: &Value) {
+    let report = match report_for(app_handle, result) {
+        Ok(Some(report)) => report,
+        Ok(None) => return,
+        Err(e) => {
+            log::warn!("{}", e);
+            return;
+        }
+    };
+    if report.exceeded {
+        log::info!(
+            "Run {} of {} drifted from its baseline: {:+.1} points, {} new findings",
+            report.run_id,
+            report.profile,
+            report.score_delta,
+            report.new_findings.len()
+        );
+        crate::notifications::show(
+            app_handle,
+            "Drift from baseline",
+            &format!(
+                "{} scored {:.0}/100 ({:+.1} from its baseline), with {} new findings",
+                report.profile,
+                report.score,
+                report.score_delta,
+                report.new_findings.len()
+            ),
+        );
+    }
+    if let Err(e) = app_handle.emit("baseline-drift", report) {
+        log::warn!("Failed to emit baseline-drift: {}", e);
+    }
+}
+
+/// Makes saved run `run_id` the baseline later runs of `profile` are compared with.
+#[tauri::command]
+pub fn set_baseline(app_handle: tauri::AppHandle, profile: String, run_id: String) -> Result<Baseline, String> {
+    if !crate::profiles::list_profiles(app_handle.clone()).profiles.iter().any(|p| p.name == profile) {
+        return Err(format!("No profile named {}", profile));
+    }
+    // Read first, so only a run that exists and opens becomes a baseline
+    crate::history::get_analysis_run(app_handle.clone(), run_id.clone())?;
+    let baseline = Baseline {
+        run_id,
+        set_at_ms: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64),
+    };
+
+    let store = app_handle.state::<SettingsStore>();
+    let mut current = store.settings.lock().unwrap();
+    current.baselines.baselines.insert(profile, baseline.clone());
+    settings::save(&app_handle, &current)?;
+    Ok(baseline)
+}
+
+/// Stops comparing `profile`'s runs with a baseline. Returns whether it had one.
+#[tauri::command]
+pub fn clear_baseline(app_handle: tauri::AppHandle, profile: String) -> Result<bool, String> {
+    let store = app_handle.state::<SettingsStore>();
+    let mut current = store.settings.lock().unwrap();
+    let cleared = current.baselines.baselines.remove(&profile).is_some();
+    if cleared {
+        settings::save(&app_handle, &current)?;
+    }
+    Ok(cleared)
+}
+
+#[tauri::command]
+pub fn get_baselines(app_handle: tauri::AppHandle) -> BTreeMap<String, Baseline> {
+    baseline_settings(&app_handle).baselines
+}
+
+/// Compares saved run `run_id` with its profile's baseline; `None` when its
+/// deployment has no baseline.
+#[tauri::command]
+pub fn get_drift_report(app_handle: tauri::AppHandle, run_id: String) -> Result<Option<DriftReport>, String> {
+    let result = crate::history::get_analysis_run(app_handle.clone(), run_id)?;
+    report_for(&app_handle, &result)
+}
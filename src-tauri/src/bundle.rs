@@ -0,0 +1,464 @@
+//! Unpacking `cribl diag` bundles for offline analysis.
+//!
+//! Bundles are gzipped tars or zips from a customer's environment, so they are
+//! treated as untrusted: entries must stay inside the workspace, only regular
+//! files and directories are written (never links), and entry count and total
+//! size are capped. Each bundle gets its own workspace under the app cache dir;
+//! the oldest are deleted as new ones are added.
+//!
+//! `extract_diag_bundle` returns a manifest of the worker groups and Edge fleets
+//! found inside, for the frontend to show before an analysis is started.
+
+use flate2::read::MultiGzDecoder;
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read};
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+const WORKSPACES_DIR: &str = "diag-bundles";
+/// Older workspaces are deleted when another bundle is extracted.
+const MAX_WORKSPACES: usize = 5;
+const MAX_BUNDLE_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+/// Guards against archives that decompress to far more than they look.
+const MAX_EXTRACTED_BYTES: u64 = 16 * 1024 * 1024 * 1024;
+const MAX_ENTRIES: usize = 500_000;
+/// How deep below the workspace to look for group configuration.
+const MAX_SCAN_DEPTH: usize = 8;
+
+const BLOCK: usize = 512;
+
+#[derive(Clone, Serialize)]
+pub struct BundleGroup {
+    pub name: String,
+    /// `worker_group` or `fleet`.
+    pub kind: &'static str,
+    /// The group's `local/cribl` (or `local/edge`) directory.
+    pub config_path: String,
+    pub pipelines: usize,
+    pub inputs: usize,
+    pub outputs: usize,
+    pub has_routes: bool,
+}
+
+#[derive(Clone, Serialize)]
+pub struct ExtractedBundleInfo {
+    pub archive: String,
+    pub workspace: String,
+    pub file_count: usize,
+    pub extracted_bytes: u64,
+    /// The configuration of the node the bundle was taken on, outside any group.
+    pub node_config: Option<String>,
+    /// Sorted by name.
+    pub groups: Vec<BundleGroup>,
+}
+
+enum Format {
+    TarGz,
+    Zip,
+}
+
+fn format_of(path: &Path) -> Option<Format> {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        Some(Format::TarGz)
+    } else if name.ends_with(".zip") {
+        Some(Format::Zip)
+    } else {
+        None
+    }
+}
+
+/// Whether `path` has the name of a bundle this module can extract.
+pub fn is_bundle(path: &Path) -> bool {
+    format_of(path).is_some()
+}
+
+/// Running totals checked against the limits as entries are extracted.
+#[derive(Default)]
+struct Extracted {
+    files: usize,
+    entries: usize,
+    bytes: u64,
+}
+
+impl Extracted {
+    fn entry(&mut self) -> Result<(), String> {
+        self.entries += 1;
+        if self.entries > MAX_ENTRIES {
+            return Err(format!("Diag bundle has more than {} entries", MAX_ENTRIES));
+        }
+        Ok(())
+    }
+
+    fn file(&mut self, size: u64) -> Result<(), String> {
+        self.files += 1;
+        self.bytes += size;
+        if self.bytes > MAX_EXTRACTED_BYTES {
+            return Err("Diag bundle is too large once extracted".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Checks an entry name is a relative path that stays inside the workspace.
+fn entry_path(name: &str) -> Result<PathBuf, String> {
+    let mut path = PathBuf::new();
+    for component in Path::new(name).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            _ => return Err(format!("Archive entry escapes the bundle: {}", name)),
+        }
+    }
+    Ok(path)
+}
+
+/// Writes exactly `size` bytes from `reader` to `dest/relative`.
+fn write_entry(dest: &Path, relative: &Path, reader: &mut impl Read, size: u64) -> Result<(), String> {
+    let path = dest.join(relative);
+    let fail = |e: io::Error| format!("Failed to extract {}: {}", relative.display(), e);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(fail)?;
+    }
+    let mut out = File::create(&path).map_err(fail)?;
+    // One byte past the size shows whether the archive under-reported it
+    let copied = io::copy(&mut reader.take(size + 1), &mut out).map_err(fail)?;
+    if copied != size {
+        return Err(format!("Archive entry {} is not the size it claims", relative.display()));
+    }
+    Ok(())
+}
+
+/// Text of a NUL-padded header field.
+fn field(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+/// A numeric header field: octal text, or base-256 when the high bit is set
+/// (GNU tar, for sizes over 8 GiB).
+fn number(bytes: &[u8]) -> Result<u64, String> {
+    if bytes.first().is_some_and(|b| b & 0x80 != 0) {
+        return Ok(bytes[1..].iter().fold(0, |n, &b| (n << 8) | b as u64));
+    }
+    let text = field(bytes);
+    let text = text.trim_matches(|c: char| c == ' ' || c == '\0');
+    if text.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(text, 8).map_err(|_| format!("Invalid number in tar header: {:?}", text))
+}
+
+fn skip(reader: &mut impl Read, bytes: u64) -> Result<(), String> {
+    let skipped = io::copy(&mut reader.take(bytes), &mut io::sink()).map_err(|e| format!("Failed to read archive: {}", e))?;
+    if skipped < bytes {
+        return Err("Diag bundle is truncated".to_string());
+    }
+    Ok(())
+}
+
+fn padding(size: u64) -> u64 {
+    (BLOCK as u64 - size % BLOCK as u64) % BLOCK as u64
+}
+
+fn read_data(reader: &mut impl Read, size: u64) -> Result<Vec<u8>, String> {
+    if size > 1024 * 1024 {
+        return Err("Oversized tar metadata entry".to_string());
+    }
+    let mut data = vec![0u8; size as usize];
+    reader
+        .read_exact(&mut data)
+        .map_err(|e| format!("Failed to read archive: {}", e))?;
+    skip(reader, padding(size))?;
+    Ok(data)
+}
+
+/// The `path` record of a pax extended header, which overrides the next entry's name.
+fn pax_path(data: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(data);
+    text.lines()
+        .filter_map(|record| record.split_once(' ').map(|(_, rest)| rest))
+        .find_map(|record| record.strip_prefix("path="))
+        .map(str::to_string)
+}
+
+fn extract_tar_gz(file: File, dest: &Path, extracted: &mut Extracted) -> Result<(), String> {
+    let mut reader = BufReader::new(MultiGzDecoder::new(BufReader::new(file)));
+    let mut header = [0u8; BLOCK];
+    let mut long_name: Option<String> = None;
+
+    loop {
+        reader
+            .read_exact(&mut header)
+            .map_err(|e| format!("Not a valid diag bundle: {}", e))?;
+        if header.iter().all(|&b| b == 0) {
+            return Ok(());
+        }
+
+        // The checksum is computed with its own field read as spaces
+        let checksum: u64 = header
+            .iter()
+            .enumerate()
+            .map(|(i, &b)| if (148..156).contains(&i) { b' ' as u64 } else { b as u64 })
+            .sum();
+        if checksum != number(&header[148..156])? {
+            return Err("Not a valid diag bundle: bad tar header checksum".to_string());
+        }
+        extracted.entry()?;
+
+        let size = number(&header[124..136])?;
+        let name = long_name.take().unwrap_or_else(|| {
+            let name = field(&header[0..100]);
+            let prefix = field(&header[345..500]);
+            if &header[257..262] == b"ustar" && !prefix.is_empty() {
+                format!("{}/{}", prefix, name)
+            } else {
+                name
+            }
+        });
+
+        match header[156] {
+            // GNU long name, and pax extended headers: both describe the next entry
+            b'L' => long_name = Some(field(&read_data(&mut reader, size)?)),
+            b'x' => long_name = pax_path(&read_data(&mut reader, size)?),
+            b'0' | b'\0' | b'7' => {
+                extracted.file(size)?;
+                write_entry(dest, &entry_path(&name)?, &mut (&mut reader).take(size), size)?;
+                skip(&mut reader, padding(size))?;
+            }
+            b'5' => {
+                fs::create_dir_all(dest.join(entry_path(&name)?))
+                    .map_err(|e| format!("Failed to extract {}: {}", name, e))?;
+                skip(&mut reader, size + padding(size))?;
+            }
+            // Links, devices and FIFOs
+            _ => skip(&mut reader, size + padding(size))?,
+        }
+    }
+}
+
+fn extract_zip(file: File, dest: &Path, extracted: &mut Extracted) -> Result<(), String> {
+    let mut archive =
+        zip::ZipArchive::new(BufReader::new(file)).map_err(|e| format!("Not a valid diag bundle: {}", e))?;
+
+    for i in 0..archive.len() {
+        extracted.entry()?;
+        let mut entry = archive
+            .by_index(i)
+            .map_err(|e| format!("Failed to read archive: {}", e))?;
+        let Some(relative) = entry.enclosed_name() else {
+            return Err(format!("Archive entry escapes the bundle: {}", entry.name()));
+        };
+        // Backslashes aren't separators on Unix, so check the path as Windows would see it too
+        entry_path(&relative.to_string_lossy().replace('\\', "/"))?;
+
+        if entry.is_dir() {
+            fs::create_dir_all(dest.join(&relative))
+                .map_err(|e| format!("Failed to extract {}: {}", relative.display(), e))?;
+        } else if !entry.is_symlink() {
+            let size = entry.size();
+            extracted.file(size)?;
+            write_entry(dest, &relative, &mut entry, size)?;
+        }
+    }
+    Ok(())
+}
+
+/// Deletes the oldest workspaces so that one more fits under `MAX_WORKSPACES`.
+fn prune_workspaces(root: &Path) {
+    let Ok(entries) = fs::read_dir(root) else {
+        return;
+    };
+    let mut workspaces: Vec<(SystemTime, PathBuf)> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|entry| {
+            let modified = entry.metadata().and_then(|m| m.modified()).unwrap_or(UNIX_EPOCH);
+            (modified, entry.path())
+        })
+        .collect();
+    workspaces.sort();
+
+    let excess = (workspaces.len() + 1).saturating_sub(MAX_WORKSPACES);
+    for (_, path) in workspaces.into_iter().take(excess) {
+        if let Err(e) = fs::remove_dir_all(&path) {
+            log::warn!("Failed to remove old diag workspace {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Names of the entries of top-level mapping `section` in a config file, like
+/// the input ids in `inputs.yml`. Cribl writes these files with two-space
+/// indentation, which is all this looks for.
+fn section_keys(text: &str, section: &str) -> Vec<String> {
+    let mut keys = Vec::new();
+    let mut inside = false;
+    for line in text.lines() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        if !line.starts_with(' ') {
+            inside = line.trim_end() == format!("{}:", section);
+            continue;
+        }
+        if inside && line.starts_with("  ") && !line[2..].starts_with(' ') {
+            if let Some((key, _)) = line[2..].split_once(':') {
+                keys.push(key.trim().trim_matches(|c| c == '"' || c == '\'').to_string());
+            }
+        }
+    }
+    keys
+}
+
+/// The Edge fleets listed in a leader's `groups.yml`, whose top-level keys are
+/// group ids.
+fn fleets_in(groups_yml: &str) -> Vec<String> {
+    let mut fleets = Vec::new();
+    let mut current: Option<String> = None;
+    for line in groups_yml.lines() {
+        if !line.starts_with(' ') && line.trim_end().ends_with(':') {
+            current = Some(line.trim_end().trim_end_matches(':').trim_matches(|c| c == '"' || c == '\'').to_string());
+        } else if line.trim() == "isFleet: true" {
+            fleets.extend(current.take());
+        }
+    }
+    fleets
+}
+
+fn describe_group(name: String, kind: &'static str, config: &Path) -> BundleGroup {
+    let read = |file: &str| fs::read_to_string(config.join(file)).unwrap_or_default();
+    let pipelines = fs::read_dir(config.join("pipelines"))
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|entry| entry.path().join("conf.yml").is_file())
+                .count()
+        })
+        .unwrap_or(0);
+
+    BundleGroup {
+        name,
+        kind,
+        config_path: config.to_string_lossy().to_string(),
+        pipelines,
+        inputs: section_keys(&read("inputs.yml"), "inputs").len(),
+        outputs: section_keys(&read("outputs.yml"), "outputs").len(),
+        has_routes: config.join("pipelines").join("route.yml").is_file(),
+    }
+}
+
+/// Finds group configuration (`groups/<name>/local/cribl`, or `local/edge` for
+/// fleets) and the node's own `local/cribl`, at any depth up to `MAX_SCAN_DEPTH`.
+fn scan(workspace: &Path) -> (Option<PathBuf>, Vec<(String, PathBuf)>) {
+    let mut node_config = None;
+    let mut groups = Vec::new();
+
+    let mut pending = vec![(workspace.to_path_buf(), 0)];
+    while let Some((dir, depth)) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if !entry.file_type().is_ok_and(|t| t.is_dir()) {
+                continue;
+            }
+            let path = entry.path();
+            let is_config = dir.file_name().is_some_and(|n| n == "local")
+                && (entry.file_name() == "cribl" || entry.file_name() == "edge");
+            let owner = dir.parent();
+            let group = owner
+                .filter(|owner| owner.parent().and_then(Path::file_name).is_some_and(|n| n == "groups"))
+                .and_then(Path::file_name);
+
+            match (is_config, group) {
+                (true, Some(group)) => groups.push((group.to_string_lossy().to_string(), path)),
+                (true, None) if entry.file_name() == "cribl" && node_config.is_none() => node_config = Some(path),
+                _ if depth < MAX_SCAN_DEPTH => pending.push((path, depth + 1)),
+                _ => {}
+            }
+        }
+    }
+    (node_config, groups)
+}
+
+fn manifest(archive: &Path, workspace: &Path, extracted: &Extracted) -> ExtractedBundleInfo {
+    let (node_config, found) = scan(workspace);
+    let fleets = node_config
+        .as_ref()
+        .and_then(|config| fs::read_to_string(config.join("groups.yml")).ok())
+        .map(|text| fleets_in(&text))
+        .unwrap_or_default();
+
+    let mut groups: Vec<BundleGroup> = found
+        .into_iter()
+        .map(|(name, config)| {
+            let is_fleet = fleets.contains(&name) || config.file_name().is_some_and(|n| n == "edge");
+            let kind = if is_fleet { "fleet" } else { "worker_group" };
+            describe_group(name, kind, &config)
+        })
+        .collect();
+    groups.sort_by(|a, b| a.name.cmp(&b.name).then(a.config_path.cmp(&b.config_path)));
+    groups.dedup_by(|a, b| a.name == b.name);
+
+    ExtractedBundleInfo {
+        archive: archive.to_string_lossy().to_string(),
+        workspace: workspace.to_string_lossy().to_string(),
+        file_count: extracted.files,
+        extracted_bytes: extracted.bytes,
+        node_config: node_config.map(|p| p.to_string_lossy().to_string()),
+        groups,
+    }
+}
+
+/// Extracts `archive` into a new workspace and describes what it contains.
+/// Blocks; the workspace is removed again if extraction fails.
+pub fn unpack(app_handle: &tauri::AppHandle, archive: &Path) -> Result<ExtractedBundleInfo, String> {
+    let format = format_of(archive).ok_or_else(|| format!("Not a .tar.gz, .tgz or .zip file: {}", archive.display()))?;
+    let file = File::open(archive).map_err(|e| format!("Failed to open {}: {}", archive.display(), e))?;
+    let size = file
+        .metadata()
+        .map_err(|e| format!("Failed to open {}: {}", archive.display(), e))?
+        .len();
+    if size > MAX_BUNDLE_BYTES {
+        return Err(format!("{} is too large to be a diag bundle", archive.display()));
+    }
+
+    let root = app_handle
+        .path()
+        .app_cache_dir()
+        .map_err(|e| format!("Failed to get cache dir: {}", e))?
+        .join(WORKSPACES_DIR);
+    fs::create_dir_all(&root).map_err(|e| format!("Failed to create diag workspace: {}", e))?;
+    prune_workspaces(&root);
+
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis());
+    let workspace = root.join(format!("bundle-{}", stamp));
+    fs::create_dir(&workspace).map_err(|e| format!("Failed to create diag workspace: {}", e))?;
+
+    let mut extracted = Extracted::default();
+    let result = match format {
+        Format::TarGz => extract_tar_gz(file, &workspace, &mut extracted),
+        Format::Zip => extract_zip(file, &workspace, &mut extracted),
+    };
+    if let Err(e) = result {
+        let _ = fs::remove_dir_all(&workspace);
+        return Err(e);
+    }
+
+    Ok(manifest(archive, &workspace, &extracted))
+}
+
+/// Safely unpacks a diag bundle (`.tar.gz`, `.tgz` or `.zip`) and returns a
+/// manifest of its worker groups and fleets. Pass `workspace` to the backend
+/// as `bundle_path` to analyze it.
+#[tauri::command]
+pub async fn extract_diag_bundle(app_handle: tauri::AppHandle, path: String) -> Result<ExtractedBundleInfo, String> {
+    tauri::async_runtime::spawn_blocking(move || unpack(&app_handle, Path::new(&path)))
+        .await
+        .map_err(|e| format!("Failed to extract diag bundle: {}", e))?
+}
@@ -0,0 +1,270 @@
+//! Copying findings to the system clipboard as Markdown or plain text, for
+//! pasting into chat threads and change tickets.
+//!
+//! Each finding is written with its severity, worker group, what it affects,
+//! where to find that in the Cribl UI and how to fix it. The clipboard is set
+//! through each platform's own means rather than a plugin: the Win32 clipboard
+//! API on Windows, `pbcopy` on macOS, and `wl-copy`, `xclip` or `xsel` on Linux.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::history::UNGROUPED;
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClipboardFormat {
+    #[default]
+    Markdown,
+    Text,
+}
+
+#[derive(Serialize)]
+pub struct CopiedFindings {
+    pub copied: usize,
+    /// Requested ids the run has no finding for.
+    pub missing: Vec<String>,
+}
+
+/// Where objects of each component kind (the prefix in `pipeline:main`) are in
+/// a worker group's UI.
+const UI_SECTIONS: &[(&str, &str)] = &[
+    ("route", "Routing › Data Routes"),
+    ("pipeline", "Processing › Pipelines"),
+    ("pack", "Processing › Packs"),
+    ("lookup", "Processing › Knowledge › Lookups"),
+    ("input", "Data › Sources"),
+    ("source", "Data › Sources"),
+    ("output", "Data › Destinations"),
+    ("destination", "Data › Destinations"),
+];
+
+fn text<'a>(finding: &'a Value, key: &str) -> &'a str {
+    finding.get(key).and_then(Value::as_str).unwrap_or_default().trim()
+}
+
+fn list<'a>(finding: &'a Value, key: &str) -> Vec<&'a str> {
+    finding
+        .get(key)
+        .and_then(Value::as_array)
+        .map(|items| items.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default()
+}
+
+/// The UI path to `finding`'s first affected object: `metadata.click_path` when
+/// the backend gave one, otherwise worked out from a `kind:id` component.
+fn click_path(finding: &Value) -> Option<String> {
+    let metadata = finding.get("metadata");
+    if let Some(path) = metadata.and_then(|m| m.get("click_path")).and_then(Value::as_str) {
+        return Some(path.to_string());
+    }
+    let (section, name) = list(finding, "affected_components").into_iter().find_map(|component| {
+        let (kind, name) = component.split_once(':')?;
+        let (_, section) = UI_SECTIONS.iter().find(|(k, _)| k.eq_ignore_ascii_case(kind.trim()))?;
+        Some((*section, name.trim()))
+    })?;
+    let group = crate::history::worker_group(finding);
+    if group == UNGROUPED {
+        Some(format!("{} › {}", section, name))
+    } else {
+        Some(format!("Manage › {} › {} › {}", group, section, name))
+    }
+}
+
+fn write_finding(out: &mut String, finding: &Value, format: ClipboardFormat) {
+    let severity = text(finding, "severity").to_ascii_uppercase();
+    let group = crate::history::worker_group(finding);
+    let affected = list(finding, "affected_components").join(", ");
+    let steps = list(finding, "remediation_steps");
+    let mut fields = vec![("Worker group", group)];
+    if !affected.is_empty() {
+        fields.push(("Affected", affected));
+    }
+    if let Some(path) = click_path(finding) {
+        fields.push(("Where", path));
+    }
+
+    match format {
+        ClipboardFormat::Markdown => {
+            out.push_str(&format!("### [{}] {}\n", severity, text(finding, "title")));
+            let description = text(finding, "description");
+            if !description.is_empty() {
+                out.push_str(&format!("{}\n", description));
+            }
+            out.push('\n');
+            for (label, value) in fields {
+                out.push_str(&format!("- **{}:** {}\n", label, value));
+            }
+            if !steps.is_empty() {
+                out.push_str("\n**Remediation**\n");
+                for (i, step) in steps.iter().enumerate() {
+                    out.push_str(&format!("{}. {}\n", i + 1, step));
+                }
+            }
+        }
+        ClipboardFormat::Text => {
+            out.push_str(&format!("[{}] {}\n", severity, text(finding, "title")));
+            let description = text(finding, "description");
+            if !description.is_empty() {
+                out.push_str(&format!("{}\n", description));
+            }
+            for (label, value) in fields {
+                out.push_str(&format!("{}: {}\n", label, value));
+            }
+            if !steps.is_empty() {
+                out.push_str("Remediation:\n");
+                for (i, step) in steps.iter().enumerate() {
+                    out.push_str(&format!("  {}. {}\n", i + 1, step));
+                }
+            }
+        }
+    }
+}
+
+/// `findings` one after another, separated by a blank line.
+pub fn format_findings(findings: &[&Value], format: ClipboardFormat) -> String {
+    let mut out = String::new();
+    for (i, finding) in findings.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        write_finding(&mut out, finding, format);
+    }
+    out
+}
+
+#[cfg(windows)]
+fn write_text(text: &str) -> Result<(), String> {
+    use windows_sys::Win32::Foundation::GlobalFree;
+    use windows_sys::Win32::System::DataExchange::{CloseClipboard, EmptyClipboard, OpenClipboard, SetClipboardData};
+    use windows_sys::Win32::System::Memory::{GlobalAlloc, GlobalLock, GlobalUnlock, GMEM_MOVEABLE};
+    use windows_sys::Win32::System::Ole::CF_UNICODETEXT;
+
+    let wide: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
+    // Another app may have the clipboard open for a moment
+    let mut attempts = 0;
+    while unsafe { OpenClipboard(std::ptr::null_mut()) } == 0 {
+        attempts += 1;
+        if attempts == 10 {
+            return Err(format!("Failed to open the clipboard: {}", std::io::Error::last_os_error()));
+        }
+        std::thread::sleep(std::time::Duration::from_millis(20));
+    }
+    let result = unsafe {
+        EmptyClipboard();
+        let memory = GlobalAlloc(GMEM_MOVEABLE, wide.len() * 2);
+        if memory.is_null() {
+            Err(format!("Failed to copy to the clipboard: {}", std::io::Error::last_os_error()))
+        } else {
+            let target = GlobalLock(memory) as *mut u16;
+            std::ptr::copy_nonoverlapping(wide.as_ptr(), target, wide.len());
+            GlobalUnlock(memory);
+            // On success the clipboard owns the memory
+            if SetClipboardData(u32::from(CF_UNICODETEXT), memory).is_null() {
+                let error = std::io::Error::last_os_error();
+                GlobalFree(memory);
+                Err(format!("Failed to copy to the clipboard: {}", error))
+            } else {
+                Ok(())
+            }
+        }
+    };
+    unsafe { CloseClipboard() };
+    result
+}
+
+/// Pipes `text` into `program` and waits for it to take it.
+#[cfg(not(windows))]
+fn pipe_to(program: &str, args: &[&str], text: &str) -> std::io::Result<std::process::ExitStatus> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new(program)
+        .args(args)
+        // pbcopy reads its input in the locale's encoding
+        .env("LANG", "en_US.UTF-8")
+        .stdin(Stdio::piped())
+        // xclip stays running to serve the selection; a piped stdout would keep us waiting for it
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    child.wait()
+}
+
+#[cfg(target_os = "macos")]
+fn write_text(text: &str) -> Result<(), String> {
+    match pipe_to("pbcopy", &[], text) {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("pbcopy exited with {}", status)),
+        Err(e) => Err(format!("Failed to run pbcopy: {}", e)),
+    }
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn write_text(text: &str) -> Result<(), String> {
+    let mut tools: Vec<(&str, &[&str])> = vec![
+        ("xclip", &["-selection", "clipboard"]),
+        ("xsel", &["--clipboard", "--input"]),
+    ];
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        tools.insert(0, ("wl-copy", &[]));
+    }
+    let mut errors = Vec::new();
+    for (program, args) in tools {
+        match pipe_to(program, args, text) {
+            Ok(status) if status.success() => return Ok(()),
+            Ok(status) => errors.push(format!("{} exited with {}", program, status)),
+            Err(e) => errors.push(format!("{}: {}", program, e)),
+        }
+    }
+    Err(format!(
+        "Failed to copy to the clipboard; install wl-copy, xclip or xsel ({})",
+        errors.join("; ")
+    ))
+}
+
+/// Copies the findings with `finding_ids` from saved run `run_id` (the newest
+/// saved run when `None`) to the clipboard, in the order given.
+#[tauri::command]
+pub async fn copy_findings_to_clipboard(
+    app_handle: tauri::AppHandle,
+    run_id: Option<String>,
+    finding_ids: Vec<String>,
+    format: Option<ClipboardFormat>,
+) -> Result<CopiedFindings, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let run_id = match run_id {
+            Some(run_id) => run_id,
+            None => crate::history::list_analysis_runs(app_handle.clone())?
+                .into_iter()
+                .next()
+                .map(|run| run.analysis_id)
+                .ok_or("There are no saved runs to copy findings from")?,
+        };
+        let result = crate::history::get_analysis_run(app_handle, run_id)?;
+        let all: &[Value] = result.get("findings").and_then(Value::as_array).map_or(&[], Vec::as_slice);
+
+        let mut findings = Vec::new();
+        let mut missing = Vec::new();
+        for id in finding_ids {
+            match all.iter().find(|f| f.get("id").and_then(Value::as_str) == Some(id.as_str())) {
+                Some(finding) => findings.push(finding),
+                None => missing.push(id),
+            }
+        }
+        if findings.is_empty() {
+            return Err("None of the selected findings are in this run".to_string());
+        }
+
+        write_text(&format_findings(&findings, format.unwrap_or_default()))?;
+        Ok(CopiedFindings {
+            copied: findings.len(),
+            missing,
+        })
+    })
+    .await
+    .map_err(|e| format!("Failed to copy findings: {}", e))?
+}
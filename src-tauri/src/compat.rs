@@ -0,0 +1,147 @@
+//! Refusing a backend this build can't work with. Each launch asks the backend
+//! for `/api/v1/version` once it is healthy, and checks its version against the
+//! range embedded at build time (`CRIBL_HC_BACKEND_RANGE`, else `BACKEND_RANGE`)
+//! and its API version against the one the frontend is written for. A backend
+//! outside them fails the launch with `IncompatibleBackend`, rather than being
+//! used until some request breaks.
+//!
+//! `get_version_matrix` reports the app, frontend and backend versions side by
+//! side for the About screen; support bundles include the same.
+
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::Manager;
+
+use crate::startup::{StartupError, StartupErrorKind};
+use crate::transport::Endpoint;
+
+/// Backend releases this build supports, unless the build overrides it.
+const BACKEND_RANGE: &str = ">=0.4.0, <0.5.0";
+/// The API version the frontend's requests are written against.
+const API_VERSION: &str = "v1";
+const FRONTEND_PACKAGE: &str = include_str!("../../frontend/package.json");
+
+/// The parts of the `/api/v1/version` response we rely on.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BackendVersion {
+    pub version: String,
+    pub api_version: String,
+}
+
+/// What a launch's check found.
+#[derive(Clone, Debug, Serialize)]
+pub struct Checked {
+    backend: Option<BackendVersion>,
+    problem: Option<String>,
+}
+
+impl Checked {
+    pub fn into_result(self) -> Result<BackendVersion, StartupError> {
+        match (self.backend, self.problem) {
+            (Some(backend), None) => Ok(backend),
+            (_, problem) => Err(StartupError::new(StartupErrorKind::IncompatibleBackend, problem.unwrap_or_default())),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct CompatState {
+    last: Mutex<Option<Checked>>,
+}
+
+fn backend_range() -> &'static str {
+    option_env!("CRIBL_HC_BACKEND_RANGE").unwrap_or(BACKEND_RANGE)
+}
+
+fn frontend_version() -> Option<String> {
+    let package: serde_json::Value = serde_json::from_str(FRONTEND_PACKAGE).ok()?;
+    package.get("version")?.as_str().map(str::to_string)
+}
+
+/// Why `backend` can't be used with app `app_version`, if it can't.
+fn problem(backend: &BackendVersion, app_version: &str) -> Option<String> {
+    let incompatible = |why: String| {
+        Some(format!(
+            "Backend v{} is incompatible with app v{}: {}",
+            backend.version, app_version, why
+        ))
+    };
+    if backend.api_version != API_VERSION {
+        return incompatible(format!("it serves API {}, the app needs {}", backend.api_version, API_VERSION));
+    }
+    let range = match VersionReq::parse(backend_range()) {
+        Ok(range) => range,
+        Err(e) => return incompatible(format!("the app's backend range {:?} is invalid ({})", backend_range(), e)),
+    };
+    match Version::parse(backend.version.trim_start_matches('v')) {
+        Ok(version) if range.matches(&version) => None,
+        Ok(_) => incompatible(format!("the app needs a backend {}", range)),
+        Err(_) => incompatible(format!("{:?} is not a release version", backend.version)),
+    }
+}
+
+/// Asks the backend on `endpoint` for its version and whether app `app_version`
+/// can work with it.
+pub fn examine(endpoint: &Endpoint, token: Option<&str>, app_version: &str) -> Checked {
+    match crate::backend_info::get::<BackendVersion>(endpoint, "/api/v1/version", token) {
+        Ok(backend) => Checked {
+            problem: problem(&backend, app_version),
+            backend: Some(backend),
+        },
+        // Every supported release reports its version, so one that doesn't is too old
+        Err(e) => Checked {
+            backend: None,
+            problem: Some(format!(
+                "Backend is incompatible with app v{}: it didn't report its version ({})",
+                app_version, e
+            )),
+        },
+    }
+}
+
+/// `examine`s the backend on `endpoint` and fails when this app can't work with
+/// it. The result is kept for `get_version_matrix`.
+pub fn check(app_handle: &tauri::AppHandle, endpoint: &Endpoint) -> Result<BackendVersion, StartupError> {
+    let app_version = app_handle.package_info().version.to_string();
+    let token = crate::gateway::token(app_handle);
+    let checked = examine(endpoint, token.as_deref(), &app_version);
+    *app_handle.state::<CompatState>().last.lock().unwrap() = Some(checked.clone());
+    checked.into_result().inspect_err(|e| log::error!("{}", e.message))
+}
+
+#[derive(Serialize)]
+pub struct VersionMatrix {
+    pub app_version: String,
+    pub frontend_version: Option<String>,
+    /// `None` until a launch got the backend to report it.
+    pub backend_version: Option<String>,
+    pub backend_api_version: Option<String>,
+    /// The backend versions this app accepts.
+    pub supported_backend_range: String,
+    pub supported_api_version: String,
+    /// `None` until a launch checked the backend.
+    pub compatible: Option<bool>,
+    /// Why the last backend checked was refused.
+    pub problem: Option<String>,
+}
+
+pub fn version_matrix(app_handle: &tauri::AppHandle) -> VersionMatrix {
+    let last = app_handle.state::<CompatState>().last.lock().unwrap().clone();
+    let backend = last.as_ref().and_then(|checked| checked.backend.clone());
+    VersionMatrix {
+        app_version: app_handle.package_info().version.to_string(),
+        frontend_version: frontend_version(),
+        backend_version: backend.as_ref().map(|b| b.version.clone()),
+        backend_api_version: backend.map(|b| b.api_version),
+        supported_backend_range: backend_range().to_string(),
+        supported_api_version: API_VERSION.to_string(),
+        compatible: last.as_ref().map(|checked| checked.problem.is_none()),
+        problem: last.and_then(|checked| checked.problem),
+    }
+}
+
+#[tauri::command]
+pub fn get_version_matrix(app_handle: tauri::AppHandle) -> VersionMatrix {
+    version_matrix(&app_handle)
+}
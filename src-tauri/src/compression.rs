@@ -0,0 +1,94 @@
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::Deserialize;
+use std::fs;
+use std::io::{Read, Write};
+
+const GZIP_MAGIC: [u8; 2] = [0x1F, 0x8B];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionFormat {
+    Gzip,
+    Zstd,
+}
+
+impl CompressionFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            CompressionFormat::Gzip => ".gz",
+            CompressionFormat::Zstd => ".zst",
+        }
+    }
+
+    fn detect(bytes: &[u8], path: &str) -> Option<Self> {
+        if bytes.starts_with(&GZIP_MAGIC) {
+            Some(CompressionFormat::Gzip)
+        } else if bytes.starts_with(&ZSTD_MAGIC) {
+            Some(CompressionFormat::Zstd)
+        } else if path.ends_with(".gz") {
+            Some(CompressionFormat::Gzip)
+        } else if path.ends_with(".zst") {
+            Some(CompressionFormat::Zstd)
+        } else {
+            None
+        }
+    }
+}
+
+fn compress(content: &[u8], format: CompressionFormat) -> std::io::Result<Vec<u8>> {
+    match format {
+        CompressionFormat::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(content)?;
+            encoder.finish()
+        }
+        CompressionFormat::Zstd => zstd::encode_all(content, 0),
+    }
+}
+
+fn decompress(content: &[u8], format: CompressionFormat) -> std::io::Result<Vec<u8>> {
+    match format {
+        CompressionFormat::Gzip => {
+            let mut decoded = Vec::new();
+            GzDecoder::new(content).read_to_end(&mut decoded)?;
+            Ok(decoded)
+        }
+        CompressionFormat::Zstd => zstd::decode_all(content),
+    }
+}
+
+#[tauri::command]
+pub async fn save_compressed_with_dialog(
+    app_handle: tauri::AppHandle,
+    filename: String,
+    content: Vec<u8>,
+    format: CompressionFormat,
+) -> Result<String, String> {
+    let compressed = compress(&content, format)
+        .map_err(|e| format!("Failed to compress content: {}", e))?;
+
+    let mut filename = filename;
+    if !filename.ends_with(format.extension()) {
+        filename.push_str(format.extension());
+    }
+
+    let path = crate::dialogs::save_path(&app_handle, &filename).await?;
+    crate::files::write_file_atomic(&path, &compressed)?;
+
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Opens a file and transparently decompresses it; uncompressed files are returned as-is.
+#[tauri::command]
+pub async fn open_compressed_with_dialog(app_handle: tauri::AppHandle) -> Result<Vec<u8>, String> {
+    let path = crate::dialogs::open_path(&app_handle).await?;
+    let content = fs::read(&path).map_err(|e| format!("Failed to read file: {}", e))?;
+
+    match CompressionFormat::detect(&content, &path.to_string_lossy()) {
+        Some(format) => decompress(&content, format)
+            .map_err(|e| format!("Failed to decompress file: {}", e)),
+        None => Ok(content),
+    }
+}
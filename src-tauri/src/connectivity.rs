@@ -0,0 +1,162 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+use tokio::sync::oneshot;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ProbeResult {
+    reachable: bool,
+    status: Option<u16>,
+    latency_ms: Option<f64>,
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ConnectivityReport {
+    backend_url: String,
+    rust: ProbeResult,
+    webview: ProbeResult,
+    diagnosis: String,
+}
+
+#[derive(Default)]
+struct Pending {
+    next_id: u64,
+    waiters: HashMap<u64, oneshot::Sender<ProbeResult>>,
+}
+
+/// Webview probes waiting for the page to report back.
+#[derive(Default)]
+pub struct ProbeRegistry {
+    pending: Mutex<Pending>,
+}
+
+async fn probe_from_rust(client: &reqwest::Client, url: &str) -> ProbeResult {
+    let started = Instant::now();
+    match client.get(url).timeout(PROBE_TIMEOUT).send().await {
+        Ok(response) => ProbeResult {
+            reachable: response.status().is_success(),
+            status: Some(response.status().as_u16()),
+            latency_ms: Some(started.elapsed().as_secs_f64() * 1000.0),
+            error: None,
+        },
+        Err(e) => ProbeResult {
+            error: Some(e.to_string()),
+            ..Default::default()
+        },
+    }
+}
+
+/// Runs a fetch inside the main window, which is subject to the page's CSP and CORS
+/// rules, and waits for it to report back through `report_connectivity_probe`.
+async fn probe_from_webview(app_handle: &tauri::AppHandle, url: &str) -> ProbeResult {
+    let Some(window) = app_handle.get_webview_window("main") else {
+        return ProbeResult {
+            error: Some("Main window not found".to_string()),
+            ..Default::default()
+        };
+    };
+
+    let registry = app_handle.state::<ProbeRegistry>();
+    let (sender, receiver) = oneshot::channel();
+    let id = {
+        let mut pending = registry.pending.lock().unwrap();
+        pending.next_id += 1;
+        let id = pending.next_id;
+        pending.waiters.insert(id, sender);
+        id
+    };
+
+    let url = serde_json::to_string(url).unwrap_or_default();
+    let script = format!(
+        r#"(async () => {{
+  const started = performance.now();
+  let report = {{ id: {id}, ok: false, status: null, error: null, latencyMs: null }};
+  try {{
+    const response = await fetch({url}, {{ cache: "no-store" }});
+    report.ok = response.ok;
+    report.status = response.status;
+    report.latencyMs = performance.now() - started;
+  }} catch (e) {{
+    report.error = String(e);
+  }}
+  await window.__TAURI_INTERNALS__.invoke("report_connectivity_probe", report);
+}})();"#
+    );
+
+    if let Err(e) = window.eval(&script) {
+        registry.pending.lock().unwrap().waiters.remove(&id);
+        return ProbeResult {
+            error: Some(format!("Failed to run probe in webview: {}", e)),
+            ..Default::default()
+        };
+    }
+
+    let result = tokio::time::timeout(PROBE_TIMEOUT, receiver).await;
+    registry.pending.lock().unwrap().waiters.remove(&id);
+
+    match result {
+        Ok(Ok(result)) => result,
+        _ => ProbeResult {
+            error: Some("Webview did not report back in time".to_string()),
+            ..Default::default()
+        },
+    }
+}
+
+fn diagnose(rust: &ProbeResult, webview: &ProbeResult) -> String {
+    match (rust.reachable, webview.reachable) {
+        (true, true) => "Backend is reachable from both the app and the webview".to_string(),
+        (true, false) => {
+            "The app can reach the backend but the webview cannot; check the CSP connect-src and CORS settings"
+                .to_string()
+        }
+        (false, true) => {
+            "The webview can reach the backend but the app cannot; check proxy or firewall settings".to_string()
+        }
+        (false, false) => "Backend is not responding; check that it is running".to_string(),
+    }
+}
+
+/// Checks `/health` from Rust and from the webview and says which side is failing.
+#[tauri::command]
+pub async fn diagnose_connectivity(app_handle: tauri::AppHandle) -> Result<ConnectivityReport, String> {
+    let backend_url = crate::get_backend_url(app_handle.clone())?;
+    let health_url = format!("{}/health", backend_url);
+    let client = reqwest::Client::new();
+
+    let rust = probe_from_rust(&client, &health_url).await;
+    let webview = probe_from_webview(&app_handle, &health_url).await;
+
+    Ok(ConnectivityReport {
+        diagnosis: diagnose(&rust, &webview),
+        backend_url,
+        rust,
+        webview,
+    })
+}
+
+/// Called by the injected probe script, not by the UI.
+#[tauri::command]
+pub fn report_connectivity_probe(
+    app_handle: tauri::AppHandle,
+    id: u64,
+    ok: bool,
+    status: Option<u16>,
+    error: Option<String>,
+    latency_ms: Option<f64>,
+) {
+    let sender = app_handle.state::<ProbeRegistry>().pending.lock().unwrap().waiters.remove(&id);
+    if let Some(sender) = sender {
+        let _ = sender.send(ProbeResult {
+            reachable: ok,
+            status,
+            latency_ms,
+            error,
+        });
+    }
+}
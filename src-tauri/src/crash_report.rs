@@ -0,0 +1,153 @@
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{Emitter, Manager};
+
+use crate::{health, output, proxy, PythonBackend};
+
+const CRASH_REPORT_DIR: &str = "crash-reports";
+const CRASH_REPORT_PREFIX: &str = "backend-crash-";
+/// Older reports are deleted once there are more than this many.
+const MAX_CRASH_REPORTS: usize = 20;
+/// Backend output lines kept in a report, counting back from the crash.
+const CRASH_LOG_LINES: usize = 500;
+
+#[derive(Serialize)]
+struct CrashReport {
+    /// Milliseconds since the Unix epoch.
+    timestamp_ms: u64,
+    app_version: String,
+    exit_code: Option<i32>,
+    /// The signal that killed the backend, on Unix.
+    signal: Option<i32>,
+    core_dumped: bool,
+    uptime_secs: Option<f64>,
+    last_output: Vec<output::OutputLine>,
+    health_history: Vec<health::HealthSample>,
+    throughput: proxy::BackendThroughput,
+}
+
+#[derive(Clone, Serialize)]
+struct CrashReportWritten {
+    path: String,
+    exit_code: Option<i32>,
+    signal: Option<i32>,
+}
+
+pub fn crash_report_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    app_handle
+        .path()
+        .app_log_dir()
+        .map(|dir| dir.join(CRASH_REPORT_DIR))
+        .map_err(|e| format!("Failed to get log dir: {}", e))
+}
+
+/// Report files, oldest first; the timestamp in the name orders them.
+fn report_paths(dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.file_name()
+                        .and_then(|n| n.to_str())
+                        .is_some_and(|n| n.starts_with(CRASH_REPORT_PREFIX) && n.ends_with(".json"))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    paths.sort();
+    paths
+}
+
+#[cfg(unix)]
+fn signal_info(status: &ExitStatus) -> (Option<i32>, bool) {
+    use std::os::unix::process::ExitStatusExt;
+    (status.signal(), status.core_dumped())
+}
+
+#[cfg(not(unix))]
+fn signal_info(_status: &ExitStatus) -> (Option<i32>, bool) {
+    (None, false)
+}
+
+fn write_report(app_handle: &tauri::AppHandle, status: &ExitStatus) -> Result<CrashReportWritten, String> {
+    let timestamp_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64);
+    let (signal, core_dumped) = signal_info(status);
+
+    let mut last_output = output::get_backend_log_buffer(app_handle.clone());
+    last_output.drain(..last_output.len().saturating_sub(CRASH_LOG_LINES));
+
+    let state = app_handle.state::<PythonBackend>();
+    let uptime_secs = state
+        .started
+        .lock()
+        .unwrap()
+        .map(|(at, _)| at.elapsed().as_secs_f64());
+
+    let report = CrashReport {
+        timestamp_ms,
+        app_version: app_handle.package_info().version.to_string(),
+        exit_code: status.code(),
+        signal,
+        core_dumped,
+        uptime_secs,
+        last_output,
+        health_history: health::get_health_history(app_handle.clone()),
+        throughput: proxy::get_backend_throughput(app_handle.clone()),
+    };
+
+    let dir = crash_report_dir(app_handle)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create crash report dir: {}", e))?;
+    let path = dir.join(format!("{}{}.json", CRASH_REPORT_PREFIX, timestamp_ms));
+    let json = serde_json::to_vec_pretty(&report).map_err(|e| format!("Failed to serialize crash report: {}", e))?;
+    crate::files::write_file_atomic(&path, &json)?;
+
+    let paths = report_paths(&dir);
+    for old in &paths[..paths.len().saturating_sub(MAX_CRASH_REPORTS)] {
+        if let Err(e) = fs::remove_file(old) {
+            log::warn!("Failed to remove old crash report {}: {}", old.display(), e);
+        }
+    }
+
+    Ok(CrashReportWritten {
+        path: path.to_string_lossy().to_string(),
+        exit_code: report.exit_code,
+        signal,
+    })
+}
+
+/// Snapshots what we know about a backend that exited abnormally into the log dir
+/// and emits `backend-crash-report` with the file's path. Must run before the
+/// backend is restarted, which resets the metrics.
+pub fn capture(app_handle: &tauri::AppHandle, status: &ExitStatus) {
+    if status.success() {
+        return;
+    }
+
+    match write_report(app_handle, status) {
+        Ok(written) => {
+            log::warn!("Wrote backend crash report to {}", written.path);
+            if let Err(e) = app_handle.emit("backend-crash-report", written) {
+                log::warn!("Failed to emit backend-crash-report: {}", e);
+            }
+        }
+        Err(e) => log::warn!("Failed to write backend crash report: {}", e),
+    }
+}
+
+/// Crash report paths, newest first.
+#[tauri::command]
+pub fn list_crash_reports(app_handle: tauri::AppHandle) -> Result<Vec<String>, String> {
+    let dir = crash_report_dir(&app_handle)?;
+    Ok(report_paths(&dir)
+        .into_iter()
+        .rev()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect())
+}
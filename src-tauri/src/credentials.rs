@@ -0,0 +1,414 @@
+//! Cribl API tokens kept in the OS credential store, keyed by deployment name.
+//!
+//! Secrets go to the macOS Keychain, Windows Credential Manager or the Secret
+//! Service (via libsecret's `secret-tool`) and never touch our own files. The
+//! settings file only lists which deployments have a token, and their URLs.
+
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+use crate::settings::{self, SettingsStore};
+
+/// Service name the platform store files our entries under.
+const SERVICE: &str = "cribl-hc";
+const MAX_DEPLOYMENT_NAME: usize = 128;
+/// Accounts for secrets other than deployment tokens start with this, and no
+/// deployment name may.
+const APP_SECRET_PREFIX: &str = "@";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SavedCredential {
+    pub deployment: String,
+    /// Leader or Cribl Cloud URL the token is for.
+    pub url: String,
+    /// Milliseconds since the Unix epoch.
+    pub saved_at_ms: u64,
+}
+
+fn validate_deployment(deployment: &str) -> Result<(), String> {
+    if deployment.trim().is_empty() {
+        return Err("Deployment name cannot be empty".to_string());
+    }
+    if deployment.len() > MAX_DEPLOYMENT_NAME
+        || deployment.chars().any(char::is_control)
+        || deployment.starts_with(APP_SECRET_PREFIX)
+    {
+        return Err(format!("Invalid deployment name: {:?}", deployment));
+    }
+    Ok(())
+}
+
+/// Tokens and passwords are printable text. Refusing control characters also
+/// keeps a secret from ending the line it is written on, which on macOS is a
+/// command to `security`.
+fn validate_secret(secret: &str) -> Result<(), String> {
+    if secret.chars().any(char::is_control) {
+        return Err("Secrets cannot contain control characters such as line breaks".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+mod keychain {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    use super::SERVICE;
+
+    fn quote(value: &str) -> String {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+
+    /// Commands go through `security -i` on stdin so the secret never appears in
+    /// the process list. Callers have refused secrets with control characters,
+    /// which `quote` leaves as they are.
+    pub fn set(account: &str, secret: &str) -> Result<(), String> {
+        let mut child = Command::new("security")
+            .arg("-i")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run security: {}", e))?;
+        let script = format!(
+            "add-generic-password -U -s {} -a {} -w {}\n",
+            quote(SERVICE),
+            quote(account),
+            quote(secret)
+        );
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(script.as_bytes())
+                .map_err(|e| format!("Failed to write to the keychain: {}", e))?;
+        }
+
+        let output = child
+            .wait_with_output()
+            .map_err(|e| format!("Failed to write to the keychain: {}", e))?;
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !output.status.success() || !stderr.trim().is_empty() {
+            return Err(format!("Failed to write to the keychain: {}", stderr.trim()));
+        }
+        Ok(())
+    }
+
+    pub fn get(account: &str) -> Result<Option<String>, String> {
+        let output = Command::new("security")
+            .args(["find-generic-password", "-s", SERVICE, "-a", account, "-w"])
+            .output()
+            .map_err(|e| format!("Failed to run security: {}", e))?;
+
+        // Exit status 44 is errSecItemNotFound
+        match output.status.code() {
+            Some(0) => Ok(Some(String::from_utf8_lossy(&output.stdout).trim_end_matches('\n').to_string())),
+            Some(44) => Ok(None),
+            _ => Err(format!(
+                "Failed to read from the keychain: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )),
+        }
+    }
+
+    pub fn delete(account: &str) -> Result<(), String> {
+        let output = Command::new("security")
+            .args(["delete-generic-password", "-s", SERVICE, "-a", account])
+            .output()
+            .map_err(|e| format!("Failed to run security: {}", e))?;
+
+        match output.status.code() {
+            Some(0) | Some(44) => Ok(()),
+            _ => Err(format!(
+                "Failed to delete from the keychain: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )),
+        }
+    }
+}
+
+#[cfg(windows)]
+mod keychain {
+    use windows_sys::Win32::Foundation::{GetLastError, ERROR_NOT_FOUND};
+    use windows_sys::Win32::Security::Credentials::{
+        CredDeleteW, CredFree, CredReadW, CredWriteW, CREDENTIALW, CRED_PERSIST_LOCAL_MACHINE, CRED_TYPE_GENERIC,
+    };
+
+    use super::SERVICE;
+
+    /// `CRED_MAX_CREDENTIAL_BLOB_SIZE`; larger writes fail with an unhelpful error.
+    const MAX_BLOB: usize = 5 * 512;
+
+    fn wide(value: &str) -> Vec<u16> {
+        value.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+
+    /// Shows up in Credential Manager as e.g. `cribl-hc:prod`.
+    fn target(account: &str) -> Vec<u16> {
+        wide(&format!("{}:{}", SERVICE, account))
+    }
+
+    pub fn set(account: &str, secret: &str) -> Result<(), String> {
+        let mut target = target(account);
+        let mut user = wide(account);
+        let mut blob = secret.as_bytes().to_vec();
+        if blob.len() > MAX_BLOB {
+            return Err(format!(
+                "Secret is too large for Credential Manager ({} bytes, at most {})",
+                blob.len(),
+                MAX_BLOB
+            ));
+        }
+
+        let credential = CREDENTIALW {
+            Flags: 0,
+            Type: CRED_TYPE_GENERIC,
+            TargetName: target.as_mut_ptr(),
+            Comment: std::ptr::null_mut(),
+            LastWritten: unsafe { std::mem::zeroed() },
+            CredentialBlobSize: blob.len() as u32,
+            CredentialBlob: blob.as_mut_ptr(),
+            Persist: CRED_PERSIST_LOCAL_MACHINE,
+            AttributeCount: 0,
+            Attributes: std::ptr::null_mut(),
+            TargetAlias: std::ptr::null_mut(),
+            UserName: user.as_mut_ptr(),
+        };
+
+        if unsafe { CredWriteW(&credential, 0) } == 0 {
+            return Err(format!(
+                "Failed to write to Credential Manager: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn get(account: &str) -> Result<Option<String>, String> {
+        let target = target(account);
+        let mut credential: *mut CREDENTIALW = std::ptr::null_mut();
+
+        unsafe {
+            if CredReadW(target.as_ptr(), CRED_TYPE_GENERIC, 0, &mut credential) == 0 {
+                if GetLastError() == ERROR_NOT_FOUND {
+                    return Ok(None);
+                }
+                return Err(format!(
+                    "Failed to read from Credential Manager: {}",
+                    std::io::Error::last_os_error()
+                ));
+            }
+
+            let blob = std::slice::from_raw_parts(
+                (*credential).CredentialBlob,
+                (*credential).CredentialBlobSize as usize,
+            );
+            let secret = String::from_utf8_lossy(blob).to_string();
+            CredFree(credential as *const _);
+            Ok(Some(secret))
+        }
+    }
+
+    pub fn delete(account: &str) -> Result<(), String> {
+        let target = target(account);
+        unsafe {
+            if CredDeleteW(target.as_ptr(), CRED_TYPE_GENERIC, 0) == 0 && GetLastError() != ERROR_NOT_FOUND {
+                return Err(format!(
+                    "Failed to delete from Credential Manager: {}",
+                    std::io::Error::last_os_error()
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+mod keychain {
+    use std::io::Write;
+    use std::process::{Command, Output, Stdio};
+
+    use super::SERVICE;
+
+    fn secret_tool(args: &[&str], stdin: Option<&str>) -> Result<Output, String> {
+        let mut child = Command::new("secret-tool")
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => {
+                    "secret-tool was not found; install libsecret-tools to store credentials".to_string()
+                }
+                _ => format!("Failed to run secret-tool: {}", e),
+            })?;
+        if let Some(mut pipe) = child.stdin.take() {
+            if let Some(input) = stdin {
+                pipe.write_all(input.as_bytes())
+                    .map_err(|e| format!("Failed to write to secret-tool: {}", e))?;
+            }
+        }
+        child
+            .wait_with_output()
+            .map_err(|e| format!("Failed to run secret-tool: {}", e))
+    }
+
+    /// The secret is read from stdin so it never appears in the process list.
+    pub fn set(account: &str, secret: &str) -> Result<(), String> {
+        let label = format!("Cribl Health Check: {}", account);
+        let output = secret_tool(
+            &["store", "--label", &label, "service", SERVICE, "deployment", account],
+            Some(secret),
+        )?;
+        if !output.status.success() {
+            return Err(format!(
+                "Failed to write to the Secret Service: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn get(account: &str) -> Result<Option<String>, String> {
+        let output = secret_tool(&["lookup", "service", SERVICE, "deployment", account], None)?;
+
+        // lookup exits 1 with no output when nothing matches
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if stderr.trim().is_empty() {
+                return Ok(None);
+            }
+            return Err(format!("Failed to read from the Secret Service: {}", stderr.trim()));
+        }
+        Ok(Some(String::from_utf8_lossy(&output.stdout).to_string()))
+    }
+
+    pub fn delete(account: &str) -> Result<(), String> {
+        let output = secret_tool(&["clear", "service", SERVICE, "deployment", account], None)?;
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !output.status.success() && !stderr.trim().is_empty() {
+            return Err(format!("Failed to delete from the Secret Service: {}", stderr.trim()));
+        }
+        Ok(())
+    }
+}
+
+/// The token saved for `deployment`, for code that talks to Cribl on the user's
+/// behalf; the frontend never gets it.
+pub fn load_token(deployment: &str) -> Result<Option<String>, String> {
+    validate_deployment(deployment)?;
+    keychain::get(deployment)
+}
+
+/// Keeps an app secret that isn't a deployment token, such as the proxy
+/// password, in the same store; `None` deletes it.
+pub fn store_app_secret(name: &str, secret: Option<&str>) -> Result<(), String> {
+    let account = format!("{}{}", APP_SECRET_PREFIX, name);
+    match secret {
+        Some(secret) => {
+            validate_secret(secret)?;
+            keychain::set(&account, secret)
+        }
+        None => keychain::delete(&account),
+    }
+}
+
+pub fn load_app_secret(name: &str) -> Result<Option<String>, String> {
+    keychain::get(&format!("{}{}", APP_SECRET_PREFIX, name))
+}
+
+/// Stores a token older versions kept in the settings file, listing it in
+/// `settings` the way `save_credentials` would; the caller saves them.
+pub fn adopt_token(settings: &mut settings::AppSettings, deployment: &str, url: &str, token: &str) -> Result<(), String> {
+    validate_deployment(deployment)?;
+    validate_secret(token)?;
+    keychain::set(deployment, token)?;
+    settings.credentials.retain(|c| c.deployment != deployment);
+    settings.credentials.push(SavedCredential {
+        deployment: deployment.to_string(),
+        url: url.to_string(),
+        saved_at_ms: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64),
+    });
+    Ok(())
+}
+
+/// Saves or replaces the token for `deployment` in the OS credential store.
+#[tauri::command]
+pub async fn save_credentials(
+    app_handle: tauri::AppHandle,
+    deployment: String,
+    url: String,
+    token: String,
+) -> Result<SavedCredential, String> {
+    validate_deployment(&deployment)?;
+    if token.is_empty() {
+        return Err("Token cannot be empty".to_string());
+    }
+    validate_secret(&token)?;
+    url::Url::parse(&url).map_err(|e| format!("Invalid deployment URL {}: {}", url, e))?;
+
+    keychain::set(&deployment, &token)?;
+
+    let entry = SavedCredential {
+        deployment,
+        url,
+        saved_at_ms: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64),
+    };
+    let store = app_handle.state::<SettingsStore>();
+    let mut current = store.settings.lock().unwrap();
+    current.credentials.retain(|c| c.deployment != entry.deployment);
+    current.credentials.push(entry.clone());
+    settings::save(&app_handle, &current)?;
+
+    Ok(entry)
+}
+
+/// Deployments with a saved token; the tokens themselves are never returned.
+#[tauri::command]
+pub fn list_credentials(app_handle: tauri::AppHandle) -> Vec<SavedCredential> {
+    let store = app_handle.state::<SettingsStore>();
+    let current = store.settings.lock().unwrap();
+    current.credentials.clone()
+}
+
+/// Removes the token for `deployment`; returns whether one was listed.
+#[tauri::command]
+pub async fn delete_credentials(app_handle: tauri::AppHandle, deployment: String) -> Result<bool, String> {
+    validate_deployment(&deployment)?;
+    keychain::delete(&deployment)?;
+
+    let store = app_handle.state::<SettingsStore>();
+    let mut current = store.settings.lock().unwrap();
+    let before = current.credentials.len();
+    current.credentials.retain(|c| c.deployment != deployment);
+    if current.credentials.len() == before {
+        return Ok(false);
+    }
+    settings::save(&app_handle, &current)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secrets_cannot_break_out_of_their_line() {
+        validate_secret("eyJhbGciOi.J9-_\"quoted\"\\").unwrap();
+        for secret in ["token\ndelete-generic-password -s cribl-hc", "token\r", "to\0ken", "tab\tbed"] {
+            assert!(validate_secret(secret).is_err(), "{:?}", secret);
+        }
+    }
+
+    #[test]
+    fn deployment_names_are_checked() {
+        validate_deployment("prod leader").unwrap();
+        assert!(validate_deployment(" ").is_err());
+        assert!(validate_deployment("@proxy").is_err());
+        assert!(validate_deployment("prod\nx").is_err());
+    }
+}
@@ -0,0 +1,404 @@
+//! A native client for the Cribl REST API, for pulls too big for the Python
+//! backend such as weeks of metrics from a large deployment.
+//!
+//! `collect_metrics` fetches every worker group concurrently, a day at a time
+//! and a page at a time, and writes each group's records as JSON lines to
+//! `<app cache>/metrics/<collection id>/<group>.jsonl`, next to a
+//! `manifest.json` describing the collection. The backend reads those files
+//! instead of calling the API itself. Progress is reported as
+//! `metrics-collection-progress` events.
+//!
+//! Tokens come from the OS credential store (see `credentials`). Requests ask
+//! for gzip, and a 429 or 5xx is retried with backoff, honouring `Retry-After`.
+
+use flate2::read::GzDecoder;
+use reqwest::header::{ACCEPT, ACCEPT_ENCODING, CONTENT_ENCODING, RETRY_AFTER};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{self, File};
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{Emitter, Manager};
+use tokio::sync::Semaphore;
+
+use crate::settings::SettingsStore;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+const PAGE_SIZE: usize = 1000;
+/// Worker groups fetched at once; Cribl Cloud rate-limits per organization.
+const MAX_CONCURRENT_GROUPS: usize = 4;
+const MAX_ATTEMPTS: u32 = 6;
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// Larger pages than this (after decompression) are refused.
+const MAX_RESPONSE_BYTES: u64 = 256 * 1024 * 1024;
+/// Long ranges are fetched in windows of this many seconds, so no single query is huge.
+const WINDOW_SECS: i64 = 24 * 60 * 60;
+
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+    token: String,
+}
+
+/// Backoff before retry `attempt` (1-based): 1 s, 2 s, 4 s, ... up to `MAX_BACKOFF`.
+fn backoff(attempt: u32) -> Duration {
+    Duration::from_secs(1u64 << attempt.saturating_sub(1).min(6)).min(MAX_BACKOFF)
+}
+
+fn retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// `Retry-After` in seconds; the HTTP-date form is rare enough to fall back to backoff.
+fn retry_after(response: &reqwest::Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?;
+    value.trim().parse::<u64>().ok().map(Duration::from_secs)
+}
+
+async fn read_body(response: reqwest::Response) -> Result<Vec<u8>, String> {
+    let gzipped = response
+        .headers()
+        .get(CONTENT_ENCODING)
+        .is_some_and(|value| value.as_bytes().eq_ignore_ascii_case(b"gzip"));
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| format!("Failed to read Cribl API response: {}", e))?;
+    if !gzipped {
+        return Ok(bytes.to_vec());
+    }
+
+    let mut body = Vec::new();
+    GzDecoder::new(bytes.as_ref())
+        .take(MAX_RESPONSE_BYTES + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| format!("Failed to decompress Cribl API response: {}", e))?;
+    if body.len() as u64 > MAX_RESPONSE_BYTES {
+        return Err("Cribl API response is too large".to_string());
+    }
+    Ok(body)
+}
+
+impl Client {
+    /// A client for `deployment`, using its saved URL and token.
+    pub async fn connect(app_handle: &tauri::AppHandle, deployment: &str) -> Result<Client, String> {
+        let base_url = app_handle
+            .state::<SettingsStore>()
+            .settings
+            .lock()
+            .unwrap()
+            .credentials
+            .iter()
+            .find(|c| c.deployment == deployment)
+            .map(|c| c.url.trim_end_matches('/').to_string())
+            .ok_or_else(|| format!("No saved credentials for deployment {}", deployment))?;
+
+        // The platform credential tools block
+        let (name, handle) = (deployment.to_string(), app_handle.clone());
+        let (token, builder) = tauri::async_runtime::spawn_blocking(move || {
+            let mut builder = crate::network::client_builder(&handle)?;
+            if let Some(identity) = crate::client_cert::identity(&handle, &name)? {
+                builder = builder.identity(identity);
+            }
+            Ok::<_, String>((crate::credentials::load_token(&name)?, builder))
+        })
+        .await
+        .map_err(|e| format!("Failed to load token: {}", e))??;
+        let token = token.ok_or_else(|| format!("No saved token for deployment {}", deployment))?;
+
+        let http = builder
+            .timeout(REQUEST_TIMEOUT)
+            .user_agent(concat!("cribl-hc/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+        Ok(Client { http, base_url, token })
+    }
+
+    /// GETs `path`, retrying rate limits and transient failures. `Ok(None)` is a 404.
+    async fn get(&self, path: &str, query: &[(&str, String)]) -> Result<Option<Value>, String> {
+        let url = format!("{}{}", self.base_url, path);
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let result = self
+                .http
+                .get(&url)
+                .query(query)
+                .bearer_auth(&self.token)
+                .header(ACCEPT, "application/json")
+                .header(ACCEPT_ENCODING, "gzip")
+                .send()
+                .await;
+
+            let (wait, error) = match result {
+                Ok(response) if response.status().is_success() => {
+                    let body = read_body(response).await?;
+                    return serde_json::from_slice(&body)
+                        .map(Some)
+                        .map_err(|e| format!("Failed to parse response from {}: {}", path, e));
+                }
+                Ok(response) if response.status() == StatusCode::NOT_FOUND => return Ok(None),
+                Ok(response) if retryable(response.status()) => (
+                    retry_after(&response).unwrap_or_else(|| backoff(attempt)),
+                    format!("{} returned {}", path, response.status()),
+                ),
+                Ok(response) => return Err(format!("Cribl API {} returned {}", path, response.status())),
+                Err(e) if e.is_timeout() || e.is_connect() => (backoff(attempt), format!("{}: {}", path, e)),
+                Err(e) => return Err(format!("Cribl API request to {} failed: {}", path, e)),
+            };
+
+            if attempt >= MAX_ATTEMPTS {
+                return Err(format!("Cribl API {} (gave up after {} attempts)", error, attempt));
+            }
+            let wait = wait.min(MAX_BACKOFF);
+            log::warn!("Cribl API {}; retrying in {} s", error, wait.as_secs());
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Worker group ids, or `None` for a single instance without a leader.
+    pub async fn worker_groups(&self) -> Result<Option<Vec<String>>, String> {
+        let Some(groups) = self.get("/api/v1/master/groups", &[]).await? else {
+            return Ok(None);
+        };
+        let ids = groups["items"]
+            .as_array()
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|group| group["id"].as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(Some(ids))
+    }
+
+    /// Follows `offset`/`limit` paging on a list endpoint, handing each page of
+    /// `items` to `on_page`. Returns how many items there were.
+    pub async fn paginate(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+        mut on_page: impl FnMut(&[Value]) -> Result<(), String>,
+    ) -> Result<usize, String> {
+        let mut offset = 0;
+        loop {
+            let mut page_query = query.to_vec();
+            page_query.push(("offset", offset.to_string()));
+            page_query.push(("limit", PAGE_SIZE.to_string()));
+
+            let Some(page) = self.get(path, &page_query).await? else {
+                return Ok(offset);
+            };
+            let items = page["items"].as_array().map(Vec::as_slice).unwrap_or_default();
+            on_page(items)?;
+            offset += items.len();
+
+            let total = page["count"].as_u64().map(|count| count as usize);
+            if items.len() < PAGE_SIZE || total.is_some_and(|total| offset >= total) {
+                return Ok(offset);
+            }
+        }
+    }
+}
+
+/// Seconds since the Unix epoch; `latest` is exclusive.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct TimeRange {
+    pub earliest: i64,
+    pub latest: i64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GroupMetrics {
+    pub group: String,
+    pub path: String,
+    pub records: usize,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MetricsCollection {
+    pub id: String,
+    pub deployment: String,
+    pub time_range: TimeRange,
+    pub dir: String,
+    pub groups: Vec<GroupMetrics>,
+}
+
+#[derive(Clone, Serialize)]
+struct Progress<'a> {
+    collection_id: &'a str,
+    group: &'a str,
+    records: usize,
+    done: bool,
+    error: Option<&'a str>,
+}
+
+fn emit_progress(app_handle: &tauri::AppHandle, progress: Progress) {
+    if let Err(e) = app_handle.emit("metrics-collection-progress", progress) {
+        log::warn!("Failed to emit metrics-collection-progress: {}", e);
+    }
+}
+
+/// Group ids are usually safe already; anything else becomes `_`.
+fn file_name(group: &str) -> String {
+    let name: String = group
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    format!("{}.jsonl", name)
+}
+
+struct Target {
+    group: String,
+    path: String,
+    file: PathBuf,
+}
+
+async fn collect_group(
+    app_handle: &tauri::AppHandle,
+    client: &Client,
+    collection_id: &str,
+    target: &Target,
+    range: TimeRange,
+) -> Result<usize, String> {
+    let file = File::create(&target.file).map_err(|e| format!("Failed to create {}: {}", target.file.display(), e))?;
+    let mut writer = BufWriter::new(file);
+    let mut records = 0;
+
+    let mut earliest = range.earliest;
+    while earliest < range.latest {
+        let latest = (earliest + WINDOW_SECS).min(range.latest);
+        let query = [("earliest", earliest.to_string()), ("latest", latest.to_string())];
+        client
+            .paginate(&target.path, &query, |items| {
+                for item in items {
+                    serde_json::to_writer(&mut writer, item)
+                        .map_err(|e| e.to_string())
+                        .and_then(|_| writer.write_all(b"\n").map_err(|e| e.to_string()))
+                        .map_err(|e| format!("Failed to write {}: {}", target.file.display(), e))?;
+                }
+                records += items.len();
+                emit_progress(
+                    app_handle,
+                    Progress {
+                        collection_id,
+                        group: &target.group,
+                        records,
+                        done: false,
+                        error: None,
+                    },
+                );
+                Ok(())
+            })
+            .await?;
+        earliest = latest;
+    }
+
+    writer
+        .flush()
+        .map_err(|e| format!("Failed to write {}: {}", target.file.display(), e))?;
+    Ok(records)
+}
+
+fn write_manifest(dir: &Path, collection: &MetricsCollection) -> Result<(), String> {
+    let json = serde_json::to_vec_pretty(collection).map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    crate::files::write_atomic(&dir.join("manifest.json"), |file| {
+        file.write_all(&json).map_err(|e| format!("Failed to write manifest: {}", e))
+    })
+}
+
+/// Pulls `deployment`'s metrics for `time_range` into files the backend reads.
+/// A group that fails is reported in its entry rather than failing the rest.
+#[tauri::command]
+pub async fn collect_metrics(
+    app_handle: tauri::AppHandle,
+    deployment: String,
+    time_range: TimeRange,
+) -> Result<MetricsCollection, String> {
+    if time_range.earliest >= time_range.latest {
+        return Err("Time range must end after it starts".to_string());
+    }
+    let client = Arc::new(Client::connect(&app_handle, &deployment).await?);
+
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis());
+    let id = format!("{}-{}", file_name(&deployment).trim_end_matches(".jsonl"), stamp);
+    let dir = app_handle
+        .path()
+        .app_cache_dir()
+        .map_err(|e| format!("Failed to get cache dir: {}", e))?
+        .join("metrics")
+        .join(&id);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+    let included = crate::profiles::worker_groups(&app_handle, &deployment);
+    let targets: Vec<Target> = match client.worker_groups().await? {
+        Some(groups) => groups
+            .into_iter()
+            .filter(|group| included.as_ref().map_or(true, |included| included.contains(group)))
+            .map(|group| Target {
+                path: format!("/api/v1/m/{}/system/metrics", group),
+                file: dir.join(file_name(&group)),
+                group,
+            })
+            .collect(),
+        None => vec![Target {
+            group: "default".to_string(),
+            path: "/api/v1/system/metrics".to_string(),
+            file: dir.join(file_name("default")),
+        }],
+    };
+
+    let slots = Arc::new(Semaphore::new(MAX_CONCURRENT_GROUPS));
+    let tasks: Vec<_> = targets
+        .into_iter()
+        .map(|target| {
+            let (app_handle, client, slots, id) = (app_handle.clone(), client.clone(), slots.clone(), id.clone());
+            tauri::async_runtime::spawn(async move {
+                let result = match slots.acquire().await {
+                    Ok(_permit) => collect_group(&app_handle, &client, &id, &target, time_range).await,
+                    Err(e) => Err(e.to_string()),
+                };
+                let error = result.as_ref().err().cloned();
+                emit_progress(
+                    &app_handle,
+                    Progress {
+                        collection_id: &id,
+                        group: &target.group,
+                        records: *result.as_ref().unwrap_or(&0),
+                        done: true,
+                        error: error.as_deref(),
+                    },
+                );
+                if let Some(e) = &error {
+                    log::warn!("Failed to collect metrics for group {}: {}", target.group, e);
+                }
+                GroupMetrics {
+                    group: target.group,
+                    path: target.file.to_string_lossy().to_string(),
+                    records: result.unwrap_or(0),
+                    error,
+                }
+            })
+        })
+        .collect();
+
+    let mut groups = Vec::with_capacity(tasks.len());
+    for task in tasks {
+        groups.push(task.await.map_err(|e| format!("Metrics collection failed: {}", e))?);
+    }
+
+    let collection = MetricsCollection {
+        id,
+        deployment,
+        time_range,
+        dir: dir.to_string_lossy().to_string(),
+        groups,
+    };
+    write_manifest(&dir, &collection)?;
+    Ok(collection)
+}
@@ -0,0 +1,318 @@
+use serde::Serialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+use tauri_plugin_deep_link::DeepLinkExt;
+use url::Url;
+
+const SCHEME: &str = "cribl-hc";
+const FINDING_ACTION: &str = "finding";
+const ANALYZE_ACTION: &str = "analyze";
+/// Everything an analyze link may carry; anything else is rejected as a typo.
+const ANALYZE_PARAMS: &[&str] = &["profile", "checks"];
+const MAX_CHECKS: usize = 64;
+
+/// A parsed `cribl-hc://<action>/<path>?<params>` link.
+#[derive(Clone, Debug, Serialize)]
+pub struct DeepLink {
+    url: String,
+    action: String,
+    path: String,
+    params: HashMap<String, String>,
+}
+
+/// Links that arrived before the frontend was ready to listen (cold start).
+#[derive(Default)]
+pub struct DeepLinkState {
+    pending: Mutex<Vec<DeepLink>>,
+}
+
+fn parse(url: &Url) -> Result<DeepLink, String> {
+    if url.scheme() != SCHEME {
+        return Err(format!("Unsupported deep link scheme: {}", url.scheme()));
+    }
+
+    let action = url.host_str().unwrap_or_default().to_string();
+    if action.is_empty() {
+        return Err(format!("Deep link has no action: {}", url));
+    }
+
+    Ok(DeepLink {
+        url: url.to_string(),
+        action,
+        path: url.path().trim_matches('/').to_string(),
+        params: url.query_pairs().into_owned().collect(),
+    })
+}
+
+fn parse_all(urls: Vec<Url>) -> Vec<DeepLink> {
+    urls.iter()
+        .filter_map(|url| {
+            parse(url)
+                .map_err(|e| log::warn!("Ignoring deep link: {}", e))
+                .ok()
+        })
+        .collect()
+}
+
+/// What `cribl-hc://analyze?profile=<name>&checks=all|<a>,<b>` asks for.
+#[derive(Debug)]
+struct AnalyzeLink {
+    profile: String,
+    /// `None` runs the profile's own checks; `Some(None)` runs every analyzer.
+    checks: Option<Option<Vec<String>>>,
+}
+
+fn parse_analyze(link: &DeepLink) -> Result<AnalyzeLink, String> {
+    if let Some(unknown) = link.params.keys().find(|k| !ANALYZE_PARAMS.contains(&k.as_str())) {
+        return Err(format!("Unknown analyze link parameter {:?}", unknown));
+    }
+    let profile = link
+        .params
+        .get("profile")
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .ok_or("Analyze links need a profile")?;
+
+    let checks = match link.params.get("checks").map(|c| c.trim()) {
+        None => None,
+        Some("all") => Some(None),
+        Some(list) => {
+            let mut checks: Vec<String> = Vec::new();
+            for check in list.split(',').map(str::trim).filter(|c| !c.is_empty()) {
+                if check.len() > 64 || !check.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+                    return Err(format!("Invalid check name {:?}", check));
+                }
+                if !checks.iter().any(|c| c == check) {
+                    checks.push(check.to_string());
+                }
+            }
+            if checks.is_empty() {
+                return Err("Analyze link names no checks; use checks=all to run every one".to_string());
+            }
+            if checks.len() > MAX_CHECKS {
+                return Err(format!("Analyze links can name at most {} checks", MAX_CHECKS));
+            }
+            Some(Some(checks))
+        }
+    };
+
+    Ok(AnalyzeLink { profile, checks })
+}
+
+#[derive(Clone, Serialize)]
+struct DeepLinkAnalysis {
+    url: String,
+    profile: Option<String>,
+    analysis_id: Option<String>,
+    /// The checks requested; `None` is every analyzer.
+    analyzers: Option<Vec<String>>,
+    error: Option<String>,
+}
+
+/// Switches to the link's profile and starts the analysis, returning its id and checks.
+async fn start_analysis(app_handle: &tauri::AppHandle, link: &AnalyzeLink) -> Result<(String, Option<Vec<String>>), String> {
+    let profile = crate::profiles::set_active_profile(app_handle.clone(), Some(link.profile.clone()))?
+        .ok_or_else(|| format!("No profile named {}", link.profile))?;
+    let deployment = profile
+        .credential
+        .ok_or_else(|| format!("Profile {} has no saved credentials to analyze with", profile.name))?;
+    let analyzers = link.checks.clone().unwrap_or(profile.analyzers);
+
+    crate::scheduler::wait_for_backend(app_handle).await?;
+    let body = json!({ "deployment_name": deployment, "analyzers": analyzers });
+    let started = crate::proxy::post_json(app_handle, "/api/v1/analysis", &body).await?;
+    let analysis_id = started
+        .get("analysis_id")
+        .and_then(|id| id.as_str())
+        .ok_or("Backend did not return an analysis id")?
+        .to_string();
+    Ok((analysis_id, analyzers))
+}
+
+/// Runs an analyze link and reports the outcome as `deep-link-analysis-started`
+/// or `deep-link-analysis-failed`; progress then arrives as for any analysis.
+async fn analyze(app_handle: tauri::AppHandle, link: DeepLink) {
+    crate::instance::focus_main_window(&app_handle);
+
+    let parsed = parse_analyze(&link);
+    let profile = parsed.as_ref().ok().map(|l| l.profile.clone());
+    let (event, payload) = match parsed {
+        Ok(parsed) => match start_analysis(&app_handle, &parsed).await {
+            Ok((analysis_id, analyzers)) => {
+                log::info!("Started analysis {} for profile {} from a link", analysis_id, parsed.profile);
+                if let Err(e) =
+                    crate::analysis_events::subscribe_analysis_progress(app_handle.clone(), analysis_id.clone()).await
+                {
+                    log::warn!("Failed to follow analysis {}: {}", analysis_id, e);
+                }
+                let payload = DeepLinkAnalysis {
+                    url: link.url,
+                    profile,
+                    analysis_id: Some(analysis_id),
+                    analyzers,
+                    error: None,
+                };
+                ("deep-link-analysis-started", payload)
+            }
+            Err(e) => ("deep-link-analysis-failed", failed(link.url, profile, e)),
+        },
+        Err(e) => ("deep-link-analysis-failed", failed(link.url, profile, e)),
+    };
+    if let Some(error) = &payload.error {
+        log::warn!("Analyze link not run: {}", error);
+    }
+    if let Err(e) = app_handle.emit(event, payload) {
+        log::warn!("Failed to emit {}: {}", event, e);
+    }
+}
+
+fn failed(url: String, profile: Option<String>, error: String) -> DeepLinkAnalysis {
+    DeepLinkAnalysis {
+        url,
+        profile,
+        analysis_id: None,
+        analyzers: None,
+        error: Some(error),
+    }
+}
+
+/// Delivers each link as a `deep-link` event; analyze links are also acted on here,
+/// so the frontend only has to show what happened.
+fn emit(app_handle: &tauri::AppHandle, links: Vec<DeepLink>) {
+    for link in links {
+        if link.action == ANALYZE_ACTION {
+            tauri::async_runtime::spawn(analyze(app_handle.clone(), link.clone()));
+        }
+        if let Err(e) = app_handle.emit("deep-link", link) {
+            log::warn!("Failed to emit deep-link: {}", e);
+        }
+    }
+}
+
+/// Registers the scheme and routes incoming links to the frontend.
+pub fn init(app: &tauri::App) {
+    // Installers register the scheme on Windows/Linux; this covers portable and dev builds.
+    #[cfg(any(windows, target_os = "linux"))]
+    if let Err(e) = app.deep_link().register_all() {
+        log::warn!("Failed to register deep link scheme: {}", e);
+    }
+
+    // Links delivered to the already-running app
+    let handle = app.handle().clone();
+    app.deep_link().on_open_url(move |event| {
+        emit(&handle, parse_all(event.urls()));
+    });
+
+    // The app was launched by a link
+    if let Ok(Some(urls)) = app.deep_link().get_current() {
+        let links = parse_all(urls);
+        app.state::<DeepLinkState>()
+            .pending
+            .lock()
+            .unwrap()
+            .extend(links.iter().cloned());
+        emit(app.handle(), links);
+    }
+}
+
+#[tauri::command]
+pub fn take_pending_deep_links(app_handle: tauri::AppHandle) -> Vec<DeepLink> {
+    std::mem::take(&mut *app_handle.state::<DeepLinkState>().pending.lock().unwrap())
+}
+
+/// Builds `cribl-hc://finding/<id>?analysis=<id>&group=<name>`, which arrives as a
+/// `deep-link` with action `finding` when opened.
+#[tauri::command]
+pub fn create_finding_permalink(
+    finding_id: String,
+    analysis_id: Option<String>,
+    worker_group: Option<String>,
+) -> Result<String, String> {
+    if finding_id.trim().is_empty() {
+        return Err("Finding ID is required".to_string());
+    }
+
+    let mut url = Url::parse(&format!("{}://{}", SCHEME, FINDING_ACTION))
+        .map_err(|e| format!("Failed to build link: {}", e))?;
+    url.path_segments_mut()
+        .map_err(|_| "Failed to build link".to_string())?
+        .push(&finding_id);
+
+    let params: Vec<(&str, String)> = [("analysis", analysis_id), ("group", worker_group)]
+        .into_iter()
+        .filter_map(|(key, value)| value.map(|v| (key, v)))
+        .collect();
+    if !params.is_empty() {
+        url.query_pairs_mut().extend_pairs(params);
+    }
+
+    Ok(url.to_string())
+}
+
+#[derive(Serialize)]
+pub struct FindingLookup {
+    /// The analysis the finding was found in.
+    analysis_id: Option<String>,
+    /// `None` when no analysis the backend still holds has this finding.
+    finding: Option<serde_json::Value>,
+}
+
+async fn findings_in(app_handle: &tauri::AppHandle, analysis_id: &str) -> Vec<serde_json::Value> {
+    let path = format!("/api/v1/analysis/{}/results", analysis_id);
+    match crate::proxy::get_json(app_handle, &path).await {
+        Ok(Some(results)) => results
+            .get("findings")
+            .and_then(|f| f.as_array())
+            .cloned()
+            .unwrap_or_default(),
+        Ok(None) => Vec::new(),
+        // Typically an analysis that is still running
+        Err(e) => {
+            log::debug!("Skipping analysis {} while resolving finding: {}", analysis_id, e);
+            Vec::new()
+        }
+    }
+}
+
+/// Looks up the finding a permalink points at, searching every analysis if the
+/// link doesn't name one. Results live in backend memory, so older links may
+/// come back empty rather than failing.
+#[tauri::command]
+pub async fn resolve_finding_link(
+    app_handle: tauri::AppHandle,
+    finding_id: String,
+    analysis_id: Option<String>,
+) -> Result<FindingLookup, String> {
+    let candidates = match analysis_id {
+        Some(id) => vec![id],
+        None => crate::proxy::get_json(&app_handle, "/api/v1/analysis")
+            .await?
+            .and_then(|list| list.as_array().cloned())
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|a| a.get("analysis_id").and_then(|id| id.as_str()).map(str::to_string))
+            .collect(),
+    };
+
+    for candidate in candidates {
+        let finding = findings_in(&app_handle, &candidate)
+            .await
+            .into_iter()
+            .find(|f| f.get("id").and_then(|id| id.as_str()) == Some(finding_id.as_str()));
+
+        if finding.is_some() {
+            return Ok(FindingLookup {
+                analysis_id: Some(candidate),
+                finding,
+            });
+        }
+    }
+
+    log::info!("Finding {} from a permalink is no longer available", finding_id);
+    Ok(FindingLookup {
+        analysis_id: None,
+        finding: None,
+    })
+}
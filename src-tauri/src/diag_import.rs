@@ -0,0 +1,93 @@
+//! Offline analysis of `cribl diag` bundles dropped onto the main window.
+//!
+//! A dropped `.tar.gz`, `.tgz` or `.zip` is extracted into a fresh workspace (see
+//! `bundle`), then analyzed by the backend, which reads the bundle's
+//! configuration in place of a live leader. Each step is reported as a
+//! `diag-import` event.
+
+use serde::Serialize;
+use std::path::PathBuf;
+use tauri::Emitter;
+
+#[derive(Clone, Serialize)]
+struct DiagImport {
+    path: String,
+    /// `extracting`, `analyzing` or `failed`.
+    status: &'static str,
+    /// What the bundle contains, once extracted.
+    bundle: Option<crate::bundle::ExtractedBundleInfo>,
+    analysis_id: Option<String>,
+    error: Option<String>,
+}
+
+fn report(app_handle: &tauri::AppHandle, update: DiagImport) {
+    if let Err(e) = app_handle.emit("diag-import", update) {
+        log::warn!("Failed to emit diag-import: {}", e);
+    }
+}
+
+async fn import(
+    app_handle: &tauri::AppHandle,
+    archive: PathBuf,
+) -> Result<(crate::bundle::ExtractedBundleInfo, String), String> {
+    let handle = app_handle.clone();
+    let source = archive.clone();
+    let bundle = tauri::async_runtime::spawn_blocking(move || crate::bundle::unpack(&handle, &source))
+        .await
+        .map_err(|e| format!("Failed to extract diag bundle: {}", e))??;
+
+    let name = archive.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let body = serde_json::json!({
+        "deployment_name": format!("diag: {}", name),
+        "bundle_path": bundle.workspace,
+    });
+    let started = crate::proxy::post_json(app_handle, "/api/v1/analysis", &body).await?;
+    let analysis_id = started
+        .get("analysis_id")
+        .and_then(|id| id.as_str())
+        .ok_or("Backend did not return an analysis id")?
+        .to_string();
+    Ok((bundle, analysis_id))
+}
+
+/// Imports any diag bundles among files dropped on the main window; other
+/// files are left to the frontend.
+pub fn on_drop(app_handle: &tauri::AppHandle, paths: &[PathBuf]) {
+    for path in paths.iter().filter(|p| crate::bundle::is_bundle(p)).cloned() {
+        let app_handle = app_handle.clone();
+        tauri::async_runtime::spawn(async move {
+            let display = path.to_string_lossy().to_string();
+            report(
+                &app_handle,
+                DiagImport {
+                    path: display.clone(),
+                    status: "extracting",
+                    bundle: None,
+                    analysis_id: None,
+                    error: None,
+                },
+            );
+
+            let update = match import(&app_handle, path).await {
+                Ok((bundle, analysis_id)) => DiagImport {
+                    path: display,
+                    status: "analyzing",
+                    bundle: Some(bundle),
+                    analysis_id: Some(analysis_id),
+                    error: None,
+                },
+                Err(e) => {
+                    log::warn!("Failed to import diag bundle {}: {}", display, e);
+                    DiagImport {
+                        path: display,
+                        status: "failed",
+                        bundle: None,
+                        analysis_id: None,
+                        error: Some(e),
+                    }
+                }
+            };
+            report(&app_handle, update);
+        });
+    }
+}
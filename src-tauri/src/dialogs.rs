@@ -0,0 +1,235 @@
+//! Native file dialogs that don't hold up app shutdown.
+//!
+//! Every open dialog registers a waiter here. When the app starts exiting,
+//! `cancel_all` releases those waiters so the calling commands return right
+//! away instead of blocking teardown. A save abandoned mid-dialog during quit
+//! writes nothing: the command returns an error and the native dialog goes away
+//! with the process.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+use tauri_plugin_dialog::{DialogExt, FilePath};
+use tokio::sync::oneshot;
+
+const SHUTDOWN_MESSAGE: &str = "Dialog dismissed because the app is shutting down";
+
+#[derive(Default)]
+struct Pending {
+    next_id: u64,
+    waiters: HashMap<u64, oneshot::Sender<Option<FilePath>>>,
+}
+
+/// Running min/avg/max without keeping every sample.
+#[derive(Clone, Copy, Default)]
+struct Latency {
+    count: u64,
+    total: Duration,
+    min: Duration,
+    max: Duration,
+}
+
+impl Latency {
+    fn record(&mut self, sample: Duration) {
+        self.min = if self.count == 0 { sample } else { self.min.min(sample) };
+        self.max = self.max.max(sample);
+        self.total += sample;
+        self.count += 1;
+    }
+
+    fn summary(&self) -> Option<LatencySummary> {
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        (self.count > 0).then(|| LatencySummary {
+            count: self.count,
+            min_ms: ms(self.min),
+            avg_ms: ms(self.total) / self.count as f64,
+            max_ms: ms(self.max),
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub struct LatencySummary {
+    count: u64,
+    min_ms: f64,
+    avg_ms: f64,
+    max_ms: f64,
+}
+
+#[derive(Serialize)]
+pub struct DialogLatencyStats {
+    /// From invocation until the dialog is dismissed. The dialog plugin reports
+    /// nothing when the dialog appears, so this includes the user's time.
+    dialog: Option<LatencySummary>,
+    /// Writing the file once a path was chosen.
+    write: Option<LatencySummary>,
+}
+
+#[derive(Default)]
+pub struct DialogRegistry {
+    pending: Mutex<Pending>,
+    shutting_down: AtomicBool,
+    save_dialog_latency: Mutex<Latency>,
+    save_write_latency: Mutex<Latency>,
+}
+
+impl DialogRegistry {
+    fn register(&self, sender: oneshot::Sender<Option<FilePath>>) -> Result<u64, String> {
+        if self.shutting_down.load(Ordering::SeqCst) {
+            return Err(SHUTDOWN_MESSAGE.to_string());
+        }
+
+        let mut pending = self.pending.lock().unwrap();
+        pending.next_id += 1;
+        let id = pending.next_id;
+        pending.waiters.insert(id, sender);
+        Ok(id)
+    }
+
+    fn complete(&self, id: u64, path: Option<FilePath>) {
+        if let Some(sender) = self.pending.lock().unwrap().waiters.remove(&id) {
+            let _ = sender.send(path);
+        }
+    }
+}
+
+async fn wait_for_dialog(
+    app_handle: &tauri::AppHandle,
+    show: impl FnOnce(Box<dyn FnOnce(Option<FilePath>) + Send>),
+) -> Result<Option<PathBuf>, String> {
+    let registry = app_handle.state::<DialogRegistry>();
+    let (sender, receiver) = oneshot::channel();
+    let id = registry.register(sender)?;
+
+    let handle = app_handle.clone();
+    show(Box::new(move |path| {
+        handle.state::<DialogRegistry>().complete(id, path);
+    }));
+
+    // The sender is dropped without a value when `cancel_all` runs.
+    let path = receiver.await.map_err(|_| SHUTDOWN_MESSAGE.to_string())?;
+    if registry.shutting_down.load(Ordering::SeqCst) {
+        return Err(SHUTDOWN_MESSAGE.to_string());
+    }
+
+    match path {
+        Some(FilePath::Path(path)) => Ok(Some(path)),
+        // The xdg-desktop-portal file chooser answers with file:// URIs
+        Some(FilePath::Url(url)) => url
+            .to_file_path()
+            .map(Some)
+            .map_err(|_| format!("URL paths not supported: {}", url)),
+        None => Ok(None),
+    }
+}
+
+/// Shows the native save dialog and returns the chosen local path, keeping the
+/// suggested filename's extension if the user typed a name without one.
+pub async fn save_path(app_handle: &tauri::AppHandle, filename: &str) -> Result<PathBuf, String> {
+    let extension = Path::new(filename).extension().and_then(|e| e.to_str());
+    save_path_with_extension(app_handle, filename, extension).await
+}
+
+/// Like `save_path`, appending `extension` when the chosen path has none.
+pub async fn save_path_with_extension(
+    app_handle: &tauri::AppHandle,
+    filename: &str,
+    extension: Option<&str>,
+) -> Result<PathBuf, String> {
+    let mut builder = app_handle.dialog().file().set_file_name(filename);
+    if let Some(dir) = crate::settings::export_dir(app_handle) {
+        builder = builder.set_directory(dir);
+    }
+    let started = Instant::now();
+    let path = wait_for_dialog(app_handle, |done| builder.save_file(done)).await?;
+    app_handle
+        .state::<DialogRegistry>()
+        .save_dialog_latency
+        .lock()
+        .unwrap()
+        .record(started.elapsed());
+    let path = path.ok_or_else(|| "Save cancelled".to_string())?;
+
+    let extension = extension.map(|e| e.trim_start_matches('.')).filter(|e| !e.is_empty());
+    match extension {
+        Some(extension) if path.extension().is_none() => {
+            let mut path = path.into_os_string();
+            path.push(".");
+            path.push(extension);
+            Ok(PathBuf::from(path))
+        }
+        _ => Ok(path),
+    }
+}
+
+/// Shows the native open dialog and returns the chosen local path.
+pub async fn open_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let builder = app_handle.dialog().file();
+    wait_for_dialog(app_handle, |done| builder.pick_file(done))
+        .await?
+        .ok_or_else(|| "Open cancelled".to_string())
+}
+
+/// Like `open_path`, listing only files with one of `extensions` (without dots).
+pub async fn open_path_with_filter(
+    app_handle: &tauri::AppHandle,
+    name: &str,
+    extensions: &[&str],
+) -> Result<PathBuf, String> {
+    let builder = app_handle.dialog().file().add_filter(name, extensions);
+    wait_for_dialog(app_handle, |done| builder.pick_file(done))
+        .await?
+        .ok_or_else(|| "Open cancelled".to_string())
+}
+
+/// Shows the native folder picker and returns the chosen directory.
+#[tauri::command]
+pub async fn pick_directory_with_dialog(
+    app_handle: tauri::AppHandle,
+    title: Option<String>,
+) -> Result<String, String> {
+    let mut builder = app_handle.dialog().file();
+    if let Some(title) = title {
+        builder = builder.set_title(title);
+    }
+
+    let path = wait_for_dialog(&app_handle, |done| builder.pick_folder(done))
+        .await?
+        .ok_or_else(|| "Selection cancelled".to_string())?;
+
+    if !path.is_dir() {
+        return Err(format!("Not a directory: {}", path.display()));
+    }
+
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Records how long writing a file chosen in a save dialog took.
+pub fn record_save_write(app_handle: &tauri::AppHandle, elapsed: Duration) {
+    app_handle
+        .state::<DialogRegistry>()
+        .save_write_latency
+        .lock()
+        .unwrap()
+        .record(elapsed);
+}
+
+/// Save dialog and write timings since launch, to tell a slow OS dialog from slow disk.
+#[tauri::command]
+pub fn get_dialog_latency_stats(app_handle: tauri::AppHandle) -> DialogLatencyStats {
+    let registry = app_handle.state::<DialogRegistry>();
+    let dialog = registry.save_dialog_latency.lock().unwrap().summary();
+    let write = registry.save_write_latency.lock().unwrap().summary();
+    DialogLatencyStats { dialog, write }
+}
+
+/// Releases every pending dialog and refuses new ones; called when the app starts exiting.
+pub fn cancel_all(app_handle: &tauri::AppHandle) {
+    let registry = app_handle.state::<DialogRegistry>();
+    registry.shutting_down.store(true, Ordering::SeqCst);
+    registry.pending.lock().unwrap().waiters.clear();
+}
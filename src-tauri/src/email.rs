@@ -0,0 +1,560 @@
+//! Analysis reports by email, sent through an SMTP server.
+//!
+//! One server is configured at a time; its password is kept in the OS credential
+//! store. `send_report_email` sends a saved run as a PDF or CSV attachment, and
+//! a schedule with `email` set sends every run it finishes (see `scheduler`).
+//!
+//! The client speaks just enough SMTP for that: implicit TLS (usually port 465)
+//! or STARTTLS (587), AUTH PLAIN or LOGIN, and one message per connection.
+//! Credentials are never sent in the clear; `SmtpSecurity::None` is for a relay
+//! on the local network that takes mail without authentication.
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::Manager;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio_rustls::rustls::{self, pki_types::ServerName};
+
+use crate::settings::{self, SettingsStore};
+
+const PASSWORD_SECRET: &str = "smtp-password";
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+/// A large attachment over a slow link takes longer than a command.
+const DATA_TIMEOUT: Duration = Duration::from_secs(300);
+const MAX_RECIPIENTS: usize = 50;
+/// Longest reply line read from the server.
+const MAX_REPLY_LINE: u64 = 4096;
+/// Servers may check the EHLO name against DNS; none of them reject this one.
+const EHLO_NAME: &str = "localhost";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpSecurity {
+    /// Upgrade a plain connection, refusing servers that don't offer STARTTLS.
+    #[default]
+    StartTls,
+    /// TLS from the first byte.
+    Tls,
+    /// Neither encryption nor authentication.
+    None,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EmailSettings {
+    pub host: String,
+    /// 587, 465 or 25 by `security` when unset.
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub security: SmtpSecurity,
+    /// Sent with AUTH when set.
+    #[serde(default)]
+    pub username: Option<String>,
+    /// Sender address, e.g. `health-check@example.com`.
+    pub from: String,
+    /// Whether a password is saved in the OS credential store; set by `configure_email`.
+    #[serde(default)]
+    pub has_password: bool,
+}
+
+impl EmailSettings {
+    fn port(&self) -> u16 {
+        self.port.unwrap_or(match self.security {
+            SmtpSecurity::StartTls => 587,
+            SmtpSecurity::Tls => 465,
+            SmtpSecurity::None => 25,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    #[default]
+    Pdf,
+    Csv,
+}
+
+/// Who a schedule's runs are emailed to, and in what form.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScheduledEmail {
+    pub recipients: Vec<String>,
+    pub format: ReportFormat,
+}
+
+fn config(app_handle: &tauri::AppHandle) -> Option<EmailSettings> {
+    app_handle.state::<SettingsStore>().settings.lock().unwrap().email.clone()
+}
+
+/// A bare `local@domain` address; anything that could break out of a header or
+/// an SMTP command is refused.
+fn valid_address(address: &str) -> bool {
+    let Some((local, domain)) = address.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && !domain.is_empty()
+        && !domain.contains('@')
+        && address.len() <= 254
+        && address.chars().all(|c| c.is_ascii_graphic() && !"<>()[]\\,;:\"".contains(c))
+}
+
+pub fn validate_recipients(recipients: &[String]) -> Result<(), String> {
+    if recipients.is_empty() {
+        return Err("At least one recipient is needed".to_string());
+    }
+    if recipients.len() > MAX_RECIPIENTS {
+        return Err(format!("At most {} recipients can be emailed at once", MAX_RECIPIENTS));
+    }
+    match recipients.iter().find(|r| !valid_address(r)) {
+        Some(invalid) => Err(format!("Invalid email address {:?}", invalid)),
+        None => Ok(()),
+    }
+}
+
+fn validate(config: &mut EmailSettings) -> Result<(), String> {
+    config.host = config.host.trim().to_string();
+    if config.host.is_empty() || config.host.contains(|c: char| c.is_whitespace() || c == '/' || c == ':') {
+        return Err(format!("Invalid SMTP host {:?}", config.host));
+    }
+    if config.port == Some(0) {
+        return Err("Invalid SMTP port 0".to_string());
+    }
+    config.from = config.from.trim().to_string();
+    if !valid_address(&config.from) {
+        return Err(format!("Invalid sender address {:?}", config.from));
+    }
+    config.username = config.username.take().map(|u| u.trim().to_string()).filter(|u| !u.is_empty());
+    if config.security == SmtpSecurity::None && config.username.is_some() {
+        return Err("Signing in to an SMTP server needs STARTTLS or TLS".to_string());
+    }
+    Ok(())
+}
+
+/// One SMTP connection, plain or TLS.
+struct Smtp<S> {
+    stream: BufReader<S>,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Smtp<S> {
+    fn new(stream: S) -> Self {
+        Self {
+            stream: BufReader::new(stream),
+        }
+    }
+
+    /// Reads one reply, which may span several lines, and returns its lines
+    /// without the code if the code is one of `expected`.
+    async fn reply(&mut self, expected: &[u16]) -> Result<Vec<String>, String> {
+        let mut lines = Vec::new();
+        loop {
+            let mut line = Vec::new();
+            let read = tokio::time::timeout(
+                COMMAND_TIMEOUT,
+                (&mut self.stream).take(MAX_REPLY_LINE).read_until(b'\n', &mut line),
+            )
+            .await
+            .map_err(|_| "Timed out waiting for the SMTP server".to_string())?
+            .map_err(|e| format!("Failed to read from the SMTP server: {}", e))?;
+            if read == 0 {
+                return Err("The SMTP server closed the connection".to_string());
+            }
+
+            let line = String::from_utf8_lossy(&line).trim_end().to_string();
+            let code: u16 = line
+                .get(..3)
+                .and_then(|code| code.parse().ok())
+                .ok_or_else(|| format!("Unexpected reply from the SMTP server: {:?}", line))?;
+            let last = line.as_bytes().get(3) != Some(&b'-');
+            lines.push(line.get(4..).unwrap_or_default().to_string());
+            if last {
+                if !expected.contains(&code) {
+                    return Err(format!("SMTP server replied {} {}", code, lines.join(" ")));
+                }
+                return Ok(lines);
+            }
+        }
+    }
+
+    async fn write(&mut self, bytes: &[u8], timeout: Duration) -> Result<(), String> {
+        let stream = self.stream.get_mut();
+        tokio::time::timeout(timeout, async {
+            stream.write_all(bytes).await?;
+            stream.flush().await
+        })
+        .await
+        .map_err(|_| "Timed out sending to the SMTP server".to_string())?
+        .map_err(|e| format!("Failed to send to the SMTP server: {}", e))
+    }
+
+    async fn command(&mut self, command: &str, expected: &[u16]) -> Result<Vec<String>, String> {
+        self.write(format!("{}\r\n", command).as_bytes(), COMMAND_TIMEOUT).await?;
+        self.reply(expected).await
+    }
+
+    /// The extensions the server offers, upper-cased, e.g. `AUTH PLAIN LOGIN`.
+    async fn ehlo(&mut self) -> Result<Vec<String>, String> {
+        let lines = self.command(&format!("EHLO {}", EHLO_NAME), &[250]).await?;
+        Ok(lines.into_iter().skip(1).map(|line| line.to_ascii_uppercase()).collect())
+    }
+
+    async fn authenticate(&mut self, extensions: &[String], username: &str, password: &str) -> Result<(), String> {
+        let mechanisms: Vec<&str> = extensions
+            .iter()
+            .filter_map(|e| e.strip_prefix("AUTH "))
+            .flat_map(str::split_whitespace)
+            .collect();
+        let encode = |text: &str| base64::engine::general_purpose::STANDARD.encode(text);
+        if mechanisms.contains(&"PLAIN") {
+            self.command(&format!("AUTH PLAIN {}", encode(&format!("\0{}\0{}", username, password))), &[235])
+                .await?;
+        } else if mechanisms.contains(&"LOGIN") {
+            self.command("AUTH LOGIN", &[334]).await?;
+            self.command(&encode(username), &[334]).await?;
+            self.command(&encode(password), &[235]).await?;
+        } else {
+            return Err("The SMTP server offers neither AUTH PLAIN nor AUTH LOGIN".to_string());
+        }
+        Ok(())
+    }
+
+    async fn deliver(mut self, from: &str, recipients: &[String], message: &[u8]) -> Result<(), String> {
+        self.command(&format!("MAIL FROM:<{}>", from), &[250]).await?;
+        for recipient in recipients {
+            self.command(&format!("RCPT TO:<{}>", recipient), &[250, 251]).await?;
+        }
+        self.command("DATA", &[354]).await?;
+        self.write(&dot_stuffed(message), DATA_TIMEOUT).await?;
+        self.reply(&[250]).await?;
+        // The message is accepted; a server that hangs up instead of answering QUIT changes nothing
+        let _ = self.command("QUIT", &[221]).await;
+        Ok(())
+    }
+}
+
+/// `message` as DATA: lines starting with a dot get another, then the final `.` line.
+fn dot_stuffed(message: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(message.len() + 8);
+    let mut line_start = true;
+    for &byte in message {
+        if line_start && byte == b'.' {
+            data.push(b'.');
+        }
+        data.push(byte);
+        line_start = byte == b'\n';
+    }
+    if !line_start {
+        data.extend_from_slice(b"\r\n");
+    }
+    data.extend_from_slice(b".\r\n");
+    data
+}
+
+async fn tls<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: S,
+    host: &str,
+    ca_bundle: Option<&str>,
+) -> Result<tokio_rustls::client::TlsStream<S>, String> {
+    let config = rustls::ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("Failed to set up TLS: {}", e))?
+        .with_root_certificates(crate::preflight::roots(ca_bundle)?)
+        .with_no_client_auth();
+    let server_name =
+        ServerName::try_from(host.to_string()).map_err(|e| format!("Invalid server name {}: {}", host, e))?;
+    tokio::time::timeout(
+        COMMAND_TIMEOUT,
+        tokio_rustls::TlsConnector::from(Arc::new(config)).connect(server_name, stream),
+    )
+    .await
+    .map_err(|_| format!("Timed out in the TLS handshake with {}", host))?
+    .map_err(|e| format!("TLS handshake with {} failed: {}", host, e))
+}
+
+/// Everything after the greeting, and after STARTTLS if there is one.
+async fn session<S: AsyncRead + AsyncWrite + Unpin>(
+    mut smtp: Smtp<S>,
+    config: &EmailSettings,
+    password: Option<&str>,
+    recipients: &[String],
+    message: &[u8],
+) -> Result<(), String> {
+    let extensions = smtp.ehlo().await?;
+    if let Some(username) = &config.username {
+        let password = password.ok_or("No SMTP password is saved")?;
+        smtp.authenticate(&extensions, username, password).await?;
+    }
+    smtp.deliver(&config.from, recipients, message).await
+}
+
+async fn send_message(
+    config: &EmailSettings,
+    password: Option<&str>,
+    ca_bundle: Option<&str>,
+    recipients: &[String],
+    message: &[u8],
+) -> Result<(), String> {
+    let address = format!("{}:{}", config.host, config.port());
+    let stream = tokio::time::timeout(COMMAND_TIMEOUT, tokio::net::TcpStream::connect(&address))
+        .await
+        .map_err(|_| format!("Timed out connecting to {}", address))?
+        .map_err(|e| format!("Failed to connect to {}: {}", address, e))?;
+
+    match config.security {
+        SmtpSecurity::Tls => {
+            let mut smtp = Smtp::new(tls(stream, &config.host, ca_bundle).await?);
+            smtp.reply(&[220]).await?;
+            session(smtp, config, password, recipients, message).await
+        }
+        SmtpSecurity::StartTls => {
+            let mut smtp = Smtp::new(stream);
+            smtp.reply(&[220]).await?;
+            if !smtp.ehlo().await?.iter().any(|e| e == "STARTTLS") {
+                return Err(format!("{} does not offer STARTTLS", config.host));
+            }
+            smtp.command("STARTTLS", &[220]).await?;
+            // Anything already buffered came in the clear and could have been injected
+            if !smtp.stream.buffer().is_empty() {
+                return Err("The SMTP server sent data before the TLS handshake".to_string());
+            }
+            let smtp = Smtp::new(tls(smtp.stream.into_inner(), &config.host, ca_bundle).await?);
+            session(smtp, config, password, recipients, message).await
+        }
+        SmtpSecurity::None => {
+            let mut smtp = Smtp::new(stream);
+            smtp.reply(&[220]).await?;
+            session(smtp, config, password, recipients, message).await
+        }
+    }
+}
+
+/// Header text with line breaks removed, and RFC 2047-encoded unless it is ASCII.
+fn header_text(text: &str) -> String {
+    let text: String = text.chars().map(|c| if c.is_control() { ' ' } else { c }).collect();
+    if text.is_ascii() {
+        text
+    } else {
+        format!("=?utf-8?B?{}?=", base64::engine::general_purpose::STANDARD.encode(text))
+    }
+}
+
+/// Base64 in 76-character lines, as MIME requires.
+fn base64_lines(data: &[u8]) -> String {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(data);
+    let mut lines = String::with_capacity(encoded.len() + encoded.len() / 38 + 2);
+    for chunk in encoded.as_bytes().chunks(76) {
+        lines.push_str(std::str::from_utf8(chunk).unwrap_or_default());
+        lines.push_str("\r\n");
+    }
+    lines
+}
+
+fn random_hex(len: usize) -> Result<String, String> {
+    let mut bytes = vec![0u8; len];
+    getrandom::getrandom(&mut bytes).map_err(|e| format!("Failed to generate random bytes: {}", e))?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+struct Attachment {
+    filename: String,
+    content_type: &'static str,
+    data: Vec<u8>,
+}
+
+fn attachment(result: &Value, run_id: &str, format: ReportFormat) -> Result<Attachment, String> {
+    Ok(match format {
+        ReportFormat::Pdf => Attachment {
+            filename: format!("{}.pdf", run_id),
+            content_type: "application/pdf",
+            data: crate::report::render(result)?,
+        },
+        ReportFormat::Csv => {
+            let (headers, rows) = crate::export::findings_table(result);
+            Attachment {
+                filename: format!("{}.csv", run_id),
+                content_type: "text/csv",
+                data: crate::export::csv_bytes(&headers, &rows)?,
+            }
+        }
+    })
+}
+
+/// The subject and a plain-text summary of the attached report.
+fn summary(result: &Value) -> (String, String) {
+    let text = |key: &str| result.get(key).and_then(Value::as_str).filter(|s| !s.is_empty());
+    let deployment = text("deployment_name").or_else(|| text("deployment_id")).unwrap_or("Cribl deployment");
+    let score = result.get("health_score").and_then(Value::as_f64);
+    let subject = match score {
+        Some(score) => format!("Cribl Health Check: {} scored {:.0}/100", deployment, score),
+        None => format!("Cribl Health Check report for {}", deployment),
+    };
+
+    let findings = result.get("findings").and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default();
+    let mut body = format!("Health check of {}", deployment);
+    if let Some(completed) = text("completed_at") {
+        body.push_str(&format!(", completed {}", completed));
+    }
+    body.push_str(".\r\n\r\n");
+    if let Some(score) = score {
+        body.push_str(&format!("Health score: {:.0}/100\r\n", score));
+    }
+    body.push_str(&format!("Findings: {}\r\n", findings.len()));
+    for severity in ["critical", "high", "medium", "low", "info"] {
+        let count = findings
+            .iter()
+            .filter(|f| f.get("severity").and_then(Value::as_str) == Some(severity))
+            .count();
+        if count > 0 {
+            body.push_str(&format!("  {}: {}\r\n", severity, count));
+        }
+    }
+    body.push_str("\r\nThe full report is attached.\r\n");
+    (subject, body)
+}
+
+fn message(
+    from: &str,
+    recipients: &[String],
+    subject: &str,
+    body: &str,
+    attachment: &Attachment,
+) -> Result<Vec<u8>, String> {
+    let boundary = format!("cribl-hc-{}", random_hex(12)?);
+    let domain = from.split_once('@').map_or("localhost", |(_, domain)| domain);
+    let mut message = format!(
+        "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nMessage-ID: <{}@{}>\r\nMIME-Version: 1.0\r\n",
+        from,
+        recipients.join(", "),
+        header_text(subject),
+        chrono::Local::now().to_rfc2822(),
+        random_hex(16)?,
+        domain
+    );
+    message.push_str(&format!("Content-Type: multipart/mixed; boundary=\"{}\"\r\n\r\n", boundary));
+    message.push_str(&format!(
+        "--{}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: base64\r\n\r\n{}",
+        boundary,
+        base64_lines(body.as_bytes())
+    ));
+    message.push_str(&format!(
+        "--{}\r\nContent-Type: {}; name=\"{}\"\r\nContent-Disposition: attachment; filename=\"{}\"\r\nContent-Transfer-Encoding: base64\r\n\r\n{}",
+        boundary,
+        attachment.content_type,
+        attachment.filename,
+        attachment.filename,
+        base64_lines(&attachment.data)
+    ));
+    message.push_str(&format!("--{}--\r\n", boundary));
+    Ok(message.into_bytes())
+}
+
+async fn send_report(
+    app_handle: &tauri::AppHandle,
+    run_id: String,
+    recipients: Vec<String>,
+    format: ReportFormat,
+) -> Result<(), String> {
+    validate_recipients(&recipients)?;
+    let config = config(app_handle).ok_or("Email is not set up")?;
+    let ca_bundle = app_handle
+        .state::<SettingsStore>()
+        .settings
+        .lock()
+        .unwrap()
+        .network
+        .ca_bundle
+        .clone();
+
+    // Reading the run, rendering it and reading the password all block
+    let handle = app_handle.clone();
+    let (from, to, id) = (config.from.clone(), recipients.clone(), run_id.clone());
+    let has_password = config.has_password;
+    let (message, password) = tauri::async_runtime::spawn_blocking(move || {
+        let result = crate::history::get_analysis_run(handle, id.clone())?;
+        let (subject, body) = summary(&result);
+        let message = message(&from, &to, &subject, &body, &attachment(&result, &id, format)?)?;
+        let password = if has_password {
+            crate::credentials::load_app_secret(PASSWORD_SECRET)?
+        } else {
+            None
+        };
+        Ok::<_, String>((message, password))
+    })
+    .await
+    .map_err(|e| format!("Failed to prepare report email: {}", e))??;
+
+    send_message(&config, password.as_deref(), ca_bundle.as_deref(), &recipients, &message).await?;
+    log::info!("Emailed {} to {} recipients", run_id, recipients.len());
+    Ok(())
+}
+
+/// Emails a run the scheduler just finished, in the background.
+pub fn dispatch_scheduled(app_handle: &tauri::AppHandle, analysis_id: String, email: ScheduledEmail) {
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = send_report(&handle, analysis_id, email.recipients, email.format).await {
+            log::warn!("Failed to email scheduled run: {}", e);
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_email_config(app_handle: tauri::AppHandle) -> Option<EmailSettings> {
+    config(&app_handle)
+}
+
+/// Sets the SMTP server; `None` removes it along with its password. `password`
+/// replaces the saved one, and is kept otherwise.
+#[tauri::command]
+pub async fn configure_email(
+    app_handle: tauri::AppHandle,
+    config: Option<EmailSettings>,
+    password: Option<String>,
+) -> Result<Option<EmailSettings>, String> {
+    let config = match config {
+        Some(mut config) => {
+            validate(&mut config)?;
+            let had_password = self::config(&app_handle).is_some_and(|c| c.has_password);
+            config.has_password = match password.filter(|p| !p.is_empty()) {
+                Some(password) => {
+                    tauri::async_runtime::spawn_blocking(move || {
+                        crate::credentials::store_app_secret(PASSWORD_SECRET, Some(&password))
+                    })
+                    .await
+                    .map_err(|e| format!("Failed to save SMTP password: {}", e))??;
+                    true
+                }
+                None => had_password,
+            };
+            Some(config)
+        }
+        None => {
+            tauri::async_runtime::spawn_blocking(|| crate::credentials::store_app_secret(PASSWORD_SECRET, None))
+                .await
+                .map_err(|e| format!("Failed to delete SMTP password: {}", e))??;
+            None
+        }
+    };
+
+    let store = app_handle.state::<SettingsStore>();
+    let mut current = store.settings.lock().unwrap();
+    current.email = config.clone();
+    settings::save(&app_handle, &current)?;
+    Ok(config)
+}
+
+/// Emails saved run `run_id` to `recipients` with the report attached as a PDF or CSV.
+#[tauri::command]
+pub async fn send_report_email(
+    app_handle: tauri::AppHandle,
+    run_id: String,
+    recipients: Vec<String>,
+    format: ReportFormat,
+) -> Result<(), String> {
+    let recipients = recipients.iter().map(|r| r.trim().to_string()).filter(|r| !r.is_empty()).collect();
+    send_report(&app_handle, run_id, recipients, format).await
+}
@@ -0,0 +1,120 @@
+//! Encodings reports can be saved in. The list is closed on purpose: content
+//! arrives as UTF-8, and these three need nothing beyond the standard library
+//! to write exactly. Names outside it are refused rather than guessed at.
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+use crate::settings::{self, SettingsStore};
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16LE_BOM: &[u8] = &[0xFF, 0xFE];
+
+/// How text content is written to disk when saving a report.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", try_from = "String")]
+pub enum TextEncoding {
+    /// Written exactly as given, so binary content is safe too.
+    #[default]
+    Utf8,
+    /// For Windows tools that only detect UTF-8 by its BOM (Excel, older Notepad).
+    Utf8Bom,
+    Utf16Le,
+}
+
+impl TryFrom<String> for TextEncoding {
+    type Error = String;
+
+    /// Takes the names settings are saved with and their usual spellings
+    /// (`UTF-8`, `utf-8-bom`, `UTF-16LE`), ignoring case.
+    fn try_from(name: String) -> Result<Self, String> {
+        match name.to_ascii_lowercase().replace('-', "_").as_str() {
+            "utf8" | "utf_8" => Ok(TextEncoding::Utf8),
+            "utf8_bom" | "utf_8_bom" => Ok(TextEncoding::Utf8Bom),
+            "utf16_le" | "utf16le" | "utf_16le" | "utf_16_le" => Ok(TextEncoding::Utf16Le),
+            _ => Err(format!("Unsupported encoding {:?}; reports can be saved as UTF-8, UTF-8 with BOM or UTF-16LE", name)),
+        }
+    }
+}
+
+fn text_without_bom(content: Vec<u8>) -> Result<String, String> {
+    let text = String::from_utf8(content).map_err(|e| format!("Content is not valid UTF-8 text: {}", e))?;
+    Ok(match text.strip_prefix('\u{FEFF}') {
+        Some(rest) => rest.to_string(),
+        None => text,
+    })
+}
+
+/// Re-encodes UTF-8 `content` for saving. Any BOM already on the content is
+/// replaced so it never ends up doubled.
+pub fn encode(content: Vec<u8>, encoding: TextEncoding) -> Result<Vec<u8>, String> {
+    match encoding {
+        TextEncoding::Utf8 => Ok(content),
+        TextEncoding::Utf8Bom => Ok([UTF8_BOM, text_without_bom(content)?.as_bytes()].concat()),
+        TextEncoding::Utf16Le => {
+            let text = text_without_bom(content)?;
+            let mut bytes = Vec::with_capacity(UTF16LE_BOM.len() + text.len() * 2);
+            bytes.extend_from_slice(UTF16LE_BOM);
+            bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+            Ok(bytes)
+        }
+    }
+}
+
+/// The encoding saves use unless a call asks for another.
+pub fn default_encoding(app_handle: &tauri::AppHandle) -> TextEncoding {
+    app_handle.state::<SettingsStore>().settings.lock().unwrap().save_encoding
+}
+
+#[tauri::command]
+pub fn set_save_encoding(app_handle: tauri::AppHandle, encoding: TextEncoding) -> Result<(), String> {
+    let store = app_handle.state::<SettingsStore>();
+    let mut current = store.settings.lock().unwrap();
+    current.save_encoding = encoding;
+    settings::save(&app_handle, &current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn named(name: &str) -> Result<TextEncoding, String> {
+        TextEncoding::try_from(name.to_string())
+    }
+
+    #[test]
+    fn names_are_parsed_and_unsupported_ones_refused() {
+        assert_eq!(named("utf8"), Ok(TextEncoding::Utf8));
+        assert_eq!(named("UTF-8"), Ok(TextEncoding::Utf8));
+        assert_eq!(named("utf8_bom"), Ok(TextEncoding::Utf8Bom));
+        assert_eq!(named("UTF-16LE"), Ok(TextEncoding::Utf16Le));
+        for name in ["latin1", "windows-1252", "utf16_be", ""] {
+            assert!(named(name).is_err(), "{:?}", name);
+        }
+        assert!(serde_json::from_str::<TextEncoding>("\"shift_jis\"").is_err());
+    }
+
+    #[test]
+    fn saved_names_round_trip() {
+        for encoding in [TextEncoding::Utf8, TextEncoding::Utf8Bom, TextEncoding::Utf16Le] {
+            let json = serde_json::to_string(&encoding).unwrap();
+            assert_eq!(serde_json::from_str::<TextEncoding>(&json).unwrap(), encoding);
+        }
+    }
+
+    #[test]
+    fn boms_are_written_once() {
+        let with_bom = [UTF8_BOM, "é".as_bytes()].concat();
+        assert_eq!(encode(with_bom.clone(), TextEncoding::Utf8Bom).unwrap(), with_bom);
+        assert_eq!(encode("é".into(), TextEncoding::Utf8Bom).unwrap(), with_bom);
+        assert_eq!(encode(with_bom, TextEncoding::Utf16Le).unwrap(), [0xFF, 0xFE, 0xE9, 0x00]);
+        assert_eq!(encode("€".into(), TextEncoding::Utf16Le).unwrap(), [0xFF, 0xFE, 0xAC, 0x20]);
+    }
+
+    #[test]
+    fn plain_utf8_is_written_untouched() {
+        let binary = vec![0xFF, 0x00, 0xFE];
+        assert_eq!(encode(binary.clone(), TextEncoding::Utf8).unwrap(), binary);
+        assert!(encode(binary, TextEncoding::Utf16Le).is_err());
+    }
+}
@@ -0,0 +1,252 @@
+//! AES-256-GCM for analysis results at rest and for shared exports.
+//!
+//! With `encrypt_history` on, saved runs, their index and the history log are
+//! sealed with a random key kept in the OS credential store (see `credentials`),
+//! so a copy of the app data dir reveals nothing on its own. Exports made with
+//! `export_encrypted` are sealed with a key derived from a passphrase instead
+//! (PBKDF2-HMAC-SHA256), so they open on any machine that knows it.
+//!
+//! Sealed data starts with `CHCENC`, a version byte and a mode byte; passphrase
+//! mode follows that with the salt and iteration count, and both modes then
+//! carry the nonce. The whole header is authenticated with the ciphertext.
+//! Readers take sealed and plain data alike, so switching encryption on or off
+//! never strands a file.
+
+use base64::Engine;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use std::num::NonZeroU32;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::Manager;
+
+use crate::settings::{self, SettingsStore};
+
+const MAGIC: &[u8] = b"CHCENC";
+const VERSION: u8 = 1;
+const MODE_KEY: u8 = 0;
+const MODE_PASSPHRASE: u8 = 1;
+const KEY_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+/// Name of the history key among the app's secrets.
+const KEY_SECRET: &str = "history-encryption-key";
+const PBKDF2_ITERATIONS: u32 = 600_000;
+/// A crafted file claiming more rounds than this could tie the app up for minutes.
+const MAX_PBKDF2_ITERATIONS: u32 = 10_000_000;
+const MIN_PASSPHRASE_CHARS: usize = 8;
+
+/// The history key, once read from the credential store.
+#[derive(Default)]
+pub struct EncryptionState {
+    key: Mutex<Option<[u8; KEY_LEN]>>,
+}
+
+fn random<const N: usize>() -> Result<[u8; N], String> {
+    let mut bytes = [0u8; N];
+    getrandom::getrandom(&mut bytes).map_err(|e| format!("Failed to generate random bytes: {}", e))?;
+    Ok(bytes)
+}
+
+fn cipher(key: &[u8; KEY_LEN]) -> Result<LessSafeKey, String> {
+    UnboundKey::new(&AES_256_GCM, key)
+        .map(LessSafeKey::new)
+        .map_err(|_| "Failed to set up encryption".to_string())
+}
+
+/// Appends a fresh nonce to `header` and seals `plaintext` after it.
+fn seal(key: &[u8; KEY_LEN], mut header: Vec<u8>, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let nonce = random::<NONCE_LEN>()?;
+    header.extend_from_slice(&nonce);
+    let mut sealed = plaintext.to_vec();
+    cipher(key)?
+        .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(&header[..]), &mut sealed)
+        .map_err(|_| "Failed to encrypt".to_string())?;
+    header.extend(sealed);
+    Ok(header)
+}
+
+/// Opens data whose header, nonce included, is `header_len` bytes long.
+fn open(key: &[u8; KEY_LEN], header_len: usize, data: &[u8]) -> Result<Vec<u8>, String> {
+    if data.len() < header_len {
+        return Err("Encrypted data is truncated".to_string());
+    }
+    let (header, ciphertext) = data.split_at(header_len);
+    let nonce = Nonce::try_assume_unique_for_key(&header[header_len - NONCE_LEN..])
+        .map_err(|_| "Encrypted data is truncated".to_string())?;
+    let mut in_out = ciphertext.to_vec();
+    let plaintext = cipher(key)?
+        .open_in_place(nonce, Aad::from(header), &mut in_out)
+        .map_err(|_| "Wrong key or passphrase, or the data is corrupt".to_string())?;
+    Ok(plaintext.to_vec())
+}
+
+fn header(mode: u8) -> Vec<u8> {
+    let mut header = MAGIC.to_vec();
+    header.extend_from_slice(&[VERSION, mode]);
+    header
+}
+
+/// The mode byte of sealed data, or `None` for plain data.
+fn mode(data: &[u8]) -> Result<Option<u8>, String> {
+    if !data.starts_with(MAGIC) {
+        return Ok(None);
+    }
+    match data.get(MAGIC.len()..MAGIC.len() + 2) {
+        Some(&[VERSION, mode]) => Ok(Some(mode)),
+        Some(&[version, _]) => Err(format!("Encrypted with a newer format (version {}); update the app", version)),
+        _ => Err("Encrypted data is truncated".to_string()),
+    }
+}
+
+/// Whether `data` was sealed by `seal_with_passphrase`.
+pub fn is_passphrase_sealed(data: &[u8]) -> bool {
+    matches!(mode(data), Ok(Some(MODE_PASSPHRASE)))
+}
+
+fn passphrase_key(passphrase: &str, salt: &[u8], iterations: NonZeroU32) -> [u8; KEY_LEN] {
+    let mut key = [0u8; KEY_LEN];
+    ring::pbkdf2::derive(ring::pbkdf2::PBKDF2_HMAC_SHA256, iterations, salt, passphrase.as_bytes(), &mut key);
+    key
+}
+
+pub fn seal_with_passphrase(passphrase: &str, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_CHARS {
+        return Err(format!("The passphrase needs at least {} characters", MIN_PASSPHRASE_CHARS));
+    }
+    let salt = random::<SALT_LEN>()?;
+    let iterations = NonZeroU32::new(PBKDF2_ITERATIONS).expect("iterations are non-zero");
+    let mut header = header(MODE_PASSPHRASE);
+    header.extend_from_slice(&salt);
+    header.extend_from_slice(&PBKDF2_ITERATIONS.to_be_bytes());
+    seal(&passphrase_key(passphrase, &salt, iterations), header, plaintext)
+}
+
+pub fn open_with_passphrase(passphrase: &str, data: &[u8]) -> Result<Vec<u8>, String> {
+    if mode(data)? != Some(MODE_PASSPHRASE) {
+        return Err("Not a passphrase-protected file".to_string());
+    }
+    let salt_at = MAGIC.len() + 2;
+    let iterations_at = salt_at + SALT_LEN;
+    let header_len = iterations_at + 4 + NONCE_LEN;
+    if data.len() < header_len {
+        return Err("Encrypted data is truncated".to_string());
+    }
+    let mut rounds = [0u8; 4];
+    rounds.copy_from_slice(&data[iterations_at..iterations_at + 4]);
+    let iterations = NonZeroU32::new(u32::from_be_bytes(rounds))
+        .filter(|n| n.get() <= MAX_PBKDF2_ITERATIONS)
+        .ok_or("Encrypted file has an invalid key derivation setting")?;
+    open(&passphrase_key(passphrase, &data[salt_at..iterations_at], iterations), header_len, data)
+}
+
+pub fn enabled(app_handle: &tauri::AppHandle) -> bool {
+    app_handle.state::<SettingsStore>().settings.lock().unwrap().encrypt_history
+}
+
+/// The history key, reading it from the credential store on first use and, with
+/// `create`, generating one if there is none. Blocks on the credential store.
+fn key(app_handle: &tauri::AppHandle, create: bool) -> Result<Option<[u8; KEY_LEN]>, String> {
+    let state = app_handle.state::<EncryptionState>();
+    let mut cached = state.key.lock().unwrap();
+    if cached.is_some() {
+        return Ok(*cached);
+    }
+
+    if let Some(encoded) = crate::credentials::load_app_secret(KEY_SECRET)? {
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(encoded.trim())
+            .map_err(|e| format!("History encryption key is corrupt: {}", e))?;
+        let key: [u8; KEY_LEN] = bytes
+            .try_into()
+            .map_err(|_| "History encryption key is corrupt".to_string())?;
+        *cached = Some(key);
+    } else if create {
+        let key = random::<KEY_LEN>()?;
+        let encoded = base64::engine::general_purpose::STANDARD.encode(key);
+        crate::credentials::store_app_secret(KEY_SECRET, Some(&encoded))?;
+        *cached = Some(key);
+    }
+    Ok(*cached)
+}
+
+/// `plaintext` as it should be written to the app data dir: sealed when
+/// encryption is on, unchanged otherwise.
+pub fn seal_for_storage(app_handle: &tauri::AppHandle, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    if !enabled(app_handle) {
+        return Ok(plaintext.to_vec());
+    }
+    let key = key(app_handle, true)?.ok_or("History encryption key is missing")?;
+    seal(&key, header(MODE_KEY), plaintext)
+}
+
+/// Reads back what `seal_for_storage` wrote, whether or not it was sealed.
+pub fn open_stored(app_handle: &tauri::AppHandle, data: &[u8]) -> Result<Vec<u8>, String> {
+    match mode(data)? {
+        None => Ok(data.to_vec()),
+        Some(MODE_KEY) => {
+            let key = key(app_handle, false)?
+                .ok_or("Saved results are encrypted but the key is missing from the credential store")?;
+            open(&key, MAGIC.len() + 2 + NONCE_LEN, data)
+        }
+        Some(_) => Err("Passphrase-protected data can't be read as saved history".to_string()),
+    }
+}
+
+/// Starts sealing saved runs and history with a key in the OS credential store,
+/// and seals what is already saved. Returns how many files were rewritten.
+#[tauri::command]
+pub async fn enable_encryption(app_handle: tauri::AppHandle) -> Result<usize, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        key(&app_handle, true)?;
+        set_enabled(&app_handle, true)?;
+        let rewritten = crate::history::reseal_all(&app_handle)?;
+        log::info!("History encryption enabled; sealed {} files", rewritten);
+        Ok(rewritten)
+    })
+    .await
+    .map_err(|e| format!("Failed to enable encryption: {}", e))?
+}
+
+/// Decrypts everything saved and forgets the key. Returns how many files were rewritten.
+#[tauri::command]
+pub async fn disable_encryption(app_handle: tauri::AppHandle) -> Result<usize, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        set_enabled(&app_handle, false)?;
+        let rewritten = crate::history::reseal_all(&app_handle)?;
+        // Only once nothing sealed with it is left
+        crate::credentials::store_app_secret(KEY_SECRET, None)?;
+        app_handle.state::<EncryptionState>().key.lock().unwrap().take();
+        log::info!("History encryption disabled; decrypted {} files", rewritten);
+        Ok(rewritten)
+    })
+    .await
+    .map_err(|e| format!("Failed to disable encryption: {}", e))?
+}
+
+fn set_enabled(app_handle: &tauri::AppHandle, enabled: bool) -> Result<(), String> {
+    let store = app_handle.state::<SettingsStore>();
+    let mut current = store.settings.lock().unwrap();
+    current.encrypt_history = enabled;
+    settings::save(app_handle, &current)
+}
+
+/// Saves a saved run as a `.criblhc` file sealed with `passphrase`, for sharing
+/// a report that only those told the passphrase can open. Returns the path.
+#[tauri::command]
+pub async fn export_encrypted(app_handle: tauri::AppHandle, run_id: String, passphrase: String) -> Result<String, String> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_CHARS {
+        return Err(format!("The passphrase needs at least {} characters", MIN_PASSPHRASE_CHARS));
+    }
+    let filename = format!("{}.{}", run_id, crate::file_association::EXTENSION);
+    let path: PathBuf =
+        crate::dialogs::save_path_with_extension(&app_handle, &filename, Some(crate::file_association::EXTENSION)).await?;
+
+    let written = path.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = crate::history::get_analysis_run(app_handle, run_id)?;
+        let json = serde_json::to_vec(&result).map_err(|e| format!("Failed to serialize analysis: {}", e))?;
+        crate::files::write_file_atomic(&written, &seal_with_passphrase(&passphrase, &json)?)
+    })
+    .await
+    .map_err(|e| format!("Failed to export analysis: {}", e))??;
+    Ok(path.to_string_lossy().to_string())
+}
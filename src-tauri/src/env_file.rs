@@ -0,0 +1,252 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use tauri::Manager;
+
+use crate::settings::{self, BackendSettings, SettingsStore};
+
+const DEFAULT_FILE: &str = ".env";
+
+fn env_file_path(app_handle: &tauri::AppHandle, settings: &BackendSettings) -> Result<PathBuf, String> {
+    match &settings.env_file {
+        Some(path) => Ok(PathBuf::from(path)),
+        None => app_handle
+            .path()
+            .app_config_dir()
+            .map(|dir| dir.join(DEFAULT_FILE))
+            .map_err(|e| format!("Failed to get config dir: {}", e)),
+    }
+}
+
+/// Parses a dotenv file. The grammar, which follows what dotenv tools commonly
+/// accept:
+///
+/// - Blank lines, and lines whose first non-blank character is `#`, are skipped.
+/// - Every other line is `KEY=VALUE`, optionally preceded by `export `. Keys are
+///   letters, digits and `_`, not starting with a digit; blanks around the key
+///   and the `=` are ignored.
+/// - An unquoted value runs to the end of the line, trimmed. A `#` after a blank
+///   starts a comment; one anywhere else is part of the value, so `KEY=a#b` and
+///   `KEY=#b` set `a#b` and `#b`.
+/// - A value in single quotes is taken literally.
+/// - A value in double quotes understands `\n`, `\r`, `\t`, `\"`, `\\` and `\$`;
+///   any other backslash is kept.
+/// - Quoted values may span lines, and only a comment may follow the closing
+///   quote. `$VAR` is never expanded.
+fn parse(content: &str) -> Result<Vec<(String, String)>, String> {
+    let mut vars = Vec::new();
+    let mut lines = content.lines().enumerate();
+
+    while let Some((index, line)) = lines.next() {
+        let number = index + 1;
+        let line = line.trim_start();
+        if line.trim_end().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = line
+            .strip_prefix("export")
+            .filter(|rest| rest.starts_with([' ', '\t']))
+            .map_or(line, str::trim_start);
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(format!("Line {}: expected KEY=VALUE", number));
+        };
+
+        let key = key.trim();
+        if !is_variable_name(key) {
+            return Err(format!("Line {}: invalid variable name {:?}", number, key));
+        }
+
+        let value = match value.trim_start().chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let mut text = value.trim_start()[1..].to_string();
+                loop {
+                    if let Some(end) = closing_quote(&text, quote) {
+                        let rest = text[end + 1..].trim();
+                        if !rest.is_empty() && !rest.starts_with('#') {
+                            return Err(format!("Line {}: unexpected {:?} after the closing quote", number, rest));
+                        }
+                        text.truncate(end);
+                        break;
+                    }
+                    let Some((_, next)) = lines.next() else {
+                        return Err(format!("Line {}: unterminated quote", number));
+                    };
+                    text.push('\n');
+                    text.push_str(next);
+                }
+                if quote == '"' {
+                    unescape(&text)
+                } else {
+                    text
+                }
+            }
+            _ => {
+                let comment = value
+                    .char_indices()
+                    .find(|&(i, c)| c == '#' && value[..i].ends_with(char::is_whitespace))
+                    .map_or(value.len(), |(i, _)| i);
+                value[..comment].trim().to_string()
+            }
+        };
+
+        vars.push((key.to_string(), value));
+    }
+
+    Ok(vars)
+}
+
+fn is_variable_name(key: &str) -> bool {
+    !key.is_empty()
+        && !key.starts_with(|c: char| c.is_ascii_digit())
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Where the quote closing `text` is; backslashes escape within double quotes.
+fn closing_quote(text: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' && quote == '"' {
+            escaped = true;
+        } else if c == quote {
+            return Some(i);
+        }
+    }
+    None
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('t') => out.push('\t'),
+            Some(c @ ('"' | '\\' | '$')) => out.push(c),
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Adds the variables from the backend's `.env` file to `command`. A missing
+/// default file is fine; a configured file that can't be read is an error.
+pub fn apply(app_handle: &tauri::AppHandle, command: &mut Command, settings: &BackendSettings) -> Result<(), String> {
+    let path = env_file_path(app_handle, settings)?;
+
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound && settings.env_file.is_none() => return Ok(()),
+        Err(e) => return Err(format!("Failed to read env file {}: {}", path.display(), e)),
+    };
+
+    let vars = parse(&content).map_err(|e| format!("Invalid env file {}: {}", path.display(), e))?;
+    let keys: Vec<&str> = vars.iter().map(|(key, _)| key.as_str()).collect();
+    log::info!("Loaded backend environment from {}: {}", path.display(), keys.join(", "));
+
+    command.envs(vars);
+    Ok(())
+}
+
+/// Sets the `.env` file used from the next backend start; `None` restores the default.
+#[tauri::command]
+pub fn set_backend_env_file(app_handle: tauri::AppHandle, path: Option<String>) -> Result<(), String> {
+    if let Some(path) = &path {
+        if !std::path::Path::new(path).is_file() {
+            return Err(format!("Env file not found: {}", path));
+        }
+    }
+
+    let store = app_handle.state::<SettingsStore>();
+    let mut current = store.settings.lock().unwrap();
+    current.backend.env_file = path;
+    settings::save(&app_handle, &current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(content: &str) -> Vec<(String, String)> {
+        parse(content).unwrap()
+    }
+
+    fn pairs(expected: &[(&str, &str)]) -> Vec<(String, String)> {
+        expected.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn blank_lines_and_comments_are_skipped() {
+        let content = "\n# a comment\n   \n  # indented\r\nA=1\r\n\nB = 2 # trailing\nC=two words\t# tab\n";
+        assert_eq!(vars(content), pairs(&[("A", "1"), ("B", "2"), ("C", "two words")]));
+    }
+
+    #[test]
+    fn a_hash_is_only_a_comment_after_a_blank() {
+        assert_eq!(
+            vars("URL=http://h/#frag\nCOLOR=#fff\nEMPTY= # nothing\nBARE=\n"),
+            pairs(&[("URL", "http://h/#frag"), ("COLOR", "#fff"), ("EMPTY", ""), ("BARE", "")])
+        );
+    }
+
+    #[test]
+    fn export_prefixes_are_allowed() {
+        assert_eq!(
+            vars("export A=1\nexport\tB=2\nexport=3\nexported=4\n"),
+            pairs(&[("A", "1"), ("B", "2"), ("export", "3"), ("exported", "4")])
+        );
+    }
+
+    #[test]
+    fn single_quotes_are_literal() {
+        assert_eq!(
+            vars("A=' keep  # this \\n $HOME '\nB='x' # comment\n"),
+            pairs(&[("A", " keep  # this \\n $HOME "), ("B", "x")])
+        );
+    }
+
+    #[test]
+    fn double_quotes_understand_escapes() {
+        assert_eq!(
+            vars(r#"A="tab\there \"quoted\" back\\slash \$HOME \q""#),
+            pairs(&[("A", "tab\there \"quoted\" back\\slash $HOME \\q")])
+        );
+        assert_eq!(vars(r#"A="ends with \\""#), pairs(&[("A", "ends with \\")]));
+    }
+
+    #[test]
+    fn quoted_values_may_span_lines() {
+        let content = "KEY=\"-----BEGIN-----\nbody  \n-----END-----\"\nNEXT='a\n# not a comment\nb'\n";
+        assert_eq!(
+            vars(content),
+            pairs(&[("KEY", "-----BEGIN-----\nbody  \n-----END-----"), ("NEXT", "a\n# not a comment\nb")])
+        );
+    }
+
+    #[test]
+    fn malformed_lines_are_reported_with_their_number() {
+        for (content, line) in [
+            ("A=1\nno equals sign\n", "Line 2"),
+            ("=1\n", "Line 1"),
+            ("1A=1\n", "Line 1"),
+            ("MY-VAR=1\n", "Line 1"),
+            ("export B\n", "Line 1"),
+            ("A=1\nB=\"open\nstill open\n", "Line 2"),
+            ("A='x' y\n", "Line 1"),
+        ] {
+            let error = parse(content).unwrap_err();
+            assert!(error.starts_with(line), "{:?} gave {:?}", content, error);
+        }
+    }
+}
@@ -0,0 +1,398 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{BufWriter, Write};
+use std::path::{Component, Path};
+use tauri::Emitter;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Progress is reported after each chunk so a single huge entry still moves the bar.
+const BUNDLE_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Writes RFC 4180 CSV; fields containing commas, quotes or newlines are quoted.
+fn write_records(out: impl Write, headers: &[String], rows: &[Vec<String>]) -> Result<(), String> {
+    let mut writer = csv::WriterBuilder::new()
+        .terminator(csv::Terminator::CRLF)
+        .from_writer(out);
+
+    if !headers.is_empty() {
+        writer
+            .write_record(headers)
+            .map_err(|e| format!("Failed to write CSV header: {}", e))?;
+    }
+
+    for (index, row) in rows.iter().enumerate() {
+        writer
+            .write_record(row)
+            .map_err(|e| format!("Failed to write CSV row {}: {}", index + 1, e))?;
+    }
+
+    writer.flush().map_err(|e| format!("Failed to save file: {}", e))
+}
+
+pub fn write_csv(path: &Path, headers: &[String], rows: &[Vec<String>]) -> Result<(), String> {
+    crate::files::write_atomic(path, |file| write_records(BufWriter::new(file), headers, rows))
+}
+
+pub fn csv_bytes(headers: &[String], rows: &[Vec<String>]) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    write_records(&mut bytes, headers, rows)?;
+    Ok(bytes)
+}
+
+/// Excel refuses cells longer than this.
+const XLSX_MAX_CELL_CHARS: usize = 32767;
+
+const XLSX_CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/><Override PartName="/xl/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml"/></Types>"#;
+
+const XLSX_ROOT_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#;
+
+const XLSX_WORKBOOK_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/><Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/></Relationships>"#;
+
+/// Style 1 is the bold header row; style 2 wraps body text at the top of its cell.
+const XLSX_STYLES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><fonts count="2"><font><sz val="11"/><name val="Calibri"/></font><font><b/><sz val="11"/><name val="Calibri"/></font></fonts><fills count="2"><fill><patternFill patternType="none"/></fill><fill><patternFill patternType="gray125"/></fill></fills><borders count="1"><border><left/><right/><top/><bottom/><diagonal/></border></borders><cellStyleXfs count="1"><xf numFmtId="0" fontId="0" fillId="0" borderId="0"/></cellStyleXfs><cellXfs count="3"><xf numFmtId="0" fontId="0" fillId="0" borderId="0" xfId="0"/><xf numFmtId="0" fontId="1" fillId="0" borderId="0" xfId="0" applyFont="1"/><xf numFmtId="0" fontId="0" fillId="0" borderId="0" xfId="0" applyAlignment="1"><alignment vertical="top" wrapText="1"/></xf></cellXfs></styleSheet>"#;
+
+/// Escapes text for XML, dropping the control characters XML can't hold at all.
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c < ' ' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// `A`, `B`, ... `Z`, `AA`, ... for a zero-based column index.
+fn column_name(mut index: usize) -> String {
+    let mut name = Vec::new();
+    loop {
+        name.push(b'A' + (index % 26) as u8);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap_or_default()
+}
+
+fn xlsx_row(row_number: usize, cells: &[String], style: u8) -> String {
+    let mut xml = format!("<row r=\"{}\">", row_number);
+    for (column, value) in cells.iter().enumerate() {
+        let value: String = value.chars().take(XLSX_MAX_CELL_CHARS).collect();
+        xml.push_str(&format!(
+            "<c r=\"{}{}\" s=\"{}\" t=\"inlineStr\"><is><t xml:space=\"preserve\">{}</t></is></c>",
+            column_name(column),
+            row_number,
+            style,
+            xml_escape(&value)
+        ));
+    }
+    xml.push_str("</row>");
+    xml
+}
+
+/// Writes a one-sheet workbook with a bold, frozen, filterable header row.
+/// `widths` are column widths in characters. Rows are streamed into the
+/// archive, so large sheets don't need building in memory first.
+pub fn write_xlsx(path: &Path, sheet_name: &str, headers: &[String], widths: &[f64], rows: &[Vec<String>]) -> Result<(), String> {
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let failed = |e: std::io::Error| format!("Failed to write spreadsheet: {}", e);
+
+    crate::files::write_atomic(path, |file| {
+        let mut zip = ZipWriter::new(BufWriter::new(file));
+        let parts = [
+            ("[Content_Types].xml", XLSX_CONTENT_TYPES.to_string()),
+            ("_rels/.rels", XLSX_ROOT_RELS.to_string()),
+            ("xl/_rels/workbook.xml.rels", XLSX_WORKBOOK_RELS.to_string()),
+            ("xl/styles.xml", XLSX_STYLES.to_string()),
+            (
+                "xl/workbook.xml",
+                format!(
+                    r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets><sheet name="{}" sheetId="1" r:id="rId1"/></sheets></workbook>"#,
+                    xml_escape(sheet_name)
+                ),
+            ),
+        ];
+        for (name, content) in parts {
+            zip.start_file(name, options)
+                .map_err(|e| format!("Failed to write spreadsheet: {}", e))?;
+            zip.write_all(content.as_bytes()).map_err(failed)?;
+        }
+
+        zip.start_file("xl/worksheets/sheet1.xml", options)
+            .map_err(|e| format!("Failed to write spreadsheet: {}", e))?;
+        let mut sheet = String::from(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetViews><sheetView workbookViewId="0"><pane ySplit="1" topLeftCell="A2" activePane="bottomLeft" state="frozen"/></sheetView></sheetViews>"#,
+        );
+        if !widths.is_empty() {
+            sheet.push_str("<cols>");
+            for (i, width) in widths.iter().enumerate() {
+                sheet.push_str(&format!("<col min=\"{0}\" max=\"{0}\" width=\"{1}\" customWidth=\"1\"/>", i + 1, width));
+            }
+            sheet.push_str("</cols>");
+        }
+        sheet.push_str("<sheetData>");
+        sheet.push_str(&xlsx_row(1, headers, 1));
+        zip.write_all(sheet.as_bytes()).map_err(failed)?;
+        for (index, row) in rows.iter().enumerate() {
+            zip.write_all(xlsx_row(index + 2, row, 2).as_bytes()).map_err(failed)?;
+        }
+        let mut end = String::from("</sheetData>");
+        if !headers.is_empty() {
+            end.push_str(&format!(
+                "<autoFilter ref=\"A1:{}{}\"/>",
+                column_name(headers.len() - 1),
+                rows.len() + 1
+            ));
+        }
+        end.push_str("</worksheet>");
+        zip.write_all(end.as_bytes()).map_err(failed)?;
+
+        zip.finish()
+            .and_then(|mut writer| writer.flush().map_err(Into::into))
+            .map_err(|e| format!("Failed to finish spreadsheet: {}", e))
+    })
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FindingsFormat {
+    Csv,
+    Xlsx,
+}
+
+/// Column headers and widths for `export_findings`.
+const FINDING_COLUMNS: &[(&str, f64)] = &[
+    ("Worker Group / Fleet", 20.0),
+    ("Severity", 10.0),
+    ("Check ID", 30.0),
+    ("Category", 14.0),
+    ("Title", 40.0),
+    ("Description", 60.0),
+    ("Affected Components", 30.0),
+    ("Remediation", 60.0),
+    ("Estimated Impact", 40.0),
+    ("Documentation", 40.0),
+];
+
+fn severity_rank(finding: &Value) -> u8 {
+    match finding.get("severity").and_then(Value::as_str) {
+        Some("critical") => 0,
+        Some("high") => 1,
+        Some("medium") => 2,
+        Some("low") => 3,
+        _ => 4,
+    }
+}
+
+fn string_list(finding: &Value, key: &str, separator: &str) -> String {
+    finding
+        .get(key)
+        .and_then(Value::as_array)
+        .map(|items| items.iter().filter_map(Value::as_str).collect::<Vec<_>>().join(separator))
+        .unwrap_or_default()
+}
+
+fn finding_row(finding: &Value) -> Vec<String> {
+    let text = |key: &str| finding.get(key).and_then(Value::as_str).unwrap_or_default().to_string();
+    // Rule-based checks name their rule; the rest are identified by the finding id
+    let check_id = finding
+        .get("metadata")
+        .and_then(|m| m.get("rule_id"))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .unwrap_or_else(|| text("id"));
+    let remediation = finding
+        .get("remediation_steps")
+        .and_then(Value::as_array)
+        .map(|steps| {
+            steps
+                .iter()
+                .filter_map(Value::as_str)
+                .enumerate()
+                .map(|(i, step)| format!("{}. {}", i + 1, step))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .unwrap_or_default();
+
+    vec![
+        crate::history::worker_group(finding),
+        text("severity"),
+        check_id,
+        text("category"),
+        text("title"),
+        text("description"),
+        string_list(finding, "affected_components", "; "),
+        remediation,
+        text("estimated_impact"),
+        string_list(finding, "documentation_links", "\n"),
+    ]
+}
+
+/// Headers and one row per finding of an analysis export, most severe first.
+pub fn findings_table(result: &Value) -> (Vec<String>, Vec<Vec<String>>) {
+    let mut findings: Vec<&Value> = result
+        .get("findings")
+        .and_then(Value::as_array)
+        .map(|findings| findings.iter().collect())
+        .unwrap_or_default();
+    findings.sort_by_key(|f| severity_rank(f));
+
+    let headers = FINDING_COLUMNS.iter().map(|(name, _)| name.to_string()).collect();
+    (headers, findings.iter().map(|f| finding_row(f)).collect())
+}
+
+/// One row per finding of a saved run, most severe first, written as CSV or
+/// XLSX to `path`. Returns how many findings were written.
+#[tauri::command]
+pub async fn export_findings(
+    app_handle: tauri::AppHandle,
+    run_id: String,
+    format: FindingsFormat,
+    path: String,
+) -> Result<usize, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = crate::history::get_analysis_run(app_handle, run_id.clone())?;
+        let (headers, rows) = findings_table(&result);
+        let path = Path::new(&path);
+        match format {
+            FindingsFormat::Csv => write_csv(path, &headers, &rows)?,
+            FindingsFormat::Xlsx => {
+                let widths: Vec<f64> = FINDING_COLUMNS.iter().map(|(_, width)| *width).collect();
+                write_xlsx(path, "Findings", &headers, &widths, &rows)?
+            }
+        }
+        log::info!("Exported {} findings of {} to {}", rows.len(), run_id, path.display());
+        Ok(rows.len())
+    })
+    .await
+    .map_err(|e| format!("Failed to export findings: {}", e))?
+}
+
+#[tauri::command]
+pub async fn save_csv_with_dialog(
+    app_handle: tauri::AppHandle,
+    filename: String,
+    rows: Vec<Vec<String>>,
+    headers: Vec<String>,
+) -> Result<String, String> {
+    let path = crate::dialogs::save_path(&app_handle, &filename).await?;
+    write_csv(&path, &headers, &rows)?;
+
+    Ok(path.to_string_lossy().to_string())
+}
+
+#[derive(Deserialize)]
+pub struct BundleEntry {
+    name: String,
+    content: Vec<u8>,
+}
+
+#[derive(Clone, Serialize)]
+struct SaveProgress {
+    file: String,
+    bytes_done: u64,
+    bytes_total: u64,
+    percent: f64,
+}
+
+/// Entry names become paths on extraction, so only plain relative ones are allowed.
+fn validate_entry_name(name: &str) -> Result<(), String> {
+    let path = Path::new(name);
+    let plain = !name.is_empty()
+        && path.components().all(|c| matches!(c, Component::Normal(_)));
+
+    if !plain {
+        return Err(format!("Invalid bundle entry name: {}", name));
+    }
+    Ok(())
+}
+
+fn write_bundle(
+    app_handle: &tauri::AppHandle,
+    path: &Path,
+    entries: &[BundleEntry],
+) -> Result<(), String> {
+    let bytes_total: u64 = entries.iter().map(|e| e.content.len() as u64).sum();
+    let mut bytes_done = 0u64;
+    let mut last_percent = -1.0;
+
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .large_file(true);
+
+    crate::files::write_atomic(path, |file| {
+        let mut zip = ZipWriter::new(BufWriter::new(file));
+
+        for entry in entries {
+            zip.start_file(entry.name.as_str(), options)
+                .map_err(|e| format!("Failed to add {} to bundle: {}", entry.name, e))?;
+
+            for chunk in entry.content.chunks(BUNDLE_CHUNK_SIZE) {
+                zip.write_all(chunk)
+                    .map_err(|e| format!("Failed to write {} to bundle: {}", entry.name, e))?;
+                bytes_done += chunk.len() as u64;
+
+                let percent = if bytes_total > 0 {
+                    (bytes_done as f64 * 100.0 / bytes_total as f64).floor()
+                } else {
+                    100.0
+                };
+                // Whole percents are plenty for a progress bar and keep the event rate sane.
+                if percent > last_percent {
+                    last_percent = percent;
+                    let progress = SaveProgress {
+                        file: entry.name.clone(),
+                        bytes_done,
+                        bytes_total,
+                        percent,
+                    };
+                    if let Err(e) = app_handle.emit("save-progress", progress) {
+                        log::warn!("Failed to emit save-progress: {}", e);
+                    }
+                }
+            }
+        }
+
+        zip.finish()
+            .and_then(|mut writer| writer.flush().map_err(Into::into))
+            .map_err(|e| format!("Failed to finish bundle: {}", e))
+    })
+}
+
+/// Saves several files as one ZIP, emitting `save-progress` as entries are compressed.
+#[tauri::command]
+pub async fn save_bundle_with_dialog(
+    app_handle: tauri::AppHandle,
+    filename: String,
+    entries: Vec<BundleEntry>,
+) -> Result<String, String> {
+    for entry in &entries {
+        validate_entry_name(&entry.name)?;
+    }
+
+    let path = crate::dialogs::save_path_with_extension(&app_handle, &filename, Some("zip")).await?;
+
+    let handle = app_handle.clone();
+    let target = path.clone();
+    tauri::async_runtime::spawn_blocking(move || write_bundle(&handle, &target, &entries))
+        .await
+        .map_err(|e| format!("Failed to save bundle: {}", e))??;
+
+    Ok(path.to_string_lossy().to_string())
+}
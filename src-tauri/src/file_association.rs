@@ -0,0 +1,185 @@
+//! Opening saved analyses: from the open dialog, by double-clicking a
+//! `.criblhc` file, or by dropping a `.json` export on the app icon.
+//!
+//! Installers register the extension (see `bundle.fileAssociations`). The OS
+//! then hands the app the file as an Apple event on macOS and as a launch
+//! argument on Windows and Linux, which reaches a running instance through the
+//! single-instance plugin. Either way the analysis is sent to the main window as
+//! `analysis-file-opened`; files opened before the frontend is listening are
+//! kept until it calls `take_pending_opened_files`.
+//!
+//! A file from `encryption::export_encrypted` arrives with `encrypted` set and
+//! no result; the frontend asks for the passphrase and calls
+//! `open_encrypted_analysis`.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+
+pub const EXTENSION: &str = "criblhc";
+/// Saved analyses are JSON exports; anything this large is not one.
+const MAX_FILE_BYTES: u64 = 512 * 1024 * 1024;
+
+#[derive(Clone, Serialize)]
+pub struct OpenedAnalysis {
+    path: String,
+    /// The backend's JSON export of the analysis; null until an encrypted file is unlocked.
+    result: Value,
+    encrypted: bool,
+}
+
+#[derive(Default)]
+pub struct OpenedFiles {
+    pending: Mutex<Vec<OpenedAnalysis>>,
+    /// Set once the frontend has collected the pending files and listens for events.
+    listening: AtomicBool,
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, String> {
+    let size = fs::metadata(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?
+        .len();
+    if size > MAX_FILE_BYTES {
+        return Err(format!("{} is too large to be a saved analysis", path.display()));
+    }
+    fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+}
+
+fn read_analysis(path: &Path) -> Result<OpenedAnalysis, String> {
+    let bytes = read_file(path)?;
+    if crate::encryption::is_passphrase_sealed(&bytes) {
+        return Ok(OpenedAnalysis {
+            path: path.to_string_lossy().to_string(),
+            result: Value::Null,
+            encrypted: true,
+        });
+    }
+    parse_analysis(path, &bytes)
+}
+
+fn parse_analysis(path: &Path, bytes: &[u8]) -> Result<OpenedAnalysis, String> {
+    let result: Value =
+        serde_json::from_slice(bytes).map_err(|e| format!("{} is not a saved analysis: {}", path.display(), e))?;
+    if result.get("analysis_id").is_none() && result.get("findings").is_none() {
+        return Err(format!("{} is not a saved analysis", path.display()));
+    }
+
+    Ok(OpenedAnalysis {
+        path: path.to_string_lossy().to_string(),
+        result,
+        encrypted: false,
+    })
+}
+
+/// Shows the native open dialog for saved analyses and returns the one chosen.
+#[tauri::command]
+pub async fn open_file_with_dialog(app_handle: tauri::AppHandle) -> Result<OpenedAnalysis, String> {
+    let path =
+        crate::dialogs::open_path_with_filter(&app_handle, "Cribl Health Check analysis", &[EXTENSION, "json"]).await?;
+    tauri::async_runtime::spawn_blocking(move || read_analysis(&path))
+        .await
+        .map_err(|e| format!("Failed to read file: {}", e))?
+}
+
+/// Decrypts a passphrase-protected `.criblhc` file that was opened with `encrypted` set.
+#[tauri::command]
+pub async fn open_encrypted_analysis(path: String, passphrase: String) -> Result<OpenedAnalysis, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let path = PathBuf::from(path);
+        let bytes = crate::encryption::open_with_passphrase(&passphrase, &read_file(&path)?)
+            .map_err(|e| format!("Failed to decrypt {}: {}", path.display(), e))?;
+        parse_analysis(&path, &bytes)
+    })
+    .await
+    .map_err(|e| format!("Failed to read file: {}", e))?
+}
+
+/// Files opened before the frontend was listening; later ones arrive as events.
+#[tauri::command]
+pub fn take_pending_opened_files(app_handle: tauri::AppHandle) -> Vec<OpenedAnalysis> {
+    let opened = app_handle.state::<OpenedFiles>();
+    let mut pending = opened.pending.lock().unwrap();
+    opened.listening.store(true, Ordering::SeqCst);
+    std::mem::take(&mut *pending)
+}
+
+fn deliver(app_handle: &tauri::AppHandle, analysis: OpenedAnalysis) {
+    let opened = app_handle.state::<OpenedFiles>();
+    {
+        let mut pending = opened.pending.lock().unwrap();
+        if !opened.listening.load(Ordering::SeqCst) {
+            pending.push(analysis);
+            return;
+        }
+    }
+
+    crate::instance::focus_main_window(app_handle);
+    if let Err(e) = app_handle.emit_to("main", "analysis-file-opened", analysis) {
+        log::warn!("Failed to emit analysis-file-opened: {}", e);
+    }
+}
+
+/// Reads each file off the main thread and routes it to the main window.
+pub fn open(app_handle: &tauri::AppHandle, paths: Vec<PathBuf>) {
+    for path in paths {
+        let handle = app_handle.clone();
+        tauri::async_runtime::spawn_blocking(move || match read_analysis(&path) {
+            Ok(analysis) => deliver(&handle, analysis),
+            Err(e) => log::warn!("Ignoring opened file: {}", e),
+        });
+    }
+}
+
+/// `arg` as a path, if it names a saved analysis: a `.criblhc` file, or an
+/// existing `.json` export such as a report dropped on the app icon. Linux file
+/// managers may pass `file://` URIs rather than paths.
+fn analysis_path(arg: &str, cwd: &Path) -> Option<PathBuf> {
+    let path = match url::Url::parse(arg) {
+        Ok(url) if url.scheme() == "file" => url.to_file_path().ok()?,
+        _ => PathBuf::from(arg),
+    };
+    let path = cwd.join(path);
+    let extension = path.extension().and_then(|e| e.to_str())?;
+    let is_analysis = extension.eq_ignore_ascii_case(EXTENSION)
+        || (extension.eq_ignore_ascii_case("json") && path.is_file());
+    is_analysis.then_some(path)
+}
+
+/// Opens any saved analyses among launch arguments (program name excluded) and
+/// returns the other arguments.
+pub fn open_from_args(app_handle: &tauri::AppHandle, args: Vec<String>, cwd: &Path) -> Vec<String> {
+    let mut paths = Vec::new();
+    let rest = args
+        .into_iter()
+        .filter(|arg| match analysis_path(arg, cwd) {
+            Some(path) => {
+                paths.push(path);
+                false
+            }
+            None => true,
+        })
+        .collect();
+    open(app_handle, paths);
+    rest
+}
+
+/// Handles files the app was launched with (Windows and Linux).
+pub fn init(app: &tauri::App) {
+    let cwd = std::env::current_dir().unwrap_or_default();
+    open_from_args(app.handle(), std::env::args().skip(1).collect(), &cwd);
+}
+
+/// macOS delivers opened files as Apple events, arriving as `RunEvent::Opened`.
+#[cfg(target_os = "macos")]
+pub fn on_opened(app_handle: &tauri::AppHandle, urls: Vec<url::Url>) {
+    let paths = urls
+        .iter()
+        .filter(|url| url.scheme() == "file")
+        .filter_map(|url| url.to_file_path().ok())
+        .collect();
+    open(app_handle, paths);
+}
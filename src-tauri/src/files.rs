@@ -0,0 +1,485 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Instant;
+use tauri::Manager;
+
+/// Upper bound on a single chunk so one read can't recreate the giant IPC payload.
+const MAX_CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+/// Largest `content` accepted by a single-call save. Bytes cross IPC as a JSON array,
+/// several times their size, so beyond this the webview stalls or the call fails;
+/// larger content should go through `begin_chunked_save`.
+pub const MAX_SAVE_PAYLOAD_BYTES: usize = 32 * 1024 * 1024;
+
+const MAX_BENCHMARK_MB: usize = 2048;
+
+/// A save being streamed into `temp` until it is finished and renamed over `path`.
+struct PendingSave {
+    path: PathBuf,
+    temp: PathBuf,
+    file: File,
+}
+
+#[derive(Default)]
+struct OpenFiles {
+    next_handle: u64,
+    files: HashMap<u64, File>,
+    saves: HashMap<u64, PendingSave>,
+}
+
+#[derive(Default)]
+pub struct FileHandles {
+    open: Mutex<OpenFiles>,
+}
+
+#[derive(Serialize)]
+pub struct FileChunk {
+    data: Vec<u8>,
+    eof: bool,
+}
+
+#[tauri::command]
+pub fn open_file_chunked(app_handle: tauri::AppHandle, path: String) -> Result<u64, String> {
+    let file = File::open(&path).map_err(|e| format!("Failed to open file: {}", e))?;
+
+    let state = app_handle.state::<FileHandles>();
+    let mut open = state.open.lock().unwrap();
+    open.next_handle += 1;
+    let handle = open.next_handle;
+    open.files.insert(handle, file);
+
+    Ok(handle)
+}
+
+#[tauri::command]
+pub fn read_next_chunk(
+    app_handle: tauri::AppHandle,
+    handle: u64,
+    size: usize,
+) -> Result<FileChunk, String> {
+    let state = app_handle.state::<FileHandles>();
+    let mut open = state.open.lock().unwrap();
+    let file = open
+        .files
+        .get_mut(&handle)
+        .ok_or_else(|| format!("Unknown file handle {}", handle))?;
+
+    let size = size.clamp(1, MAX_CHUNK_SIZE);
+    let mut data = Vec::with_capacity(size);
+    Read::by_ref(file)
+        .take(size as u64)
+        .read_to_end(&mut data)
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+
+    Ok(FileChunk {
+        eof: data.len() < size,
+        data,
+    })
+}
+
+#[tauri::command]
+pub fn close_file(app_handle: tauri::AppHandle, handle: u64) -> bool {
+    let state = app_handle.state::<FileHandles>();
+    let removed = state.open.lock().unwrap().files.remove(&handle);
+    removed.is_some()
+}
+
+fn is_cross_device(error: &io::Error) -> bool {
+    #[cfg(unix)]
+    {
+        error.raw_os_error() == Some(libc::EXDEV)
+    }
+
+    #[cfg(windows)]
+    {
+        error.raw_os_error() == Some(windows_sys::Win32::Foundation::ERROR_NOT_SAME_DEVICE as i32)
+    }
+}
+
+pub fn temp_path(path: &Path) -> Result<PathBuf, String> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| format!("Invalid file path: {}", path.display()))?;
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(name);
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    Ok(path.with_file_name(temp_name))
+}
+
+/// Flushes `file` and moves `temp` into place at `path`.
+pub fn commit_temp(file: File, temp: &Path, path: &Path) -> Result<(), String> {
+    file.sync_all().map_err(|e| format!("Failed to save file: {}", e))?;
+    drop(file);
+
+    match fs::rename(temp, path) {
+        Ok(()) => Ok(()),
+        // Same directory, but e.g. a bind-mounted destination file; fall back to a plain copy.
+        Err(e) if is_cross_device(&e) => fs::copy(temp, path)
+            .map(|_| ())
+            .map_err(|e| format!("Failed to save file: {}", e)),
+        Err(e) => Err(format!("Failed to save file: {}", e)),
+    }
+}
+
+/// Whether `path` is inside the document portal's FUSE mount, where a sandboxed
+/// app's save dialog hands back files (`/run/user/<uid>/doc/<id>/<name>`). Only the
+/// chosen file itself is exposed there, so nothing can be created next to it.
+#[cfg(target_os = "linux")]
+fn is_portal_path(path: &Path) -> bool {
+    let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(format!("/run/user/{}", unsafe { libc::getuid() })));
+    path.starts_with(runtime_dir.join("doc"))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_portal_path(_path: &Path) -> bool {
+    false
+}
+
+/// Writes straight into a portal-provided file, since the temp file an atomic save
+/// needs can't be created beside it.
+fn write_portal(path: &Path, write: impl FnOnce(&mut File) -> Result<(), String>) -> Result<(), String> {
+    let mut file = File::create(path).map_err(|e| match e.kind() {
+        io::ErrorKind::PermissionDenied => format!(
+            "PortalPermissionDenied: the desktop file portal did not grant write access to {}. \
+             Choose the location again in the save dialog, or give the app access to that folder \
+             (for Flatpak: flatpak override --user --filesystem=<folder>)",
+            path.display()
+        ),
+        _ => format!("Failed to create file: {}", e),
+    })?;
+    write(&mut file)?;
+    file.sync_all().map_err(|e| format!("Failed to save file: {}", e))
+}
+
+/// Writes through a temp file next to `path` and renames it into place, so after a
+/// crash `path` holds either its old contents or the complete new ones.
+pub fn write_atomic(
+    path: &Path,
+    write: impl FnOnce(&mut File) -> Result<(), String>,
+) -> Result<(), String> {
+    if is_portal_path(path) {
+        return write_portal(path, write);
+    }
+    let temp = temp_path(path)?;
+
+    let result = (|| {
+        let mut file = File::create(&temp).map_err(|e| format!("Failed to create file: {}", e))?;
+        write(&mut file)?;
+        commit_temp(file, &temp, path)
+    })();
+
+    let _ = fs::remove_file(&temp);
+    result
+}
+
+/// Versions of downloaded rule packs and updates become directory names, so
+/// they are kept to plain characters; `kind` names what was downloaded.
+pub fn validate_version(kind: &str, version: &str) -> Result<(), String> {
+    let valid = !version.is_empty()
+        && !version.starts_with('.')
+        && version.len() <= 64
+        && version.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | '+'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid {} version {:?}", kind, version))
+    }
+}
+
+/// Rejects content too large to have come through a single IPC call comfortably.
+pub fn check_save_payload(len: usize) -> Result<(), String> {
+    if len > MAX_SAVE_PAYLOAD_BYTES {
+        return Err(format!(
+            "PayloadTooLarge: {} bytes exceeds the {} byte limit for a single save; use begin_chunked_save instead",
+            len, MAX_SAVE_PAYLOAD_BYTES
+        ));
+    }
+    Ok(())
+}
+
+pub fn write_file_atomic(path: &Path, content: &[u8]) -> Result<(), String> {
+    write_atomic(path, |file| {
+        file.write_all(content).map_err(|e| format!("Failed to save file: {}", e))
+    })
+}
+
+/// Asks where to save and returns a handle to stream content to with
+/// `write_save_chunk`. Nothing appears at the destination until `finish_chunked_save`.
+#[tauri::command]
+pub async fn begin_chunked_save(
+    app_handle: tauri::AppHandle,
+    filename: String,
+    default_extension: Option<String>,
+) -> Result<u64, String> {
+    let path = crate::dialogs::save_path_with_extension(&app_handle, &filename, default_extension.as_deref()).await?;
+    let temp = temp_path(&path)?;
+    let file = File::create(&temp).map_err(|e| format!("Failed to create file: {}", e))?;
+
+    let state = app_handle.state::<FileHandles>();
+    let mut open = state.open.lock().unwrap();
+    open.next_handle += 1;
+    let handle = open.next_handle;
+    open.saves.insert(handle, PendingSave { path, temp, file });
+
+    Ok(handle)
+}
+
+#[tauri::command]
+pub fn write_save_chunk(app_handle: tauri::AppHandle, handle: u64, data: Vec<u8>) -> Result<(), String> {
+    if data.len() > MAX_CHUNK_SIZE {
+        return Err(format!(
+            "PayloadTooLarge: {} bytes exceeds the {} byte chunk limit",
+            data.len(),
+            MAX_CHUNK_SIZE
+        ));
+    }
+
+    let state = app_handle.state::<FileHandles>();
+    let mut open = state.open.lock().unwrap();
+    let save = open
+        .saves
+        .get_mut(&handle)
+        .ok_or_else(|| format!("Unknown save handle {}", handle))?;
+
+    save.file
+        .write_all(&data)
+        .map_err(|e| format!("Failed to save file: {}", e))
+}
+
+/// Moves the streamed content into place and returns the saved path.
+#[tauri::command]
+pub fn finish_chunked_save(app_handle: tauri::AppHandle, handle: u64) -> Result<String, String> {
+    let state = app_handle.state::<FileHandles>();
+    let save = state
+        .open
+        .lock()
+        .unwrap()
+        .saves
+        .remove(&handle)
+        .ok_or_else(|| format!("Unknown save handle {}", handle))?;
+
+    let result = commit_temp(save.file, &save.temp, &save.path);
+    let _ = fs::remove_file(&save.temp);
+    result.map(|_| save.path.to_string_lossy().to_string())
+}
+
+/// Discards a streamed save, leaving the destination untouched.
+#[tauri::command]
+pub fn abort_chunked_save(app_handle: tauri::AppHandle, handle: u64) -> bool {
+    let state = app_handle.state::<FileHandles>();
+    let removed = state.open.lock().unwrap().saves.remove(&handle);
+    removed.is_some_and(|save| {
+        drop(save.file);
+        let _ = fs::remove_file(&save.temp);
+        true
+    })
+}
+
+#[derive(Serialize)]
+pub struct SaveBenchmark {
+    bytes: u64,
+    elapsed_ms: f64,
+    throughput_mb_per_sec: f64,
+}
+
+/// Times writing `size_mb` of dummy data to a temp file, bypassing the dialog and IPC.
+#[tauri::command]
+pub async fn benchmark_save(size_mb: usize) -> Result<SaveBenchmark, String> {
+    if size_mb == 0 || size_mb > MAX_BENCHMARK_MB {
+        return Err(format!("Size must be between 1 and {} MB", MAX_BENCHMARK_MB));
+    }
+
+    let block: Vec<u8> = (0..1024 * 1024).map(|i| (i % 251) as u8).collect();
+    let path = std::env::temp_dir().join(format!("cribl-hc-benchmark-{}.tmp", std::process::id()));
+
+    let started = Instant::now();
+    let result = File::create(&path).and_then(|mut file| {
+        for _ in 0..size_mb {
+            file.write_all(&block)?;
+        }
+        // Include the flush to disk, otherwise we only measure the page cache.
+        file.sync_all()
+    });
+    let elapsed = started.elapsed();
+    let _ = fs::remove_file(&path);
+
+    result.map_err(|e| format!("Failed to write benchmark file: {}", e))?;
+
+    let secs = elapsed.as_secs_f64();
+    Ok(SaveBenchmark {
+        bytes: (size_mb * block.len()) as u64,
+        elapsed_ms: secs * 1000.0,
+        throughput_mb_per_sec: if secs > 0.0 { size_mb as f64 / secs } else { 0.0 },
+    })
+}
+
+/// Creates and removes a file in `dir`; permission bits alone miss ACLs and read-only mounts.
+fn probe_writable(dir: &Path) -> io::Result<()> {
+    let probe = dir.join(format!(".cribl-hc-write-test-{}", std::process::id()));
+    let result = fs::OpenOptions::new().write(true).create_new(true).open(&probe);
+    let _ = fs::remove_file(&probe);
+    result.map(|_| ())
+}
+
+#[derive(Serialize)]
+pub struct SaveDirectoryCheck {
+    valid: bool,
+    exists: bool,
+    is_directory: bool,
+    writable: bool,
+    reason: Option<String>,
+}
+
+/// Checks a typed-in save directory without opening a dialog.
+#[tauri::command]
+pub async fn validate_save_directory(path: String) -> SaveDirectoryCheck {
+    let dir = Path::new(&path);
+    let failed = |exists, is_directory, reason: String| SaveDirectoryCheck {
+        valid: false,
+        exists,
+        is_directory,
+        writable: false,
+        reason: Some(reason),
+    };
+
+    if path.trim().is_empty() {
+        return failed(false, false, "No directory given".to_string());
+    }
+
+    let metadata = match fs::metadata(dir) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return failed(false, false, "Directory does not exist".to_string())
+        }
+        Err(e) => return failed(false, false, format!("Cannot access directory: {}", e)),
+    };
+
+    if !metadata.is_dir() {
+        return failed(true, false, "Path is a file, not a directory".to_string());
+    }
+
+    match probe_writable(dir) {
+        Ok(_) => SaveDirectoryCheck {
+            valid: true,
+            exists: true,
+            is_directory: true,
+            writable: true,
+            reason: None,
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+            failed(true, true, "Directory is not writable".to_string())
+        }
+        Err(e) => failed(true, true, format!("Cannot write to directory: {}", e)),
+    }
+}
+
+#[derive(Serialize)]
+pub struct ResourceDirCheck {
+    path: String,
+    writable: bool,
+    reason: Option<String>,
+}
+
+/// Reports whether the install's resource dir is writable. Nothing in the app writes
+/// there (settings, logs and temp files use the per-user dirs), so a read-only mount
+/// is supported; this exists so support can rule it out quickly.
+#[tauri::command]
+pub fn check_resource_dir_writable(app_handle: tauri::AppHandle) -> Result<ResourceDirCheck, String> {
+    let dir = app_handle
+        .path()
+        .resource_dir()
+        .map_err(|e| format!("Failed to get resource dir: {}", e))?;
+    let result = probe_writable(&dir);
+
+    Ok(ResourceDirCheck {
+        path: dir.to_string_lossy().to_string(),
+        writable: result.is_ok(),
+        reason: result.err().map(|e| e.to_string()),
+    })
+}
+
+/// Rebuilds objects with their keys in sorted order. serde_json keeps insertion
+/// order here (another dependency enables `preserve_order`), so this can't rely
+/// on the map type doing it.
+fn canonicalize(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut entries: Vec<_> = map.into_iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            serde_json::Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, canonicalize(value)))
+                    .collect(),
+            )
+        }
+        serde_json::Value::Array(items) => serde_json::Value::Array(items.into_iter().map(canonicalize).collect()),
+        other => other,
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Normalization {
+    /// Parsed and re-serialized compactly with sorted keys.
+    Json,
+    /// CRLF line endings converted to LF.
+    LineEndings,
+    /// Not text; hashed as-is.
+    None,
+}
+
+#[derive(Serialize)]
+pub struct FileFingerprint {
+    sha256: String,
+    normalization: Normalization,
+    size: u64,
+}
+
+/// SHA-256 of a file's normalized content, so a config that was only reformatted
+/// or re-saved on another OS fingerprints the same.
+#[tauri::command]
+pub async fn fingerprint_file(path: String) -> Result<FileFingerprint, String> {
+    let bytes = fs::read(&path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let size = bytes.len() as u64;
+
+    let (normalized, normalization) = match serde_json::from_slice::<serde_json::Value>(&bytes) {
+        Ok(value) => {
+            let canonical = serde_json::to_vec(&canonicalize(value))
+                .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
+            (canonical, Normalization::Json)
+        }
+        Err(_) => match String::from_utf8(bytes) {
+            Ok(text) => (text.replace("\r\n", "\n").into_bytes(), Normalization::LineEndings),
+            Err(e) => (e.into_bytes(), Normalization::None),
+        },
+    };
+
+    Ok(FileFingerprint {
+        sha256: format!("{:x}", Sha256::digest(&normalized)),
+        normalization,
+        size,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_cannot_leave_their_directory() {
+        for version in ["1.4.0", "2.0.0-rc.1", "1.0+build_7"] {
+            validate_version("update", version).unwrap();
+        }
+        for version in ["", "..", "../..", ".hidden", "1.0/../..", "..\\evil", "/etc", "C:x", "1.0 ", &"9".repeat(65)] {
+            assert!(validate_version("update", version).is_err(), "{:?}", version);
+        }
+    }
+}
@@ -0,0 +1,261 @@
+//! Authenticating reverse proxy in front of the backend.
+//!
+//! The backend listens on loopback (or a local socket, see `transport`) without
+//! auth of its own, so any local process could drive it. Instead the webview talks to this gateway, which only forwards
+//! requests carrying the per-launch session token and adds the token to what it
+//! forwards; the backend is started with the same token and rejects requests
+//! without it.
+//!
+//! `get_backend_url` hands out `http://127.0.0.1:<port>/s/<token>`, so relative
+//! paths, `fetch`, window navigation and WebSockets all carry the token in the
+//! path. Requests authenticated that way also get a session cookie, for pages
+//! like the API docs that load assets from absolute paths.
+
+use http_body_util::{combinators::BoxBody, BodyExt, Full};
+use hyper::body::{Bytes, Incoming};
+use hyper::header::{self, HeaderValue};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::Manager;
+
+use crate::transport::{self, Endpoint};
+use crate::PythonBackend;
+
+/// Header the gateway adds to forwarded requests, and that callers may send
+/// instead of the path prefix.
+pub const TOKEN_HEADER: &str = "x-cribl-hc-token";
+/// Passes the session token to the backend.
+pub const TOKEN_VAR: &str = "CRIBL_HC_API_TOKEN";
+const SESSION_PREFIX: &str = "/s/";
+const SESSION_COOKIE: &str = "cribl_hc_session";
+
+type Body = BoxBody<Bytes, hyper::Error>;
+
+struct Listening {
+    port: u16,
+    token: Arc<str>,
+}
+
+#[derive(Default)]
+pub struct GatewayState {
+    listening: Mutex<Option<Listening>>,
+}
+
+fn same_token(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// How a request proved it holds the token.
+enum Authenticated {
+    /// Through the `/s/<token>` prefix; the backend path is what follows it.
+    Path(String),
+    /// Through the header or the session cookie.
+    Credential,
+}
+
+fn authenticate(token: &str, req: &Request<Incoming>) -> Option<Authenticated> {
+    let path_and_query = req.uri().path_and_query().map_or("/", |pq| pq.as_str());
+    if let Some(rest) = path_and_query.strip_prefix(SESSION_PREFIX) {
+        let end = rest.find(['/', '?']).unwrap_or(rest.len());
+        let (candidate, remainder) = rest.split_at(end);
+        if same_token(candidate, token) {
+            return Some(Authenticated::Path(match remainder.strip_prefix('/') {
+                Some(path) => format!("/{}", path),
+                None => format!("/{}", remainder),
+            }));
+        }
+    }
+
+    let header = req
+        .headers()
+        .get(TOKEN_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| same_token(value, token));
+    let cookie = req
+        .headers()
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .any(|(name, value)| name == SESSION_COOKIE && same_token(value, token));
+
+    (header || cookie).then_some(Authenticated::Credential)
+}
+
+fn plain(status: StatusCode, message: &str) -> Response<Body> {
+    let mut response = Response::new(
+        Full::new(Bytes::from(message.to_string()))
+            .map_err(|never| match never {})
+            .boxed(),
+    );
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static("text/plain; charset=utf-8"));
+    response
+}
+
+/// Splices an upgraded (WebSocket) connection to the backend's once both sides
+/// have switched protocols.
+fn splice(client: hyper::upgrade::OnUpgrade, backend: hyper::upgrade::OnUpgrade) {
+    tauri::async_runtime::spawn(async move {
+        match tokio::try_join!(client, backend) {
+            Ok((client, backend)) => {
+                let _ = tokio::io::copy_bidirectional(&mut TokioIo::new(client), &mut TokioIo::new(backend)).await;
+            }
+            Err(e) => log::debug!("Gateway upgrade failed: {}", e),
+        }
+    });
+}
+
+async fn forward(
+    backend: &Endpoint,
+    token: &str,
+    path: Option<String>,
+    mut req: Request<Incoming>,
+) -> Result<Response<Body>, String> {
+    let stream = transport::connect(backend)
+        .await
+        .map_err(|e| format!("Failed to connect to backend: {}", e))?;
+    let (mut sender, connection) = hyper::client::conn::http1::handshake(TokioIo::new(stream))
+        .await
+        .map_err(|e| format!("Failed to connect to backend: {}", e))?;
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = connection.with_upgrades().await {
+            log::debug!("Gateway connection to backend ended: {}", e);
+        }
+    });
+
+    let client_upgrade = req
+        .headers()
+        .contains_key(header::UPGRADE)
+        .then(|| hyper::upgrade::on(&mut req));
+
+    if let Some(path) = path {
+        *req.uri_mut() = path.parse().map_err(|e| format!("Invalid request path {}: {}", path, e))?;
+    }
+    let headers = req.headers_mut();
+    // Values we built ourselves from a port number and hex token
+    headers.insert(
+        header::HOST,
+        HeaderValue::from_str(&backend.host()).map_err(|e| e.to_string())?,
+    );
+    headers.insert(TOKEN_HEADER, HeaderValue::from_str(token).map_err(|e| e.to_string())?);
+
+    let mut response = sender
+        .send_request(req)
+        .await
+        .map_err(|e| format!("Backend request failed: {}", e))?;
+
+    if response.status() == StatusCode::SWITCHING_PROTOCOLS {
+        if let Some(client_upgrade) = client_upgrade {
+            splice(client_upgrade, hyper::upgrade::on(&mut response));
+        }
+    }
+    Ok(response.map(BodyExt::boxed))
+}
+
+async fn handle(app_handle: tauri::AppHandle, token: Arc<str>, req: Request<Incoming>) -> Result<Response<Body>, Infallible> {
+    let Some(authenticated) = authenticate(&token, &req) else {
+        return Ok(plain(StatusCode::UNAUTHORIZED, "Missing or invalid session token"));
+    };
+    let Some(backend) = app_handle.state::<PythonBackend>().endpoint.lock().unwrap().clone() else {
+        return Ok(plain(StatusCode::SERVICE_UNAVAILABLE, "Backend not started yet"));
+    };
+
+    let (path, set_cookie) = match authenticated {
+        Authenticated::Path(path) => (Some(path), true),
+        Authenticated::Credential => (None, false),
+    };
+    let mut response = match forward(&backend, &token, path, req).await {
+        Ok(response) => response,
+        Err(e) => return Ok(plain(StatusCode::BAD_GATEWAY, &e)),
+    };
+
+    if set_cookie {
+        let cookie = format!("{}={}; Path=/; HttpOnly; SameSite=Strict", SESSION_COOKIE, token);
+        if let Ok(value) = HeaderValue::from_str(&cookie) {
+            response.headers_mut().append(header::SET_COOKIE, value);
+        }
+    }
+    Ok(response)
+}
+
+async fn serve(app_handle: tauri::AppHandle, listener: std::net::TcpListener, token: Arc<str>) {
+    let listener = match tokio::net::TcpListener::from_std(listener) {
+        Ok(listener) => listener,
+        Err(e) => {
+            log::error!("Backend gateway failed to start: {}", e);
+            return;
+        }
+    };
+
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                // Usually out of file descriptors; give in-flight requests a moment to finish
+                log::warn!("Backend gateway failed to accept a connection: {}", e);
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+
+        let app_handle = app_handle.clone();
+        let token = token.clone();
+        tauri::async_runtime::spawn(async move {
+            let service = service_fn(move |req| handle(app_handle.clone(), token.clone(), req));
+            if let Err(e) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .with_upgrades()
+                .await
+            {
+                log::debug!("Gateway connection ended: {}", e);
+            }
+        });
+    }
+}
+
+/// Binds the gateway on a free loopback port and starts serving. The port and
+/// token stay the same for the life of the app, across backend restarts.
+pub fn start(app_handle: &tauri::AppHandle) -> Result<(), String> {
+    let token: Arc<str> = crate::new_token()?.into();
+    let listener = std::net::TcpListener::bind(("127.0.0.1", 0))
+        .and_then(|listener| listener.set_nonblocking(true).map(|_| listener))
+        .map_err(|e| format!("Failed to start backend gateway: {}", e))?;
+    let port = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to start backend gateway: {}", e))?
+        .port();
+
+    *app_handle.state::<GatewayState>().listening.lock().unwrap() = Some(Listening {
+        port,
+        token: token.clone(),
+    });
+    tauri::async_runtime::spawn(serve(app_handle.clone(), listener, token));
+    log::info!("Backend gateway listening on port {}", port);
+    Ok(())
+}
+
+/// The token the backend must require, once the gateway is running.
+pub fn token(app_handle: &tauri::AppHandle) -> Option<String> {
+    let state = app_handle.state::<GatewayState>();
+    let listening = state.listening.lock().unwrap();
+    listening.as_ref().map(|l| l.token.to_string())
+}
+
+/// Base URL of the authenticated endpoint.
+pub fn url(app_handle: &tauri::AppHandle) -> Result<String, String> {
+    let state = app_handle.state::<GatewayState>();
+    let listening = state.listening.lock().unwrap();
+    listening
+        .as_ref()
+        .map(|l| format!("http://127.0.0.1:{}{}{}", l.port, SESSION_PREFIX, l.token))
+        .ok_or_else(|| "Backend gateway is not running".to_string())
+}
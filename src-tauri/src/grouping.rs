@@ -0,0 +1,257 @@
+//! Grouping of like findings into cards, for reports with thousands of findings
+//! that mostly repeat a few checks across many objects.
+//!
+//! `GroupingStrategy::Check` groups findings of the same check in the same
+//! worker group. A finding's check is its rule id when it has one; otherwise its
+//! category and title, with the names of affected objects, quoted text and
+//! numbers taken out, since those are what differ between occurrences.
+//! `GroupingStrategy::Similar` goes further and merges checks whose titles and
+//! descriptions share most of their words, across worker groups.
+//!
+//! Findings reported more than once (same finding id in the same worker group)
+//! count once.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+const SEVERITIES: &[&str] = &["critical", "high", "medium", "low", "info"];
+/// Share of words two checks need in common to be merged by `Similar`.
+const SIMILARITY_THRESHOLD: f64 = 0.75;
+/// Affected objects and finding ids listed per card; the rest are only counted.
+const MAX_LISTED: usize = 500;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupingStrategy {
+    #[default]
+    Check,
+    Similar,
+}
+
+#[derive(Serialize)]
+pub struct FindingGroup {
+    /// Stable while the findings stay the same, for keeping a card expanded.
+    pub key: String,
+    /// The title of the group's most severe finding.
+    pub title: String,
+    pub severity: String,
+    pub category: String,
+    pub worker_groups: Vec<String>,
+    pub occurrences: usize,
+    /// Sorted; at most `MAX_LISTED`.
+    pub affected_components: Vec<String>,
+    pub affected_count: usize,
+    /// At most `MAX_LISTED`.
+    pub finding_ids: Vec<String>,
+    /// The most severe finding in full, for its description and remediation.
+    pub sample: Value,
+}
+
+#[derive(Serialize)]
+pub struct FindingGroups {
+    pub strategy: GroupingStrategy,
+    pub findings_count: usize,
+    /// Findings dropped as repeats of another.
+    pub duplicates: usize,
+    /// Most severe first, then most occurrences.
+    pub groups: Vec<FindingGroup>,
+}
+
+fn text<'a>(finding: &'a Value, key: &str) -> &'a str {
+    finding.get(key).and_then(Value::as_str).unwrap_or_default()
+}
+
+fn rank(severity: &str) -> usize {
+    SEVERITIES.iter().position(|s| *s == severity).unwrap_or(SEVERITIES.len())
+}
+
+fn severity(finding: &Value) -> String {
+    text(finding, "severity").trim().to_ascii_lowercase()
+}
+
+fn affected(finding: &Value) -> Vec<&str> {
+    finding
+        .get("affected_components")
+        .and_then(Value::as_array)
+        .map(|items| items.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default()
+}
+
+/// `text` lower-cased with object names, quoted text and numbers replaced, so
+/// that occurrences of one check read the same.
+fn template(text: &str, names: &[&str]) -> String {
+    let mut text = text.to_string();
+    let mut names: Vec<&str> = names.iter().copied().filter(|n| n.chars().count() > 1).collect();
+    // Longest first, so a name containing another is replaced whole
+    names.sort_by_key(|n| std::cmp::Reverse(n.len()));
+    for name in names {
+        text = text.replace(name, "*");
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut quote: Option<char> = None;
+    for c in text.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '\'' || c == '"' || c == '`' => {
+                quote = Some(c);
+                out.push('*');
+            }
+            None if c.is_ascii_digit() => {
+                if !out.ends_with('#') {
+                    out.push('#');
+                }
+            }
+            None => out.extend(c.to_lowercase()),
+        }
+    }
+    out.trim().to_string()
+}
+
+/// What `Check` groups by, before the worker group.
+fn check_key(finding: &Value) -> String {
+    let metadata = finding.get("metadata");
+    if let Some(rule) = ["rule_id", "check_id"]
+        .iter()
+        .find_map(|key| metadata.and_then(|m| m.get(key)).and_then(Value::as_str))
+    {
+        return format!("rule:{}", rule);
+    }
+    format!("{}:{}", text(finding, "category"), template(text(finding, "title"), &affected(finding)))
+}
+
+fn words(finding: &Value) -> HashSet<String> {
+    let names = affected(finding);
+    let text = format!("{} {}", text(finding, "title"), text(finding, "description"));
+    template(&text, &names)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.len() > 1)
+        .map(str::to_string)
+        .collect()
+}
+
+fn similarity(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    let shared = a.intersection(b).count();
+    shared as f64 / (a.len() + b.len() - shared) as f64
+}
+
+/// The findings that make up one card.
+struct Cluster<'a> {
+    key: String,
+    findings: Vec<&'a Value>,
+}
+
+fn card(cluster: Cluster) -> FindingGroup {
+    let most_severe = cluster
+        .findings
+        .iter()
+        .copied()
+        .min_by_key(|f| rank(&severity(f)))
+        .cloned()
+        .unwrap_or(Value::Null);
+    let worker_groups: BTreeSet<String> = cluster.findings.iter().map(|f| crate::history::worker_group(f)).collect();
+    let components: BTreeSet<&str> = cluster.findings.iter().flat_map(|f| affected(f)).collect();
+
+    FindingGroup {
+        key: cluster.key,
+        title: text(&most_severe, "title").to_string(),
+        severity: severity(&most_severe),
+        category: text(&most_severe, "category").to_string(),
+        worker_groups: worker_groups.into_iter().collect(),
+        occurrences: cluster.findings.len(),
+        affected_count: components.len(),
+        affected_components: components.into_iter().take(MAX_LISTED).map(str::to_string).collect(),
+        finding_ids: cluster
+            .findings
+            .iter()
+            .filter_map(|f| f.get("id").and_then(Value::as_str))
+            .take(MAX_LISTED)
+            .map(str::to_string)
+            .collect(),
+        sample: most_severe,
+    }
+}
+
+/// Groups the findings of an analysis result (the backend's JSON export).
+pub fn group(result: &Value, strategy: GroupingStrategy) -> FindingGroups {
+    let findings: &[Value] = result.get("findings").and_then(Value::as_array).map_or(&[], Vec::as_slice);
+
+    let mut seen = HashSet::new();
+    let unique: Vec<&Value> = findings
+        .iter()
+        .filter(|f| match f.get("id").and_then(Value::as_str) {
+            Some(id) => seen.insert((crate::history::worker_group(f), id)),
+            None => true,
+        })
+        .collect();
+    let duplicates = findings.len() - unique.len();
+
+    // Both strategies start from checks; `Similar` merges them further
+    let mut by_check: BTreeMap<String, Vec<&Value>> = BTreeMap::new();
+    for finding in unique {
+        let key = match strategy {
+            GroupingStrategy::Check => format!("{}|{}", crate::history::worker_group(finding), check_key(finding)),
+            GroupingStrategy::Similar => check_key(finding),
+        };
+        by_check.entry(key).or_default().push(finding);
+    }
+
+    let clusters: Vec<Cluster> = match strategy {
+        GroupingStrategy::Check => by_check
+            .into_iter()
+            .map(|(key, findings)| Cluster { key, findings })
+            .collect(),
+        GroupingStrategy::Similar => {
+            // Checks are few even when findings are many, so comparing each
+            // check with every cluster so far stays cheap
+            let mut clusters: Vec<(HashSet<String>, Cluster)> = Vec::new();
+            for (key, findings) in by_check {
+                let words = words(findings[0]);
+                match clusters
+                    .iter_mut()
+                    .find(|(representative, _)| similarity(representative, &words) >= SIMILARITY_THRESHOLD)
+                {
+                    Some((_, cluster)) => cluster.findings.extend(findings),
+                    None => clusters.push((words, Cluster { key, findings })),
+                }
+            }
+            clusters.into_iter().map(|(_, cluster)| cluster).collect()
+        }
+    };
+
+    let mut groups: Vec<FindingGroup> = clusters.into_iter().map(card).collect();
+    groups.sort_by(|a, b| {
+        rank(&a.severity)
+            .cmp(&rank(&b.severity))
+            .then(b.occurrences.cmp(&a.occurrences))
+            .then_with(|| a.key.cmp(&b.key))
+    });
+
+    FindingGroups {
+        strategy,
+        findings_count: findings.len(),
+        duplicates,
+        groups,
+    }
+}
+
+/// Groups saved run `run_id`'s findings into cards of like findings, with how
+/// often each occurs and what it affects.
+#[tauri::command]
+pub async fn group_findings(
+    app_handle: tauri::AppHandle,
+    run_id: String,
+    strategy: Option<GroupingStrategy>,
+) -> Result<FindingGroups, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = crate::history::get_analysis_run(app_handle, run_id)?;
+        Ok(group(&result, strategy.unwrap_or_default()))
+    })
+    .await
+    .map_err(|e| format!("Failed to group findings: {}", e))?
+}
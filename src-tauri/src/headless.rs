@@ -0,0 +1,441 @@
+//! `--headless` mode for CI: start the backend, run one analysis against a saved
+//! deployment, write the JSON report and exit non-zero if the health score is
+//! below the threshold. Runs before any Tauri setup, so it needs no display.
+//!
+//! ```text
+//! cribl-hc --headless --config run.yaml --output report.json [--min-score 80] [--verbose]
+//! ```
+//!
+//! The config file is JSON or a flat YAML mapping:
+//!
+//! ```yaml
+//! deployment: prod          # a deployment with saved credentials
+//! analyzers: [health, config]
+//! min_health_score: 75
+//! timeout_secs: 900
+//! output: report.json
+//! ```
+
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+
+use crate::{process, sidecar};
+
+/// Health score met (or no threshold).
+const EXIT_PASSED: i32 = 0;
+/// Health score below the threshold, or the run produced none.
+const EXIT_BELOW_THRESHOLD: i32 = 1;
+/// Bad arguments or config, or the backend or analysis failed.
+const EXIT_ERROR: i32 = 2;
+
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+const DEFAULT_ANALYSIS_TIMEOUT_SECS: u64 = 900;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RunConfig {
+    deployment: Option<String>,
+    analyzers: Option<Vec<String>>,
+    min_health_score: Option<f64>,
+    timeout_secs: Option<u64>,
+    output: Option<PathBuf>,
+}
+
+#[derive(Default)]
+struct Args {
+    config: Option<PathBuf>,
+    output: Option<PathBuf>,
+    deployment: Option<String>,
+    min_score: Option<f64>,
+    verbose: bool,
+}
+
+fn parse_args(args: &[String]) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    let mut rest = args.iter();
+
+    while let Some(arg) = rest.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = || {
+            inline
+                .clone()
+                .or_else(|| rest.next().cloned())
+                .ok_or_else(|| format!("{} needs a value", flag))
+        };
+
+        match flag {
+            "--headless" => {}
+            "--verbose" => parsed.verbose = true,
+            "--config" => parsed.config = Some(PathBuf::from(value()?)),
+            "--output" => parsed.output = Some(PathBuf::from(value()?)),
+            "--deployment" => parsed.deployment = Some(value()?),
+            "--min-score" => {
+                let score = value()?;
+                parsed.min_score = Some(score.parse().map_err(|_| format!("Invalid --min-score: {}", score))?);
+            }
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+    Ok(parsed)
+}
+
+/// A YAML scalar as JSON: quoted strings stay strings, bare ones may be numbers or booleans.
+fn yaml_scalar(raw: &str) -> Value {
+    let raw = raw.trim();
+    for quote in ['"', '\''] {
+        if let Some(inner) = raw.strip_prefix(quote).and_then(|s| s.strip_suffix(quote)) {
+            return Value::String(inner.to_string());
+        }
+    }
+    match raw {
+        "true" => return Value::Bool(true),
+        "false" => return Value::Bool(false),
+        "null" | "~" | "" => return Value::Null,
+        _ => {}
+    }
+    if let Ok(n) = raw.parse::<u64>() {
+        return json!(n);
+    }
+    if let Ok(n) = raw.parse::<f64>() {
+        return json!(n);
+    }
+    Value::String(raw.to_string())
+}
+
+fn strip_comment(line: &str) -> &str {
+    match line.find(" #") {
+        Some(at) => &line[..at],
+        None if line.trim_start().starts_with('#') => "",
+        None => line,
+    }
+}
+
+/// Enough YAML for a run config: top-level `key: value` pairs whose values are
+/// scalars, `[a, b]` lists or `- item` block lists.
+fn parse_yaml(text: &str) -> Result<Value, String> {
+    let mut map = Map::new();
+    let mut list_key: Option<String> = None;
+
+    for (number, line) in text.lines().enumerate() {
+        let line = strip_comment(line).trim_end();
+        if line.trim().is_empty() || line == "---" {
+            continue;
+        }
+
+        if let Some(item) = line.trim_start().strip_prefix("- ") {
+            let Some(Value::Array(items)) = list_key.as_ref().and_then(|key| map.get_mut(key)) else {
+                return Err(format!("Line {}: list item outside a list", number + 1));
+            };
+            items.push(yaml_scalar(item));
+            continue;
+        }
+        if line.starts_with(char::is_whitespace) {
+            return Err(format!("Line {}: nested mappings are not supported", number + 1));
+        }
+
+        let (key, value) = line
+            .split_once(':')
+            .ok_or_else(|| format!("Line {}: expected `key: value`", number + 1))?;
+        let key = key.trim().to_string();
+        let value = value.trim();
+
+        list_key = None;
+        let parsed = if value.is_empty() {
+            list_key = Some(key.clone());
+            Value::Array(Vec::new())
+        } else if let Some(inner) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+            Value::Array(
+                inner
+                    .split(',')
+                    .filter(|item| !item.trim().is_empty())
+                    .map(yaml_scalar)
+                    .collect(),
+            )
+        } else {
+            yaml_scalar(value)
+        };
+        map.insert(key, parsed);
+    }
+    Ok(Value::Object(map))
+}
+
+fn load_config(path: &Path) -> Result<RunConfig, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let value = match serde_json::from_str(&text) {
+        Ok(value) => value,
+        Err(_) => parse_yaml(&text).map_err(|e| format!("Invalid config {}: {}", path.display(), e))?,
+    };
+    serde_json::from_value(value).map_err(|e| format!("Invalid config {}: {}", path.display(), e))
+}
+
+/// A backend started for this run, stopped again when dropped.
+struct Backend {
+    child: Child,
+    _job: Option<process::Job>,
+    port: u16,
+    shutdown_token: String,
+}
+
+impl Drop for Backend {
+    fn drop(&mut self) {
+        let requested = crate::request_shutdown(&crate::transport::Endpoint::Tcp(self.port), &self.shutdown_token);
+        process::terminate(&mut self.child, crate::GRACEFUL_STOP_TIMEOUT, !requested);
+    }
+}
+
+/// Forwards each line of `stream` to `lines`, echoing it to stderr when `verbose`.
+fn forward_lines(stream: impl Read + Send + 'static, lines: mpsc::Sender<String>, verbose: bool) {
+    std::thread::spawn(move || {
+        for line in BufReader::new(stream).lines().map_while(Result::ok) {
+            if verbose {
+                eprintln!("[backend] {}", line);
+            }
+            // Keep draining after the handshake so the backend never blocks on a full pipe
+            let _ = lines.send(line);
+        }
+    });
+}
+
+/// Waits for the `PORT:<n>` and `READY` lines run_api.py prints once it is listening.
+fn wait_for_port(lines: &Receiver<String>) -> Result<u16, String> {
+    let deadline = Instant::now() + STARTUP_TIMEOUT;
+    let mut port = None;
+    let mut last_line = String::new();
+
+    let exited = loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let line = match lines.recv_timeout(remaining) {
+            Ok(line) => line,
+            Err(e) => break e == mpsc::RecvTimeoutError::Disconnected,
+        };
+        let line = line.trim().to_string();
+        if let Some(p) = port.filter(|_| line == "READY") {
+            return Ok(p);
+        }
+        if let Some(value) = line.strip_prefix("PORT:") {
+            port = value.trim().parse().ok();
+        }
+        if !line.is_empty() {
+            last_line = line;
+        }
+    };
+
+    match port {
+        Some(p) => Ok(p),
+        None if !last_line.is_empty() => Err(format!("Backend did not start: {}", last_line)),
+        None if exited => Err("Backend exited before reporting its port".to_string()),
+        None => Err(format!("Backend did not start within {} s", STARTUP_TIMEOUT.as_secs())),
+    }
+}
+
+fn start_backend(verbose: bool) -> Result<Backend, String> {
+    let sidecar_path = sidecar::resolve_path_beside_exe()?;
+    let shutdown_token = crate::new_token()?;
+
+    let mut command = Command::new(&sidecar_path);
+    command
+        .arg("--port")
+        .arg("0")
+        .arg("--host")
+        .arg("127.0.0.1")
+        .env("PYTHONUNBUFFERED", "1")
+        .env(crate::SHUTDOWN_TOKEN_VAR, &shutdown_token)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    process::isolate_before_spawn(&mut command);
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| sidecar::explain_startup_failure(&sidecar_path, format!("Failed to start backend: {}", e)))?;
+    let job = process::contain(&child).ok();
+
+    let (sender, lines) = mpsc::channel();
+    if let Some(stdout) = child.stdout.take() {
+        forward_lines(stdout, sender.clone(), verbose);
+    }
+    if let Some(stderr) = child.stderr.take() {
+        forward_lines(stderr, sender, verbose);
+    }
+
+    match wait_for_port(&lines) {
+        Ok(port) => Ok(Backend {
+            child,
+            _job: job,
+            port,
+            shutdown_token,
+        }),
+        Err(e) => {
+            process::kill_tree(&mut child);
+            Err(sidecar::explain_startup_failure(&sidecar_path, e))
+        }
+    }
+}
+
+async fn json_response(response: reqwest::Response, action: &str) -> Result<Value, String> {
+    let status = response.status();
+    let body: Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to {}: invalid response: {}", action, e))?;
+    if !status.is_success() {
+        let detail = body.get("detail").map_or_else(|| body.to_string(), |d| d.to_string());
+        return Err(format!("Failed to {}: HTTP {}: {}", action, status.as_u16(), detail));
+    }
+    Ok(body)
+}
+
+/// Starts the analysis, polls until it finishes and returns its JSON export.
+async fn run_analysis(port: u16, deployment: &str, analyzers: Option<&[String]>, timeout: Duration) -> Result<Value, String> {
+    let base = format!("http://127.0.0.1:{}/api/v1/analysis", port);
+    let client = reqwest::Client::new();
+
+    let started = client
+        .post(&base)
+        .json(&json!({ "deployment_name": deployment, "analyzers": analyzers }))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to start analysis: {}", e))?;
+    let started = json_response(started, "start analysis").await?;
+    let analysis_id = started
+        .get("analysis_id")
+        .and_then(Value::as_str)
+        .ok_or("Failed to start analysis: no analysis_id in response")?
+        .to_string();
+    eprintln!("Analysis {} started for deployment {}", analysis_id, deployment);
+
+    let deadline = Instant::now() + timeout;
+    let mut last_step = None;
+    loop {
+        let response = client
+            .get(format!("{}/{}", base, analysis_id))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to check analysis status: {}", e))?;
+        let status = json_response(response, "check analysis status").await?;
+
+        let step = status.get("current_step").and_then(Value::as_str).map(str::to_string);
+        if step.is_some() && step != last_step {
+            eprintln!("  {}", step.as_deref().unwrap_or_default());
+            last_step = step;
+        }
+
+        match status.get("status").and_then(Value::as_str) {
+            Some("completed") => break,
+            Some("failed") => {
+                let response = client
+                    .get(format!("{}/{}/export/json", base, analysis_id))
+                    .send()
+                    .await
+                    .map_err(|e| format!("Analysis failed: {}", e))?;
+                let export = json_response(response, "export analysis").await.unwrap_or_default();
+                let error = export.get("error").and_then(Value::as_str).unwrap_or("unknown error");
+                return Err(format!("Analysis failed: {}", error));
+            }
+            _ => {}
+        }
+
+        if Instant::now() >= deadline {
+            return Err(format!("Analysis did not finish within {} s", timeout.as_secs()));
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+
+    let response = client
+        .get(format!("{}/{}/export/json", base, analysis_id))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to export analysis: {}", e))?;
+    json_response(response, "export analysis").await
+}
+
+fn run_headless(args: &[String]) -> Result<i32, String> {
+    let args = parse_args(args)?;
+    let config = match &args.config {
+        Some(path) => load_config(path)?,
+        None => RunConfig::default(),
+    };
+
+    let deployment = args
+        .deployment
+        .or(config.deployment)
+        .ok_or("No deployment given; set `deployment` in the config or pass --deployment")?;
+    let output = args.output.or(config.output);
+    let min_score = args.min_score.or(config.min_health_score);
+    let timeout = Duration::from_secs(config.timeout_secs.unwrap_or(DEFAULT_ANALYSIS_TIMEOUT_SECS));
+
+    let backend = start_backend(args.verbose)?;
+    let port = backend.port;
+    let report = tauri::async_runtime::block_on(run_analysis(
+        port,
+        &deployment,
+        config.analyzers.as_deref(),
+        timeout,
+    ));
+    drop(backend);
+    let report = report?;
+
+    if let Some(path) = &output {
+        let json = serde_json::to_vec_pretty(&report).map_err(|e| format!("Failed to serialize report: {}", e))?;
+        crate::files::write_file_atomic(path, &json)?;
+        eprintln!("Wrote report to {}", path.display());
+    }
+
+    let score = report.get("health_score").and_then(Value::as_f64);
+    let findings = report.get("findings").and_then(Value::as_array).map_or(0, Vec::len);
+    let passed = match (min_score, score) {
+        (None, _) => true,
+        (Some(min), Some(score)) => score >= min,
+        (Some(_), None) => false,
+    };
+
+    let score_text = score.map_or_else(|| "none".to_string(), |s| format!("{:.1}", s));
+    match min_score {
+        Some(min) => println!(
+            "Health score {} (minimum {:.1}), {} findings: {}",
+            score_text,
+            min,
+            findings,
+            if passed { "PASSED" } else { "FAILED" }
+        ),
+        None => println!("Health score {}, {} findings", score_text, findings),
+    }
+
+    Ok(if passed { EXIT_PASSED } else { EXIT_BELOW_THRESHOLD })
+}
+
+/// Release builds on Windows have no console of their own; borrow the one we were
+/// started from so CI logs show our output.
+#[cfg(windows)]
+fn attach_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_console() {}
+
+/// Runs headless mode if `--headless` is among `args` and returns the exit code;
+/// `None` means start the app as usual.
+pub fn run(args: &[String]) -> Option<i32> {
+    if !args.iter().any(|arg| arg == "--headless") {
+        return None;
+    }
+    attach_console();
+
+    Some(run_headless(args).unwrap_or_else(|e| {
+        eprintln!("cribl-hc: {}", e);
+        EXIT_ERROR
+    }))
+}
@@ -0,0 +1,273 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+use tokio_util::sync::CancellationToken;
+
+use crate::{settings, supervisor};
+
+/// An hour of samples at a 5s interval.
+const HISTORY_LIMIT: usize = 720;
+const MIN_INTERVAL: Duration = Duration::from_millis(250);
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Skew beyond this makes finding timestamps misleading next to Cribl's own.
+const CLOCK_SKEW_WARN_MS: i64 = 5_000;
+
+#[derive(Clone, Serialize)]
+pub struct HealthSample {
+    /// Milliseconds since the Unix epoch.
+    timestamp_ms: u64,
+    pub reachable: bool,
+    latency_ms: Option<f64>,
+    status: Option<u16>,
+}
+
+pub struct HealthMonitor {
+    client: reqwest::Client,
+    history: Mutex<VecDeque<HealthSample>>,
+    running: Mutex<Option<CancellationToken>>,
+}
+
+impl Default for HealthMonitor {
+    fn default() -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(CHECK_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            history: Mutex::new(VecDeque::new()),
+            running: Mutex::new(None),
+        }
+    }
+}
+
+impl HealthMonitor {
+    fn push(&self, sample: HealthSample) {
+        let mut history = self.history.lock().unwrap();
+        if history.len() >= HISTORY_LIMIT {
+            history.pop_front();
+        }
+        history.push_back(sample);
+    }
+}
+
+pub async fn check(app_handle: &tauri::AppHandle) -> HealthSample {
+    let timestamp_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64);
+
+    let Ok(base_url) = crate::get_backend_url(app_handle.clone()) else {
+        return HealthSample {
+            timestamp_ms,
+            reachable: false,
+            latency_ms: None,
+            status: None,
+        };
+    };
+
+    let monitor = app_handle.state::<HealthMonitor>();
+    let started = Instant::now();
+    let result = monitor.client.get(format!("{}/health", base_url)).send().await;
+    let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+    match result {
+        Ok(response) => HealthSample {
+            timestamp_ms,
+            reachable: response.status().is_success(),
+            latency_ms: Some(latency_ms),
+            status: Some(response.status().as_u16()),
+        },
+        Err(e) => {
+            log::debug!("Health check failed: {}", e);
+            HealthSample {
+                timestamp_ms,
+                reachable: false,
+                latency_ms: None,
+                status: None,
+            }
+        }
+    }
+}
+
+/// Starts polling `/health`, replacing any monitor that is already running.
+/// When the watchdog is enabled, it also restarts a backend that stops answering.
+#[tauri::command]
+pub fn start_health_monitor(app_handle: tauri::AppHandle, interval_ms: u64) {
+    let interval = Duration::from_millis(interval_ms).max(MIN_INTERVAL);
+    // A child of the app-wide shutdown token, so teardown stops the monitor too.
+    let token = app_handle.state::<crate::PythonBackend>().shutdown.child_token();
+
+    let monitor = app_handle.state::<HealthMonitor>();
+    if let Some(previous) = monitor.running.lock().unwrap().replace(token.clone()) {
+        previous.cancel();
+    }
+
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let mut consecutive_failures = 0;
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = token.cancelled() => return,
+            }
+
+            // A frozen backend can't answer; don't record that as an outage or trip the watchdog.
+            if app_handle.state::<crate::PythonBackend>().suspended.load(Ordering::SeqCst) {
+                continue;
+            }
+
+            let sample = check(&app_handle).await;
+            consecutive_failures = if sample.reachable { 0 } else { consecutive_failures + 1 };
+            app_handle.state::<HealthMonitor>().push(sample);
+
+            let watchdog = settings::backend(&app_handle).watchdog;
+            if watchdog.enabled && consecutive_failures >= watchdog.failure_threshold {
+                let handle = app_handle.clone();
+                let failures = consecutive_failures;
+                // start_backend blocks until the new backend hands back its port
+                let result = tauri::async_runtime::spawn_blocking(move || {
+                    supervisor::restart_unresponsive(&handle, failures)
+                })
+                .await;
+
+                match result {
+                    Ok(Err(e)) => log::error!("Watchdog restart failed: {}", e),
+                    Err(e) => log::error!("Watchdog restart panicked: {}", e),
+                    Ok(Ok(_)) => {}
+                }
+                consecutive_failures = 0;
+            }
+        }
+    });
+}
+
+/// Returns whether a monitor was running.
+#[tauri::command]
+pub fn stop_health_monitor(app_handle: tauri::AppHandle) -> bool {
+    let monitor = app_handle.state::<HealthMonitor>();
+    let token = monitor.running.lock().unwrap().take();
+
+    match token {
+        Some(token) => {
+            token.cancel();
+            true
+        }
+        None => false,
+    }
+}
+
+/// Samples oldest first.
+#[tauri::command]
+pub fn get_health_history(app_handle: tauri::AppHandle) -> Vec<HealthSample> {
+    let monitor = app_handle.state::<HealthMonitor>();
+    let history = monitor.history.lock().unwrap();
+    history.iter().cloned().collect()
+}
+
+/// Formats epoch milliseconds as an ISO-8601 UTC timestamp, e.g. `2024-05-01T12:00:00.250Z`.
+fn iso8601(timestamp_ms: u64) -> String {
+    let secs = timestamp_ms / 1000;
+    let (days, time) = ((secs / 86_400) as i64, secs % 86_400);
+
+    // Civil-from-days (Howard Hinnant), valid for any date after the epoch
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60,
+        timestamp_ms % 1000
+    )
+}
+
+#[tauri::command]
+pub async fn export_health_history_csv(app_handle: tauri::AppHandle) -> Result<String, String> {
+    let rows: Vec<Vec<String>> = get_health_history(app_handle.clone())
+        .iter()
+        .map(|sample| {
+            vec![
+                iso8601(sample.timestamp_ms),
+                sample.reachable.to_string(),
+                sample.latency_ms.map(|ms| format!("{:.3}", ms)).unwrap_or_default(),
+            ]
+        })
+        .collect();
+    let headers = ["timestamp", "reachable", "latency_ms"].map(String::from);
+
+    let path = crate::dialogs::save_path(&app_handle, "cribl-hc-health-history.csv").await?;
+    crate::export::write_csv(&path, &headers, &rows)?;
+
+    Ok(path.to_string_lossy().to_string())
+}
+
+#[derive(Serialize)]
+pub struct ClockSkew {
+    /// Backend clock minus local clock; positive means the backend is ahead.
+    skew_ms: i64,
+    round_trip_ms: f64,
+    threshold_ms: i64,
+    exceeds_threshold: bool,
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as i64)
+}
+
+/// Compares the backend's `/health` timestamp with the local clock, taking the
+/// middle of the round trip as the moment the backend answered.
+#[tauri::command]
+pub async fn check_clock_skew(app_handle: tauri::AppHandle) -> Result<ClockSkew, String> {
+    let base_url = crate::get_backend_url(app_handle.clone())?;
+    let monitor = app_handle.state::<HealthMonitor>();
+
+    let sent_ms = now_ms();
+    let started = Instant::now();
+    let body: serde_json::Value = monitor
+        .client
+        .get(format!("{}/health", base_url))
+        .send()
+        .await
+        .map_err(|e| format!("Backend request failed: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid health response: {}", e))?;
+    let round_trip = started.elapsed();
+
+    let backend_ms = body
+        .get("timestamp_ms")
+        .and_then(|v| v.as_i64())
+        .ok_or("Backend does not report its clock; update the backend")?;
+
+    let local_ms = sent_ms + round_trip.as_millis() as i64 / 2;
+    let skew_ms = backend_ms - local_ms;
+    let exceeds_threshold = skew_ms.abs() > CLOCK_SKEW_WARN_MS;
+    if exceeds_threshold {
+        log::warn!("Backend clock is off by {} ms", skew_ms);
+    }
+
+    Ok(ClockSkew {
+        skew_ms,
+        round_trip_ms: round_trip.as_secs_f64() * 1000.0,
+        threshold_ms: CLOCK_SKEW_WARN_MS,
+        exceeds_threshold,
+    })
+}
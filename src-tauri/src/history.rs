@@ -0,0 +1,500 @@
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+const HISTORY_FILE: &str = "analysis-history.jsonl";
+/// The previous generation, replaced each time the current file fills up.
+const ROTATED_HISTORY_FILE: &str = "analysis-history.1.jsonl";
+const MAX_HISTORY_BYTES: u64 = 1024 * 1024;
+const DEFAULT_HISTORY_LIMIT: usize = 50;
+
+/// Full results of saved runs, one file each, with a summary index beside them.
+const RUNS_DIR: &str = "analysis-runs";
+const RUNS_INDEX_FILE: &str = "index.json";
+/// The oldest saved runs are deleted past this many.
+const MAX_SAVED_RUNS: usize = 200;
+
+/// Serializes appends, rotation and index updates between concurrent commands.
+#[derive(Default)]
+pub struct HistoryState {
+    lock: Mutex<()>,
+}
+
+/// What the frontend knows about an analysis once it completes.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AnalysisRunMetadata {
+    pub config_fingerprint: Option<String>,
+    pub worker_group: Option<String>,
+    pub finding_count: u64,
+    pub report_path: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AnalysisRun {
+    /// Milliseconds since the Unix epoch.
+    pub timestamp_ms: u64,
+    #[serde(flatten)]
+    pub metadata: AnalysisRunMetadata,
+}
+
+fn history_paths(app_handle: &tauri::AppHandle) -> Result<(PathBuf, PathBuf), String> {
+    let dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get data dir: {}", e))?;
+
+    Ok((dir.join(HISTORY_FILE), dir.join(ROTATED_HISTORY_FILE)))
+}
+
+/// One history line as JSON; with encryption on, lines are sealed and base64-encoded.
+fn open_line(app_handle: &tauri::AppHandle, line: &str) -> Option<Vec<u8>> {
+    if line.starts_with('{') {
+        return Some(line.as_bytes().to_vec());
+    }
+    let sealed = base64::engine::general_purpose::STANDARD.decode(line).ok()?;
+    crate::encryption::open_stored(app_handle, &sealed)
+        .map_err(|e| log::warn!("Skipping unreadable history entry: {}", e))
+        .ok()
+}
+
+fn seal_line(app_handle: &tauri::AppHandle, json: &[u8]) -> Result<String, String> {
+    if !crate::encryption::enabled(app_handle) {
+        return Ok(String::from_utf8_lossy(json).into_owned());
+    }
+    let sealed = crate::encryption::seal_for_storage(app_handle, json)?;
+    Ok(base64::engine::general_purpose::STANDARD.encode(sealed))
+}
+
+/// Reads one history file, oldest entry first. Lines that don't parse are skipped
+/// so a torn write only loses that entry.
+fn read_runs(app_handle: &tauri::AppHandle, path: &PathBuf) -> Result<Vec<AnalysisRun>, String> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+
+    Ok(text
+        .lines()
+        .filter_map(|line| serde_json::from_slice(&open_line(app_handle, line)?).ok())
+        .collect())
+}
+
+#[tauri::command]
+pub fn record_analysis_run(app_handle: tauri::AppHandle, metadata: AnalysisRunMetadata) -> Result<AnalysisRun, String> {
+    let (path, rotated) = history_paths(&app_handle)?;
+    let run = AnalysisRun {
+        timestamp_ms: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64),
+        metadata,
+    };
+    let json = serde_json::to_vec(&run).map_err(|e| format!("Failed to serialize history entry: {}", e))?;
+    let mut line = seal_line(&app_handle, &json)?;
+    line.push('\n');
+
+    let state = app_handle.state::<HistoryState>();
+    let _guard = state.lock.lock().unwrap();
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create data dir: {}", e))?;
+    }
+
+    let size = fs::metadata(&path).map_or(0, |m| m.len());
+    if size > 0 && size + line.len() as u64 > MAX_HISTORY_BYTES {
+        fs::rename(&path, &rotated).map_err(|e| format!("Failed to rotate analysis history: {}", e))?;
+    }
+
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|e| format!("Failed to write analysis history: {}", e))?;
+
+    Ok(run)
+}
+
+/// Returns the most recent runs, newest first.
+#[tauri::command]
+pub fn get_analysis_history(app_handle: tauri::AppHandle, limit: Option<usize>) -> Result<Vec<AnalysisRun>, String> {
+    let (path, rotated) = history_paths(&app_handle)?;
+
+    let state = app_handle.state::<HistoryState>();
+    let _guard = state.lock.lock().unwrap();
+
+    let mut runs = read_runs(&app_handle, &rotated)?;
+    runs.extend(read_runs(&app_handle, &path)?);
+    runs.reverse();
+    runs.truncate(limit.unwrap_or(DEFAULT_HISTORY_LIMIT));
+    Ok(runs)
+}
+
+#[tauri::command]
+pub fn clear_analysis_history(app_handle: tauri::AppHandle) -> Result<(), String> {
+    let (path, rotated) = history_paths(&app_handle)?;
+
+    let state = app_handle.state::<HistoryState>();
+    let _guard = state.lock.lock().unwrap();
+
+    for file in [path, rotated] {
+        match fs::remove_file(&file) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("Failed to clear analysis history: {}", e)),
+        }
+    }
+    Ok(())
+}
+
+/// What's shown in the list of saved runs, taken from the backend's result export.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RunSummary {
+    pub analysis_id: String,
+    pub deployment_name: Option<String>,
+    pub health_score: Option<f64>,
+    pub findings_count: usize,
+    pub completed_at: Option<String>,
+    /// Milliseconds since the Unix epoch.
+    pub saved_at_ms: u64,
+}
+
+fn runs_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    app_handle
+        .path()
+        .app_data_dir()
+        .map(|dir| dir.join(RUNS_DIR))
+        .map_err(|e| format!("Failed to get data dir: {}", e))
+}
+
+/// Analysis ids become file names, so only accept the characters the backend uses.
+fn run_path(dir: &Path, analysis_id: &str) -> Result<PathBuf, String> {
+    let valid = !analysis_id.is_empty()
+        && analysis_id.len() <= 128
+        && analysis_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(format!("Invalid analysis id: {:?}", analysis_id));
+    }
+    Ok(dir.join(format!("{}.json", analysis_id)))
+}
+
+/// Saved runs, oldest first. A missing or unreadable index means none.
+fn read_index(app_handle: &tauri::AppHandle, dir: &Path) -> Vec<RunSummary> {
+    fs::read(dir.join(RUNS_INDEX_FILE))
+        .ok()
+        .and_then(|bytes| {
+            crate::encryption::open_stored(app_handle, &bytes)
+                .map_err(|e| log::warn!("Failed to read the saved run index: {}", e))
+                .ok()
+        })
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+fn write_index(app_handle: &tauri::AppHandle, dir: &Path, index: &[RunSummary]) -> Result<(), String> {
+    let json = serde_json::to_vec_pretty(index).map_err(|e| format!("Failed to serialize run index: {}", e))?;
+    let sealed = crate::encryption::seal_for_storage(app_handle, &json)?;
+    crate::files::write_file_atomic(&dir.join(RUNS_INDEX_FILE), &sealed)
+}
+
+fn summarize(result: &Value) -> Result<RunSummary, String> {
+    let analysis_id = result
+        .get("analysis_id")
+        .and_then(Value::as_str)
+        .ok_or("Analysis result has no analysis_id")?
+        .to_string();
+    let findings_count = result
+        .get("findings")
+        .and_then(Value::as_array)
+        .map(Vec::len)
+        .or_else(|| result.get("findings_count").and_then(Value::as_u64).map(|n| n as usize))
+        .unwrap_or(0);
+
+    Ok(RunSummary {
+        analysis_id,
+        deployment_name: result.get("deployment_name").and_then(Value::as_str).map(str::to_string),
+        health_score: result.get("health_score").and_then(Value::as_f64),
+        findings_count,
+        completed_at: result.get("completed_at").and_then(Value::as_str).map(str::to_string),
+        saved_at_ms: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64),
+    })
+}
+
+/// Keeps a completed analysis (the backend's JSON export) so it can be reopened
+/// later. Saving the same analysis again replaces it.
+#[tauri::command]
+pub fn save_analysis_result(app_handle: tauri::AppHandle, result: Value) -> Result<RunSummary, String> {
+    let summary = summarize(&result)?;
+    let dir = runs_dir(&app_handle)?;
+    let path = run_path(&dir, &summary.analysis_id)?;
+
+    let state = app_handle.state::<HistoryState>();
+    let guard = state.lock.lock().unwrap();
+
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create run history dir: {}", e))?;
+    let json = serde_json::to_vec(&result).map_err(|e| format!("Failed to serialize analysis result: {}", e))?;
+    crate::files::write_file_atomic(&path, &crate::encryption::seal_for_storage(&app_handle, &json)?)?;
+
+    let mut index = read_index(&app_handle, &dir);
+    index.retain(|run| run.analysis_id != summary.analysis_id);
+    index.push(summary.clone());
+    // Oldest first, skipping baselines, which later runs are still compared with
+    let baselines = crate::baseline::run_ids(&app_handle);
+    let mut excess = index.len().saturating_sub(MAX_SAVED_RUNS);
+    index.retain(|run| {
+        if excess == 0 || baselines.contains(&run.analysis_id) {
+            return true;
+        }
+        excess -= 1;
+        if let Ok(old_path) = run_path(&dir, &run.analysis_id) {
+            let _ = fs::remove_file(old_path);
+        }
+        false
+    });
+    write_index(&app_handle, &dir, &index)?;
+    drop(guard);
+    crate::search::invalidate(&app_handle);
+    crate::baseline::check(&app_handle, &result);
+
+    // The tray updates on the main thread, so don't make it wait on the history lock
+    crate::tray::show_last_score(&app_handle, summary.health_score);
+    Ok(summary)
+}
+
+/// Saved runs, newest first.
+#[tauri::command]
+pub fn list_analysis_runs(app_handle: tauri::AppHandle) -> Result<Vec<RunSummary>, String> {
+    let dir = runs_dir(&app_handle)?;
+
+    let state = app_handle.state::<HistoryState>();
+    let _guard = state.lock.lock().unwrap();
+
+    let mut index = read_index(&app_handle, &dir);
+    index.reverse();
+    Ok(index)
+}
+
+#[tauri::command]
+pub fn get_analysis_run(app_handle: tauri::AppHandle, analysis_id: String) -> Result<Value, String> {
+    let dir = runs_dir(&app_handle)?;
+    let path = run_path(&dir, &analysis_id)?;
+
+    let bytes = match fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(format!("No saved analysis {}", analysis_id));
+        }
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    let bytes = crate::encryption::open_stored(&app_handle, &bytes)
+        .map_err(|e| format!("Failed to read saved analysis {}: {}", analysis_id, e))?;
+    serde_json::from_slice(&bytes).map_err(|e| format!("Saved analysis {} is corrupt: {}", analysis_id, e))
+}
+
+/// Rewrites every saved run, the index and the history log as encryption is now
+/// set: sealing what is plain, or the reverse. Returns how many files changed.
+pub fn reseal_all(app_handle: &tauri::AppHandle) -> Result<usize, String> {
+    let state = app_handle.state::<HistoryState>();
+    let _guard = state.lock.lock().unwrap();
+    let mut rewritten = 0;
+
+    let dir = runs_dir(app_handle)?;
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries.filter_map(Result::ok).map(|e| e.path()).collect(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(format!("Failed to read run history dir: {}", e)),
+    };
+    for path in entries {
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let bytes = fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let plain = crate::encryption::open_stored(app_handle, &bytes)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let resealed = crate::encryption::seal_for_storage(app_handle, &plain)?;
+        if resealed != bytes {
+            crate::files::write_file_atomic(&path, &resealed)?;
+            rewritten += 1;
+        }
+    }
+
+    let (path, rotated) = history_paths(app_handle)?;
+    for file in [path, rotated] {
+        let text = match fs::read_to_string(&file) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(format!("Failed to read {}: {}", file.display(), e)),
+        };
+        let mut lines = String::new();
+        for line in text.lines() {
+            // Entries that can't be read are dropped rather than kept in the wrong form
+            if let Some(json) = open_line(app_handle, line) {
+                lines.push_str(&seal_line(app_handle, &json)?);
+                lines.push('\n');
+            }
+        }
+        if lines != text {
+            crate::files::write_file_atomic(&file, lines.as_bytes())?;
+            rewritten += 1;
+        }
+    }
+    Ok(rewritten)
+}
+
+/// Returns whether the run was saved.
+#[tauri::command]
+pub fn delete_analysis_run(app_handle: tauri::AppHandle, analysis_id: String) -> Result<bool, String> {
+    let dir = runs_dir(&app_handle)?;
+    let path = run_path(&dir, &analysis_id)?;
+
+    let state = app_handle.state::<HistoryState>();
+    let _guard = state.lock.lock().unwrap();
+
+    let mut index = read_index(&app_handle, &dir);
+    let before = index.len();
+    index.retain(|run| run.analysis_id != analysis_id);
+    let listed = index.len() != before;
+    if listed {
+        write_index(&app_handle, &dir, &index)?;
+        crate::search::invalidate(&app_handle);
+    }
+
+    match fs::remove_file(&path) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(listed),
+        Err(e) => Err(format!("Failed to delete saved analysis: {}", e)),
+    }
+}
+
+/// Findings without a worker group (leader and deployment-wide checks) land here.
+pub const UNGROUPED: &str = "(deployment)";
+
+/// Findings present in only one of two runs, for one worker group.
+#[derive(Serialize)]
+pub struct WorkerGroupDiff {
+    pub worker_group: String,
+    /// In `run_b` but not in `run_a`.
+    pub added: Vec<Value>,
+    /// In `run_a` but no longer in `run_b`.
+    pub resolved: Vec<Value>,
+    pub unchanged_count: usize,
+    /// Each run's findings for this group scored the way the backend scores a
+    /// deployment when it has no health score of its own.
+    pub health_score_a: f64,
+    pub health_score_b: f64,
+    pub health_score_delta: f64,
+}
+
+#[derive(Serialize)]
+pub struct AnalysisComparison {
+    pub run_a: RunSummary,
+    pub run_b: RunSummary,
+    /// `run_b`'s overall health score minus `run_a`'s, when both have one.
+    pub health_score_delta: Option<f64>,
+    /// Sorted by worker group name.
+    pub groups: Vec<WorkerGroupDiff>,
+}
+
+pub fn worker_group(finding: &Value) -> String {
+    let metadata = finding.get("metadata");
+    ["worker_group", "group"]
+        .iter()
+        .find_map(|key| metadata.and_then(|m| m.get(key)).and_then(Value::as_str))
+        .filter(|group| !group.is_empty())
+        .unwrap_or(UNGROUPED)
+        .to_string()
+}
+
+/// Finding ids are derived from what was checked, so the same problem keeps its
+/// id between runs. Findings without one fall back to their title and components.
+fn finding_key(finding: &Value) -> String {
+    if let Some(id) = finding.get("id").and_then(Value::as_str) {
+        return id.to_string();
+    }
+    format!(
+        "{}|{}",
+        finding.get("title").and_then(Value::as_str).unwrap_or_default(),
+        finding.get("affected_components").map(Value::to_string).unwrap_or_default()
+    )
+}
+
+/// Findings by worker group, then by key.
+pub fn group_findings(result: &Value) -> BTreeMap<String, BTreeMap<String, Value>> {
+    let mut groups: BTreeMap<String, BTreeMap<String, Value>> = BTreeMap::new();
+    for finding in result.get("findings").and_then(Value::as_array).into_iter().flatten() {
+        groups
+            .entry(worker_group(finding))
+            .or_default()
+            .insert(finding_key(finding), finding.clone());
+    }
+    groups
+}
+
+/// Diffs two saved runs per worker group: which findings appeared, which were
+/// resolved, and how each group's score moved from `run_a` to `run_b`.
+#[tauri::command]
+pub fn compare_analyses(app_handle: tauri::AppHandle, run_a: String, run_b: String) -> Result<AnalysisComparison, String> {
+    let index = read_index(&app_handle, &runs_dir(&app_handle)?);
+    let load = |analysis_id: String| -> Result<(RunSummary, Value), String> {
+        let result = get_analysis_run(app_handle.clone(), analysis_id.clone())?;
+        let summary = match index.iter().find(|run| run.analysis_id == analysis_id) {
+            Some(listed) => listed.clone(),
+            None => summarize(&result)?,
+        };
+        Ok((summary, result))
+    };
+    let (summary_a, result_a) = load(run_a)?;
+    let (summary_b, result_b) = load(run_b)?;
+
+    let mut groups_a = group_findings(&result_a);
+    let mut groups_b = group_findings(&result_b);
+    let names: BTreeSet<String> = groups_a.keys().chain(groups_b.keys()).cloned().collect();
+
+    let groups = names
+        .into_iter()
+        .map(|name| {
+            let before = groups_a.remove(&name).unwrap_or_default();
+            let after = groups_b.remove(&name).unwrap_or_default();
+            let health_score_a = crate::scoring::score_findings(before.values());
+            let health_score_b = crate::scoring::score_findings(after.values());
+
+            let added: Vec<Value> = after
+                .iter()
+                .filter(|(key, _)| !before.contains_key(*key))
+                .map(|(_, finding)| finding.clone())
+                .collect();
+            let unchanged_count = after.len() - added.len();
+            let resolved: Vec<Value> = before
+                .into_iter()
+                .filter(|(key, _)| !after.contains_key(key))
+                .map(|(_, finding)| finding)
+                .collect();
+
+            WorkerGroupDiff {
+                worker_group: name,
+                added,
+                resolved,
+                unchanged_count,
+                health_score_a,
+                health_score_b,
+                health_score_delta: health_score_b - health_score_a,
+            }
+        })
+        .collect();
+
+    Ok(AnalysisComparison {
+        health_score_delta: summary_a.health_score.zip(summary_b.health_score).map(|(a, b)| b - a),
+        run_a: summary_a,
+        run_b: summary_b,
+        groups,
+    })
+}
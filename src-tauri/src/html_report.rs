@@ -0,0 +1,245 @@
+//! Single-file HTML export of a saved run, for attaching to an email or ticket:
+//! it opens in any browser, with nothing to install and nothing fetched.
+//!
+//! The page is `templates/report.html`, which holds the layout and all the CSS.
+//! Its `{{name}}` slots are filled here with escaped HTML: the health score as
+//! an SVG gauge, worker group scores (see `scoring`), and findings as cards of
+//! like findings (see `grouping`), most severe first. Each card's anchor is
+//! `finding-<id>`, for `reports::open_report_at_anchor`.
+
+use serde_json::Value;
+use std::path::Path;
+
+use crate::grouping::{FindingGroup, GroupingStrategy};
+
+const TEMPLATE: &str = include_str!("../templates/report.html");
+const SEVERITIES: &[&str] = &["critical", "high", "medium", "low", "info"];
+/// Affected objects shown per card; the rest are counted.
+const SHOWN_COMPONENTS: usize = 20;
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// `text` usable in an `id`: letters, digits, `-` and `_`.
+fn anchor(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
+        .collect()
+}
+
+/// Fills each `{{name}}` in `template` in one pass, so braces in the values are left alone.
+fn fill(template: &str, values: &[(&str, String)]) -> Result<String, String> {
+    let mut out = String::with_capacity(template.len() + values.iter().map(|(_, v)| v.len()).sum::<usize>());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after.find("}}").ok_or("Report template has an unclosed {{")?;
+        let name = after[..end].trim();
+        let (_, value) = values
+            .iter()
+            .find(|(key, _)| *key == name)
+            .ok_or_else(|| format!("Report template has no value for {{{{{}}}}}", name))?;
+        out.push_str(value);
+        rest = &after[end + 2..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+fn score_colour(score: f64) -> &'static str {
+    if score >= 80.0 {
+        "#16a34a"
+    } else if score >= 60.0 {
+        "#ea580c"
+    } else {
+        "#dc2626"
+    }
+}
+
+/// A half-circle gauge filled clockwise from the left in proportion to `score`.
+fn gauge(score: f64) -> String {
+    let (cx, cy, r) = (110.0, 110.0, 90.0);
+    let angle = std::f64::consts::PI * (1.0 - score.clamp(0.0, 100.0) / 100.0);
+    let (x, y) = (cx + r * angle.cos(), cy - r * angle.sin());
+    let filled = if score > 0.0 {
+        format!(
+            r#"<path d="M {:.1} {cy} A {r} {r} 0 0 1 {x:.2} {y:.2}" fill="none" stroke="{}" stroke-width="22" stroke-linecap="round"/>"#,
+            cx - r,
+            score_colour(score)
+        )
+    } else {
+        String::new()
+    };
+    format!(
+        r##"<svg class="gauge" width="220" height="130" viewBox="0 0 220 130" role="img" aria-label="Health score {score:.0} out of 100"><path d="M {:.1} {cy} A {r} {r} 0 0 1 {:.1} {cy}" fill="none" stroke="#e5e5e5" stroke-width="22" stroke-linecap="round"/>{filled}<text x="{cx}" y="{:.0}" text-anchor="middle" font-size="34" fill="#1a1a1a">{score:.0}</text><text x="{cx}" y="{:.0}" text-anchor="middle" font-size="12" fill="#666">out of 100</text></svg>"##,
+        cx - r,
+        cx + r,
+        cy - 10.0,
+        cy + 12.0
+    )
+}
+
+fn counts(findings: &[Value]) -> String {
+    SEVERITIES
+        .iter()
+        .map(|severity| {
+            let count = findings
+                .iter()
+                .filter(|f| f.get("severity").and_then(Value::as_str).is_some_and(|s| s.eq_ignore_ascii_case(severity)))
+                .count();
+            format!(r#"<div class="count {severity}"><strong>{count}</strong>{severity}</div>"#)
+        })
+        .collect()
+}
+
+fn group_scores(score: &crate::scoring::HealthScore) -> String {
+    if score.groups.is_empty() {
+        return String::new();
+    }
+    let rows: String = score
+        .groups
+        .iter()
+        .map(|group| {
+            format!(
+                r#"<tr id="group-{}"><td>{}</td><td class="score" style="color: {}">{:.1}</td><td>{}</td></tr>"#,
+                anchor(&group.worker_group),
+                escape(&group.worker_group),
+                score_colour(group.score),
+                group.score,
+                group.findings_count
+            )
+        })
+        .collect();
+    format!(
+        "<h2>Worker groups</h2><table><thead><tr><th>Worker group / fleet</th><th>Score</th><th>Findings</th></tr></thead><tbody>{}</tbody></table>",
+        rows
+    )
+}
+
+fn list(finding: &Value, key: &str) -> Vec<String> {
+    finding
+        .get(key)
+        .and_then(Value::as_array)
+        .map(|items| items.iter().filter_map(Value::as_str).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+fn card(group: &FindingGroup) -> String {
+    let sample = &group.sample;
+    let text = |key: &str| sample.get(key).and_then(Value::as_str).unwrap_or_default();
+    let severity = if SEVERITIES.contains(&group.severity.as_str()) { group.severity.as_str() } else { "info" };
+    let id = group.finding_ids.first().map_or_else(|| anchor(&group.key), |id| anchor(id));
+
+    let mut html = format!(
+        r#"<article class="card {severity}" id="finding-{id}"><header><span class="badge">{severity}</span><h3>{}</h3><span class="occurrences">{}</span></header>"#,
+        escape(&group.title),
+        escape(&format!(
+            "{} in {}",
+            match group.occurrences {
+                1 => "1 occurrence".to_string(),
+                n => format!("{} occurrences", n),
+            },
+            group.worker_groups.join(", ")
+        ))
+    );
+    let description = text("description");
+    if !description.is_empty() {
+        html.push_str(&format!("<p>{}</p>", escape(description)));
+    }
+    if !group.affected_components.is_empty() {
+        let shown: Vec<String> = group.affected_components.iter().take(SHOWN_COMPONENTS).map(|c| escape(c)).collect();
+        let more = group.affected_count.saturating_sub(shown.len());
+        html.push_str(&format!(
+            r#"<div class="label">Affected</div><div class="components">{}{}</div>"#,
+            shown.join(", "),
+            if more > 0 { format!(" and {} more", more) } else { String::new() }
+        ));
+    }
+    let impact = text("estimated_impact");
+    if !impact.is_empty() {
+        html.push_str(&format!(r#"<div class="label">Impact</div><p>{}</p>"#, escape(impact)));
+    }
+    let steps = list(sample, "remediation_steps");
+    if !steps.is_empty() {
+        let items: String = steps.iter().map(|s| format!("<li>{}</li>", escape(s))).collect();
+        html.push_str(&format!(r#"<div class="label">Remediation</div><ol>{}</ol>"#, items));
+    }
+    // Only web links; anything else in a shared file could be a `javascript:` URL
+    let links: Vec<String> = list(sample, "documentation_links")
+        .into_iter()
+        .filter(|l| l.starts_with("https://") || l.starts_with("http://"))
+        .map(|l| format!(r#"<a href="{0}" rel="noopener noreferrer">{0}</a>"#, escape(&l)))
+        .collect();
+    if !links.is_empty() {
+        html.push_str(&format!(r#"<div class="label">Documentation</div><p>{}</p>"#, links.join("<br>")));
+    }
+    html.push_str("</article>");
+    html
+}
+
+/// Renders an analysis result (the backend's JSON export) as a self-contained HTML page.
+pub fn render(result: &Value) -> Result<String, String> {
+    let text = |key: &str| result.get(key).and_then(Value::as_str).filter(|s| !s.is_empty());
+    let deployment = text("deployment_name")
+        .or_else(|| text("deployment_id"))
+        .unwrap_or("Cribl deployment");
+    let findings: &[Value] = result.get("findings").and_then(Value::as_array).map_or(&[], Vec::as_slice);
+
+    let mut meta = Vec::new();
+    if let Some(completed) = text("completed_at").or_else(|| text("started_at")) {
+        meta.push(format!("Completed {}", completed));
+    }
+    if let Some(analysis_id) = text("analysis_id") {
+        meta.push(format!("Analysis {}", analysis_id));
+    }
+    meta.push(format!("{} findings", findings.len()));
+
+    let score = crate::scoring::compute(result);
+    let grouped = crate::grouping::group(result, GroupingStrategy::Check);
+    let cards = if grouped.groups.is_empty() {
+        "<h2>Findings</h2><p>No findings. Everything checked looks healthy.</p>".to_string()
+    } else {
+        format!(
+            "<h2>Findings</h2>{}",
+            grouped.groups.iter().map(card).collect::<String>()
+        )
+    };
+
+    fill(
+        TEMPLATE,
+        &[
+            ("title", escape(&format!("Cribl Health Check - {}", deployment))),
+            ("meta", escape(&meta.join(" · "))),
+            ("gauge", gauge(score.overall)),
+            ("counts", counts(findings)),
+            ("groups", group_scores(&score)),
+            ("findings", cards),
+            ("generated", escape(&chrono::Local::now().format("%Y-%m-%d %H:%M %Z").to_string())),
+        ],
+    )
+}
+
+/// Writes saved run `run_id` to `path` as a single-file HTML report.
+#[tauri::command]
+pub async fn export_html_report(app_handle: tauri::AppHandle, run_id: String, path: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = crate::history::get_analysis_run(app_handle, run_id)?;
+        let html = render(&result)?;
+        crate::files::write_file_atomic(Path::new(&path), html.as_bytes())
+    })
+    .await
+    .map_err(|e| format!("Failed to export HTML report: {}", e))?
+}
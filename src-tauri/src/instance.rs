@@ -0,0 +1,36 @@
+use serde::Serialize;
+use std::path::Path;
+use tauri::{Emitter, Manager};
+
+#[derive(Clone, Serialize)]
+struct SecondInstance {
+    args: Vec<String>,
+    cwd: String,
+}
+
+/// Brings the main window to the front, restoring it if minimized or hidden.
+pub fn focus_main_window(app_handle: &tauri::AppHandle) {
+    let Some(window) = app_handle.get_webview_window("main") else {
+        return;
+    };
+
+    let _ = window.unminimize();
+    let _ = window.show();
+    let _ = window.set_focus();
+}
+
+/// Called in the running instance when the app is launched again. Deep links
+/// are routed through the deep-link handler and saved analyses are opened; any
+/// other arguments are forwarded as an event.
+pub fn on_second_instance(app_handle: &tauri::AppHandle, argv: Vec<String>, cwd: String) {
+    focus_main_window(app_handle);
+
+    let args = crate::file_association::open_from_args(app_handle, argv.into_iter().skip(1).collect(), Path::new(&cwd));
+    if args.is_empty() {
+        return;
+    }
+
+    if let Err(e) = app_handle.emit("second-instance", SecondInstance { args, cwd }) {
+        log::warn!("Failed to emit second-instance: {}", e);
+    }
+}
@@ -0,0 +1,538 @@
+//! Outbound webhooks that post finished analyses to Slack, Teams, PagerDuty or
+//! anything else that takes JSON.
+//!
+//! Each webhook has a payload format (one of the built-in ones, or a template),
+//! a minimum severity, and optionally a secret. The secret lives in the OS
+//! credential store: it is sent in the webhook's secret header, or for
+//! PagerDuty used as the routing key. Scheduled runs are sent to every webhook
+//! that asks for them; any saved run can be sent with `send_analysis_to_webhooks`.
+//!
+//! Templates are JSON with `{{name}}` placeholders for `analysis_id`,
+//! `deployment`, `health_score`, `findings_count`, `max_severity` and `summary`.
+//! Values are JSON-escaped but not quoted, so placeholders go inside strings:
+//! `{"text": "{{deployment}} scored {{health_score}}"}`.
+//!
+//! Failed requests are retried with backoff. Each outcome is kept for
+//! `list_webhook_deliveries` and reported as a `webhook-delivery` event.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{Emitter, Manager};
+
+use crate::settings::{self, SettingsStore};
+
+const MAX_ATTEMPTS: u32 = 4;
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(2);
+/// A `Retry-After` longer than this is cut short.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_DELIVERIES: usize = 100;
+/// Findings listed by name in a summary; the rest are counted.
+const SUMMARY_FINDINGS: usize = 10;
+const PAGERDUTY_EVENTS_URL: &str = "https://events.pagerduty.com/v2/enqueue";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookKind {
+    /// The analysis summary and its matching findings as JSON.
+    #[default]
+    Generic,
+    Slack,
+    Teams,
+    /// An Events API v2 trigger; the secret is the routing key.
+    PagerDuty,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    #[default]
+    Info,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "info" => Some(Self::Info),
+            "low" => Some(Self::Low),
+            "medium" => Some(Self::Medium),
+            "high" => Some(Self::High),
+            "critical" => Some(Self::Critical),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+            Self::Critical => "critical",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Webhook {
+    pub name: String,
+    /// Not used by PagerDuty, which always posts to its Events API.
+    pub url: String,
+    pub kind: WebhookKind,
+    /// Replaces the payload `kind` would send.
+    pub template: Option<String>,
+    /// Only findings at least this severe are sent; nothing is sent without any.
+    pub min_severity: Severity,
+    /// Header the secret is sent in, e.g. `Authorization` or `X-Api-Key`.
+    pub secret_header: Option<String>,
+    /// Whether a secret is saved in the OS credential store; set by `save_webhook`.
+    pub has_secret: bool,
+    /// Send every scheduled run.
+    pub on_schedule: bool,
+    pub enabled: bool,
+}
+
+impl Default for Webhook {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            url: String::new(),
+            kind: WebhookKind::default(),
+            template: None,
+            min_severity: Severity::default(),
+            secret_header: None,
+            has_secret: false,
+            on_schedule: true,
+            enabled: true,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Delivered,
+    /// No findings were severe enough to send.
+    Skipped,
+    Failed,
+}
+
+#[derive(Clone, Serialize)]
+pub struct WebhookDelivery {
+    webhook: String,
+    analysis_id: String,
+    status: DeliveryStatus,
+    attempts: u32,
+    http_status: Option<u16>,
+    error: Option<String>,
+    /// Milliseconds since the Unix epoch.
+    finished_at_ms: u64,
+}
+
+#[derive(Default)]
+pub struct IntegrationState {
+    deliveries: Mutex<VecDeque<WebhookDelivery>>,
+}
+
+fn secret_name(webhook: &str) -> String {
+    format!("webhook-secret:{}", webhook)
+}
+
+fn webhooks(app_handle: &tauri::AppHandle) -> Vec<Webhook> {
+    app_handle.state::<SettingsStore>().settings.lock().unwrap().webhooks.clone()
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+fn severity_of(finding: &Value) -> Severity {
+    finding
+        .get("severity")
+        .and_then(Value::as_str)
+        .and_then(Severity::parse)
+        .unwrap_or_default()
+}
+
+/// Findings at or above `min`, most severe first.
+fn matching_findings(result: &Value, min: Severity) -> Vec<&Value> {
+    let mut findings: Vec<&Value> = result
+        .get("findings")
+        .and_then(Value::as_array)
+        .map(|findings| findings.iter().filter(|f| severity_of(f) >= min).collect())
+        .unwrap_or_default();
+    findings.sort_by(|a, b| severity_of(b).partial_cmp(&severity_of(a)).unwrap_or(std::cmp::Ordering::Equal));
+    findings
+}
+
+fn summary_text(deployment: &str, score: Option<f64>, findings: &[&Value]) -> String {
+    let mut text = match score {
+        Some(score) => format!("{} scored {:.0}/100 with {} findings", deployment, score, findings.len()),
+        None => format!("{} has {} findings", deployment, findings.len()),
+    };
+    for finding in findings.iter().take(SUMMARY_FINDINGS) {
+        let title = finding.get("title").and_then(Value::as_str).unwrap_or("Untitled finding");
+        text.push_str(&format!("\n• [{}] {}", severity_of(finding).as_str(), title));
+    }
+    if findings.len() > SUMMARY_FINDINGS {
+        text.push_str(&format!("\n…and {} more", findings.len() - SUMMARY_FINDINGS));
+    }
+    text
+}
+
+/// Placeholder values for a template, already JSON-escaped.
+fn placeholders(result: &Value, findings: &[&Value]) -> Vec<(&'static str, String)> {
+    let escape = |text: &str| {
+        let quoted = Value::String(text.to_string()).to_string();
+        quoted[1..quoted.len() - 1].to_string()
+    };
+    let deployment = result.get("deployment_name").and_then(Value::as_str).unwrap_or("Deployment");
+    let score = result.get("health_score").and_then(Value::as_f64);
+    vec![
+        ("analysis_id", escape(result.get("analysis_id").and_then(Value::as_str).unwrap_or_default())),
+        ("deployment", escape(deployment)),
+        ("health_score", score.map(|s| format!("{:.0}", s)).unwrap_or_default()),
+        ("findings_count", findings.len().to_string()),
+        ("max_severity", findings.first().map(|f| severity_of(f).as_str()).unwrap_or_default().to_string()),
+        ("summary", escape(&summary_text(deployment, score, findings))),
+    ]
+}
+
+fn render_template(template: &str, result: &Value, findings: &[&Value]) -> Result<Value, String> {
+    let mut body = template.to_string();
+    for (name, value) in placeholders(result, findings) {
+        body = body.replace(&format!("{{{{{}}}}}", name), &value);
+    }
+    serde_json::from_str(&body).map_err(|e| format!("Webhook template isn't valid JSON once filled in: {}", e))
+}
+
+fn payload(webhook: &Webhook, secret: Option<&str>, result: &Value, findings: &[&Value]) -> Result<Value, String> {
+    if let Some(template) = &webhook.template {
+        return render_template(template, result, findings);
+    }
+
+    let analysis_id = result.get("analysis_id").and_then(Value::as_str).unwrap_or_default();
+    let deployment = result.get("deployment_name").and_then(Value::as_str).unwrap_or("Deployment");
+    let score = result.get("health_score").and_then(Value::as_f64);
+    let summary = summary_text(deployment, score, findings);
+    Ok(match webhook.kind {
+        WebhookKind::Generic => json!({
+            "analysis_id": analysis_id,
+            "deployment": deployment,
+            "health_score": score,
+            "completed_at": result.get("completed_at"),
+            "findings": findings,
+        }),
+        WebhookKind::Slack => json!({ "text": summary }),
+        WebhookKind::Teams => json!({
+            "@type": "MessageCard",
+            "@context": "https://schema.org/extensions",
+            "summary": format!("Cribl health check: {}", deployment),
+            "title": format!("Cribl health check: {}", deployment),
+            "text": summary.replace('\n', "\n\n"),
+        }),
+        WebhookKind::PagerDuty => {
+            let severity = match findings.first().map(|f| severity_of(f)) {
+                Some(Severity::Critical) => "critical",
+                Some(Severity::High) => "error",
+                Some(Severity::Medium) => "warning",
+                _ => "info",
+            };
+            json!({
+                "routing_key": secret.ok_or("PagerDuty webhooks need a routing key as their secret")?,
+                "event_action": "trigger",
+                "dedup_key": format!("cribl-hc-{}", deployment),
+                "payload": {
+                    "summary": summary.lines().next().unwrap_or_default(),
+                    "source": deployment,
+                    "severity": severity,
+                    "custom_details": { "analysis_id": analysis_id, "findings": findings },
+                },
+            })
+        }
+    })
+}
+
+fn validate(webhook: &mut Webhook) -> Result<(), String> {
+    webhook.name = webhook.name.trim().to_string();
+    if webhook.name.is_empty() {
+        return Err("A webhook needs a name".to_string());
+    }
+    if webhook.kind == WebhookKind::PagerDuty {
+        webhook.url = PAGERDUTY_EVENTS_URL.to_string();
+    }
+    let url = url::Url::parse(webhook.url.trim()).map_err(|e| format!("Invalid webhook URL {:?}: {}", webhook.url, e))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("Unsupported URL scheme {}", url.scheme()));
+    }
+    webhook.url = url.to_string();
+
+    if let Some(header) = &webhook.secret_header {
+        let header = header.trim().to_string();
+        reqwest::header::HeaderName::from_bytes(header.as_bytes())
+            .map_err(|_| format!("Invalid header name {:?}", header))?;
+        webhook.secret_header = Some(header).filter(|h| !h.is_empty());
+    }
+    webhook.template = webhook.template.take().filter(|t| !t.trim().is_empty());
+    if let Some(template) = &webhook.template {
+        let sample = json!({ "analysis_id": "sample", "deployment_name": "sample", "health_score": 100.0, "findings": [] });
+        render_template(template, &sample, &[])?;
+    }
+    Ok(())
+}
+
+/// How long to wait before trying again, or `None` if the request shouldn't be retried.
+fn retry_delay(status: Option<reqwest::StatusCode>, retry_after: Option<Duration>, attempt: u32) -> Option<Duration> {
+    let retryable = match status {
+        None => true,
+        Some(status) => status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS,
+    };
+    if !retryable || attempt >= MAX_ATTEMPTS {
+        return None;
+    }
+    let backoff = FIRST_RETRY_DELAY * 2u32.pow(attempt - 1);
+    Some(retry_after.unwrap_or(backoff).min(MAX_RETRY_DELAY))
+}
+
+async fn post(client: &reqwest::Client, webhook: &Webhook, secret: Option<&str>, body: &Value) -> (u32, Option<u16>, Result<(), String>) {
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let mut request = client.post(&webhook.url).timeout(REQUEST_TIMEOUT).json(body);
+        if let (Some(header), Some(secret)) = (&webhook.secret_header, secret) {
+            request = request.header(header.as_str(), secret);
+        }
+
+        let (status, retry_after, error) = match request.send().await {
+            Ok(response) if response.status().is_success() => return (attempt, Some(response.status().as_u16()), Ok(())),
+            Ok(response) => {
+                let retry_after = response
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.trim().parse().ok())
+                    .map(Duration::from_secs);
+                let status = response.status();
+                let detail = response.text().await.unwrap_or_default();
+                let detail = detail.trim().chars().take(200).collect::<String>();
+                (Some(status), retry_after, format!("{} responded {} {}", webhook.name, status, detail).trim().to_string())
+            }
+            Err(e) => (None, None, format!("Failed to reach {}: {}", webhook.name, e)),
+        };
+
+        match retry_delay(status, retry_after, attempt) {
+            Some(delay) => {
+                log::info!("{}; retrying in {} s", error, delay.as_secs());
+                tokio::time::sleep(delay).await;
+            }
+            None => return (attempt, status.map(|s| s.as_u16()), Err(error)),
+        }
+    }
+}
+
+fn record(app_handle: &tauri::AppHandle, delivery: WebhookDelivery) {
+    {
+        let state = app_handle.state::<IntegrationState>();
+        let mut deliveries = state.deliveries.lock().unwrap();
+        deliveries.push_back(delivery.clone());
+        while deliveries.len() > MAX_DELIVERIES {
+            deliveries.pop_front();
+        }
+    }
+    if let Err(e) = app_handle.emit("webhook-delivery", delivery) {
+        log::warn!("Failed to emit webhook-delivery: {}", e);
+    }
+}
+
+async fn deliver(app_handle: &tauri::AppHandle, client: &reqwest::Client, webhook: &Webhook, result: &Value) -> WebhookDelivery {
+    let analysis_id = result.get("analysis_id").and_then(Value::as_str).unwrap_or_default().to_string();
+    let mut delivery = WebhookDelivery {
+        webhook: webhook.name.clone(),
+        analysis_id,
+        status: DeliveryStatus::Failed,
+        attempts: 0,
+        http_status: None,
+        error: None,
+        finished_at_ms: 0,
+    };
+
+    let findings = matching_findings(result, webhook.min_severity);
+    if findings.is_empty() {
+        delivery.status = DeliveryStatus::Skipped;
+    } else {
+        let secret = if webhook.has_secret {
+            let name = secret_name(&webhook.name);
+            tauri::async_runtime::spawn_blocking(move || crate::credentials::load_app_secret(&name))
+                .await
+                .map_err(|e| format!("Failed to read webhook secret: {}", e))
+                .and_then(|secret| secret)
+        } else {
+            Ok(None)
+        };
+        let outcome = match secret.and_then(|secret| Ok((payload(webhook, secret.as_deref(), result, &findings)?, secret))) {
+            Ok((body, secret)) => {
+                let (attempts, http_status, outcome) = post(client, webhook, secret.as_deref(), &body).await;
+                delivery.attempts = attempts;
+                delivery.http_status = http_status;
+                outcome
+            }
+            Err(e) => Err(e),
+        };
+        match outcome {
+            Ok(()) => delivery.status = DeliveryStatus::Delivered,
+            Err(e) => {
+                log::warn!("Webhook delivery failed: {}", e);
+                delivery.error = Some(e);
+            }
+        }
+    }
+
+    delivery.finished_at_ms = now_ms();
+    record(app_handle, delivery.clone());
+    delivery
+}
+
+async fn send(app_handle: &tauri::AppHandle, result: &Value, targets: Vec<Webhook>) -> Result<Vec<WebhookDelivery>, String> {
+    let handle = app_handle.clone();
+    let client = tauri::async_runtime::spawn_blocking(move || crate::network::client_builder(&handle))
+        .await
+        .map_err(|e| format!("Failed to configure the network: {}", e))??
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    // Concurrently, so one slow endpoint's retries don't hold up the rest
+    let result = std::sync::Arc::new(result.clone());
+    let tasks: Vec<_> = targets
+        .into_iter()
+        .map(|webhook| {
+            let (handle, client, result) = (app_handle.clone(), client.clone(), result.clone());
+            tauri::async_runtime::spawn(async move { deliver(&handle, &client, &webhook, &result).await })
+        })
+        .collect();
+    let mut deliveries = Vec::new();
+    for task in tasks {
+        deliveries.push(task.await.map_err(|e| format!("Webhook delivery failed: {}", e))?);
+    }
+    Ok(deliveries)
+}
+
+/// Sends a finished scheduled run to the webhooks that want scheduled runs, in the background.
+pub fn dispatch_scheduled(app_handle: &tauri::AppHandle, analysis_id: String) {
+    let targets: Vec<Webhook> = webhooks(app_handle).into_iter().filter(|w| w.enabled && w.on_schedule).collect();
+    if targets.is_empty() {
+        return;
+    }
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = match crate::history::get_analysis_run(handle.clone(), analysis_id) {
+            Ok(result) => result,
+            Err(e) => return log::warn!("Not sending scheduled run to webhooks: {}", e),
+        };
+        if let Err(e) = send(&handle, &result, targets).await {
+            log::warn!("Failed to send scheduled run to webhooks: {}", e);
+        }
+    });
+}
+
+#[tauri::command]
+pub fn list_webhooks(app_handle: tauri::AppHandle) -> Vec<Webhook> {
+    webhooks(&app_handle)
+}
+
+/// Adds a webhook, or replaces the one with the same name; `previous_name`
+/// renames one. `secret` replaces the saved secret; an empty one removes it.
+#[tauri::command]
+pub async fn save_webhook(
+    app_handle: tauri::AppHandle,
+    mut webhook: Webhook,
+    secret: Option<String>,
+    previous_name: Option<String>,
+) -> Result<Webhook, String> {
+    validate(&mut webhook)?;
+    let replaces = previous_name.unwrap_or_else(|| webhook.name.clone());
+    let existing = webhooks(&app_handle);
+    if replaces != webhook.name && existing.iter().any(|w| w.name == webhook.name) {
+        return Err(format!("A webhook named {} already exists", webhook.name));
+    }
+    let had_secret = existing.iter().any(|w| w.name == replaces && w.has_secret);
+
+    let name = webhook.name.clone();
+    let old_name = replaces.clone();
+    webhook.has_secret = tauri::async_runtime::spawn_blocking(move || -> Result<bool, String> {
+        let secret = match secret {
+            Some(secret) => Some(secret).filter(|s| !s.is_empty()),
+            None if had_secret => crate::credentials::load_app_secret(&secret_name(&old_name))?,
+            None => None,
+        };
+        if old_name != name {
+            crate::credentials::store_app_secret(&secret_name(&old_name), None)?;
+        }
+        crate::credentials::store_app_secret(&secret_name(&name), secret.as_deref())?;
+        Ok(secret.is_some())
+    })
+    .await
+    .map_err(|e| format!("Failed to save webhook secret: {}", e))??;
+
+    let store = app_handle.state::<SettingsStore>();
+    let mut current = store.settings.lock().unwrap();
+    match current.webhooks.iter_mut().find(|w| w.name == replaces) {
+        Some(slot) => *slot = webhook.clone(),
+        None => current.webhooks.push(webhook.clone()),
+    }
+    settings::save(&app_handle, &current)?;
+    Ok(webhook)
+}
+
+/// Returns whether the webhook existed.
+#[tauri::command]
+pub async fn delete_webhook(app_handle: tauri::AppHandle, name: String) -> Result<bool, String> {
+    let secret = secret_name(&name);
+    tauri::async_runtime::spawn_blocking(move || crate::credentials::store_app_secret(&secret, None))
+        .await
+        .map_err(|e| format!("Failed to delete webhook secret: {}", e))??;
+
+    let store = app_handle.state::<SettingsStore>();
+    let mut current = store.settings.lock().unwrap();
+    let before = current.webhooks.len();
+    current.webhooks.retain(|w| w.name != name);
+    if current.webhooks.len() == before {
+        return Ok(false);
+    }
+    settings::save(&app_handle, &current)?;
+    Ok(true)
+}
+
+/// Sends a saved run to every enabled webhook and waits for the outcomes.
+#[tauri::command]
+pub async fn send_analysis_to_webhooks(app_handle: tauri::AppHandle, run_id: String) -> Result<Vec<WebhookDelivery>, String> {
+    let targets: Vec<Webhook> = webhooks(&app_handle).into_iter().filter(|w| w.enabled).collect();
+    if targets.is_empty() {
+        return Err("No webhooks are set up".to_string());
+    }
+    let result = crate::history::get_analysis_run(app_handle.clone(), run_id)?;
+    send(&app_handle, &result, targets).await
+}
+
+/// Recent deliveries, newest first.
+#[tauri::command]
+pub fn list_webhook_deliveries(app_handle: tauri::AppHandle) -> Vec<WebhookDelivery> {
+    app_handle
+        .state::<IntegrationState>()
+        .deliveries
+        .lock()
+        .unwrap()
+        .iter()
+        .rev()
+        .cloned()
+        .collect()
+}
@@ -0,0 +1,416 @@
+//! Analyses queued and run side by side, e.g. one per customer environment.
+//!
+//! `start_analysis` queues a job and returns straight away; at most
+//! `AppSettings::analysis_concurrency` jobs run on the backend at once and the
+//! rest wait their turn in order. Every change to a job is sent as an
+//! `analysis-job` event carrying the whole job, so the UI needs no polling.
+//! Finished jobs are kept for the session, up to `MAX_FINISHED_JOBS`.
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{Emitter, Manager};
+use tokio_util::sync::CancellationToken;
+
+use crate::settings::{self, SettingsStore};
+
+const DEFAULT_CONCURRENCY: u32 = 2;
+pub const MAX_CONCURRENCY: u32 = 16;
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_FINISHED_JOBS: usize = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    fn is_finished(self) -> bool {
+        matches!(self, Self::Completed | Self::Failed | Self::Cancelled)
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct AnalysisJob {
+    pub id: String,
+    pub deployment: String,
+    pub analyzers: Option<Vec<String>>,
+    pub bundle_path: Option<String>,
+    pub status: JobStatus,
+    /// The backend's id for the analysis, once it has been started there.
+    pub analysis_id: Option<String>,
+    pub percent: u32,
+    /// The analyzer running now, if any.
+    pub step: Option<String>,
+    pub health_score: Option<f64>,
+    pub error: Option<String>,
+    /// Milliseconds since the Unix epoch.
+    pub queued_at_ms: u64,
+    pub started_at_ms: Option<u64>,
+    pub finished_at_ms: Option<u64>,
+}
+
+struct Entry {
+    job: AnalysisJob,
+    cancel: CancellationToken,
+}
+
+#[derive(Default)]
+struct Jobs {
+    next_id: u64,
+    entries: HashMap<String, Entry>,
+    queue: VecDeque<String>,
+}
+
+#[derive(Default)]
+pub struct JobState {
+    jobs: Mutex<Jobs>,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+fn concurrency(app_handle: &tauri::AppHandle) -> usize {
+    let limit = app_handle
+        .state::<SettingsStore>()
+        .settings
+        .lock()
+        .unwrap()
+        .analysis_concurrency
+        .unwrap_or(DEFAULT_CONCURRENCY);
+    limit.clamp(1, MAX_CONCURRENCY) as usize
+}
+
+fn emit(app_handle: &tauri::AppHandle, job: AnalysisJob) {
+    if let Err(e) = app_handle.emit("analysis-job", job) {
+        log::warn!("Failed to emit analysis-job: {}", e);
+    }
+}
+
+/// Applies `change` to job `id` and reports it, unless the job has already
+/// finished (a late poll result mustn't undo a cancellation).
+fn update(app_handle: &tauri::AppHandle, id: &str, change: impl FnOnce(&mut AnalysisJob)) {
+    let updated = {
+        let state = app_handle.state::<JobState>();
+        let mut jobs = state.jobs.lock().unwrap();
+        let Some(entry) = jobs.entries.get_mut(id) else {
+            return;
+        };
+        if entry.job.status.is_finished() {
+            return;
+        }
+        change(&mut entry.job);
+        if entry.job.status.is_finished() {
+            entry.job.finished_at_ms = Some(now_ms());
+        }
+        entry.job.clone()
+    };
+    emit(app_handle, updated);
+}
+
+fn status(app_handle: &tauri::AppHandle, id: &str) -> Option<JobStatus> {
+    let state = app_handle.state::<JobState>();
+    let jobs = state.jobs.lock().unwrap();
+    jobs.entries.get(id).map(|e| e.job.status)
+}
+
+/// Drops the oldest finished jobs past `MAX_FINISHED_JOBS`.
+fn prune(jobs: &mut Jobs) {
+    let mut finished: Vec<(u64, String)> = jobs
+        .entries
+        .values()
+        .filter(|e| e.job.status.is_finished())
+        .map(|e| (e.job.finished_at_ms.unwrap_or_default(), e.job.id.clone()))
+        .collect();
+    if finished.len() <= MAX_FINISHED_JOBS {
+        return;
+    }
+    finished.sort();
+    for (_, id) in &finished[..finished.len() - MAX_FINISHED_JOBS] {
+        jobs.entries.remove(id);
+    }
+}
+
+/// Starts queued jobs while there is room under the concurrency limit.
+fn dispatch(app_handle: &tauri::AppHandle) {
+    let limit = concurrency(app_handle);
+    let started: Vec<(AnalysisJob, CancellationToken)> = {
+        let state = app_handle.state::<JobState>();
+        let mut jobs = state.jobs.lock().unwrap();
+        prune(&mut jobs);
+        let mut running = jobs
+            .entries
+            .values()
+            .filter(|e| e.job.status == JobStatus::Running)
+            .count();
+        let mut started = Vec::new();
+        while running < limit {
+            let Some(id) = jobs.queue.pop_front() else {
+                break;
+            };
+            let Some(entry) = jobs.entries.get_mut(&id) else {
+                continue;
+            };
+            entry.job.status = JobStatus::Running;
+            entry.job.started_at_ms = Some(now_ms());
+            started.push((entry.job.clone(), entry.cancel.clone()));
+            running += 1;
+        }
+        started
+    };
+
+    for (job, cancel) in started {
+        emit(app_handle, job.clone());
+        let handle = app_handle.clone();
+        tauri::async_runtime::spawn(async move {
+            let id = job.id.clone();
+            let result = tokio::select! {
+                result = run(&handle, &job) => result,
+                _ = cancel.cancelled() => Err("Analysis was cancelled".to_string()),
+            };
+            // A job aborted by `abort_running` is cancelled too, but its analysis died with the backend
+            if status(&handle, &id) == Some(JobStatus::Cancelled) {
+                cancel_on_backend(&handle, &id).await;
+            } else if let Err(e) = result {
+                log::warn!("Analysis job {} of {} failed: {}", id, job.deployment, e);
+                update(&handle, &id, |job| {
+                    job.status = JobStatus::Failed;
+                    job.error = Some(e);
+                });
+            }
+            dispatch(&handle);
+        });
+    }
+}
+
+/// Starts the analysis on the backend and follows it until it ends.
+async fn run(app_handle: &tauri::AppHandle, job: &AnalysisJob) -> Result<(), String> {
+    crate::scheduler::wait_for_backend(app_handle).await?;
+
+    let body = json!({
+        "deployment_name": job.deployment,
+        "analyzers": job.analyzers,
+        "bundle_path": job.bundle_path,
+    });
+    let started = crate::proxy::post_json(app_handle, "/api/v1/analysis", &body).await?;
+    let analysis_id = started
+        .get("analysis_id")
+        .and_then(Value::as_str)
+        .ok_or("Backend did not return an analysis id")?
+        .to_string();
+    update(app_handle, &job.id, |job| job.analysis_id = Some(analysis_id.clone()));
+
+    let path = format!("/api/v1/analysis/{}", analysis_id);
+    loop {
+        let status = crate::proxy::get_json(app_handle, &path)
+            .await?
+            .ok_or_else(|| format!("Analysis {} disappeared", analysis_id))?;
+        let percent = status.get("progress_percent").and_then(Value::as_u64).unwrap_or_default() as u32;
+        let step = status.get("current_step").and_then(Value::as_str).map(str::to_string);
+
+        match status.get("status").and_then(Value::as_str) {
+            Some("completed") => {
+                let results = crate::proxy::get_json(app_handle, &format!("{}/results", path)).await?;
+                let health_score = results.as_ref().and_then(|r| r.get("health_score")).and_then(Value::as_f64);
+                update(app_handle, &job.id, |job| {
+                    job.status = JobStatus::Completed;
+                    job.percent = 100;
+                    job.step = None;
+                    job.health_score = health_score;
+                });
+                return Ok(());
+            }
+            Some("failed") => {
+                let error = status.get("error").and_then(Value::as_str).unwrap_or("unknown error");
+                return Err(format!("Analysis failed: {}", error));
+            }
+            Some("cancelled") => {
+                update(app_handle, &job.id, |job| {
+                    job.status = JobStatus::Cancelled;
+                    job.error = Some("Analysis was cancelled".to_string());
+                });
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        let changed = {
+            let state = app_handle.state::<JobState>();
+            let jobs = state.jobs.lock().unwrap();
+            jobs.entries
+                .get(&job.id)
+                .is_some_and(|e| e.job.percent != percent || e.job.step != step)
+        };
+        if changed {
+            update(app_handle, &job.id, |job| {
+                job.percent = percent;
+                job.step = step;
+            });
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Stops job `id`'s analysis on the backend too, if it got that far.
+async fn cancel_on_backend(app_handle: &tauri::AppHandle, id: &str) {
+    let analysis_id = app_handle
+        .state::<JobState>()
+        .jobs
+        .lock()
+        .unwrap()
+        .entries
+        .get(id)
+        .and_then(|e| e.job.analysis_id.clone());
+    let Some(analysis_id) = analysis_id else {
+        return;
+    };
+    let path = format!("/api/v1/analysis/{}/cancel", analysis_id);
+    if let Err(e) = crate::proxy::post_json(app_handle, &path, &json!({})).await {
+        log::warn!("Failed to cancel analysis {} on the backend: {}", analysis_id, e);
+    }
+}
+
+/// Queues an analysis of `deployment`, or of the extracted diag bundle at
+/// `bundle_path`, and returns the job; it starts once there is room.
+#[tauri::command]
+pub fn start_analysis(
+    app_handle: tauri::AppHandle,
+    deployment: String,
+    analyzers: Option<Vec<String>>,
+    bundle_path: Option<String>,
+) -> Result<AnalysisJob, String> {
+    if deployment.trim().is_empty() {
+        return Err("An analysis needs a deployment".to_string());
+    }
+
+    let job = {
+        let state = app_handle.state::<JobState>();
+        let mut jobs = state.jobs.lock().unwrap();
+        jobs.next_id += 1;
+        let job = AnalysisJob {
+            id: format!("job-{}", jobs.next_id),
+            deployment,
+            analyzers,
+            bundle_path,
+            status: JobStatus::Queued,
+            analysis_id: None,
+            percent: 0,
+            step: None,
+            health_score: None,
+            error: None,
+            queued_at_ms: now_ms(),
+            started_at_ms: None,
+            finished_at_ms: None,
+        };
+        jobs.queue.push_back(job.id.clone());
+        jobs.entries.insert(
+            job.id.clone(),
+            Entry {
+                job: job.clone(),
+                cancel: CancellationToken::new(),
+            },
+        );
+        job
+    };
+    log::info!("Queued analysis job {} of {}", job.id, job.deployment);
+
+    emit(&app_handle, job.clone());
+    dispatch(&app_handle);
+    Ok(job)
+}
+
+/// Cancels a queued or running job and returns it as it now stands; a job that
+/// already finished is returned unchanged.
+#[tauri::command]
+pub fn cancel_analysis(app_handle: tauri::AppHandle, job_id: String) -> Result<AnalysisJob, String> {
+    {
+        let state = app_handle.state::<JobState>();
+        let mut jobs = state.jobs.lock().unwrap();
+        jobs.queue.retain(|id| id != &job_id);
+        let entry = jobs
+            .entries
+            .get(&job_id)
+            .ok_or_else(|| format!("No analysis job {}", job_id))?;
+        entry.cancel.cancel();
+    }
+    update(&app_handle, &job_id, |job| {
+        job.status = JobStatus::Cancelled;
+        job.error = Some("Analysis was cancelled".to_string());
+    });
+    log::info!("Cancelled analysis job {}", job_id);
+
+    let state = app_handle.state::<JobState>();
+    let jobs = state.jobs.lock().unwrap();
+    jobs.entries
+        .get(&job_id)
+        .map(|e| e.job.clone())
+        .ok_or_else(|| format!("No analysis job {}", job_id))
+}
+
+/// Every job this session, oldest first.
+#[tauri::command]
+pub fn list_jobs(app_handle: tauri::AppHandle) -> Vec<AnalysisJob> {
+    let state = app_handle.state::<JobState>();
+    let mut jobs: Vec<AnalysisJob> = state
+        .jobs
+        .lock()
+        .unwrap()
+        .entries
+        .values()
+        .map(|e| e.job.clone())
+        .collect();
+    jobs.sort_by_key(|job| job.queued_at_ms);
+    jobs
+}
+
+/// Sets how many analyses may run at once; queued jobs start at once if this
+/// makes room.
+#[tauri::command]
+pub fn set_analysis_concurrency(app_handle: tauri::AppHandle, limit: u32) -> Result<u32, String> {
+    if !(1..=MAX_CONCURRENCY).contains(&limit) {
+        return Err(format!("Concurrency must be between 1 and {}", MAX_CONCURRENCY));
+    }
+    {
+        let store = app_handle.state::<SettingsStore>();
+        let mut current = store.settings.lock().unwrap();
+        current.analysis_concurrency = Some(limit);
+        settings::save(&app_handle, &current)?;
+    }
+    dispatch(&app_handle);
+    Ok(limit)
+}
+
+/// Fails every running job with `reason`, e.g. because the backend running them
+/// is being killed. Queued jobs stay queued and start once the backend is ready
+/// again. Returns the jobs that were aborted.
+pub fn abort_running(app_handle: &tauri::AppHandle, reason: &str) -> Vec<AnalysisJob> {
+    let aborted: Vec<AnalysisJob> = {
+        let state = app_handle.state::<JobState>();
+        let mut jobs = state.jobs.lock().unwrap();
+        jobs.entries
+            .values_mut()
+            .filter(|e| e.job.status == JobStatus::Running)
+            .map(|e| {
+                e.cancel.cancel();
+                e.job.status = JobStatus::Failed;
+                e.job.error = Some(reason.to_string());
+                e.job.finished_at_ms = Some(now_ms());
+                e.job.clone()
+            })
+            .collect()
+    };
+    for job in &aborted {
+        emit(app_handle, job.clone());
+    }
+    aborted
+}
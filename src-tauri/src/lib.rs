@@ -1,81 +1,1021 @@
 use std::process::{Command, Child, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Mutex;
-use std::fs;
-use std::io::{BufRead, BufReader};
-use tauri::Manager;
+use std::sync::mpsc::Receiver;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{Emitter, Manager};
+use tokio_util::sync::CancellationToken;
+
+use startup::{StartupError, StartupErrorKind};
+
+mod analysis_events;
+mod antivirus;
+mod backend_info;
+mod baseline;
+mod bundle;
+mod client_cert;
+mod clipboard;
+mod compat;
+mod compression;
+mod connectivity;
+mod crash_report;
+mod credentials;
+mod cribl_api;
+mod deep_link;
+mod diag_import;
+mod dialogs;
+mod email;
+mod encoding;
+mod encryption;
+mod env_file;
+mod export;
+mod file_association;
+mod files;
+mod gateway;
+mod grouping;
+mod headless;
+mod health;
+mod history;
+mod html_report;
+mod instance;
+mod integrations;
+mod jobs;
+mod limits;
+mod logging;
+mod network;
+mod notifications;
+mod opener;
+mod output;
+mod passthrough;
+mod preflight;
+mod priority;
+mod process;
+mod profiles;
+mod proxy;
+mod readiness;
+mod report;
+mod reports;
+mod reset;
+mod resources;
+mod rulepacks;
+mod scheduler;
+mod scoring;
+mod search;
+mod self_test;
+mod settings;
+mod shortcut;
+mod sidecar;
+mod sidecar_cache;
+mod startup;
+mod supervisor;
+mod support_bundle;
+mod telemetry;
+mod ticketing;
+mod transport;
+mod tray;
+mod tunnel;
+mod update;
+mod wake;
+mod watch;
+mod window_state;
+
+/// Where the backend is in its lifecycle. Every start and stop moves through here,
+/// so overlapping calls are rejected instead of interleaving.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum Lifecycle {
+    #[default]
+    Stopped,
+    Starting,
+    Running,
+    Stopping,
+}
+
+impl Lifecycle {
+    fn describe(self) -> &'static str {
+        match self {
+            Lifecycle::Stopped => "stopped",
+            Lifecycle::Starting => "starting",
+            Lifecycle::Running => "running",
+            Lifecycle::Stopping => "stopping",
+        }
+    }
+
+    /// The moves starts and stops make; a backend that exits on its own goes
+    /// straight from running to stopped.
+    fn can_become(self, to: Lifecycle) -> bool {
+        matches!(
+            (self, to),
+            (Lifecycle::Stopped, Lifecycle::Starting)
+                | (Lifecycle::Starting, Lifecycle::Running | Lifecycle::Stopped)
+                | (Lifecycle::Running, Lifecycle::Stopping | Lifecycle::Stopped)
+                | (Lifecycle::Stopping, Lifecycle::Stopped)
+        )
+    }
+}
 
 struct PythonBackend {
+    lifecycle: Mutex<Lifecycle>,
     process: Mutex<Option<Child>>,
-    port: Mutex<Option<u16>>,
+    /// Holds the backend's subprocesses to its lifetime; dropped with the process.
+    job: Mutex<Option<process::Job>>,
+    /// Where the running backend accepts connections.
+    endpoint: Mutex<Option<transport::Endpoint>>,
+    /// What the backend reported on `/health` when it started.
+    health: Mutex<Option<backend_info::Health>>,
+    /// When the current backend was launched; only meaningful while `Running`.
+    started: Mutex<Option<(Instant, SystemTime)>>,
+    /// Spawn-to-ready time of the most recent successful launch.
+    last_startup: Mutex<Option<Duration>>,
+    restart_breaker: Mutex<supervisor::RestartBreaker>,
+    /// Set while the backend process is frozen by `suspend_backend`.
+    suspended: AtomicBool,
+    /// Whether we spawned the running backend, as opposed to adopting one that was
+    /// already there; only backends we own are stopped on exit.
+    owned: AtomicBool,
+    /// Cancelled once at teardown; background threads and tasks watch it and wind down.
+    shutdown: CancellationToken,
+    /// True while `Running`, for callers that await readiness instead of polling.
+    ready: tokio::sync::watch::Sender<bool>,
+    /// Secret the running backend accepts on `POST /api/v1/shutdown`; new for each launch.
+    shutdown_token: Mutex<Option<String>>,
+    /// Port the next launch should reuse, set by `force_restart_backend`.
+    resume_port: Mutex<Option<u16>>,
+}
+
+impl PythonBackend {
+    fn new() -> Self {
+        Self {
+            lifecycle: Mutex::new(Lifecycle::default()),
+            process: Default::default(),
+            job: Default::default(),
+            endpoint: Default::default(),
+            health: Default::default(),
+            started: Default::default(),
+            last_startup: Default::default(),
+            restart_breaker: Default::default(),
+            suspended: AtomicBool::new(false),
+            owned: AtomicBool::new(false),
+            shutdown: CancellationToken::new(),
+            ready: tokio::sync::watch::channel(false).0,
+            shutdown_token: Default::default(),
+            resume_port: Default::default(),
+        }
+    }
+
+    fn lifecycle(&self) -> Lifecycle {
+        *self.lifecycle.lock().unwrap()
+    }
+
+    /// Moves from `from` to `to`, failing if another caller got there first or
+    /// the lifecycle never moves that way.
+    fn transition(&self, from: Lifecycle, to: Lifecycle) -> Result<(), String> {
+        if !from.can_become(to) {
+            return Err(format!("The backend can't go from {} to {}", from.describe(), to.describe()));
+        }
+        let mut lifecycle = self.lifecycle.lock().unwrap();
+        if *lifecycle != from {
+            let action = match to {
+                Lifecycle::Starting | Lifecycle::Running => "start",
+                Lifecycle::Stopping | Lifecycle::Stopped => "stop",
+            };
+            return Err(format!("Cannot {} the backend while it is {}", action, lifecycle.describe()));
+        }
+
+        *lifecycle = to;
+        self.ready.send_replace(to == Lifecycle::Running);
+        Ok(())
+    }
+}
+
+/// Give up on the handshake after this many output lines or this long, whichever comes first.
+const HANDSHAKE_LINE_LIMIT: usize = 20;
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(30);
+/// How long to wait for `READY` after a `PORT:` line; backends that predate the
+/// marker never send it.
+const READY_GRACE: Duration = Duration::from_secs(5);
+
+/// Output from the OS or Python when a fixed port is taken (Linux/macOS, Windows).
+const ADDRESS_IN_USE_SIGNATURES: &[&str] = &["address already in use", "only one usage of each socket address"];
+const ADDRESS_IN_USE_ERROR: &str = "Backend port is already in use";
+
+enum Handshake {
+    Ready(transport::Endpoint),
+    AddressInUse(Vec<String>),
+    /// No port; carries everything the backend printed meanwhile.
+    Failed(Vec<String>),
+}
+
+/// Reads `PORT:<n>` (or `SOCKET:<path>`) lines until the backend prints `READY`
+/// and returns the last one announced, since a backend that rebinds prints a new
+/// one. Without a `READY`, the last endpoint seen inside the window is used.
+fn read_handshake(lines: &Receiver<output::OutputLine>, max_wait: Duration) -> Handshake {
+    let deadline = Instant::now() + HANDSHAKE_TIMEOUT.min(max_wait);
+    let mut endpoint = None;
+    let mut wait_until = deadline;
+    let mut seen = Vec::new();
+
+    for _ in 0..HANDSHAKE_LINE_LIMIT {
+        let remaining = wait_until.saturating_duration_since(Instant::now());
+        let Ok(output) = lines.recv_timeout(remaining) else {
+            break;
+        };
+        seen.push(output.line.clone());
+        let line = output.line.trim();
+
+        if line == "READY" {
+            if let Some(endpoint) = endpoint.take() {
+                return Handshake::Ready(endpoint);
+            }
+        }
+
+        // run_api.py reports a taken port on stderr instead of a PORT: line
+        let lowercase = line.to_ascii_lowercase();
+        if ADDRESS_IN_USE_SIGNATURES.iter().any(|s| lowercase.contains(s)) {
+            return Handshake::AddressInUse(seen);
+        }
+
+        // Look for line like "PORT:8080"
+        if let Some(value) = line.strip_prefix("PORT:") {
+            if let Ok(p) = value.trim().parse::<u16>() {
+                log::info!("Backend reported port {} on {:?}", p, output.stream);
+                endpoint = Some(transport::Endpoint::Tcp(p));
+                wait_until = deadline.min(Instant::now() + READY_GRACE);
+            }
+        } else if let Some(path) = line.strip_prefix("SOCKET:").map(str::trim).filter(|p| !p.is_empty()) {
+            log::info!("Backend reported socket {} on {:?}", path, output.stream);
+            endpoint = Some(transport::Endpoint::Socket(path.into()));
+            wait_until = deadline.min(Instant::now() + READY_GRACE);
+        }
+    }
+
+    match endpoint {
+        Some(endpoint) => {
+            log::warn!("Backend did not print READY; using last reported {}", endpoint);
+            Handshake::Ready(endpoint)
+        }
+        None => {
+            // Whatever else is already queued, such as the rest of a traceback
+            seen.extend(lines.try_iter().take(HANDSHAKE_LINE_LIMIT).map(|output| output.line));
+            Handshake::Failed(seen)
+        }
+    }
+}
+
+/// Overall ceiling on `launch_backend`, checked after each phase and bounding every wait.
+struct StartupDeadline {
+    at: Instant,
+    limit: Duration,
+}
+
+impl StartupDeadline {
+    fn new(limit: Duration) -> Self {
+        Self {
+            at: Instant::now() + limit,
+            limit,
+        }
+    }
+
+    fn remaining(&self) -> Duration {
+        self.at.saturating_duration_since(Instant::now())
+    }
+
+    fn passed(&self) -> bool {
+        Instant::now() >= self.at
+    }
+
+    /// Fails with a `Timeout` naming `phase` once the deadline has passed.
+    fn check(&self, phase: &str) -> Result<(), StartupError> {
+        if !self.passed() {
+            return Ok(());
+        }
+        Err(StartupError::new(
+            StartupErrorKind::Timeout,
+            format!(
+                "Backend did not start within {} s (deadline hit while {})",
+                self.limit.as_secs(),
+                phase
+            ),
+        ))
+    }
+}
+
+/// The endpoint `child` announces on `lines`, or why it didn't announce one.
+fn await_endpoint(
+    child: &mut Child,
+    lines: &Receiver<output::OutputLine>,
+    deadline: &StartupDeadline,
+) -> Result<transport::Endpoint, StartupError> {
+    match read_handshake(lines, deadline.remaining()) {
+        Handshake::Ready(endpoint) => Ok(endpoint),
+        Handshake::AddressInUse(output) => {
+            Err(StartupError::new(StartupErrorKind::SpawnFailed, ADDRESS_IN_USE_ERROR).with_output(output))
+        }
+        Handshake::Failed(output) => {
+            let error = if deadline.passed() {
+                deadline.check("waiting for the port handshake").unwrap_err()
+            } else if let Ok(Some(status)) = child.try_wait() {
+                StartupError::new(
+                    StartupErrorKind::SpawnFailed,
+                    format!("Backend exited with {} before reporting its port", status),
+                )
+            } else {
+                StartupError::new(StartupErrorKind::PortParseError, "Failed to read port from backend")
+            };
+            Err(error.with_output(output))
+        }
+    }
+}
+
+/// Runs `ready` against a freshly spawned `child`. When it fails, the child's
+/// tree is killed and everything recorded about it cleared, so the next start
+/// begins from a clean slate; `listen` is the socket it was told to create.
+fn finish_or_discard<T>(
+    state: &PythonBackend,
+    child: &mut Child,
+    job: Option<process::Job>,
+    listen: Option<&transport::Endpoint>,
+    ready: impl FnOnce(&mut Child) -> Result<T, StartupError>,
+) -> Result<(Option<process::Job>, T), StartupError> {
+    match ready(child) {
+        Ok(value) => Ok((job, value)),
+        Err(e) => {
+            // `job` goes when this returns, after the tree it holds is already dead
+            process::kill_tree(child);
+            *state.process.lock().unwrap() = None;
+            *state.job.lock().unwrap() = None;
+            *state.endpoint.lock().unwrap() = None;
+            *state.health.lock().unwrap() = None;
+            if let Some(listen) = listen {
+                transport::cleanup(listen);
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Everything between spawning the backend and handing it over; any error means the child must go.
+fn finish_startup(
+    app_handle: &tauri::AppHandle,
+    child: &mut Child,
+    backend_settings: &settings::BackendSettings,
+    deadline: &StartupDeadline,
+) -> Result<(transport::Endpoint, backend_info::Health), StartupError> {
+    limits::apply_after_spawn(child, &backend_settings.resource_limits)?;
+    deadline.check("applying resource limits")?;
+
+    // Read the port from whichever stream the backend prints it on
+    let lines = output::spawn_readers(app_handle, child)?;
+    let endpoint = await_endpoint(child, &lines, deadline);
+    drop(lines);
+    let endpoint = endpoint?;
+    deadline.check("waiting for the port handshake")?;
+
+    // A bound socket isn't a serving API; wait until it answers
+    let token = gateway::token(app_handle);
+    let health = backend_info::wait_until_healthy(&endpoint, token.as_deref(), deadline.remaining())?;
+    deadline.check("waiting for the health check")?;
+    compat::check(app_handle, &endpoint)?;
+
+    // Refuse to keep a backend that ended up reachable from other machines
+    if backend_settings.transport == transport::BackendTransport::Tcp && backend_settings.is_loopback() {
+        process::verify_loopback_only(child.id(), deadline.remaining())?;
+        deadline.check("verifying the backend's listeners")?;
+    }
+
+    Ok((endpoint, health))
+}
+
+const DEV_PORT: u16 = 8080;
+
+/// The configured working directory, else the backend data dir or the app data
+/// dir (created on demand).
+fn backend_working_dir(
+    app_handle: &tauri::AppHandle,
+    backend_settings: &settings::BackendSettings,
+) -> Result<std::path::PathBuf, String> {
+    if let Some(dir) = &backend_settings.working_dir {
+        let dir = std::path::PathBuf::from(dir);
+        if !dir.is_dir() {
+            return Err(format!("Backend working directory does not exist: {}", dir.display()));
+        }
+        return Ok(dir);
+    }
+    if let Some(dir) = passthrough::data_dir(backend_settings)? {
+        return Ok(dir);
+    }
+
+    let dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get data dir: {}", e))?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create data dir: {}", e))?;
+    Ok(dir)
+}
+
+/// Starts the backend (or, in development, adopts the one on the dev port),
+/// blocking until it is ready or has failed.
+fn start_backend_blocking(app_handle: tauri::AppHandle) -> Result<String, StartupError> {
+    let state: tauri::State<PythonBackend> = app_handle.state();
+    state.transition(Lifecycle::Stopped, Lifecycle::Starting)?;
+
+    match launch_backend(&app_handle) {
+        Ok(message) => {
+            state.transition(Lifecycle::Starting, Lifecycle::Running)?;
+            supervisor::spawn(app_handle.clone());
+            Ok(message)
+        }
+        Err(e) => {
+            state.transition(Lifecycle::Starting, Lifecycle::Stopped)?;
+            telemetry::record_startup_failure(&app_handle, &e);
+            Err(e)
+        }
+    }
 }
 
 #[tauri::command]
-fn start_backend(app_handle: tauri::AppHandle) -> Result<String, String> {
+async fn start_backend(app_handle: tauri::AppHandle) -> Result<String, StartupError> {
+    tauri::async_runtime::spawn_blocking(move || start_backend_blocking(app_handle))
+        .await
+        .map_err(|e| StartupError::from(format!("Failed to start backend: {}", e)))?
+}
+
+fn port_available(bind_address: &str, port: u16) -> bool {
+    std::net::TcpListener::bind((bind_address, port)).is_ok()
+}
+
+#[derive(Clone, serde::Serialize)]
+struct PortFallback {
+    requested_port: u16,
+    reason: String,
+}
+
+/// Reports that a fixed port couldn't be used and the OS will pick one instead.
+fn port_fallback(app_handle: &tauri::AppHandle, requested_port: u16, reason: &str) {
+    log::warn!("Backend port {} unavailable ({}); letting the OS pick one", requested_port, reason);
+    let payload = PortFallback {
+        requested_port,
+        reason: reason.to_string(),
+    };
+    if let Err(e) = app_handle.emit("backend-port-fallback", payload) {
+        log::warn!("Failed to emit backend-port-fallback: {}", e);
+    }
+}
+
+fn backend_command(
+    app_handle: &tauri::AppHandle,
+    sidecar_path: &std::path::Path,
+    descriptor: Option<&sidecar::LaunchDescriptor>,
+    backend_settings: &settings::BackendSettings,
+    working_dir: &std::path::Path,
+    listen: &transport::Endpoint,
+    shutdown_token: &str,
+) -> Result<Command, String> {
+    let mut command = Command::new(sidecar_path);
+    if let Some(descriptor) = descriptor {
+        log::info!("Launching backend via {}", sidecar_path.display());
+        command.args(&descriptor.script).args(&descriptor.args);
+    }
+    match listen {
+        // 0 lets the backend pick a free port; run_api.py calls its bind address `--host`
+        transport::Endpoint::Tcp(port) => command
+            .arg("--port")
+            .arg(port.to_string())
+            .arg("--host")
+            .arg(&backend_settings.bind_address),
+        transport::Endpoint::Socket(path) => command.arg("--socket").arg(path),
+    };
+    command
+        .current_dir(working_dir)
+        // Python block-buffers stdout when it isn't a TTY, as with our pipes. run_api.py
+        // flushes the PORT: line itself, but any other output (and any build of the
+        // backend that doesn't flush) would otherwise arrive late and in bursts
+        .env("PYTHONUNBUFFERED", "1")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    env_file::apply(app_handle, &mut command, backend_settings)?;
+    network::apply(app_handle, &mut command)?;
+    client_cert::apply(app_handle, &mut command);
+    rulepacks::apply(app_handle, &mut command);
+    // After the env file, so it can't be overridden
+    command.env(SHUTDOWN_TOKEN_VAR, shutdown_token);
+    if let Some(token) = gateway::token(app_handle) {
+        command.env(gateway::TOKEN_VAR, token);
+    }
+    limits::apply_before_spawn(&mut command, &backend_settings.resource_limits);
+    priority::apply_before_spawn(&mut command, backend_settings.priority);
+    process::isolate_before_spawn(&mut command);
+    // Only pass it when needed so a CRIBL_HC_BACKEND_BIN build without the flag still starts
+    if backend_settings.workers > 1 {
+        command.arg("--workers").arg(backend_settings.workers.to_string());
+    }
+    passthrough::apply(&mut command, backend_settings)?;
+
+    Ok(command)
+}
+
+fn launch_backend(app_handle: &tauri::AppHandle) -> Result<String, StartupError> {
+    let backend_settings = settings::backend(app_handle);
+    let deadline = StartupDeadline::new(Duration::from_secs(backend_settings.startup_timeout_secs.max(1)));
+
     // In development, Python backend runs separately on port 8080
     if cfg!(debug_assertions) {
+        let health = backend_info::check(DEV_PORT, gateway::token(app_handle).as_deref())?;
+        compat::check(app_handle, &transport::Endpoint::Tcp(DEV_PORT))?;
+
         let state: tauri::State<PythonBackend> = app_handle.state();
-        *state.port.lock().unwrap() = Some(8080);
-        return Ok("Development mode - Python backend should be started manually on port 8080".to_string());
+        *state.endpoint.lock().unwrap() = Some(transport::Endpoint::Tcp(DEV_PORT));
+        *state.health.lock().unwrap() = Some(health);
+        state.owned.store(false, Ordering::SeqCst);
+        *state.started.lock().unwrap() = Some((Instant::now(), SystemTime::now()));
+        app_handle.state::<proxy::ProxyState>().reset_metrics();
+        return Ok(format!("Development mode - using the backend already running on port {}", DEV_PORT));
     }
 
-    // Get the sidecar path
-    let sidecar_path = app_handle
-        .path()
-        .resource_dir()
-        .map_err(|e| format!("Failed to get resource dir: {}", e))?
-        .join("binaries")
-        .join("cribl-hc-backend");
-
-    // Start backend with random port (0 = auto-assign)
-    let mut child = Command::new(&sidecar_path)
-        .arg("--port")
-        .arg("0")
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .map_err(|e| format!("Failed to start backend: {}", e))?;
-
-    // Read the port from stdout (backend will print it)
-    let stdout = child.stdout.take().ok_or("Failed to capture stdout")?;
-    let reader = BufReader::new(stdout);
-
-    let mut port: Option<u16> = None;
-    for line in reader.lines().take(10) {
-        if let Ok(line) = line {
-            // Look for line like "PORT:8080"
-            if line.starts_with("PORT:") {
-                if let Ok(p) = line[5..].trim().parse::<u16>() {
-                    port = Some(p);
-                    break;
+    // Get the sidecar path, or the interpreter when the backend ships as a script
+    let descriptor = sidecar::launch_descriptor(app_handle)?;
+    let sidecar_path = match &descriptor {
+        Some(descriptor) => descriptor.interpreter.clone(),
+        None => sidecar::resolve_path(app_handle)?,
+    };
+    startup::check_binary(&sidecar_path)?;
+
+    // An x86_64 backend under Rosetta (or the reverse) starts slowly or not at all
+    if let Some(arch) = sidecar::check_arch(&sidecar_path)? {
+        if !arch.matches {
+            return Err(StartupError::new(
+                StartupErrorKind::SpawnFailed,
+                format!(
+                    "Backend binary architecture {:?} does not match host {}",
+                    arch.binary_archs, arch.host_arch
+                ),
+            ));
+        }
+    }
+
+    if descriptor.is_none() {
+        sidecar::verify_integrity(app_handle, &sidecar_path)
+            .map_err(|e| StartupError::new(StartupErrorKind::IntegrityCheckFailed, e))?;
+    }
+    if backend_settings.require_signed_backend {
+        sidecar::require_signed(&sidecar_path)?;
+    }
+    let sidecar_path = match descriptor {
+        Some(_) => sidecar_path,
+        None => sidecar_cache::prepare(app_handle, sidecar_path, &backend_settings),
+    };
+    deadline.check("checking the backend binary")?;
+
+    let working_dir = backend_working_dir(app_handle, &backend_settings)?;
+
+    let shutdown_token = new_token()?;
+    // Only set for the socket transport, whose path is fixed before launch
+    let listen = match backend_settings.transport {
+        transport::BackendTransport::Tcp => None,
+        transport::BackendTransport::Socket => {
+            Some(transport::Endpoint::Socket(transport::socket_path(app_handle, &shutdown_token)?))
+        }
+    };
+
+    let resume_port = app_handle.state::<PythonBackend>().resume_port.lock().unwrap().take();
+    let mut port_arg = resume_port.or(backend_settings.port).unwrap_or(0);
+    if listen.is_none() && port_arg != 0 && !port_available(&backend_settings.bind_address, port_arg) {
+        port_fallback(app_handle, port_arg, "it is in use by another process");
+        port_arg = 0;
+    }
+    let (child, job, spawned_at, endpoint, health) = loop {
+        let mut command = backend_command(
+            app_handle,
+            &sidecar_path,
+            descriptor.as_ref(),
+            &backend_settings,
+            &working_dir,
+            listen.as_ref().unwrap_or(&transport::Endpoint::Tcp(port_arg)),
+            &shutdown_token,
+        )?;
+
+        let mut child = command.spawn().map_err(|e| {
+            StartupError::new(StartupErrorKind::SpawnFailed, format!("Failed to start backend: {}", e))
+                .explain(&sidecar_path)
+        })?;
+        let spawned_at = Instant::now();
+        let job = process::contain(&child)
+            .map_err(|e| log::warn!("Backend subprocesses may outlive it: {}", e))
+            .ok();
+
+        let state: tauri::State<PythonBackend> = app_handle.state();
+        let ready = finish_or_discard(&state, &mut child, job, listen.as_ref(), |child| {
+            deadline
+                .check("spawning the backend")
+                .and_then(|_| finish_startup(app_handle, child, &backend_settings, &deadline))
+        });
+        match ready {
+            Ok((job, (endpoint, health))) => break (child, job, spawned_at, endpoint, health),
+            Err(e) => {
+                // Something grabbed the port between our check and the backend's bind
+                if e.message == ADDRESS_IN_USE_ERROR && port_arg != 0 && listen.is_none() {
+                    port_fallback(app_handle, port_arg, "the backend could not bind it");
+                    port_arg = 0;
+                    continue;
                 }
+                return Err(e.explain(&sidecar_path));
             }
         }
-    }
+    };
 
-    let port = port.ok_or("Failed to read port from backend")?;
+    let startup = spawned_at.elapsed();
+    log::info!(
+        "Backend {} ready on {} after {} ms",
+        health.version,
+        endpoint,
+        startup.as_millis()
+    );
 
     let state: tauri::State<PythonBackend> = app_handle.state();
+    *state.last_startup.lock().unwrap() = Some(startup);
     *state.process.lock().unwrap() = Some(child);
-    *state.port.lock().unwrap() = Some(port);
+    *state.job.lock().unwrap() = job;
+    let message = format!("Backend started on {}", endpoint);
+    *state.endpoint.lock().unwrap() = Some(endpoint);
+    *state.health.lock().unwrap() = Some(health);
+    *state.shutdown_token.lock().unwrap() = Some(shutdown_token);
+    state.suspended.store(false, Ordering::SeqCst);
+    state.owned.store(true, Ordering::SeqCst);
+    *state.started.lock().unwrap() = Some((spawned_at, SystemTime::now() - spawned_at.elapsed()));
+    app_handle.state::<proxy::ProxyState>().reset_metrics();
+
+    Ok(message)
+}
+
+/// How long a stopping backend gets to finish in-flight work before it is killed.
+const GRACEFUL_STOP_TIMEOUT: Duration = Duration::from_secs(5);
+const SHUTDOWN_TOKEN_VAR: &str = "CRIBL_HC_SHUTDOWN_TOKEN";
+
+fn new_token() -> Result<String, String> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).map_err(|e| format!("Failed to generate token: {}", e))?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Asks the backend to exit on its own; works the same on every OS, where a
+/// signal would not on Windows. Returns whether the backend accepted.
+fn request_shutdown(endpoint: &transport::Endpoint, token: &str) -> bool {
+    let request = format!(
+        "POST /api/v1/shutdown HTTP/1.1\r\nHost: {}\r\nX-Shutdown-Token: {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        endpoint.host(),
+        token
+    );
+    transport::exchange(endpoint, &request, Duration::from_secs(2))
+        .is_ok_and(|response| response.starts_with("HTTP/1.1 202"))
+}
+
+/// Stops the backend this app spawned, if any, and forgets its endpoint. It is asked
+/// to shut down and given `GRACEFUL_STOP_TIMEOUT` before being killed.
+fn stop_backend_process(app_handle: &tauri::AppHandle) -> Result<(), String> {
+    let state: tauri::State<PythonBackend> = app_handle.state();
+    state.transition(Lifecycle::Running, Lifecycle::Stopping)?;
+
+    // Taking the child out of state also stops its supervisor thread
+    let child = state.process.lock().unwrap().take();
+    let endpoint = state.endpoint.lock().unwrap().take();
+    let token = state.shutdown_token.lock().unwrap().take();
+    if let Some(mut child) = child {
+        // A frozen backend can't act on a shutdown request
+        if state.suspended.load(Ordering::SeqCst) {
+            if let Err(e) = process::resume(child.id()) {
+                log::warn!("Failed to resume backend before stopping it: {}", e);
+            }
+        }
+
+        let requested = endpoint
+            .as_ref()
+            .zip(token)
+            .is_some_and(|(endpoint, token)| request_shutdown(endpoint, &token));
+        process::terminate(&mut child, GRACEFUL_STOP_TIMEOUT, !requested);
+        if let Some(endpoint) = &endpoint {
+            transport::cleanup(endpoint);
+        }
+    }
+    state.job.lock().unwrap().take();
+    state.suspended.store(false, Ordering::SeqCst);
+
+    state.transition(Lifecycle::Stopping, Lifecycle::Stopped)
+}
+
+/// Stops a backend we spawned. One we adopted is only detached from, unless
+/// `force` is set, in which case it is killed by the PID it reports.
+#[tauri::command]
+async fn stop_backend(app_handle: tauri::AppHandle, force: Option<bool>) -> Result<String, String> {
+    let state: tauri::State<PythonBackend> = app_handle.state();
+    if state.lifecycle() == Lifecycle::Running && !state.owned.load(Ordering::SeqCst) {
+        if !force.unwrap_or(false) {
+            stop_backend_process(&app_handle)?;
+            return Ok("Detached from a backend this app did not start; it is still running".to_string());
+        }
+
+        let pid = proxy::get_json(&app_handle, "/api/v1/config")
+            .await?
+            .and_then(|config| config.get("pid").and_then(|pid| pid.as_u64()))
+            .ok_or("Backend does not report its PID, so it cannot be stopped from here")?;
+        process::kill(pid as u32)?;
+    }
+
+    stop_backend_process(&app_handle)?;
+    Ok("Backend stopped".to_string())
+}
+
+/// Stops the backend if it is running and starts a fresh one. Being asked
+/// explicitly, it also clears a tripped restart breaker.
+#[tauri::command]
+async fn restart_backend(app_handle: tauri::AppHandle) -> Result<String, String> {
+    supervisor::reset_backend_circuit_breaker(app_handle.clone());
+
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let state: tauri::State<PythonBackend> = handle.state();
+        if state.lifecycle() == Lifecycle::Running {
+            stop_backend_process(&handle)?;
+        }
+        start_backend_blocking(handle).map_err(String::from)
+    })
+    .await
+    .map_err(|e| format!("Failed to restart backend: {}", e))?
+}
+
+/// Tells every background thread and task to stop, then takes the backend down.
+/// Threads are detached: the supervisor notices within one poll interval, and the
+/// output readers end on their own once the backend's pipes close.
+fn shutdown(app_handle: &tauri::AppHandle) {
+    let state: tauri::State<PythonBackend> = app_handle.state();
+    state.shutdown.cancel();
+
+    if state.lifecycle() == Lifecycle::Running {
+        if let Err(e) = stop_backend_process(app_handle) {
+            log::warn!("Failed to stop backend during shutdown: {}", e);
+        }
+    }
+}
+
+/// PID of the backend process this app spawned.
+fn backend_pid(app_handle: &tauri::AppHandle) -> Result<u32, String> {
+    let state: tauri::State<PythonBackend> = app_handle.state();
+    let process = state.process.lock().unwrap();
 
-    Ok(format!("Backend started on port {}", port))
+    process
+        .as_ref()
+        .map(|child| child.id())
+        .ok_or_else(|| "Backend process is not running".to_string())
 }
 
+/// The backend's base URL, through the authenticating gateway.
 #[tauri::command]
 fn get_backend_url(app_handle: tauri::AppHandle) -> Result<String, String> {
     let state: tauri::State<PythonBackend> = app_handle.state();
-    let port = state.port.lock().unwrap();
+    if state.endpoint.lock().unwrap().is_none() {
+        return Err("Backend not started yet".to_string());
+    }
+    gateway::url(&app_handle)
+}
+
+const INSPECTOR_WINDOW: &str = "inspector";
+
+/// Opens the backend's interactive API docs in their own window, or focuses it if
+/// already open. Closing the window leaves the backend running.
+#[tauri::command]
+async fn open_inspector_window(app_handle: tauri::AppHandle) -> Result<(), String> {
+    let docs_url = format!("{}/api/docs", get_backend_url(app_handle.clone())?);
+    let url: url::Url = docs_url
+        .parse()
+        .map_err(|e| format!("Failed to build inspector URL: {}", e))?;
+
+    if let Some(window) = app_handle.get_webview_window(INSPECTOR_WINDOW) {
+        // The backend may have restarted on a different port since the window opened
+        if window.url().ok().as_ref().map(|u| u.port()) != Some(url.port()) {
+            window
+                .navigate(url)
+                .map_err(|e| format!("Failed to open inspector: {}", e))?;
+        }
+        return window.set_focus().map_err(|e| format!("Failed to focus inspector window: {}", e));
+    }
+
+    tauri::WebviewWindowBuilder::new(&app_handle, INSPECTOR_WINDOW, tauri::WebviewUrl::External(url))
+        .title("Backend API Inspector")
+        .inner_size(1200.0, 900.0)
+        .min_inner_size(800.0, 600.0)
+        .build()
+        .map_err(|e| format!("Failed to open inspector window: {}", e))?;
+    Ok(())
+}
+
+#[derive(serde::Serialize)]
+struct BackendUptime {
+    uptime_secs: f64,
+    /// Milliseconds since the Unix epoch.
+    started_at_ms: u64,
+}
+
+#[tauri::command]
+fn get_backend_uptime(app_handle: tauri::AppHandle) -> Result<BackendUptime, String> {
+    let state: tauri::State<PythonBackend> = app_handle.state();
+    let started = *state.started.lock().unwrap();
+
+    match (state.lifecycle(), started) {
+        (Lifecycle::Running, Some((instant, wall_clock))) => Ok(BackendUptime {
+            uptime_secs: instant.elapsed().as_secs_f64(),
+            started_at_ms: wall_clock
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_millis() as u64),
+        }),
+        _ => Err("Backend is not running".to_string()),
+    }
+}
+
+/// Cold-start time of the last successful launch, to spot regressions after updates.
+#[tauri::command]
+fn get_last_startup_duration_ms(app_handle: tauri::AppHandle) -> Result<f64, String> {
+    let state: tauri::State<PythonBackend> = app_handle.state();
+    let last_startup = *state.last_startup.lock().unwrap();
+
+    last_startup
+        .map(|d| d.as_secs_f64() * 1000.0)
+        .ok_or_else(|| "Backend has not started yet".to_string())
+}
+
+#[tauri::command]
+fn get_backend_workers(app_handle: tauri::AppHandle) -> u32 {
+    settings::backend(&app_handle).workers
+}
 
-    match *port {
-        Some(p) => Ok(format!("http://localhost:{}", p)),
-        None => Err("Backend not started yet".to_string()),
+/// Saves the worker count and restarts a running backend so it takes effect.
+/// Analysis state lives in each worker's memory, so follow-up requests for an
+/// analysis may land on a worker that doesn't know it when this is above 1.
+#[tauri::command]
+async fn set_backend_workers(app_handle: tauri::AppHandle, workers: u32) -> Result<u32, String> {
+    let max = std::thread::available_parallelism().map_or(1, |n| n.get() as u32);
+    if workers == 0 || workers > max {
+        return Err(format!("Workers must be between 1 and {}", max));
+    }
+
+    {
+        let store = app_handle.state::<settings::SettingsStore>();
+        let mut current = store.settings.lock().unwrap();
+        current.backend.workers = workers;
+        settings::save(&app_handle, &current)?;
+    }
+
+    if app_handle.state::<PythonBackend>().lifecycle() == Lifecycle::Running {
+        let handle = app_handle.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            stop_backend_process(&handle)?;
+            start_backend_blocking(handle).map_err(String::from)
+        })
+        .await
+        .map_err(|e| format!("Failed to restart backend: {}", e))??;
     }
+
+    Ok(workers)
+}
+
+/// Takes effect on the next start; `None` or 0 lets the OS pick a free port.
+#[tauri::command]
+fn set_backend_port(app_handle: tauri::AppHandle, port: Option<u16>) -> Result<(), String> {
+    let store = app_handle.state::<settings::SettingsStore>();
+    let mut current = store.settings.lock().unwrap();
+    current.backend.port = port.filter(|&p| p != 0);
+    settings::save(&app_handle, &current)
+}
+
+#[tauri::command]
+fn get_backend_working_dir(app_handle: tauri::AppHandle) -> Result<String, String> {
+    let dir = backend_working_dir(&app_handle, &settings::backend(&app_handle))?;
+    Ok(dir.to_string_lossy().to_string())
+}
+
+/// Takes effect on the next start; `None` goes back to the app data dir.
+#[tauri::command]
+fn set_backend_working_dir(app_handle: tauri::AppHandle, path: Option<String>) -> Result<(), String> {
+    if let Some(path) = &path {
+        if !std::path::Path::new(path).is_dir() {
+            return Err(format!("Not a directory: {}", path));
+        }
+    }
+
+    let store = app_handle.state::<settings::SettingsStore>();
+    let mut current = store.settings.lock().unwrap();
+    current.backend.working_dir = path;
+    settings::save(&app_handle, &current)
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ReloadMethod {
+    Signal,
+    Restart,
+}
+
+#[derive(serde::Serialize)]
+struct ConfigReload {
+    method: ReloadMethod,
+    /// Why a restart was needed instead of a signal.
+    reason: Option<String>,
+}
+
+/// Whether the backend advertises handling SIGHUP; sending it to one that
+/// doesn't would terminate it.
+async fn backend_handles_sighup(app_handle: &tauri::AppHandle) -> bool {
+    match proxy::get_json(app_handle, "/api/v1/config").await {
+        Ok(Some(config)) => config
+            .get("reload_signals")
+            .and_then(|s| s.as_array())
+            .is_some_and(|signals| signals.iter().any(|s| s == "SIGHUP")),
+        _ => false,
+    }
+}
+
+/// Reloads the backend's configuration with SIGHUP where the platform and backend
+/// support it, otherwise restarts it.
+#[tauri::command]
+async fn reload_backend_config(app_handle: tauri::AppHandle) -> Result<ConfigReload, String> {
+    if app_handle.state::<PythonBackend>().lifecycle() != Lifecycle::Running {
+        return Err("Backend is not running".to_string());
+    }
+
+    let reason = if !cfg!(unix) {
+        "Signals are not supported on this platform"
+    } else if !backend_handles_sighup(&app_handle).await {
+        "Backend does not support reloading on SIGHUP"
+    } else {
+        #[cfg(unix)]
+        process::request_reload(backend_pid(&app_handle)?)?;
+        log::info!("Sent SIGHUP to the backend");
+        return Ok(ConfigReload {
+            method: ReloadMethod::Signal,
+            reason: None,
+        });
+    };
+
+    log::info!("Restarting backend to reload config: {}", reason);
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        stop_backend_process(&handle)?;
+        start_backend_blocking(handle).map_err(String::from)
+    })
+    .await
+    .map_err(|e| format!("Failed to restart backend: {}", e))??;
+
+    Ok(ConfigReload {
+        method: ReloadMethod::Restart,
+        reason: Some(reason.to_string()),
+    })
+}
+
+/// Freezes the backend to free CPU without losing its state.
+#[tauri::command]
+fn suspend_backend(app_handle: tauri::AppHandle) -> Result<(), String> {
+    let state: tauri::State<PythonBackend> = app_handle.state();
+    if state.lifecycle() != Lifecycle::Running {
+        return Err("Backend is not running".to_string());
+    }
+    if state.suspended.load(Ordering::SeqCst) {
+        return Err("Backend is already suspended".to_string());
+    }
+
+    process::suspend(backend_pid(&app_handle)?)?;
+    state.suspended.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+#[tauri::command]
+fn resume_backend(app_handle: tauri::AppHandle) -> Result<(), String> {
+    let state: tauri::State<PythonBackend> = app_handle.state();
+    if !state.suspended.load(Ordering::SeqCst) {
+        return Err("Backend is not suspended".to_string());
+    }
+
+    process::resume(backend_pid(&app_handle)?)?;
+    state.suspended.store(false, Ordering::SeqCst);
+    Ok(())
 }
 
 #[tauri::command]
 fn get_backend_status(app_handle: tauri::AppHandle) -> Result<String, String> {
+    let state: tauri::State<PythonBackend> = app_handle.state();
+    if state.restart_breaker.lock().unwrap().is_tripped() {
+        return Ok("Backend status: Failed (automatic restarts throttled)".to_string());
+    }
+
+    match state.lifecycle() {
+        Lifecycle::Starting => return Ok("Backend status: Starting".to_string()),
+        Lifecycle::Stopping => return Ok("Backend status: Stopping".to_string()),
+        Lifecycle::Stopped | Lifecycle::Running => {}
+    }
+    if state.suspended.load(Ordering::SeqCst) {
+        return Ok("Backend status: Suspended".to_string());
+    }
+
+    let workers = settings::backend(&app_handle).workers;
+    let origin = if state.owned.load(Ordering::SeqCst) { "" } else { ", adopted" };
     let url = get_backend_url(app_handle)?;
-    Ok(format!("Backend status: Running on {}", url))
+    Ok(format!("Backend status: Running on {} ({} workers{})", url, workers, origin))
 }
 
 #[tauri::command]
@@ -83,90 +1023,585 @@ async fn save_file_with_dialog(
     app_handle: tauri::AppHandle,
     filename: String,
     content: Vec<u8>,
+    default_extension: Option<String>,
+    encoding: Option<encoding::TextEncoding>,
 ) -> Result<String, String> {
-    use tauri_plugin_dialog::{DialogExt, FilePath};
+    files::check_save_payload(content.len())?;
+    let encoding = encoding.unwrap_or_else(|| encoding::default_encoding(&app_handle));
+    let content = encoding::encode(content, encoding)?;
 
-    // Show save dialog
-    let file_path = app_handle
-        .dialog()
-        .file()
-        .set_file_name(&filename)
-        .blocking_save_file();
+    // Show save dialog; without an explicit extension, fall back to the suggested filename's
+    let path = match default_extension {
+        Some(extension) => dialogs::save_path_with_extension(&app_handle, &filename, Some(&extension)).await?,
+        None => dialogs::save_path(&app_handle, &filename).await?,
+    };
 
-    match file_path {
-        Some(FilePath::Path(path)) => {
-            // Write file to chosen location
-            fs::write(&path, content)
-                .map_err(|e| format!("Failed to save file: {}", e))?;
+    // Write file to chosen location
+    let write_started = Instant::now();
+    files::write_file_atomic(&path, &content)?;
+    dialogs::record_save_write(&app_handle, write_started.elapsed());
 
-            Ok(path.to_string_lossy().to_string())
-        }
-        Some(FilePath::Url(_)) => Err("URL paths not supported".to_string()),
-        None => Err("Save cancelled".to_string()),
-    }
+    Ok(path.to_string_lossy().to_string())
 }
 
 #[tauri::command]
 fn open_downloads_folder() -> Result<(), String> {
-    // Open Downloads folder in native file manager
-    #[cfg(target_os = "macos")]
-    {
-        Command::new("open")
-            .arg(std::env::var("HOME").unwrap_or_default() + "/Downloads")
-            .spawn()
-            .map_err(|e| format!("Failed to open Downloads: {}", e))?;
-    }
+    // Prefer the platform's configured Downloads folder (XDG user dirs, Known Folders)
+    let downloads = dirs::download_dir()
+        .or_else(|| dirs::home_dir().map(|home| home.join("Downloads")))
+        .ok_or("Could not determine the Downloads folder")?;
 
-    #[cfg(target_os = "linux")]
-    {
-        Command::new("xdg-open")
-            .arg(std::env::var("HOME").unwrap_or_default() + "/Downloads")
-            .spawn()
-            .map_err(|e| format!("Failed to open Downloads: {}", e))?;
-    }
+    opener::open_folder(&downloads)
+}
 
-    #[cfg(target_os = "windows")]
-    {
-        Command::new("explorer")
-            .arg(std::env::var("USERPROFILE").unwrap_or_default() + "\\Downloads")
-            .spawn()
-            .map_err(|e| format!("Failed to open Downloads: {}", e))?;
-    }
+/// Runs the CI mode instead of the app when started with `--headless`; see
+/// `headless.rs`. Returns the process exit code, or `None` to launch the app.
+pub fn run_headless(args: &[String]) -> Option<i32> {
+    headless::run(args)
+}
 
-    Ok(())
+/// Answers ssh's password prompt when an SSH tunnel runs us as `SSH_ASKPASS`;
+/// see `tunnel.rs`. Returns the exit code, or `None` to carry on as usual.
+pub fn run_askpass() -> Option<i32> {
+    tunnel::askpass()
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
   tauri::Builder::default()
-    .manage(PythonBackend {
-        process: Default::default(),
-        port: Default::default(),
-    })
+    // Must be registered first so a second launch exits before doing any work
+    .plugin(tauri_plugin_single_instance::init(|app, argv, cwd| {
+        instance::on_second_instance(app, argv, cwd);
+    }))
+    .manage(analysis_events::ProgressSubscriptions::default())
+    .manage(PythonBackend::new())
+    .manage(compat::CompatState::default())
+    .manage(connectivity::ProbeRegistry::default())
+    .manage(deep_link::DeepLinkState::default())
+    .manage(dialogs::DialogRegistry::default())
+    .manage(file_association::OpenedFiles::default())
+    .manage(encryption::EncryptionState::default())
+    .manage(search::SearchState::default())
+    .manage(files::FileHandles::default())
+    .manage(gateway::GatewayState::default())
+    .manage(health::HealthMonitor::default())
+    .manage(jobs::JobState::default())
+    .manage(history::HistoryState::default())
+    .manage(integrations::IntegrationState::default())
+    .manage(output::OutputState::default())
+    .manage(profiles::ProfileStore::default())
+    .manage(proxy::ProxyState::default())
+    .manage(readiness::ReadyWaiters::default())
+    .manage(resources::ResourceMonitor::default())
+    .manage(scheduler::SchedulerState::default())
+    .manage(settings::SettingsStore::default())
+    .manage(tray::TrayState::default())
+    .manage(shortcut::ShortcutState::default())
+    .manage(tunnel::TunnelState::default())
+    .manage(update::UpdateState::default())
+    .manage(watch::WatchState::default())
     .plugin(tauri_plugin_dialog::init())
-    .invoke_handler(tauri::generate_handler![start_backend, get_backend_url, get_backend_status, save_file_with_dialog, open_downloads_folder])
+    .plugin(tauri_plugin_deep_link::init())
+    .invoke_handler(tauri::generate_handler![
+        start_backend,
+        stop_backend,
+        restart_backend,
+        get_backend_url,
+        readiness::await_backend_ready_cancellable,
+        readiness::cancel_await,
+        open_inspector_window,
+        get_backend_status,
+        backend_info::get_backend_info,
+        compat::get_version_matrix,
+        get_backend_uptime,
+        get_last_startup_duration_ms,
+        get_backend_workers,
+        set_backend_workers,
+        reload_backend_config,
+        set_backend_port,
+        get_backend_working_dir,
+        set_backend_working_dir,
+        suspend_backend,
+        resume_backend,
+        save_file_with_dialog,
+        file_association::open_file_with_dialog,
+        file_association::take_pending_opened_files,
+        file_association::open_encrypted_analysis,
+        open_downloads_folder,
+        opener::open_export_folder,
+        opener::reveal_file_in_folder,
+        opener::open_file_with_default_app,
+        opener::open_external_url,
+        logging::get_log_file_path,
+        logging::set_log_level,
+        output::enable_backend_log_file,
+        output::disable_backend_log_file,
+        output::set_backend_json_events,
+        output::get_backend_log_buffer,
+        output::get_backend_logs,
+        output::set_backend_log_limits,
+        priority::set_backend_priority,
+        env_file::set_backend_env_file,
+        settings::get_settings,
+        settings::update_settings,
+        settings::export_app_config,
+        settings::import_app_config,
+        settings::repair_settings,
+        proxy::proxy_backend_request,
+        proxy::proxy_backend_request_validated,
+        proxy::get_backend_throughput,
+        proxy::get_proxy_queue_depth,
+        proxy::set_proxy_limits,
+        proxy::set_proxy_default_headers,
+        proxy::get_proxy_default_headers,
+        proxy::cancel_proxy_request,
+        proxy::save_url_to_file,
+        proxy::warm_backend,
+        proxy::get_backend_runtime_config,
+        reports::list_reports_in_downloads,
+        reports::scan_for_reports,
+        reports::open_report_at_anchor,
+        reset::factory_reset,
+        self_test::run_self_test,
+        watch::watch_config_path,
+        watch::unwatch_config_path,
+        sidecar::get_sidecar_path,
+        sidecar::check_backend_arch,
+        sidecar::check_backend_freshness,
+        sidecar::verify_sidecar_signature,
+        sidecar::check_backend_dependencies,
+        sidecar_cache::probe_sidecar_volume,
+        update::check_for_updates,
+        update::install_update,
+        update::rollback_backend_update,
+        rulepacks::list_rulepacks,
+        rulepacks::update_rulepacks,
+        rulepacks::pin_rulepack_version,
+        sidecar_cache::set_sidecar_cache,
+        supervisor::reset_backend_circuit_breaker,
+        supervisor::force_restart_backend,
+        wake::verify_backend_after_sleep,
+        supervisor::set_backend_watchdog,
+        supervisor::set_backend_auto_restart,
+        files::open_file_chunked,
+        files::read_next_chunk,
+        files::close_file,
+        encoding::set_save_encoding,
+        files::begin_chunked_save,
+        files::write_save_chunk,
+        files::finish_chunked_save,
+        files::abort_chunked_save,
+        files::benchmark_save,
+        files::validate_save_directory,
+        files::check_resource_dir_writable,
+        files::fingerprint_file,
+        health::start_health_monitor,
+        resources::get_resource_usage,
+        resources::set_resource_monitor,
+        health::stop_health_monitor,
+        health::get_health_history,
+        health::check_clock_skew,
+        health::export_health_history_csv,
+        history::record_analysis_run,
+        history::get_analysis_history,
+        history::clear_analysis_history,
+        history::save_analysis_result,
+        history::list_analysis_runs,
+        history::get_analysis_run,
+        history::delete_analysis_run,
+        history::compare_analyses,
+        baseline::set_baseline,
+        baseline::clear_baseline,
+        baseline::get_baselines,
+        baseline::get_drift_report,
+        scoring::compute_health_score,
+        grouping::group_findings,
+        clipboard::copy_findings_to_clipboard,
+        search::search_findings,
+        encryption::enable_encryption,
+        encryption::disable_encryption,
+        encryption::export_encrypted,
+        profiles::list_profiles,
+        profiles::save_profile,
+        profiles::delete_profile,
+        profiles::get_active_profile,
+        profiles::set_active_profile,
+        jobs::start_analysis,
+        jobs::cancel_analysis,
+        jobs::list_jobs,
+        jobs::set_analysis_concurrency,
+        scheduler::schedule_analysis,
+        scheduler::get_analysis_schedule,
+        shortcut::get_global_shortcut,
+        shortcut::set_global_shortcut,
+        notifications::get_notification_settings,
+        notifications::set_notification_enabled,
+        integrations::list_webhooks,
+        integrations::save_webhook,
+        integrations::delete_webhook,
+        integrations::send_analysis_to_webhooks,
+        integrations::list_webhook_deliveries,
+        ticketing::get_ticketing_config,
+        ticketing::configure_ticketing,
+        ticketing::create_tickets,
+        ticketing::list_tickets,
+        email::get_email_config,
+        email::configure_email,
+        email::send_report_email,
+        report::export_pdf,
+        html_report::export_html_report,
+        analysis_events::subscribe_analysis_progress,
+        analysis_events::unsubscribe_analysis_progress,
+        antivirus::diagnose_antivirus,
+        bundle::extract_diag_bundle,
+        compression::save_compressed_with_dialog,
+        compression::open_compressed_with_dialog,
+        connectivity::diagnose_connectivity,
+        connectivity::report_connectivity_probe,
+        crash_report::list_crash_reports,
+        telemetry::get_pending_crash_reports,
+        telemetry::submit_crash_report,
+        credentials::save_credentials,
+        credentials::list_credentials,
+        credentials::delete_credentials,
+        client_cert::register_client_certificate,
+        client_cert::list_client_certificates,
+        client_cert::remove_client_certificate,
+        cribl_api::collect_metrics,
+        network::get_network_settings,
+        network::set_proxy_config,
+        network::test_connection,
+        preflight::run_preflight,
+        tunnel::open_ssh_tunnel,
+        tunnel::close_ssh_tunnel,
+        tunnel::list_ssh_tunnels,
+        export::save_csv_with_dialog,
+        export::save_bundle_with_dialog,
+        export::export_findings,
+        support_bundle::generate_support_bundle,
+        process::get_backend_listeners,
+        process::get_backend_process_tree,
+        window_state::save_window_state,
+        window_state::restore_window_state,
+        window_state::open_results_window,
+        deep_link::take_pending_deep_links,
+        deep_link::create_finding_permalink,
+        deep_link::resolve_finding_link,
+        dialogs::pick_directory_with_dialog,
+        dialogs::get_dialog_latency_stats,
+    ])
+    .on_window_event(|window, event| {
+        if let tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop { paths, .. }) = event {
+            if window.label() == "main" {
+                diag_import::on_drop(window.app_handle(), paths);
+            }
+        }
+        if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+            if window_state::is_results_window(window.label()) {
+                if let Err(e) = window_state::save_results_window(window) {
+                    log::warn!("Failed to save results window state: {}", e);
+                }
+            }
+            if window.label() == "main" {
+                if let Err(e) = window_state::save(window.app_handle()) {
+                    log::warn!("Failed to save window state: {}", e);
+                }
+                // The report and inspector windows would otherwise keep the app alive
+                for (label, other) in window.app_handle().webview_windows() {
+                    if label != "main" {
+                        if let Err(e) = other.close() {
+                            log::warn!("Failed to close {} window: {}", label, e);
+                        }
+                    }
+                }
+                // Scheduled checks keep running from the tray
+                if scheduler::keeps_running(window.app_handle()) {
+                    api.prevent_close();
+                    if let Err(e) = window.hide() {
+                        log::warn!("Failed to hide main window: {}", e);
+                    }
+                }
+            }
+        }
+    })
     .setup(|app| {
-      if cfg!(debug_assertions) {
-        app.handle().plugin(
-          tauri_plugin_log::Builder::default()
-            .level(log::LevelFilter::Info)
-            .build(),
-        )?;
+      if let Err(e) = logging::init(app.handle()) {
+          eprintln!("{}", e);
+      }
+      let loaded = settings::load(app.handle());
+      logging::set_level(loaded.log_level.as_deref());
+      app.state::<proxy::ProxyState>().set_limits(loaded.proxy_limits);
+      *app.state::<settings::SettingsStore>().settings.lock().unwrap() = loaded;
+      telemetry::init(app.handle());
+      profiles::init(app.handle());
+      if let Err(e) = window_state::restore(app.handle()) {
+          log::warn!("Failed to restore window state: {}", e);
+      }
+      deep_link::init(app);
+      file_association::init(app);
+      wake::spawn(app.handle().clone());
+      if let Err(e) = tray::init(app) {
+          log::warn!("Failed to create tray icon: {}", e);
+      }
+      scheduler::spawn(app.handle().clone());
+      shortcut::init(app.handle());
+      resources::spawn(app.handle().clone());
+      if let Err(e) = gateway::start(app.handle()) {
+          log::error!("{}", e);
       }
 
       // Auto-start Python backend in production
       if !cfg!(debug_assertions) {
           let handle = app.handle().clone();
-          tauri::async_runtime::spawn(async move {
+          tauri::async_runtime::spawn_blocking(move || {
               std::thread::sleep(std::time::Duration::from_millis(500));
-              if let Err(e) = start_backend(handle) {
-                  eprintln!("Failed to start backend: {}", e);
+              if let Err(e) = start_backend_blocking(handle.clone()) {
+                  log::error!("Failed to start backend: {}", e);
+                  // Nothing awaits the auto-start, so tell the UI why there is no backend
+                  if let Err(e) = handle.emit("backend-start-failed", e) {
+                      log::warn!("Failed to emit backend-start-failed: {}", e);
+                  }
               }
           });
       }
 
       Ok(())
     })
-    .run(tauri::generate_context!())
-    .expect("error while running tauri application");
+    .build(tauri::generate_context!())
+    .expect("error while building tauri application")
+    .run(|app_handle, event| match event {
+      tauri::RunEvent::ExitRequested { .. } => {
+        dialogs::cancel_all(app_handle);
+      }
+      tauri::RunEvent::Exit => {
+        shutdown(app_handle);
+        watch::unwatch_all(app_handle);
+        shortcut::unregister(app_handle);
+        tunnel::close_all(app_handle);
+      }
+      #[cfg(target_os = "macos")]
+      tauri::RunEvent::Opened { urls } => {
+        file_association::on_opened(app_handle, urls);
+      }
+      _ => {}
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Barrier;
+
+    const RACERS: usize = 16;
+
+    /// Has `RACERS` threads attempt `from` → `to` at once, returning how many succeeded.
+    fn race(state: &PythonBackend, from: Lifecycle, to: Lifecycle) -> usize {
+        let barrier = Barrier::new(RACERS);
+        std::thread::scope(|scope| {
+            let attempts: Vec<_> = (0..RACERS)
+                .map(|_| {
+                    scope.spawn(|| {
+                        barrier.wait();
+                        state.transition(from, to).is_ok()
+                    })
+                })
+                .collect();
+            attempts.into_iter().map(|attempt| attempt.join().unwrap()).filter(|won| *won).count()
+        })
+    }
+
+    #[test]
+    fn one_caller_wins_each_edge() {
+        let state = PythonBackend::new();
+        for _ in 0..50 {
+            assert_eq!(race(&state, Lifecycle::Stopped, Lifecycle::Starting), 1);
+            assert_eq!(race(&state, Lifecycle::Starting, Lifecycle::Running), 1);
+            assert!(*state.ready.borrow());
+            assert_eq!(race(&state, Lifecycle::Running, Lifecycle::Stopping), 1);
+            assert!(!*state.ready.borrow());
+            assert_eq!(race(&state, Lifecycle::Stopping, Lifecycle::Stopped), 1);
+        }
+        assert_eq!(state.lifecycle(), Lifecycle::Stopped);
+    }
+
+    #[test]
+    fn concurrent_starts_and_stops_never_overlap() {
+        let state = PythonBackend::new();
+        let barrier = Barrier::new(RACERS);
+        std::thread::scope(|scope| {
+            for i in 0..RACERS {
+                let (state, barrier) = (&state, &barrier);
+                scope.spawn(move || {
+                    barrier.wait();
+                    for _ in 0..200 {
+                        if i % 2 == 0 {
+                            if state.transition(Lifecycle::Stopped, Lifecycle::Starting).is_ok() {
+                                assert_eq!(state.lifecycle(), Lifecycle::Starting);
+                                state.transition(Lifecycle::Starting, Lifecycle::Running).unwrap();
+                            }
+                        } else if state.transition(Lifecycle::Running, Lifecycle::Stopping).is_ok() {
+                            assert_eq!(state.lifecycle(), Lifecycle::Stopping);
+                            state.transition(Lifecycle::Stopping, Lifecycle::Stopped).unwrap();
+                        }
+                    }
+                });
+            }
+        });
+        assert!(matches!(state.lifecycle(), Lifecycle::Stopped | Lifecycle::Running));
+    }
+
+    #[test]
+    fn illegal_transitions_are_rejected() {
+        let all = [Lifecycle::Stopped, Lifecycle::Starting, Lifecycle::Running, Lifecycle::Stopping];
+        let legal = [
+            (Lifecycle::Stopped, Lifecycle::Starting),
+            (Lifecycle::Starting, Lifecycle::Running),
+            (Lifecycle::Starting, Lifecycle::Stopped),
+            (Lifecycle::Running, Lifecycle::Stopping),
+            (Lifecycle::Running, Lifecycle::Stopped),
+            (Lifecycle::Stopping, Lifecycle::Stopped),
+        ];
+        for from in all {
+            for to in all {
+                let state = PythonBackend::new();
+                *state.lifecycle.lock().unwrap() = from;
+                let result = state.transition(from, to);
+                assert_eq!(result.is_ok(), legal.contains(&(from, to)), "{:?} -> {:?}", from, to);
+                if result.is_err() {
+                    assert_eq!(state.lifecycle(), from, "a rejected {:?} -> {:?} still moved", from, to);
+                }
+            }
+        }
+
+        // Stopping a backend that was never started
+        let state = PythonBackend::new();
+        assert!(state.transition(Lifecycle::Stopped, Lifecycle::Stopping).is_err());
+        assert_eq!(state.lifecycle(), Lifecycle::Stopped);
+    }
+
+    #[cfg(unix)]
+    mod failed_launch {
+        use super::*;
+        use std::io::{BufRead, BufReader, Write};
+        use std::net::TcpListener;
+        use std::process::Command;
+
+        /// A stand-in backend, in its own process group like the real one.
+        fn spawn(program: &str, args: &[&str]) -> Child {
+            let mut command = Command::new(program);
+            command.args(args).stdout(Stdio::null()).stderr(Stdio::null());
+            process::isolate_before_spawn(&mut command);
+            command.spawn().unwrap()
+        }
+
+        /// What a launch has recorded by the time a phase fails.
+        fn launching(child: &Child) -> (PythonBackend, Option<process::Job>) {
+            let state = PythonBackend::new();
+            *state.endpoint.lock().unwrap() = Some(transport::Endpoint::Tcp(1));
+            *state.job.lock().unwrap() = process::contain(child).ok();
+            (state, process::contain(child).ok())
+        }
+
+        fn assert_discarded<T>(
+            state: &PythonBackend,
+            child: &mut Child,
+            result: Result<T, StartupError>,
+            kind: StartupErrorKind,
+        ) {
+            let Err(error) = result else {
+                panic!("the failing phase was treated as a successful start");
+            };
+            assert_eq!(error.kind, kind, "{}", error.message);
+            assert!(child.try_wait().unwrap().is_some(), "the backend was left running");
+            assert!(state.process.lock().unwrap().is_none());
+            assert!(state.job.lock().unwrap().is_none());
+            assert!(state.endpoint.lock().unwrap().is_none());
+            assert!(state.health.lock().unwrap().is_none());
+        }
+
+        #[test]
+        fn limits() {
+            let mut child = spawn("sleep", &["30"]);
+            let (state, job) = launching(&child);
+            let socket = std::env::temp_dir().join(format!("cribl-hc-test-{}.sock", std::process::id()));
+            std::fs::write(&socket, b"").unwrap();
+            let listen = transport::Endpoint::Socket(socket.clone());
+
+            let result = finish_or_discard(&state, &mut child, job, Some(&listen), |_| -> Result<(), _> {
+                Err(StartupError::from("Failed to set memory limit: Access is denied.".to_string()))
+            });
+            assert_discarded(&state, &mut child, result, StartupErrorKind::Other);
+            assert!(!socket.exists(), "the socket file was left behind");
+        }
+
+        #[test]
+        fn handshake() {
+            let mut child = spawn("sleep", &["30"]);
+            let (state, job) = launching(&child);
+            let (sender, lines) = std::sync::mpsc::channel();
+            for line in ["Traceback (most recent call last):", "ModuleNotFoundError: No module named 'uvicorn'"] {
+                let line = line.to_string();
+                sender.send(output::OutputLine { stream: output::Stream::Stderr, line }).unwrap();
+            }
+            drop(sender);
+
+            let deadline = StartupDeadline::new(Duration::from_secs(5));
+            let result = finish_or_discard(&state, &mut child, job, None, |child| {
+                await_endpoint(child, &lines, &deadline)
+            });
+            assert_discarded(&state, &mut child, result, StartupErrorKind::PortParseError);
+        }
+
+        #[test]
+        fn health() {
+            let mut child = spawn("sleep", &["30"]);
+            let (state, job) = launching(&child);
+            let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+
+            let result = finish_or_discard(&state, &mut child, job, None, |_| {
+                backend_info::wait_until_healthy(&transport::Endpoint::Tcp(port), None, Duration::from_millis(500))
+            });
+            assert_discarded(&state, &mut child, result, StartupErrorKind::HealthCheckFailed);
+        }
+
+        #[test]
+        fn compat() {
+            let mut child = spawn("sleep", &["30"]);
+            let (state, job) = launching(&child);
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let port = listener.local_addr().unwrap().port();
+            std::thread::spawn(move || {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 0 && line != "\r\n" {
+                    line.clear();
+                }
+                let body = r#"{"version":"9.0.0","api_version":"v1"}"#;
+                let response = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}", body.len(), body);
+                stream.write_all(response.as_bytes()).unwrap();
+            });
+
+            let result = finish_or_discard(&state, &mut child, job, None, |_| {
+                compat::examine(&transport::Endpoint::Tcp(port), None, "0.1.0").into_result()
+            });
+            assert_discarded(&state, &mut child, result, StartupErrorKind::IncompatibleBackend);
+        }
+
+        #[test]
+        fn listener_check() {
+            if process::listeners(std::process::id()).is_err() {
+                eprintln!("skipping: no tool to list listening sockets");
+                return;
+            }
+            let script = "import socket, time\ns = socket.socket()\ns.bind(('0.0.0.0', 0))\ns.listen()\ntime.sleep(30)";
+            let mut child = spawn("python3", &["-c", script]);
+            let (state, job) = launching(&child);
+
+            let result = finish_or_discard(&state, &mut child, job, None, |child| {
+                process::verify_loopback_only(child.id(), Duration::from_secs(5)).map_err(StartupError::from)
+            });
+            assert_discarded(&state, &mut child, result, StartupErrorKind::Other);
+        }
+    }
 }
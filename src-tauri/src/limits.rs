@@ -0,0 +1,120 @@
+use serde::{Deserialize, Serialize};
+use std::process::{Child, Command};
+
+/// Optional caps on the backend process; `None` leaves a resource unlimited.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResourceLimits {
+    pub max_memory_mb: Option<u64>,
+    pub max_cpu_percent: Option<u32>,
+}
+
+/// Applies limits that must be in place before the process starts (Linux rlimits).
+#[cfg(target_os = "linux")]
+pub fn apply_before_spawn(command: &mut Command, limits: &ResourceLimits) {
+    use std::os::unix::process::CommandExt;
+
+    if let Some(mb) = limits.max_memory_mb {
+        let bytes = mb.saturating_mul(1024 * 1024) as libc::rlim_t;
+        log::info!("Limiting backend address space to {} MB", mb);
+
+        // Runs in the forked child before exec, so only async-signal-safe calls here.
+        unsafe {
+            command.pre_exec(move || {
+                let limit = libc::rlimit {
+                    rlim_cur: bytes,
+                    rlim_max: bytes,
+                };
+                if libc::setrlimit(libc::RLIMIT_AS, &limit) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+
+    if limits.max_cpu_percent.is_some() {
+        log::warn!("CPU limits are not supported on Linux; ignoring max_cpu_percent");
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn apply_before_spawn(_command: &mut Command, limits: &ResourceLimits) {
+    if cfg!(not(windows)) && (limits.max_memory_mb.is_some() || limits.max_cpu_percent.is_some()) {
+        log::warn!("Backend resource limits are not supported on this platform; skipping");
+    }
+}
+
+/// Applies limits that attach to a running process (Windows Job Objects).
+#[cfg(windows)]
+pub fn apply_after_spawn(child: &Child, limits: &ResourceLimits) -> Result<(), String> {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectCpuRateControlInformation,
+        JobObjectExtendedLimitInformation, SetInformationJobObject,
+        JOBOBJECT_CPU_RATE_CONTROL_INFORMATION, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+        JOB_OBJECT_CPU_RATE_CONTROL_ENABLE, JOB_OBJECT_CPU_RATE_CONTROL_HARD_CAP,
+        JOB_OBJECT_LIMIT_PROCESS_MEMORY,
+    };
+
+    if limits.max_memory_mb.is_none() && limits.max_cpu_percent.is_none() {
+        return Ok(());
+    }
+
+    unsafe {
+        let job = CreateJobObjectW(std::ptr::null(), std::ptr::null());
+        if job.is_null() {
+            return Err(format!("Failed to create job object: {}", std::io::Error::last_os_error()));
+        }
+
+        let result = (|| {
+            if let Some(mb) = limits.max_memory_mb {
+                let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = std::mem::zeroed();
+                info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_PROCESS_MEMORY;
+                info.ProcessMemoryLimit = (mb as usize).saturating_mul(1024 * 1024);
+                if SetInformationJobObject(
+                    job,
+                    JobObjectExtendedLimitInformation,
+                    &info as *const _ as *const _,
+                    std::mem::size_of_val(&info) as u32,
+                ) == 0
+                {
+                    return Err(format!("Failed to set memory limit: {}", std::io::Error::last_os_error()));
+                }
+                log::info!("Limiting backend memory to {} MB", mb);
+            }
+
+            if let Some(percent) = limits.max_cpu_percent {
+                let mut info: JOBOBJECT_CPU_RATE_CONTROL_INFORMATION = std::mem::zeroed();
+                info.ControlFlags = JOB_OBJECT_CPU_RATE_CONTROL_ENABLE | JOB_OBJECT_CPU_RATE_CONTROL_HARD_CAP;
+                // CpuRate is expressed in hundredths of a percent.
+                info.Anonymous.CpuRate = percent.clamp(1, 100) * 100;
+                if SetInformationJobObject(
+                    job,
+                    JobObjectCpuRateControlInformation,
+                    &info as *const _ as *const _,
+                    std::mem::size_of_val(&info) as u32,
+                ) == 0
+                {
+                    return Err(format!("Failed to set CPU limit: {}", std::io::Error::last_os_error()));
+                }
+                log::info!("Limiting backend CPU to {}%", percent);
+            }
+
+            if AssignProcessToJobObject(job, child.as_raw_handle() as _) == 0 {
+                return Err(format!("Failed to assign backend to job object: {}", std::io::Error::last_os_error()));
+            }
+            Ok(())
+        })();
+
+        // The job lives on as long as the backend is assigned to it.
+        CloseHandle(job);
+        result
+    }
+}
+
+#[cfg(not(windows))]
+pub fn apply_after_spawn(_child: &Child, _limits: &ResourceLimits) -> Result<(), String> {
+    Ok(())
+}
@@ -0,0 +1,185 @@
+//! Always-on app logging: one JSON object per line in the platform log dir,
+//! rotated once the file reaches `MAX_FILE_BYTES` and keeping `KEEP_FILES`
+//! generations (`cribl-hc.log`, `cribl-hc.1.log`, ...). Debug builds also echo
+//! to stderr.
+//!
+//! The level is saved in settings and can be changed while running with
+//! `set_log_level`. Debug and trace records are only kept from this crate;
+//! dependencies such as hyper are far too chatty at those levels.
+
+use serde::Serialize;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+use tauri::Manager;
+
+use crate::settings::{self, SettingsStore};
+
+const LOG_FILE_STEM: &str = "cribl-hc";
+const MAX_FILE_BYTES: u64 = 10 * 1024 * 1024;
+/// Files kept, the current one included.
+const KEEP_FILES: usize = 5;
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+struct LogFile {
+    file: File,
+    size: u64,
+}
+
+struct Logger {
+    dir: PathBuf,
+    file: Mutex<Option<LogFile>>,
+}
+
+#[derive(Serialize)]
+struct Record<'a> {
+    timestamp: String,
+    level: &'a str,
+    target: &'a str,
+    message: String,
+}
+
+fn file_path(dir: &Path, generation: usize) -> PathBuf {
+    match generation {
+        0 => dir.join(format!("{}.log", LOG_FILE_STEM)),
+        n => dir.join(format!("{}.{}.log", LOG_FILE_STEM, n)),
+    }
+}
+
+fn open(dir: &Path) -> std::io::Result<LogFile> {
+    fs::create_dir_all(dir)?;
+    let file = OpenOptions::new().create(true).append(true).open(file_path(dir, 0))?;
+    let size = file.metadata()?.len();
+    Ok(LogFile { file, size })
+}
+
+/// Shifts each file up a generation, dropping the oldest.
+fn rotate(dir: &Path) -> std::io::Result<()> {
+    let _ = fs::remove_file(file_path(dir, KEEP_FILES - 1));
+    for generation in (0..KEEP_FILES - 1).rev() {
+        let from = file_path(dir, generation);
+        if from.exists() {
+            fs::rename(&from, file_path(dir, generation + 1))?;
+        }
+    }
+    Ok(())
+}
+
+impl Logger {
+    fn write(&self, line: &[u8]) -> std::io::Result<()> {
+        let mut file = self.file.lock().unwrap();
+        if file
+            .as_ref()
+            .is_some_and(|f| f.size > 0 && f.size + line.len() as u64 > MAX_FILE_BYTES)
+        {
+            // Close before renaming; Windows won't rename an open file
+            *file = None;
+            rotate(&self.dir)?;
+        }
+        if file.is_none() {
+            *file = Some(open(&self.dir)?);
+        }
+
+        let Some(current) = file.as_mut() else {
+            return Ok(());
+        };
+        current.file.write_all(line)?;
+        current.size += line.len() as u64;
+        Ok(())
+    }
+}
+
+impl log::Log for Logger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        let ours = metadata.target().starts_with(env!("CARGO_CRATE_NAME"));
+        metadata.level() <= log::max_level() && (metadata.level() <= log::Level::Info || ours)
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let entry = Record {
+            timestamp: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            level: record.level().as_str(),
+            target: record.target(),
+            message: record.args().to_string(),
+        };
+        if cfg!(debug_assertions) {
+            eprintln!("[{} {}] {}", entry.level, entry.target, entry.message);
+        }
+
+        let Ok(mut line) = serde_json::to_vec(&entry) else {
+            return;
+        };
+        line.push(b'\n');
+        // There is nowhere left to report a failure to write the log
+        let _ = self.write(&line);
+    }
+
+    fn flush(&self) {
+        if let Some(file) = self.file.lock().unwrap().as_mut() {
+            let _ = file.file.flush();
+        }
+    }
+}
+
+pub fn parse_level(level: &str) -> Result<log::LevelFilter, String> {
+    log::LevelFilter::from_str(level)
+        .map_err(|_| format!("Unknown log level {:?}; use off, error, warn, info, debug or trace", level))
+}
+
+/// Installs the logger at info; called first thing in setup, so whatever loading
+/// the settings logs is kept. `set_level` then applies the saved level.
+pub fn init(app_handle: &tauri::AppHandle) -> Result<(), String> {
+    let dir = app_handle
+        .path()
+        .app_log_dir()
+        .map_err(|e| format!("Failed to get log dir: {}", e))?;
+    let logger = LOGGER.get_or_init(|| Logger {
+        dir,
+        file: Mutex::new(None),
+    });
+
+    log::set_logger(logger).map_err(|e| format!("Failed to install logger: {}", e))?;
+    log::set_max_level(log::LevelFilter::Info);
+    Ok(())
+}
+
+/// Logs at the saved `level`, or info if it is unset or unknown.
+pub fn set_level(level: Option<&str>) {
+    let level = level.and_then(|level| parse_level(level).ok());
+    log::set_max_level(level.unwrap_or(log::LevelFilter::Info));
+}
+
+#[tauri::command]
+pub fn get_log_file_path(app_handle: tauri::AppHandle) -> Result<String, String> {
+    let dir = match LOGGER.get() {
+        Some(logger) => logger.dir.clone(),
+        None => app_handle
+            .path()
+            .app_log_dir()
+            .map_err(|e| format!("Failed to get log dir: {}", e))?,
+    };
+    Ok(file_path(&dir, 0).to_string_lossy().to_string())
+}
+
+/// Changes the log level straight away and keeps it for later launches.
+#[tauri::command]
+pub fn set_log_level(app_handle: tauri::AppHandle, level: String) -> Result<(), String> {
+    let filter = parse_level(&level)?;
+    {
+        let store = app_handle.state::<SettingsStore>();
+        let mut current = store.settings.lock().unwrap();
+        current.log_level = Some(filter.as_str().to_ascii_lowercase());
+        settings::save(&app_handle, &current)?;
+    }
+
+    log::set_max_level(filter);
+    log::info!("Log level set to {}", filter);
+    Ok(())
+}
@@ -0,0 +1,402 @@
+//! How connections to Cribl leave the machine: through the system proxy, an
+//! explicit proxy, or directly, trusting an extra CA bundle for proxies that
+//! intercept TLS. Applies to our own API client (`client_builder`) and to the
+//! Python backend, which gets the same setup as `HTTP(S)_PROXY`, `NO_PROXY` and
+//! `SSL_CERT_FILE` when it is spawned.
+//!
+//! "System" means `HTTP(S)_PROXY` if set, otherwise the OS setting: the
+//! Internet Settings registry key on Windows and `scutil --proxy` on macOS. PAC
+//! scripts and NTLM/Kerberos proxy authentication aren't supported. The proxy
+//! password is kept in the OS credential store, never in settings.
+
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+use crate::settings::{self, SettingsStore};
+
+const PROXY_PASSWORD_SECRET: &str = "network-proxy-password";
+const TEST_TIMEOUT: Duration = Duration::from_secs(15);
+/// Never proxied, since the backend and our gateway live there.
+const LOOPBACK: &str = "localhost,127.0.0.1,::1";
+const PROXY_VARS: &[&str] = &["HTTP_PROXY", "HTTPS_PROXY", "ALL_PROXY", "http_proxy", "https_proxy", "all_proxy"];
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum ProxyConfig {
+    #[default]
+    System,
+    /// Connect directly, ignoring any system or environment proxy.
+    None,
+    Manual {
+        /// `http://host:port`.
+        url: String,
+        username: Option<String>,
+        /// Comma-separated hosts or domains reached directly.
+        no_proxy: Option<String>,
+    },
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkSettings {
+    pub proxy: ProxyConfig,
+    /// PEM file of CA certificates trusted on top of the built-in ones.
+    pub ca_bundle: Option<String>,
+}
+
+enum Route {
+    /// `HTTP(S)_PROXY` is set; clients already honour it.
+    Environment,
+    Direct,
+    Proxy {
+        url: url::Url,
+        auth: Option<(String, String)>,
+        no_proxy: String,
+    },
+}
+
+impl Route {
+    fn describe(&self) -> String {
+        match self {
+            Route::Environment => "proxy from HTTP(S)_PROXY".to_string(),
+            Route::Direct => "direct".to_string(),
+            Route::Proxy { url, .. } => format!("proxy {}", url),
+        }
+    }
+}
+
+fn with_loopback(no_proxy: Option<&str>) -> String {
+    match no_proxy.map(str::trim).filter(|hosts| !hosts.is_empty()) {
+        Some(hosts) => format!("{},{}", LOOPBACK, hosts),
+        None => LOOPBACK.to_string(),
+    }
+}
+
+fn parse_proxy_url(url: &str) -> Result<url::Url, String> {
+    let with_scheme = if url.contains("://") { url.to_string() } else { format!("http://{}", url) };
+    let parsed = url::Url::parse(&with_scheme).map_err(|e| format!("Invalid proxy URL {}: {}", url, e))?;
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+        return Err(format!("Proxy URL must be http:// or https:// with a host: {}", url));
+    }
+    if !parsed.username().is_empty() || parsed.password().is_some() {
+        return Err("Put the proxy username and password in their own fields, not the URL".to_string());
+    }
+    Ok(parsed)
+}
+
+/// The OS proxy as a URL plus its bypass list.
+#[cfg(windows)]
+fn system_proxy() -> Option<(String, Option<String>)> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let output = Command::new("reg")
+        .args(["query", r"HKCU\Software\Microsoft\Windows\CurrentVersion\Internet Settings"])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    // Lines look like "    ProxyServer    REG_SZ    proxy.corp:8080"
+    let value = |name: &str| {
+        text.lines().find_map(|line| {
+            let mut parts = line.split_whitespace();
+            (parts.next()? == name).then(|| parts.skip(1).collect::<Vec<_>>().join(" "))
+        })
+    };
+
+    if value("ProxyEnable").as_deref() != Some("0x1") {
+        return None;
+    }
+    // Either one proxy for everything or "http=host:port;https=host:port;..."
+    let server = value("ProxyServer")?;
+    let for_scheme = |scheme: &str| {
+        server
+            .split(';')
+            .find_map(|entry| entry.strip_prefix(scheme)?.strip_prefix('=').map(str::to_string))
+    };
+    let proxy = match server.contains('=') {
+        true => for_scheme("https").or_else(|| for_scheme("http"))?,
+        false => server.clone(),
+    };
+    let bypass = value("ProxyOverride").map(|hosts| {
+        hosts
+            .split(';')
+            .filter(|host| !host.is_empty() && *host != "<local>")
+            .collect::<Vec<_>>()
+            .join(",")
+    });
+    Some((proxy, bypass))
+}
+
+#[cfg(target_os = "macos")]
+fn system_proxy() -> Option<(String, Option<String>)> {
+    let output = Command::new("scutil").arg("--proxy").output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    // Lines look like "  HTTPSProxy : proxy.corp"
+    let field = |name: &str| {
+        text.lines().find_map(|line| {
+            let value = line.trim().strip_prefix(name)?.trim_start().strip_prefix(':')?;
+            Some(value.trim().to_string())
+        })
+    };
+    let proxy = |scheme: &str| {
+        if field(&format!("{}Enable", scheme)).as_deref() != Some("1") {
+            return None;
+        }
+        let host = field(&format!("{}Proxy", scheme))?;
+        Some(match field(&format!("{}Port", scheme)) {
+            Some(port) => format!("{}:{}", host, port),
+            None => host,
+        })
+    };
+    let proxy = proxy("HTTPS").or_else(|| proxy("HTTP"))?;
+
+    let mut bypass = Vec::new();
+    let mut in_exceptions = false;
+    for line in text.lines().map(str::trim) {
+        if line.starts_with("ExceptionsList") {
+            in_exceptions = true;
+        } else if in_exceptions && line == "}" {
+            break;
+        } else if in_exceptions {
+            if let Some((_, host)) = line.split_once(" : ") {
+                bypass.push(host.trim());
+            }
+        }
+    }
+    Some((proxy, Some(bypass.join(","))))
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn system_proxy() -> Option<(String, Option<String>)> {
+    None
+}
+
+fn current(app_handle: &tauri::AppHandle) -> NetworkSettings {
+    app_handle.state::<SettingsStore>().settings.lock().unwrap().network.clone()
+}
+
+/// Where connections go. May read the proxy password from the OS credential
+/// store, so call it off the async runtime.
+fn resolve(settings: &NetworkSettings) -> Result<Route, String> {
+    match &settings.proxy {
+        ProxyConfig::None => Ok(Route::Direct),
+        ProxyConfig::System => {
+            if PROXY_VARS.iter().any(|var| std::env::var_os(var).is_some_and(|v| !v.is_empty())) {
+                return Ok(Route::Environment);
+            }
+            let Some((proxy, bypass)) = system_proxy() else {
+                return Ok(Route::Environment);
+            };
+            let url = parse_proxy_url(&proxy).map_err(|e| format!("System proxy setting is unusable: {}", e))?;
+            Ok(Route::Proxy {
+                url,
+                auth: None,
+                no_proxy: with_loopback(bypass.as_deref()),
+            })
+        }
+        ProxyConfig::Manual {
+            url,
+            username,
+            no_proxy,
+        } => {
+            let auth = match username.as_deref().filter(|name| !name.is_empty()) {
+                Some(name) => {
+                    let password = crate::credentials::load_app_secret(PROXY_PASSWORD_SECRET)?.unwrap_or_default();
+                    Some((name.to_string(), password))
+                }
+                None => None,
+            };
+            Ok(Route::Proxy {
+                url: parse_proxy_url(url)?,
+                auth,
+                no_proxy: with_loopback(no_proxy.as_deref()),
+            })
+        }
+    }
+}
+
+fn ca_certificates(path: &str) -> Result<Vec<reqwest::Certificate>, String> {
+    let pem = std::fs::read(path).map_err(|e| format!("Failed to read CA bundle {}: {}", path, e))?;
+    let certificates =
+        reqwest::Certificate::from_pem_bundle(&pem).map_err(|e| format!("Invalid CA bundle {}: {}", path, e))?;
+    if certificates.is_empty() {
+        return Err(format!("No certificates found in CA bundle {}", path));
+    }
+    Ok(certificates)
+}
+
+fn build(settings: &NetworkSettings, route: &Route) -> Result<reqwest::ClientBuilder, String> {
+    let mut builder = reqwest::Client::builder();
+    match route {
+        Route::Environment => {}
+        Route::Direct => builder = builder.no_proxy(),
+        Route::Proxy { url, auth, no_proxy } => {
+            let mut proxy = reqwest::Proxy::all(url.as_str())
+                .map_err(|e| format!("Invalid proxy URL {}: {}", url, e))?
+                .no_proxy(reqwest::NoProxy::from_string(no_proxy));
+            if let Some((username, password)) = auth {
+                proxy = proxy.basic_auth(username, password);
+            }
+            builder = builder.proxy(proxy);
+        }
+    }
+
+    if let Some(path) = &settings.ca_bundle {
+        for certificate in ca_certificates(path)? {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+    Ok(builder)
+}
+
+/// A client builder for connections to Cribl with the saved proxy and CA
+/// settings. Blocks on the credential store; call it off the async runtime.
+pub fn client_builder(app_handle: &tauri::AppHandle) -> Result<reqwest::ClientBuilder, String> {
+    let settings = current(app_handle);
+    build(&settings, &resolve(&settings)?)
+}
+
+/// Passes the proxy and CA settings to the backend through its environment.
+pub fn apply(app_handle: &tauri::AppHandle, command: &mut Command) -> Result<(), String> {
+    let settings = current(app_handle);
+    match resolve(&settings)? {
+        Route::Environment => {}
+        Route::Direct => {
+            for var in PROXY_VARS {
+                command.env_remove(var);
+            }
+            command.env("NO_PROXY", "*").env("no_proxy", "*");
+        }
+        Route::Proxy { mut url, auth, no_proxy } => {
+            if let Some((username, password)) = &auth {
+                // Url percent-encodes both, which is what clients expect
+                let _ = url.set_username(username);
+                let _ = url.set_password(Some(password));
+            }
+            for var in PROXY_VARS {
+                command.env(var, url.as_str());
+            }
+            command.env("NO_PROXY", &no_proxy).env("no_proxy", &no_proxy);
+        }
+    }
+
+    if let Some(path) = &settings.ca_bundle {
+        command.env("SSL_CERT_FILE", path).env("REQUESTS_CA_BUNDLE", path);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_network_settings(app_handle: tauri::AppHandle) -> NetworkSettings {
+    current(&app_handle)
+}
+
+/// Checks the proxy URL and CA bundle, dropping a blank bundle path.
+pub fn validate(network: &mut NetworkSettings) -> Result<(), String> {
+    if let ProxyConfig::Manual { url, .. } = &network.proxy {
+        parse_proxy_url(url)?;
+    }
+    network.ca_bundle = network.ca_bundle.take().filter(|path| !path.trim().is_empty());
+    if let Some(path) = &network.ca_bundle {
+        ca_certificates(path)?;
+    }
+    Ok(())
+}
+
+/// Saves the proxy and CA settings; the backend picks them up when it next starts.
+/// `password` replaces the saved proxy password, an empty one removes it and
+/// `None` keeps it.
+#[tauri::command]
+pub async fn set_proxy_config(
+    app_handle: tauri::AppHandle,
+    network: NetworkSettings,
+    password: Option<String>,
+) -> Result<NetworkSettings, String> {
+    let mut network = network;
+    validate(&mut network)?;
+
+    match password.as_deref() {
+        Some("") => crate::credentials::store_app_secret(PROXY_PASSWORD_SECRET, None)?,
+        Some(password) => crate::credentials::store_app_secret(PROXY_PASSWORD_SECRET, Some(password))?,
+        None => {}
+    }
+
+    let store = app_handle.state::<SettingsStore>();
+    let mut current = store.settings.lock().unwrap();
+    current.network = network;
+    settings::save(&app_handle, &current)?;
+    Ok(current.network.clone())
+}
+
+#[derive(Serialize)]
+pub struct ConnectionTest {
+    pub url: String,
+    /// How the request was sent: directly or through which proxy.
+    pub route: String,
+    /// Whether any HTTP response came back, whatever its status.
+    pub reachable: bool,
+    pub status: Option<u16>,
+    pub latency_ms: Option<f64>,
+    pub error: Option<String>,
+}
+
+/// The error and everything that caused it, which is where TLS and proxy
+/// problems are actually described.
+fn describe(error: &reqwest::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = std::error::Error::source(error);
+    while let Some(cause) = source {
+        message = format!("{}: {}", message, cause);
+        source = cause.source();
+    }
+    if message.to_ascii_lowercase().contains("certificate") {
+        message.push_str(" (if a proxy inspects TLS, add your organization's CA bundle)");
+    }
+    message
+}
+
+/// Requests `url` with the saved network settings, as the backend would.
+#[tauri::command]
+pub async fn test_connection(app_handle: tauri::AppHandle, url: String) -> Result<ConnectionTest, String> {
+    let target = url::Url::parse(&url).map_err(|e| format!("Invalid URL {}: {}", url, e))?;
+    if !matches!(target.scheme(), "http" | "https") {
+        return Err(format!("Only http:// and https:// URLs can be tested: {}", url));
+    }
+
+    let (builder, route) = tauri::async_runtime::spawn_blocking(move || {
+        let settings = current(&app_handle);
+        let route = resolve(&settings)?;
+        Ok::<_, String>((build(&settings, &route)?, route.describe()))
+    })
+    .await
+    .map_err(|e| format!("Failed to prepare connection test: {}", e))??;
+    let client = builder
+        .timeout(TEST_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let started = Instant::now();
+    let result = client.get(target).send().await;
+    let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+    Ok(match result {
+        Ok(response) => ConnectionTest {
+            url,
+            route,
+            reachable: true,
+            status: Some(response.status().as_u16()),
+            latency_ms: Some(latency_ms),
+            error: None,
+        },
+        Err(e) => ConnectionTest {
+            url,
+            route,
+            reachable: false,
+            status: None,
+            latency_ms: None,
+            error: Some(describe(&e)),
+        },
+    })
+}
@@ -0,0 +1,158 @@
+//! Native desktop notifications for finished analyses and backend crashes, each
+//! category switchable in settings.
+//!
+//! Raised through each platform's own tool rather than a plugin: `osascript` on
+//! macOS, a WinRT toast through PowerShell on Windows and `notify-send` on Linux.
+//! Title and body are passed as arguments or environment, never spliced into a
+//! script, so they need no escaping.
+
+use serde::{Deserialize, Serialize};
+use std::process::{Command, Stdio};
+use tauri::Manager;
+
+use crate::settings::{self, SettingsStore};
+
+#[cfg(target_os = "macos")]
+fn command(_app_handle: &tauri::AppHandle, title: &str, body: &str) -> Command {
+    let mut command = Command::new("osascript");
+    command
+        .args(["-e", "on run argv"])
+        .args(["-e", "display notification (item 2 of argv) with title (item 1 of argv)"])
+        .args(["-e", "end run"])
+        .args([title, body]);
+    command
+}
+
+#[cfg(windows)]
+const TOAST_SCRIPT: &str = r#"
+[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] > $null
+$template = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02)
+$text = $template.GetElementsByTagName('text')
+$text.Item(0).AppendChild($template.CreateTextNode($env:CRIBL_HC_NOTIFY_TITLE)) > $null
+$text.Item(1).AppendChild($template.CreateTextNode($env:CRIBL_HC_NOTIFY_BODY)) > $null
+$toast = [Windows.UI.Notifications.ToastNotification]::new($template)
+[Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier($env:CRIBL_HC_NOTIFY_APP_ID).Show($toast)
+"#;
+
+#[cfg(windows)]
+fn command(app_handle: &tauri::AppHandle, title: &str, body: &str) -> Command {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let mut command = Command::new("powershell.exe");
+    command
+        .args(["-NoProfile", "-NonInteractive", "-Command", TOAST_SCRIPT])
+        // The installer registers the bundle identifier as the app's AppUserModelID
+        .env("CRIBL_HC_NOTIFY_APP_ID", &app_handle.config().identifier)
+        .env("CRIBL_HC_NOTIFY_TITLE", title)
+        .env("CRIBL_HC_NOTIFY_BODY", body)
+        .creation_flags(CREATE_NO_WINDOW);
+    command
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn command(app_handle: &tauri::AppHandle, title: &str, body: &str) -> Command {
+    let mut command = Command::new("notify-send");
+    command
+        .arg("--app-name")
+        .arg(app_handle.package_info().name.as_str())
+        .args([title, body]);
+    command
+}
+
+/// Shows a notification without waiting for it. Failures are only logged: a
+/// missing notification daemon shouldn't break whatever raised it.
+pub fn show(app_handle: &tauri::AppHandle, title: &str, body: &str) {
+    let mut command = command(app_handle, title, body);
+    command.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+
+    std::thread::spawn(move || match command.status() {
+        Ok(status) if status.success() => {}
+        Ok(status) => log::warn!("Notification command exited with {}", status),
+        Err(e) => log::warn!("Failed to show notification: {}", e),
+    });
+}
+
+/// Which events raise a notification; all of them by default.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationSettings {
+    pub analysis_complete: bool,
+    pub analysis_failed: bool,
+    pub backend_crashed: bool,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            analysis_complete: true,
+            analysis_failed: true,
+            backend_crashed: true,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationCategory {
+    AnalysisComplete,
+    AnalysisFailed,
+    BackendCrashed,
+}
+
+impl NotificationSettings {
+    fn enabled(&mut self, category: NotificationCategory) -> &mut bool {
+        match category {
+            NotificationCategory::AnalysisComplete => &mut self.analysis_complete,
+            NotificationCategory::AnalysisFailed => &mut self.analysis_failed,
+            NotificationCategory::BackendCrashed => &mut self.backend_crashed,
+        }
+    }
+}
+
+/// Shows a notification for `category` if it is turned on and the user is in
+/// another app; with the main window focused the UI already shows it.
+pub fn notify(app_handle: &tauri::AppHandle, category: NotificationCategory, title: &str, body: &str) {
+    let mut settings = app_handle
+        .state::<SettingsStore>()
+        .settings
+        .lock()
+        .unwrap()
+        .notifications
+        .clone();
+    if !*settings.enabled(category) {
+        return;
+    }
+
+    let focused = app_handle
+        .get_webview_window("main")
+        .and_then(|window| window.is_focused().ok())
+        .unwrap_or(false);
+    if !focused {
+        show(app_handle, title, body);
+    }
+}
+
+#[tauri::command]
+pub fn get_notification_settings(app_handle: tauri::AppHandle) -> NotificationSettings {
+    app_handle
+        .state::<SettingsStore>()
+        .settings
+        .lock()
+        .unwrap()
+        .notifications
+        .clone()
+}
+
+#[tauri::command]
+pub fn set_notification_enabled(
+    app_handle: tauri::AppHandle,
+    category: NotificationCategory,
+    enabled: bool,
+) -> Result<NotificationSettings, String> {
+    let store = app_handle.state::<SettingsStore>();
+    let mut current = store.settings.lock().unwrap();
+    *current.notifications.enabled(category) = enabled;
+    settings::save(&app_handle, &current)?;
+    Ok(current.notifications.clone())
+}
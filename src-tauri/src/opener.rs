@@ -0,0 +1,226 @@
+use std::ffi::OsStr;
+use std::path::Path;
+use std::process::Command;
+use tauri::Manager;
+
+use crate::settings::SettingsStore;
+
+/// Extensions the OS would run rather than display.
+const EXECUTABLE_EXTENSIONS: &[&str] = &[
+    "app", "appimage", "bat", "cmd", "com", "command", "cpl", "desktop", "exe", "hta", "jar",
+    "lnk", "msi", "pif", "ps1", "reg", "scr", "sh", "url", "vbe", "vbs", "ws", "wsf",
+];
+
+fn is_executable(path: &Path) -> bool {
+    let by_extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| EXECUTABLE_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()));
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let by_mode = std::fs::metadata(path).is_ok_and(|m| m.permissions().mode() & 0o111 != 0);
+        by_extension || by_mode
+    }
+
+    #[cfg(not(unix))]
+    {
+        by_extension
+    }
+}
+
+/// Documentation and support links in findings point here (or at a subdomain).
+const TRUSTED_DOMAINS: &[&str] = &["cribl.io"];
+
+/// Hands the file (or URL) to the desktop's default handler.
+fn launch(target: impl AsRef<OsStr>) -> Result<(), String> {
+    let target = target.as_ref();
+    #[cfg(target_os = "macos")]
+    let mut command = Command::new("open");
+    #[cfg(target_os = "windows")]
+    let mut command = Command::new("explorer");
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let mut command = Command::new("xdg-open");
+
+    command.arg(target);
+
+    // explorer's exit code is meaningless, so only check the ones that report a missing handler
+    if cfg!(target_os = "windows") {
+        command
+            .spawn()
+            .map_err(|e| format!("Failed to open file: {}", e))?;
+        return Ok(());
+    }
+
+    let output = command.output().map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => "No default application handler is available".to_string(),
+        _ => format!("Failed to open file: {}", e),
+    })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "No application is available to open {}: {}",
+            target.to_string_lossy(),
+            stderr.trim()
+        ));
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn open_file_with_default_app(path: String) -> Result<(), String> {
+    let path = Path::new(&path);
+
+    if !path.is_file() {
+        return Err(format!("File not found: {}", path.display()));
+    }
+    if is_executable(path) {
+        return Err(format!("Refusing to open executable file: {}", path.display()));
+    }
+
+    launch(path)
+}
+
+/// Hosts of every saved leader, from credentials and profiles.
+fn leader_hosts(app_handle: &tauri::AppHandle) -> Vec<String> {
+    let mut urls: Vec<String> = app_handle
+        .state::<SettingsStore>()
+        .settings
+        .lock()
+        .unwrap()
+        .credentials
+        .iter()
+        .map(|c| c.url.clone())
+        .collect();
+    urls.extend(crate::profiles::list_profiles(app_handle.clone()).profiles.into_iter().map(|p| p.url));
+    urls.iter()
+        .filter_map(|u| url::Url::parse(u).ok()?.host_str().map(str::to_ascii_lowercase))
+        .collect()
+}
+
+/// Checks `url` is one the app may hand to the browser: https on cribl.io, or
+/// http(s) on a leader the user has configured. Anything else could come from a
+/// crafted report file, so it is refused rather than opened.
+fn vet_external_url(app_handle: &tauri::AppHandle, url: &str) -> Result<url::Url, String> {
+    let parsed = url::Url::parse(url.trim()).map_err(|e| format!("Invalid URL {:?}: {}", url, e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!("Refusing to open a {} link", parsed.scheme()));
+    }
+    if !parsed.username().is_empty() || parsed.password().is_some() {
+        return Err("Refusing to open a link with credentials in it".to_string());
+    }
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| format!("Link has no host: {}", url))?
+        .trim_end_matches('.')
+        .to_ascii_lowercase();
+
+    let trusted = TRUSTED_DOMAINS
+        .iter()
+        .any(|domain| host == *domain || host.ends_with(&format!(".{}", domain)));
+    if trusted && parsed.scheme() == "https" {
+        return Ok(parsed);
+    }
+    if leader_hosts(app_handle).contains(&host) {
+        return Ok(parsed);
+    }
+    Err(format!("Refusing to open a link to {}, which isn't cribl.io or a configured leader", host))
+}
+
+/// Opens a finding's documentation or remediation link in the default browser,
+/// once `vet_external_url` has allowed it.
+#[tauri::command]
+pub async fn open_external_url(app_handle: tauri::AppHandle, url: String) -> Result<(), String> {
+    let vetted = vet_external_url(&app_handle, &url).map_err(|e| {
+        log::warn!("{}", e);
+        e
+    })?;
+    tauri::async_runtime::spawn_blocking(move || launch(vetted.as_str()))
+        .await
+        .map_err(|e| format!("Failed to open link: {}", e))?
+}
+
+/// Opens `dir` in the file manager.
+pub fn open_folder(dir: &Path) -> Result<(), String> {
+    if !dir.is_dir() {
+        return Err(format!("Folder not found: {}", dir.display()));
+    }
+    launch(dir)
+}
+
+/// Opens the folder holding `path` with the file selected. Linux file managers
+/// that don't implement the freedesktop `ShowItems` call just open the folder.
+fn reveal(path: &Path) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        let status = Command::new("open")
+            .arg("-R")
+            .arg(path)
+            .status()
+            .map_err(|e| format!("Failed to reveal file: {}", e))?;
+        if !status.success() {
+            return Err(format!("Failed to reveal {}", path.display()));
+        }
+        Ok(())
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        // explorer wants `/select,` and the path as one argument, which normal quoting breaks
+        Command::new("explorer")
+            .raw_arg(format!("/select,\"{}\"", path.display()))
+            .spawn()
+            .map_err(|e| format!("Failed to reveal file: {}", e))?;
+        Ok(())
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        let uri = url::Url::from_file_path(path).map_err(|_| format!("Failed to build a URL for {}", path.display()))?;
+        let shown = Command::new("dbus-send")
+            .args([
+                "--session",
+                "--dest=org.freedesktop.FileManager1",
+                "--type=method_call",
+                "--reply-timeout=2000",
+                "/org/freedesktop/FileManager1",
+                "org.freedesktop.FileManager1.ShowItems",
+            ])
+            .arg(format!("array:string:{}", uri))
+            .arg("string:")
+            .output()
+            .is_ok_and(|output| output.status.success());
+        if shown {
+            return Ok(());
+        }
+        let dir = path.parent().ok_or_else(|| format!("{} has no parent folder", path.display()))?;
+        launch(dir)
+    }
+}
+
+#[tauri::command]
+pub async fn reveal_file_in_folder(path: String) -> Result<(), String> {
+    let path = std::path::PathBuf::from(path);
+    if !path.exists() {
+        return Err(format!("File not found: {}", path.display()));
+    }
+    let path = path.canonicalize().unwrap_or(path);
+    tauri::async_runtime::spawn_blocking(move || reveal(&path))
+        .await
+        .map_err(|e| format!("Failed to reveal file: {}", e))?
+}
+
+/// Opens the export directory from settings, or Downloads when none is set.
+#[tauri::command]
+pub async fn open_export_folder(app_handle: tauri::AppHandle) -> Result<String, String> {
+    let dir = crate::settings::export_folder(&app_handle)?;
+    let opened = dir.clone();
+    tauri::async_runtime::spawn_blocking(move || open_folder(&opened))
+        .await
+        .map_err(|e| format!("Failed to open export folder: {}", e))??;
+    Ok(dir.to_string_lossy().to_string())
+}
@@ -0,0 +1,356 @@
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Child;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+
+use crate::settings::{self, LogBufferLimits, SettingsStore};
+
+const ELLIPSIS: &str = "…";
+/// Backend output in the app log is filed under this target.
+const BACKEND_LOG_TARGET: &str = concat!(env!("CARGO_CRATE_NAME"), "::backend");
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+#[derive(Clone, Serialize)]
+pub struct OutputLine {
+    pub stream: Stream,
+    pub line: String,
+}
+
+/// A file mirroring backend output, reopened if it is rotated or deleted underneath us.
+struct LogFile {
+    path: PathBuf,
+    file: File,
+}
+
+impl LogFile {
+    fn open(path: &Path) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+        })
+    }
+
+    fn is_stale(&self) -> bool {
+        let Ok(on_disk) = fs::metadata(&self.path) else {
+            return true;
+        };
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            self.file.metadata().map_or(true, |open| open.ino() != on_disk.ino())
+        }
+
+        #[cfg(not(unix))]
+        {
+            let _ = on_disk;
+            false
+        }
+    }
+
+    fn append(&mut self, line: &str) -> std::io::Result<()> {
+        if self.is_stale() {
+            *self = Self::open(&self.path)?;
+        }
+
+        writeln!(self.file, "{}", line)?;
+        self.file.flush()
+    }
+}
+
+/// Recent backend output, bounded by line count and total size.
+#[derive(Default)]
+struct LogBuffer {
+    lines: VecDeque<OutputLine>,
+    bytes: usize,
+    limits: LogBufferLimits,
+}
+
+impl LogBuffer {
+    fn push(&mut self, stream: Stream, line: &str) {
+        let line = truncate_line(line, self.limits.max_line_bytes);
+        self.bytes += line.len();
+        self.lines.push_back(OutputLine { stream, line });
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        while self.lines.len() > self.limits.max_lines || self.bytes > self.limits.max_bytes {
+            let Some(oldest) = self.lines.pop_front() else {
+                break;
+            };
+            self.bytes -= oldest.line.len();
+        }
+    }
+
+    fn set_limits(&mut self, limits: LogBufferLimits) {
+        self.limits = limits;
+        self.evict();
+    }
+}
+
+/// Cuts `line` to at most `max_bytes` (ellipsis included) on a character boundary.
+fn truncate_line(line: &str, max_bytes: usize) -> String {
+    if line.len() <= max_bytes {
+        return line.to_string();
+    }
+
+    let mut end = max_bytes.saturating_sub(ELLIPSIS.len());
+    while !line.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}{}", &line[..end], ELLIPSIS)
+}
+
+#[derive(Default)]
+pub struct OutputState {
+    log_file: Mutex<Option<LogFile>>,
+    buffer: Mutex<LogBuffer>,
+    /// Parse JSON log lines into `backend-event`s instead of treating them as text.
+    json_events: AtomicBool,
+}
+
+/// A structured log record from the backend.
+#[derive(Clone, Serialize)]
+pub struct BackendEvent {
+    stream: Stream,
+    level: Option<String>,
+    message: Option<String>,
+    component: Option<String>,
+}
+
+/// Accepts the key names used by Python's logging and by structlog.
+fn parse_event(stream: Stream, line: &str) -> Option<BackendEvent> {
+    let serde_json::Value::Object(fields) = serde_json::from_str(line).ok()? else {
+        return None;
+    };
+
+    let field = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| fields.get(*key).and_then(|v| v.as_str()))
+            .map(str::to_string)
+    };
+
+    Some(BackendEvent {
+        stream,
+        level: field(&["level", "levelname"]),
+        message: field(&["message", "msg", "event"]),
+        component: field(&["component", "logger", "name"]),
+    })
+}
+
+/// Logs, buffers and emits one line of backend output.
+fn relay(app_handle: &tauri::AppHandle, output: &OutputLine) {
+    let state = app_handle.state::<OutputState>();
+    let event = if state.json_events.load(Ordering::Relaxed) {
+        parse_event(output.stream, &output.line)
+    } else {
+        None
+    };
+    if let Some(event) = event {
+        if let Err(e) = app_handle.emit("backend-event", event) {
+            log::warn!("Failed to emit backend-event: {}", e);
+        }
+    }
+    log::info!(target: BACKEND_LOG_TARGET, "{:?}: {}", output.stream, output.line);
+
+    state.buffer.lock().unwrap().push(output.stream, &output.line);
+    if let Err(e) = app_handle.emit("backend-log", output.clone()) {
+        log::warn!("Failed to emit backend-log: {}", e);
+    }
+
+    if let Some(log_file) = state.log_file.lock().unwrap().as_mut() {
+        if let Err(e) = log_file.append(&output.line) {
+            log::warn!("Failed to write backend log file {}: {}", log_file.path.display(), e);
+        }
+    };
+}
+
+fn spawn_reader(
+    pipe: impl Read + Send + 'static,
+    stream: Stream,
+    sender: Sender<OutputLine>,
+    on_line: impl Fn(&OutputLine) + Send + 'static,
+) {
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(pipe);
+        let mut raw = Vec::new();
+
+        // Read bytes rather than `lines()`: one line that isn't UTF-8 (e.g. a path printed in a
+        // legacy code page) would otherwise end the loop and stop draining the pipe.
+        while matches!(reader.read_until(b'\n', &mut raw), Ok(n) if n > 0) {
+            let line = String::from_utf8_lossy(&raw).trim_end_matches(['\r', '\n']).to_string();
+            raw.clear();
+            let output = OutputLine { stream, line };
+            on_line(&output);
+
+            // Nobody is listening once the handshake is done; keep draining the pipe anyway
+            // so the backend never blocks on a full buffer.
+            let _ = sender.send(output);
+        }
+    });
+}
+
+/// Reads both of the child's output pipes, passing each line to `on_line` and
+/// merging them into one channel.
+fn read_pipes(
+    child: &mut Child,
+    on_line: impl Fn(&OutputLine) + Clone + Send + 'static,
+) -> Result<Receiver<OutputLine>, String> {
+    let stdout = child.stdout.take().ok_or("Failed to capture stdout")?;
+    let stderr = child.stderr.take().ok_or("Failed to capture stderr")?;
+
+    let (sender, receiver) = mpsc::channel();
+    spawn_reader(stdout, Stream::Stdout, sender.clone(), on_line.clone());
+    spawn_reader(stderr, Stream::Stderr, sender, on_line);
+    Ok(receiver)
+}
+
+/// Starts reading both of the child's output pipes, merging their lines into one channel.
+pub fn spawn_readers(app_handle: &tauri::AppHandle, child: &mut Child) -> Result<Receiver<OutputLine>, String> {
+    let limits = settings::backend(app_handle).log_buffer;
+    app_handle.state::<OutputState>().buffer.lock().unwrap().set_limits(limits);
+
+    let app_handle = app_handle.clone();
+    read_pipes(child, move |output| relay(&app_handle, output))
+}
+
+/// Mirrors every backend output line into `path`, appending and flushing per line.
+#[tauri::command]
+pub fn enable_backend_log_file(app_handle: tauri::AppHandle, path: String) -> Result<(), String> {
+    let log_file = LogFile::open(Path::new(&path))
+        .map_err(|e| format!("Failed to open log file: {}", e))?;
+
+    *app_handle.state::<OutputState>().log_file.lock().unwrap() = Some(log_file);
+    Ok(())
+}
+
+/// Returns whether a log file was active.
+#[tauri::command]
+pub fn disable_backend_log_file(app_handle: tauri::AppHandle) -> bool {
+    app_handle.state::<OutputState>().log_file.lock().unwrap().take().is_some()
+}
+
+/// The file backend output is being mirrored to, if any.
+pub fn log_file_path(app_handle: &tauri::AppHandle) -> Option<PathBuf> {
+    let state = app_handle.state::<OutputState>();
+    let log_file = state.log_file.lock().unwrap();
+    log_file.as_ref().map(|f| f.path.clone())
+}
+
+/// Toggles parsing of JSON log lines into `backend-event`s; other lines are logged as before.
+#[tauri::command]
+pub fn set_backend_json_events(app_handle: tauri::AppHandle, enabled: bool) {
+    app_handle.state::<OutputState>().json_events.store(enabled, Ordering::Relaxed);
+}
+
+/// Buffered backend output, oldest first.
+#[tauri::command]
+pub fn get_backend_log_buffer(app_handle: tauri::AppHandle) -> Vec<OutputLine> {
+    let state = app_handle.state::<OutputState>();
+    let buffer = state.buffer.lock().unwrap();
+    buffer.lines.iter().cloned().collect()
+}
+
+/// The last `tail_lines` buffered lines (all of them when unset), oldest first.
+/// Lines after these arrive as `backend-log` events.
+#[tauri::command]
+pub fn get_backend_logs(app_handle: tauri::AppHandle, tail_lines: Option<usize>) -> Vec<OutputLine> {
+    let state = app_handle.state::<OutputState>();
+    let buffer = state.buffer.lock().unwrap();
+    let skip = tail_lines.map_or(0, |n| buffer.lines.len().saturating_sub(n));
+    buffer.lines.iter().skip(skip).cloned().collect()
+}
+
+/// Persists new buffer limits and applies them right away, evicting as needed.
+#[tauri::command]
+pub fn set_backend_log_limits(app_handle: tauri::AppHandle, limits: LogBufferLimits) -> Result<(), String> {
+    if limits.max_lines == 0 || limits.max_bytes == 0 || limits.max_line_bytes <= ELLIPSIS.len() {
+        return Err("Log buffer limits must leave room for at least one line".to_string());
+    }
+
+    {
+        let store = app_handle.state::<SettingsStore>();
+        let mut current = store.settings.lock().unwrap();
+        current.backend.log_buffer = limits;
+        settings::save(&app_handle, &current)?;
+    }
+
+    app_handle.state::<OutputState>().buffer.lock().unwrap().set_limits(limits);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A backend installed under a path with spaces and non-ASCII characters.
+    #[cfg(unix)]
+    #[test]
+    fn spawns_and_reads_a_sidecar_at_an_awkward_path() {
+        use std::os::unix::fs::PermissionsExt;
+        use std::process::{Command, Stdio};
+        use std::sync::Arc;
+
+        let dir = std::env::temp_dir().join(format!("cribl hc tëst {}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let sidecar = dir.join("bäck end ✓");
+        let script = "#!/bin/sh\n\
+            for arg in \"$@\"; do printf 'arg:%s\\n' \"$arg\"; done\n\
+            printf 'PORT:%s\\n' \"$2\"\n\
+            printf 'caf\\351 in Latin-1\\n'\n\
+            echo 'Grüße from stderr' >&2\n";
+        fs::write(&sidecar, script).unwrap();
+        fs::set_permissions(&sidecar, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let socket = dir.join("sö cket.sock");
+        let mut child = Command::new(&sidecar)
+            .args(["--port", "0", "--host", "127.0.0.1", "--socket"])
+            .arg(&socket)
+            .current_dir(&dir)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let relayed = Arc::new(Mutex::new(0));
+        let counter = relayed.clone();
+        let lines = read_pipes(&mut child, move |_| *counter.lock().unwrap() += 1).unwrap();
+        // Both readers hang up at EOF, ending the iterator
+        let output: Vec<OutputLine> = lines.iter().collect();
+        assert!(child.wait().unwrap().success());
+        fs::remove_dir_all(&dir).unwrap();
+
+        let from = |stream: Stream| -> Vec<String> {
+            output.iter().filter(|o| o.stream == stream).map(|o| o.line.clone()).collect()
+        };
+        assert_eq!(
+            from(Stream::Stdout),
+            [
+                "arg:--port".to_string(),
+                "arg:0".to_string(),
+                "arg:--host".to_string(),
+                "arg:127.0.0.1".to_string(),
+                "arg:--socket".to_string(),
+                format!("arg:{}", socket.display()),
+                "PORT:0".to_string(),
+                "caf\u{FFFD} in Latin-1".to_string(),
+            ]
+        );
+        assert_eq!(from(Stream::Stderr), ["Grüße from stderr"]);
+        assert_eq!(*relayed.lock().unwrap(), output.len());
+    }
+}
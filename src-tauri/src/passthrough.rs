@@ -0,0 +1,93 @@
+//! Extra arguments, environment variables and a data directory for the backend,
+//! from the `backend` settings, for tuning it without editing packaged files.
+//!
+//! They are checked when saved and again at each launch, since the settings file
+//! can be edited by hand: arguments can't take over what the app itself passes
+//! (`--port`, `--host`, `--socket`, `--workers`), and variables can't replace the
+//! tokens the app uses to talk to the backend. Each launch logs what was added;
+//! variable values are left out of the log, as they may be secrets.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::settings::BackendSettings;
+
+/// Tells the backend where its data directory is.
+pub const DATA_DIR_VAR: &str = "CRIBL_HC_DATA_DIR";
+/// Set by the app itself on every launch.
+const MANAGED_ARGS: &[&str] = &["--port", "--host", "--socket", "--workers"];
+const MANAGED_VARS: &[&str] = &[crate::SHUTDOWN_TOKEN_VAR, crate::gateway::TOKEN_VAR, DATA_DIR_VAR];
+
+fn validate_arg(arg: &str) -> Result<(), String> {
+    if arg.contains('\0') {
+        return Err(format!("Backend argument {:?} contains a NUL byte", arg));
+    }
+    let flag = arg.split('=').next().unwrap_or_default();
+    if MANAGED_ARGS.contains(&flag) {
+        return Err(format!("{} is set by the app; use the backend settings for it instead", flag));
+    }
+    Ok(())
+}
+
+fn validate_var(name: &str, value: &str) -> Result<(), String> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') || name.starts_with(|c: char| c.is_ascii_digit()) {
+        return Err(format!("Invalid backend environment variable name {:?}", name));
+    }
+    if MANAGED_VARS.iter().any(|managed| managed.eq_ignore_ascii_case(name)) {
+        return Err(format!("{} is set by the app and can't be overridden", name));
+    }
+    if value.contains('\0') {
+        return Err(format!("Backend environment variable {} contains a NUL byte", name));
+    }
+    Ok(())
+}
+
+/// Checks the extra arguments, variables and data directory in `settings`.
+pub fn validate(settings: &BackendSettings) -> Result<(), String> {
+    for arg in &settings.extra_args {
+        validate_arg(arg)?;
+    }
+    for (name, value) in &settings.env {
+        validate_var(name, value)?;
+    }
+    if let Some(dir) = &settings.data_dir {
+        if !Path::new(dir).is_absolute() {
+            return Err(format!("Backend data directory must be an absolute path: {}", dir));
+        }
+    }
+    Ok(())
+}
+
+/// The configured data directory, created if missing.
+pub fn data_dir(settings: &BackendSettings) -> Result<Option<PathBuf>, String> {
+    let Some(dir) = &settings.data_dir else {
+        return Ok(None);
+    };
+    let dir = PathBuf::from(dir);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create backend data dir {}: {}", dir.display(), e))?;
+    Ok(Some(dir))
+}
+
+/// Adds the extra arguments, variables and data directory to `command`, after
+/// what the app passes itself, and logs what was added.
+pub fn apply(command: &mut Command, settings: &BackendSettings) -> Result<(), String> {
+    validate(settings)?;
+    let mut added = Vec::new();
+    if !settings.extra_args.is_empty() {
+        command.args(&settings.extra_args);
+        added.push(format!("arguments {:?}", settings.extra_args));
+    }
+    if !settings.env.is_empty() {
+        command.envs(&settings.env);
+        let names: Vec<&str> = settings.env.keys().map(String::as_str).collect();
+        added.push(format!("environment {}", names.join(", ")));
+    }
+    if let Some(dir) = data_dir(settings)? {
+        command.env(DATA_DIR_VAR, &dir);
+        added.push(format!("data dir {}", dir.display()));
+    }
+    if !added.is_empty() {
+        log::info!("Backend overrides from settings: {}", added.join("; "));
+    }
+    Ok(())
+}
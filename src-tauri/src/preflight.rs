@@ -0,0 +1,526 @@
+//! Connectivity checks against a deployment's leader, run before an analysis so
+//! a failure can be pinned on the network rather than on the analysis.
+//!
+//! `run_preflight` resolves the leader's host, opens a TCP connection, completes
+//! a TLS handshake (recording the certificate chain and its expiry), checks the
+//! saved token and asks the API for its version. Each step depends on the one
+//! before, so after a failure the rest are reported as skipped. The DNS, TCP and
+//! TLS steps take the direct path; the auth and version requests go through the
+//! saved proxy and CA settings, as the backend's would.
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use reqwest::header::ACCEPT;
+use reqwest::StatusCode;
+use serde::Serialize;
+use serde_json::Value;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::Manager;
+use tokio_rustls::rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use tokio_rustls::rustls::client::WebPkiServerVerifier;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use tokio_rustls::rustls::{self, DigitallySignedStruct, RootCertStore, SignatureScheme};
+
+use crate::settings::SettingsStore;
+
+const STEP_TIMEOUT: Duration = Duration::from_secs(10);
+/// A leaf certificate this close to expiring passes with a warning.
+const EXPIRY_WARNING_DAYS: i64 = 30;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Pass,
+    /// Passed, but with something worth fixing, like a certificate about to expire.
+    Warn,
+    Fail,
+    /// Not run because an earlier step failed.
+    Skipped,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CertificateInfo {
+    pub subject: String,
+    pub issuer: String,
+    pub not_before: Option<String>,
+    pub not_after: Option<String>,
+    /// Negative once the certificate has expired.
+    pub days_until_expiry: Option<i64>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PreflightStep {
+    /// `dns`, `tcp`, `tls`, `auth` or `api_version`.
+    pub step: &'static str,
+    pub status: StepStatus,
+    pub duration_ms: Option<f64>,
+    pub detail: String,
+    /// The chain the leader presented, leaf first; only for `tls`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub certificates: Vec<CertificateInfo>,
+}
+
+#[derive(Serialize)]
+pub struct PreflightReport {
+    pub deployment: String,
+    pub url: String,
+    /// True when no step failed; warnings still count as passing.
+    pub passed: bool,
+    pub steps: Vec<PreflightStep>,
+}
+
+struct Outcome {
+    status: StepStatus,
+    detail: String,
+    certificates: Vec<CertificateInfo>,
+}
+
+impl Outcome {
+    fn pass(detail: impl Into<String>) -> Self {
+        Self {
+            status: StepStatus::Pass,
+            detail: detail.into(),
+            certificates: Vec::new(),
+        }
+    }
+
+    fn fail(detail: impl Into<String>) -> Self {
+        Self {
+            status: StepStatus::Fail,
+            ..Self::pass(detail)
+        }
+    }
+}
+
+/// Collects the steps, timing each and skipping everything after a failure.
+struct Steps {
+    steps: Vec<PreflightStep>,
+}
+
+impl Steps {
+    fn failed(&self) -> bool {
+        self.steps.iter().any(|s| s.status == StepStatus::Fail)
+    }
+
+    fn record(&mut self, step: &'static str, started: Instant, outcome: Outcome) {
+        self.steps.push(PreflightStep {
+            step,
+            status: outcome.status,
+            duration_ms: Some(started.elapsed().as_secs_f64() * 1000.0),
+            detail: outcome.detail,
+            certificates: outcome.certificates,
+        });
+    }
+
+    fn skip(&mut self, step: &'static str, detail: &str) {
+        self.steps.push(PreflightStep {
+            step,
+            status: StepStatus::Skipped,
+            duration_ms: None,
+            detail: detail.to_string(),
+            certificates: Vec::new(),
+        });
+    }
+}
+
+/// One DER element: its tag, its contents and whatever follows it.
+fn der(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = input.split_first()?;
+    let (&first, mut rest) = rest.split_first()?;
+    let len = if first < 0x80 {
+        first as usize
+    } else {
+        let octets = (first & 0x7f) as usize;
+        if octets == 0 || octets > 4 || rest.len() < octets {
+            return None;
+        }
+        let len = rest[..octets].iter().fold(0usize, |len, &b| (len << 8) | b as usize);
+        rest = &rest[octets..];
+        len
+    };
+    (rest.len() >= len).then(|| (tag, &rest[..len], &rest[len..]))
+}
+
+const OID_COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+const OID_ORGANIZATION: &[u8] = &[0x55, 0x04, 0x0a];
+
+/// The common name of an X.501 name, or its organization when it has none.
+fn name(mut rdns: &[u8]) -> String {
+    let (mut common_name, mut organization) = (None, None);
+    while let Some((_, set, rest)) = der(rdns) {
+        rdns = rest;
+        let Some((_, attribute, _)) = der(set) else { continue };
+        let Some((_, oid, value)) = der(attribute) else { continue };
+        let Some((_, value, _)) = der(value) else { continue };
+        let value = String::from_utf8_lossy(value).into_owned();
+        match oid {
+            OID_COMMON_NAME => common_name = Some(value),
+            OID_ORGANIZATION => organization = Some(value),
+            _ => {}
+        }
+    }
+    common_name.or(organization).unwrap_or_else(|| "(unnamed)".to_string())
+}
+
+/// UTCTime (`YYMMDDHHMMSSZ`, years 1950-2049) or GeneralizedTime.
+fn time(tag: u8, value: &[u8]) -> Option<DateTime<Utc>> {
+    let text = std::str::from_utf8(value).ok()?;
+    let full = match tag {
+        0x17 => format!("{}{}", if text.get(..2)? < "50" { "20" } else { "19" }, text),
+        0x18 => text.to_string(),
+        _ => return None,
+    };
+    NaiveDateTime::parse_from_str(&full, "%Y%m%d%H%M%SZ").ok().map(|t| t.and_utc())
+}
+
+/// Subject, issuer and validity of a DER certificate, without verifying anything.
+fn describe_certificate(certificate: &[u8]) -> CertificateInfo {
+    let fields = || {
+        let (_, certificate, _) = der(certificate)?;
+        let (_, mut tbs, _) = der(certificate)?;
+        // The version is an optional explicit [0] before the serial number
+        if tbs.first() == Some(&0xa0) {
+            tbs = der(tbs)?.2;
+        }
+        let (_, _serial, rest) = der(tbs)?;
+        let (_, _algorithm, rest) = der(rest)?;
+        let (_, issuer, rest) = der(rest)?;
+        let (_, validity, rest) = der(rest)?;
+        let (_, subject, _) = der(rest)?;
+        let (before_tag, before, rest) = der(validity)?;
+        let (after_tag, after, _) = der(rest)?;
+        Some((name(subject), name(issuer), time(before_tag, before), time(after_tag, after)))
+    };
+
+    match fields() {
+        Some((subject, issuer, not_before, not_after)) => CertificateInfo {
+            subject,
+            issuer,
+            not_before: not_before.map(|t| t.to_rfc3339()),
+            days_until_expiry: not_after.map(|t| (t - Utc::now()).num_days()),
+            not_after: not_after.map(|t| t.to_rfc3339()),
+        },
+        None => CertificateInfo {
+            subject: "(unparseable certificate)".to_string(),
+            issuer: String::new(),
+            not_before: None,
+            not_after: None,
+            days_until_expiry: None,
+        },
+    }
+}
+
+/// Verifies as the real clients would, but remembers the chain and lets the
+/// handshake finish even when verification fails, so the report can show why.
+#[derive(Debug)]
+struct RecordingVerifier {
+    inner: Arc<WebPkiServerVerifier>,
+    chain: Mutex<Vec<CertificateDer<'static>>>,
+    error: Mutex<Option<String>>,
+}
+
+impl ServerCertVerifier for RecordingVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        *self.chain.lock().unwrap() = std::iter::once(end_entity)
+            .chain(intermediates)
+            .map(|c| c.clone().into_owned())
+            .collect();
+        if let Err(e) = self
+            .inner
+            .verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)
+        {
+            *self.error.lock().unwrap() = Some(e.to_string());
+        }
+        // Nothing is sent over this connection, so finishing an unverified handshake is harmless
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+/// The built-in roots plus the configured CA bundle, as `network` trusts them.
+pub fn roots(ca_bundle: Option<&str>) -> Result<RootCertStore, String> {
+    let mut roots = RootCertStore::from_iter(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    if let Some(path) = ca_bundle {
+        let pem = std::fs::read(path).map_err(|e| format!("Failed to read CA bundle {}: {}", path, e))?;
+        for certificate in CertificateDer::pem_slice_iter(&pem) {
+            let certificate = certificate.map_err(|e| format!("Invalid CA bundle {}: {}", path, e))?;
+            roots
+                .add(certificate)
+                .map_err(|e| format!("Invalid certificate in CA bundle {}: {}", path, e))?;
+        }
+    }
+    Ok(roots)
+}
+
+async fn resolve(host: &str, port: u16) -> Outcome {
+    match tokio::time::timeout(STEP_TIMEOUT, tokio::net::lookup_host((host, port))).await {
+        Err(_) => Outcome::fail(format!("Timed out resolving {}", host)),
+        Ok(Err(e)) => Outcome::fail(format!("Failed to resolve {}: {}", host, e)),
+        Ok(Ok(addresses)) => {
+            let addresses: Vec<String> = addresses.map(|a| a.ip().to_string()).collect();
+            if addresses.is_empty() {
+                return Outcome::fail(format!("{} has no addresses", host));
+            }
+            Outcome::pass(format!("{} resolves to {}", host, addresses.join(", ")))
+        }
+    }
+}
+
+/// Tries each address in turn, returning the first connection that opens.
+async fn connect(host: &str, port: u16) -> (Outcome, Option<tokio::net::TcpStream>) {
+    let addresses: Vec<SocketAddr> = match tokio::net::lookup_host((host, port)).await {
+        Ok(addresses) => addresses.collect(),
+        Err(e) => return (Outcome::fail(format!("Failed to resolve {}: {}", host, e)), None),
+    };
+    let mut errors = Vec::new();
+    for address in addresses {
+        match tokio::time::timeout(STEP_TIMEOUT, tokio::net::TcpStream::connect(address)).await {
+            Ok(Ok(stream)) => return (Outcome::pass(format!("Connected to {}", address)), Some(stream)),
+            Ok(Err(e)) => errors.push(format!("{}: {}", address, e)),
+            Err(_) => errors.push(format!("{}: timed out", address)),
+        }
+    }
+    (Outcome::fail(format!("Failed to connect to port {}: {}", port, errors.join("; "))), None)
+}
+
+async fn handshake(stream: tokio::net::TcpStream, host: &str, ca_bundle: Option<&str>) -> Outcome {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let roots = match roots(ca_bundle) {
+        Ok(roots) => Arc::new(roots),
+        Err(e) => return Outcome::fail(e),
+    };
+    let inner = match WebPkiServerVerifier::builder_with_provider(roots, provider.clone()).build() {
+        Ok(inner) => inner,
+        Err(e) => return Outcome::fail(format!("Failed to set up certificate verification: {}", e)),
+    };
+    let verifier = Arc::new(RecordingVerifier {
+        inner,
+        chain: Mutex::new(Vec::new()),
+        error: Mutex::new(None),
+    });
+    let config = match rustls::ClientConfig::builder_with_provider(provider).with_safe_default_protocol_versions() {
+        Ok(builder) => builder
+            .dangerous()
+            .with_custom_certificate_verifier(verifier.clone())
+            .with_no_client_auth(),
+        Err(e) => return Outcome::fail(format!("Failed to set up TLS: {}", e)),
+    };
+    let server_name = match ServerName::try_from(host.to_string()) {
+        Ok(name) => name,
+        Err(e) => return Outcome::fail(format!("Invalid server name {}: {}", host, e)),
+    };
+
+    let connector = tokio_rustls::TlsConnector::from(Arc::new(config));
+    let connected = tokio::time::timeout(STEP_TIMEOUT, connector.connect(server_name, stream)).await;
+    let certificates: Vec<CertificateInfo> = verifier
+        .chain
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|c| describe_certificate(c))
+        .collect();
+    let verify_error = verifier.error.lock().unwrap().take();
+
+    let mut outcome = match connected {
+        Err(_) => Outcome::fail("Timed out during the TLS handshake"),
+        Ok(Err(e)) => Outcome::fail(format!("TLS handshake failed: {}", e)),
+        Ok(Ok(stream)) => {
+            let (_, connection) = stream.get_ref();
+            let protocol = connection.protocol_version().map_or_else(|| "TLS".to_string(), |v| format!("{:?}", v));
+            match verify_error {
+                Some(e) => Outcome::fail(format!("Certificate not trusted: {}", e)),
+                None => match certificates.first().and_then(|leaf| leaf.days_until_expiry) {
+                    Some(days) if days < EXPIRY_WARNING_DAYS => Outcome {
+                        status: StepStatus::Warn,
+                        ..Outcome::pass(format!("{} handshake succeeded; certificate expires in {} days", protocol, days))
+                    },
+                    _ => Outcome::pass(format!("{} handshake succeeded", protocol)),
+                },
+            }
+        }
+    };
+    outcome.certificates = certificates;
+    outcome
+}
+
+async fn http_client(app_handle: &tauri::AppHandle, deployment: &str) -> Result<(reqwest::Client, Option<String>), String> {
+    // The platform credential tools block
+    let (name, handle) = (deployment.to_string(), app_handle.clone());
+    let (token, builder) = tauri::async_runtime::spawn_blocking(move || {
+        let mut builder = crate::network::client_builder(&handle)?;
+        if let Some(identity) = crate::client_cert::identity(&handle, &name)? {
+            builder = builder.identity(identity);
+        }
+        Ok::<_, String>((crate::credentials::load_token(&name)?, builder))
+    })
+    .await
+    .map_err(|e| format!("Failed to load token: {}", e))??;
+    let client = builder
+        .timeout(STEP_TIMEOUT)
+        .user_agent(concat!("cribl-hc/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    Ok((client, token))
+}
+
+async fn get(client: &reqwest::Client, url: &str, token: &str) -> Result<(StatusCode, Option<Value>), String> {
+    let response = client
+        .get(url)
+        .bearer_auth(token)
+        .header(ACCEPT, "application/json")
+        .send()
+        .await
+        .map_err(|e| format!("Request to {} failed: {}", url, e))?;
+    let status = response.status();
+    Ok((status, response.json().await.ok()))
+}
+
+/// Checks the token against an endpoint that needs one; returns the response for
+/// the version step to fall back on.
+async fn authenticate(client: &reqwest::Client, base_url: &str, token: Option<&str>) -> (Outcome, Option<Value>) {
+    let Some(token) = token else {
+        return (Outcome::fail("No saved token for this deployment"), None);
+    };
+    match get(client, &format!("{}/api/v1/system/info", base_url), token).await {
+        Err(e) => (Outcome::fail(e), None),
+        Ok((status, body)) if status.is_success() => (Outcome::pass("Token accepted"), body),
+        Ok((StatusCode::UNAUTHORIZED, _)) => (Outcome::fail("Token rejected (401); it may have expired"), None),
+        Ok((StatusCode::FORBIDDEN, _)) => (Outcome::fail("Token lacks permission to read system info (403)"), None),
+        Ok((status, _)) => (Outcome::fail(format!("Leader returned {}", status)), None),
+    }
+}
+
+/// `{"version": ...}` from `/api/v1/version`, or `items[0].BUILD.version` from system info.
+fn version_of(body: &Value) -> Option<String> {
+    body.get("version")
+        .or_else(|| body.pointer("/items/0/BUILD/version"))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
+
+async fn probe_version(client: &reqwest::Client, base_url: &str, token: &str, system_info: Option<&Value>) -> Outcome {
+    let (version, product) = match get(client, &format!("{}/api/v1/version", base_url), token).await {
+        Ok((status, Some(body))) if status.is_success() => (
+            version_of(&body),
+            body.get("product").and_then(Value::as_str).map(str::to_string),
+        ),
+        _ => (None, None),
+    };
+    match version.or_else(|| system_info.and_then(version_of)) {
+        Some(version) => Outcome::pass(format!(
+            "Cribl {} {}",
+            product.as_deref().unwrap_or("Stream"),
+            version
+        )),
+        None => Outcome::fail("The leader did not report a version"),
+    }
+}
+
+/// Checks, step by step, whether `deployment`'s leader can be reached and used.
+#[tauri::command]
+pub async fn run_preflight(app_handle: tauri::AppHandle, deployment: String) -> Result<PreflightReport, String> {
+    let (url, ca_bundle) = {
+        let store = app_handle.state::<SettingsStore>();
+        let current = store.settings.lock().unwrap();
+        let url = current
+            .credentials
+            .iter()
+            .find(|c| c.deployment == deployment)
+            .map(|c| c.url.trim_end_matches('/').to_string())
+            .ok_or_else(|| format!("No saved credentials for deployment {}", deployment))?;
+        (url, current.network.ca_bundle.clone())
+    };
+    let parsed = url::Url::parse(&url).map_err(|e| format!("Invalid deployment URL {}: {}", url, e))?;
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| format!("Deployment URL has no host: {}", url))?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_string();
+    let port = parsed
+        .port_or_known_default()
+        .ok_or_else(|| format!("Deployment URL has no port: {}", url))?;
+    let https = parsed.scheme() == "https";
+
+    let mut steps = Steps { steps: Vec::new() };
+    const SKIPPED: &str = "Skipped after an earlier failure";
+
+    let started = Instant::now();
+    steps.record("dns", started, resolve(&host, port).await);
+
+    let mut stream = None;
+    if steps.failed() {
+        steps.skip("tcp", SKIPPED);
+    } else {
+        let started = Instant::now();
+        let (outcome, connected) = connect(&host, port).await;
+        steps.record("tcp", started, outcome);
+        stream = connected;
+    }
+
+    match stream {
+        _ if !https => steps.skip("tls", "The deployment URL is not HTTPS"),
+        Some(stream) => {
+            let started = Instant::now();
+            let outcome = handshake(stream, &host, ca_bundle.as_deref()).await;
+            steps.record("tls", started, outcome);
+        }
+        None => steps.skip("tls", SKIPPED),
+    }
+
+    if steps.failed() {
+        steps.skip("auth", SKIPPED);
+        steps.skip("api_version", SKIPPED);
+    } else {
+        let (client, token) = http_client(&app_handle, &deployment).await?;
+        let started = Instant::now();
+        let (outcome, system_info) = authenticate(&client, &url, token.as_deref()).await;
+        steps.record("auth", started, outcome);
+
+        match token.filter(|_| !steps.failed()) {
+            Some(token) => {
+                let started = Instant::now();
+                let outcome = probe_version(&client, &url, &token, system_info.as_ref()).await;
+                steps.record("api_version", started, outcome);
+            }
+            None => steps.skip("api_version", SKIPPED),
+        }
+    }
+
+    Ok(PreflightReport {
+        passed: !steps.failed(),
+        deployment,
+        url,
+        steps: steps.steps,
+    })
+}
@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use tauri::Manager;
+
+use crate::settings::{self, SettingsStore};
+
+/// Scheduling priority of the backend relative to interactive apps.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackendPriority {
+    Normal,
+    #[default]
+    BelowNormal,
+    Low,
+}
+
+impl BackendPriority {
+    #[cfg(unix)]
+    fn niceness(self) -> libc::c_int {
+        match self {
+            BackendPriority::Normal => 0,
+            BackendPriority::BelowNormal => 5,
+            BackendPriority::Low => 10,
+        }
+    }
+
+    #[cfg(windows)]
+    fn priority_class(self) -> u32 {
+        use windows_sys::Win32::System::Threading::{
+            BELOW_NORMAL_PRIORITY_CLASS, IDLE_PRIORITY_CLASS, NORMAL_PRIORITY_CLASS,
+        };
+
+        match self {
+            BackendPriority::Normal => NORMAL_PRIORITY_CLASS,
+            BackendPriority::BelowNormal => BELOW_NORMAL_PRIORITY_CLASS,
+            BackendPriority::Low => IDLE_PRIORITY_CLASS,
+        }
+    }
+}
+
+/// Makes the backend start at the given priority.
+#[cfg(unix)]
+pub fn apply_before_spawn(command: &mut Command, priority: BackendPriority) {
+    use std::os::unix::process::CommandExt;
+
+    let niceness = priority.niceness();
+    if niceness == 0 {
+        return;
+    }
+
+    // Runs in the forked child before exec, so only async-signal-safe calls here.
+    unsafe {
+        command.pre_exec(move || {
+            if libc::setpriority(libc::PRIO_PROCESS, 0, niceness) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+}
+
+#[cfg(windows)]
+pub fn apply_before_spawn(command: &mut Command, priority: BackendPriority) {
+    use std::os::windows::process::CommandExt;
+
+    command.creation_flags(priority.priority_class());
+}
+
+/// Changes the priority of an already running process.
+#[cfg(unix)]
+fn set_running(pid: u32, priority: BackendPriority) -> Result<(), String> {
+    let result = unsafe { libc::setpriority(libc::PRIO_PROCESS, pid as libc::id_t, priority.niceness()) };
+    if result == 0 {
+        return Ok(());
+    }
+
+    let error = std::io::Error::last_os_error();
+    match error.kind() {
+        // Unprivileged users may lower priority but never raise it back.
+        std::io::ErrorKind::PermissionDenied => Err(
+            "Raising the priority of a running backend requires elevated privileges; restart it instead"
+                .to_string(),
+        ),
+        _ => Err(format!("Failed to set backend priority: {}", error)),
+    }
+}
+
+#[cfg(windows)]
+fn set_running(pid: u32, priority: BackendPriority) -> Result<(), String> {
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::Threading::{OpenProcess, SetPriorityClass, PROCESS_SET_INFORMATION};
+
+    unsafe {
+        let process = OpenProcess(PROCESS_SET_INFORMATION, 0, pid);
+        if process.is_null() {
+            return Err(format!("Failed to open backend process: {}", std::io::Error::last_os_error()));
+        }
+
+        let result = SetPriorityClass(process, priority.priority_class());
+        let error = std::io::Error::last_os_error();
+        CloseHandle(process);
+
+        if result == 0 {
+            return Err(format!("Failed to set backend priority: {}", error));
+        }
+    }
+
+    Ok(())
+}
+
+/// Saves the priority for future launches and applies it to the running backend.
+#[tauri::command]
+pub fn set_backend_priority(app_handle: tauri::AppHandle, level: BackendPriority) -> Result<(), String> {
+    {
+        let store = app_handle.state::<SettingsStore>();
+        let mut current = store.settings.lock().unwrap();
+        current.backend.priority = level;
+        settings::save(&app_handle, &current)?;
+    }
+
+    match crate::backend_pid(&app_handle) {
+        Ok(pid) => set_running(pid, level),
+        Err(_) => Ok(()),
+    }
+}
@@ -0,0 +1,669 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::process::{Child, Command};
+use std::time::{Duration, Instant};
+
+/// How long to wait for a freshly started backend to open its listening socket.
+const LISTENER_WAIT: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Listener {
+    pub address: String,
+    pub port: u16,
+    pub loopback: bool,
+}
+
+/// Splits "127.0.0.1:8080", "[::1]:8080" or "*:8080" into address and port.
+fn parse_socket(value: &str) -> Option<Listener> {
+    let (address, port) = value.rsplit_once(':')?;
+    let address = address.trim_start_matches('[').trim_end_matches(']');
+    let address = match address {
+        "*" => "0.0.0.0",
+        other => other.split('%').next().unwrap_or(other),
+    };
+
+    let loopback = address
+        .parse::<std::net::IpAddr>()
+        .map(|ip| ip.is_loopback())
+        .unwrap_or(address == "localhost");
+
+    Some(Listener {
+        address: address.to_string(),
+        port: port.parse().ok()?,
+        loopback,
+    })
+}
+
+fn run(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+
+    if !output.status.success() {
+        return Err(format!("{} exited with {}", program, output.status));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+#[cfg(not(target_os = "windows"))]
+fn listeners_from_lsof(pid: u32) -> Result<Vec<Listener>, String> {
+    let pid = pid.to_string();
+    let output = run("lsof", &["-nP", "-a", "-p", &pid, "-iTCP", "-sTCP:LISTEN", "-Fn"])?;
+
+    Ok(output
+        .lines()
+        .filter_map(|line| line.strip_prefix('n'))
+        .filter_map(parse_socket)
+        .collect())
+}
+
+#[cfg(target_os = "linux")]
+fn listeners_from_ss(pid: u32) -> Result<Vec<Listener>, String> {
+    let output = run("ss", &["-ltnpH"])?;
+    let needle = format!("pid={},", pid);
+
+    Ok(output
+        .lines()
+        .filter(|line| line.contains(&needle))
+        .filter_map(|line| line.split_whitespace().nth(3))
+        .filter_map(parse_socket)
+        .collect())
+}
+
+#[cfg(target_os = "windows")]
+fn listeners_from_netstat(pid: u32) -> Result<Vec<Listener>, String> {
+    let output = run("netstat", &["-ano", "-p", "TCP"])?;
+    let output_v6 = run("netstat", &["-ano", "-p", "TCPv6"]).unwrap_or_default();
+    let pid = pid.to_string();
+
+    Ok(output
+        .lines()
+        .chain(output_v6.lines())
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .filter(|cols| cols.len() == 5 && cols[3] == "LISTENING" && cols[4] == pid)
+        .filter_map(|cols| parse_socket(cols[1]))
+        .collect())
+}
+
+/// Lists the TCP sockets the given process is listening on.
+pub fn listeners(pid: u32) -> Result<Vec<Listener>, String> {
+    #[cfg(target_os = "linux")]
+    {
+        listeners_from_ss(pid).or_else(|_| listeners_from_lsof(pid))
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        listeners_from_netstat(pid)
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+    {
+        listeners_from_lsof(pid)
+    }
+}
+
+/// Waits (up to `max_wait`) for the process to start listening and fails if any socket
+/// is reachable from outside the machine. Missing OS tooling is logged rather than
+/// treated as a failure.
+pub fn verify_loopback_only(pid: u32, max_wait: Duration) -> Result<(), String> {
+    let deadline = Instant::now() + LISTENER_WAIT.min(max_wait);
+
+    loop {
+        match listeners(pid) {
+            Ok(found) if !found.is_empty() => {
+                return match found.iter().find(|l| !l.loopback) {
+                    Some(exposed) => Err(format!(
+                        "Backend is listening on non-loopback address {}:{}",
+                        exposed.address, exposed.port
+                    )),
+                    None => Ok(()),
+                };
+            }
+            Ok(_) => {}
+            Err(e) => {
+                log::warn!("Could not verify backend listeners: {}", e);
+                return Ok(());
+            }
+        }
+
+        if Instant::now() >= deadline {
+            log::warn!("Backend did not report any listening sockets");
+            return Ok(());
+        }
+        std::thread::sleep(Duration::from_millis(200));
+    }
+}
+
+#[tauri::command]
+pub fn get_backend_listeners(app_handle: tauri::AppHandle) -> Result<Vec<Listener>, String> {
+    let pid = crate::backend_pid(&app_handle)?;
+    listeners(pid)
+}
+
+#[cfg(unix)]
+fn signal(pid: u32, signal: libc::c_int) -> Result<(), String> {
+    if unsafe { libc::kill(pid as libc::pid_t, signal) } != 0 {
+        return Err(format!("Failed to signal backend: {}", std::io::Error::last_os_error()));
+    }
+    Ok(())
+}
+
+/// Suspends or resumes every thread of the process; Windows has no process-wide
+/// equivalent of SIGSTOP in its documented API.
+#[cfg(windows)]
+fn set_threads_suspended(pid: u32, suspend: bool) -> Result<(), String> {
+    use windows_sys::Win32::Foundation::{CloseHandle, INVALID_HANDLE_VALUE};
+    use windows_sys::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, Thread32First, Thread32Next, TH32CS_SNAPTHREAD, THREADENTRY32,
+    };
+    use windows_sys::Win32::System::Threading::{OpenThread, ResumeThread, SuspendThread, THREAD_SUSPEND_RESUME};
+
+    unsafe {
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0);
+        if snapshot == INVALID_HANDLE_VALUE {
+            return Err(format!("Failed to list backend threads: {}", std::io::Error::last_os_error()));
+        }
+
+        let mut entry: THREADENTRY32 = std::mem::zeroed();
+        entry.dwSize = std::mem::size_of::<THREADENTRY32>() as u32;
+        let mut found = false;
+
+        let mut more = Thread32First(snapshot, &mut entry) != 0;
+        while more {
+            if entry.th32OwnerProcessID == pid {
+                let thread = OpenThread(THREAD_SUSPEND_RESUME, 0, entry.th32ThreadID);
+                if !thread.is_null() {
+                    if suspend {
+                        SuspendThread(thread);
+                    } else {
+                        ResumeThread(thread);
+                    }
+                    CloseHandle(thread);
+                    found = true;
+                }
+            }
+            more = Thread32Next(snapshot, &mut entry) != 0;
+        }
+
+        CloseHandle(snapshot);
+
+        if !found {
+            return Err("Failed to access any backend threads".to_string());
+        }
+    }
+
+    Ok(())
+}
+
+/// Freezes the process without losing its state.
+pub fn suspend(pid: u32) -> Result<(), String> {
+    #[cfg(unix)]
+    {
+        signal(pid, libc::SIGSTOP)
+    }
+
+    #[cfg(windows)]
+    {
+        set_threads_suspended(pid, true)
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = pid;
+        Err("Suspending the backend is not supported on this platform".to_string())
+    }
+}
+
+pub fn resume(pid: u32) -> Result<(), String> {
+    #[cfg(unix)]
+    {
+        signal(pid, libc::SIGCONT)
+    }
+
+    #[cfg(windows)]
+    {
+        set_threads_suspended(pid, false)
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = pid;
+        Err("Suspending the backend is not supported on this platform".to_string())
+    }
+}
+
+/// Asks the process to reload its configuration in place.
+#[cfg(unix)]
+pub fn request_reload(pid: u32) -> Result<(), String> {
+    signal(pid, libc::SIGHUP)
+}
+
+/// Kills a process we hold no handle for, such as an adopted backend.
+pub fn kill(pid: u32) -> Result<(), String> {
+    #[cfg(unix)]
+    {
+        signal(pid, libc::SIGKILL)
+    }
+
+    #[cfg(windows)]
+    {
+        run("taskkill", &["/PID", &pid.to_string(), "/F"]).map(|_| ())
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = pid;
+        Err("Killing the backend is not supported on this platform".to_string())
+    }
+}
+
+struct ProcessInfo {
+    pid: u32,
+    parent: u32,
+    name: String,
+    cpu_percent: Option<f64>,
+    memory_bytes: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct ProcessNode {
+    pid: u32,
+    name: String,
+    cpu_percent: Option<f64>,
+    memory_bytes: Option<u64>,
+    children: Vec<ProcessNode>,
+}
+
+#[cfg(unix)]
+fn process_table() -> Result<Vec<ProcessInfo>, String> {
+    // `comm` goes last since it may contain spaces
+    let output = run("ps", &["-A", "-o", "pid=", "-o", "ppid=", "-o", "pcpu=", "-o", "rss=", "-o", "comm="])?;
+
+    Ok(output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let pid = fields.next()?.parse().ok()?;
+            let parent = fields.next()?.parse().ok()?;
+            let cpu_percent = fields.next()?.parse().ok();
+            let rss_kb: Option<u64> = fields.next()?.parse().ok();
+            Some(ProcessInfo {
+                pid,
+                parent,
+                name: fields.collect::<Vec<_>>().join(" "),
+                cpu_percent,
+                memory_bytes: rss_kb.map(|kb| kb * 1024),
+            })
+        })
+        .collect())
+}
+
+#[cfg(windows)]
+fn process_table() -> Result<Vec<ProcessInfo>, String> {
+    #[derive(serde::Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct Win32Process {
+        process_id: u32,
+        parent_process_id: u32,
+        name: Option<String>,
+        working_set_size: Option<u64>,
+    }
+
+    let script = "ConvertTo-Json -Compress -InputObject @(Get-CimInstance Win32_Process | \
+                  Select-Object ProcessId,ParentProcessId,Name,WorkingSetSize)";
+    let output = run("powershell", &["-NoProfile", "-NonInteractive", "-Command", script])?;
+    let processes: Vec<Win32Process> =
+        serde_json::from_str(&output).map_err(|e| format!("Failed to parse process list: {}", e))?;
+
+    // Win32_Process has no instantaneous CPU figure
+    Ok(processes
+        .into_iter()
+        .map(|p| ProcessInfo {
+            pid: p.process_id,
+            parent: p.parent_process_id,
+            name: p.name.unwrap_or_default(),
+            cpu_percent: None,
+            memory_bytes: p.working_set_size,
+        })
+        .collect())
+}
+
+#[cfg(not(any(unix, windows)))]
+fn process_table() -> Result<Vec<ProcessInfo>, String> {
+    Err("Listing processes is not supported on this platform".to_string())
+}
+
+fn build_node(info: &ProcessInfo, children: &HashMap<u32, Vec<&ProcessInfo>>) -> ProcessNode {
+    ProcessNode {
+        pid: info.pid,
+        name: info.name.clone(),
+        cpu_percent: info.cpu_percent,
+        memory_bytes: info.memory_bytes,
+        children: children
+            .get(&info.pid)
+            .map(|kids| kids.iter().map(|kid| build_node(kid, children)).collect())
+            .unwrap_or_default(),
+    }
+}
+
+/// The process and everything it spawned, as currently running.
+pub fn tree(pid: u32) -> Result<ProcessNode, String> {
+    let table = process_table()?;
+    let mut children: HashMap<u32, Vec<&ProcessInfo>> = HashMap::new();
+    for info in table.iter().filter(|info| info.pid != info.parent) {
+        children.entry(info.parent).or_default().push(info);
+    }
+
+    let root = table
+        .iter()
+        .find(|info| info.pid == pid)
+        .ok_or_else(|| format!("Process {} not found", pid))?;
+    Ok(build_node(root, &children))
+}
+
+/// A process tree's combined footprint.
+pub struct TreeUsage {
+    /// The root first, then everything it spawned.
+    pub pids: Vec<u32>,
+    /// Summed from `ps`, so a recent average rather than an instant figure;
+    /// `None` on Windows.
+    pub cpu_percent: Option<f64>,
+    pub memory_bytes: Option<u64>,
+}
+
+pub fn tree_usage(pid: u32) -> Result<TreeUsage, String> {
+    fn add(node: &ProcessNode, usage: &mut TreeUsage) {
+        usage.pids.push(node.pid);
+        if let Some(cpu) = node.cpu_percent {
+            *usage.cpu_percent.get_or_insert(0.0) += cpu;
+        }
+        if let Some(memory) = node.memory_bytes {
+            *usage.memory_bytes.get_or_insert(0) += memory;
+        }
+        for child in &node.children {
+            add(child, usage);
+        }
+    }
+
+    let mut usage = TreeUsage { pids: Vec::new(), cpu_percent: None, memory_bytes: None };
+    add(&tree(pid)?, &mut usage);
+    Ok(usage)
+}
+
+/// CPU time the process has used so far, user and system together, where the
+/// platform makes it cheap to read. Differences between two readings give the
+/// current CPU use.
+#[cfg(target_os = "linux")]
+pub fn cpu_time_ms(pid: u32) -> Option<u64> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // The command name is in parentheses and may contain spaces; utime and stime
+    // are the 12th and 13th fields after it
+    let mut fields = stat.rsplit_once(')')?.1.split_whitespace().skip(11);
+    let utime: u64 = fields.next()?.parse().ok()?;
+    let stime: u64 = fields.next()?.parse().ok()?;
+    let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    (ticks > 0).then(|| (utime + stime) * 1000 / ticks as u64)
+}
+
+#[cfg(windows)]
+pub fn cpu_time_ms(pid: u32) -> Option<u64> {
+    use windows_sys::Win32::Foundation::{CloseHandle, FILETIME};
+    use windows_sys::Win32::System::Threading::{GetProcessTimes, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION};
+
+    let as_100ns = |t: &FILETIME| ((t.dwHighDateTime as u64) << 32) | t.dwLowDateTime as u64;
+    unsafe {
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if process.is_null() {
+            return None;
+        }
+        let mut times: [FILETIME; 4] = std::mem::zeroed();
+        let [created, exited, kernel, user] = &mut times;
+        let ok = GetProcessTimes(process, created, exited, kernel, user) != 0;
+        CloseHandle(process);
+        ok.then(|| (as_100ns(kernel) + as_100ns(user)) / 10_000)
+    }
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+pub fn cpu_time_ms(_pid: u32) -> Option<u64> {
+    None
+}
+
+fn descendants(node: &ProcessNode, out: &mut Vec<u32>) {
+    for child in &node.children {
+        descendants(child, out);
+        out.push(child.pid);
+    }
+}
+
+/// Starts the backend as the leader of its own process group, so its workers can
+/// be signalled together. Windows uses a `Job` instead.
+pub fn isolate_before_spawn(command: &mut Command) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = command;
+    }
+}
+
+/// Ties every process the backend starts to the lifetime of this value: dropping it,
+/// or the app dying, terminates them all. Only Windows holds anything here; Unix
+/// relies on the process group from `isolate_before_spawn`.
+pub struct Job {
+    #[cfg(windows)]
+    handle: windows_sys::Win32::Foundation::HANDLE,
+}
+
+// The handle is only closed, once, in `drop`.
+#[cfg(windows)]
+unsafe impl Send for Job {}
+#[cfg(windows)]
+unsafe impl Sync for Job {}
+
+#[cfg(windows)]
+impl Drop for Job {
+    fn drop(&mut self) {
+        unsafe {
+            windows_sys::Win32::Foundation::CloseHandle(self.handle);
+        }
+    }
+}
+
+#[cfg(windows)]
+pub fn contain(child: &Child) -> Result<Job, String> {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation, SetInformationJobObject,
+        JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+    };
+
+    unsafe {
+        let handle = CreateJobObjectW(std::ptr::null(), std::ptr::null());
+        if handle.is_null() {
+            return Err(format!("Failed to create job object: {}", std::io::Error::last_os_error()));
+        }
+
+        let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = std::mem::zeroed();
+        info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+        let configured = SetInformationJobObject(
+            handle,
+            JobObjectExtendedLimitInformation,
+            &info as *const _ as *const _,
+            std::mem::size_of_val(&info) as u32,
+        ) != 0;
+
+        if !configured || AssignProcessToJobObject(handle, child.as_raw_handle() as _) == 0 {
+            let error = std::io::Error::last_os_error();
+            CloseHandle(handle);
+            return Err(format!("Failed to put backend in a job object: {}", error));
+        }
+
+        Ok(Job { handle })
+    }
+}
+
+#[cfg(not(windows))]
+pub fn contain(_child: &Child) -> Result<Job, String> {
+    Ok(Job {})
+}
+
+/// Lets the child exit on its own for up to `timeout`, then kills its tree. With
+/// `send_sigterm` set, Unix backends are first sent SIGTERM across their process group;
+/// on Windows the caller must already have asked the backend to exit.
+pub fn terminate(child: &mut Child, timeout: Duration, send_sigterm: bool) {
+    #[cfg(unix)]
+    if send_sigterm && unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGTERM) } != 0 {
+        log::debug!("Failed to signal backend process group: {}", std::io::Error::last_os_error());
+    }
+    #[cfg(not(unix))]
+    let _ = send_sigterm;
+
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                log::info!("Backend exited ({})", status);
+                // Workers that outlived it still go
+                kill_group(child);
+                return;
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(50)),
+            Err(_) => break,
+        }
+    }
+
+    log::warn!("Backend did not exit within {} s; killing it", timeout.as_secs());
+    kill_tree(child);
+}
+
+/// Kills what is left of the process group of a child that has exited and been
+/// reaped. Its pid may name another process by now, so nothing is looked up by
+/// it; the group keeps its id while any member lives. On Windows the `Job`
+/// takes care of stragglers.
+fn kill_group(child: &Child) {
+    #[cfg(unix)]
+    if unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL) } != 0 {
+        // Usually ESRCH: nothing outlived the backend
+        log::debug!("Failed to kill backend process group: {}", std::io::Error::last_os_error());
+    }
+    #[cfg(not(unix))]
+    let _ = child;
+}
+
+/// Kills the child and every process below it: the whole process group on Unix
+/// or `taskkill /T` on Windows, then anything the tree walk found (a worker
+/// that left the group), deepest first so none is reparented out of reach while we work.
+///
+/// The tree is listed before anything is signalled, while the child is alive
+/// and its pid still its own. A child that has already exited gets
+/// `kill_group` instead.
+pub fn kill_tree(child: &mut Child) {
+    if !matches!(child.try_wait(), Ok(None)) {
+        kill_group(child);
+        let _ = child.wait();
+        return;
+    }
+
+    let mut pids = Vec::new();
+    match tree(child.id()) {
+        Ok(root) => descendants(&root, &mut pids),
+        Err(e) => log::warn!("Could not list backend subprocesses: {}", e),
+    }
+
+    #[cfg(unix)]
+    if unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL) } != 0 {
+        log::debug!("Failed to kill backend process group: {}", std::io::Error::last_os_error());
+    }
+    #[cfg(windows)]
+    if let Err(e) = run("taskkill", &["/PID", &child.id().to_string(), "/T", "/F"]) {
+        log::debug!("Failed to kill backend process tree: {}", e);
+    }
+
+    for pid in pids {
+        if let Err(e) = kill(pid) {
+            log::debug!("Failed to kill backend subprocess {}: {}", pid, e);
+        }
+    }
+
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+#[tauri::command]
+pub fn get_backend_process_tree(app_handle: tauri::AppHandle) -> Result<ProcessNode, String> {
+    tree(crate::backend_pid(&app_handle)?)
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::io::BufRead;
+    use std::process::Stdio;
+
+    fn gone(pid: u32) -> bool {
+        // A zombie is as good as gone; the container's init may not reap it
+        match std::fs::read_to_string(format!("/proc/{}/stat", pid)) {
+            Ok(stat) => stat.rsplit(')').next().is_some_and(|rest| rest.trim_start().starts_with('Z')),
+            Err(_) => true,
+        }
+    }
+
+    fn wait_until_gone(pid: u32) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
+            if gone(pid) {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        false
+    }
+
+    /// Runs `script` as a backend would be, returning it and the pid it prints.
+    fn spawn(script: &str) -> (Child, u32) {
+        let mut command = Command::new("sh");
+        command.args(["-c", script]).stdout(Stdio::piped());
+        isolate_before_spawn(&mut command);
+        let mut child = command.spawn().unwrap();
+        let mut line = String::new();
+        std::io::BufReader::new(child.stdout.take().unwrap()).read_line(&mut line).unwrap();
+        (child, line.trim().parse().unwrap())
+    }
+
+    #[test]
+    fn workers_outliving_an_exited_backend_are_killed() {
+        let (mut child, worker) = spawn("sleep 30 & echo $!");
+        terminate(&mut child, Duration::from_secs(5), false);
+        assert!(wait_until_gone(worker));
+    }
+
+    #[test]
+    fn killing_a_running_backend_takes_workers_that_left_its_group() {
+        // `setsid` puts the worker in a session, and group, of its own
+        let (mut child, worker) = spawn("setsid sleep 30 & echo $!; wait");
+        // Give setsid time to exec, so the pid printed is the sleep's
+        std::thread::sleep(Duration::from_millis(200));
+        kill_tree(&mut child);
+        assert!(child.try_wait().unwrap().is_some());
+        assert!(wait_until_gone(worker));
+    }
+
+    #[test]
+    fn killing_an_exited_backend_still_clears_its_group() {
+        let (mut child, worker) = spawn("sleep 30 & echo $!");
+        while child.try_wait().unwrap().is_none() {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        kill_tree(&mut child);
+        assert!(wait_until_gone(worker));
+    }
+}
@@ -0,0 +1,433 @@
+//! Named deployment profiles (prod, staging, a customer's leader, ...) and which
+//! one is active.
+//!
+//! A profile points at saved credentials rather than holding a token, so
+//! switching environments never moves secrets around. Profiles live in their own
+//! file in the config dir, apart from the other settings, so they can be copied
+//! between machines on their own. The scheduler falls back to the active profile
+//! when its schedule names no deployment.
+//!
+//! Versions before settings schema 3 kept profiles, tokens included, in
+//! settings.json; `adopt_legacy` moves them here when those settings load.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+
+use crate::scheduler::AnalysisSchedule;
+use crate::settings::{AppSettings, SettingsStore};
+
+const PROFILES_FILE: &str = "profiles.json";
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeploymentProfile {
+    pub name: String,
+    /// Leader URL, e.g. `https://leader.example.com:9000`.
+    pub url: String,
+    /// The deployment whose saved credentials this profile signs in with.
+    pub credential: Option<String>,
+    /// Worker groups to include; every group when empty.
+    pub worker_groups: Vec<String>,
+    /// Checks run when none are chosen; every analyzer when unset.
+    pub analyzers: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profiles {
+    pub active: Option<String>,
+    pub profiles: Vec<DeploymentProfile>,
+}
+
+impl Profiles {
+    pub fn active_profile(&self) -> Option<&DeploymentProfile> {
+        let active = self.active.as_deref()?;
+        self.profiles.iter().find(|p| p.name == active)
+    }
+}
+
+/// A profile as settings.json held it before schema 3.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct LegacyProfile {
+    name: String,
+    url: String,
+    token: Option<String>,
+}
+
+#[derive(Default)]
+pub struct ProfileStore {
+    profiles: Mutex<Profiles>,
+}
+
+fn profiles_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app_handle
+        .path()
+        .app_config_dir()
+        .map_err(|e| format!("Failed to get config dir: {}", e))?;
+
+    Ok(dir.join(PROFILES_FILE))
+}
+
+/// The saved profiles; none when the file is missing. A file that doesn't
+/// parse is an error and is left where it is.
+fn read(path: &std::path::Path) -> Result<Profiles, String> {
+    match fs::read(path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| format!("Profiles file {} is unreadable ({})", path.display(), e)),
+        Err(_) => Ok(Profiles::default()),
+    }
+}
+
+/// Reads the saved profiles into the store. A file that doesn't parse is left
+/// where it is, so nothing is lost before the user can look at it.
+pub fn init(app_handle: &tauri::AppHandle) {
+    let Ok(path) = profiles_path(app_handle) else {
+        return;
+    };
+    let loaded = read(&path).unwrap_or_else(|e| {
+        log::warn!("{}; starting without profiles", e);
+        Profiles::default()
+    });
+    *app_handle.state::<ProfileStore>().profiles.lock().unwrap() = loaded;
+}
+
+/// Adds `legacy`, the `profiles` array of an older settings.json, to
+/// profiles.json; runs while settings load, before `init`. Each token moves to
+/// the credential store under its profile's name, which the profile then signs
+/// in with. A profile already in profiles.json wins over a legacy one with the
+/// same name. Returns how many were added.
+pub fn adopt_legacy(app_handle: &tauri::AppHandle, settings: &mut AppSettings, legacy: Value) -> Result<usize, String> {
+    let legacy: Vec<LegacyProfile> = serde_json::from_value(legacy).map_err(|e| format!("Unreadable profiles: {}", e))?;
+    let path = profiles_path(app_handle)?;
+    let mut profiles = read(&path)?;
+
+    let mut adopted = 0;
+    for old in legacy {
+        let name = old.name.trim().to_string();
+        if name.is_empty() || profiles.profiles.iter().any(|p| p.name == name) {
+            continue;
+        }
+        let url = old.url.trim().trim_end_matches('/').to_string();
+        let credential = match old.token.filter(|t| !t.is_empty()) {
+            Some(token) => match crate::credentials::adopt_token(settings, &name, &url, &token) {
+                Ok(()) => Some(name.clone()),
+                Err(e) => {
+                    log::warn!("Token of profile {} was not kept: {}", name, e);
+                    None
+                }
+            },
+            None => None,
+        };
+        profiles.profiles.push(DeploymentProfile {
+            name,
+            url,
+            credential,
+            ..Default::default()
+        });
+        adopted += 1;
+    }
+    if adopted > 0 {
+        save(app_handle, &profiles)?;
+    }
+    Ok(adopted)
+}
+
+fn save(app_handle: &tauri::AppHandle, profiles: &Profiles) -> Result<(), String> {
+    let path = profiles_path(app_handle)?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create config dir: {}", e))?;
+    }
+
+    let json = serde_json::to_vec_pretty(profiles)
+        .map_err(|e| format!("Failed to serialize profiles: {}", e))?;
+
+    crate::files::write_file_atomic(&path, &json)
+}
+
+/// Forgets every profile, for a factory reset.
+pub fn reset(app_handle: &tauri::AppHandle) -> Result<(), String> {
+    let defaults = Profiles::default();
+    save(app_handle, &defaults)?;
+    *app_handle.state::<ProfileStore>().profiles.lock().unwrap() = defaults;
+    Ok(())
+}
+
+pub fn active(app_handle: &tauri::AppHandle) -> Option<DeploymentProfile> {
+    app_handle
+        .state::<ProfileStore>()
+        .profiles
+        .lock()
+        .unwrap()
+        .active_profile()
+        .cloned()
+}
+
+/// Worker groups the active profile limits `deployment` to, if it signs in as it.
+pub fn worker_groups(app_handle: &tauri::AppHandle, deployment: &str) -> Option<Vec<String>> {
+    active(app_handle)
+        .filter(|p| p.credential.as_deref() == Some(deployment) && !p.worker_groups.is_empty())
+        .map(|p| p.worker_groups)
+}
+
+/// Fills in what `schedule` leaves open from the active profile: the deployment
+/// when it names none, and the analyzers when it runs that profile's deployment
+/// without choosing any.
+pub fn apply_to_schedule(app_handle: &tauri::AppHandle, mut schedule: AnalysisSchedule) -> AnalysisSchedule {
+    let Some(profile) = active(app_handle) else {
+        return schedule;
+    };
+    let Some(credential) = profile.credential else {
+        return schedule;
+    };
+    if schedule.deployment.trim().is_empty() {
+        schedule.deployment = credential.clone();
+    }
+    if schedule.analyzers.is_none() && schedule.deployment == credential {
+        schedule.analyzers = profile.analyzers;
+    }
+    schedule
+}
+
+fn saved_credential_url(app_handle: &tauri::AppHandle, deployment: &str) -> Option<String> {
+    app_handle
+        .state::<SettingsStore>()
+        .settings
+        .lock()
+        .unwrap()
+        .credentials
+        .iter()
+        .find(|c| c.deployment == deployment)
+        .map(|c| c.url.clone())
+}
+
+/// Checks and tidies what a profile says about itself, apart from its credentials.
+fn validate_fields(profile: &mut DeploymentProfile) -> Result<(), String> {
+    profile.name = profile.name.trim().to_string();
+    if profile.name.is_empty() {
+        return Err("A profile needs a name".to_string());
+    }
+
+    let url = url::Url::parse(profile.url.trim()).map_err(|e| format!("Invalid URL {:?}: {}", profile.url, e))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("Unsupported URL scheme {}", url.scheme()));
+    }
+    profile.url = url.as_str().trim_end_matches('/').to_string();
+
+    profile.worker_groups.retain(|g| !g.trim().is_empty());
+    let mut seen = HashSet::new();
+    profile.worker_groups.retain(|g| seen.insert(g.clone()));
+    Ok(())
+}
+
+fn validate(app_handle: &tauri::AppHandle, profile: &mut DeploymentProfile) -> Result<(), String> {
+    validate_fields(profile)?;
+    if let Some(credential) = &profile.credential {
+        let saved = saved_credential_url(app_handle, credential)
+            .ok_or_else(|| format!("No saved credentials for {}", credential))?;
+        if saved.trim_end_matches('/') != profile.url {
+            return Err(format!("The credentials saved for {} are for {}, not {}", credential, saved, profile.url));
+        }
+    }
+    Ok(())
+}
+
+/// Checks profiles from an exported config. Their credentials aren't required
+/// to exist yet, since tokens never leave the machine they were saved on.
+pub fn validate_import(profiles: &mut Profiles) -> Result<(), String> {
+    let mut names = HashSet::new();
+    for profile in &mut profiles.profiles {
+        validate_fields(profile)?;
+        if !names.insert(profile.name.clone()) {
+            return Err(format!("The config has two profiles named {}", profile.name));
+        }
+    }
+    if let Some(active) = &profiles.active {
+        if !names.contains(active) {
+            return Err(format!("The config's active profile {} isn't one of its profiles", active));
+        }
+    }
+    Ok(())
+}
+
+/// Replaces every profile, for an imported config checked by `validate_import`.
+pub fn replace_all(app_handle: &tauri::AppHandle, profiles: Profiles) -> Result<(), String> {
+    let store = app_handle.state::<ProfileStore>();
+    let mut current = store.profiles.lock().unwrap();
+    save(app_handle, &profiles)?;
+    *current = profiles;
+    let active = current.active_profile().cloned();
+    drop(current);
+
+    emit_active(app_handle, active);
+    crate::scheduler::reschedule(app_handle);
+    Ok(())
+}
+
+fn emit_active(app_handle: &tauri::AppHandle, profile: Option<DeploymentProfile>) {
+    if let Err(e) = app_handle.emit("active-profile-changed", profile) {
+        log::warn!("Failed to emit active-profile-changed: {}", e);
+    }
+}
+
+#[tauri::command]
+pub fn list_profiles(app_handle: tauri::AppHandle) -> Profiles {
+    app_handle.state::<ProfileStore>().profiles.lock().unwrap().clone()
+}
+
+/// Adds a profile, or replaces the one with the same name. `previous_name`
+/// renames an existing profile, keeping it active if it was.
+#[tauri::command]
+pub fn save_profile(
+    app_handle: tauri::AppHandle,
+    mut profile: DeploymentProfile,
+    previous_name: Option<String>,
+) -> Result<DeploymentProfile, String> {
+    validate(&app_handle, &mut profile)?;
+
+    let store = app_handle.state::<ProfileStore>();
+    let mut current = store.profiles.lock().unwrap();
+    let mut updated = current.clone();
+    let replaces = previous_name.as_deref().unwrap_or(&profile.name).to_string();
+    if replaces != profile.name && updated.profiles.iter().any(|p| p.name == profile.name) {
+        return Err(format!("A profile named {} already exists", profile.name));
+    }
+
+    match updated.profiles.iter_mut().find(|p| p.name == replaces) {
+        Some(existing) => *existing = profile.clone(),
+        None => updated.profiles.push(profile.clone()),
+    }
+    let was_active = updated.active.as_deref() == Some(replaces.as_str());
+    if was_active {
+        updated.active = Some(profile.name.clone());
+    }
+
+    save(&app_handle, &updated)?;
+    *current = updated;
+    drop(current);
+
+    if was_active {
+        emit_active(&app_handle, Some(profile.clone()));
+    }
+    Ok(profile)
+}
+
+/// Deletes a profile; returns whether it existed. Deleting the active profile
+/// leaves none active.
+#[tauri::command]
+pub fn delete_profile(app_handle: tauri::AppHandle, name: String) -> Result<bool, String> {
+    let store = app_handle.state::<ProfileStore>();
+    let mut current = store.profiles.lock().unwrap();
+    let mut updated = current.clone();
+    updated.profiles.retain(|p| p.name != name);
+    if updated.profiles.len() == current.profiles.len() {
+        return Ok(false);
+    }
+    let was_active = updated.active.as_deref() == Some(name.as_str());
+    if was_active {
+        updated.active = None;
+    }
+
+    save(&app_handle, &updated)?;
+    *current = updated;
+    drop(current);
+
+    if was_active {
+        emit_active(&app_handle, None);
+    }
+    Ok(true)
+}
+
+#[tauri::command]
+pub fn get_active_profile(app_handle: tauri::AppHandle) -> Option<DeploymentProfile> {
+    active(&app_handle)
+}
+
+/// Switches environments (`None` clears the active profile) and tells every
+/// window through an `active-profile-changed` event.
+#[tauri::command]
+pub fn set_active_profile(app_handle: tauri::AppHandle, name: Option<String>) -> Result<Option<DeploymentProfile>, String> {
+    let store = app_handle.state::<ProfileStore>();
+    let mut current = store.profiles.lock().unwrap();
+    let profile = match &name {
+        Some(name) => Some(
+            current
+                .profiles
+                .iter()
+                .find(|p| &p.name == name)
+                .cloned()
+                .ok_or_else(|| format!("No profile named {}", name))?,
+        ),
+        None => None,
+    };
+
+    let mut updated = current.clone();
+    updated.active = name;
+    save(&app_handle, &updated)?;
+    *current = updated;
+    drop(current);
+
+    log::info!(
+        "Active profile is now {}",
+        profile.as_ref().map_or("none", |p| p.name.as_str())
+    );
+    emit_active(&app_handle, profile.clone());
+    crate::scheduler::reschedule(&app_handle);
+    Ok(profile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(name: &str, worker_groups: &[&str]) -> DeploymentProfile {
+        DeploymentProfile {
+            name: name.to_string(),
+            url: "https://leader.example.com:9000".to_string(),
+            worker_groups: worker_groups.iter().map(|g| g.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn repeated_worker_groups_are_dropped_wherever_they_are() {
+        let mut p = profile("prod", &["default", "edge", " ", "default", "edge"]);
+        validate_fields(&mut p).unwrap();
+        assert_eq!(p.worker_groups, ["default", "edge"]);
+    }
+
+    #[test]
+    fn imports_need_unique_names_and_a_real_active_profile() {
+        let mut twice = Profiles {
+            active: None,
+            profiles: vec![profile("prod", &[]), profile(" prod ", &[])],
+        };
+        assert!(validate_import(&mut twice).is_err());
+
+        let mut dangling = Profiles {
+            active: Some("staging".to_string()),
+            profiles: vec![profile("prod", &[])],
+        };
+        assert!(validate_import(&mut dangling).is_err());
+
+        let mut fine = Profiles {
+            active: Some("prod".to_string()),
+            profiles: vec![profile("prod", &[]), profile("staging", &[])],
+        };
+        validate_import(&mut fine).unwrap();
+    }
+
+    #[test]
+    fn legacy_profiles_parse_with_missing_fields() {
+        let legacy: Vec<LegacyProfile> =
+            serde_json::from_value(serde_json::json!([{ "name": "prod", "url": "https://x", "token": "t" }, { "name": "dev" }])).unwrap();
+        assert_eq!(legacy[0].token.as_deref(), Some("t"));
+        assert_eq!(legacy[1].url, "");
+    }
+}
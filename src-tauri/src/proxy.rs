@@ -0,0 +1,659 @@
+use serde::Serialize;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs;
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::Manager;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio_util::sync::CancellationToken;
+
+use crate::settings::{self, ProxyLimits, SettingsStore};
+
+/// Cheap read-only endpoints that pull in the analyzer and analysis modules.
+const WARMUP_PATHS: &[&str] = &["/api/v1/version", "/api/v1/analyzers", "/api/v1/analysis"];
+
+/// Rolling window used for throughput reporting.
+const THROUGHPUT_WINDOW: Duration = Duration::from_secs(60);
+
+struct RequestSample {
+    at: Instant,
+    bytes: u64,
+    latency: Duration,
+}
+
+struct Metrics {
+    samples: VecDeque<RequestSample>,
+    reset_at: Instant,
+}
+
+impl Metrics {
+    fn prune(&mut self, now: Instant) {
+        while let Some(sample) = self.samples.front() {
+            if now.duration_since(sample.at) <= THROUGHPUT_WINDOW {
+                break;
+            }
+            self.samples.pop_front();
+        }
+    }
+}
+
+/// Bounds concurrent proxied requests. Changing the limits swaps in a fresh
+/// semaphore; requests already holding or awaiting the old one finish on it.
+struct Limiter {
+    limits: ProxyLimits,
+    slots: Arc<Semaphore>,
+}
+
+impl Limiter {
+    fn new(limits: ProxyLimits) -> Self {
+        Self {
+            limits,
+            slots: Arc::new(Semaphore::new(limits.max_in_flight)),
+        }
+    }
+}
+
+pub struct ProxyState {
+    client: reqwest::Client,
+    metrics: Mutex<Metrics>,
+    in_flight: Mutex<HashMap<String, CancellationToken>>,
+    limiter: Mutex<Limiter>,
+    queued: AtomicUsize,
+}
+
+impl Default for ProxyState {
+    fn default() -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            metrics: Mutex::new(Metrics {
+                samples: VecDeque::new(),
+                reset_at: Instant::now(),
+            }),
+            in_flight: Mutex::new(HashMap::new()),
+            limiter: Mutex::new(Limiter::new(ProxyLimits::default())),
+            queued: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl ProxyState {
+    /// Clears throughput counters; called whenever the backend (re)starts.
+    pub fn reset_metrics(&self) {
+        let mut metrics = self.metrics.lock().unwrap();
+        metrics.samples.clear();
+        metrics.reset_at = Instant::now();
+    }
+
+    pub fn set_limits(&self, limits: ProxyLimits) {
+        *self.limiter.lock().unwrap() = Limiter::new(limits);
+    }
+
+    /// Waits for a request slot, or fails straight away when the queue is full.
+    async fn acquire_slot(&self) -> Result<OwnedSemaphorePermit, String> {
+        let (slots, max_queued) = {
+            let limiter = self.limiter.lock().unwrap();
+            (limiter.slots.clone(), limiter.limits.max_queued)
+        };
+
+        if let Ok(permit) = slots.clone().try_acquire_owned() {
+            return Ok(permit);
+        }
+
+        if self.queued.fetch_add(1, Ordering::SeqCst) >= max_queued {
+            self.queued.fetch_sub(1, Ordering::SeqCst);
+            return Err("Backend is too busy; try again shortly".to_string());
+        }
+        let _queued = QueuedGuard(&self.queued);
+
+        slots
+            .acquire_owned()
+            .await
+            .map_err(|_| "Request queue was closed".to_string())
+    }
+
+    /// Registers a request under `request_id`, if given, so `cancel_proxy_request`
+    /// can abort it. It is unregistered when the guard drops.
+    fn track(&self, request_id: Option<String>) -> Result<(CancellationToken, InFlightGuard<'_>), String> {
+        let token = CancellationToken::new();
+        if let Some(id) = &request_id {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            if in_flight.contains_key(id) {
+                return Err(format!("A request with id {} is already in flight", id));
+            }
+            in_flight.insert(id.clone(), token.clone());
+        }
+        Ok((token, InFlightGuard { state: self, id: request_id }))
+    }
+
+    fn record(&self, bytes: u64, latency: Duration) {
+        let now = Instant::now();
+        let mut metrics = self.metrics.lock().unwrap();
+        metrics.samples.push_back(RequestSample { at: now, bytes, latency });
+        metrics.prune(now);
+    }
+}
+
+pub fn parse_header(name: &str, value: &str) -> Result<(HeaderName, HeaderValue), String> {
+    // Only the name goes into errors; the value may be a credential
+    let header_name = HeaderName::from_bytes(name.as_bytes()).map_err(|_| format!("Invalid header name: {}", name))?;
+    let mut header_value = HeaderValue::from_str(value).map_err(|_| format!("Invalid value for header {}", name))?;
+    header_value.set_sensitive(settings::is_secret_header(name));
+    Ok((header_name, header_value))
+}
+
+/// The configured default headers; any that no longer parse are skipped.
+fn default_headers(app_handle: &tauri::AppHandle) -> HeaderMap {
+    let configured = app_handle
+        .state::<SettingsStore>()
+        .settings
+        .lock()
+        .unwrap()
+        .proxy_headers
+        .clone();
+
+    let mut headers = HeaderMap::new();
+    for (name, value) in &configured {
+        match parse_header(name, value) {
+            Ok((name, value)) => {
+                headers.insert(name, value);
+            }
+            Err(e) => log::warn!("Skipping default proxy header: {}", e),
+        }
+    }
+    headers
+}
+
+/// Leaves the queue count when a queued request gets its slot or is abandoned.
+struct QueuedGuard<'a>(&'a AtomicUsize);
+
+impl Drop for QueuedGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Unregisters a cancellable request once it finishes, however it finishes.
+struct InFlightGuard<'a> {
+    state: &'a ProxyState,
+    id: Option<String>,
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(id) = &self.id {
+            self.state.in_flight.lock().unwrap().remove(id);
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ProxyResponse {
+    status: u16,
+    headers: HashMap<String, String>,
+    body: String,
+}
+
+#[derive(Serialize)]
+pub struct ValidatedProxyResponse {
+    #[serde(flatten)]
+    response: ProxyResponse,
+    schema: String,
+    valid: bool,
+    validation_errors: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct BackendThroughput {
+    window_secs: f64,
+    requests: usize,
+    requests_per_sec: f64,
+    bytes_per_sec: f64,
+    avg_latency_ms: f64,
+}
+
+#[tauri::command]
+pub async fn proxy_backend_request(
+    app_handle: tauri::AppHandle,
+    method: String,
+    path: String,
+    headers: Option<HashMap<String, String>>,
+    body: Option<String>,
+    request_id: Option<String>,
+) -> Result<ProxyResponse, String> {
+    let base_url = crate::get_backend_url(app_handle.clone())?;
+    let method = reqwest::Method::from_bytes(method.to_uppercase().as_bytes())
+        .map_err(|_| format!("Invalid HTTP method: {}", method))?;
+
+    let state = app_handle.state::<ProxyState>();
+    // Per-request headers replace defaults of the same name
+    let mut merged = default_headers(&app_handle);
+    for (name, value) in headers.unwrap_or_default() {
+        let (name, value) = parse_header(&name, &value)?;
+        merged.insert(name, value);
+    }
+    let mut request = state
+        .client
+        .request(method, format!("{}{}", base_url, path))
+        .headers(merged);
+
+    let request_bytes = body.as_ref().map_or(0, |b| b.len() as u64);
+    if let Some(body) = body {
+        request = request.body(body);
+    }
+
+    let (token, _guard) = state.track(request_id)?;
+
+    // Cancellable while queued, too
+    let _slot = tokio::select! {
+        slot = state.acquire_slot() => slot?,
+        _ = token.cancelled() => return Err("Request cancelled".to_string()),
+    };
+
+    let started = Instant::now();
+    let exchange = async {
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Backend request failed: {}", e))?;
+
+        let status = response.status().as_u16();
+        let headers = response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| {
+                value.to_str().ok().map(|v| (name.to_string(), v.to_string()))
+            })
+            .collect();
+        let body = response
+            .text()
+            .await
+            .map_err(|e| format!("Failed to read backend response: {}", e))?;
+
+        Ok::<_, String>(ProxyResponse { status, headers, body })
+    };
+
+    // Dropping the exchange future aborts the underlying HTTP request.
+    let response = tokio::select! {
+        result = exchange => result?,
+        _ = token.cancelled() => return Err("Request cancelled".to_string()),
+    };
+
+    state.record(request_bytes + response.body.len() as u64, started.elapsed());
+
+    Ok(response)
+}
+
+/// Sent on `save_url_to_file`'s progress channel.
+#[derive(Clone, Serialize)]
+pub struct DownloadProgress {
+    received_bytes: u64,
+    /// From Content-Length, when the backend sends one.
+    total_bytes: Option<u64>,
+}
+
+const DOWNLOAD_PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// A backend path like `/api/v1/...`, or a full URL under the backend's base URL.
+fn backend_download_url(base_url: &str, url: &str) -> Result<String, String> {
+    if url.starts_with('/') {
+        return Ok(format!("{}{}", base_url, url));
+    }
+    match url.strip_prefix(base_url) {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => Ok(url.to_string()),
+        _ => Err("Only backend URLs can be saved".to_string()),
+    }
+}
+
+/// Streams a backend response, such as a large export, straight to a file the user
+/// picks so the body never crosses IPC. Progress goes to `on_progress`; with a
+/// `request_id` the download can be aborted with `cancel_proxy_request`, which
+/// leaves the destination untouched.
+#[tauri::command]
+pub async fn save_url_to_file(
+    app_handle: tauri::AppHandle,
+    url: String,
+    filename: String,
+    default_extension: Option<String>,
+    on_progress: tauri::ipc::Channel<DownloadProgress>,
+    request_id: Option<String>,
+) -> Result<String, String> {
+    let base_url = crate::get_backend_url(app_handle.clone())?;
+    let url = backend_download_url(&base_url, &url)?;
+
+    let state = app_handle.state::<ProxyState>();
+    let (token, _guard) = state.track(request_id)?;
+
+    let path = tokio::select! {
+        path = crate::dialogs::save_path_with_extension(&app_handle, &filename, default_extension.as_deref()) => path?,
+        _ = token.cancelled() => return Err("Request cancelled".to_string()),
+    };
+    let temp = crate::files::temp_path(&path)?;
+
+    let started = Instant::now();
+    let download = async {
+        let mut response = state
+            .client
+            .get(&url)
+            .headers(default_headers(&app_handle))
+            .send()
+            .await
+            .map_err(|e| format!("Backend request failed: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("Backend returned HTTP {}", response.status().as_u16()));
+        }
+
+        let total_bytes = response.content_length();
+        let mut file = fs::File::create(&temp).map_err(|e| format!("Failed to create file: {}", e))?;
+        let mut received_bytes = 0;
+        let mut last_report = Instant::now();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| format!("Failed to read backend response: {}", e))?
+        {
+            file.write_all(&chunk).map_err(|e| format!("Failed to save file: {}", e))?;
+            received_bytes += chunk.len() as u64;
+            if last_report.elapsed() >= DOWNLOAD_PROGRESS_INTERVAL {
+                let _ = on_progress.send(DownloadProgress { received_bytes, total_bytes });
+                last_report = Instant::now();
+            }
+        }
+        let _ = on_progress.send(DownloadProgress { received_bytes, total_bytes });
+        Ok((file, received_bytes))
+    };
+
+    // Dropping the download closes the temp file, so it can be removed below
+    let result = tokio::select! {
+        result = download => result,
+        _ = token.cancelled() => Err("Request cancelled".to_string()),
+    };
+    let result = result.and_then(|(file, bytes)| crate::files::commit_temp(file, &temp, &path).map(|_| bytes));
+    let _ = fs::remove_file(&temp);
+
+    state.record(result?, started.elapsed());
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Loads a schema bundled under `resources/schemas`, e.g. "health" or "analysis-results".
+fn load_schema(app_handle: &tauri::AppHandle, name: &str) -> Result<serde_json::Value, String> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(format!("Invalid schema name: {}", name));
+    }
+
+    let path = app_handle
+        .path()
+        .resource_dir()
+        .map_err(|e| format!("Failed to get resource dir: {}", e))?
+        .join("schemas")
+        .join(format!("{}.json", name));
+
+    let content = fs::read(&path).map_err(|e| format!("Failed to read schema {}: {}", name, e))?;
+    serde_json::from_slice(&content).map_err(|e| format!("Invalid schema {}: {}", name, e))
+}
+
+fn validate(schema: &serde_json::Value, body: &str) -> Result<Vec<String>, String> {
+    let validator = jsonschema::validator_for(schema)
+        .map_err(|e| format!("Failed to compile schema: {}", e))?;
+
+    let instance: serde_json::Value = match serde_json::from_str(body) {
+        Ok(instance) => instance,
+        Err(e) => return Ok(vec![format!("Response is not valid JSON: {}", e)]),
+    };
+
+    Ok(validator
+        .iter_errors(&instance)
+        .map(|error| match error.instance_path.to_string() {
+            path if path.is_empty() => error.to_string(),
+            path => format!("{}: {}", path, error),
+        })
+        .collect())
+}
+
+/// Same as `proxy_backend_request`, but also checks the response body against a
+/// bundled schema. Mismatches are reported alongside the response rather than as an error.
+#[tauri::command]
+pub async fn proxy_backend_request_validated(
+    app_handle: tauri::AppHandle,
+    method: String,
+    path: String,
+    headers: Option<HashMap<String, String>>,
+    body: Option<String>,
+    request_id: Option<String>,
+    schema_name: String,
+) -> Result<ValidatedProxyResponse, String> {
+    let schema = load_schema(&app_handle, &schema_name)?;
+    let response = proxy_backend_request(app_handle, method, path, headers, body, request_id).await?;
+    let validation_errors = validate(&schema, &response.body)?;
+
+    Ok(ValidatedProxyResponse {
+        response,
+        schema: schema_name,
+        valid: validation_errors.is_empty(),
+        validation_errors,
+    })
+}
+
+#[derive(Serialize)]
+pub struct WarmupResult {
+    elapsed_ms: f64,
+    failed_paths: Vec<String>,
+}
+
+/// Hits a few read-only endpoints so the backend's lazy imports happen now rather
+/// than during the user's first analysis. Call it once the backend is ready.
+#[tauri::command]
+pub async fn warm_backend(app_handle: tauri::AppHandle) -> Result<WarmupResult, String> {
+    let base_url = crate::get_backend_url(app_handle.clone())?;
+    let state = app_handle.state::<ProxyState>();
+    let started = Instant::now();
+    let mut failed_paths = Vec::new();
+
+    for path in WARMUP_PATHS {
+        let request = state.client.get(format!("{}{}", base_url, path)).headers(default_headers(&app_handle));
+        let ok = match request.send().await {
+            Ok(response) => response.status().is_success(),
+            Err(e) => {
+                log::debug!("Warm-up request to {} failed: {}", path, e);
+                false
+            }
+        };
+        if !ok {
+            failed_paths.push(path.to_string());
+        }
+    }
+
+    Ok(WarmupResult {
+        elapsed_ms: started.elapsed().as_secs_f64() * 1000.0,
+        failed_paths,
+    })
+}
+
+#[derive(Serialize)]
+pub struct RuntimeConfig {
+    /// What the backend reports from `/api/v1/config`; `None` for backends without it.
+    backend: Option<serde_json::Value>,
+    /// What we launched it with.
+    launch: crate::settings::BackendSettings,
+}
+
+/// GETs a backend JSON endpoint; `None` when it answers 404.
+pub async fn get_json(app_handle: &tauri::AppHandle, path: &str) -> Result<Option<serde_json::Value>, String> {
+    let base_url = crate::get_backend_url(app_handle.clone())?;
+    let state = app_handle.state::<ProxyState>();
+
+    let response = state
+        .client
+        .get(format!("{}{}", base_url, path))
+        .headers(default_headers(app_handle))
+        .send()
+        .await
+        .map_err(|e| format!("Backend request failed: {}", e))?;
+
+    match response.status() {
+        reqwest::StatusCode::NOT_FOUND => Ok(None),
+        status if status.is_success() => response
+            .json()
+            .await
+            .map(Some)
+            .map_err(|e| format!("Invalid response from backend for {}: {}", path, e)),
+        status => Err(format!("Backend returned {} for {}", status, path)),
+    }
+}
+
+/// POSTs `body` to a backend path and returns the JSON response.
+pub async fn post_json(
+    app_handle: &tauri::AppHandle,
+    path: &str,
+    body: &serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    let base_url = crate::get_backend_url(app_handle.clone())?;
+    let state = app_handle.state::<ProxyState>();
+
+    let response = state
+        .client
+        .post(format!("{}{}", base_url, path))
+        .headers(default_headers(app_handle))
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| format!("Backend request failed: {}", e))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        // FastAPI puts the reason in `detail`
+        let detail = response
+            .json::<serde_json::Value>()
+            .await
+            .ok()
+            .and_then(|body| body.get("detail").and_then(|d| d.as_str()).map(str::to_string));
+        return Err(match detail {
+            Some(detail) => format!("Backend returned {} for {}: {}", status, path, detail),
+            None => format!("Backend returned {} for {}", status, path),
+        });
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid response from backend for {}: {}", path, e))
+}
+
+#[tauri::command]
+pub async fn get_backend_runtime_config(app_handle: tauri::AppHandle) -> Result<RuntimeConfig, String> {
+    Ok(RuntimeConfig {
+        backend: get_json(&app_handle, "/api/v1/config").await?,
+        launch: crate::settings::backend(&app_handle),
+    })
+}
+
+/// Aborts an in-flight proxied request; returns whether one was found.
+#[tauri::command]
+pub fn cancel_proxy_request(app_handle: tauri::AppHandle, id: String) -> bool {
+    let state = app_handle.state::<ProxyState>();
+    let token = state.in_flight.lock().unwrap().remove(&id);
+
+    match token {
+        Some(token) => {
+            token.cancel();
+            true
+        }
+        None => false,
+    }
+}
+
+#[tauri::command]
+pub fn get_backend_throughput(app_handle: tauri::AppHandle) -> BackendThroughput {
+    let state = app_handle.state::<ProxyState>();
+    let now = Instant::now();
+    let mut metrics = state.metrics.lock().unwrap();
+    metrics.prune(now);
+
+    // Right after a restart the window is only partially filled.
+    let window = now.duration_since(metrics.reset_at).min(THROUGHPUT_WINDOW);
+    let window_secs = window.as_secs_f64().max(1.0);
+
+    let requests = metrics.samples.len();
+    let bytes: u64 = metrics.samples.iter().map(|s| s.bytes).sum();
+    let total_latency: Duration = metrics.samples.iter().map(|s| s.latency).sum();
+    let avg_latency_ms = if requests > 0 {
+        total_latency.as_secs_f64() * 1000.0 / requests as f64
+    } else {
+        0.0
+    };
+
+    BackendThroughput {
+        window_secs,
+        requests,
+        requests_per_sec: requests as f64 / window_secs,
+        bytes_per_sec: bytes as f64 / window_secs,
+        avg_latency_ms,
+    }
+}
+
+#[derive(Serialize)]
+pub struct ProxyQueue {
+    in_flight: usize,
+    queued: usize,
+    limits: ProxyLimits,
+}
+
+#[tauri::command]
+pub fn get_proxy_queue_depth(app_handle: tauri::AppHandle) -> ProxyQueue {
+    let state = app_handle.state::<ProxyState>();
+    let limiter = state.limiter.lock().unwrap();
+    ProxyQueue {
+        in_flight: limiter.limits.max_in_flight - limiter.slots.available_permits(),
+        queued: state.queued.load(Ordering::SeqCst),
+        limits: limiter.limits,
+    }
+}
+
+#[tauri::command]
+pub fn set_proxy_limits(app_handle: tauri::AppHandle, limits: ProxyLimits) -> Result<(), String> {
+    if limits.max_in_flight == 0 {
+        return Err("At least one request must be allowed in flight".to_string());
+    }
+
+    {
+        let store = app_handle.state::<SettingsStore>();
+        let mut current = store.settings.lock().unwrap();
+        current.proxy_limits = limits;
+        settings::save(&app_handle, &current)?;
+    }
+
+    app_handle.state::<ProxyState>().set_limits(limits);
+    Ok(())
+}
+
+/// Replaces the default headers. Values are validated but never logged.
+#[tauri::command]
+pub fn set_proxy_default_headers(
+    app_handle: tauri::AppHandle,
+    headers: BTreeMap<String, String>,
+) -> Result<(), String> {
+    for (name, value) in &headers {
+        parse_header(name, value)?;
+    }
+
+    let store = app_handle.state::<SettingsStore>();
+    let mut current = store.settings.lock().unwrap();
+    current.proxy_headers = headers;
+    settings::save(&app_handle, &current)
+}
+
+/// The default headers with credential values masked.
+#[tauri::command]
+pub fn get_proxy_default_headers(app_handle: tauri::AppHandle) -> BTreeMap<String, String> {
+    let store = app_handle.state::<SettingsStore>();
+    let current = store.settings.lock().unwrap();
+    current
+        .proxy_headers
+        .iter()
+        .map(|(name, value)| {
+            let shown = if settings::is_secret_header(name) { "***".to_string() } else { value.clone() };
+            (name.clone(), shown)
+        })
+        .collect()
+}
@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::Manager;
+use tokio_util::sync::CancellationToken;
+
+use crate::PythonBackend;
+
+/// Cancellation tokens of pending `await_backend_ready_cancellable` calls, by caller id.
+#[derive(Default)]
+pub struct ReadyWaiters {
+    pending: Mutex<HashMap<String, CancellationToken>>,
+}
+
+/// Unregisters a waiter however its await ends.
+struct WaiterGuard<'a> {
+    waiters: &'a ReadyWaiters,
+    id: String,
+}
+
+impl Drop for WaiterGuard<'_> {
+    fn drop(&mut self) {
+        self.waiters.pending.lock().unwrap().remove(&self.id);
+    }
+}
+
+/// Resolves with the backend URL once the backend is running, straight away if it
+/// already is. Every waiter shares one readiness signal; `cancel_await(token_id)`
+/// ends just this one.
+#[tauri::command]
+pub async fn await_backend_ready_cancellable(app_handle: tauri::AppHandle, token_id: String) -> Result<String, String> {
+    let waiters = app_handle.state::<ReadyWaiters>();
+    let token = CancellationToken::new();
+    {
+        let mut pending = waiters.pending.lock().unwrap();
+        if pending.contains_key(&token_id) {
+            return Err(format!("A wait with id {} is already pending", token_id));
+        }
+        pending.insert(token_id.clone(), token.clone());
+    }
+    let _guard = WaiterGuard {
+        waiters: &waiters,
+        id: token_id,
+    };
+
+    let state = app_handle.state::<PythonBackend>();
+    let mut ready = state.ready.subscribe();
+    tokio::select! {
+        result = ready.wait_for(|running| *running) => {
+            result.map_err(|_| "Backend state is gone".to_string())?;
+        }
+        _ = token.cancelled() => return Err("Cancelled".to_string()),
+        _ = state.shutdown.cancelled() => return Err("App is shutting down".to_string()),
+    }
+
+    crate::get_backend_url(app_handle.clone())
+}
+
+/// Cancels a pending wait; returns whether one was found.
+#[tauri::command]
+pub fn cancel_await(app_handle: tauri::AppHandle, token_id: String) -> bool {
+    let waiters = app_handle.state::<ReadyWaiters>();
+    let token = waiters.pending.lock().unwrap().remove(&token_id);
+
+    match token {
+        Some(token) => {
+            token.cancel();
+            true
+        }
+        None => false,
+    }
+}
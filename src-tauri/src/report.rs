@@ -0,0 +1,407 @@
+//! PDF export of a saved analysis run, for people who want a document rather
+//! than the JSON or HTML export.
+//!
+//! The PDF is written directly: text only, in the standard Helvetica fonts that
+//! every PDF reader provides (so nothing is embedded), wrapped and paginated here.
+
+use flate2::write::ZlibEncoder;
+use serde_json::Value;
+use std::io::Write;
+use std::path::Path;
+
+/// US Letter, in points.
+const PAGE_WIDTH: f32 = 612.0;
+const PAGE_HEIGHT: f32 = 792.0;
+/// The running header and page numbers sit inside the top and bottom margins.
+const MARGIN: f32 = 54.0;
+const BODY_WIDTH: f32 = PAGE_WIDTH - 2.0 * MARGIN;
+
+/// Helvetica advance widths for `' '..='~'`, in thousandths of an em.
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, // ' '..='/'
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, // '0'..='?'
+    1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778, // '@'..='O'
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556, // 'P'..='_'
+    333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556, // '`'..='o'
+    556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584, // 'p'..='~'
+];
+
+const SEVERITIES: &[&str] = &["critical", "high", "medium", "low", "info"];
+
+#[derive(Clone, Copy)]
+enum Font {
+    Regular,
+    Bold,
+}
+
+#[derive(Clone, Copy)]
+struct Style {
+    font: Font,
+    size: f32,
+    rgb: [f32; 3],
+}
+
+const BLACK: [f32; 3] = [0.1, 0.1, 0.1];
+const GREY: [f32; 3] = [0.4, 0.4, 0.4];
+
+const TITLE: Style = Style { font: Font::Bold, size: 20.0, rgb: BLACK };
+const HEADING: Style = Style { font: Font::Bold, size: 14.0, rgb: BLACK };
+const FINDING_TITLE: Style = Style { font: Font::Bold, size: 10.5, rgb: BLACK };
+const BODY: Style = Style { font: Font::Regular, size: 10.0, rgb: BLACK };
+const NOTE: Style = Style { font: Font::Regular, size: 8.5, rgb: GREY };
+
+fn severity_rgb(severity: &str) -> [f32; 3] {
+    match severity {
+        "critical" => [0.75, 0.1, 0.1],
+        "high" => [0.85, 0.35, 0.0],
+        "medium" => [0.7, 0.5, 0.0],
+        "low" => [0.15, 0.4, 0.7],
+        _ => GREY,
+    }
+}
+
+fn text_width(text: &str, style: Style) -> f32 {
+    let em: u32 = text
+        .chars()
+        .map(|c| match c {
+            ' '..='~' => HELVETICA_WIDTHS[c as usize - 32] as u32,
+            _ => 556,
+        })
+        .sum();
+    // Bold glyphs run up to ~10% wider; overestimating only wraps a little early
+    let scale = match style.font {
+        Font::Regular => 1.0,
+        Font::Bold => 1.1,
+    };
+    em as f32 * scale * style.size / 1000.0
+}
+
+/// Breaks `text` into lines no wider than `width`, splitting words that are
+/// longer than a line on their own.
+fn wrap(text: &str, style: Style, width: f32) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let candidate = if line.is_empty() { word.to_string() } else { format!("{} {}", line, word) };
+            if text_width(&candidate, style) <= width {
+                line = candidate;
+                continue;
+            }
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            for c in word.chars() {
+                line.push(c);
+                if text_width(&line, style) > width && line.chars().count() > 1 {
+                    line.pop();
+                    lines.push(std::mem::replace(&mut line, c.to_string()));
+                }
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// A PDF string literal in WinAnsiEncoding; characters it can't represent
+/// become `?`.
+fn pdf_string(text: &str) -> Vec<u8> {
+    let mut out = vec![b'('];
+    for c in text.chars() {
+        let byte = match c {
+            '\t' => b' ',
+            ' '..='~' => c as u8,
+            '\u{a0}'..='\u{ff}' => c as u32 as u8,
+            '\u{2018}' => 0x91,
+            '\u{2019}' => 0x92,
+            '\u{201c}' => 0x93,
+            '\u{201d}' => 0x94,
+            '\u{2022}' => 0x95,
+            '\u{2013}' => 0x96,
+            '\u{2014}' => 0x97,
+            '\u{2026}' => 0x85,
+            _ => b'?',
+        };
+        if matches!(byte, b'(' | b')' | b'\\') {
+            out.push(b'\\');
+        }
+        out.push(byte);
+    }
+    out.push(b')');
+    out
+}
+
+fn show_text(content: &mut Vec<u8>, x: f32, y: f32, style: Style, text: &str) {
+    let font = match style.font {
+        Font::Regular => "F1",
+        Font::Bold => "F2",
+    };
+    let [r, g, b] = style.rgb;
+    content.extend_from_slice(
+        format!("BT {} {} {} rg /{} {} Tf {:.2} {:.2} Td ", r, g, b, font, style.size, x, y).as_bytes(),
+    );
+    content.extend_from_slice(&pdf_string(text));
+    content.extend_from_slice(b" Tj ET\n");
+}
+
+fn rule(content: &mut Vec<u8>, y: f32) {
+    content.extend_from_slice(format!("0.8 G 0.5 w {} {:.2} m {} {:.2} l S\n", MARGIN, y, PAGE_WIDTH - MARGIN, y).as_bytes());
+}
+
+/// Lays text out top to bottom, starting a new page when the current one fills.
+struct Layout {
+    /// Content stream of each page so far.
+    pages: Vec<Vec<u8>>,
+    /// Baseline of the last line written.
+    y: f32,
+    header_left: String,
+    header_right: String,
+}
+
+impl Layout {
+    fn new(header_left: String, header_right: String) -> Self {
+        let mut layout = Self {
+            pages: Vec::new(),
+            y: 0.0,
+            header_left,
+            header_right,
+        };
+        layout.new_page();
+        layout
+    }
+
+    fn new_page(&mut self) {
+        let mut content = Vec::new();
+        let baseline = PAGE_HEIGHT - MARGIN + 12.0;
+        show_text(&mut content, MARGIN, baseline, NOTE, &self.header_left);
+        let right_x = PAGE_WIDTH - MARGIN - text_width(&self.header_right, NOTE);
+        show_text(&mut content, right_x, baseline, NOTE, &self.header_right);
+        rule(&mut content, baseline - 6.0);
+        self.pages.push(content);
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+
+    /// Moves to a new page unless `height` more points fit on this one.
+    fn keep(&mut self, height: f32) {
+        if self.y - height < MARGIN {
+            self.new_page();
+        }
+    }
+
+    fn gap(&mut self, height: f32) {
+        self.y -= height;
+    }
+
+    fn paragraph(&mut self, text: &str, style: Style, indent: f32) {
+        let leading = style.size * 1.3;
+        for line in wrap(text, style, BODY_WIDTH - indent) {
+            self.keep(leading);
+            self.y -= leading;
+            let page = self.pages.last_mut().expect("layout always has a page");
+            show_text(page, MARGIN + indent, self.y, style, &line);
+        }
+    }
+
+    fn rule(&mut self) {
+        self.keep(8.0);
+        self.y -= 6.0;
+        rule(self.pages.last_mut().expect("layout always has a page"), self.y);
+        self.y -= 2.0;
+    }
+}
+
+/// Serializes the laid-out pages, adding page numbers to each.
+fn write_pdf(mut pages: Vec<Vec<u8>>, title: &str) -> Result<Vec<u8>, String> {
+    let count = pages.len();
+    for (i, content) in pages.iter_mut().enumerate() {
+        let label = format!("Page {} of {}", i + 1, count);
+        let x = PAGE_WIDTH - MARGIN - text_width(&label, NOTE);
+        show_text(content, x, MARGIN - 6.0, NOTE, &label);
+    }
+
+    // 1 catalog, 2 page tree, 3-4 fonts, 5 info, then a page and its contents for each page
+    let first_page = 6;
+    let kids: Vec<String> = (0..count).map(|i| format!("{} 0 R", first_page + 2 * i)).collect();
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), count).into_bytes(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_vec(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>".to_vec(),
+        [b"<< /Title ".as_slice(), &pdf_string(title), b" /Producer (Cribl Health Check) >>"].concat(),
+    ];
+    for (i, content) in pages.iter().enumerate() {
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                PAGE_WIDTH,
+                PAGE_HEIGHT,
+                first_page + 2 * i + 1
+            )
+            .into_bytes(),
+        );
+        let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder
+            .write_all(content)
+            .map_err(|e| format!("Failed to compress PDF page: {}", e))?;
+        let compressed = encoder
+            .finish()
+            .map_err(|e| format!("Failed to compress PDF page: {}", e))?;
+        let mut stream = format!("<< /Length {} /Filter /FlateDecode >>\nstream\n", compressed.len()).into_bytes();
+        stream.extend_from_slice(&compressed);
+        stream.extend_from_slice(b"\nendstream");
+        objects.push(stream);
+    }
+
+    let mut pdf = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+        pdf.extend_from_slice(object);
+        pdf.extend_from_slice(b"\nendobj\n");
+    }
+
+    let xref = pdf.len();
+    pdf.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+    for offset in offsets {
+        pdf.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    pdf.extend_from_slice(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R /Info 5 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref
+        )
+        .as_bytes(),
+    );
+    Ok(pdf)
+}
+
+fn severity_rank(finding: &Value) -> usize {
+    let severity = finding.get("severity").and_then(Value::as_str).unwrap_or_default();
+    SEVERITIES.iter().position(|s| *s == severity).unwrap_or(SEVERITIES.len())
+}
+
+fn strings(finding: &Value, key: &str) -> Vec<String> {
+    finding
+        .get(key)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .map(str::to_string)
+        .collect()
+}
+
+fn render_finding(layout: &mut Layout, finding: &Value) {
+    let text = |key: &str| finding.get(key).and_then(Value::as_str).unwrap_or_default();
+    let severity = text("severity");
+
+    // Keep a finding's title with at least the first lines of its description
+    layout.keep(48.0);
+    layout.gap(4.0);
+    let label = Style {
+        rgb: severity_rgb(severity),
+        ..FINDING_TITLE
+    };
+    layout.paragraph(&format!("[{}] {}", severity.to_uppercase(), text("title")), label, 0.0);
+    layout.paragraph(text("description"), BODY, 12.0);
+
+    let impact = text("estimated_impact");
+    if !impact.is_empty() {
+        layout.paragraph(&format!("Impact: {}", impact), BODY, 12.0);
+    }
+    let components = strings(finding, "affected_components");
+    if !components.is_empty() {
+        layout.paragraph(&format!("Affected: {}", components.join(", ")), BODY, 12.0);
+    }
+    let steps = strings(finding, "remediation_steps");
+    if !steps.is_empty() {
+        layout.paragraph("Remediation:", BODY, 12.0);
+        for (i, step) in steps.iter().enumerate() {
+            layout.paragraph(&format!("{}. {}", i + 1, step), BODY, 24.0);
+        }
+    }
+    for link in strings(finding, "documentation_links") {
+        layout.paragraph(&link, NOTE, 12.0);
+    }
+}
+
+/// Renders an analysis result (the backend's JSON export) as a PDF.
+pub fn render(result: &Value) -> Result<Vec<u8>, String> {
+    let text = |key: &str| result.get(key).and_then(Value::as_str).filter(|s| !s.is_empty());
+    let deployment = text("deployment_name")
+        .or_else(|| text("deployment_id"))
+        .unwrap_or("Cribl deployment");
+    let timestamp = text("completed_at")
+        .or_else(|| text("started_at"))
+        .or_else(|| text("created_at"))
+        .unwrap_or_default();
+
+    let mut layout = Layout::new(deployment.to_string(), timestamp.to_string());
+    layout.paragraph("Cribl Health Check Report", TITLE, 0.0);
+    layout.gap(6.0);
+    layout.paragraph(&format!("Deployment: {}", deployment), BODY, 0.0);
+    if !timestamp.is_empty() {
+        layout.paragraph(&format!("Completed: {}", timestamp), BODY, 0.0);
+    }
+    if let Some(analysis_id) = text("analysis_id") {
+        layout.paragraph(&format!("Analysis: {}", analysis_id), NOTE, 0.0);
+    }
+
+    let findings: Vec<&Value> = result.get("findings").and_then(Value::as_array).into_iter().flatten().collect();
+    layout.gap(12.0);
+    match result.get("health_score").and_then(Value::as_f64) {
+        Some(score) => layout.paragraph(&format!("Health score: {:.0} / 100", score), HEADING, 0.0),
+        None => layout.paragraph("Health score: not available", HEADING, 0.0),
+    }
+    let counts: Vec<String> = SEVERITIES
+        .iter()
+        .map(|severity| {
+            let n = findings
+                .iter()
+                .filter(|f| f.get("severity").and_then(Value::as_str) == Some(severity))
+                .count();
+            format!("{} {}", n, severity)
+        })
+        .collect();
+    layout.paragraph(&format!("{} findings: {}", findings.len(), counts.join(", ")), BODY, 0.0);
+
+    if findings.is_empty() {
+        layout.gap(12.0);
+        layout.paragraph("No findings.", BODY, 0.0);
+    }
+    for (group, by_key) in crate::history::group_findings(result) {
+        let mut group_findings: Vec<&Value> = by_key.values().collect();
+        group_findings.sort_by_key(|f| severity_rank(f));
+
+        layout.keep(80.0);
+        layout.gap(18.0);
+        layout.paragraph(&format!("Worker group: {}", group), HEADING, 0.0);
+        layout.paragraph(
+            &format!(
+                "{} findings, group score {:.0}",
+                group_findings.len(),
+                crate::scoring::score_findings(group_findings.iter().copied())
+            ),
+            NOTE,
+            0.0,
+        );
+        layout.rule();
+        for finding in group_findings {
+            render_finding(&mut layout, finding);
+        }
+    }
+
+    write_pdf(layout.pages, &format!("Cribl Health Check - {}", deployment))
+}
+
+/// Writes saved run `run_id` to `path` as a paginated PDF: overall health score,
+/// then findings by worker group, most severe first.
+#[tauri::command]
+pub async fn export_pdf(app_handle: tauri::AppHandle, run_id: String, path: String) -> Result<(), String> {
+    let result = crate::history::get_analysis_run(app_handle, run_id)?;
+    let pdf = render(&result)?;
+    crate::files::write_file_atomic(Path::new(&path), &pdf)
+}
@@ -0,0 +1,190 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use tauri::{Manager, WebviewUrl, WebviewWindowBuilder};
+
+use crate::settings::SettingsStore;
+
+/// File name prefixes used for exported reports: ours, and the web UI's download name.
+const REPORT_PREFIXES: &[&str] = &["cribl-hc-report-", "health-check-"];
+const REPORT_EXTENSIONS: &[&str] = &["json", "html", "md", "pdf"];
+
+const DEFAULT_SCAN_LIMIT: usize = 500;
+/// How deep a recursive scan goes below each directory.
+const MAX_SCAN_DEPTH: usize = 8;
+
+/// Saved reports open here rather than in the main window. It isn't listed in any
+/// capability, so a report's scripts get no access to app commands.
+const REPORT_WINDOW: &str = "report-viewer";
+
+#[derive(Serialize)]
+pub struct ReportFile {
+    name: String,
+    path: String,
+    size: u64,
+    /// Milliseconds since the Unix epoch.
+    modified_ms: u64,
+}
+
+fn is_report_name(name: &str) -> bool {
+    let Some((stem, extension)) = name.rsplit_once('.') else {
+        return false;
+    };
+
+    REPORT_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+        && REPORT_PREFIXES
+            .iter()
+            .any(|prefix| stem.len() > prefix.len() && stem.starts_with(prefix))
+}
+
+fn report_file(entry: &fs::DirEntry) -> Option<ReportFile> {
+    let name = entry.file_name().to_str()?.to_string();
+    if !is_report_name(&name) {
+        return None;
+    }
+
+    let metadata = entry.metadata().ok().filter(|m| m.is_file())?;
+    let modified_ms = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_millis() as u64);
+
+    Some(ReportFile {
+        name,
+        path: entry.path().to_string_lossy().to_string(),
+        size: metadata.len(),
+        modified_ms,
+    })
+}
+
+/// Lists report files directly inside `dir`, newest first. A missing directory yields no reports.
+fn list_reports(dir: &Path) -> Result<Vec<ReportFile>, String> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read {}: {}", dir.display(), e)),
+    };
+
+    let mut reports: Vec<ReportFile> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| report_file(&entry))
+        .collect();
+
+    reports.sort_by_key(|r| std::cmp::Reverse(r.modified_ms));
+    Ok(reports)
+}
+
+/// Adds reports under `dir` to `found`, keyed by canonical path so a folder reached
+/// twice (listed twice, or via a symlink) counts once. Unreadable directories are skipped.
+fn collect_reports(dir: &Path, depth: usize, found: &mut HashMap<PathBuf, ReportFile>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            log::debug!("Skipping {} while scanning for reports: {}", dir.display(), e);
+            return;
+        }
+    };
+
+    for entry in entries.filter_map(Result::ok) {
+        // Don't follow directory symlinks; they can loop
+        let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+        if is_dir {
+            if depth > 0 {
+                collect_reports(&entry.path(), depth - 1, found);
+            }
+        } else if let Some(report) = report_file(&entry) {
+            let key = fs::canonicalize(entry.path()).unwrap_or_else(|_| entry.path());
+            found.entry(key).or_insert(report);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn list_reports_in_downloads() -> Result<Vec<ReportFile>, String> {
+    // Honours XDG_DOWNLOAD_DIR / user-dirs.dirs on Linux and the known folder elsewhere
+    let Some(dir) = dirs::download_dir() else {
+        return Ok(Vec::new());
+    };
+
+    list_reports(&dir)
+}
+
+/// Finds reports across `dirs`, newest first and at most `limit` of them. With no
+/// directories given, scans the recent directories and Downloads.
+#[tauri::command]
+pub async fn scan_for_reports(
+    app_handle: tauri::AppHandle,
+    dirs: Vec<String>,
+    recursive: Option<bool>,
+    limit: Option<usize>,
+) -> Result<Vec<ReportFile>, String> {
+    let mut roots: Vec<PathBuf> = dirs.into_iter().map(PathBuf::from).collect();
+    if roots.is_empty() {
+        let store = app_handle.state::<SettingsStore>();
+        roots.extend(store.settings.lock().unwrap().recent_dirs.iter().map(PathBuf::from));
+        roots.extend(::dirs::download_dir());
+    }
+
+    let depth = if recursive.unwrap_or(false) { MAX_SCAN_DEPTH } else { 0 };
+    let limit = limit.unwrap_or(DEFAULT_SCAN_LIMIT);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut found = HashMap::new();
+        for root in &roots {
+            collect_reports(root, depth, &mut found);
+        }
+
+        let mut reports: Vec<ReportFile> = found.into_values().collect();
+        reports.sort_by_key(|r| std::cmp::Reverse(r.modified_ms));
+        reports.truncate(limit);
+        reports
+    })
+    .await
+    .map_err(|e| format!("Failed to scan for reports: {}", e))
+}
+
+/// Opens a saved HTML report scrolled to `anchor`, reusing the report window if it is
+/// already open. An in-app window is used because desktop file handlers drop the
+/// `#fragment` from file URLs.
+#[tauri::command]
+pub async fn open_report_at_anchor(app_handle: tauri::AppHandle, path: String, anchor: String) -> Result<(), String> {
+    // Not canonicalized: Windows would hand back a `\\?\` path, which has no file URL
+    let path = Path::new(&path);
+    if !path.is_absolute() {
+        return Err(format!("Report path must be absolute: {}", path.display()));
+    }
+    if !path.is_file() {
+        return Err(format!("Report not found: {}", path.display()));
+    }
+    let is_html = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("html") || e.eq_ignore_ascii_case("htm"));
+    if !is_html {
+        return Err(format!("Not an HTML report: {}", path.display()));
+    }
+
+    // Percent-encodes spaces and non-ASCII characters in both the path and the anchor
+    let mut url =
+        url::Url::from_file_path(path).map_err(|_| format!("Failed to build a URL for {}", path.display()))?;
+    let anchor = anchor.trim_start_matches('#');
+    url.set_fragment((!anchor.is_empty()).then_some(anchor));
+
+    if let Some(window) = app_handle.get_webview_window(REPORT_WINDOW) {
+        window
+            .navigate(url)
+            .map_err(|e| format!("Failed to open report: {}", e))?;
+        return window.set_focus().map_err(|e| format!("Failed to focus report window: {}", e));
+    }
+
+    let title = path.file_name().map_or_else(|| "Report".into(), |n| n.to_string_lossy());
+    WebviewWindowBuilder::new(&app_handle, REPORT_WINDOW, WebviewUrl::External(url))
+        .title(title)
+        .inner_size(1100.0, 800.0)
+        .build()
+        .map_err(|e| format!("Failed to open report window: {}", e))?;
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use std::fs;
+use std::path::Path;
+use tauri::{Emitter, Manager};
+
+use crate::settings::{self, AppSettings, SettingsStore};
+
+fn remove_dir_contents(dir: &Path) -> Result<(), String> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(format!("Failed to read {}: {}", dir.display(), e)),
+    };
+
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        let result = if path.is_dir() { fs::remove_dir_all(&path) } else { fs::remove_file(&path) };
+        result.map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+    }
+
+    Ok(())
+}
+
+/// Leftovers from benchmark runs that never got to clean up after themselves.
+fn remove_temp_files() {
+    let Ok(entries) = fs::read_dir(std::env::temp_dir()) else {
+        return;
+    };
+
+    for entry in entries.filter_map(Result::ok) {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with("cribl-hc-benchmark-") && name.ends_with(".tmp") {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+/// Stops the backend and wipes settings, profiles, recent files and caches, then
+/// re-seeds defaults. Logs are kept so the reset itself can be diagnosed.
+#[tauri::command]
+pub fn factory_reset(app_handle: tauri::AppHandle, confirm: bool) -> Result<(), String> {
+    if !confirm {
+        return Err("Factory reset must be confirmed".to_string());
+    }
+
+    log::warn!("Performing factory reset");
+
+    crate::health::stop_health_monitor(app_handle.clone());
+    if app_handle.state::<crate::PythonBackend>().lifecycle() != crate::Lifecycle::Stopped {
+        crate::stop_backend_process(&app_handle)?;
+    }
+
+    let cache_dir = app_handle
+        .path()
+        .app_cache_dir()
+        .map_err(|e| format!("Failed to get cache dir: {}", e))?;
+    remove_dir_contents(&cache_dir)?;
+    remove_temp_files();
+
+    let defaults = AppSettings::default();
+    settings::save(&app_handle, &defaults)?;
+    *app_handle.state::<SettingsStore>().settings.lock().unwrap() = defaults;
+    crate::profiles::reset(&app_handle)?;
+
+    if let Err(e) = app_handle.emit("factory-reset", ()) {
+        log::warn!("Failed to emit factory-reset: {}", e);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,314 @@
+//! CPU and memory used by the backend, and memory left on the host, sampled
+//! in the background and sent to the UI as `resource-usage` events.
+//!
+//! The backend's figures cover its whole process tree, so uvicorn workers count.
+//! CPU is a percentage of one core, as `top` shows it, so a busy backend on a
+//! many-core machine can exceed 100.
+//!
+//! With a memory ceiling set, a backend that grows past it raises a
+//! `resource-ceiling-exceeded` event and a notification, once each time it
+//! crosses, and is restarted if the settings ask for that.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{Emitter, Manager};
+
+use crate::settings::{self, SettingsStore};
+use crate::{Lifecycle, PythonBackend};
+
+const MIN_INTERVAL_SECS: u64 = 1;
+const MAX_INTERVAL_SECS: u64 = 3600;
+/// Python alone needs about this much; a lower ceiling would restart it endlessly.
+const MIN_CEILING_MB: u64 = 128;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CeilingAction {
+    #[default]
+    Warn,
+    Restart,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResourceMonitorSettings {
+    pub interval_secs: u64,
+    /// Backend memory, across its process tree, that counts as too much.
+    pub memory_ceiling_mb: Option<u64>,
+    pub ceiling_action: CeilingAction,
+}
+
+impl Default for ResourceMonitorSettings {
+    fn default() -> Self {
+        Self {
+            interval_secs: 5,
+            memory_ceiling_mb: None,
+            ceiling_action: CeilingAction::default(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct BackendUsage {
+    pid: u32,
+    processes: usize,
+    cpu_percent: Option<f64>,
+    memory_bytes: Option<u64>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct HostMemory {
+    total_bytes: u64,
+    available_bytes: u64,
+}
+
+#[derive(Clone, Serialize)]
+pub struct ResourceUsage {
+    /// Milliseconds since the Unix epoch.
+    timestamp_ms: u64,
+    /// `None` while the backend isn't running.
+    backend: Option<BackendUsage>,
+    host: Option<HostMemory>,
+    memory_ceiling_bytes: Option<u64>,
+}
+
+#[derive(Clone, Serialize)]
+struct CeilingExceeded {
+    memory_bytes: u64,
+    memory_ceiling_bytes: u64,
+    action: CeilingAction,
+}
+
+/// CPU time of the backend tree at the previous sample, to measure the next against.
+struct CpuReading {
+    at: Instant,
+    root: u32,
+    cpu_time_ms: u64,
+}
+
+#[derive(Default)]
+pub struct ResourceMonitor {
+    last_cpu: Mutex<Option<CpuReading>>,
+    /// Whether the backend was over its ceiling at the last sample.
+    over_ceiling: Mutex<bool>,
+}
+
+pub fn validate(settings: &ResourceMonitorSettings) -> Result<(), String> {
+    if !(MIN_INTERVAL_SECS..=MAX_INTERVAL_SECS).contains(&settings.interval_secs) {
+        return Err(format!(
+            "The sampling interval must be between {} and {} seconds",
+            MIN_INTERVAL_SECS, MAX_INTERVAL_SECS
+        ));
+    }
+    if settings.memory_ceiling_mb.is_some_and(|mb| mb < MIN_CEILING_MB) {
+        return Err(format!("The memory ceiling must be at least {} MB", MIN_CEILING_MB));
+    }
+    Ok(())
+}
+
+fn monitor_settings(app_handle: &tauri::AppHandle) -> ResourceMonitorSettings {
+    app_handle.state::<SettingsStore>().settings.lock().unwrap().resource_monitor.clone()
+}
+
+#[cfg(target_os = "linux")]
+fn host_memory() -> Option<HostMemory> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let field = |name: &str| -> Option<u64> {
+        let line = meminfo.lines().find(|line| line.starts_with(name))?;
+        let kb: u64 = line[name.len()..].trim().trim_end_matches("kB").trim().parse().ok()?;
+        Some(kb * 1024)
+    };
+    Some(HostMemory {
+        total_bytes: field("MemTotal:")?,
+        available_bytes: field("MemAvailable:")?,
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn host_memory() -> Option<HostMemory> {
+    use std::process::Command;
+
+    let total = Command::new("sysctl").args(["-n", "hw.memsize"]).output().ok()?;
+    let total_bytes: u64 = String::from_utf8_lossy(&total.stdout).trim().parse().ok()?;
+
+    // e.g. "Mach Virtual Memory Statistics: (page size of 16384 bytes)" then "Pages free:  1234."
+    let vm_stat = Command::new("vm_stat").output().ok()?;
+    let vm_stat = String::from_utf8_lossy(&vm_stat.stdout);
+    let page_size: u64 = vm_stat
+        .split_once("page size of ")
+        .and_then(|(_, rest)| rest.split_whitespace().next())
+        .and_then(|size| size.parse().ok())?;
+    let pages = |name: &str| -> u64 {
+        vm_stat
+            .lines()
+            .find_map(|line| line.strip_prefix(name))
+            .and_then(|value| value.trim().trim_end_matches('.').parse().ok())
+            .unwrap_or(0)
+    };
+    let available_pages = pages("Pages free:") + pages("Pages inactive:") + pages("Pages speculative:");
+    Some(HostMemory {
+        total_bytes,
+        available_bytes: available_pages * page_size,
+    })
+}
+
+#[cfg(windows)]
+fn host_memory() -> Option<HostMemory> {
+    use windows_sys::Win32::System::SystemInformation::{GlobalMemoryStatusEx, MEMORYSTATUSEX};
+
+    unsafe {
+        let mut status: MEMORYSTATUSEX = std::mem::zeroed();
+        status.dwLength = std::mem::size_of::<MEMORYSTATUSEX>() as u32;
+        if GlobalMemoryStatusEx(&mut status) == 0 {
+            return None;
+        }
+        Some(HostMemory {
+            total_bytes: status.ullTotalPhys,
+            available_bytes: status.ullAvailPhys,
+        })
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn host_memory() -> Option<HostMemory> {
+    None
+}
+
+/// CPU use since the previous sample where the OS reports CPU time, otherwise
+/// what `ps` says. The first sample after a (re)start has nothing to compare
+/// with and falls back to `ps` too.
+fn cpu_percent(monitor: &ResourceMonitor, usage: &crate::process::TreeUsage) -> Option<f64> {
+    let root = *usage.pids.first()?;
+    let times: Option<Vec<u64>> = usage.pids.iter().map(|&pid| crate::process::cpu_time_ms(pid)).collect();
+    let Some(total) = times.map(|t| t.iter().sum::<u64>()) else {
+        return usage.cpu_percent;
+    };
+
+    let now = Instant::now();
+    let previous = monitor.last_cpu.lock().unwrap().replace(CpuReading { at: now, root, cpu_time_ms: total });
+    match previous {
+        // Workers that exit take their CPU time with them, so the total can drop
+        Some(previous) if previous.root == root && total >= previous.cpu_time_ms => {
+            let elapsed_ms = now.duration_since(previous.at).as_millis() as f64;
+            (elapsed_ms > 0.0).then(|| (total - previous.cpu_time_ms) as f64 * 100.0 / elapsed_ms)
+        }
+        _ => usage.cpu_percent,
+    }
+}
+
+/// Reads the current figures; runs `ps` or PowerShell, so call it off the async runtime.
+fn sample(app_handle: &tauri::AppHandle) -> ResourceUsage {
+    let monitor = app_handle.state::<ResourceMonitor>();
+    let backend = crate::backend_pid(app_handle).ok().and_then(|pid| match crate::process::tree_usage(pid) {
+        Ok(usage) => Some(BackendUsage {
+            pid,
+            processes: usage.pids.len(),
+            cpu_percent: cpu_percent(&monitor, &usage),
+            memory_bytes: usage.memory_bytes,
+        }),
+        Err(e) => {
+            log::debug!("Failed to measure the backend: {}", e);
+            None
+        }
+    });
+
+    ResourceUsage {
+        timestamp_ms: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64),
+        backend,
+        host: host_memory(),
+        memory_ceiling_bytes: monitor_settings(app_handle).memory_ceiling_mb.map(|mb| mb * 1024 * 1024),
+    }
+}
+
+fn check_ceiling(app_handle: &tauri::AppHandle, usage: &ResourceUsage) {
+    let settings = monitor_settings(app_handle);
+    let monitor = app_handle.state::<ResourceMonitor>();
+    let (Some(memory), Some(ceiling)) = (usage.backend.as_ref().and_then(|b| b.memory_bytes), usage.memory_ceiling_bytes) else {
+        *monitor.over_ceiling.lock().unwrap() = false;
+        return;
+    };
+
+    let over = memory > ceiling;
+    let was_over = std::mem::replace(&mut *monitor.over_ceiling.lock().unwrap(), over);
+    if !over || was_over {
+        return;
+    }
+
+    log::warn!("Backend is using {} MB, over its {} MB ceiling", memory / 1024 / 1024, ceiling / 1024 / 1024);
+    let exceeded = CeilingExceeded { memory_bytes: memory, memory_ceiling_bytes: ceiling, action: settings.ceiling_action };
+    if let Err(e) = app_handle.emit("resource-ceiling-exceeded", exceeded) {
+        log::warn!("Failed to emit resource-ceiling-exceeded: {}", e);
+    }
+    let body = match settings.ceiling_action {
+        CeilingAction::Warn => format!("The backend is using {} MB of memory", memory / 1024 / 1024),
+        CeilingAction::Restart => format!("The backend reached {} MB of memory and is being restarted", memory / 1024 / 1024),
+    };
+    crate::notifications::show(app_handle, "Backend memory is high", &body);
+
+    let owned = app_handle.state::<PythonBackend>().owned.load(std::sync::atomic::Ordering::SeqCst);
+    if settings.ceiling_action == CeilingAction::Restart && owned {
+        // Cleared so the restarted backend can warn again if it grows back
+        *monitor.over_ceiling.lock().unwrap() = false;
+        if let Err(e) = crate::supervisor::force_restart(app_handle) {
+            log::error!("Failed to restart the backend after it went over its memory ceiling: {}", e);
+        }
+    }
+}
+
+/// Starts the background sampling, which runs for the life of the app.
+pub fn spawn(app_handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let shutdown = app_handle.state::<PythonBackend>().shutdown.clone();
+        loop {
+            let interval = Duration::from_secs(monitor_settings(&app_handle).interval_secs.max(MIN_INTERVAL_SECS));
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = shutdown.cancelled() => return,
+            }
+            if app_handle.state::<PythonBackend>().lifecycle() != Lifecycle::Running {
+                *app_handle.state::<ResourceMonitor>().last_cpu.lock().unwrap() = None;
+            }
+
+            let handle = app_handle.clone();
+            let sampled = tauri::async_runtime::spawn_blocking(move || {
+                let usage = sample(&handle);
+                check_ceiling(&handle, &usage);
+                usage
+            })
+            .await;
+            match sampled {
+                Ok(usage) => {
+                    if let Err(e) = app_handle.emit("resource-usage", usage) {
+                        log::warn!("Failed to emit resource-usage: {}", e);
+                    }
+                }
+                Err(e) => log::warn!("Failed to sample resource usage: {}", e),
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_resource_usage(app_handle: tauri::AppHandle) -> Result<ResourceUsage, String> {
+    tauri::async_runtime::spawn_blocking(move || sample(&app_handle))
+        .await
+        .map_err(|e| format!("Failed to sample resource usage: {}", e))
+}
+
+/// Changes how often usage is sampled and the backend's memory ceiling; takes
+/// effect from the next sample.
+#[tauri::command]
+pub fn set_resource_monitor(
+    app_handle: tauri::AppHandle,
+    monitor: ResourceMonitorSettings,
+) -> Result<ResourceMonitorSettings, String> {
+    validate(&monitor)?;
+    let store = app_handle.state::<SettingsStore>();
+    let mut current = store.settings.lock().unwrap();
+    current.resource_monitor = monitor.clone();
+    settings::save(&app_handle, &current)?;
+    Ok(monitor)
+}
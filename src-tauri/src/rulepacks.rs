@@ -0,0 +1,402 @@
+//! Rule packs: the best-practice rules the backend checks configs against,
+//! updated separately from the app.
+//!
+//! A pack is a zip holding `rulepack.json` (`{ "version": "2026.10.1",
+//! "min_app_version": "1.3.0" }`) and `cribl_rules.yaml`, signed like updates
+//! (see `update`). The channel URL serves an index:
+//!
+//! ```json
+//! { "packs": [ { "version": "2026.10.1", "url": "...", "sha256": "...",
+//!                "signature": "...", "min_app_version": "1.3.0" } ] }
+//! ```
+//!
+//! Air-gapped installs sideload the same zip from disk, with its signature in a
+//! `.sig` file beside it. Packs are unpacked to `rulepacks/<version>` in the app
+//! data dir; `rulepacks/active` names the one in use, which the backend reads
+//! each time it loads rules, so switching packs doesn't need a restart. The
+//! active pack is the pinned one if set, otherwise the newest installed.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tauri::{Emitter, Manager};
+
+use crate::settings::{self, SettingsStore};
+
+const CHANNEL_URL: Option<&str> = option_env!("CRIBL_HC_RULEPACK_URL");
+const DIR_VAR: &str = "CRIBL_HC_RULEPACKS_DIR";
+const RULEPACKS_DIR: &str = "rulepacks";
+const ACTIVE_FILE: &str = "active";
+const PACK_METADATA_FILE: &str = "rulepack.json";
+const RULES_FILE: &str = "cribl_rules.yaml";
+const MAX_INDEX_BYTES: usize = 1024 * 1024;
+const MAX_PACK_BYTES: u64 = 64 * 1024 * 1024;
+/// Per unpacked file; rules are YAML, so anything bigger isn't a rule pack.
+const MAX_PACK_FILE_BYTES: u64 = 16 * 1024 * 1024;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RulePackSettings {
+    /// Replaces the channel URL this build was made with.
+    pub channel_url: Option<String>,
+    /// Stay on this version instead of moving to the newest installed.
+    pub pinned: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PackMetadata {
+    version: String,
+    min_app_version: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ChannelIndex {
+    #[serde(default)]
+    packs: Vec<ChannelPack>,
+}
+
+#[derive(Deserialize)]
+struct ChannelPack {
+    version: String,
+    url: String,
+    sha256: String,
+    signature: String,
+    min_app_version: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct RulePack {
+    version: String,
+    active: bool,
+    pinned: bool,
+}
+
+#[derive(Serialize)]
+pub struct RulePacks {
+    /// `None` when the backend uses the rules bundled with it.
+    active: Option<String>,
+    pinned: Option<String>,
+    packs: Vec<RulePack>,
+}
+
+fn packs_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get data dir: {}", e))?
+        .join(RULEPACKS_DIR))
+}
+
+fn rulepack_settings(app_handle: &tauri::AppHandle) -> RulePackSettings {
+    app_handle.state::<SettingsStore>().settings.lock().unwrap().rulepacks.clone()
+}
+
+/// Installed versions, oldest first.
+fn installed(dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut versions: Vec<String> = entries
+        .flatten()
+        .filter(|entry| entry.path().join(RULES_FILE).is_file())
+        .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+        .filter(|version| crate::files::validate_version("rule pack", version).is_ok())
+        .collect();
+    versions.sort_by(|a, b| {
+        if crate::update::is_newer(a, b) {
+            std::cmp::Ordering::Greater
+        } else if crate::update::is_newer(b, a) {
+            std::cmp::Ordering::Less
+        } else {
+            a.cmp(b)
+        }
+    });
+    versions
+}
+
+fn active_version(pinned: Option<&str>, installed: &[String]) -> Option<String> {
+    match pinned {
+        Some(pinned) if installed.iter().any(|v| v == pinned) => Some(pinned.to_string()),
+        _ => installed.last().cloned(),
+    }
+}
+
+/// Points the backend at the pack that should be active and lists what's installed.
+fn activate(app_handle: &tauri::AppHandle) -> Result<RulePacks, String> {
+    let dir = packs_dir(app_handle)?;
+    let pinned = rulepack_settings(app_handle).pinned;
+    let versions = installed(&dir);
+    let active = active_version(pinned.as_deref(), &versions);
+
+    let pointer = dir.join(ACTIVE_FILE);
+    let previous = fs::read_to_string(&pointer).ok().map(|v| v.trim().to_string());
+    match &active {
+        Some(version) => crate::files::write_file_atomic(&pointer, version.as_bytes())?,
+        None if pointer.exists() => {
+            fs::remove_file(&pointer).map_err(|e| format!("Failed to clear active rule pack: {}", e))?
+        }
+        None => {}
+    }
+
+    if previous != active {
+        log::info!("Active rule pack is now {}", active.as_deref().unwrap_or("the bundled rules"));
+        if let Err(e) = app_handle.emit("rulepack-changed", &active) {
+            log::warn!("Failed to emit rulepack-changed: {}", e);
+        }
+    }
+
+    Ok(RulePacks {
+        packs: versions
+            .into_iter()
+            .rev()
+            .map(|version| RulePack {
+                active: active.as_deref() == Some(version.as_str()),
+                pinned: pinned.as_deref() == Some(version.as_str()),
+                version,
+            })
+            .collect(),
+        active,
+        pinned,
+    })
+}
+
+fn check_app_version(app_handle: &tauri::AppHandle, version: &str, min_app_version: Option<&str>) -> Result<(), String> {
+    let current = app_handle.package_info().version.to_string();
+    match min_app_version {
+        Some(min) if crate::update::is_newer(min, &current) => Err(format!(
+            "Rule pack {} needs version {} of the app; this is {}",
+            version, min, current
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Verifies a pack archive and unpacks it as its version. `expected_version`
+/// is what the channel said it was; a sideloaded pack is whatever it says inside.
+fn install(
+    app_handle: &tauri::AppHandle,
+    source: &str,
+    archive: &[u8],
+    sha256: Option<&str>,
+    signature: &str,
+    expected_version: Option<&str>,
+) -> Result<String, String> {
+    let digest = Sha256::digest(archive);
+    if let Some(expected) = sha256 {
+        let actual: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+        if !actual.eq_ignore_ascii_case(expected.trim()) {
+            return Err(format!("Rule pack {} is corrupt (SHA-256 {}, expected {})", source, actual, expected));
+        }
+    }
+    crate::update::verify_signature(source, &digest, signature)?;
+
+    let mut zip = zip::ZipArchive::new(Cursor::new(archive)).map_err(|e| format!("Not a valid rule pack: {}", e))?;
+    let metadata: PackMetadata = {
+        let mut entry = zip
+            .by_name(PACK_METADATA_FILE)
+            .map_err(|_| format!("Rule pack {} has no {}", source, PACK_METADATA_FILE))?;
+        let mut text = String::new();
+        (&mut entry)
+            .take(MAX_PACK_FILE_BYTES)
+            .read_to_string(&mut text)
+            .map_err(|e| format!("Failed to read {}: {}", PACK_METADATA_FILE, e))?;
+        serde_json::from_str(&text).map_err(|e| format!("Invalid {}: {}", PACK_METADATA_FILE, e))?
+    };
+    crate::files::validate_version("rule pack", &metadata.version)?;
+    if let Some(expected) = expected_version.filter(|v| *v != metadata.version) {
+        return Err(format!("Rule pack {} is version {}, not {}", source, metadata.version, expected));
+    }
+    check_app_version(app_handle, &metadata.version, metadata.min_app_version.as_deref())?;
+    if zip.by_name(RULES_FILE).is_err() {
+        return Err(format!("Rule pack {} has no {}", source, RULES_FILE));
+    }
+
+    let dir = packs_dir(app_handle)?;
+    let target = dir.join(&metadata.version);
+    let staging = dir.join(format!(".{}.part", metadata.version));
+    let _ = fs::remove_dir_all(&staging);
+    fs::create_dir_all(&staging).map_err(|e| format!("Failed to create {}: {}", staging.display(), e))?;
+
+    // Only top-level files are used; anything nested, or linked, is left out
+    let unpacked: Result<(), String> = (0..zip.len()).try_for_each(|i| {
+        let mut entry = zip.by_index(i).map_err(|e| format!("Failed to read rule pack: {}", e))?;
+        let Some(name) = entry
+            .enclosed_name()
+            .filter(|name| name.components().count() == 1)
+            .and_then(|name| name.to_str().map(str::to_string))
+        else {
+            return Ok(());
+        };
+        if entry.is_dir() || entry.is_symlink() {
+            return Ok(());
+        }
+        if entry.size() > MAX_PACK_FILE_BYTES {
+            return Err(format!("{} in rule pack {} is too large", name, source));
+        }
+        let mut content = Vec::new();
+        (&mut entry)
+            .take(MAX_PACK_FILE_BYTES)
+            .read_to_end(&mut content)
+            .map_err(|e| format!("Failed to read {} from rule pack: {}", name, e))?;
+        fs::write(staging.join(&name), content).map_err(|e| format!("Failed to unpack {}: {}", name, e))
+    });
+    if let Err(e) = unpacked {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(e);
+    }
+
+    if target.exists() {
+        fs::remove_dir_all(&target).map_err(|e| format!("Failed to replace rule pack {}: {}", metadata.version, e))?;
+    }
+    fs::rename(&staging, &target).map_err(|e| format!("Failed to install rule pack {}: {}", metadata.version, e))?;
+    log::info!("Installed rule pack {} from {}", metadata.version, source);
+    Ok(metadata.version)
+}
+
+fn sideload(app_handle: &tauri::AppHandle, path: &str, signature: Option<String>) -> Result<String, String> {
+    let size = fs::metadata(path).map_err(|e| format!("Failed to read {}: {}", path, e))?.len();
+    if size > MAX_PACK_BYTES {
+        return Err(format!("{} is too large to be a rule pack", path));
+    }
+    let archive = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let signature = match signature {
+        Some(signature) => signature,
+        None => {
+            let sig_path = format!("{}.sig", path);
+            fs::read_to_string(&sig_path)
+                .map_err(|e| format!("Failed to read signature {}: {}; pass the signature or put it beside the pack", sig_path, e))?
+        }
+    };
+    install(app_handle, path, &archive, None, &signature, None)
+}
+
+async fn download(app_handle: &tauri::AppHandle) -> Result<Option<String>, String> {
+    let url = rulepack_settings(app_handle)
+        .channel_url
+        .or_else(|| CHANNEL_URL.map(str::to_string))
+        .filter(|url| !url.trim().is_empty())
+        .ok_or("This build has no rule pack channel; sideload a pack from disk instead")?;
+    let client = crate::update::http_client(app_handle).await?;
+
+    let body = client
+        .get(&url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to check for rule packs: {}", e))?
+        .bytes()
+        .await
+        .map_err(|e| format!("Failed to read rule pack index: {}", e))?;
+    if body.len() > MAX_INDEX_BYTES {
+        return Err("Rule pack index is too large".to_string());
+    }
+    let index: ChannelIndex = serde_json::from_slice(&body).map_err(|e| format!("Invalid rule pack index: {}", e))?;
+
+    let current = app_handle.package_info().version.to_string();
+    let Some(newest) = index
+        .packs
+        .into_iter()
+        .filter(|p| crate::files::validate_version("rule pack", &p.version).is_ok())
+        .filter(|p| p.min_app_version.as_deref().map_or(true, |min| !crate::update::is_newer(min, &current)))
+        .reduce(|a, b| if crate::update::is_newer(&b.version, &a.version) { b } else { a })
+    else {
+        return Ok(None);
+    };
+    if installed(&packs_dir(app_handle)?).contains(&newest.version) {
+        return Ok(None);
+    }
+
+    let mut response = client
+        .get(&newest.url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to download rule pack {}: {}", newest.version, e))?;
+    let mut archive = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Download of rule pack {} failed: {}", newest.version, e))?
+    {
+        if (archive.len() + chunk.len()) as u64 > MAX_PACK_BYTES {
+            return Err(format!("Rule pack {} is too large", newest.version));
+        }
+        archive.extend_from_slice(&chunk);
+    }
+
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        install(
+            &handle,
+            &newest.url,
+            &archive,
+            Some(&newest.sha256),
+            &newest.signature,
+            Some(&newest.version),
+        )
+    })
+    .await
+    .map_err(|e| format!("Failed to install rule pack: {}", e))?
+    .map(Some)
+}
+
+/// Tells the backend where rule packs live; it reads the active one itself.
+pub fn apply(app_handle: &tauri::AppHandle, command: &mut Command) {
+    match packs_dir(app_handle) {
+        Ok(dir) => {
+            command.env(DIR_VAR, dir);
+        }
+        Err(e) => log::warn!("{}", e),
+    }
+}
+
+#[tauri::command]
+pub fn list_rulepacks(app_handle: tauri::AppHandle) -> Result<RulePacks, String> {
+    activate(&app_handle)
+}
+
+/// Installs the newest pack from the channel, or with `path` a pack from disk
+/// (signed by `signature`, else by the `.sig` file beside it), and makes the
+/// newest installed pack active unless one is pinned.
+#[tauri::command]
+pub async fn update_rulepacks(
+    app_handle: tauri::AppHandle,
+    path: Option<String>,
+    signature: Option<String>,
+) -> Result<RulePacks, String> {
+    match path {
+        Some(path) => {
+            let handle = app_handle.clone();
+            tauri::async_runtime::spawn_blocking(move || sideload(&handle, &path, signature))
+                .await
+                .map_err(|e| format!("Failed to install rule pack: {}", e))??;
+        }
+        None => {
+            download(&app_handle).await?;
+        }
+    }
+    activate(&app_handle)
+}
+
+/// Keeps the backend on `version` through later updates; `None` goes back to
+/// following the newest installed pack.
+#[tauri::command]
+pub fn pin_rulepack_version(app_handle: tauri::AppHandle, version: Option<String>) -> Result<RulePacks, String> {
+    if let Some(version) = &version {
+        crate::files::validate_version("rule pack", version)?;
+        if !installed(&packs_dir(&app_handle)?).contains(version) {
+            return Err(format!("Rule pack {} isn't installed", version));
+        }
+    }
+
+    {
+        let store = app_handle.state::<SettingsStore>();
+        let mut current = store.settings.lock().unwrap();
+        current.rulepacks.pinned = version;
+        settings::save(&app_handle, &current)?;
+    }
+    activate(&app_handle)
+}
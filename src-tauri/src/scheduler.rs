@@ -0,0 +1,482 @@
+//! Scheduled health checks that run in the background, including while the main
+//! window is closed to the tray.
+//!
+//! The schedule is a five-field cron expression in local time, saved with the
+//! other settings. Each run is saved to the analysis history like one started
+//! from the UI, reported as a `scheduled-analysis` event, and raises a
+//! notification when its health score is below the schedule's threshold. Runs
+//! that finish are also sent to the webhooks set up for them (see `integrations`),
+//! and emailed when the schedule has recipients (see `email`).
+//! A run that falls due while the machine is asleep happens once on waking;
+//! any further runs missed in that time are skipped.
+//!
+//! A schedule without a deployment runs the active profile's (see `profiles`),
+//! so switching profiles switches what the schedule checks.
+
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, Local, NaiveDateTime, TimeZone, Timelike};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+
+use crate::notifications::{self, NotificationCategory};
+use crate::settings::SettingsStore;
+use crate::PythonBackend;
+
+/// The schedule is re-read at least this often, so clock changes and sleep are noticed.
+const MAX_SLEEP: Duration = Duration::from_secs(60);
+const BACKEND_READY_TIMEOUT: Duration = Duration::from_secs(60);
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+const ANALYSIS_TIMEOUT: Duration = Duration::from_secs(900);
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnalysisSchedule {
+    pub enabled: bool,
+    /// `minute hour day-of-month month day-of-week`, e.g. `0 6 * * 1-5`, or one
+    /// of `@hourly`, `@daily`, `@weekly` and `@monthly`.
+    pub cron: String,
+    /// A deployment with saved credentials; the active profile's when empty.
+    pub deployment: String,
+    /// Every analyzer when unset.
+    pub analyzers: Option<Vec<String>>,
+    /// Notify when a run scores below this.
+    pub min_health_score: Option<f64>,
+    /// Email each finished run's report to these recipients.
+    pub email: Option<crate::email::ScheduledEmail>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct ScheduledRun {
+    pub deployment: String,
+    pub analysis_id: Option<String>,
+    pub health_score: Option<f64>,
+    pub error: Option<String>,
+    pub finished_at: String,
+}
+
+#[derive(Serialize)]
+pub struct ScheduleStatus {
+    pub schedule: Option<AnalysisSchedule>,
+    pub next_run: Option<String>,
+    pub running: bool,
+    pub last_run: Option<ScheduledRun>,
+}
+
+#[derive(Default)]
+pub struct SchedulerState {
+    changed: tokio::sync::Notify,
+    running: AtomicBool,
+    last_run: Mutex<Option<ScheduledRun>>,
+}
+
+/// Clears a running flag however a run ends.
+struct RunGuard<'a>(&'a AtomicBool);
+
+impl Drop for RunGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+/// A parsed cron expression; each field is a bit set of the values it allows.
+struct Cron {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Cron matches either day field when both are restricted, and both otherwise.
+    either_day: bool,
+}
+
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut bits = 0u64;
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step.parse().map_err(|_| format!("Invalid step in {:?}", item))?;
+                if step == 0 {
+                    return Err(format!("Invalid step in {:?}", item));
+                }
+                (range, step)
+            }
+            None => (item, 1),
+        };
+        let value = |text: &str| -> Result<u32, String> {
+            let n: u32 = text.parse().map_err(|_| format!("Invalid value {:?}", text))?;
+            if n < min || n > max {
+                return Err(format!("{} is outside {}-{}", n, min, max));
+            }
+            Ok(n)
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (value(start)?, value(end)?),
+                // `5/15` means from 5 to the end in steps of 15
+                None if step > 1 => (value(range)?, max),
+                None => (value(range)?, value(range)?),
+            },
+        };
+        if start > end {
+            return Err(format!("Invalid range {:?}", range));
+        }
+        for n in (start..=end).step_by(step as usize) {
+            bits |= 1 << n;
+        }
+    }
+    Ok(bits)
+}
+
+impl Cron {
+    fn parse(expression: &str) -> Result<Self, String> {
+        let expression = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            other => other,
+        };
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!("Expected five cron fields, got {:?}", expression));
+        };
+        let invalid = |e: String| format!("Invalid cron expression {:?}: {}", expression, e);
+
+        let mut weekdays = parse_field(weekday, 0, 7).map_err(invalid)?;
+        // Both 0 and 7 are Sunday
+        if weekdays & (1 << 7) != 0 {
+            weekdays |= 1;
+        }
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59).map_err(invalid)?,
+            hours: parse_field(hour, 0, 23).map_err(invalid)?,
+            days: parse_field(day, 1, 31).map_err(invalid)?,
+            months: parse_field(month, 1, 12).map_err(invalid)?,
+            weekdays,
+            either_day: !day.starts_with('*') && !weekday.starts_with('*'),
+        })
+    }
+
+    fn day_matches(&self, time: &NaiveDateTime) -> bool {
+        let day = self.days & (1 << time.day()) != 0;
+        let weekday = self.weekdays & (1 << time.weekday().num_days_from_sunday()) != 0;
+        if self.either_day {
+            day || weekday
+        } else {
+            day && weekday
+        }
+    }
+
+    /// The first matching minute after `after`. Skips whole months, days and
+    /// hours that can't match, so even `0 0 29 2 *` is found quickly.
+    fn next_after(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        let start = after.naive_local().with_second(0)?.with_nanosecond(0)? + ChronoDuration::minutes(1);
+        let mut time = start;
+        // Far enough for a February 29th falling on a particular weekday
+        let limit = start + ChronoDuration::days(366 * 28);
+
+        while time < limit {
+            if self.months & (1 << time.month()) == 0 {
+                let (year, month) = if time.month() == 12 {
+                    (time.year() + 1, 1)
+                } else {
+                    (time.year(), time.month() + 1)
+                };
+                time = chrono::NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)?;
+            } else if !self.day_matches(&time) {
+                time = time.date().succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if self.hours & (1 << time.hour()) == 0 {
+                time = time.with_minute(0)? + ChronoDuration::hours(1);
+            } else if self.minutes & (1 << time.minute()) == 0 {
+                time += ChronoDuration::minutes(1);
+            } else {
+                // Times inside a DST gap don't exist; the first run after the gap stands in
+                match Local.from_local_datetime(&time).earliest() {
+                    Some(local) if local > after => return Some(local),
+                    _ => time += ChronoDuration::minutes(1),
+                }
+            }
+        }
+        None
+    }
+}
+
+fn schedule(app_handle: &tauri::AppHandle) -> Option<AnalysisSchedule> {
+    app_handle
+        .state::<SettingsStore>()
+        .settings
+        .lock()
+        .unwrap()
+        .schedule
+        .clone()
+}
+
+fn next_run(schedule: Option<&AnalysisSchedule>) -> Option<DateTime<Local>> {
+    let schedule = schedule.filter(|s| s.enabled)?;
+    Cron::parse(&schedule.cron).ok()?.next_after(Local::now())
+}
+
+/// Whether closing the main window should leave the app running in the tray.
+pub fn keeps_running(app_handle: &tauri::AppHandle) -> bool {
+    schedule(app_handle).is_some_and(|s| s.enabled)
+}
+
+fn status(app_handle: &tauri::AppHandle) -> ScheduleStatus {
+    let state = app_handle.state::<SchedulerState>();
+    let schedule = schedule(app_handle);
+    let status = ScheduleStatus {
+        next_run: next_run(schedule.as_ref()).map(|t| t.to_rfc3339()),
+        schedule,
+        running: state.running.load(Ordering::SeqCst),
+        last_run: state.last_run.lock().unwrap().clone(),
+    };
+    status
+}
+
+pub fn validate(app_handle: &tauri::AppHandle, schedule: &AnalysisSchedule) -> Result<(), String> {
+    if Cron::parse(&schedule.cron)?.next_after(Local::now()).is_none() {
+        return Err(format!("Cron expression {:?} never matches a date", schedule.cron));
+    }
+    let from_profile = crate::profiles::active(app_handle).is_some_and(|p| p.credential.is_some());
+    if schedule.deployment.trim().is_empty() && !from_profile {
+        return Err("A scheduled analysis needs a deployment or an active profile with credentials".to_string());
+    }
+    if let Some(email) = &schedule.email {
+        crate::email::validate_recipients(&email.recipients)?;
+        if app_handle.state::<SettingsStore>().settings.lock().unwrap().email.is_none() {
+            return Err("Emailing scheduled runs needs an SMTP server; set one up first".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// Saves the schedule (`None` removes it) and returns when it next runs.
+#[tauri::command]
+pub fn schedule_analysis(
+    app_handle: tauri::AppHandle,
+    schedule: Option<AnalysisSchedule>,
+) -> Result<ScheduleStatus, String> {
+    if let Some(schedule) = &schedule {
+        validate(&app_handle, schedule)?;
+    }
+
+    {
+        let store = app_handle.state::<SettingsStore>();
+        let mut settings = store.settings.lock().unwrap();
+        let mut updated = settings.clone();
+        updated.schedule = schedule;
+        crate::settings::save(&app_handle, &updated)?;
+        *settings = updated;
+    }
+    app_handle.state::<SchedulerState>().changed.notify_one();
+
+    Ok(status(&app_handle))
+}
+
+#[tauri::command]
+pub fn get_analysis_schedule(app_handle: tauri::AppHandle) -> ScheduleStatus {
+    status(&app_handle)
+}
+
+pub async fn wait_for_backend(app_handle: &tauri::AppHandle) -> Result<(), String> {
+    let mut ready = app_handle.state::<PythonBackend>().ready.subscribe();
+    let result = tokio::time::timeout(BACKEND_READY_TIMEOUT, ready.wait_for(|running| *running)).await;
+    match result {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(_)) => Err("Backend state is gone".to_string()),
+        Err(_) => Err("Backend is not running".to_string()),
+    }
+}
+
+/// Starts an analysis with request `body`, polls until it finishes and returns
+/// its JSON export.
+pub async fn run_analysis(app_handle: &tauri::AppHandle, body: &Value) -> Result<Value, String> {
+    let started = crate::proxy::post_json(app_handle, "/api/v1/analysis", body).await?;
+    let analysis_id = started
+        .get("analysis_id")
+        .and_then(Value::as_str)
+        .ok_or("Backend did not return an analysis id")?
+        .to_string();
+    let path = format!("/api/v1/analysis/{}", analysis_id);
+
+    let deadline = Instant::now() + ANALYSIS_TIMEOUT;
+    loop {
+        let status = crate::proxy::get_json(app_handle, &path)
+            .await?
+            .ok_or_else(|| format!("Analysis {} disappeared", analysis_id))?;
+        match status.get("status").and_then(Value::as_str) {
+            Some("completed") => break,
+            Some("failed") => {
+                let error = status.get("error").and_then(Value::as_str).unwrap_or("unknown error");
+                return Err(format!("Analysis failed: {}", error));
+            }
+            _ => {}
+        }
+        if Instant::now() >= deadline {
+            return Err(format!("Analysis did not finish within {} s", ANALYSIS_TIMEOUT.as_secs()));
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+
+    crate::proxy::get_json(app_handle, &format!("{}/export/json", path))
+        .await?
+        .ok_or_else(|| format!("Analysis {} has no results", analysis_id))
+}
+
+async fn analyze(app_handle: &tauri::AppHandle, schedule: &AnalysisSchedule) -> Result<Value, String> {
+    wait_for_backend(app_handle).await?;
+    let body = json!({ "deployment_name": schedule.deployment, "analyzers": schedule.analyzers });
+    run_analysis(app_handle, &body).await
+}
+
+/// Why `check` didn't run.
+pub(crate) enum Skipped {
+    NoDeployment,
+    AlreadyRunning,
+}
+
+/// Runs the analysis `schedule` describes and saves it to the history, unless it
+/// names no deployment or `running` says a check of the same kind is under way.
+/// `label` names the check in the log, e.g. "Scheduled analysis".
+pub(crate) async fn check(
+    app_handle: &tauri::AppHandle,
+    schedule: &AnalysisSchedule,
+    running: &AtomicBool,
+    label: &str,
+) -> Result<ScheduledRun, Skipped> {
+    if schedule.deployment.trim().is_empty() {
+        return Err(Skipped::NoDeployment);
+    }
+    if running.swap(true, Ordering::SeqCst) {
+        return Err(Skipped::AlreadyRunning);
+    }
+    let _guard = RunGuard(running);
+    log::info!("Running {} of {}", label.to_lowercase(), schedule.deployment);
+
+    let result = analyze(app_handle, schedule).await.and_then(|export| {
+        crate::history::save_analysis_result(app_handle.clone(), export)
+    });
+    Ok(match result {
+        Ok(summary) => ScheduledRun {
+            deployment: schedule.deployment.clone(),
+            analysis_id: Some(summary.analysis_id),
+            health_score: summary.health_score,
+            error: None,
+            finished_at: Local::now().to_rfc3339(),
+        },
+        Err(e) => {
+            log::warn!("{} of {} failed: {}", label, schedule.deployment, e);
+            ScheduledRun {
+                deployment: schedule.deployment.clone(),
+                analysis_id: None,
+                health_score: None,
+                error: Some(e),
+                finished_at: Local::now().to_rfc3339(),
+            }
+        }
+    })
+}
+
+/// Raises the "`label` complete" or "`label` failed" notification for `run`.
+pub(crate) fn notify_finished(app_handle: &tauri::AppHandle, run: &ScheduledRun, label: &str) {
+    match (run.health_score, &run.error) {
+        (Some(score), _) => notifications::notify(
+            app_handle,
+            NotificationCategory::AnalysisComplete,
+            &format!("{} complete", label),
+            &format!("{} scored {:.0}/100", run.deployment, score),
+        ),
+        (None, Some(error)) => notifications::notify(
+            app_handle,
+            NotificationCategory::AnalysisFailed,
+            &format!("{} failed", label),
+            &format!("{}: {}", run.deployment, error),
+        ),
+        (None, None) => {}
+    }
+}
+
+/// Runs `schedule` once, unless a scheduled run is already in progress.
+pub async fn run(app_handle: &tauri::AppHandle, schedule: AnalysisSchedule) {
+    let schedule = crate::profiles::apply_to_schedule(app_handle, schedule);
+    let state = app_handle.state::<SchedulerState>();
+    let run = match check(app_handle, &schedule, &state.running, "Scheduled analysis").await {
+        Ok(run) => run,
+        Err(Skipped::NoDeployment) => {
+            log::warn!("Skipping scheduled analysis: it names no deployment and no profile with credentials is active");
+            return;
+        }
+        Err(Skipped::AlreadyRunning) => {
+            log::info!("Skipping scheduled analysis: the previous one is still running");
+            return;
+        }
+    };
+
+    if let Some(analysis_id) = &run.analysis_id {
+        crate::integrations::dispatch_scheduled(app_handle, analysis_id.clone());
+        if let Some(email) = schedule.email.clone() {
+            crate::email::dispatch_scheduled(app_handle, analysis_id.clone(), email);
+        }
+    }
+    match (run.health_score, schedule.min_health_score) {
+        (Some(score), Some(min)) if score < min => notifications::show(
+            app_handle,
+            "Health score dropped",
+            &format!("{} scored {:.0}/100, below your threshold of {:.0}", run.deployment, score, min),
+        ),
+        _ => notify_finished(app_handle, &run, "Scheduled analysis"),
+    }
+
+    *state.last_run.lock().unwrap() = Some(run.clone());
+    if let Err(e) = app_handle.emit("scheduled-analysis", run) {
+        log::warn!("Failed to emit scheduled-analysis: {}", e);
+    }
+}
+
+/// Runs the saved schedule's analysis straight away, whether or not it is enabled.
+/// Returns false when there is no schedule to take the deployment from.
+pub fn run_now(app_handle: &tauri::AppHandle) -> bool {
+    let Some(schedule) = schedule(app_handle) else {
+        return false;
+    };
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move { run(&handle, schedule).await });
+    true
+}
+
+/// Re-reads the schedule, e.g. after the active profile or the settings change.
+pub fn reschedule(app_handle: &tauri::AppHandle) {
+    app_handle.state::<SchedulerState>().changed.notify_one();
+}
+
+/// Starts the background task that waits for each scheduled run.
+pub fn spawn(app_handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let state = app_handle.state::<SchedulerState>();
+        let shutdown = app_handle.state::<PythonBackend>().shutdown.clone();
+        loop {
+            let schedule = schedule(&app_handle);
+            let next = next_run(schedule.as_ref());
+            let wait = next
+                .and_then(|next| (next - Local::now()).to_std().ok())
+                .map_or(MAX_SLEEP, |wait| wait.min(MAX_SLEEP));
+
+            tokio::select! {
+                _ = tokio::time::sleep(wait) => {}
+                _ = state.changed.notified() => continue,
+                _ = shutdown.cancelled() => return,
+            }
+
+            if let (Some(schedule), Some(next)) = (schedule, next) {
+                if Local::now() >= next {
+                    let handle = app_handle.clone();
+                    tauri::async_runtime::spawn(async move { run(&handle, schedule).await });
+                }
+            }
+        }
+    });
+}
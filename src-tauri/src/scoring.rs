@@ -0,0 +1,244 @@
+//! Health scores computed from a run's findings, so every view, export and
+//! comparison shows the same number however the backend reported it.
+//!
+//! Each finding costs its group points by severity (the weights the backend's
+//! HTML export uses), and a group scores 100 minus what its findings cost, never
+//! below 0. A finding reported twice in a group counts once. The overall score
+//! is the mean of the worker groups' scores, less what deployment-wide findings
+//! (leader and other checks without a group) cost; a run with no worker groups
+//! scores like a single group.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::history::UNGROUPED;
+
+/// Points a finding of each severity costs its group. Anything else, `info`
+/// included, costs nothing.
+const SEVERITY_WEIGHTS: &[(&str, f64)] = &[("critical", 20.0), ("high", 10.0), ("medium", 3.0), ("low", 0.5)];
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Contribution {
+    pub finding_id: Option<String>,
+    pub title: String,
+    pub severity: String,
+    /// Points this finding takes off its group's score.
+    pub penalty: f64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GroupScore {
+    pub worker_group: String,
+    pub score: f64,
+    pub findings_count: usize,
+    /// The findings that cost points, most costly first.
+    pub contributing: Vec<Contribution>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct HealthScore {
+    pub analysis_id: Option<String>,
+    pub overall: f64,
+    /// What the backend saved with the run, which may have been computed differently.
+    pub reported: Option<f64>,
+    /// Sorted by worker group name; deployment-wide findings are `(deployment)`.
+    pub groups: Vec<GroupScore>,
+}
+
+/// Scores are shown to one decimal; rounding here keeps them stable across views.
+fn round(score: f64) -> f64 {
+    (score * 10.0).round() / 10.0
+}
+
+fn severity(finding: &Value) -> String {
+    finding
+        .get("severity")
+        .and_then(Value::as_str)
+        .unwrap_or("info")
+        .trim()
+        .to_ascii_lowercase()
+}
+
+/// Points `finding` takes off its group's score.
+pub fn penalty(finding: &Value) -> f64 {
+    let severity = severity(finding);
+    SEVERITY_WEIGHTS
+        .iter()
+        .find(|(name, _)| *name == severity)
+        .map_or(0.0, |(_, weight)| *weight)
+}
+
+fn total_penalty<'a>(findings: impl Iterator<Item = &'a Value>) -> f64 {
+    findings.map(penalty).sum()
+}
+
+/// A group's score from its findings: 100 less their penalties, never below 0.
+pub fn score_findings<'a>(findings: impl Iterator<Item = &'a Value>) -> f64 {
+    round((100.0 - total_penalty(findings)).clamp(0.0, 100.0))
+}
+
+fn contribution(finding: &Value) -> Contribution {
+    let text = |key: &str| finding.get(key).and_then(Value::as_str).map(str::to_string);
+    Contribution {
+        finding_id: text("id"),
+        title: text("title").unwrap_or_default(),
+        severity: severity(finding),
+        penalty: penalty(finding),
+    }
+}
+
+/// Scores an analysis result (the backend's JSON export).
+pub fn compute(result: &Value) -> HealthScore {
+    let groups: Vec<GroupScore> = crate::history::group_findings(result)
+        .into_iter()
+        .map(|(worker_group, findings)| {
+            let mut contributing: Vec<Contribution> =
+                findings.values().map(contribution).filter(|c| c.penalty > 0.0).collect();
+            contributing.sort_by(|a, b| b.penalty.total_cmp(&a.penalty).then_with(|| a.title.cmp(&b.title)));
+            GroupScore {
+                score: score_findings(findings.values()),
+                findings_count: findings.len(),
+                contributing,
+                worker_group,
+            }
+        })
+        .collect();
+
+    let (deployment_wide, worker_groups): (Vec<&GroupScore>, Vec<&GroupScore>) =
+        groups.iter().partition(|g| g.worker_group == UNGROUPED);
+    let deployment_penalty: f64 = deployment_wide
+        .iter()
+        .flat_map(|g| g.contributing.iter())
+        .map(|c| c.penalty)
+        .sum();
+    let groups_mean = if worker_groups.is_empty() {
+        100.0
+    } else {
+        worker_groups.iter().map(|g| g.score).sum::<f64>() / worker_groups.len() as f64
+    };
+
+    HealthScore {
+        analysis_id: result.get("analysis_id").and_then(Value::as_str).map(str::to_string),
+        overall: round((groups_mean - deployment_penalty).clamp(0.0, 100.0)),
+        reported: result.get("health_score").and_then(Value::as_f64),
+        groups,
+    }
+}
+
+/// Scores saved run `run_id` overall and per worker group, with the findings
+/// that cost each group points.
+#[tauri::command]
+pub fn compute_health_score(app_handle: tauri::AppHandle, run_id: String) -> Result<HealthScore, String> {
+    let result = crate::history::get_analysis_run(app_handle, run_id)?;
+    Ok(compute(&result))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn finding(id: &str, severity: &str, group: Option<&str>) -> Value {
+        let metadata = group.map_or(json!({}), |g| json!({ "worker_group": g }));
+        json!({ "id": id, "title": id, "severity": severity, "metadata": metadata })
+    }
+
+    fn result(findings: Vec<Value>) -> Value {
+        json!({ "analysis_id": "run-1", "health_score": 0.0, "findings": findings })
+    }
+
+    fn group<'a>(score: &'a HealthScore, name: &str) -> &'a GroupScore {
+        score.groups.iter().find(|g| g.worker_group == name).unwrap()
+    }
+
+    #[test]
+    fn no_findings_scores_100() {
+        let score = compute(&result(vec![]));
+        assert_eq!(score.overall, 100.0);
+        assert!(score.groups.is_empty());
+        assert_eq!(score.reported, Some(0.0));
+    }
+
+    #[test]
+    fn missing_findings_scores_100() {
+        assert_eq!(compute(&json!({ "analysis_id": "run-1" })).overall, 100.0);
+    }
+
+    #[test]
+    fn info_findings_cost_nothing() {
+        let findings = (0..500).map(|i| finding(&format!("f{}", i), "info", Some("default"))).collect();
+        assert_eq!(compute(&result(findings)).overall, 100.0);
+    }
+
+    #[test]
+    fn severity_is_case_insensitive() {
+        let score = compute(&result(vec![finding("a", "CRITICAL", None), finding("b", " High ", None)]));
+        assert_eq!(score.overall, 70.0);
+    }
+
+    #[test]
+    fn groups_are_scored_separately_and_averaged() {
+        let score = compute(&result(vec![
+            finding("a", "critical", Some("default")),
+            finding("b", "high", Some("default")),
+            finding("c", "low", Some("edge")),
+        ]));
+        assert_eq!(group(&score, "default").score, 70.0);
+        assert_eq!(group(&score, "edge").score, 99.5);
+        assert_eq!(score.overall, 84.8);
+    }
+
+    #[test]
+    fn one_bad_group_does_not_zero_the_deployment() {
+        let mut findings: Vec<Value> = (0..20).map(|i| finding(&format!("f{}", i), "critical", Some("bad"))).collect();
+        findings.push(finding("ok", "low", Some("good")));
+        let score = compute(&result(findings));
+        assert_eq!(group(&score, "bad").score, 0.0);
+        assert_eq!(group(&score, "good").score, 99.5);
+        assert_eq!(score.overall, 49.8);
+    }
+
+    #[test]
+    fn deployment_wide_findings_cost_the_overall_score() {
+        let score = compute(&result(vec![
+            finding("leader", "high", None),
+            finding("a", "medium", Some("default")),
+        ]));
+        assert_eq!(group(&score, UNGROUPED).score, 90.0);
+        assert_eq!(score.overall, 87.0);
+    }
+
+    #[test]
+    fn scores_never_go_below_zero() {
+        let findings = (0..50).map(|i| finding(&format!("f{}", i), "critical", None)).collect();
+        let score = compute(&result(findings));
+        assert_eq!(score.overall, 0.0);
+        assert_eq!(group(&score, UNGROUPED).score, 0.0);
+    }
+
+    #[test]
+    fn repeated_findings_count_once() {
+        let score = compute(&result(vec![
+            finding("same", "critical", Some("default")),
+            finding("same", "critical", Some("default")),
+        ]));
+        assert_eq!(group(&score, "default").findings_count, 1);
+        assert_eq!(score.overall, 80.0);
+    }
+
+    #[test]
+    fn contributing_findings_are_most_costly_first() {
+        let score = compute(&result(vec![
+            finding("low", "low", Some("default")),
+            finding("info", "info", Some("default")),
+            finding("critical", "critical", Some("default")),
+            finding("medium", "medium", Some("default")),
+        ]));
+        let contributing: Vec<&str> = group(&score, "default")
+            .contributing
+            .iter()
+            .map(|c| c.title.as_str())
+            .collect();
+        assert_eq!(contributing, ["critical", "medium", "low"]);
+    }
+}
@@ -0,0 +1,388 @@
+//! Full-text search over the findings of every saved run, for questions like
+//! "have we seen this error on this customer before?".
+//!
+//! The index covers finding titles, descriptions, worker groups and remediation
+//! steps, and ranks hits with BM25, titles counting most. It is built in memory
+//! the first time it's needed and again after a run is saved or deleted; it is
+//! never written to disk, so it can't leak what history encryption protects.
+//!
+//! Queries match findings that contain every word. A word ending in `*` matches
+//! any word it begins, and text in double quotes must appear as written.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::Manager;
+
+/// How much a word counts in each field, relative to the description.
+const TITLE_WEIGHT: f64 = 3.0;
+const WORKER_GROUP_WEIGHT: f64 = 2.0;
+const TEXT_WEIGHT: f64 = 1.0;
+/// BM25 term-frequency saturation and length normalization.
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 500;
+/// A prefix shorter than this would match most of the index.
+const MIN_PREFIX_CHARS: usize = 2;
+const SNIPPET_CHARS: usize = 160;
+
+#[derive(Default)]
+pub struct SearchState {
+    index: Mutex<Option<Arc<Index>>>,
+    /// Bumped whenever saved runs change, so a build that started before is not kept.
+    generation: AtomicU64,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct SearchFilters {
+    /// Exact deployment name.
+    pub deployment: Option<String>,
+    pub worker_group: Option<String>,
+    /// Any of these severities.
+    pub severities: Option<Vec<String>>,
+    pub category: Option<String>,
+    pub run_id: Option<String>,
+    /// Runs saved in this range, in milliseconds since the Unix epoch.
+    pub saved_after_ms: Option<u64>,
+    pub saved_before_ms: Option<u64>,
+    /// At most `MAX_LIMIT`; `DEFAULT_LIMIT` when unset.
+    pub limit: Option<usize>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct SearchHit {
+    pub run_id: String,
+    pub deployment_name: Option<String>,
+    pub completed_at: Option<String>,
+    pub saved_at_ms: u64,
+    pub finding_id: Option<String>,
+    pub title: String,
+    pub severity: String,
+    pub category: String,
+    pub worker_group: String,
+    /// Part of the description around the first match.
+    pub snippet: String,
+    pub score: f64,
+}
+
+#[derive(Serialize)]
+pub struct SearchResults {
+    /// Matching findings, of which `hits` are the best.
+    pub total: usize,
+    pub hits: Vec<SearchHit>,
+    pub runs_indexed: usize,
+    pub findings_indexed: usize,
+}
+
+struct Doc {
+    hit: SearchHit,
+    description: String,
+    /// Every indexed field, lower-cased, for quoted phrases.
+    text: String,
+    /// Weighted count of words.
+    length: f64,
+}
+
+struct Posting {
+    doc: usize,
+    /// Weighted occurrences of the word in the finding.
+    frequency: f64,
+}
+
+#[derive(Default)]
+struct Index {
+    docs: Vec<Doc>,
+    words: BTreeMap<String, Vec<Posting>>,
+    average_length: f64,
+    runs: usize,
+}
+
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+}
+
+fn text<'a>(finding: &'a Value, key: &str) -> &'a str {
+    finding.get(key).and_then(Value::as_str).unwrap_or_default()
+}
+
+fn remediation(finding: &Value) -> String {
+    finding
+        .get("remediation_steps")
+        .and_then(Value::as_array)
+        .map(|steps| steps.iter().filter_map(Value::as_str).collect::<Vec<_>>().join("\n"))
+        .unwrap_or_default()
+}
+
+impl Index {
+    fn add_run(&mut self, summary: &crate::history::RunSummary, result: &Value) {
+        self.runs += 1;
+        for finding in result.get("findings").and_then(Value::as_array).into_iter().flatten() {
+            let worker_group = crate::history::worker_group(finding);
+            let title = text(finding, "title");
+            let description = text(finding, "description");
+            let remediation = remediation(finding);
+
+            let mut frequencies: HashMap<String, f64> = HashMap::new();
+            let mut length = 0.0;
+            for (field, weight) in [
+                (title, TITLE_WEIGHT),
+                (worker_group.as_str(), WORKER_GROUP_WEIGHT),
+                (description, TEXT_WEIGHT),
+                (remediation.as_str(), TEXT_WEIGHT),
+            ] {
+                for word in words(field) {
+                    *frequencies.entry(word).or_default() += weight;
+                    length += weight;
+                }
+            }
+
+            let doc = self.docs.len();
+            for (word, frequency) in frequencies {
+                self.words.entry(word).or_default().push(Posting { doc, frequency });
+            }
+            self.docs.push(Doc {
+                text: [title, &worker_group, description, &remediation].join("\n").to_lowercase(),
+                hit: SearchHit {
+                    run_id: summary.analysis_id.clone(),
+                    deployment_name: summary.deployment_name.clone(),
+                    completed_at: summary.completed_at.clone(),
+                    saved_at_ms: summary.saved_at_ms,
+                    finding_id: finding.get("id").and_then(Value::as_str).map(str::to_string),
+                    title: title.to_string(),
+                    severity: text(finding, "severity").to_ascii_lowercase(),
+                    category: text(finding, "category").to_string(),
+                    worker_group,
+                    snippet: String::new(),
+                    score: 0.0,
+                },
+                description: description.to_string(),
+                length,
+            });
+        }
+    }
+
+    fn finish(&mut self) {
+        let total: f64 = self.docs.iter().map(|d| d.length).sum();
+        self.average_length = if self.docs.is_empty() { 0.0 } else { total / self.docs.len() as f64 };
+    }
+
+    /// Indexed words `term` stands for: itself, or every word it begins when it ends in `*`.
+    fn expand<'a>(&'a self, term: &'a Term) -> Box<dyn Iterator<Item = &'a Vec<Posting>> + 'a> {
+        match term {
+            Term::Word(word) => Box::new(self.words.get(word).into_iter()),
+            Term::Prefix(prefix) => Box::new(
+                self.words
+                    .range(prefix.clone()..)
+                    .take_while(move |(word, _)| word.starts_with(prefix.as_str()))
+                    .map(|(_, postings)| postings),
+            ),
+        }
+    }
+
+    fn bm25(&self, postings: &[Posting], doc: &Doc, frequency: f64) -> f64 {
+        let n = self.docs.len() as f64;
+        let matching = postings.len() as f64;
+        let idf = ((n - matching + 0.5) / (matching + 0.5) + 1.0).ln();
+        let length = doc.length / self.average_length.max(1.0);
+        idf * frequency * (K1 + 1.0) / (frequency + K1 * (1.0 - B + B * length))
+    }
+}
+
+fn matches(filters: &SearchFilters, hit: &SearchHit) -> bool {
+    let equal = |wanted: &Option<String>, value: &str| wanted.as_deref().map_or(true, |w| w.eq_ignore_ascii_case(value));
+    equal(&filters.deployment, hit.deployment_name.as_deref().unwrap_or_default())
+        && equal(&filters.worker_group, &hit.worker_group)
+        && equal(&filters.category, &hit.category)
+        && filters.run_id.as_deref().map_or(true, |id| id == hit.run_id)
+        && filters
+            .severities
+            .as_ref()
+            .map_or(true, |wanted| wanted.iter().any(|s| s.eq_ignore_ascii_case(&hit.severity)))
+        && filters.saved_after_ms.map_or(true, |after| hit.saved_at_ms >= after)
+        && filters.saved_before_ms.map_or(true, |before| hit.saved_at_ms < before)
+}
+
+enum Term {
+    Word(String),
+    Prefix(String),
+}
+
+struct Query {
+    terms: Vec<Term>,
+    phrases: Vec<String>,
+}
+
+fn parse(query: &str) -> Result<Query, String> {
+    let mut terms = Vec::new();
+    let mut phrases = Vec::new();
+    for (i, part) in query.split('"').enumerate() {
+        // Odd parts are inside quotes
+        if i % 2 == 1 {
+            let phrase = part.trim().to_lowercase();
+            if !phrase.is_empty() {
+                terms.extend(words(&phrase).map(Term::Word));
+                phrases.push(phrase);
+            }
+            continue;
+        }
+        for token in part.split_whitespace() {
+            match token.strip_suffix('*') {
+                Some(prefix) => {
+                    let mut prefix_words: Vec<String> = words(prefix).collect();
+                    let Some(last) = prefix_words.pop() else { continue };
+                    if last.chars().count() < MIN_PREFIX_CHARS {
+                        return Err(format!("Use at least {} characters before *", MIN_PREFIX_CHARS));
+                    }
+                    terms.extend(prefix_words.into_iter().map(Term::Word));
+                    terms.push(Term::Prefix(last));
+                }
+                None => terms.extend(words(token).map(Term::Word)),
+            }
+        }
+    }
+    if terms.is_empty() {
+        return Err("Enter something to search for".to_string());
+    }
+    Ok(Query { terms, phrases })
+}
+
+/// The description around the first query word it contains, or its start.
+fn snippet(description: &str, query: &Query) -> String {
+    // ASCII lower-casing keeps byte offsets, so matches map back onto the original
+    let lowered = description.to_ascii_lowercase();
+    let first = query
+        .terms
+        .iter()
+        .filter_map(|term| match term {
+            Term::Word(word) | Term::Prefix(word) => lowered.find(word.as_str()),
+        })
+        .min()
+        .unwrap_or(0);
+    let start = description[..first]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_CHARS / 3)
+        .map_or(0, |(i, _)| i);
+    let excerpt: String = description[start..].chars().take(SNIPPET_CHARS).collect();
+    let mut snippet = excerpt.trim().to_string();
+    if start > 0 {
+        snippet.insert(0, '…');
+    }
+    if description.len() - start > excerpt.len() {
+        snippet.push('…');
+    }
+    snippet
+}
+
+fn search(index: &Index, query: &Query, filters: &SearchFilters) -> SearchResults {
+    // Score each finding that has every term
+    let mut scores: Option<HashMap<usize, f64>> = None;
+    for term in &query.terms {
+        let mut term_scores: HashMap<usize, f64> = HashMap::new();
+        for postings in index.expand(term) {
+            for posting in postings {
+                if scores.as_ref().map_or(true, |s| s.contains_key(&posting.doc)) {
+                    let doc = &index.docs[posting.doc];
+                    *term_scores.entry(posting.doc).or_default() += index.bm25(postings, doc, posting.frequency);
+                }
+            }
+        }
+        scores = Some(match scores {
+            None => term_scores,
+            Some(previous) => term_scores
+                .into_iter()
+                .filter_map(|(doc, score)| previous.get(&doc).map(|earlier| (doc, earlier + score)))
+                .collect(),
+        });
+    }
+
+    let mut hits: Vec<(usize, f64)> = scores
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|(doc, _)| {
+            let doc = &index.docs[*doc];
+            matches(filters, &doc.hit) && query.phrases.iter().all(|p| doc.text.contains(p.as_str()))
+        })
+        .collect();
+    hits.sort_by(|a, b| {
+        b.1.total_cmp(&a.1)
+            .then_with(|| index.docs[b.0].hit.saved_at_ms.cmp(&index.docs[a.0].hit.saved_at_ms))
+    });
+
+    let total = hits.len();
+    let limit = filters.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    SearchResults {
+        total,
+        hits: hits
+            .into_iter()
+            .take(limit)
+            .map(|(doc, score)| {
+                let doc = &index.docs[doc];
+                SearchHit {
+                    snippet: snippet(&doc.description, query),
+                    score: (score * 1000.0).round() / 1000.0,
+                    ..doc.hit.clone()
+                }
+            })
+            .collect(),
+        runs_indexed: index.runs,
+        findings_indexed: index.docs.len(),
+    }
+}
+
+fn build(app_handle: &tauri::AppHandle) -> Result<Index, String> {
+    let mut index = Index::default();
+    for summary in crate::history::list_analysis_runs(app_handle.clone())? {
+        match crate::history::get_analysis_run(app_handle.clone(), summary.analysis_id.clone()) {
+            Ok(result) => index.add_run(&summary, &result),
+            Err(e) => log::warn!("Not indexing saved run {}: {}", summary.analysis_id, e),
+        }
+    }
+    index.finish();
+    log::info!("Indexed {} findings from {} saved runs", index.docs.len(), index.runs);
+    Ok(index)
+}
+
+/// The current index, building it if saved runs changed since the last one.
+fn index(app_handle: &tauri::AppHandle) -> Result<Arc<Index>, String> {
+    let state = app_handle.state::<SearchState>();
+    if let Some(index) = state.index.lock().unwrap().as_ref() {
+        return Ok(index.clone());
+    }
+    let generation = state.generation.load(Ordering::SeqCst);
+    let index = Arc::new(build(app_handle)?);
+    if state.generation.load(Ordering::SeqCst) == generation {
+        *state.index.lock().unwrap() = Some(index.clone());
+    }
+    Ok(index)
+}
+
+/// Drops the index after saved runs change; the next search rebuilds it.
+pub fn invalidate(app_handle: &tauri::AppHandle) {
+    let state = app_handle.state::<SearchState>();
+    state.generation.fetch_add(1, Ordering::SeqCst);
+    state.index.lock().unwrap().take();
+}
+
+/// Finds saved findings matching `query`, best first.
+#[tauri::command]
+pub async fn search_findings(
+    app_handle: tauri::AppHandle,
+    query: String,
+    filters: Option<SearchFilters>,
+) -> Result<SearchResults, String> {
+    let query = parse(&query)?;
+    let filters = filters.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || {
+        let index = index(&app_handle)?;
+        Ok(search(&index, &query, &filters))
+    })
+    .await
+    .map_err(|e| format!("Failed to search findings: {}", e))?
+}
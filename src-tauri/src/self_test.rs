@@ -0,0 +1,130 @@
+use serde::Serialize;
+use std::fs;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+use crate::{health, Lifecycle, PythonBackend};
+
+const READY_TIMEOUT: Duration = Duration::from_secs(30);
+const ROUND_TRIP_CONTENT: &[u8] = b"cribl-hc self-test\n";
+
+#[derive(Serialize)]
+pub struct SelfTestStep {
+    name: &'static str,
+    passed: bool,
+    detail: Option<String>,
+    elapsed_ms: u64,
+}
+
+#[derive(Serialize)]
+pub struct SelfTestReport {
+    passed: bool,
+    steps: Vec<SelfTestStep>,
+}
+
+#[derive(Default)]
+struct Steps(Vec<SelfTestStep>);
+
+impl Steps {
+    /// Records the outcome of a step and returns whether it passed.
+    fn record(&mut self, name: &'static str, started: Instant, result: Result<Option<String>, String>) -> bool {
+        let passed = result.is_ok();
+        self.0.push(SelfTestStep {
+            name,
+            passed,
+            detail: result.unwrap_or_else(Some),
+            elapsed_ms: started.elapsed().as_millis() as u64,
+        });
+        passed
+    }
+}
+
+async fn blocking<T: Send + 'static>(
+    work: impl FnOnce() -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    tauri::async_runtime::spawn_blocking(work)
+        .await
+        .map_err(|e| format!("Self-test step panicked: {}", e))?
+}
+
+async fn wait_until_ready(app_handle: &tauri::AppHandle) -> Result<Option<String>, String> {
+    let state = app_handle.state::<PythonBackend>();
+    let mut ready = state.ready.subscribe();
+    tokio::time::timeout(READY_TIMEOUT, ready.wait_for(|running| *running))
+        .await
+        .map_err(|_| format!("Backend not ready after {} s", READY_TIMEOUT.as_secs()))?
+        .map_err(|_| "Backend state is gone".to_string())?;
+
+    crate::get_backend_url(app_handle.clone()).map(Some)
+}
+
+async fn health_round_trip(app_handle: &tauri::AppHandle) -> Result<Option<String>, String> {
+    let sample = health::check(app_handle).await;
+    if !sample.reachable {
+        return Err("Backend did not answer /health".to_string());
+    }
+    Ok(None)
+}
+
+/// Writes a temp file the way saves do and reads it back. The file is removed
+/// whether or not the step passes.
+fn file_round_trip() -> Result<Option<String>, String> {
+    let path = std::env::temp_dir().join(format!("cribl-hc-self-test-{}.txt", std::process::id()));
+    let result = crate::files::write_file_atomic(&path, ROUND_TRIP_CONTENT).and_then(|_| {
+        let read = fs::read(&path).map_err(|e| format!("Failed to read back {}: {}", path.display(), e))?;
+        if read != ROUND_TRIP_CONTENT {
+            return Err(format!("Read back different content from {}", path.display()));
+        }
+        Ok(Some(path.to_string_lossy().to_string()))
+    });
+
+    if let Err(e) = fs::remove_file(&path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            log::warn!("Failed to remove self-test file {}: {}", path.display(), e);
+        }
+    }
+    result
+}
+
+/// Smoke-tests an install: start the backend, wait for it, check health, save and
+/// read back a temp file, then stop. A backend that was already running is used
+/// as-is and left running; one the test started is always stopped again.
+#[tauri::command]
+pub async fn run_self_test(app_handle: tauri::AppHandle) -> Result<SelfTestReport, String> {
+    let mut steps = Steps::default();
+    let already_running = app_handle.state::<PythonBackend>().lifecycle() == Lifecycle::Running;
+
+    let started = Instant::now();
+    let result = if already_running {
+        Ok(Some("Backend was already running; testing it in place".to_string()))
+    } else {
+        let handle = app_handle.clone();
+        blocking(move || crate::start_backend_blocking(handle).map_err(String::from)).await.map(Some)
+    };
+    let backend_up = steps.record("start", started, result);
+
+    if backend_up {
+        let started = Instant::now();
+        let ready = wait_until_ready(&app_handle).await;
+        if steps.record("ready", started, ready) {
+            let started = Instant::now();
+            let health = health_round_trip(&app_handle).await;
+            steps.record("health", started, health);
+        }
+    }
+
+    let started = Instant::now();
+    steps.record("file_round_trip", started, blocking(file_round_trip).await);
+
+    if backend_up && !already_running {
+        let started = Instant::now();
+        let handle = app_handle.clone();
+        let stopped = blocking(move || crate::stop_backend_process(&handle)).await.map(|_| None);
+        steps.record("stop", started, stopped);
+    }
+
+    Ok(SelfTestReport {
+        passed: steps.0.iter().all(|step| step.passed),
+        steps: steps.0,
+    })
+}
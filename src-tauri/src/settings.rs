@@ -0,0 +1,848 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::Manager;
+
+use crate::baseline::BaselineSettings;
+use crate::client_cert::SavedClientCertificate;
+use crate::credentials::SavedCredential;
+use crate::email::EmailSettings;
+use crate::encoding::TextEncoding;
+use crate::integrations::Webhook;
+use crate::limits::ResourceLimits;
+use crate::network::NetworkSettings;
+use crate::notifications::NotificationSettings;
+use crate::priority::BackendPriority;
+use crate::profiles::Profiles;
+use crate::resources::ResourceMonitorSettings;
+use crate::rulepacks::RulePackSettings;
+use crate::scheduler::AnalysisSchedule;
+use crate::telemetry::TelemetrySettings;
+use crate::ticketing::TicketingSettings;
+use crate::transport::BackendTransport;
+use crate::update::UpdateSettings;
+use crate::window_state::WindowState;
+
+const SETTINGS_FILE: &str = "settings.json";
+/// Where an unreadable settings file is moved so a later save can't destroy it.
+const CORRUPT_BACKUP_FILE: &str = "settings.corrupt.json";
+
+/// Header names containing any of these carry credentials.
+const SECRET_HEADER_MARKERS: &[&str] = &["authorization", "cookie", "token", "key", "secret"];
+
+/// Bumped whenever the exported config layout changes incompatibly.
+const CONFIG_EXPORT_VERSION: u32 = 1;
+
+/// Bumped whenever a saved setting changes shape or meaning; `migrate` brings
+/// older files forward. Files from before versioning count as version 1.
+const SETTINGS_VERSION: u32 = 3;
+const VERSION_KEY: &str = "schema_version";
+
+/// Settings only their own commands may change, since they mirror state kept
+/// elsewhere (the OS credential store, the windows themselves).
+const READ_ONLY_KEYS: &[&str] = &["credentials", "client_certificates", "webhooks", "ticketing", "email", "encrypt_history", "global_shortcut", "window", "results_window"];
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    pub recent_dirs: Vec<String>,
+    pub backend: BackendSettings,
+    pub window: Option<WindowState>,
+    /// Where the last results window was closed; see `window_state::open_results_window`.
+    pub results_window: Option<WindowState>,
+    pub proxy_limits: ProxyLimits,
+    /// Sent with every proxied request unless the request sets the same header.
+    pub proxy_headers: BTreeMap<String, String>,
+    pub save_encoding: TextEncoding,
+    /// Deployments with a token in the OS credential store; never the token itself.
+    pub credentials: Vec<SavedCredential>,
+    /// Deployments with an mTLS client certificate; the key is in the OS credential store.
+    pub client_certificates: Vec<SavedClientCertificate>,
+    /// Analyses run at once; see `jobs`. 2 when unset.
+    pub analysis_concurrency: Option<u32>,
+    /// Background health checks; see `scheduler`.
+    pub schedule: Option<AnalysisSchedule>,
+    pub notifications: NotificationSettings,
+    /// `off`, `error`, `warn`, `info`, `debug` or `trace`; info when unset.
+    pub log_level: Option<String>,
+    /// Proxy and CA settings for connections to Cribl; see `network`.
+    pub network: NetworkSettings,
+    /// Where save dialogs for reports and exports start.
+    pub export_dir: Option<String>,
+    /// Where updates come from and which backend an update installed; see `update`.
+    pub updates: UpdateSettings,
+    /// Rule pack channel and pinned version; see `rulepacks`.
+    pub rulepacks: RulePackSettings,
+    /// Where finished analyses are posted; see `integrations`.
+    pub webhooks: Vec<Webhook>,
+    /// Where `ticketing::create_tickets` opens tickets.
+    pub ticketing: Option<TicketingSettings>,
+    /// The SMTP server `email::send_report_email` sends through.
+    pub email: Option<EmailSettings>,
+    /// Sampling interval and backend memory ceiling; see `resources`.
+    pub resource_monitor: ResourceMonitorSettings,
+    /// Seal saved runs and history with a key in the OS credential store; see `encryption`.
+    pub encrypt_history: bool,
+    /// Accelerator that runs a quick health check from anywhere; see `shortcut`.
+    pub global_shortcut: Option<String>,
+    /// Opt-in crash reporting; see `telemetry`.
+    pub telemetry: TelemetrySettings,
+    /// Baseline runs by profile and when drift from them is notified; see `baseline`.
+    pub baselines: BaselineSettings,
+}
+
+/// The settings as a JSON object without `READ_ONLY_KEYS`, the part an
+/// imported config replaces.
+fn importable(settings: &AppSettings) -> Result<serde_json::Map<String, Value>, String> {
+    let Value::Object(mut fields) = serde_json::to_value(settings).map_err(|e| format!("Failed to serialize settings: {}", e))? else {
+        return Err("Settings did not serialize to an object".to_string());
+    };
+    fields.retain(|key, _| !READ_ONLY_KEYS.contains(&key.as_str()));
+    Ok(fields)
+}
+
+/// How the bundled backend is launched.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackendSettings {
+    pub bind_address: String,
+    /// Fixed port to listen on; `None` lets the OS pick a free one.
+    pub port: Option<u16>,
+    /// TCP, or a Unix socket / named pipe for desktops that forbid localhost listeners.
+    pub transport: BackendTransport,
+    pub resource_limits: ResourceLimits,
+    pub priority: BackendPriority,
+    /// Uvicorn worker processes; 1 runs the API in a single process.
+    pub workers: u32,
+    /// Refuse to launch a backend without a valid code signature.
+    pub require_signed_backend: bool,
+    /// Restart the backend, with backoff, when it exits on its own.
+    pub auto_restart: bool,
+    pub watchdog: WatchdogSettings,
+    /// `.env` file applied to the backend's environment; defaults to one in the config dir.
+    pub env_file: Option<String>,
+    pub log_buffer: LogBufferLimits,
+    /// Directory relative backend paths resolve against; defaults to the app data dir.
+    pub working_dir: Option<String>,
+    /// Hard ceiling on the whole launch, from resolving the binary to the listener check.
+    pub startup_timeout_secs: u64,
+    /// Launch from a local copy when the bundled backend sits on a slow volume.
+    pub cache_slow_sidecar: bool,
+    /// Appended to the backend's command line; see `passthrough`.
+    pub extra_args: Vec<String>,
+    /// Set in the backend's environment, over the `.env` file.
+    pub env: BTreeMap<String, String>,
+    /// Where the backend keeps its data, created on demand; also its working
+    /// directory unless `working_dir` is set.
+    pub data_dir: Option<String>,
+}
+
+impl Default for BackendSettings {
+    fn default() -> Self {
+        Self {
+            bind_address: "127.0.0.1".to_string(),
+            port: None,
+            transport: BackendTransport::default(),
+            resource_limits: ResourceLimits::default(),
+            priority: BackendPriority::default(),
+            workers: 1,
+            require_signed_backend: false,
+            auto_restart: true,
+            watchdog: WatchdogSettings::default(),
+            env_file: None,
+            log_buffer: LogBufferLimits::default(),
+            working_dir: None,
+            startup_timeout_secs: 30,
+            cache_slow_sidecar: false,
+            extra_args: Vec::new(),
+            env: BTreeMap::new(),
+            data_dir: None,
+        }
+    }
+}
+
+impl BackendSettings {
+    pub fn is_loopback(&self) -> bool {
+        self.bind_address
+            .parse::<std::net::IpAddr>()
+            .map(|ip| ip.is_loopback())
+            .unwrap_or(self.bind_address == "localhost")
+    }
+}
+
+/// Caps on proxied backend requests; requests past `max_in_flight` wait in a queue
+/// of at most `max_queued` and beyond that are turned away.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProxyLimits {
+    pub max_in_flight: usize,
+    pub max_queued: usize,
+}
+
+impl Default for ProxyLimits {
+    fn default() -> Self {
+        Self {
+            max_in_flight: 8,
+            max_queued: 64,
+        }
+    }
+}
+
+/// Restarts a backend that is alive but has stopped answering health checks.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchdogSettings {
+    pub enabled: bool,
+    /// Consecutive failed health checks before the backend is restarted.
+    pub failure_threshold: u32,
+}
+
+impl Default for WatchdogSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            failure_threshold: 3,
+        }
+    }
+}
+
+/// Bounds on the in-memory backend log; the oldest lines go first once either total is exceeded.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogBufferLimits {
+    pub max_lines: usize,
+    pub max_bytes: usize,
+    /// Longer lines are cut short and end in an ellipsis.
+    pub max_line_bytes: usize,
+}
+
+impl Default for LogBufferLimits {
+    fn default() -> Self {
+        Self {
+            max_lines: 2_000,
+            max_bytes: 1024 * 1024,
+            max_line_bytes: 16 * 1024,
+        }
+    }
+}
+
+pub fn is_secret_header(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    SECRET_HEADER_MARKERS.iter().any(|marker| name.contains(marker))
+}
+
+#[derive(Default)]
+pub struct SettingsStore {
+    pub settings: Mutex<AppSettings>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AppConfigExport {
+    schema_version: u32,
+    settings: AppSettings,
+    /// Missing from exports made before profiles moved to profiles.json.
+    #[serde(default)]
+    profiles: Option<Profiles>,
+}
+
+fn settings_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app_handle
+        .path()
+        .app_config_dir()
+        .map_err(|e| format!("Failed to get config dir: {}", e))?;
+
+    Ok(dir.join(SETTINGS_FILE))
+}
+
+/// Loads settings from disk, falling back to defaults when none are saved yet.
+pub fn load(app_handle: &tauri::AppHandle) -> AppSettings {
+    let Ok(path) = settings_path(app_handle) else {
+        return AppSettings::default();
+    };
+
+    let parsed = fs::read(&path).map(|bytes| {
+        serde_json::from_slice::<Value>(&bytes).and_then(|mut value| {
+            let original = value.clone();
+            let version = migrate(&mut value);
+            serde_json::from_value::<AppSettings>(value).map(|settings| (settings, version, original))
+        })
+    });
+
+    match parsed {
+        Ok(Ok((mut settings, version, mut original))) => {
+            if version < SETTINGS_VERSION {
+                // Keep the original in case the migration got something wrong
+                let legacy_profiles = original.get("profiles").cloned();
+                let backup = path.with_file_name(format!("settings.v{}.json", version));
+                if let Err(e) = back_up(&path, &backup, &mut original) {
+                    log::warn!("Failed to back up settings before migrating them: {}", e);
+                }
+                if let Some(legacy) = legacy_profiles.filter(|_| version < 3) {
+                    adopt_legacy_profiles(app_handle, &mut settings, legacy);
+                }
+                log::info!("Migrated settings from version {} to {}", version, SETTINGS_VERSION);
+            } else if version > SETTINGS_VERSION {
+                log::warn!(
+                    "Settings were saved by a newer version of the app (schema {}); anything it added is lost on the next save",
+                    version
+                );
+            }
+            settings
+        }
+        Ok(Err(e)) => {
+            let backup = path.with_file_name(CORRUPT_BACKUP_FILE);
+            match fs::rename(&path, &backup) {
+                Ok(()) => log::warn!(
+                    "Settings file {} is unreadable ({}); moved it to {} and using defaults",
+                    path.display(),
+                    e,
+                    backup.display()
+                ),
+                Err(rename_error) => log::warn!(
+                    "Settings file {} is unreadable ({}) and could not be backed up: {}",
+                    path.display(),
+                    e,
+                    rename_error
+                ),
+            }
+            AppSettings::default()
+        }
+        Err(_) => AppSettings::default(),
+    }
+}
+
+/// Copies the settings file at `path` to `backup`, minus the profile tokens
+/// version 2 kept in it: those move to the credential store, and a copy left
+/// behind in plain text would defeat that.
+fn back_up(path: &std::path::Path, backup: &std::path::Path, original: &mut Value) -> Result<(), String> {
+    if !scrub_profile_tokens(original) {
+        return fs::copy(path, backup).map(|_| ()).map_err(|e| e.to_string());
+    }
+    let json = serde_json::to_vec_pretty(original).map_err(|e| e.to_string())?;
+    crate::files::write_file_atomic(backup, &json)
+}
+
+/// Removes `token` from every entry of `profiles`; true when there was one.
+fn scrub_profile_tokens(settings: &mut Value) -> bool {
+    let Some(Value::Array(profiles)) = settings.get_mut("profiles") else {
+        return false;
+    };
+    let mut scrubbed = false;
+    for profile in profiles.iter_mut().filter_map(Value::as_object_mut) {
+        scrubbed |= profile.remove("token").is_some();
+    }
+    scrubbed
+}
+
+/// Version 2 stopped saving 0 for "any port" and recent directories twice.
+fn migrate_v1(settings: &mut serde_json::Map<String, Value>) {
+    if let Some(Value::Object(backend)) = settings.get_mut("backend") {
+        if backend.get("port").and_then(Value::as_u64) == Some(0) {
+            backend.remove("port");
+        }
+    }
+    if let Some(Value::Array(dirs)) = settings.get_mut("recent_dirs") {
+        let mut seen = std::collections::HashSet::new();
+        dirs.retain(|dir| seen.insert(dir.to_string()));
+    }
+}
+
+/// Version 3 moved profiles to profiles.json, their tokens to the credential
+/// store; see `adopt_legacy_profiles`.
+fn migrate_v2(settings: &mut serde_json::Map<String, Value>) {
+    settings.remove("profiles");
+}
+
+/// Hands the profiles a version 2 settings file held to `profiles`, and saves
+/// the settings straight away so the tokens are off disk without waiting for
+/// the next change.
+fn adopt_legacy_profiles(app_handle: &tauri::AppHandle, settings: &mut AppSettings, legacy: Value) {
+    match crate::profiles::adopt_legacy(app_handle, settings, legacy) {
+        Ok(0) => {}
+        Ok(adopted) => log::info!("Moved {} profile(s) from settings.json to profiles.json", adopted),
+        Err(e) => {
+            log::warn!("Failed to move profiles out of settings.json: {}", e);
+            return;
+        }
+    }
+    if let Err(e) = save(app_handle, settings) {
+        log::warn!("Failed to save migrated settings: {}", e);
+    }
+}
+
+/// Upgrades saved settings to `SETTINGS_VERSION` one version at a time and
+/// strips the version key; returns the version they were saved at.
+fn migrate(value: &mut Value) -> u32 {
+    let Value::Object(settings) = value else {
+        return SETTINGS_VERSION;
+    };
+    let saved = settings
+        .remove(VERSION_KEY)
+        .and_then(|v| v.as_u64())
+        .map_or(1, |v| v as u32);
+
+    let migrations: [fn(&mut serde_json::Map<String, Value>); 2] = [migrate_v1, migrate_v2];
+    for (from, migration) in (1..).zip(migrations) {
+        if saved <= from {
+            migration(settings);
+        }
+    }
+    saved
+}
+
+/// Cuts truncated JSON back to its last complete value and closes whatever is
+/// still open, e.g. `{"a":1,"b":{"c":2,"d":"tr` becomes `{"a":1,"b":{"c":2}}`.
+fn close_truncated(text: &str) -> String {
+    let mut open = Vec::new();
+    let mut last_safe = (0, Vec::new());
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (index, c) in text.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_string = true,
+            '{' | '[' => {
+                open.push(if c == '{' { '}' } else { ']' });
+                last_safe = (index + 1, open.clone());
+            }
+            '}' | ']' => {
+                open.pop();
+                last_safe = (index + 1, open.clone());
+            }
+            ',' => last_safe = (index, open.clone()),
+            _ => {}
+        }
+    }
+
+    let (end, still_open) = last_safe;
+    let mut repaired = text[..end].to_string();
+    repaired.extend(still_open.iter().rev());
+    repaired
+}
+
+/// Keeps every top-level setting that still deserializes, leaving the rest at their defaults.
+fn salvage(value: serde_json::Value) -> (AppSettings, Vec<String>, Vec<String>) {
+    let mut recovered = Vec::new();
+    let mut dropped = Vec::new();
+    let mut merged = serde_json::to_value(AppSettings::default()).unwrap_or_default();
+
+    if let (serde_json::Value::Object(fields), Some(target)) = (value, merged.as_object_mut()) {
+        for (key, field) in fields {
+            let previous = target.insert(key.clone(), field);
+            let candidate = serde_json::Value::Object(target.clone());
+            if serde_json::from_value::<AppSettings>(candidate).is_ok() && previous.is_some() {
+                recovered.push(key);
+            } else {
+                match previous {
+                    Some(previous) => target.insert(key.clone(), previous),
+                    None => target.remove(&key),
+                };
+                dropped.push(key);
+            }
+        }
+    }
+
+    let settings = serde_json::from_value(merged).unwrap_or_default();
+    (settings, recovered, dropped)
+}
+
+pub fn backend(app_handle: &tauri::AppHandle) -> BackendSettings {
+    app_handle
+        .state::<SettingsStore>()
+        .settings
+        .lock()
+        .unwrap()
+        .backend
+        .clone()
+}
+
+/// The configured export directory, if it still exists.
+pub fn export_dir(app_handle: &tauri::AppHandle) -> Option<PathBuf> {
+    let dir = app_handle.state::<SettingsStore>().settings.lock().unwrap().export_dir.clone()?;
+    Some(PathBuf::from(dir)).filter(|dir| dir.is_dir())
+}
+
+/// Where exports go by default: the configured directory, else the platform's
+/// Downloads folder (XDG user dirs, Known Folders).
+pub fn export_folder(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    export_dir(app_handle)
+        .or_else(dirs::download_dir)
+        .or_else(|| dirs::home_dir().map(|home| home.join("Downloads")))
+        .ok_or_else(|| "Could not determine the Downloads folder; set an export directory".to_string())
+}
+
+pub fn save(app_handle: &tauri::AppHandle, settings: &AppSettings) -> Result<(), String> {
+    let path = settings_path(app_handle)?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create config dir: {}", e))?;
+    }
+
+    let mut value = serde_json::to_value(settings).map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    if let Value::Object(fields) = &mut value {
+        fields.insert(VERSION_KEY.to_string(), SETTINGS_VERSION.into());
+    }
+    let json = serde_json::to_vec_pretty(&value)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+
+    crate::files::write_file_atomic(&path, &json)
+}
+
+/// Drops credential-bearing headers.
+fn without_secrets(mut settings: AppSettings) -> AppSettings {
+    settings.proxy_headers.retain(|name, _| !is_secret_header(name));
+    settings
+}
+
+/// RFC 7386 merge: objects merge key by key, `null` removes, anything else replaces.
+fn merge_patch(target: &mut Value, patch: Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch;
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Default::default());
+    }
+    if let Value::Object(target) = target {
+        for (key, value) in patch {
+            if value.is_null() {
+                target.remove(&key);
+            } else {
+                merge_patch(target.entry(key).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
+
+/// What serde can't check: ranges, paths, and fields with their own parsers.
+fn validate(app_handle: &tauri::AppHandle, settings: &mut AppSettings) -> Result<(), String> {
+    let backend = &mut settings.backend;
+    if backend.bind_address.parse::<std::net::IpAddr>().is_err() && backend.bind_address != "localhost" {
+        return Err(format!("Invalid bind address {:?}", backend.bind_address));
+    }
+    backend.port = backend.port.filter(|&p| p != 0);
+    if backend.workers == 0 {
+        return Err("The backend needs at least one worker".to_string());
+    }
+    if backend.startup_timeout_secs == 0 {
+        return Err("The startup timeout must be at least one second".to_string());
+    }
+    if backend.watchdog.failure_threshold == 0 {
+        return Err("Failure threshold must be at least 1".to_string());
+    }
+    backend.data_dir = backend.data_dir.take().filter(|dir| !dir.trim().is_empty());
+    crate::passthrough::validate(backend)?;
+    if settings.proxy_limits.max_in_flight == 0 {
+        return Err("At least one request must be allowed in flight".to_string());
+    }
+    for (name, value) in &settings.proxy_headers {
+        crate::proxy::parse_header(name, value)?;
+    }
+    if let Some(level) = &settings.log_level {
+        settings.log_level = Some(crate::logging::parse_level(level)?.as_str().to_ascii_lowercase());
+    }
+    if let Some(limit) = settings.analysis_concurrency {
+        if !(1..=crate::jobs::MAX_CONCURRENCY).contains(&limit) {
+            return Err(format!("Concurrency must be between 1 and {}", crate::jobs::MAX_CONCURRENCY));
+        }
+    }
+    crate::network::validate(&mut settings.network)?;
+    crate::resources::validate(&settings.resource_monitor)?;
+    if let Some(schedule) = &settings.schedule {
+        crate::scheduler::validate(app_handle, schedule)?;
+    }
+    let max_drop = settings.baselines.max_score_drop;
+    if max_drop.is_nan() || max_drop < 0.0 {
+        return Err("The baseline score drop threshold must be 0 or more".to_string());
+    }
+    settings.export_dir = settings.export_dir.take().filter(|dir| !dir.trim().is_empty());
+    if let Some(dir) = &settings.export_dir {
+        if !std::path::Path::new(dir).is_dir() {
+            return Err(format!("Export directory does not exist: {}", dir));
+        }
+    }
+    Ok(())
+}
+
+/// Every setting, without secrets (see `without_secrets`).
+#[tauri::command]
+pub fn get_settings(app_handle: tauri::AppHandle) -> AppSettings {
+    let settings = app_handle.state::<SettingsStore>().settings.lock().unwrap().clone();
+    without_secrets(settings)
+}
+
+#[derive(Serialize)]
+pub struct SettingsUpdate {
+    settings: AppSettings,
+    /// Whether the change only reaches the backend once it restarts.
+    restart_required: bool,
+}
+
+/// Applies `patch`, a JSON merge patch of the settings as `get_settings` returns
+/// them: objects merge key by key and `null` resets a setting to its default.
+/// Nothing is saved unless the result is valid as a whole. Secrets left out by
+/// `get_settings` are kept.
+#[tauri::command]
+pub fn update_settings(app_handle: tauri::AppHandle, patch: Value) -> Result<SettingsUpdate, String> {
+    let Value::Object(fields) = &patch else {
+        return Err("Settings patch must be a JSON object".to_string());
+    };
+    if let Some(key) = fields.keys().find(|k| READ_ONLY_KEYS.contains(&k.as_str()) || *k == VERSION_KEY) {
+        return Err(format!("{} can't be changed through update_settings", key));
+    }
+
+    let store = app_handle.state::<SettingsStore>();
+    let current = store.settings.lock().unwrap();
+    let mut merged = serde_json::to_value(&*current).map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    merge_patch(&mut merged, patch);
+    let updated: AppSettings = serde_json::from_value(merged).map_err(|e| format!("Invalid settings: {}", e))?;
+
+    let update = replace(&app_handle, current, updated)?;
+    log::info!("Settings updated");
+    Ok(update)
+}
+
+/// Validates `updated` and, if it is valid, saves it in place of `current` and
+/// applies what takes effect without a restart.
+fn replace(
+    app_handle: &tauri::AppHandle,
+    mut current: std::sync::MutexGuard<AppSettings>,
+    mut updated: AppSettings,
+) -> Result<SettingsUpdate, String> {
+    validate(app_handle, &mut updated)?;
+
+    let before = serde_json::to_value(&*current).map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    let after = serde_json::to_value(&updated).map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    let restart_required = ["backend", "network"].iter().any(|key| before[key] != after[key]);
+    save(app_handle, &updated)?;
+    *current = updated.clone();
+    drop(current);
+
+    app_handle.state::<crate::proxy::ProxyState>().set_limits(updated.proxy_limits);
+    if let Some(level) = updated.log_level.as_deref().and_then(|l| crate::logging::parse_level(l).ok()) {
+        log::set_max_level(level);
+    }
+    crate::scheduler::reschedule(app_handle);
+
+    Ok(SettingsUpdate {
+        settings: without_secrets(updated),
+        restart_required,
+    })
+}
+
+#[tauri::command]
+pub async fn export_app_config(
+    app_handle: tauri::AppHandle,
+    include_secrets: bool,
+) -> Result<String, String> {
+    let mut settings = app_handle
+        .state::<SettingsStore>()
+        .settings
+        .lock()
+        .unwrap()
+        .clone();
+
+    if !include_secrets {
+        settings = without_secrets(settings);
+    }
+
+    let export = AppConfigExport {
+        schema_version: CONFIG_EXPORT_VERSION,
+        settings,
+        profiles: Some(crate::profiles::list_profiles(app_handle.clone())),
+    };
+    let content = serde_json::to_vec_pretty(&export)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+
+    let path = crate::dialogs::save_path(&app_handle, "cribl-hc-config.json").await?;
+    crate::files::write_file_atomic(&path, &content)?;
+
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Replaces the settings with those in an exported config, checked and applied
+/// the way `update_settings` does. Settings that mirror state kept elsewhere
+/// (`READ_ONLY_KEYS`) keep their current values. Profiles in the export replace
+/// the saved ones. Fails unless `overwrite` is set when anything has been changed
+/// from its default or a profile is saved.
+#[tauri::command]
+pub fn import_app_config(
+    app_handle: tauri::AppHandle,
+    content: Vec<u8>,
+    overwrite: bool,
+) -> Result<SettingsUpdate, String> {
+    let export: AppConfigExport = serde_json::from_slice(&content)
+        .map_err(|e| format!("Invalid config file: {}", e))?;
+
+    if export.schema_version != CONFIG_EXPORT_VERSION {
+        return Err(format!(
+            "Unsupported config version {} (expected {})",
+            export.schema_version, CONFIG_EXPORT_VERSION
+        ));
+    }
+
+    let mut profiles = export.profiles;
+    if let Some(profiles) = &mut profiles {
+        crate::profiles::validate_import(profiles)?;
+    }
+
+    let store = app_handle.state::<SettingsStore>();
+    let current = store.settings.lock().unwrap();
+
+    let customized = importable(&current)? != importable(&AppSettings::default())?
+        || !crate::profiles::list_profiles(app_handle.clone()).profiles.is_empty();
+    if customized && !overwrite {
+        return Err("Existing configuration would be overwritten; confirm to continue".to_string());
+    }
+
+    let mut fields = importable(&export.settings)?;
+    let Value::Object(kept) = serde_json::to_value(&*current).map_err(|e| format!("Failed to serialize settings: {}", e))? else {
+        return Err("Settings did not serialize to an object".to_string());
+    };
+    fields.extend(kept.into_iter().filter(|(key, _)| READ_ONLY_KEYS.contains(&key.as_str())));
+    let mut imported: AppSettings =
+        serde_json::from_value(Value::Object(fields)).map_err(|e| format!("Invalid config file: {}", e))?;
+
+    for (name, value) in &current.proxy_headers {
+        if is_secret_header(name) && !imported.proxy_headers.contains_key(name) {
+            imported.proxy_headers.insert(name.clone(), value.clone());
+        }
+    }
+
+    let update = replace(&app_handle, current, imported)?;
+    if let Some(profiles) = profiles {
+        crate::profiles::replace_all(&app_handle, profiles)?;
+    }
+    log::info!("Settings imported");
+    Ok(update)
+}
+
+#[derive(Serialize)]
+pub struct SettingsRepair {
+    /// The file that was repaired, if any needed it.
+    source: Option<String>,
+    recovered_keys: Vec<String>,
+    dropped_keys: Vec<String>,
+}
+
+/// Salvages what it can from a corrupt settings file (or the backup `load` made
+/// of one), saves the result and applies it.
+#[tauri::command]
+pub fn repair_settings(app_handle: tauri::AppHandle) -> Result<SettingsRepair, String> {
+    let path = settings_path(&app_handle)?;
+    let backup = path.with_file_name(CORRUPT_BACKUP_FILE);
+
+    let current_is_valid = fs::read(&path)
+        .map(|bytes| {
+            serde_json::from_slice::<Value>(&bytes).is_ok_and(|mut value| {
+                migrate(&mut value);
+                serde_json::from_value::<AppSettings>(value).is_ok()
+            })
+        })
+        .unwrap_or(true);
+    let source = if !current_is_valid {
+        path.clone()
+    } else if backup.exists() {
+        backup.clone()
+    } else {
+        return Ok(SettingsRepair {
+            source: None,
+            recovered_keys: Vec::new(),
+            dropped_keys: Vec::new(),
+        });
+    };
+
+    let bytes = fs::read(&source).map_err(|e| format!("Failed to read {}: {}", source.display(), e))?;
+    let text = String::from_utf8_lossy(&bytes);
+    let value = serde_json::from_str(&text)
+        .or_else(|_| serde_json::from_str(&close_truncated(&text)))
+        .map_err(|e| format!("Nothing could be recovered from {}: {}", source.display(), e))?;
+
+    let mut value = value;
+    migrate(&mut value);
+    let (repaired, recovered_keys, dropped_keys) = salvage(value);
+    save(&app_handle, &repaired)?;
+    *app_handle.state::<SettingsStore>().settings.lock().unwrap() = repaired;
+
+    log::info!(
+        "Repaired settings from {}: recovered {:?}, dropped {:?}",
+        source.display(),
+        recovered_keys,
+        dropped_keys
+    );
+    if source == backup {
+        let _ = fs::remove_file(&backup);
+    }
+
+    Ok(SettingsRepair {
+        source: Some(source.to_string_lossy().to_string()),
+        recovered_keys,
+        dropped_keys,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unchanged(settings: &AppSettings) -> bool {
+        importable(settings).unwrap() == importable(&AppSettings::default()).unwrap()
+    }
+
+    #[test]
+    fn fresh_settings_need_no_confirmation_to_import_over() {
+        assert!(unchanged(&AppSettings::default()));
+
+        // Where the windows were closed isn't configuration
+        let settings = AppSettings {
+            window: Some(WindowState { x: 10, y: 10, width: 800, height: 600, maximized: false }),
+            ..Default::default()
+        };
+        assert!(unchanged(&settings));
+    }
+
+    #[test]
+    fn any_changed_setting_needs_confirmation() {
+        let mut settings = AppSettings::default();
+        settings.backend.port = Some(8181);
+        assert!(!unchanged(&settings));
+
+        let mut settings = AppSettings::default();
+        settings.proxy_headers.insert("X-Team".to_string(), "ops".to_string());
+        assert!(!unchanged(&settings));
+
+        let settings = AppSettings {
+            log_level: Some("debug".to_string()),
+            ..Default::default()
+        };
+        assert!(!unchanged(&settings));
+    }
+
+    #[test]
+    fn backups_keep_profiles_but_not_their_tokens() {
+        let mut original = serde_json::json!({
+            "profiles": [{ "name": "prod", "url": "https://x", "token": "secret" }, { "name": "dev", "url": "https://y" }],
+        });
+        assert!(scrub_profile_tokens(&mut original));
+        assert_eq!(
+            original,
+            serde_json::json!({ "profiles": [{ "name": "prod", "url": "https://x" }, { "name": "dev", "url": "https://y" }] })
+        );
+        assert!(!scrub_profile_tokens(&mut original));
+        assert!(!scrub_profile_tokens(&mut serde_json::json!({ "log_level": "info" })));
+    }
+}
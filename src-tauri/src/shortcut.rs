@@ -0,0 +1,676 @@
+//! A system-wide keyboard shortcut that runs a quick health check of the active
+//! profile's deployment, even while the app is in the background or closed to
+//! the tray.
+//!
+//! The shortcut is an accelerator such as `CmdOrCtrl+Shift+H`, saved with the
+//! other settings and off until one is set. Each check is saved to the history
+//! like one started from the UI (which updates the tray's last score), reported
+//! as a `quick-check-complete` event, and raises a notification.
+//!
+//! There is no portable API for this, so each platform registers the shortcut
+//! natively: `RegisterHotKey` on Windows, Carbon hot keys on macOS and a key
+//! grab on the X11 root window on Linux. Wayland doesn't let apps grab keys, so
+//! there the shortcut only works for X11 (XWayland) windows, or not at all.
+
+use std::fmt;
+use std::sync::atomic::AtomicBool;
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+
+use crate::notifications;
+use crate::scheduler::{self, AnalysisSchedule, Skipped};
+use crate::settings::SettingsStore;
+
+/// Highest function key accepted; macOS has no key codes past F20.
+const MAX_FUNCTION_KEY: u8 = 20;
+
+type Callback = Box<dyn Fn() + Send + 'static>;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Key {
+    /// An ASCII upper-case letter or digit.
+    Char(u8),
+    Function(u8),
+    Space,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Accelerator {
+    ctrl: bool,
+    alt: bool,
+    shift: bool,
+    /// Cmd on macOS, the Windows key on Windows, Super on Linux.
+    meta: bool,
+    key: Key,
+}
+
+impl fmt::Display for Accelerator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let meta = if cfg!(target_os = "macos") {
+            "Cmd"
+        } else if cfg!(windows) {
+            "Win"
+        } else {
+            "Super"
+        };
+        for (held, name) in [(self.ctrl, "Ctrl"), (self.alt, "Alt"), (self.shift, "Shift"), (self.meta, meta)] {
+            if held {
+                write!(f, "{}+", name)?;
+            }
+        }
+        match self.key {
+            Key::Char(c) => write!(f, "{}", c as char),
+            Key::Function(n) => write!(f, "F{}", n),
+            Key::Space => write!(f, "Space"),
+        }
+    }
+}
+
+fn parse_key(name: &str) -> Option<Key> {
+    let upper = name.to_ascii_uppercase();
+    match upper.as_bytes() {
+        [c] if c.is_ascii_alphanumeric() => Some(Key::Char(*c)),
+        _ if upper == "SPACE" => Some(Key::Space),
+        [b'F', digits @ ..] => std::str::from_utf8(digits)
+            .ok()?
+            .parse::<u8>()
+            .ok()
+            .filter(|n| (1..=MAX_FUNCTION_KEY).contains(n))
+            .map(Key::Function),
+        _ => None,
+    }
+}
+
+/// Parses an accelerator like `CmdOrCtrl+Shift+H`: modifiers (`Ctrl`, `Alt` or
+/// `Option`, `Shift`, `Cmd` or `Super`, and `CmdOrCtrl`) then one key, a letter,
+/// digit, `F1` to `F20` or `Space`. Anything but a function key needs Ctrl, Alt
+/// or Cmd, so the shortcut can't swallow ordinary typing.
+pub fn parse(text: &str) -> Result<Accelerator, String> {
+    let invalid = |reason: &str| format!("Invalid shortcut {:?}: {}", text, reason);
+    let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+    let key = parts.pop().filter(|k| !k.is_empty()).ok_or_else(|| invalid("it has no key"))?;
+    let key = parse_key(key).ok_or_else(|| invalid(&format!("{} is not a letter, digit, F1-F{} or Space", key, MAX_FUNCTION_KEY)))?;
+
+    let mut accelerator = Accelerator { ctrl: false, alt: false, shift: false, meta: false, key };
+    for part in parts {
+        let held = match part.to_ascii_lowercase().as_str() {
+            "ctrl" | "control" => &mut accelerator.ctrl,
+            "alt" | "option" => &mut accelerator.alt,
+            "shift" => &mut accelerator.shift,
+            "cmd" | "command" | "super" | "meta" | "win" => &mut accelerator.meta,
+            "cmdorctrl" | "commandorcontrol" if cfg!(target_os = "macos") => &mut accelerator.meta,
+            "cmdorctrl" | "commandorcontrol" => &mut accelerator.ctrl,
+            _ => return Err(invalid(&format!("{:?} is not a modifier", part))),
+        };
+        *held = true;
+    }
+    let function_key = matches!(accelerator.key, Key::Function(_));
+    if !(function_key || accelerator.ctrl || accelerator.alt || accelerator.meta) {
+        return Err(invalid("it needs Ctrl, Alt or Cmd"));
+    }
+    Ok(accelerator)
+}
+
+#[derive(Default)]
+pub struct ShortcutState {
+    /// Unregisters the shortcut when dropped.
+    registration: Mutex<Option<platform::Registration>>,
+    running: AtomicBool,
+}
+
+/// Runs the active profile's analysis, unless a quick check is already running.
+async fn quick_check(app_handle: &tauri::AppHandle) {
+    let schedule = crate::profiles::apply_to_schedule(app_handle, AnalysisSchedule::default());
+    let state = app_handle.state::<ShortcutState>();
+    let run = match scheduler::check(app_handle, &schedule, &state.running, "Quick health check").await {
+        Ok(run) => run,
+        Err(Skipped::NoDeployment) => {
+            notifications::show(
+                app_handle,
+                "Quick health check",
+                "Choose a profile with saved credentials to check",
+            );
+            return;
+        }
+        Err(Skipped::AlreadyRunning) => {
+            log::info!("Ignoring shortcut: a quick health check is already running");
+            return;
+        }
+    };
+
+    scheduler::notify_finished(app_handle, &run, "Quick health check");
+    if let Err(e) = app_handle.emit("quick-check-complete", run) {
+        log::warn!("Failed to emit quick-check-complete: {}", e);
+    }
+}
+
+fn on_press(app_handle: &tauri::AppHandle) -> Callback {
+    let app_handle = app_handle.clone();
+    Box::new(move || {
+        let handle = app_handle.clone();
+        tauri::async_runtime::spawn(async move { quick_check(&handle).await });
+    })
+}
+
+/// Registers `shortcut` in place of the current one; `None` only unregisters.
+fn apply(app_handle: &tauri::AppHandle, shortcut: Option<&str>) -> Result<(), String> {
+    let accelerator = shortcut.map(parse).transpose()?;
+    let state = app_handle.state::<ShortcutState>();
+    let mut registration = state.registration.lock().unwrap();
+    // Dropped first, so setting the same shortcut again doesn't clash with itself
+    *registration = None;
+    if let Some(accelerator) = accelerator {
+        *registration = Some(platform::register(app_handle, accelerator, on_press(app_handle))?);
+        log::info!("Registered global shortcut {}", accelerator);
+    }
+    Ok(())
+}
+
+/// Registers the saved shortcut, if any; called once from setup.
+pub fn init(app_handle: &tauri::AppHandle) {
+    let shortcut = app_handle.state::<SettingsStore>().settings.lock().unwrap().global_shortcut.clone();
+    if let Err(e) = apply(app_handle, shortcut.as_deref()) {
+        log::warn!("Failed to register global shortcut: {}", e);
+    }
+}
+
+/// Unregisters the shortcut; called on exit.
+pub fn unregister(app_handle: &tauri::AppHandle) {
+    app_handle.state::<ShortcutState>().registration.lock().unwrap().take();
+}
+
+#[tauri::command]
+pub fn get_global_shortcut(app_handle: tauri::AppHandle) -> Option<String> {
+    app_handle.state::<SettingsStore>().settings.lock().unwrap().global_shortcut.clone()
+}
+
+/// Sets the shortcut that runs a quick health check, or turns it off with
+/// `None`. Fails, keeping the previous shortcut, when another app already uses it.
+#[tauri::command]
+pub fn set_global_shortcut(app_handle: tauri::AppHandle, shortcut: Option<String>) -> Result<Option<String>, String> {
+    let shortcut = shortcut.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    let previous = get_global_shortcut(app_handle.clone());
+    if let Err(e) = apply(&app_handle, shortcut.as_deref()) {
+        if let Err(restore) = apply(&app_handle, previous.as_deref()) {
+            log::warn!("Failed to restore global shortcut: {}", restore);
+        }
+        return Err(e);
+    }
+
+    let store = app_handle.state::<SettingsStore>();
+    let mut current = store.settings.lock().unwrap();
+    current.global_shortcut = shortcut.clone();
+    crate::settings::save(&app_handle, &current)?;
+    Ok(shortcut)
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::{Accelerator, Callback, Key};
+    use std::sync::mpsc;
+    use std::thread::JoinHandle;
+    use windows_sys::Win32::System::Threading::GetCurrentThreadId;
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{
+        RegisterHotKey, UnregisterHotKey, MOD_ALT, MOD_CONTROL, MOD_NOREPEAT, MOD_SHIFT, MOD_WIN, VK_F1, VK_SPACE,
+    };
+    use windows_sys::Win32::UI::WindowsAndMessaging::{GetMessageW, PostThreadMessageW, MSG, WM_HOTKEY, WM_QUIT};
+
+    const HOT_KEY_ID: i32 = 1;
+    const ERROR_HOTKEY_ALREADY_REGISTERED: i32 = 1409;
+
+    pub struct Registration {
+        thread_id: u32,
+        thread: Option<JoinHandle<()>>,
+    }
+
+    impl Drop for Registration {
+        fn drop(&mut self) {
+            unsafe { PostThreadMessageW(self.thread_id, WM_QUIT, 0, 0) };
+            if let Some(thread) = self.thread.take() {
+                let _ = thread.join();
+            }
+        }
+    }
+
+    pub fn register(
+        _app_handle: &tauri::AppHandle,
+        accelerator: Accelerator,
+        on_press: Callback,
+    ) -> Result<Registration, String> {
+        let mut modifiers = MOD_NOREPEAT;
+        for (held, modifier) in [
+            (accelerator.ctrl, MOD_CONTROL),
+            (accelerator.alt, MOD_ALT),
+            (accelerator.shift, MOD_SHIFT),
+            (accelerator.meta, MOD_WIN),
+        ] {
+            if held {
+                modifiers |= modifier;
+            }
+        }
+        let key = match accelerator.key {
+            // Virtual-key codes for letters and digits are their ASCII codes
+            Key::Char(c) => c as u32,
+            Key::Function(n) => (VK_F1 + u16::from(n) - 1) as u32,
+            Key::Space => VK_SPACE as u32,
+        };
+
+        // A hot key is posted to the thread that registered it, so that thread
+        // registers it, runs the message loop and unregisters it on WM_QUIT
+        let (tx, rx) = mpsc::channel();
+        let thread = std::thread::Builder::new()
+            .name("global-shortcut".to_string())
+            .spawn(move || {
+                if unsafe { RegisterHotKey(std::ptr::null_mut(), HOT_KEY_ID, modifiers, key) } == 0 {
+                    let _ = tx.send(Err(std::io::Error::last_os_error()));
+                    return;
+                }
+                let _ = tx.send(Ok(unsafe { GetCurrentThreadId() }));
+                let mut msg: MSG = unsafe { std::mem::zeroed() };
+                while unsafe { GetMessageW(&mut msg, std::ptr::null_mut(), 0, 0) } > 0 {
+                    if msg.message == WM_HOTKEY {
+                        on_press();
+                    }
+                }
+                unsafe { UnregisterHotKey(std::ptr::null_mut(), HOT_KEY_ID) };
+            })
+            .map_err(|e| format!("Failed to start global shortcut thread: {}", e))?;
+
+        match rx.recv() {
+            Ok(Ok(thread_id)) => Ok(Registration { thread_id, thread: Some(thread) }),
+            Ok(Err(e)) if e.raw_os_error() == Some(ERROR_HOTKEY_ALREADY_REGISTERED) => {
+                Err(format!("{} is already used by another app", accelerator))
+            }
+            Ok(Err(e)) => Err(format!("Failed to register {}: {}", accelerator, e)),
+            Err(_) => Err("Global shortcut thread exited".to_string()),
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{Accelerator, Callback, Key};
+    use std::ffi::c_void;
+    use std::sync::mpsc;
+
+    type OSStatus = i32;
+    type EventTargetRef = *mut c_void;
+    type EventHandlerRef = *mut c_void;
+    type EventHotKeyRef = *mut c_void;
+    type EventHandler = extern "C" fn(*mut c_void, *mut c_void, *mut c_void) -> OSStatus;
+
+    #[repr(C)]
+    struct EventTypeSpec {
+        event_class: u32,
+        event_kind: u32,
+    }
+
+    #[repr(C)]
+    struct EventHotKeyID {
+        signature: u32,
+        id: u32,
+    }
+
+    #[link(name = "Carbon", kind = "framework")]
+    extern "C" {
+        fn GetApplicationEventTarget() -> EventTargetRef;
+        fn InstallEventHandler(
+            target: EventTargetRef,
+            handler: EventHandler,
+            num_types: u32,
+            types: *const EventTypeSpec,
+            user_data: *mut c_void,
+            out_ref: *mut EventHandlerRef,
+        ) -> OSStatus;
+        fn RemoveEventHandler(handler: EventHandlerRef) -> OSStatus;
+        fn RegisterEventHotKey(
+            key_code: u32,
+            modifiers: u32,
+            id: EventHotKeyID,
+            target: EventTargetRef,
+            options: u32,
+            out_ref: *mut EventHotKeyRef,
+        ) -> OSStatus;
+        fn UnregisterEventHotKey(hot_key: EventHotKeyRef) -> OSStatus;
+    }
+
+    const EVENT_CLASS_KEYBOARD: u32 = u32::from_be_bytes(*b"keyb");
+    const EVENT_HOT_KEY_PRESSED: u32 = 5;
+    const HOT_KEY_SIGNATURE: u32 = u32::from_be_bytes(*b"CHCk");
+    const HOT_KEY_EXISTS: OSStatus = -9878;
+
+    const CMD_KEY: u32 = 1 << 8;
+    const SHIFT_KEY: u32 = 1 << 9;
+    const OPTION_KEY: u32 = 1 << 11;
+    const CONTROL_KEY: u32 = 1 << 12;
+
+    /// Virtual key codes (`kVK_ANSI_*`) of A to Z, 0 to 9, F1 to F20 and Space.
+    const LETTER_KEYS: [u32; 26] = [
+        0x00, 0x0B, 0x08, 0x02, 0x0E, 0x03, 0x05, 0x04, 0x22, 0x26, 0x28, 0x25, 0x2E, 0x2D, 0x1F, 0x23, 0x0C, 0x0F,
+        0x01, 0x11, 0x20, 0x09, 0x0D, 0x07, 0x10, 0x06,
+    ];
+    const DIGIT_KEYS: [u32; 10] = [0x1D, 0x12, 0x13, 0x14, 0x15, 0x17, 0x16, 0x1A, 0x1C, 0x19];
+    const FUNCTION_KEYS: [u32; 20] = [
+        0x7A, 0x78, 0x63, 0x76, 0x60, 0x61, 0x62, 0x64, 0x65, 0x6D, 0x67, 0x6F, 0x69, 0x6B, 0x71, 0x6A, 0x40, 0x4F,
+        0x50, 0x5A,
+    ];
+    const SPACE_KEY: u32 = 0x31;
+
+    extern "C" fn on_hot_key(_call: *mut c_void, _event: *mut c_void, user_data: *mut c_void) -> OSStatus {
+        let on_press = unsafe { &*(user_data as *const Callback) };
+        on_press();
+        0
+    }
+
+    /// What `install` created, as addresses so they can be sent to the main thread.
+    struct Installed {
+        hot_key: usize,
+        handler: usize,
+        callback: usize,
+    }
+
+    impl Installed {
+        /// Must run on the main thread.
+        unsafe fn release(self) {
+            UnregisterEventHotKey(self.hot_key as EventHotKeyRef);
+            RemoveEventHandler(self.handler as EventHandlerRef);
+            drop(Box::from_raw(self.callback as *mut Callback));
+        }
+    }
+
+    /// Must run on the main thread, whose event loop Carbon hot keys are delivered to.
+    unsafe fn install(key_code: u32, modifiers: u32, on_press: Callback) -> Result<Installed, OSStatus> {
+        let callback = Box::into_raw(Box::new(on_press));
+        let types = EventTypeSpec { event_class: EVENT_CLASS_KEYBOARD, event_kind: EVENT_HOT_KEY_PRESSED };
+        let mut handler: EventHandlerRef = std::ptr::null_mut();
+        let status = InstallEventHandler(
+            GetApplicationEventTarget(),
+            on_hot_key,
+            1,
+            &types,
+            callback as *mut c_void,
+            &mut handler,
+        );
+        if status != 0 {
+            drop(Box::from_raw(callback));
+            return Err(status);
+        }
+        let mut hot_key: EventHotKeyRef = std::ptr::null_mut();
+        let id = EventHotKeyID { signature: HOT_KEY_SIGNATURE, id: 1 };
+        let status = RegisterEventHotKey(key_code, modifiers, id, GetApplicationEventTarget(), 0, &mut hot_key);
+        if status != 0 {
+            RemoveEventHandler(handler);
+            drop(Box::from_raw(callback));
+            return Err(status);
+        }
+        Ok(Installed { hot_key: hot_key as usize, handler: handler as usize, callback: callback as usize })
+    }
+
+    fn on_main_thread() -> bool {
+        unsafe { libc::pthread_main_np() != 0 }
+    }
+
+    pub struct Registration {
+        app_handle: tauri::AppHandle,
+        installed: Option<Installed>,
+    }
+
+    impl Drop for Registration {
+        fn drop(&mut self) {
+            let Some(installed) = self.installed.take() else {
+                return;
+            };
+            // Released straight away when possible, so registering the same
+            // shortcut next doesn't find it still taken
+            if on_main_thread() {
+                unsafe { installed.release() };
+            } else if let Err(e) = self.app_handle.run_on_main_thread(move || unsafe { installed.release() }) {
+                log::warn!("Failed to unregister global shortcut: {}", e);
+            }
+        }
+    }
+
+    pub fn register(
+        app_handle: &tauri::AppHandle,
+        accelerator: Accelerator,
+        on_press: Callback,
+    ) -> Result<Registration, String> {
+        let mut modifiers = 0;
+        for (held, modifier) in [
+            (accelerator.ctrl, CONTROL_KEY),
+            (accelerator.alt, OPTION_KEY),
+            (accelerator.shift, SHIFT_KEY),
+            (accelerator.meta, CMD_KEY),
+        ] {
+            if held {
+                modifiers |= modifier;
+            }
+        }
+        let key_code = match accelerator.key {
+            Key::Char(c @ b'A'..=b'Z') => LETTER_KEYS[usize::from(c - b'A')],
+            Key::Char(c) => DIGIT_KEYS[usize::from(c - b'0')],
+            Key::Function(n) => FUNCTION_KEYS[usize::from(n - 1)],
+            Key::Space => SPACE_KEY,
+        };
+
+        let (tx, rx) = mpsc::channel();
+        let task = move || {
+            let _ = tx.send(unsafe { install(key_code, modifiers, on_press) });
+        };
+        // Setup and synchronous commands already run on the main thread
+        if on_main_thread() {
+            task();
+        } else {
+            app_handle
+                .run_on_main_thread(task)
+                .map_err(|e| format!("Failed to register {}: {}", accelerator, e))?;
+        }
+
+        match rx.recv() {
+            Ok(Ok(installed)) => Ok(Registration { app_handle: app_handle.clone(), installed: Some(installed) }),
+            Ok(Err(HOT_KEY_EXISTS)) => Err(format!("{} is already used by another app", accelerator)),
+            Ok(Err(status)) => Err(format!("Failed to register {}: OSStatus {}", accelerator, status)),
+            Err(_) => Err(format!("Failed to register {}: the main thread is gone", accelerator)),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::{Accelerator, Callback, Key};
+    use std::os::raw::{c_int, c_uint, c_ulong};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{mpsc, Arc};
+    use std::thread::JoinHandle;
+    use x11_dl::xlib;
+
+    /// Grabbed alongside the shortcut, so it still works with Caps Lock or Num Lock on.
+    const LOCK_MASKS: [c_uint; 4] = [0, xlib::LockMask, xlib::Mod2Mask, xlib::LockMask | xlib::Mod2Mask];
+    /// How long the event loop waits for X events before checking whether to stop.
+    const POLL_TIMEOUT_MS: c_int = 250;
+    const XK_F1: c_ulong = 0xffbe;
+
+    /// Set by `on_error` when a grab fails because another client holds the key.
+    static GRAB_FAILED: AtomicBool = AtomicBool::new(false);
+
+    unsafe extern "C" fn on_error(_display: *mut xlib::Display, event: *mut xlib::XErrorEvent) -> c_int {
+        if (*event).error_code == xlib::BadAccess {
+            GRAB_FAILED.store(true, Ordering::SeqCst);
+        }
+        0
+    }
+
+    pub struct Registration {
+        stop: Arc<AtomicBool>,
+        thread: Option<JoinHandle<()>>,
+    }
+
+    impl Drop for Registration {
+        fn drop(&mut self) {
+            self.stop.store(true, Ordering::SeqCst);
+            if let Some(thread) = self.thread.take() {
+                let _ = thread.join();
+            }
+        }
+    }
+
+    struct Grab<'a> {
+        xlib: &'a xlib::Xlib,
+        display: *mut xlib::Display,
+        root: xlib::Window,
+        keycode: c_int,
+        modifiers: c_uint,
+    }
+
+    impl Grab<'_> {
+        /// Grabs the key on the root window; false when another client already has it.
+        unsafe fn grab(&self) -> bool {
+            GRAB_FAILED.store(false, Ordering::SeqCst);
+            // Grab errors arrive asynchronously; the handler is process-wide, so it's
+            // only swapped in until the server has answered
+            let previous = (self.xlib.XSetErrorHandler)(Some(on_error));
+            for lock in LOCK_MASKS {
+                (self.xlib.XGrabKey)(
+                    self.display,
+                    self.keycode,
+                    self.modifiers | lock,
+                    self.root,
+                    xlib::False,
+                    xlib::GrabModeAsync,
+                    xlib::GrabModeAsync,
+                );
+            }
+            (self.xlib.XSync)(self.display, xlib::False);
+            (self.xlib.XSetErrorHandler)(previous);
+            !GRAB_FAILED.load(Ordering::SeqCst)
+        }
+
+        unsafe fn ungrab(&self) {
+            for lock in LOCK_MASKS {
+                (self.xlib.XUngrabKey)(self.display, self.keycode, self.modifiers | lock, self.root);
+            }
+            (self.xlib.XSync)(self.display, xlib::False);
+        }
+
+        /// Calls `on_press` for each press of the key until `stop` is set.
+        unsafe fn listen(&self, stop: &AtomicBool, on_press: &Callback) {
+            let mut event: xlib::XEvent = std::mem::zeroed();
+            let mut poll = libc::pollfd {
+                fd: (self.xlib.XConnectionNumber)(self.display),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            while !stop.load(Ordering::SeqCst) {
+                while (self.xlib.XPending)(self.display) > 0 {
+                    (self.xlib.XNextEvent)(self.display, &mut event);
+                    if event.get_type() == xlib::KeyPress {
+                        on_press();
+                    }
+                }
+                libc::poll(&mut poll, 1, POLL_TIMEOUT_MS);
+            }
+        }
+    }
+
+    /// Opens its own X connection, grabs the key and listens for presses until
+    /// `stop` is set. `ready` gets whether the grab worked.
+    fn run(
+        keysym: c_ulong,
+        modifiers: c_uint,
+        name: String,
+        stop: &AtomicBool,
+        on_press: Callback,
+        ready: mpsc::Sender<Result<(), String>>,
+    ) {
+        let xlib = match xlib::Xlib::open() {
+            Ok(xlib) => xlib,
+            Err(e) => {
+                let _ = ready.send(Err(format!("Global shortcuts need X11: {}", e)));
+                return;
+            }
+        };
+        unsafe {
+            let display = (xlib.XOpenDisplay)(std::ptr::null());
+            if display.is_null() {
+                let _ = ready.send(Err("Global shortcuts need an X11 session; Wayland isn't supported".to_string()));
+                return;
+            }
+            let grab = Grab {
+                xlib: &xlib,
+                display,
+                root: (xlib.XDefaultRootWindow)(display),
+                keycode: c_int::from((xlib.XKeysymToKeycode)(display, keysym)),
+                modifiers,
+            };
+            if grab.keycode == 0 {
+                let _ = ready.send(Err(format!("{} has no key on this keyboard", name)));
+            } else if !grab.grab() {
+                grab.ungrab();
+                let _ = ready.send(Err(format!("{} is already used by another app", name)));
+            } else {
+                let _ = ready.send(Ok(()));
+                grab.listen(stop, &on_press);
+                grab.ungrab();
+            }
+            (xlib.XCloseDisplay)(display);
+        }
+    }
+
+    pub fn register(
+        _app_handle: &tauri::AppHandle,
+        accelerator: Accelerator,
+        on_press: Callback,
+    ) -> Result<Registration, String> {
+        let mut modifiers = 0;
+        for (held, modifier) in [
+            (accelerator.ctrl, xlib::ControlMask),
+            (accelerator.alt, xlib::Mod1Mask),
+            (accelerator.shift, xlib::ShiftMask),
+            (accelerator.meta, xlib::Mod4Mask),
+        ] {
+            if held {
+                modifiers |= modifier;
+            }
+        }
+        let keysym = match accelerator.key {
+            // Letter keysyms are the lower-case ASCII codes; digits are ASCII too
+            Key::Char(c) => c_ulong::from(c.to_ascii_lowercase()),
+            Key::Function(n) => XK_F1 + c_ulong::from(n) - 1,
+            Key::Space => c_ulong::from(b' '),
+        };
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = mpsc::channel();
+        let thread = {
+            let stop = stop.clone();
+            let name = accelerator.to_string();
+            std::thread::Builder::new()
+                .name("global-shortcut".to_string())
+                .spawn(move || run(keysym, modifiers, name, &stop, on_press, tx))
+                .map_err(|e| format!("Failed to start global shortcut thread: {}", e))?
+        };
+        match rx.recv() {
+            Ok(Ok(())) => Ok(Registration { stop, thread: Some(thread) }),
+            Ok(Err(e)) => {
+                let _ = thread.join();
+                Err(e)
+            }
+            Err(_) => Err("Global shortcut thread exited".to_string()),
+        }
+    }
+}
+
+#[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+mod platform {
+    use super::{Accelerator, Callback};
+
+    pub struct Registration;
+
+    pub fn register(
+        _app_handle: &tauri::AppHandle,
+        _accelerator: Accelerator,
+        _on_press: Callback,
+    ) -> Result<Registration, String> {
+        Err("Global shortcuts aren't supported on this platform".to_string())
+    }
+}
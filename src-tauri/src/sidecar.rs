@@ -0,0 +1,501 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+/// Enough of the file to cover ELF/Mach-O/PE headers, including universal binaries.
+const HEADER_READ_LIMIT: u64 = 64 * 1024;
+
+/// SHA-256 of the backend that was bundled at build time; empty if none was present.
+pub const EXPECTED_SHA256: &str = env!("CRIBL_HC_BACKEND_SHA256");
+
+/// Points the app at a different backend binary, e.g. a local build.
+const PATH_OVERRIDE_VAR: &str = "CRIBL_HC_BACKEND_BIN";
+
+fn path_override() -> Option<PathBuf> {
+    std::env::var_os(PATH_OVERRIDE_VAR)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Path of the backend binary: `CRIBL_HC_BACKEND_BIN` if set, otherwise one an
+/// update installed, otherwise the bundled one.
+pub fn resolve_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    if let Some(path) = path_override() {
+        return Ok(path);
+    }
+    if let Some(installed) = crate::update::installed_backend(app_handle) {
+        return Ok(PathBuf::from(installed.path));
+    }
+
+    Ok(app_handle
+        .path()
+        .resource_dir()
+        .map_err(|e| format!("Failed to get resource dir: {}", e))?
+        .join("binaries")
+        .join("cribl-hc-backend"))
+}
+
+/// Path of the backend binary when there is no app handle, as in headless mode:
+/// `CRIBL_HC_BACKEND_BIN` if set, otherwise the sidecar installed beside our executable.
+pub fn resolve_path_beside_exe() -> Result<PathBuf, String> {
+    if let Some(path) = path_override() {
+        return Ok(path);
+    }
+
+    let exe = std::env::current_exe().map_err(|e| format!("Failed to locate executable: {}", e))?;
+    let path = exe
+        .parent()
+        .ok_or("Executable has no parent directory")?
+        .join("cribl-hc-backend");
+    if !file_on_disk(&path).is_file() {
+        return Err(format!("Backend not found at {}", file_on_disk(&path).display()));
+    }
+    Ok(path)
+}
+
+/// Sits next to the bundled backend when it ships as a script plus an interpreter.
+const LAUNCH_DESCRIPTOR_FILE: &str = "backend-launch.json";
+
+/// How to run a backend that isn't a self-contained executable. Relative paths
+/// resolve against the directory holding the descriptor.
+#[derive(Debug, Deserialize)]
+pub struct LaunchDescriptor {
+    pub interpreter: PathBuf,
+    pub script: Option<PathBuf>,
+    /// Passed after the script and before the app's own flags.
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+/// The bundled launch descriptor, if any. `CRIBL_HC_BACKEND_BIN` and updated
+/// backends name an executable, so they take precedence.
+pub fn launch_descriptor(app_handle: &tauri::AppHandle) -> Result<Option<LaunchDescriptor>, String> {
+    if path_override().is_some() || crate::update::installed_backend(app_handle).is_some() {
+        return Ok(None);
+    }
+
+    let dir = app_handle
+        .path()
+        .resource_dir()
+        .map_err(|e| format!("Failed to get resource dir: {}", e))?
+        .join("binaries");
+    let path = dir.join(LAUNCH_DESCRIPTOR_FILE);
+
+    let bytes = match std::fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    let mut descriptor: LaunchDescriptor = serde_json::from_slice(&bytes)
+        .map_err(|e| format!("Invalid launch descriptor {}: {}", path.display(), e))?;
+
+    descriptor.interpreter = file_on_disk(&dir.join(&descriptor.interpreter));
+    descriptor.script = descriptor.script.map(|script| dir.join(script));
+
+    if !descriptor.interpreter.is_file() {
+        return Err(format!("Backend interpreter not found: {}", descriptor.interpreter.display()));
+    }
+    if let Some(script) = descriptor.script.as_ref().filter(|script| !script.is_file()) {
+        return Err(format!("Backend script not found: {}", script.display()));
+    }
+
+    Ok(Some(descriptor))
+}
+
+/// The file behind a resolved backend path. Bundled sidecars keep their .exe suffix
+/// on Windows even though `Command` finds them without it.
+pub fn file_on_disk(path: &Path) -> PathBuf {
+    if cfg!(windows) && path.extension().is_none() {
+        path.with_extension("exe")
+    } else {
+        path.to_path_buf()
+    }
+}
+
+#[derive(Serialize)]
+pub struct SidecarPath {
+    path: String,
+    overridden: bool,
+    exists: bool,
+    size: Option<u64>,
+    /// Milliseconds since the Unix epoch.
+    modified_ms: Option<u64>,
+}
+
+/// Reports which backend binary `start_backend` would launch.
+#[tauri::command]
+pub fn get_sidecar_path(app_handle: tauri::AppHandle) -> Result<SidecarPath, String> {
+    let path = file_on_disk(&resolve_path(&app_handle)?);
+    let metadata = std::fs::metadata(&path).ok();
+
+    Ok(SidecarPath {
+        path: path.to_string_lossy().to_string(),
+        overridden: path_override().is_some(),
+        exists: metadata.is_some(),
+        size: metadata.as_ref().map(|m| m.len()),
+        modified_ms: metadata
+            .and_then(|m| m.modified().ok())
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as u64),
+    })
+}
+
+#[derive(Serialize)]
+pub struct ArchCheck {
+    pub format: String,
+    pub binary_archs: Vec<String>,
+    pub host_arch: String,
+    pub matches: bool,
+}
+
+fn u16_at(bytes: &[u8], offset: usize, little_endian: bool) -> Option<u16> {
+    let raw: [u8; 2] = bytes.get(offset..offset + 2)?.try_into().ok()?;
+    Some(if little_endian { u16::from_le_bytes(raw) } else { u16::from_be_bytes(raw) })
+}
+
+fn u32_at(bytes: &[u8], offset: usize, little_endian: bool) -> Option<u32> {
+    let raw: [u8; 4] = bytes.get(offset..offset + 4)?.try_into().ok()?;
+    Some(if little_endian { u32::from_le_bytes(raw) } else { u32::from_be_bytes(raw) })
+}
+
+fn elf_arch(machine: u16) -> &'static str {
+    match machine {
+        0x03 => "x86",
+        0x28 => "arm",
+        0x3E => "x86_64",
+        0xB7 => "aarch64",
+        _ => "unknown",
+    }
+}
+
+fn macho_arch(cputype: u32) -> &'static str {
+    match cputype {
+        0x0000_0007 => "x86",
+        0x0100_0007 => "x86_64",
+        0x0000_000C => "arm",
+        0x0100_000C => "aarch64",
+        _ => "unknown",
+    }
+}
+
+fn pe_arch(machine: u16) -> &'static str {
+    match machine {
+        0x014C => "x86",
+        0x8664 => "x86_64",
+        0xAA64 => "aarch64",
+        _ => "unknown",
+    }
+}
+
+/// Reads the executable header and returns its format and target architectures.
+fn detect_arch(bytes: &[u8]) -> Option<(&'static str, Vec<&'static str>)> {
+    match bytes.get(..4)? {
+        [0x7F, b'E', b'L', b'F'] => {
+            let little_endian = *bytes.get(5)? == 1;
+            Some(("elf", vec![elf_arch(u16_at(bytes, 18, little_endian)?)]))
+        }
+        [0xCF, 0xFA, 0xED, 0xFE] | [0xCE, 0xFA, 0xED, 0xFE] => {
+            Some(("mach-o", vec![macho_arch(u32_at(bytes, 4, true)?)]))
+        }
+        [0xCA, 0xFE, 0xBA, 0xBE] => {
+            // Universal binary: big-endian fat header followed by 20-byte arch entries.
+            let count = u32_at(bytes, 4, false)? as usize;
+            let archs = (0..count)
+                .map(|i| u32_at(bytes, 8 + i * 20, false).map(macho_arch))
+                .collect::<Option<Vec<_>>>()?;
+            Some(("mach-o-universal", archs))
+        }
+        [b'M', b'Z', _, _] => {
+            let pe_offset = u32_at(bytes, 0x3C, true)? as usize;
+            if bytes.get(pe_offset..pe_offset + 4)? != b"PE\0\0" {
+                return None;
+            }
+            Some(("pe", vec![pe_arch(u16_at(bytes, pe_offset + 4, true)?)]))
+        }
+        _ => None,
+    }
+}
+
+/// Compares the binary's architecture against the host. `None` means the
+/// format wasn't recognised (e.g. a script), in which case there is nothing to check.
+pub fn check_arch(path: &Path) -> Result<Option<ArchCheck>, String> {
+    let mut header = Vec::new();
+    File::open(file_on_disk(path))
+        .and_then(|f| f.take(HEADER_READ_LIMIT).read_to_end(&mut header))
+        .map_err(|e| format!("Failed to read backend binary: {}", e))?;
+
+    let Some((format, archs)) = detect_arch(&header) else {
+        return Ok(None);
+    };
+
+    let host_arch = std::env::consts::ARCH;
+    Ok(Some(ArchCheck {
+        format: format.to_string(),
+        matches: archs.contains(&host_arch),
+        binary_archs: archs.into_iter().map(String::from).collect(),
+        host_arch: host_arch.to_string(),
+    }))
+}
+
+#[tauri::command]
+pub fn check_backend_arch(app_handle: tauri::AppHandle) -> Result<ArchCheck, String> {
+    let path = resolve_path(&app_handle)?;
+    check_arch(&path)?.ok_or_else(|| "Unrecognised backend binary format".to_string())
+}
+
+pub fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file = File::open(file_on_disk(path)).map_err(|e| format!("Failed to open backend binary: {}", e))?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher).map_err(|e| format!("Failed to hash backend binary: {}", e))?;
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Set to anything to launch a backend that doesn't match `EXPECTED_SHA256`, for development.
+const SKIP_VERIFY_VAR: &str = "CRIBL_HC_SKIP_BACKEND_VERIFY";
+
+/// SHA-256 the backend `resolve_path` picks should have: the one its update
+/// was signed with, otherwise the one bundled at build time.
+pub fn expected_sha256(app_handle: &tauri::AppHandle) -> Option<String> {
+    match crate::update::installed_backend(app_handle) {
+        Some(installed) => Some(installed.sha256),
+        None => (!EXPECTED_SHA256.is_empty()).then(|| EXPECTED_SHA256.to_string()),
+    }
+}
+
+/// Fails unless the backend is byte-for-byte the one this build shipped with, or
+/// the one an update installed. A backend chosen with `CRIBL_HC_BACKEND_BIN`, or a
+/// build that bundled none, has nothing to compare against and passes.
+pub fn verify_integrity(app_handle: &tauri::AppHandle, path: &Path) -> Result<(), String> {
+    if std::env::var_os(SKIP_VERIFY_VAR).is_some_and(|value| !value.is_empty()) {
+        log::warn!("{} is set; not verifying the backend binary", SKIP_VERIFY_VAR);
+        return Ok(());
+    }
+    if path_override().is_some() {
+        return Ok(());
+    }
+    let Some(expected) = expected_sha256(app_handle) else {
+        return Ok(());
+    };
+
+    let actual = sha256_file(path)?;
+    if actual != expected {
+        return Err(format!(
+            "Backend binary {} has been modified (SHA-256 {}, expected {}); reinstall the app",
+            file_on_disk(path).display(),
+            actual,
+            expected
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+pub struct Freshness {
+    expected_sha256: Option<String>,
+    actual_sha256: String,
+    matches: bool,
+}
+
+/// Detects installs where the app was updated but the bundled backend was not.
+#[tauri::command]
+pub fn check_backend_freshness(app_handle: tauri::AppHandle) -> Result<Freshness, String> {
+    let path = resolve_path(&app_handle)?;
+    let actual_sha256 = sha256_file(&path)?;
+    let expected_sha256 = expected_sha256(&app_handle);
+
+    Ok(Freshness {
+        matches: expected_sha256.as_deref() == Some(actual_sha256.as_str()),
+        expected_sha256,
+        actual_sha256,
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+// Each platform only ever produces some of these.
+#[allow(dead_code)]
+pub enum SignatureState {
+    Signed,
+    Unsigned,
+    NotApplicable,
+}
+
+#[derive(Serialize)]
+pub struct SignatureStatus {
+    pub state: SignatureState,
+    pub identity: Option<String>,
+    pub detail: Option<String>,
+}
+
+#[cfg(target_os = "macos")]
+fn signature_status(path: &Path) -> Result<SignatureStatus, String> {
+    use std::process::Command;
+
+    let verify = Command::new("codesign")
+        .args(["--verify", "--strict"])
+        .arg(path)
+        .output()
+        .map_err(|e| format!("Failed to run codesign: {}", e))?;
+
+    if !verify.status.success() {
+        return Ok(SignatureStatus {
+            state: SignatureState::Unsigned,
+            identity: None,
+            detail: Some(String::from_utf8_lossy(&verify.stderr).trim().to_string()),
+        });
+    }
+
+    // `codesign -dv` prints its details on stderr; the first Authority is the leaf certificate.
+    let details = Command::new("codesign")
+        .args(["-dv", "--verbose=2"])
+        .arg(path)
+        .output()
+        .map_err(|e| format!("Failed to run codesign: {}", e))?;
+    let details = String::from_utf8_lossy(&details.stderr);
+    let identity = details
+        .lines()
+        .find_map(|line| line.strip_prefix("Authority="))
+        .map(str::to_string);
+    let adhoc = details.lines().any(|line| line == "Signature=adhoc");
+
+    Ok(SignatureStatus {
+        state: if adhoc { SignatureState::Unsigned } else { SignatureState::Signed },
+        identity,
+        detail: adhoc.then(|| "Ad-hoc signature".to_string()),
+    })
+}
+
+#[cfg(target_os = "windows")]
+fn signature_status(path: &Path) -> Result<SignatureStatus, String> {
+    use std::process::Command;
+
+    let path = file_on_disk(path);
+    let literal = path.to_string_lossy().replace('\'', "''");
+    let script = format!(
+        "$s = Get-AuthenticodeSignature -LiteralPath '{}'; $s.Status; $s.SignerCertificate.Subject",
+        literal
+    );
+
+    let output = Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+        .output()
+        .map_err(|e| format!("Failed to run powershell: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to read Authenticode signature: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut lines = stdout.lines().map(str::trim).filter(|l| !l.is_empty());
+    let status = lines.next().unwrap_or("Unknown").to_string();
+    let identity = lines.next().map(str::to_string);
+
+    Ok(SignatureStatus {
+        state: if status == "Valid" { SignatureState::Signed } else { SignatureState::Unsigned },
+        identity,
+        detail: Some(status),
+    })
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn signature_status(_path: &Path) -> Result<SignatureStatus, String> {
+    Ok(SignatureStatus {
+        state: SignatureState::NotApplicable,
+        identity: None,
+        detail: Some("Code signing is not applicable on this platform".to_string()),
+    })
+}
+
+/// Fails unless the backend is signed, for installs that require signed binaries.
+/// Platforms without code signing pass.
+pub fn require_signed(path: &Path) -> Result<(), String> {
+    let status = signature_status(path)?;
+    match status.state {
+        SignatureState::Unsigned => Err(format!(
+            "Backend binary is not signed{}",
+            status.detail.map(|d| format!(": {}", d)).unwrap_or_default()
+        )),
+        SignatureState::Signed | SignatureState::NotApplicable => Ok(()),
+    }
+}
+
+#[tauri::command]
+pub async fn verify_sidecar_signature(app_handle: tauri::AppHandle) -> Result<SignatureStatus, String> {
+    let path = resolve_path(&app_handle)?;
+    signature_status(&path)
+}
+
+#[derive(Serialize)]
+pub struct DependencyCheck {
+    applicable: bool,
+    /// Shared libraries the dynamic loader could not find, e.g. `libz.so.1`.
+    missing: Vec<String>,
+    detail: Option<String>,
+}
+
+#[cfg(target_os = "linux")]
+fn dependency_check(path: &Path) -> Result<DependencyCheck, String> {
+    use std::process::Command;
+
+    let output = Command::new("ldd")
+        .arg(file_on_disk(path))
+        .output()
+        .map_err(|e| format!("Failed to run ldd: {}", e))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    // e.g. "\tlibz.so.1 => not found"
+    let missing = stdout
+        .lines()
+        .filter_map(|line| line.split_once("=>"))
+        .filter(|(_, target)| target.trim() == "not found")
+        .map(|(name, _)| name.trim().to_string())
+        .collect();
+
+    // ldd exits non-zero for static binaries and scripts, which have nothing to resolve
+    let detail = (!output.status.success()).then(|| {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        [stdout.trim(), stderr.trim()]
+            .into_iter()
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    });
+
+    Ok(DependencyCheck {
+        applicable: true,
+        missing,
+        detail,
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn dependency_check(_path: &Path) -> Result<DependencyCheck, String> {
+    Ok(DependencyCheck {
+        applicable: false,
+        missing: Vec::new(),
+        detail: Some("Shared library checks only apply on Linux".to_string()),
+    })
+}
+
+/// Adds any missing shared libraries to a startup error, since the loader's own
+/// failure usually surfaces as a bare exit or an unhelpful spawn error.
+pub fn explain_startup_failure(path: &Path, error: String) -> String {
+    match dependency_check(path) {
+        Ok(check) if !check.missing.is_empty() => {
+            format!("{} (missing shared libraries: {})", error, check.missing.join(", "))
+        }
+        _ => error,
+    }
+}
+
+#[tauri::command]
+pub async fn check_backend_dependencies(app_handle: tauri::AppHandle) -> Result<DependencyCheck, String> {
+    let path = resolve_path(&app_handle)?;
+    dependency_check(&path)
+}
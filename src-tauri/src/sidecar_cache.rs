@@ -0,0 +1,266 @@
+//! Runs the backend from a local cache when the bundled copy sits on a slow volume.
+//!
+//! Encrypted (eCryptfs) and network home directories can read so slowly that a
+//! one-file backend, which unpacks itself on every start, runs into the startup
+//! timeout. Timing a short read of the binary tells us whether that's likely; if so
+//! the binary is copied once into the app's cache, keyed by its SHA-256, and
+//! launched from there on later runs until the bundled file changes.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use tauri::{Emitter, Manager};
+
+use crate::settings::{self, BackendSettings, SettingsStore};
+use crate::sidecar;
+
+/// How much of the binary is read to judge the volume.
+const SAMPLE_BYTES: u64 = 4 * 1024 * 1024;
+/// A local disk reads the sample in a few tens of milliseconds even when cold.
+const SLOW_SAMPLE_READ: Duration = Duration::from_millis(250);
+const CACHE_DIR: &str = "sidecar";
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Identifies the bundled file a cached copy was made from, so it can be reused
+/// without rehashing the original on the slow volume.
+#[derive(Serialize, Deserialize, PartialEq)]
+struct Manifest {
+    source: String,
+    size: u64,
+    modified_ms: u64,
+    sha256: String,
+}
+
+#[derive(Clone, Serialize)]
+pub struct VolumeProbe {
+    path: String,
+    sample_bytes: u64,
+    read_ms: u64,
+    slow: bool,
+}
+
+#[derive(Clone, Serialize)]
+struct Relocated {
+    source: String,
+    cached: String,
+    /// False when a copy from an earlier run was reused.
+    copied: bool,
+}
+
+fn probe(path: &Path) -> Result<VolumeProbe, String> {
+    let started = Instant::now();
+    let mut sample = Vec::new();
+    File::open(path)
+        .and_then(|f| f.take(SAMPLE_BYTES).read_to_end(&mut sample))
+        .map_err(|e| format!("Failed to read backend binary: {}", e))?;
+    let elapsed = started.elapsed();
+
+    Ok(VolumeProbe {
+        path: path.to_string_lossy().to_string(),
+        sample_bytes: sample.len() as u64,
+        read_ms: elapsed.as_millis() as u64,
+        slow: elapsed >= SLOW_SAMPLE_READ,
+    })
+}
+
+fn cache_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    app_handle
+        .path()
+        .app_cache_dir()
+        .map(|dir| dir.join(CACHE_DIR))
+        .map_err(|e| format!("Failed to get cache dir: {}", e))
+}
+
+fn source_identity(source: &Path) -> Result<(u64, u64), String> {
+    let metadata = fs::metadata(source).map_err(|e| format!("Failed to read backend binary: {}", e))?;
+    let modified_ms = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_millis() as u64);
+    Ok((metadata.len(), modified_ms))
+}
+
+fn cached_path(dir: &Path, sha256: &str, source: &Path) -> PathBuf {
+    dir.join(sha256).join(source.file_name().unwrap_or_default())
+}
+
+/// The copy made on an earlier run, if the bundled file hasn't changed since.
+fn reusable_copy(dir: &Path, source: &Path) -> Option<PathBuf> {
+    let manifest: Manifest = serde_json::from_slice(&fs::read(dir.join(MANIFEST_FILE)).ok()?).ok()?;
+    let (size, modified_ms) = source_identity(source).ok()?;
+    if manifest.source != source.to_string_lossy() || manifest.size != size || manifest.modified_ms != modified_ms {
+        return None;
+    }
+
+    let cached = cached_path(dir, &manifest.sha256, source);
+    fs::metadata(&cached).is_ok_and(|m| m.len() == size).then_some(cached)
+}
+
+/// Copies and hashes `source` in one pass, then moves it under its hash and drops
+/// copies of older builds.
+fn copy_to_cache(dir: &Path, source: &Path) -> Result<PathBuf, String> {
+    let (size, modified_ms) = source_identity(source)?;
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create sidecar cache: {}", e))?;
+
+    let temp = dir.join(format!(".copy.{}.tmp", std::process::id()));
+    let sha256 = (|| {
+        let mut reader = File::open(source)?;
+        let mut writer = File::create(&temp)?;
+        let mut hasher = Sha256::new();
+        let mut buffer = vec![0; 1024 * 1024];
+        loop {
+            let read = reader.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+            writer.write_all(&buffer[..read])?;
+        }
+        writer.sync_all()?;
+        fs::set_permissions(&temp, fs::metadata(source)?.permissions())?;
+        io::Result::Ok(format!("{:x}", hasher.finalize()))
+    })()
+    .map_err(|e| {
+        let _ = fs::remove_file(&temp);
+        format!("Failed to copy backend to cache: {}", e)
+    })?;
+
+    let cached = cached_path(dir, &sha256, source);
+    if let Some(parent) = cached.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create sidecar cache: {}", e))?;
+    }
+    fs::rename(&temp, &cached).map_err(|e| {
+        let _ = fs::remove_file(&temp);
+        format!("Failed to copy backend to cache: {}", e)
+    })?;
+
+    let manifest = Manifest {
+        source: source.to_string_lossy().to_string(),
+        size,
+        modified_ms,
+        sha256: sha256.clone(),
+    };
+    let json = serde_json::to_vec_pretty(&manifest).map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    crate::files::write_file_atomic(&dir.join(MANIFEST_FILE), &json)?;
+
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.filter_map(Result::ok) {
+            let stale = entry.file_type().is_ok_and(|t| t.is_dir()) && entry.file_name() != sha256.as_str();
+            if stale {
+                let _ = fs::remove_dir_all(entry.path());
+            }
+        }
+    }
+
+    Ok(cached)
+}
+
+fn emit<S: Serialize + Clone>(app_handle: &tauri::AppHandle, event: &str, payload: S) {
+    if let Err(e) = app_handle.emit(event, payload) {
+        log::warn!("Failed to emit {}: {}", event, e);
+    }
+}
+
+/// The path to launch the backend from: a cached local copy when the bundled binary
+/// is on a slow volume and caching is enabled, otherwise `path` itself. Problems
+/// here never stop the launch; they only mean launching from `path`.
+pub fn prepare(app_handle: &tauri::AppHandle, path: PathBuf, backend_settings: &BackendSettings) -> PathBuf {
+    let source = sidecar::file_on_disk(&path);
+    let dir = match cache_dir(app_handle) {
+        Ok(dir) => dir,
+        Err(e) => {
+            log::warn!("{}", e);
+            return path;
+        }
+    };
+
+    if backend_settings.cache_slow_sidecar {
+        if let Some(cached) = reusable_copy(&dir, &source) {
+            log::info!("Launching backend from cached copy {}", cached.display());
+            emit(
+                app_handle,
+                "backend-sidecar-relocated",
+                Relocated {
+                    source: source.to_string_lossy().to_string(),
+                    cached: cached.to_string_lossy().to_string(),
+                    copied: false,
+                },
+            );
+            return cached;
+        }
+    }
+
+    let volume = match probe(&source) {
+        Ok(volume) if volume.slow => volume,
+        Ok(_) => return path,
+        Err(e) => {
+            log::warn!("{}", e);
+            return path;
+        }
+    };
+    log::warn!(
+        "Backend binary reads slowly ({} ms for {} bytes); its volume may be encrypted or remote",
+        volume.read_ms,
+        volume.sample_bytes
+    );
+
+    // Let the frontend offer the cache rather than copying behind the user's back
+    if !backend_settings.cache_slow_sidecar {
+        emit(app_handle, "backend-sidecar-slow", volume);
+        return path;
+    }
+
+    match copy_to_cache(&dir, &source) {
+        Ok(cached) => {
+            log::info!("Copied backend to {} to launch from local disk", cached.display());
+            emit(
+                app_handle,
+                "backend-sidecar-relocated",
+                Relocated {
+                    source: source.to_string_lossy().to_string(),
+                    cached: cached.to_string_lossy().to_string(),
+                    copied: true,
+                },
+            );
+            cached
+        }
+        Err(e) => {
+            log::warn!("{}", e);
+            path
+        }
+    }
+}
+
+/// Times a read of the bundled backend to see whether it sits on a slow volume.
+#[tauri::command]
+pub async fn probe_sidecar_volume(app_handle: tauri::AppHandle) -> Result<VolumeProbe, String> {
+    let path = sidecar::file_on_disk(&sidecar::resolve_path(&app_handle)?);
+    tauri::async_runtime::spawn_blocking(move || probe(&path))
+        .await
+        .map_err(|e| format!("Failed to probe backend volume: {}", e))?
+}
+
+/// Disabling also removes any cached copy.
+#[tauri::command]
+pub fn set_sidecar_cache(app_handle: tauri::AppHandle, enabled: bool) -> Result<(), String> {
+    {
+        let store = app_handle.state::<SettingsStore>();
+        let mut current = store.settings.lock().unwrap();
+        current.backend.cache_slow_sidecar = enabled;
+        settings::save(&app_handle, &current)?;
+    }
+
+    if !enabled {
+        let dir = cache_dir(&app_handle)?;
+        match fs::remove_dir_all(&dir) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("Failed to clear sidecar cache: {}", e)),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,106 @@
+//! Why a backend launch failed, in a form the UI can act on rather than a bare
+//! string: serialized as `{ "kind": "Timeout", "message": ..., "output": [...] }`.
+
+use serde::Serialize;
+use std::fmt;
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum StartupErrorKind {
+    /// No backend binary (or interpreter) where one was expected.
+    BinaryMissing,
+    /// The binary is there but couldn't be run, or exited before its handshake.
+    SpawnFailed,
+    /// `startup_timeout_secs` ran out.
+    Timeout,
+    /// The backend printed something other than a `PORT:` line.
+    PortParseError,
+    /// The backend started but isn't serving its API.
+    HealthCheckFailed,
+    /// The bundled binary doesn't match the hash embedded at build time.
+    IntegrityCheckFailed,
+    /// The backend's version or API version is outside what this app supports.
+    IncompatibleBackend,
+    /// Anything else, like a bad working directory or a missing signature.
+    Other,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct StartupError {
+    pub kind: StartupErrorKind,
+    pub message: String,
+    /// What the backend printed before it failed, when it got that far.
+    pub output: Vec<String>,
+}
+
+impl StartupError {
+    pub fn new(kind: StartupErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            output: Vec::new(),
+        }
+    }
+
+    pub fn with_output(mut self, output: Vec<String>) -> Self {
+        self.output = output;
+        self
+    }
+
+    /// Adds any shared libraries the binary is missing to the message.
+    pub fn explain(mut self, path: &Path) -> Self {
+        self.message = crate::sidecar::explain_startup_failure(path, self.message);
+        self
+    }
+}
+
+impl fmt::Display for StartupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)?;
+        if let Some(last) = self.output.iter().rev().find(|line| !line.trim().is_empty()) {
+            write!(f, " (backend said: {})", last.trim())?;
+        }
+        Ok(())
+    }
+}
+
+impl From<String> for StartupError {
+    fn from(message: String) -> Self {
+        Self::new(StartupErrorKind::Other, message)
+    }
+}
+
+impl From<StartupError> for String {
+    fn from(error: StartupError) -> Self {
+        error.to_string()
+    }
+}
+
+/// Checks the binary is there and, on Unix, executable, before trying to spawn it.
+pub fn check_binary(path: &Path) -> Result<(), StartupError> {
+    let on_disk = crate::sidecar::file_on_disk(path);
+    let metadata = match std::fs::metadata(&on_disk) {
+        Ok(metadata) if metadata.is_file() => metadata,
+        _ => {
+            return Err(StartupError::new(
+                StartupErrorKind::BinaryMissing,
+                format!("Backend not found at {}", on_disk.display()),
+            ))
+        }
+    };
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o111 == 0 {
+            return Err(StartupError::new(
+                StartupErrorKind::SpawnFailed,
+                format!("Backend at {} is not executable", on_disk.display()),
+            ));
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = metadata;
+
+    Ok(())
+}
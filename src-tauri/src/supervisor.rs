@@ -0,0 +1,312 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+
+use crate::notifications::NotificationCategory;
+use crate::settings::{self, SettingsStore};
+use crate::{crash_report, process, Lifecycle, PythonBackend};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Delay before an automatic restart, doubling with each recent restart up to the cap.
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// More than this many automatic restarts within `RESTART_WINDOW` trips the breaker.
+const MAX_RESTARTS: usize = 5;
+const RESTART_WINDOW: Duration = Duration::from_secs(120);
+
+/// Stops auto-restarting a backend that keeps crashing.
+#[derive(Default)]
+pub struct RestartBreaker {
+    restarts: VecDeque<Instant>,
+    tripped: bool,
+}
+
+impl RestartBreaker {
+    pub fn is_tripped(&self) -> bool {
+        self.tripped
+    }
+
+    /// Records a restart attempt, returning false once the limit is exceeded.
+    fn allow_restart(&mut self) -> bool {
+        if self.tripped {
+            return false;
+        }
+
+        let now = Instant::now();
+        while let Some(at) = self.restarts.front() {
+            if now.duration_since(*at) <= RESTART_WINDOW {
+                break;
+            }
+            self.restarts.pop_front();
+        }
+
+        if self.restarts.len() >= MAX_RESTARTS {
+            self.tripped = true;
+            return false;
+        }
+
+        self.restarts.push_back(now);
+        true
+    }
+
+    fn reset(&mut self) {
+        self.restarts.clear();
+        self.tripped = false;
+    }
+
+    /// How long to wait before the next restart, given those already in the window.
+    fn backoff(&self) -> Duration {
+        let doublings = self.restarts.len().min(16) as u32;
+        INITIAL_BACKOFF.saturating_mul(1 << doublings).min(MAX_BACKOFF)
+    }
+}
+
+#[derive(Clone, Serialize)]
+struct BackendRecovered {
+    /// Jobs that were running on the killed backend; see `jobs`.
+    aborted_jobs: Vec<String>,
+    port: Option<u16>,
+}
+
+#[derive(Clone, Serialize)]
+struct BackendCrashed {
+    exit_code: Option<i32>,
+    /// Whether the supervisor is going to restart it.
+    restarting: bool,
+}
+
+/// Sleeps for `duration`, waking early and returning false if the app shuts down.
+fn sleep_unless_shutdown(state: &PythonBackend, duration: Duration) -> bool {
+    let deadline = Instant::now() + duration;
+    while !state.shutdown.is_cancelled() {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return true;
+        }
+        std::thread::sleep(remaining.min(POLL_INTERVAL));
+    }
+    false
+}
+
+/// Watches the spawned backend and restarts it if it exits on its own.
+/// The thread ends once the process is cleared from state or a restart succeeds,
+/// since a successful `start_backend` spawns a fresh supervisor.
+pub fn spawn(app_handle: tauri::AppHandle) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(POLL_INTERVAL);
+
+        let state = app_handle.state::<PythonBackend>();
+        if state.shutdown.is_cancelled() {
+            return;
+        }
+        let exit_status = {
+            let mut process = state.process.lock().unwrap();
+            let Some(child) = process.as_mut() else {
+                return;
+            };
+
+            match child.try_wait() {
+                Ok(Some(status)) => {
+                    *process = None;
+                    status
+                }
+                _ => continue,
+            }
+        };
+
+        // Closing the job takes down any workers the backend left behind
+        state.job.lock().unwrap().take();
+        *state.endpoint.lock().unwrap() = None;
+        log::warn!("Backend exited unexpectedly ({})", exit_status);
+        crash_report::capture(&app_handle, &exit_status);
+
+        // A stop already in progress owns the lifecycle; don't restart behind its back.
+        if state.transition(Lifecycle::Running, Lifecycle::Stopped).is_err() {
+            return;
+        }
+
+        let restarting = settings::backend(&app_handle).auto_restart;
+        let crashed = BackendCrashed {
+            exit_code: exit_status.code(),
+            restarting,
+        };
+        if let Err(e) = app_handle.emit("backend-crashed", crashed) {
+            log::warn!("Failed to emit backend-crashed: {}", e);
+        }
+        crate::notifications::notify(
+            &app_handle,
+            NotificationCategory::BackendCrashed,
+            "Backend stopped unexpectedly",
+            if restarting {
+                "Restarting it now"
+            } else {
+                "Restart it from the app to keep analyzing"
+            },
+        );
+        if !restarting {
+            return;
+        }
+
+        loop {
+            let delay = state.restart_breaker.lock().unwrap().backoff();
+            // Stop waiting if the app is quitting or someone started the backend meanwhile
+            if !sleep_unless_shutdown(&state, delay) || state.lifecycle() != Lifecycle::Stopped {
+                return;
+            }
+            if !state.restart_breaker.lock().unwrap().allow_restart() {
+                log::error!("Backend restarted too often; giving up on automatic restarts");
+                if let Err(e) = app_handle.emit("backend-restart-throttled", MAX_RESTARTS) {
+                    log::warn!("Failed to emit backend-restart-throttled: {}", e);
+                }
+                return;
+            }
+
+            match crate::start_backend_blocking(app_handle.clone()) {
+                Ok(_) => return,
+                Err(e) => log::error!("Failed to restart backend: {}", e),
+            }
+        }
+    });
+}
+
+/// Kills a backend that is still running but not answering and starts a fresh one.
+/// Returns false if there was no live process to restart.
+pub fn restart_unresponsive(app_handle: &tauri::AppHandle, failures: u32) -> Result<bool, String> {
+    let state = app_handle.state::<PythonBackend>();
+    if state.shutdown.is_cancelled() {
+        return Ok(false);
+    }
+
+    let alive = matches!(
+        state.process.lock().unwrap().as_mut().map(|child| child.try_wait()),
+        Some(Ok(None))
+    );
+    if !alive {
+        return Ok(false);
+    }
+
+    state.transition(Lifecycle::Running, Lifecycle::Stopping)?;
+    log::warn!("Backend failed {} consecutive health checks; restarting it", failures);
+
+    // Taking the child out of state also stops its supervisor thread.
+    let child = state.process.lock().unwrap().take();
+    if let Some(mut child) = child {
+        process::kill_tree(&mut child);
+    }
+    state.job.lock().unwrap().take();
+    *state.endpoint.lock().unwrap() = None;
+    state.transition(Lifecycle::Stopping, Lifecycle::Stopped)?;
+
+    if let Err(e) = app_handle.emit("backend-watchdog-restart", failures) {
+        log::warn!("Failed to emit backend-watchdog-restart: {}", e);
+    }
+
+    if !state.restart_breaker.lock().unwrap().allow_restart() {
+        if let Err(e) = app_handle.emit("backend-restart-throttled", MAX_RESTARTS) {
+            log::warn!("Failed to emit backend-restart-throttled: {}", e);
+        }
+        return Err("Backend restarted too often; giving up on automatic restarts".to_string());
+    }
+
+    crate::start_backend_blocking(app_handle.clone())?;
+    Ok(true)
+}
+
+#[tauri::command]
+pub fn set_backend_watchdog(
+    app_handle: tauri::AppHandle,
+    enabled: bool,
+    failure_threshold: u32,
+) -> Result<(), String> {
+    if failure_threshold == 0 {
+        return Err("Failure threshold must be at least 1".to_string());
+    }
+
+    let store = app_handle.state::<SettingsStore>();
+    let mut current = store.settings.lock().unwrap();
+    current.backend.watchdog = settings::WatchdogSettings {
+        enabled,
+        failure_threshold,
+    };
+    settings::save(&app_handle, &current)
+}
+
+#[tauri::command]
+pub fn set_backend_auto_restart(app_handle: tauri::AppHandle, enabled: bool) -> Result<(), String> {
+    let store = app_handle.state::<SettingsStore>();
+    let mut current = store.settings.lock().unwrap();
+    current.backend.auto_restart = enabled;
+    settings::save(&app_handle, &current)
+}
+
+#[tauri::command]
+pub fn reset_backend_circuit_breaker(app_handle: tauri::AppHandle) {
+    let state = app_handle.state::<PythonBackend>();
+    state.restart_breaker.lock().unwrap().reset();
+}
+
+const FORCE_RESTART_REASON: &str = "Aborted: the backend was force-restarted";
+
+/// Kills the backend's whole process tree without waiting for it, e.g. when an
+/// analysis has wedged it, and starts a fresh one on the same port. Running jobs
+/// are failed, queued ones start once it is back. Emits `backend-force-restarting`
+/// before the kill, then `backend-recovered` or `backend-recovery-failed`.
+#[tauri::command]
+pub async fn force_restart_backend(app_handle: tauri::AppHandle) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || force_restart(&app_handle))
+        .await
+        .map_err(|e| format!("Failed to restart backend: {}", e))?
+}
+
+pub fn force_restart(app_handle: &tauri::AppHandle) -> Result<String, String> {
+    let state = app_handle.state::<PythonBackend>();
+    if state.lifecycle() == Lifecycle::Running && !state.owned.load(std::sync::atomic::Ordering::SeqCst) {
+        return Err("The backend was not started by this app, so it cannot be restarted from here".to_string());
+    }
+    state.transition(Lifecycle::Running, Lifecycle::Stopping)?;
+
+    let aborted: Vec<String> = crate::jobs::abort_running(app_handle, FORCE_RESTART_REASON)
+        .into_iter()
+        .map(|job| job.id)
+        .collect();
+    if let Err(e) = app_handle.emit("backend-force-restarting", &aborted) {
+        log::warn!("Failed to emit backend-force-restarting: {}", e);
+    }
+    log::warn!("Force-restarting the backend; aborting {} running job(s)", aborted.len());
+
+    // Taking the child out of state also stops its supervisor thread.
+    let child = state.process.lock().unwrap().take();
+    if let Some(mut child) = child {
+        process::kill_tree(&mut child);
+    }
+    state.job.lock().unwrap().take();
+    state.shutdown_token.lock().unwrap().take();
+    state.suspended.store(false, std::sync::atomic::Ordering::SeqCst);
+    *state.resume_port.lock().unwrap() = state.endpoint.lock().unwrap().take().and_then(|e| e.port());
+    state.transition(Lifecycle::Stopping, Lifecycle::Stopped)?;
+
+    // Asked for explicitly, so earlier crashes shouldn't hold it back
+    state.restart_breaker.lock().unwrap().reset();
+    match crate::start_backend_blocking(app_handle.clone()) {
+        Ok(message) => {
+            let recovered = BackendRecovered {
+                aborted_jobs: aborted,
+                port: state.endpoint.lock().unwrap().as_ref().and_then(|e| e.port()),
+            };
+            if let Err(e) = app_handle.emit("backend-recovered", recovered) {
+                log::warn!("Failed to emit backend-recovered: {}", e);
+            }
+            Ok(message)
+        }
+        Err(e) => {
+            state.resume_port.lock().unwrap().take();
+            if let Err(emit_error) = app_handle.emit("backend-recovery-failed", &e) {
+                log::warn!("Failed to emit backend-recovery-failed: {}", emit_error);
+            }
+            Err(e.into())
+        }
+    }
+}
@@ -0,0 +1,224 @@
+//! A ZIP of everything needed to troubleshoot the app itself, for attaching to
+//! bug reports: app logs and crash reports, captured backend output, OS and
+//! version details, the last analysis's metadata, settings and the Tauri config.
+//!
+//! Anything under a credential-like key (see `settings::is_secret_header`) is
+//! replaced with `[redacted]` before it is written.
+
+use serde_json::{json, Value};
+use std::fs::{self, File};
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::output::Stream;
+use crate::settings::SettingsStore;
+
+const REDACTED: &str = "[redacted]";
+/// Only the end of a longer log is included.
+const MAX_LOG_BYTES: u64 = 20 * 1024 * 1024;
+/// How deep into the log dir to collect files (crash reports sit one level down).
+const MAX_LOG_DEPTH: usize = 2;
+
+fn redact(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            for (key, field) in fields.iter_mut() {
+                if crate::settings::is_secret_header(key) && !field.is_null() {
+                    *field = Value::String(REDACTED.to_string());
+                } else {
+                    redact(field);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+fn redacted(value: impl serde::Serialize) -> Value {
+    let mut value = serde_json::to_value(value).unwrap_or(Value::Null);
+    redact(&mut value);
+    value
+}
+
+#[cfg(target_os = "linux")]
+fn os_version() -> Option<String> {
+    let release = fs::read_to_string("/etc/os-release").ok()?;
+    release
+        .lines()
+        .find_map(|line| line.strip_prefix("PRETTY_NAME="))
+        .map(|name| name.trim_matches('"').to_string())
+}
+
+#[cfg(target_os = "macos")]
+fn os_version() -> Option<String> {
+    let output = std::process::Command::new("sw_vers").arg("-productVersion").output().ok()?;
+    Some(format!("macOS {}", String::from_utf8_lossy(&output.stdout).trim()))
+}
+
+#[cfg(windows)]
+fn os_version() -> Option<String> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let output = std::process::Command::new("cmd")
+        .args(["/C", "ver"])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .ok()?;
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn os_version() -> Option<String> {
+    None
+}
+
+fn system_info(app_handle: &tauri::AppHandle) -> Value {
+    let package = app_handle.package_info();
+    json!({
+        "generated_at_ms": SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64),
+        "app_name": package.name,
+        "app_version": package.version.to_string(),
+        "tauri_version": tauri::VERSION,
+        "os": std::env::consts::OS,
+        "os_family": std::env::consts::FAMILY,
+        "os_version": os_version(),
+        "arch": std::env::consts::ARCH,
+        "backend_status": crate::get_backend_status(app_handle.clone()).unwrap_or_else(|e| e),
+    })
+}
+
+/// The last analysis recorded in history and the last one saved in full; the
+/// results themselves are left out.
+fn last_analysis(app_handle: &tauri::AppHandle) -> Value {
+    let recorded = crate::history::get_analysis_history(app_handle.clone(), Some(1))
+        .ok()
+        .and_then(|runs| runs.into_iter().next());
+    let saved = crate::history::list_analysis_runs(app_handle.clone())
+        .ok()
+        .and_then(|runs| runs.into_iter().next());
+    redacted(json!({ "recorded": recorded, "saved": saved }))
+}
+
+fn backend_output(app_handle: &tauri::AppHandle) -> String {
+    crate::output::get_backend_log_buffer(app_handle.clone())
+        .iter()
+        .map(|line| {
+            let stream = match line.stream {
+                Stream::Stdout => "stdout",
+                Stream::Stderr => "stderr",
+            };
+            format!("[{}] {}\n", stream, line.line)
+        })
+        .collect()
+}
+
+/// Files under `dir`, at most `MAX_LOG_DEPTH` levels down, with their paths
+/// relative to it.
+fn log_files(dir: &Path) -> Vec<(PathBuf, String)> {
+    let mut files = Vec::new();
+    let mut pending = vec![(dir.to_path_buf(), 0)];
+    while let Some((current, depth)) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&current) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() && depth + 1 < MAX_LOG_DEPTH {
+                pending.push((path, depth + 1));
+            } else if file_type.is_file() {
+                if let Ok(relative) = path.strip_prefix(dir) {
+                    let parts: Vec<_> = relative.components().map(|c| c.as_os_str().to_string_lossy()).collect();
+                    let name = parts.join("/");
+                    files.push((path, name));
+                }
+            }
+        }
+    }
+    files.sort_by(|a, b| a.1.cmp(&b.1));
+    files
+}
+
+/// The last `MAX_LOG_BYTES` of a file.
+fn read_tail(path: &Path) -> std::io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    if len > MAX_LOG_BYTES {
+        file.seek(SeekFrom::Start(len - MAX_LOG_BYTES))?;
+    }
+    let mut content = Vec::new();
+    file.read_to_end(&mut content)?;
+    Ok(content)
+}
+
+fn collect(app_handle: &tauri::AppHandle) -> Vec<(String, Vec<u8>)> {
+    let json = |value: &Value| serde_json::to_vec_pretty(value).unwrap_or_default();
+    let settings = app_handle.state::<SettingsStore>().settings.lock().unwrap().clone();
+
+    let mut entries = vec![
+        ("system.json".to_string(), json(&system_info(app_handle))),
+        ("versions.json".to_string(), json(&json!(crate::compat::version_matrix(app_handle)))),
+        ("last-analysis.json".to_string(), json(&last_analysis(app_handle))),
+        ("settings.json".to_string(), json(&redacted(&settings))),
+        ("tauri.conf.json".to_string(), json(&redacted(app_handle.config()))),
+        ("backend/output.log".to_string(), backend_output(app_handle).into_bytes()),
+    ];
+
+    if let Some(path) = crate::output::log_file_path(app_handle) {
+        match read_tail(&path) {
+            Ok(content) => entries.push(("backend/log-file.log".to_string(), content)),
+            Err(e) => log::warn!("Failed to read backend log file {}: {}", path.display(), e),
+        }
+    }
+
+    if let Ok(dir) = app_handle.path().app_log_dir() {
+        for (path, name) in log_files(&dir) {
+            match read_tail(&path) {
+                Ok(content) => entries.push((format!("logs/{}", name), content)),
+                Err(e) => log::warn!("Failed to read log {}: {}", path.display(), e),
+            }
+        }
+    }
+    entries
+}
+
+fn write_bundle(path: &Path, entries: &[(String, Vec<u8>)]) -> Result<(), String> {
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .large_file(true);
+
+    crate::files::write_atomic(path, |file| {
+        let mut zip = ZipWriter::new(BufWriter::new(file));
+        for (name, content) in entries {
+            zip.start_file(name.as_str(), options)
+                .and_then(|_| zip.write_all(content).map_err(Into::into))
+                .map_err(|e| format!("Failed to add {} to support bundle: {}", name, e))?;
+        }
+        zip.finish()
+            .and_then(|mut writer| writer.flush().map_err(Into::into))
+            .map_err(|e| format!("Failed to finish support bundle: {}", e))
+    })
+}
+
+/// Collects a support bundle and saves it where the user picks.
+#[tauri::command]
+pub async fn generate_support_bundle(app_handle: tauri::AppHandle) -> Result<String, String> {
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let filename = format!("cribl-hc-support-{}.zip", stamp);
+    let path = crate::dialogs::save_path_with_extension(&app_handle, &filename, Some("zip")).await?;
+
+    let target = path.clone();
+    tauri::async_runtime::spawn_blocking(move || write_bundle(&target, &collect(&app_handle)))
+        .await
+        .map_err(|e| format!("Failed to save support bundle: {}", e))??;
+
+    Ok(path.to_string_lossy().to_string())
+}
@@ -0,0 +1,354 @@
+//! Crash capture for the app itself, and opt-in crash reporting.
+//!
+//! Rust panics and backend startup failures are always written to the crash
+//! report dir in the log dir, next to the backend crash reports from
+//! `crash_report`, as `app-crash-<id>.json`. Nothing leaves the machine unless
+//! `telemetry.enabled` is turned on in settings. Then pending reports are sent
+//! at startup and by `submit_crash_report`. What is sent is metadata only:
+//! versions, platform, function names from the backtrace, and the message with
+//! paths, URLs, email addresses and the user name taken out.
+//! `get_pending_crash_reports` shows exactly that before anything is sent.
+//! Sent reports are kept locally as `app-crash-<id>.sent.json`.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+use crate::settings::SettingsStore;
+use crate::startup::StartupError;
+
+const ENDPOINT: Option<&str> = option_env!("CRIBL_HC_TELEMETRY_URL");
+const PREFIX: &str = "app-crash-";
+const SENT_SUFFIX: &str = ".sent.json";
+/// Older reports are deleted once there are more than this many, sent or not.
+const MAX_REPORTS: usize = 20;
+/// Backtrace frames sent with a report, counting from the panic.
+const MAX_FRAMES: usize = 50;
+/// Characters of the message sent with a report.
+const MAX_MESSAGE_CHARS: usize = 500;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TelemetrySettings {
+    /// Send crash reports; off unless the user turns it on.
+    pub enabled: bool,
+    /// Replaces the crash report URL this build was made with.
+    pub endpoint: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CrashKind {
+    Panic,
+    BackendStartup,
+}
+
+/// A crash as written to disk, with everything known about it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct AppCrash {
+    kind: CrashKind,
+    /// Milliseconds since the Unix epoch.
+    timestamp_ms: u64,
+    app_version: String,
+    message: String,
+    /// Where the panic was raised, as `file:line:column`.
+    location: Option<String>,
+    thread: Option<String>,
+    backtrace: Option<String>,
+    /// The `StartupErrorKind` of a backend startup failure.
+    startup_kind: Option<String>,
+}
+
+/// What a crash report sends: nothing that identifies the user or their deployments.
+#[derive(Clone, Debug, Serialize)]
+pub struct CrashMetadata {
+    pub kind: CrashKind,
+    pub timestamp_ms: u64,
+    pub app_version: String,
+    pub os: &'static str,
+    pub arch: &'static str,
+    pub message: String,
+    pub location: Option<String>,
+    /// Function names only, innermost first.
+    pub frames: Vec<String>,
+    pub startup_kind: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct PendingCrashReport {
+    pub id: String,
+    pub kind: CrashKind,
+    pub timestamp_ms: u64,
+    /// As it happened; this stays on the machine.
+    pub message: String,
+    /// What `submit_crash_report` would send.
+    pub upload: CrashMetadata,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+fn settings(app_handle: &tauri::AppHandle) -> TelemetrySettings {
+    app_handle.state::<SettingsStore>().settings.lock().unwrap().telemetry.clone()
+}
+
+fn endpoint(app_handle: &tauri::AppHandle) -> Result<String, String> {
+    settings(app_handle)
+        .endpoint
+        .or_else(|| ENDPOINT.map(str::to_string))
+        .filter(|url| !url.trim().is_empty())
+        .ok_or_else(|| "This build has no crash report URL".to_string())
+}
+
+fn report_path(dir: &Path, id: &str) -> PathBuf {
+    dir.join(format!("{}{}.json", PREFIX, id))
+}
+
+/// Ids of the reports in `dir`, oldest first, with whether each was sent.
+fn report_ids(dir: &Path) -> Vec<(String, bool)> {
+    let mut ids: Vec<(String, bool)> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .filter_map(|entry| {
+                    let name = entry.file_name().to_str()?.strip_prefix(PREFIX)?.to_string();
+                    match name.strip_suffix(SENT_SUFFIX) {
+                        Some(id) => Some((id.to_string(), true)),
+                        None => name.strip_suffix(".json").map(|id| (id.to_string(), false)),
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    // Ids start with the timestamp, zero-padded, so they sort by age
+    ids.sort();
+    ids
+}
+
+fn write(dir: &Path, crash: &AppCrash) -> Result<PathBuf, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create crash report dir: {}", e))?;
+    let mut suffix = [0u8; 3];
+    getrandom::getrandom(&mut suffix).map_err(|e| format!("Failed to generate random bytes: {}", e))?;
+    let suffix: String = suffix.iter().map(|b| format!("{:02x}", b)).collect();
+    let path = report_path(dir, &format!("{:013}-{}", crash.timestamp_ms, suffix));
+    let json = serde_json::to_vec_pretty(crash).map_err(|e| format!("Failed to serialize crash report: {}", e))?;
+    crate::files::write_file_atomic(&path, &json)?;
+
+    let ids = report_ids(dir);
+    for (id, sent) in &ids[..ids.len().saturating_sub(MAX_REPORTS)] {
+        let old = if *sent { dir.join(format!("{}{}{}", PREFIX, id, SENT_SUFFIX)) } else { report_path(dir, id) };
+        if let Err(e) = fs::remove_file(&old) {
+            log::warn!("Failed to remove old crash report {}: {}", old.display(), e);
+        }
+    }
+    Ok(path)
+}
+
+fn read(dir: &Path, id: &str) -> Result<AppCrash, String> {
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(format!("Invalid crash report id: {}", id));
+    }
+    let path = report_path(dir, id);
+    let bytes = fs::read(&path).map_err(|e| format!("Failed to read crash report {}: {}", id, e))?;
+    serde_json::from_slice(&bytes).map_err(|e| format!("Crash report {} is corrupt: {}", id, e))
+}
+
+/// `word` with anything identifying replaced: URLs (which name leaders),
+/// email addresses, and paths, of which only the file name is kept.
+fn anonymize_word(word: &str, user: Option<&str>) -> String {
+    let is_edge = |c: char| matches!(c, '"' | '\'' | '`' | '(' | ')' | '[' | ']' | '{' | '}' | '<' | '>' | ',' | ';');
+    let start = word.len() - word.trim_start_matches(is_edge).len();
+    let core = word.trim_matches(is_edge);
+    let (before, after) = (&word[..start], &word[start + core.len()..]);
+
+    let replaced = if core.contains("://") {
+        "<url>".to_string()
+    } else if core.contains('@') && core.contains('.') {
+        "<email>".to_string()
+    } else if core.contains('/') || core.contains('\\') {
+        let file = core.rsplit(['/', '\\']).next().unwrap_or_default();
+        format!("<path>/{}", file)
+    } else {
+        core.to_string()
+    };
+    let replaced = match user {
+        Some(user) => replaced.replace(user, "<user>"),
+        None => replaced,
+    };
+    format!("{}{}{}", before, replaced, after)
+}
+
+fn anonymize(text: &str) -> String {
+    let user = ["USER", "USERNAME"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok())
+        .filter(|user| user.chars().count() > 2);
+    let anonymized: Vec<String> = text
+        .split_whitespace()
+        .map(|word| anonymize_word(word, user.as_deref()))
+        .collect();
+    anonymized.join(" ").chars().take(MAX_MESSAGE_CHARS).collect()
+}
+
+/// Function names from a `std::backtrace::Backtrace` as printed, whose frames
+/// read `  3: crate::module::function` followed by an `at file:line` line.
+fn frames(backtrace: &str) -> Vec<String> {
+    backtrace
+        .lines()
+        .filter_map(|line| {
+            let (index, name) = line.trim().split_once(": ")?;
+            index.parse::<usize>().ok()?;
+            Some(name.trim().to_string())
+        })
+        .take(MAX_FRAMES)
+        .collect()
+}
+
+fn metadata(crash: &AppCrash) -> CrashMetadata {
+    CrashMetadata {
+        kind: crash.kind,
+        timestamp_ms: crash.timestamp_ms,
+        app_version: crash.app_version.clone(),
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        message: anonymize(&crash.message),
+        location: crash.location.as_deref().map(anonymize),
+        frames: crash.backtrace.as_deref().map(frames).unwrap_or_default(),
+        startup_kind: crash.startup_kind.clone(),
+    }
+}
+
+/// Writes a report for each panic before the default hook prints it. The hook
+/// only touches the filesystem, so it works however broken the app state is.
+fn install_panic_hook(dir: PathBuf, app_version: String) {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let payload = info.payload();
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "panic with a non-string payload".to_string());
+        let crash = AppCrash {
+            kind: CrashKind::Panic,
+            timestamp_ms: now_ms(),
+            app_version: app_version.clone(),
+            message,
+            location: info.location().map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())),
+            thread: std::thread::current().name().map(str::to_string),
+            backtrace: Some(std::backtrace::Backtrace::force_capture().to_string()),
+            startup_kind: None,
+        };
+        if let Err(e) = write(&dir, &crash) {
+            eprintln!("Failed to write crash report: {}", e);
+        }
+        previous(info);
+    }));
+}
+
+/// Records a backend that failed to start.
+pub fn record_startup_failure(app_handle: &tauri::AppHandle, error: &StartupError) {
+    let crash = AppCrash {
+        kind: CrashKind::BackendStartup,
+        timestamp_ms: now_ms(),
+        app_version: app_handle.package_info().version.to_string(),
+        message: error.to_string(),
+        location: None,
+        thread: None,
+        backtrace: None,
+        startup_kind: Some(format!("{:?}", error.kind)),
+    };
+    let result = crate::crash_report::crash_report_dir(app_handle).and_then(|dir| write(&dir, &crash));
+    match result {
+        Ok(path) => log::info!("Wrote startup failure report to {}", path.display()),
+        Err(e) => log::warn!("Failed to write startup failure report: {}", e),
+    }
+}
+
+async fn upload(app_handle: &tauri::AppHandle, metadata: &CrashMetadata) -> Result<(), String> {
+    let url = endpoint(app_handle)?;
+    let handle = app_handle.clone();
+    let client = tauri::async_runtime::spawn_blocking(move || crate::network::client_builder(&handle))
+        .await
+        .map_err(|e| format!("Failed to configure the network: {}", e))??
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let response = client
+        .post(&url)
+        .timeout(REQUEST_TIMEOUT)
+        .json(metadata)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send crash report: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Crash report server responded {}", response.status()));
+    }
+    Ok(())
+}
+
+async fn submit(app_handle: &tauri::AppHandle, id: &str) -> Result<(), String> {
+    if !settings(app_handle).enabled {
+        return Err("Crash reporting is off; turn it on in settings to send reports".to_string());
+    }
+    let dir = crate::crash_report::crash_report_dir(app_handle)?;
+    let crash = read(&dir, id)?;
+    upload(app_handle, &metadata(&crash)).await?;
+    let sent = dir.join(format!("{}{}{}", PREFIX, id, SENT_SUFFIX));
+    fs::rename(report_path(&dir, id), sent).map_err(|e| format!("Failed to mark crash report {} sent: {}", id, e))
+}
+
+/// Installs the panic hook and, with crash reporting on, sends any reports
+/// still pending; called once from setup.
+pub fn init(app_handle: &tauri::AppHandle) {
+    match crate::crash_report::crash_report_dir(app_handle) {
+        Ok(dir) => install_panic_hook(dir, app_handle.package_info().version.to_string()),
+        Err(e) => log::warn!("Crash reports won't be written: {}", e),
+    }
+    if !settings(app_handle).enabled {
+        return;
+    }
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let Ok(dir) = crate::crash_report::crash_report_dir(&handle) else {
+            return;
+        };
+        for (id, _) in report_ids(&dir).into_iter().filter(|(_, sent)| !sent) {
+            if let Err(e) = submit(&handle, &id).await {
+                log::warn!("Failed to send crash report {}: {}", id, e);
+                break;
+            }
+        }
+    });
+}
+
+/// Crash reports not sent yet, newest first, each with exactly what sending it would upload.
+#[tauri::command]
+pub fn get_pending_crash_reports(app_handle: tauri::AppHandle) -> Result<Vec<PendingCrashReport>, String> {
+    let dir = crate::crash_report::crash_report_dir(&app_handle)?;
+    let mut pending = Vec::new();
+    for (id, _) in report_ids(&dir).into_iter().rev().filter(|(_, sent)| !sent) {
+        match read(&dir, &id) {
+            Ok(crash) => pending.push(PendingCrashReport {
+                upload: metadata(&crash),
+                kind: crash.kind,
+                timestamp_ms: crash.timestamp_ms,
+                message: crash.message,
+                id,
+            }),
+            Err(e) => log::warn!("{}", e),
+        }
+    }
+    Ok(pending)
+}
+
+/// Sends crash report `id`'s metadata. Fails unless crash reporting is turned on.
+#[tauri::command]
+pub async fn submit_crash_report(app_handle: tauri::AppHandle, id: String) -> Result<(), String> {
+    submit(&app_handle, &id).await
+}
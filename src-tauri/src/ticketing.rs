@@ -0,0 +1,406 @@
+//! Jira or ServiceNow tickets for findings.
+//!
+//! One endpoint is configured at a time. Its API token (Jira) or password
+//! (ServiceNow) is kept in the OS credential store. Every ticket links back to
+//! its finding with a `cribl-hc://finding/...` link, so it can be opened in the
+//! app from the ticket.
+//!
+//! Tickets are remembered by finding id in `tickets.json` in the app data dir.
+//! Finding ids stay the same between runs, so later runs still show a finding's
+//! ticket, and asking again for a finding that has one returns it instead of
+//! opening another.
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::Manager;
+
+use crate::settings::{self, SettingsStore};
+
+const TOKEN_SECRET: &str = "ticketing-token";
+const TICKETS_FILE: &str = "tickets.json";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// Jira rejects longer summaries.
+const MAX_SUMMARY_CHARS: usize = 250;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TicketingProvider {
+    Jira,
+    ServiceNow,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TicketingSettings {
+    pub provider: TicketingProvider,
+    /// e.g. `https://example.atlassian.net` or `https://example.service-now.com`.
+    pub url: String,
+    /// Jira account email or ServiceNow user. Jira without one uses the token as
+    /// a personal access token.
+    #[serde(default)]
+    pub username: Option<String>,
+    /// Jira project key, e.g. `OPS`.
+    #[serde(default)]
+    pub project_key: Option<String>,
+    /// Jira issue type; `Task` when unset.
+    #[serde(default)]
+    pub issue_type: Option<String>,
+    /// ServiceNow table; `incident` when unset.
+    #[serde(default)]
+    pub table: Option<String>,
+    /// Whether a token is saved in the OS credential store; set by `configure_ticketing`.
+    #[serde(default)]
+    pub has_token: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Ticket {
+    /// `OPS-123` or `INC0010001`.
+    pub key: String,
+    pub url: String,
+    pub finding_id: String,
+    /// Milliseconds since the Unix epoch.
+    pub created_at_ms: u64,
+}
+
+#[derive(Serialize)]
+pub struct TicketResult {
+    finding_id: String,
+    ticket: Option<Ticket>,
+    /// False when the finding already had this ticket.
+    created: bool,
+    error: Option<String>,
+}
+
+fn config(app_handle: &tauri::AppHandle) -> Option<TicketingSettings> {
+    app_handle.state::<SettingsStore>().settings.lock().unwrap().ticketing.clone()
+}
+
+fn tickets_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get data dir: {}", e))?
+        .join(TICKETS_FILE))
+}
+
+fn read_tickets(app_handle: &tauri::AppHandle) -> Result<BTreeMap<String, Ticket>, String> {
+    let path = tickets_path(app_handle)?;
+    match fs::read(&path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| format!("{} is corrupt: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+fn write_tickets(app_handle: &tauri::AppHandle, tickets: &BTreeMap<String, Ticket>) -> Result<(), String> {
+    let path = tickets_path(app_handle)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create data dir: {}", e))?;
+    }
+    let json = serde_json::to_vec_pretty(tickets).map_err(|e| format!("Failed to serialize tickets: {}", e))?;
+    crate::files::write_file_atomic(&path, &json)
+}
+
+fn validate(config: &mut TicketingSettings) -> Result<(), String> {
+    let url = url::Url::parse(config.url.trim()).map_err(|e| format!("Invalid URL {:?}: {}", config.url, e))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("Unsupported URL scheme {}", url.scheme()));
+    }
+    config.url = url.as_str().trim_end_matches('/').to_string();
+
+    let trim = |value: &mut Option<String>| *value = value.take().map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+    trim(&mut config.username);
+    trim(&mut config.project_key);
+    trim(&mut config.issue_type);
+    trim(&mut config.table);
+
+    match config.provider {
+        TicketingProvider::Jira if config.project_key.is_none() => Err("Jira needs a project key".to_string()),
+        TicketingProvider::ServiceNow if config.username.is_none() => Err("ServiceNow needs a user name".to_string()),
+        TicketingProvider::ServiceNow
+            if config
+                .table
+                .as_deref()
+                .is_some_and(|t| !t.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')) =>
+        {
+            Err(format!("Invalid ServiceNow table {:?}", config.table.as_deref().unwrap_or_default()))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn text<'a>(finding: &'a Value, key: &str) -> &'a str {
+    finding.get(key).and_then(Value::as_str).unwrap_or_default()
+}
+
+fn list(finding: &Value, key: &str) -> Vec<String> {
+    finding
+        .get(key)
+        .and_then(Value::as_array)
+        .map(|items| items.iter().filter_map(Value::as_str).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+fn summary(finding: &Value) -> String {
+    let summary = format!("[{}] {}", text(finding, "severity"), text(finding, "title"));
+    summary.chars().take(MAX_SUMMARY_CHARS).collect()
+}
+
+/// The ticket body as plain text, which both Jira's v2 API and ServiceNow accept.
+fn description(finding: &Value, deployment: &str, group: &str, link: &str) -> String {
+    let mut body = format!(
+        "{}\n\nDeployment: {}\nWorker group / fleet: {}\nSeverity: {}\nCategory: {}\n",
+        text(finding, "description"),
+        deployment,
+        group,
+        text(finding, "severity"),
+        text(finding, "category")
+    );
+    let components = list(finding, "affected_components");
+    if !components.is_empty() {
+        body.push_str(&format!("Affected components: {}\n", components.join(", ")));
+    }
+    let impact = text(finding, "estimated_impact");
+    if !impact.is_empty() {
+        body.push_str(&format!("\nImpact: {}\n", impact));
+    }
+    let steps = list(finding, "remediation_steps");
+    if !steps.is_empty() {
+        body.push_str("\nRemediation:\n");
+        for (i, step) in steps.iter().enumerate() {
+            body.push_str(&format!("{}. {}\n", i + 1, step));
+        }
+    }
+    let docs = list(finding, "documentation_links");
+    if !docs.is_empty() {
+        body.push_str("\nDocumentation:\n");
+        for doc in docs {
+            body.push_str(&format!("{}\n", doc));
+        }
+    }
+    body.push_str(&format!("\nOpen in Cribl Health Check: {}\n", link));
+    body
+}
+
+fn basic_auth(username: &str, token: &str) -> String {
+    format!(
+        "Basic {}",
+        base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", username, token))
+    )
+}
+
+async fn open_ticket(
+    client: &reqwest::Client,
+    config: &TicketingSettings,
+    token: &str,
+    finding: &Value,
+    body: String,
+) -> Result<(String, String), String> {
+    let (endpoint, auth, payload) = match config.provider {
+        TicketingProvider::Jira => {
+            let mut labels = vec!["cribl-hc".to_string()];
+            labels.extend(Some(text(finding, "severity").to_string()).filter(|s| !s.is_empty()));
+            (
+                format!("{}/rest/api/2/issue", config.url),
+                match &config.username {
+                    Some(username) => basic_auth(username, token),
+                    None => format!("Bearer {}", token),
+                },
+                json!({ "fields": {
+                    "project": { "key": config.project_key },
+                    "issuetype": { "name": config.issue_type.as_deref().unwrap_or("Task") },
+                    "summary": summary(finding),
+                    "description": body,
+                    "labels": labels,
+                } }),
+            )
+        }
+        TicketingProvider::ServiceNow => {
+            // ServiceNow's 1 is the highest urgency and impact
+            let level = match text(finding, "severity") {
+                "critical" => 1,
+                "high" | "medium" => 2,
+                _ => 3,
+            };
+            (
+                format!("{}/api/now/table/{}", config.url, config.table.as_deref().unwrap_or("incident")),
+                basic_auth(config.username.as_deref().unwrap_or_default(), token),
+                json!({
+                    "short_description": summary(finding),
+                    "description": body,
+                    "urgency": level,
+                    "impact": level,
+                    "category": "software",
+                }),
+            )
+        }
+    };
+
+    let response = client
+        .post(&endpoint)
+        .timeout(REQUEST_TIMEOUT)
+        .header(reqwest::header::AUTHORIZATION, auth)
+        .header(reqwest::header::ACCEPT, "application/json")
+        .json(&payload)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach {}: {}", config.url, e))?;
+    let status = response.status();
+    let reply: Value = response.json().await.unwrap_or(Value::Null);
+    if !status.is_success() {
+        let detail = match config.provider {
+            TicketingProvider::Jira => reply.get("errors").map(Value::to_string).or_else(|| {
+                reply.get("errorMessages").map(Value::to_string)
+            }),
+            TicketingProvider::ServiceNow => reply.pointer("/error/message").and_then(Value::as_str).map(str::to_string),
+        };
+        return Err(format!("Ticket creation failed ({}): {}", status, detail.unwrap_or_default()));
+    }
+
+    match config.provider {
+        TicketingProvider::Jira => {
+            let key = reply.get("key").and_then(Value::as_str).ok_or("Jira did not return an issue key")?;
+            Ok((key.to_string(), format!("{}/browse/{}", config.url, key)))
+        }
+        TicketingProvider::ServiceNow => {
+            let result = reply.get("result").ok_or("ServiceNow did not return the record")?;
+            let number = result.get("number").and_then(Value::as_str).ok_or("ServiceNow did not return a number")?;
+            let sys_id = result.get("sys_id").and_then(Value::as_str).unwrap_or_default();
+            let table = config.table.as_deref().unwrap_or("incident");
+            Ok((number.to_string(), format!("{}/nav_to.do?uri={}.do%3Fsys_id%3D{}", config.url, table, sys_id)))
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_ticketing_config(app_handle: tauri::AppHandle) -> Option<TicketingSettings> {
+    config(&app_handle)
+}
+
+/// Sets the ticketing endpoint; `None` removes it along with its token. `token`
+/// replaces the saved one, and is kept otherwise.
+#[tauri::command]
+pub async fn configure_ticketing(
+    app_handle: tauri::AppHandle,
+    config: Option<TicketingSettings>,
+    token: Option<String>,
+) -> Result<Option<TicketingSettings>, String> {
+    let config = match config {
+        Some(mut config) => {
+            validate(&mut config)?;
+            let had_token = self::config(&app_handle).is_some_and(|c| c.has_token);
+            config.has_token = match token.filter(|t| !t.is_empty()) {
+                Some(token) => {
+                    tauri::async_runtime::spawn_blocking(move || crate::credentials::store_app_secret(TOKEN_SECRET, Some(&token)))
+                        .await
+                        .map_err(|e| format!("Failed to save ticketing token: {}", e))??;
+                    true
+                }
+                None => had_token,
+            };
+            Some(config)
+        }
+        None => {
+            tauri::async_runtime::spawn_blocking(|| crate::credentials::store_app_secret(TOKEN_SECRET, None))
+                .await
+                .map_err(|e| format!("Failed to delete ticketing token: {}", e))??;
+            None
+        }
+    };
+
+    let store = app_handle.state::<SettingsStore>();
+    let mut current = store.settings.lock().unwrap();
+    current.ticketing = config.clone();
+    settings::save(&app_handle, &current)?;
+    Ok(config)
+}
+
+/// Opens a ticket for each of `finding_ids` in saved run `run_id`, in order.
+/// Findings that already have a ticket get it back rather than a new one. One
+/// that fails doesn't stop the rest; its result carries the error.
+#[tauri::command]
+pub async fn create_tickets(
+    app_handle: tauri::AppHandle,
+    run_id: String,
+    finding_ids: Vec<String>,
+) -> Result<Vec<TicketResult>, String> {
+    let config = config(&app_handle).ok_or("Set up Jira or ServiceNow first")?;
+    if !config.has_token {
+        return Err("No ticketing token is saved".to_string());
+    }
+    let token = tauri::async_runtime::spawn_blocking(|| crate::credentials::load_app_secret(TOKEN_SECRET))
+        .await
+        .map_err(|e| format!("Failed to read ticketing token: {}", e))??
+        .ok_or("The ticketing token is missing from the credential store; save it again")?;
+
+    let result = crate::history::get_analysis_run(app_handle.clone(), run_id.clone())?;
+    let deployment = result.get("deployment_name").and_then(Value::as_str).unwrap_or("unknown").to_string();
+    let findings: Vec<Value> = result.get("findings").and_then(Value::as_array).cloned().unwrap_or_default();
+
+    let handle = app_handle.clone();
+    let client = tauri::async_runtime::spawn_blocking(move || crate::network::client_builder(&handle))
+        .await
+        .map_err(|e| format!("Failed to configure the network: {}", e))??
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let mut tickets = read_tickets(&app_handle)?;
+    let mut results = Vec::new();
+    for finding_id in finding_ids {
+        if let Some(ticket) = tickets.get(&finding_id) {
+            results.push(TicketResult { finding_id, ticket: Some(ticket.clone()), created: false, error: None });
+            continue;
+        }
+        let Some(finding) = findings.iter().find(|f| text(f, "id") == finding_id) else {
+            results.push(TicketResult {
+                error: Some(format!("Run {} has no finding {}", run_id, finding_id)),
+                finding_id,
+                ticket: None,
+                created: false,
+            });
+            continue;
+        };
+
+        let group = crate::history::worker_group(finding);
+        let link = crate::deep_link::create_finding_permalink(finding_id.clone(), Some(run_id.clone()), Some(group.clone()))?;
+        let body = description(finding, &deployment, &group, &link);
+        match open_ticket(&client, &config, &token, finding, body).await {
+            Ok((key, url)) => {
+                log::info!("Opened ticket {} for finding {}", key, finding_id);
+                let ticket = Ticket {
+                    key,
+                    url,
+                    finding_id: finding_id.clone(),
+                    created_at_ms: std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .map_or(0, |d| d.as_millis() as u64),
+                };
+                tickets.insert(finding_id.clone(), ticket.clone());
+                // Saved after each one, so a later failure can't lose tickets already opened
+                write_tickets(&app_handle, &tickets)?;
+                results.push(TicketResult { finding_id, ticket: Some(ticket), created: true, error: None });
+            }
+            Err(e) => {
+                log::warn!("Failed to open ticket for finding {}: {}", finding_id, e);
+                results.push(TicketResult { finding_id, ticket: None, created: false, error: Some(e) });
+            }
+        }
+    }
+    Ok(results)
+}
+
+/// Tickets already opened for `finding_ids`, or for every finding when empty.
+#[tauri::command]
+pub fn list_tickets(app_handle: tauri::AppHandle, finding_ids: Vec<String>) -> Result<Vec<Ticket>, String> {
+    let tickets = read_tickets(&app_handle)?;
+    Ok(if finding_ids.is_empty() {
+        tickets.into_values().collect()
+    } else {
+        finding_ids.iter().filter_map(|id| tickets.get(id).cloned()).collect()
+    })
+}
@@ -0,0 +1,214 @@
+//! How the app reaches the backend: a loopback TCP port, or, where policy forbids
+//! listening on localhost, a Unix domain socket (a named pipe on Windows).
+//!
+//! With `BackendTransport::Socket` the backend is started with `--socket <path>`
+//! and announces `SOCKET:<path>` instead of `PORT:<n>`. Everything that talks to
+//! it directly (the gateway, health checks, the progress WebSocket, shutdown)
+//! connects through `connect` or `exchange`, so the webview keeps using the
+//! gateway's URL either way.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackendTransport {
+    #[default]
+    Tcp,
+    Socket,
+}
+
+/// Where the running backend accepts connections.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Endpoint {
+    Tcp(u16),
+    Socket(PathBuf),
+}
+
+impl Endpoint {
+    pub fn port(&self) -> Option<u16> {
+        match self {
+            Endpoint::Tcp(port) => Some(*port),
+            Endpoint::Socket(_) => None,
+        }
+    }
+
+    /// `Host` header value for requests sent to this endpoint.
+    pub fn host(&self) -> String {
+        match self {
+            Endpoint::Tcp(port) => format!("127.0.0.1:{}", port),
+            Endpoint::Socket(_) => "localhost".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Endpoint::Tcp(port) => write!(f, "port {}", port),
+            Endpoint::Socket(path) => write!(f, "socket {}", path.display()),
+        }
+    }
+}
+
+/// Leaves room under the 104-byte `sun_path` limit on macOS.
+#[cfg(unix)]
+const MAX_SOCKET_PATH: usize = 100;
+
+/// A fresh socket path for the next launch, in a directory only this user can read.
+#[cfg(unix)]
+pub fn socket_path(app_handle: &tauri::AppHandle, token: &str) -> Result<PathBuf, String> {
+    use std::os::unix::fs::DirBuilderExt;
+    use tauri::Manager;
+
+    let data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get data dir: {}", e))?;
+    let mut dir = data_dir.join("run");
+    if dir.join("backend.sock").as_os_str().len() > MAX_SOCKET_PATH {
+        dir = std::env::temp_dir().join(format!("cribl-hc-{}", &token[..8]));
+    }
+    std::fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(&dir)
+        .map_err(|e| format!("Failed to create socket directory: {}", e))?;
+
+    let path = dir.join("backend.sock");
+    // A socket left by a backend that crashed would fail the bind
+    let _ = std::fs::remove_file(&path);
+    Ok(path)
+}
+
+/// A pipe name unique to this launch; pipes live in their own namespace, not on disk.
+#[cfg(windows)]
+pub fn socket_path(_app_handle: &tauri::AppHandle, token: &str) -> Result<PathBuf, String> {
+    Ok(PathBuf::from(format!(r"\\.\pipe\cribl-hc-{}", token)))
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn socket_path(_app_handle: &tauri::AppHandle, _token: &str) -> Result<PathBuf, String> {
+    Err("The socket transport is not supported on this platform".to_string())
+}
+
+/// Removes the socket file once the backend is gone; named pipes go away on their own.
+pub fn cleanup(endpoint: &Endpoint) {
+    if let Endpoint::Socket(path) = endpoint {
+        if cfg!(unix) {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+pub trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Stream for T {}
+
+/// Opens a connection to the backend for async callers.
+pub async fn connect(endpoint: &Endpoint) -> std::io::Result<Box<dyn Stream>> {
+    match endpoint {
+        Endpoint::Tcp(port) => Ok(Box::new(tokio::net::TcpStream::connect(("127.0.0.1", *port)).await?)),
+        Endpoint::Socket(path) => connect_socket(path).await,
+    }
+}
+
+#[cfg(unix)]
+async fn connect_socket(path: &Path) -> std::io::Result<Box<dyn Stream>> {
+    Ok(Box::new(tokio::net::UnixStream::connect(path).await?))
+}
+
+/// Every pipe instance can be busy for a moment between the backend accepting
+/// one connection and creating the next instance.
+#[cfg(windows)]
+const PIPE_BUSY_WAIT: Duration = Duration::from_secs(2);
+
+#[cfg(windows)]
+fn pipe_busy(e: &std::io::Error) -> bool {
+    e.raw_os_error() == Some(windows_sys::Win32::Foundation::ERROR_PIPE_BUSY as i32)
+}
+
+#[cfg(windows)]
+async fn connect_socket(path: &Path) -> std::io::Result<Box<dyn Stream>> {
+    let deadline = std::time::Instant::now() + PIPE_BUSY_WAIT;
+    loop {
+        match tokio::net::windows::named_pipe::ClientOptions::new().open(path) {
+            Ok(client) => return Ok(Box::new(client)),
+            Err(e) if pipe_busy(&e) && std::time::Instant::now() < deadline => {
+                tokio::time::sleep(Duration::from_millis(20)).await
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+async fn connect_socket(_path: &Path) -> std::io::Result<Box<dyn Stream>> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "socket transport not supported"))
+}
+
+/// Sends a raw `Connection: close` request and returns up to 64 KiB of the
+/// response, for blocking callers like the startup health check.
+pub fn exchange(endpoint: &Endpoint, request: &str, timeout: Duration) -> Result<String, String> {
+    match endpoint {
+        Endpoint::Tcp(port) => {
+            let addr = std::net::SocketAddr::from(([127, 0, 0, 1], *port));
+            let stream =
+                std::net::TcpStream::connect_timeout(&addr, timeout).map_err(|e| format!("Failed to connect: {}", e))?;
+            let _ = stream.set_read_timeout(Some(timeout));
+            send(stream, request)
+        }
+        Endpoint::Socket(path) => exchange_socket(path, request, timeout),
+    }
+}
+
+fn send(mut stream: impl Read + Write, request: &str) -> Result<String, String> {
+    stream
+        .write_all(request.as_bytes())
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+    let mut response = String::new();
+    let _ = stream.take(64 * 1024).read_to_string(&mut response);
+    Ok(response)
+}
+
+#[cfg(unix)]
+fn exchange_socket(path: &Path, request: &str, timeout: Duration) -> Result<String, String> {
+    let stream = std::os::unix::net::UnixStream::connect(path).map_err(|e| format!("Failed to connect: {}", e))?;
+    let _ = stream.set_read_timeout(Some(timeout));
+    let _ = stream.set_write_timeout(Some(timeout));
+    send(stream, request)
+}
+
+/// Synchronous pipe handles have no read timeout, so the exchange runs on its own
+/// thread and is abandoned if it takes too long.
+#[cfg(windows)]
+fn exchange_socket(path: &Path, request: &str, timeout: Duration) -> Result<String, String> {
+    let (path, request) = (path.to_path_buf(), request.to_string());
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let deadline = std::time::Instant::now() + PIPE_BUSY_WAIT;
+        let pipe = loop {
+            match std::fs::OpenOptions::new().read(true).write(true).open(&path) {
+                Err(e) if pipe_busy(&e) && std::time::Instant::now() < deadline => {
+                    std::thread::sleep(Duration::from_millis(20))
+                }
+                result => break result,
+            }
+        };
+        let result = pipe
+            .map_err(|e| format!("Failed to connect: {}", e))
+            .and_then(|pipe| send(pipe, &request));
+        let _ = sender.send(result);
+    });
+    receiver
+        .recv_timeout(timeout)
+        .unwrap_or_else(|_| Err("Timed out waiting for the backend".to_string()))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn exchange_socket(_path: &Path, _request: &str, _timeout: Duration) -> Result<String, String> {
+    Err("The socket transport is not supported on this platform".to_string())
+}
@@ -0,0 +1,107 @@
+//! The system tray icon, which keeps the app reachable while the main window is
+//! hidden for scheduled health checks.
+//!
+//! "Run health check now" runs the saved schedule's analysis; without a schedule
+//! it asks the main window to start one (`tray-run-requested`). "Open results"
+//! shows the newest saved run in the main window (`open-analysis-run`).
+
+use serde_json::json;
+use std::sync::Mutex;
+use tauri::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tauri::tray::TrayIconBuilder;
+use tauri::{Emitter, Manager};
+
+const RUN_NOW: &str = "run-now";
+const LAST_SCORE: &str = "last-score";
+const OPEN_RESULTS: &str = "open-results";
+const SHOW_WINDOW: &str = "show-window";
+const QUIT: &str = "quit";
+
+#[derive(Default)]
+pub struct TrayState {
+    last_score: Mutex<Option<MenuItem<tauri::Wry>>>,
+}
+
+fn score_label(score: Option<f64>) -> String {
+    match score {
+        Some(score) => format!("Last score: {:.0}/100", score),
+        None => "Last score: none yet".to_string(),
+    }
+}
+
+/// Updates the "Last score" item after a run is saved.
+pub fn show_last_score(app_handle: &tauri::AppHandle, score: Option<f64>) {
+    let item = app_handle.state::<TrayState>().last_score.lock().unwrap().clone();
+    if let Some(item) = item {
+        if let Err(e) = item.set_text(score_label(score)) {
+            log::warn!("Failed to update tray menu: {}", e);
+        }
+    }
+}
+
+fn emit_to_main(app_handle: &tauri::AppHandle, event: &str, payload: serde_json::Value) {
+    crate::instance::focus_main_window(app_handle);
+    if let Err(e) = app_handle.emit_to("main", event, payload) {
+        log::warn!("Failed to emit {}: {}", event, e);
+    }
+}
+
+fn run_now(app_handle: &tauri::AppHandle) {
+    if !crate::scheduler::run_now(app_handle) {
+        emit_to_main(app_handle, "tray-run-requested", json!({}));
+    }
+}
+
+fn on_menu_event(app_handle: &tauri::AppHandle, event: MenuEvent) {
+    match event.id().as_ref() {
+        RUN_NOW => run_now(app_handle),
+        OPEN_RESULTS => {
+            let newest = crate::history::list_analysis_runs(app_handle.clone())
+                .ok()
+                .and_then(|runs| runs.into_iter().next());
+            let analysis_id = newest.map(|run| run.analysis_id);
+            emit_to_main(app_handle, "open-analysis-run", json!({ "analysis_id": analysis_id }));
+        }
+        SHOW_WINDOW => crate::instance::focus_main_window(app_handle),
+        QUIT => app_handle.exit(0),
+        _ => {}
+    }
+}
+
+/// Adds the tray icon; called once from setup.
+pub fn init(app: &tauri::App) -> tauri::Result<()> {
+    let newest = crate::history::list_analysis_runs(app.handle().clone())
+        .ok()
+        .and_then(|runs| runs.into_iter().next());
+
+    let last_score = MenuItem::with_id(
+        app,
+        LAST_SCORE,
+        score_label(newest.and_then(|run| run.health_score)),
+        false,
+        None::<&str>,
+    )?;
+    let menu = Menu::with_items(
+        app,
+        &[
+            &MenuItem::with_id(app, RUN_NOW, "Run health check now", true, None::<&str>)?,
+            &last_score,
+            &MenuItem::with_id(app, OPEN_RESULTS, "Open results", true, None::<&str>)?,
+            &PredefinedMenuItem::separator(app)?,
+            &MenuItem::with_id(app, SHOW_WINDOW, "Show window", true, None::<&str>)?,
+            &MenuItem::with_id(app, QUIT, "Quit", true, None::<&str>)?,
+        ],
+    )?;
+
+    let mut builder = TrayIconBuilder::with_id("main")
+        .tooltip(app.package_info().name.as_str())
+        .menu(&menu)
+        .on_menu_event(on_menu_event);
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+
+    *app.state::<TrayState>().last_score.lock().unwrap() = Some(last_score);
+    Ok(())
+}
@@ -0,0 +1,319 @@
+//! SSH tunnels to leaders that are only reachable through a jump host.
+//!
+//! Runs the system OpenSSH client (`ssh -N -L ...`), which ships with macOS,
+//! Linux and Windows 10 and later, forwarding a free loopback port to the
+//! leader; the analysis then connects to `local_addr`. Keepalives notice a dead
+//! connection, a tunnel that drops is reported as `ssh-tunnel-closed`, and every
+//! tunnel is torn down when the app exits.
+//!
+//! Unknown host keys are accepted and remembered on first use; a changed key is
+//! refused. For password logins ssh runs this executable as its `SSH_ASKPASS`,
+//! which answers with the password from its environment (see `askpass`).
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{Emitter, Manager};
+
+const ASKPASS_SECRET_VAR: &str = "CRIBL_HC_SSH_ASKPASS_SECRET";
+const OPEN_TIMEOUT: Duration = Duration::from_secs(30);
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+const KEEPALIVE_SECS: u32 = 15;
+const KEEPALIVE_MISSES: u32 = 3;
+
+/// When ssh runs us as its `SSH_ASKPASS`, prints the password and returns the
+/// exit code; `None` means this is a normal launch.
+pub fn askpass() -> Option<i32> {
+    let secret = std::env::var(ASKPASS_SECRET_VAR).ok()?;
+    println!("{}", secret);
+    Some(0)
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum TunnelAuth {
+    /// Keys from ssh-agent or `~/.ssh`, as plain `ssh` would use.
+    Agent,
+    KeyFile { path: String },
+    Password { password: String },
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TunnelInfo {
+    pub id: String,
+    pub host: String,
+    pub user: String,
+    pub remote_addr: String,
+    /// Where the analysis should connect, e.g. `127.0.0.1:53122`.
+    pub local_addr: String,
+    /// Milliseconds since the Unix epoch.
+    pub opened_at_ms: u64,
+}
+
+struct Tunnel {
+    info: TunnelInfo,
+    child: Child,
+}
+
+#[derive(Default)]
+pub struct TunnelState {
+    tunnels: Mutex<HashMap<String, Tunnel>>,
+}
+
+#[derive(Clone, Serialize)]
+struct TunnelClosed {
+    id: String,
+    /// What ssh last printed when the tunnel dropped by itself.
+    error: Option<String>,
+}
+
+/// Splits `host[:port]`, allowing a bracketed IPv6 address.
+fn split_host_port(value: &str) -> Result<(String, Option<u16>), String> {
+    let value = value.trim();
+    let (host, port) = match value.strip_prefix('[') {
+        Some(rest) => {
+            let (host, rest) = rest.split_once(']').ok_or_else(|| format!("Invalid address {}", value))?;
+            (host, rest.strip_prefix(':'))
+        }
+        None => match value.rsplit_once(':') {
+            Some((host, port)) if !host.contains(':') => (host, Some(port)),
+            _ => (value, None),
+        },
+    };
+    if host.is_empty() || host.starts_with('-') || host.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(format!("Invalid host {:?}", value));
+    }
+    let port = port
+        .map(|port| port.parse::<u16>().map_err(|_| format!("Invalid port in {}", value)))
+        .transpose()?;
+    Ok((host.to_string(), port))
+}
+
+/// A loopback port nothing is using right now; ssh fails cleanly
+/// (`ExitOnForwardFailure`) if it's taken before the tunnel binds it.
+fn free_port() -> Result<u16, String> {
+    TcpListener::bind(("127.0.0.1", 0))
+        .and_then(|listener| listener.local_addr())
+        .map(|addr| addr.port())
+        .map_err(|e| format!("Failed to find a free local port: {}", e))
+}
+
+fn ssh_command(host: &str, port: Option<u16>, user: &str, auth: &TunnelAuth, forward: &str) -> Result<Command, String> {
+    let mut command = Command::new("ssh");
+    command
+        .args(["-N", "-T"])
+        .args(["-o", "ExitOnForwardFailure=yes"])
+        .args(["-o", "StrictHostKeyChecking=accept-new"])
+        .args(["-o", "ConnectTimeout=15"])
+        .arg("-o")
+        .arg(format!("ServerAliveInterval={}", KEEPALIVE_SECS))
+        .arg("-o")
+        .arg(format!("ServerAliveCountMax={}", KEEPALIVE_MISSES))
+        .arg("-L")
+        .arg(forward)
+        .arg("-l")
+        .arg(user);
+    if let Some(port) = port {
+        command.arg("-p").arg(port.to_string());
+    }
+
+    match auth {
+        TunnelAuth::Agent => {
+            command.args(["-o", "BatchMode=yes"]);
+        }
+        TunnelAuth::KeyFile { path } => {
+            command
+                .arg("-i")
+                .arg(path)
+                .args(["-o", "IdentitiesOnly=yes", "-o", "BatchMode=yes"]);
+        }
+        TunnelAuth::Password { password } => {
+            let exe = std::env::current_exe().map_err(|e| format!("Failed to locate executable: {}", e))?;
+            command
+                .args(["-o", "PreferredAuthentications=password,keyboard-interactive"])
+                .args(["-o", "NumberOfPasswordPrompts=1"])
+                .env("SSH_ASKPASS", exe)
+                .env("SSH_ASKPASS_REQUIRE", "force")
+                // OpenSSH before 8.4 only uses SSH_ASKPASS with a DISPLAY set
+                .env("DISPLAY", std::env::var("DISPLAY").unwrap_or_else(|_| ":0".to_string()))
+                .env(ASKPASS_SECRET_VAR, password);
+        }
+    }
+    command.arg(host).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::piped());
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    Ok(command)
+}
+
+/// The last thing ssh printed, which is where it says why it gave up.
+fn last_error(output: &str) -> Option<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .rfind(|line| !line.is_empty())
+        .map(str::to_string)
+}
+
+/// Waits until the forward accepts connections, which ssh only allows once it
+/// has logged in and bound the port.
+fn wait_until_open(child: &mut Child, local: SocketAddr) -> Result<(), String> {
+    let deadline = Instant::now() + OPEN_TIMEOUT;
+    loop {
+        if let Ok(Some(status)) = child.try_wait() {
+            let mut output = String::new();
+            if let Some(mut stderr) = child.stderr.take() {
+                let _ = stderr.read_to_string(&mut output);
+            }
+            return Err(match last_error(&output) {
+                Some(error) => format!("SSH tunnel failed: {}", error),
+                None => format!("SSH tunnel failed: ssh exited with {}", status),
+            });
+        }
+        if TcpStream::connect_timeout(&local, POLL_INTERVAL).is_ok() {
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            return Err(format!("SSH tunnel did not open within {} s", OPEN_TIMEOUT.as_secs()));
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+fn close(tunnel: &mut Tunnel) {
+    let _ = tunnel.child.kill();
+    let _ = tunnel.child.wait();
+}
+
+/// Logs what ssh prints while the tunnel is up, and reports the tunnel closed
+/// once ssh exits on its own.
+fn watch(app_handle: tauri::AppHandle, id: String, stderr: std::process::ChildStderr) {
+    std::thread::spawn(move || {
+        let mut last = None;
+        for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+            log::warn!("SSH tunnel {}: {}", id, line);
+            last = last_error(&line).or(last);
+        }
+
+        // Gone already if close_ssh_tunnel took it down
+        let tunnel = app_handle.state::<TunnelState>().tunnels.lock().unwrap().remove(&id);
+        if let Some(mut tunnel) = tunnel {
+            close(&mut tunnel);
+            log::warn!("SSH tunnel {} to {} closed", id, tunnel.info.remote_addr);
+            if let Err(e) = app_handle.emit("ssh-tunnel-closed", TunnelClosed { id, error: last }) {
+                log::warn!("Failed to emit ssh-tunnel-closed: {}", e);
+            }
+        }
+    });
+}
+
+fn open(app_handle: tauri::AppHandle, host: String, user: String, auth: TunnelAuth, remote_addr: String) -> Result<TunnelInfo, String> {
+    let (ssh_host, ssh_port) = split_host_port(&host)?;
+    let (remote_host, remote_port) = split_host_port(&remote_addr)?;
+    let remote_port = remote_port.ok_or_else(|| format!("Remote address {} needs a port", remote_addr))?;
+    if user.trim().is_empty() || user.starts_with('-') {
+        return Err(format!("Invalid SSH user {:?}", user));
+    }
+
+    let local_port = free_port()?;
+    let local = SocketAddr::from(([127, 0, 0, 1], local_port));
+    let remote = match remote_host.contains(':') {
+        true => format!("[{}]:{}", remote_host, remote_port),
+        false => format!("{}:{}", remote_host, remote_port),
+    };
+    let forward = format!("127.0.0.1:{}:{}", local_port, remote);
+
+    let mut child = ssh_command(&ssh_host, ssh_port, &user, &auth, &forward)?
+        .spawn()
+        .map_err(|e| format!("Failed to run ssh (is OpenSSH installed?): {}", e))?;
+    if let Err(e) = wait_until_open(&mut child, local) {
+        let _ = child.kill();
+        let _ = child.wait();
+        return Err(e);
+    }
+
+    let id = format!("{}-{}", ssh_host, local_port);
+    let info = TunnelInfo {
+        id: id.clone(),
+        host,
+        user,
+        remote_addr,
+        local_addr: local.to_string(),
+        opened_at_ms: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64),
+    };
+    log::info!("SSH tunnel {} forwarding {} to {} via {}", id, info.local_addr, remote, ssh_host);
+
+    let stderr = child.stderr.take();
+    app_handle.state::<TunnelState>().tunnels.lock().unwrap().insert(
+        id.clone(),
+        Tunnel {
+            info: info.clone(),
+            child,
+        },
+    );
+    if let Some(stderr) = stderr {
+        watch(app_handle, id, stderr);
+    }
+    Ok(info)
+}
+
+/// Opens a tunnel to `remote_addr` (`host:port` as the jump host sees it)
+/// through `host` (`host[:port]`) and returns once it accepts connections.
+#[tauri::command]
+pub async fn open_ssh_tunnel(
+    app_handle: tauri::AppHandle,
+    host: String,
+    user: String,
+    auth: TunnelAuth,
+    remote_addr: String,
+) -> Result<TunnelInfo, String> {
+    tauri::async_runtime::spawn_blocking(move || open(app_handle, host, user, auth, remote_addr))
+        .await
+        .map_err(|e| format!("Failed to open SSH tunnel: {}", e))?
+}
+
+/// Closes tunnel `id`; returns whether it was open.
+#[tauri::command]
+pub fn close_ssh_tunnel(app_handle: tauri::AppHandle, id: String) -> bool {
+    let tunnel = app_handle.state::<TunnelState>().tunnels.lock().unwrap().remove(&id);
+    match tunnel {
+        Some(mut tunnel) => {
+            close(&mut tunnel);
+            log::info!("SSH tunnel {} closed", id);
+            true
+        }
+        None => false,
+    }
+}
+
+#[tauri::command]
+pub fn list_ssh_tunnels(app_handle: tauri::AppHandle) -> Vec<TunnelInfo> {
+    let state = app_handle.state::<TunnelState>();
+    let mut open: Vec<_> = state
+        .tunnels
+        .lock()
+        .unwrap()
+        .values()
+        .map(|tunnel| tunnel.info.clone())
+        .collect();
+    open.sort_by_key(|info| info.opened_at_ms);
+    open
+}
+
+/// Takes every tunnel down; called when the app exits.
+pub fn close_all(app_handle: &tauri::AppHandle) {
+    let tunnels: Vec<_> = app_handle.state::<TunnelState>().tunnels.lock().unwrap().drain().collect();
+    for (_, mut tunnel) in tunnels {
+        close(&mut tunnel);
+    }
+}
@@ -0,0 +1,526 @@
+//! Updates for the app and, separately, its bundled backend.
+//!
+//! The update URL serves a JSON manifest:
+//!
+//! ```json
+//! { "version": "1.4.0", "notes": "...", "pub_date": "2026-09-01T00:00:00Z",
+//!   "platforms": { "macos-aarch64": {
+//!       "app":     { "url": "...", "sha256": "...", "signature": "..." },
+//!       "backend": { "url": "...", "sha256": "...", "signature": "..." } } } }
+//! ```
+//!
+//! Platforms are keyed `<os>-<arch>` as Rust names them (`windows-x86_64`,
+//! `linux-x86_64`, ...). Each signature is a base64 Ed25519 signature of the raw
+//! SHA-256 digest of the file, made with the key whose public half is built in as
+//! `CRIBL_HC_UPDATE_PUBLIC_KEY`; builds without one can't update.
+//!
+//! A new backend is installed beside the app data rather than over the bundled
+//! one, which may sit somewhere read-only. Switching to it is a single settings
+//! save, so it either happens or it doesn't. If the new backend won't start, the
+//! previous one is put back and restarted. The app itself is updated by its
+//! platform installer, which is downloaded, verified and launched.
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{Emitter, Manager};
+
+use crate::settings::{self, SettingsStore};
+use crate::{Lifecycle, PythonBackend};
+
+const MANIFEST_URL: Option<&str> = option_env!("CRIBL_HC_UPDATE_URL");
+const PUBLIC_KEY: Option<&str> = option_env!("CRIBL_HC_UPDATE_PUBLIC_KEY");
+const MAX_MANIFEST_BYTES: usize = 1024 * 1024;
+const MAX_DOWNLOAD_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+/// Progress events are sent at most this often, in bytes.
+const PROGRESS_STEP: u64 = 512 * 1024;
+const DOWNLOADS_DIR: &str = "updates";
+const BACKENDS_DIR: &str = "backends";
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UpdateSettings {
+    /// Replaces the update URL this build was made with.
+    pub manifest_url: Option<String>,
+    /// A backend installed by an update, used instead of the bundled one.
+    pub backend: Option<InstalledBackend>,
+    /// What `backend` replaced, kept for `rollback_backend_update`. `None` as the
+    /// value of a rollback target means the bundled backend.
+    pub previous_backend: Option<Option<InstalledBackend>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InstalledBackend {
+    pub version: String,
+    /// Without the `.exe` a Windows build adds.
+    pub path: String,
+    pub sha256: String,
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+    version: String,
+    notes: Option<String>,
+    pub_date: Option<String>,
+    #[serde(default)]
+    platforms: HashMap<String, PlatformAssets>,
+}
+
+#[derive(Clone, Default, Deserialize)]
+struct PlatformAssets {
+    app: Option<Asset>,
+    backend: Option<Asset>,
+}
+
+#[derive(Clone, Deserialize)]
+struct Asset {
+    url: String,
+    sha256: String,
+    signature: String,
+}
+
+#[derive(Serialize)]
+pub struct UpdateInfo {
+    current_version: String,
+    version: String,
+    notes: Option<String>,
+    pub_date: Option<String>,
+    available: bool,
+    /// Whether the update includes an app installer for this platform.
+    app: bool,
+    /// Whether it includes a new backend for this platform.
+    backend: bool,
+}
+
+#[derive(Clone, Serialize)]
+struct UpdateProgress {
+    /// `app` or `backend`.
+    component: &'static str,
+    /// `downloading`, `verifying`, `installing` or `restarting`.
+    stage: &'static str,
+    downloaded: u64,
+    total: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct InstalledUpdate {
+    version: String,
+    backend_replaced: bool,
+    /// The installer that was launched, if the update includes one; the app
+    /// should be quit so it can finish.
+    installer: Option<String>,
+}
+
+#[derive(Default)]
+pub struct UpdateState {
+    installing: AtomicBool,
+}
+
+/// Clears `installing` however an install ends.
+struct InstallGuard<'a>(&'a AtomicBool);
+
+impl Drop for InstallGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+fn platform_key() -> String {
+    format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH)
+}
+
+/// Compares dotted versions numerically; pre-release suffixes sort before the release.
+pub fn is_newer(candidate: &str, current: &str) -> bool {
+    fn parts(version: &str) -> (Vec<u64>, bool) {
+        let version = version.trim().trim_start_matches('v');
+        let (release, pre) = match version.split_once('-') {
+            Some((release, _)) => (release, true),
+            None => (version, false),
+        };
+        (release.split('.').map(|p| p.parse().unwrap_or(0)).collect(), pre)
+    }
+    let (mut a, a_pre) = parts(candidate);
+    let (mut b, b_pre) = parts(current);
+    let len = a.len().max(b.len());
+    a.resize(len, 0);
+    b.resize(len, 0);
+    a > b || (a == b && b_pre && !a_pre)
+}
+
+fn update_settings(app_handle: &tauri::AppHandle) -> UpdateSettings {
+    app_handle.state::<SettingsStore>().settings.lock().unwrap().updates.clone()
+}
+
+/// The backend an update installed, if it is still on disk.
+pub fn installed_backend(app_handle: &tauri::AppHandle) -> Option<InstalledBackend> {
+    update_settings(app_handle)
+        .backend
+        .filter(|b| crate::sidecar::file_on_disk(Path::new(&b.path)).is_file())
+}
+
+fn manifest_url(app_handle: &tauri::AppHandle) -> Result<String, String> {
+    update_settings(app_handle)
+        .manifest_url
+        .or_else(|| MANIFEST_URL.map(str::to_string))
+        .filter(|url| !url.trim().is_empty())
+        .ok_or_else(|| "This build has no update URL".to_string())
+}
+
+fn public_key() -> Result<Vec<u8>, String> {
+    let key = PUBLIC_KEY.ok_or("This build has no update signing key, so it can't verify updates")?;
+    base64::engine::general_purpose::STANDARD
+        .decode(key.trim())
+        .map_err(|e| format!("Built-in update key is invalid: {}", e))
+}
+
+/// Checks a base64 Ed25519 `signature` of `digest`, the SHA-256 of `source`,
+/// against the publisher key. Rule packs are signed the same way.
+pub fn verify_signature(source: &str, digest: &[u8], signature: &str) -> Result<(), String> {
+    let signature = base64::engine::general_purpose::STANDARD
+        .decode(signature.trim())
+        .map_err(|e| format!("Invalid signature for {}: {}", source, e))?;
+    ring::signature::UnparsedPublicKey::new(&ring::signature::ED25519, public_key()?)
+        .verify(digest, &signature)
+        .map_err(|_| format!("{} is not signed by the publisher", source))
+}
+
+fn verify(asset: &Asset, digest: &[u8]) -> Result<(), String> {
+    let actual: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    if !actual.eq_ignore_ascii_case(asset.sha256.trim()) {
+        return Err(format!("Download from {} is corrupt (SHA-256 {}, expected {})", asset.url, actual, asset.sha256));
+    }
+    verify_signature(&asset.url, digest, &asset.signature)
+}
+
+fn emit_progress(app_handle: &tauri::AppHandle, progress: UpdateProgress) {
+    if let Err(e) = app_handle.emit("update-progress", progress) {
+        log::warn!("Failed to emit update-progress: {}", e);
+    }
+}
+
+pub async fn http_client(app_handle: &tauri::AppHandle) -> Result<reqwest::Client, String> {
+    let handle = app_handle.clone();
+    let builder = tauri::async_runtime::spawn_blocking(move || crate::network::client_builder(&handle))
+        .await
+        .map_err(|e| format!("Failed to configure the network: {}", e))??;
+    builder
+        .user_agent(format!("cribl-hc/{}", app_handle.package_info().version))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
+
+async fn fetch_manifest(app_handle: &tauri::AppHandle, client: &reqwest::Client) -> Result<Manifest, String> {
+    let url = manifest_url(app_handle)?;
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to check for updates: {}", e))?;
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| format!("Failed to read update manifest: {}", e))?;
+    if body.len() > MAX_MANIFEST_BYTES {
+        return Err("Update manifest is too large".to_string());
+    }
+    let manifest: Manifest = serde_json::from_slice(&body).map_err(|e| format!("Invalid update manifest: {}", e))?;
+    // Only the assets are signed, and the version names directories
+    crate::files::validate_version("update", &manifest.version)?;
+    Ok(manifest)
+}
+
+/// Downloads `asset` to `target`, verifying it before it is moved into place.
+async fn download(
+    app_handle: &tauri::AppHandle,
+    client: &reqwest::Client,
+    component: &'static str,
+    asset: &Asset,
+    target: &Path,
+) -> Result<(), String> {
+    let mut response = client
+        .get(&asset.url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to download {}: {}", asset.url, e))?;
+    let total = response.content_length();
+    if total.is_some_and(|t| t > MAX_DOWNLOAD_BYTES) {
+        return Err(format!("{} is too large to be an update", asset.url));
+    }
+
+    if let Some(dir) = target.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let partial = target.with_extension("part");
+    let mut file = std::fs::File::create(&partial).map_err(|e| format!("Failed to create {}: {}", partial.display(), e))?;
+    let mut hasher = Sha256::new();
+    let mut downloaded = 0u64;
+    let mut reported = 0u64;
+    let result: Result<(), String> = async {
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| format!("Download of {} failed: {}", asset.url, e))?
+        {
+            downloaded += chunk.len() as u64;
+            if downloaded > MAX_DOWNLOAD_BYTES {
+                return Err(format!("{} is too large to be an update", asset.url));
+            }
+            hasher.update(&chunk);
+            file.write_all(&chunk).map_err(|e| format!("Failed to save update: {}", e))?;
+            if downloaded - reported >= PROGRESS_STEP {
+                reported = downloaded;
+                emit_progress(app_handle, UpdateProgress { component, stage: "downloading", downloaded, total });
+            }
+        }
+        file.sync_all().map_err(|e| format!("Failed to save update: {}", e))?;
+        emit_progress(app_handle, UpdateProgress { component, stage: "verifying", downloaded, total });
+        verify(asset, &hasher.finalize())
+    }
+    .await;
+    drop(file);
+
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&partial);
+        return Err(e);
+    }
+    std::fs::rename(&partial, target).map_err(|e| format!("Failed to move update into place: {}", e))
+}
+
+fn app_data_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get data dir: {}", e))
+}
+
+/// Installer file name from the URL, so the OS knows what kind it is.
+fn installer_name(url: &str) -> String {
+    url::Url::parse(url)
+        .ok()
+        .and_then(|u| u.path_segments().and_then(|mut s| s.next_back().map(str::to_string)))
+        .filter(|name| !name.is_empty() && !name.contains(['/', '\\']))
+        .unwrap_or_else(|| "cribl-hc-installer".to_string())
+}
+
+fn set_backend(
+    app_handle: &tauri::AppHandle,
+    backend: Option<InstalledBackend>,
+    previous: Option<Option<InstalledBackend>>,
+) -> Result<(), String> {
+    let store = app_handle.state::<SettingsStore>();
+    let mut current = store.settings.lock().unwrap();
+    current.updates.backend = backend;
+    current.updates.previous_backend = previous;
+    settings::save(app_handle, &current)
+}
+
+/// Restarts the backend if it's ours and running, so it picks up a switched binary.
+fn restart(app_handle: &tauri::AppHandle) -> Result<(), String> {
+    let state = app_handle.state::<PythonBackend>();
+    if state.lifecycle() == Lifecycle::Running && state.owned.load(Ordering::SeqCst) {
+        crate::stop_backend_process(app_handle)?;
+    }
+    if state.lifecycle() == Lifecycle::Stopped {
+        crate::start_backend_blocking(app_handle.clone()).map_err(String::from)?;
+    }
+    Ok(())
+}
+
+/// Switches to `new`, restarting the backend, and switches back if it won't start.
+fn swap_backend(app_handle: &tauri::AppHandle, new: InstalledBackend) -> Result<(), String> {
+    let previous = update_settings(app_handle).backend;
+    set_backend(app_handle, Some(new.clone()), Some(previous.clone()))?;
+    log::info!("Switched to backend {} at {}", new.version, new.path);
+
+    let Err(e) = restart(app_handle) else {
+        remove_unused_backends(app_handle);
+        return Ok(());
+    };
+
+    log::error!("Backend {} failed to start ({}); rolling back", new.version, e);
+    set_backend(app_handle, previous, None)?;
+    let restored = restart(app_handle);
+    if let Err(emit_error) = app_handle.emit("update-rolled-back", &e) {
+        log::warn!("Failed to emit update-rolled-back: {}", emit_error);
+    }
+    match restored {
+        Ok(()) => Err(format!("The new backend failed to start, so the previous one was restored: {}", e)),
+        Err(restore_error) => Err(format!(
+            "The new backend failed to start ({}) and the previous one didn't either: {}",
+            e, restore_error
+        )),
+    }
+}
+
+/// Deletes installed backends that are neither current nor the rollback target.
+fn remove_unused_backends(app_handle: &tauri::AppHandle) {
+    let Ok(dir) = app_data_dir(app_handle).map(|d| d.join(BACKENDS_DIR)) else {
+        return;
+    };
+    let updates = update_settings(app_handle);
+    let keep: Vec<PathBuf> = [updates.backend, updates.previous_backend.flatten()]
+        .into_iter()
+        .flatten()
+        .filter_map(|b| Path::new(&b.path).parent().map(Path::to_path_buf))
+        .collect();
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() && !keep.contains(&path) {
+            if let Err(e) = std::fs::remove_dir_all(&path) {
+                log::warn!("Failed to remove old backend {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+/// Launches a verified installer. Windows installers and macOS packages run
+/// directly; anything else (a disk image, an AppImage) is shown in its folder.
+fn launch_installer(path: &Path) -> Result<(), String> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+    let mut command = match extension.as_str() {
+        "msi" => {
+            let mut command = std::process::Command::new("msiexec");
+            command.arg("/i").arg(path);
+            command
+        }
+        "exe" => std::process::Command::new(path),
+        "pkg" | "dmg" if cfg!(target_os = "macos") => {
+            let mut command = std::process::Command::new("open");
+            command.arg(path);
+            command
+        }
+        _ => return crate::opener::open_folder(path.parent().unwrap_or(path)),
+    };
+    command
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to launch installer {}: {}", path.display(), e))
+}
+
+#[tauri::command]
+pub async fn check_for_updates(app_handle: tauri::AppHandle) -> Result<UpdateInfo, String> {
+    let client = http_client(&app_handle).await?;
+    let manifest = fetch_manifest(&app_handle, &client).await?;
+    let current_version = app_handle.package_info().version.to_string();
+    let assets = manifest.platforms.get(&platform_key()).cloned().unwrap_or_default();
+
+    Ok(UpdateInfo {
+        available: is_newer(&manifest.version, &current_version) && (assets.app.is_some() || assets.backend.is_some()),
+        current_version,
+        version: manifest.version,
+        notes: manifest.notes,
+        pub_date: manifest.pub_date,
+        app: assets.app.is_some(),
+        backend: assets.backend.is_some(),
+    })
+}
+
+/// Downloads and verifies the update, switches to its backend (restarting the
+/// running one, and rolling back if the new one won't start), then launches the
+/// app installer. Progress arrives as `update-progress` events.
+#[tauri::command]
+pub async fn install_update(app_handle: tauri::AppHandle) -> Result<InstalledUpdate, String> {
+    let state = app_handle.state::<UpdateState>();
+    if state.installing.swap(true, Ordering::SeqCst) {
+        return Err("An update is already being installed".to_string());
+    }
+    let _guard = InstallGuard(&state.installing);
+
+    public_key()?;
+    let client = http_client(&app_handle).await?;
+    let manifest = fetch_manifest(&app_handle, &client).await?;
+    let current_version = app_handle.package_info().version.to_string();
+    if !is_newer(&manifest.version, &current_version) {
+        return Err(format!("Already up to date ({})", current_version));
+    }
+    let assets = manifest.platforms.get(&platform_key()).cloned().unwrap_or_default();
+    if assets.app.is_none() && assets.backend.is_none() {
+        return Err(format!("Update {} has nothing for {}", manifest.version, platform_key()));
+    }
+    log::info!("Installing update {}", manifest.version);
+
+    let data_dir = app_data_dir(&app_handle)?;
+    let downloads = data_dir.join(DOWNLOADS_DIR).join(&manifest.version);
+    let backend = match &assets.backend {
+        Some(asset) => {
+            let dir = data_dir.join(BACKENDS_DIR).join(&manifest.version);
+            let path = dir.join("cribl-hc-backend");
+            let on_disk = crate::sidecar::file_on_disk(&path);
+            download(&app_handle, &client, "backend", asset, &on_disk).await?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                std::fs::set_permissions(&on_disk, std::fs::Permissions::from_mode(0o755))
+                    .map_err(|e| format!("Failed to make the new backend executable: {}", e))?;
+            }
+            Some(InstalledBackend {
+                version: manifest.version.clone(),
+                path: path.to_string_lossy().to_string(),
+                sha256: asset.sha256.trim().to_ascii_lowercase(),
+            })
+        }
+        None => None,
+    };
+    let installer = match &assets.app {
+        Some(asset) => {
+            let path = downloads.join(installer_name(&asset.url));
+            download(&app_handle, &client, "app", asset, &path).await?;
+            Some(path)
+        }
+        None => None,
+    };
+
+    let backend_replaced = backend.is_some();
+    if let Some(backend) = backend {
+        emit_progress(&app_handle, UpdateProgress { component: "backend", stage: "restarting", downloaded: 0, total: None });
+        let handle = app_handle.clone();
+        tauri::async_runtime::spawn_blocking(move || swap_backend(&handle, backend))
+            .await
+            .map_err(|e| format!("Failed to install the new backend: {}", e))??;
+    }
+
+    if let Some(path) = &installer {
+        emit_progress(&app_handle, UpdateProgress { component: "app", stage: "installing", downloaded: 0, total: None });
+        launch_installer(path)?;
+    }
+
+    Ok(InstalledUpdate {
+        version: manifest.version,
+        backend_replaced,
+        installer: installer.map(|p| p.to_string_lossy().to_string()),
+    })
+}
+
+/// Goes back to the backend the last update replaced and restarts it.
+#[tauri::command]
+pub async fn rollback_backend_update(app_handle: tauri::AppHandle) -> Result<Option<InstalledBackend>, String> {
+    let updates = update_settings(&app_handle);
+    let previous = updates
+        .previous_backend
+        .ok_or("There is no earlier backend to go back to")?;
+    set_backend(&app_handle, previous.clone(), None)?;
+    log::info!(
+        "Rolled back to backend {}",
+        previous.as_ref().map_or("bundled with the app", |b| b.version.as_str())
+    );
+
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn_blocking(move || restart(&handle))
+        .await
+        .map_err(|e| format!("Failed to restart backend: {}", e))??;
+    remove_unused_backends(&app_handle);
+    Ok(previous)
+}
@@ -0,0 +1,118 @@
+//! Notices when the machine wakes from sleep and makes sure the backend survived it.
+//!
+//! There is no portable sleep/wake notification, so a background thread ticks and
+//! compares the clocks: during sleep the wall clock keeps going while the monotonic
+//! clock stalls (Linux, macOS), or the thread simply isn't scheduled for much longer
+//! than its tick (Windows). Either gap shows up as a missed stretch of time.
+
+use serde::Serialize;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{Emitter, Manager};
+
+use crate::{health, Lifecycle, PythonBackend};
+
+const TICK: Duration = Duration::from_secs(5);
+/// Missing more time than this counts as having slept.
+const SLEEP_THRESHOLD: Duration = Duration::from_secs(30);
+/// Networking can take a moment to come back after wake.
+const HEALTH_ATTEMPTS: u32 = 3;
+const HEALTH_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Serialize)]
+pub struct WakeCheck {
+    /// How long the machine appeared to be asleep; `None` for a manual check.
+    slept_secs: Option<u64>,
+    responsive: bool,
+    restarted: bool,
+}
+
+async fn responds(app_handle: &tauri::AppHandle) -> bool {
+    for attempt in 0..HEALTH_ATTEMPTS {
+        if attempt > 0 {
+            tokio::time::sleep(HEALTH_RETRY_DELAY).await;
+        }
+        if health::check(app_handle).await.reachable {
+            return true;
+        }
+    }
+    false
+}
+
+/// Health-checks the running backend and restarts one we own if it stopped answering.
+async fn verify(app_handle: &tauri::AppHandle, slept: Option<Duration>) -> Result<WakeCheck, String> {
+    let state = app_handle.state::<PythonBackend>();
+    if state.lifecycle() != Lifecycle::Running || state.suspended.load(Ordering::SeqCst) {
+        return Err("Backend is not running".to_string());
+    }
+
+    let slept_secs = slept.map(|d| d.as_secs());
+    if responds(app_handle).await {
+        return Ok(WakeCheck {
+            slept_secs,
+            responsive: true,
+            restarted: false,
+        });
+    }
+
+    if !state.owned.load(Ordering::SeqCst) {
+        return Err("Backend is not responding, and this app did not start it".to_string());
+    }
+
+    log::warn!("Backend stopped responding after sleep; restarting it");
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        crate::stop_backend_process(&handle)?;
+        crate::start_backend_blocking(handle).map_err(String::from)
+    })
+    .await
+    .map_err(|e| format!("Failed to restart backend: {}", e))??;
+
+    let check = WakeCheck {
+        slept_secs,
+        responsive: false,
+        restarted: true,
+    };
+    if let Err(e) = app_handle.emit("backend-recovered-after-sleep", check.clone()) {
+        log::warn!("Failed to emit backend-recovered-after-sleep: {}", e);
+    }
+    Ok(check)
+}
+
+/// Starts the wake detector; it stops with the app.
+pub fn spawn(app_handle: tauri::AppHandle) {
+    std::thread::spawn(move || {
+        let mut last_tick = (Instant::now(), SystemTime::now());
+
+        loop {
+            std::thread::sleep(TICK);
+            if app_handle.state::<PythonBackend>().shutdown.is_cancelled() {
+                return;
+            }
+
+            let now = (Instant::now(), SystemTime::now());
+            let monotonic = now.0.duration_since(last_tick.0);
+            let wall = now.1.duration_since(last_tick.1).unwrap_or_default();
+            last_tick = now;
+
+            let missed = wall.saturating_sub(monotonic).max(monotonic.saturating_sub(TICK));
+            if missed < SLEEP_THRESHOLD {
+                continue;
+            }
+
+            log::info!("Woke after about {} s asleep; checking the backend", missed.as_secs());
+            let handle = app_handle.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = verify(&handle, Some(missed)).await {
+                    log::debug!("Skipped post-wake check: {}", e);
+                }
+            });
+        }
+    });
+}
+
+/// Runs the post-wake check on demand.
+#[tauri::command]
+pub async fn verify_backend_after_sleep(app_handle: tauri::AppHandle) -> Result<WakeCheck, String> {
+    verify(&app_handle, None).await
+}
@@ -0,0 +1,270 @@
+//! Live re-analysis of a local directory of Cribl configuration, such as a
+//! gitops checkout, while it is being edited.
+//!
+//! Changes are debounced and reported as `config-changed`, then the directory
+//! is analyzed by the backend the way a diag bundle is (it finds `local/cribl`
+//! under the root, per worker group for `groups/<name>/local/cribl`). Only the
+//! analyzers that read the changed files run, unless something else changed.
+//! Each result is sent as `reanalysis-complete`. Changes made while an analysis
+//! runs are collected and analyzed together once it finishes.
+
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+/// Quiet period before a burst of file changes is reported as one event.
+const DEBOUNCE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Analyzers that read each part of `local/cribl`: a file name, or a directory
+/// anywhere in the path.
+const ANALYZERS_BY_PATH: &[(&str, &[&str])] = &[
+    ("pipelines", &["config", "dataflow_topology"]),
+    ("inputs.yml", &["config", "security", "dataflow_topology"]),
+    ("outputs.yml", &["config", "security", "dataflow_topology"]),
+    ("lookups", &["lookup_health"]),
+];
+
+/// Which analyzers a change needs.
+#[derive(Clone, Debug)]
+enum Scope {
+    All,
+    Analyzers(BTreeSet<&'static str>),
+}
+
+impl Scope {
+    fn merge(self, other: Scope) -> Scope {
+        match (self, other) {
+            (Scope::Analyzers(mut a), Scope::Analyzers(b)) => {
+                a.extend(b);
+                Scope::Analyzers(a)
+            }
+            _ => Scope::All,
+        }
+    }
+
+    fn analyzers(&self) -> Option<Vec<&'static str>> {
+        match self {
+            Scope::All => None,
+            Scope::Analyzers(analyzers) => Some(analyzers.iter().copied().collect()),
+        }
+    }
+}
+
+/// A directory's re-analysis: whether one is running, and what changed since it started.
+#[derive(Default)]
+struct Reanalysis {
+    running: bool,
+    queued: Option<Scope>,
+}
+
+#[derive(Default)]
+pub struct WatchState {
+    watchers: Mutex<HashMap<PathBuf, Debouncer<RecommendedWatcher>>>,
+    reanalyses: Mutex<HashMap<PathBuf, Reanalysis>>,
+}
+
+#[derive(Clone, Serialize)]
+struct ConfigChanged {
+    path: String,
+    changed: Vec<String>,
+}
+
+#[derive(Clone, Serialize)]
+struct ReanalysisComplete {
+    path: String,
+    /// The analyzers that ran; `None` for all of them.
+    analyzers: Option<Vec<&'static str>>,
+    analysis_id: Option<String>,
+    health_score: Option<f64>,
+    /// The backend's JSON export of the analysis.
+    result: Option<Value>,
+    error: Option<String>,
+}
+
+fn canonical_path(path: &str) -> Result<PathBuf, String> {
+    PathBuf::from(path)
+        .canonicalize()
+        .map_err(|e| format!("Invalid watch path {}: {}", path, e))
+}
+
+/// Skips `.git` and other hidden directories, and editors' swap and backup files.
+fn is_relevant(root: &Path, path: &Path) -> bool {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let hidden = relative
+        .components()
+        .any(|c| matches!(c, Component::Normal(name) if name.to_string_lossy().starts_with('.')));
+    let name = relative.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    !hidden && !name.ends_with('~') && !name.ends_with(".swp") && !name.ends_with(".tmp")
+}
+
+fn scope_of(root: &Path, path: &Path) -> Scope {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let names: Vec<String> = relative
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+            _ => None,
+        })
+        .collect();
+    ANALYZERS_BY_PATH
+        .iter()
+        .find(|(part, _)| names.iter().any(|name| name == part))
+        .map_or(Scope::All, |(_, analyzers)| Scope::Analyzers(analyzers.iter().copied().collect()))
+}
+
+/// Queues `scope` for `root`, starting a re-analysis unless one is running.
+fn queue(app_handle: &tauri::AppHandle, root: PathBuf, scope: Scope) {
+    let state = app_handle.state::<WatchState>();
+    let mut reanalyses = state.reanalyses.lock().unwrap();
+    let entry = reanalyses.entry(root.clone()).or_default();
+    entry.queued = Some(match entry.queued.take() {
+        Some(queued) => queued.merge(scope),
+        None => scope,
+    });
+    if entry.running {
+        return;
+    }
+    entry.running = true;
+
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let scope = {
+                let state = handle.state::<WatchState>();
+                let mut reanalyses = state.reanalyses.lock().unwrap();
+                let Some(entry) = reanalyses.get_mut(&root) else {
+                    return;
+                };
+                match entry.queued.take() {
+                    Some(scope) => scope,
+                    None => {
+                        entry.running = false;
+                        return;
+                    }
+                }
+            };
+            reanalyze(&handle, &root, scope).await;
+        }
+    });
+}
+
+async fn reanalyze(app_handle: &tauri::AppHandle, root: &Path, scope: Scope) {
+    let analyzers = scope.analyzers();
+    let name = root.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let body = json!({
+        "deployment_name": format!("local: {}", name),
+        "bundle_path": root,
+        "analyzers": analyzers,
+    });
+    let result = match crate::scheduler::wait_for_backend(app_handle).await {
+        Ok(()) => crate::scheduler::run_analysis(app_handle, &body).await,
+        Err(e) => Err(e),
+    };
+
+    let complete = match result {
+        Ok(result) => ReanalysisComplete {
+            path: root.to_string_lossy().to_string(),
+            analyzers,
+            analysis_id: result.get("analysis_id").and_then(Value::as_str).map(str::to_string),
+            health_score: result.get("health_score").and_then(Value::as_f64),
+            result: Some(result),
+            error: None,
+        },
+        Err(e) => {
+            log::warn!("Re-analysis of {} failed: {}", root.display(), e);
+            ReanalysisComplete {
+                path: root.to_string_lossy().to_string(),
+                analyzers,
+                analysis_id: None,
+                health_score: None,
+                result: None,
+                error: Some(e),
+            }
+        }
+    };
+    if let Err(e) = app_handle.emit("reanalysis-complete", complete) {
+        log::warn!("Failed to emit reanalysis-complete: {}", e);
+    }
+}
+
+/// Watches a directory of Cribl configuration and analyzes it now and after every
+/// change. Returns the canonical path, which is what events carry.
+#[tauri::command]
+pub fn watch_config_path(app_handle: tauri::AppHandle, path: String) -> Result<String, String> {
+    let root = canonical_path(&path)?;
+    let state = app_handle.state::<WatchState>();
+    let mut watchers = state.watchers.lock().unwrap();
+
+    if watchers.contains_key(&root) {
+        return Ok(root.to_string_lossy().to_string());
+    }
+
+    let handle = app_handle.clone();
+    let watched = root.clone();
+    let event_root = root.to_string_lossy().to_string();
+    let mut debouncer = new_debouncer(DEBOUNCE_INTERVAL, move |result: DebounceEventResult| {
+        match result {
+            Ok(events) => {
+                let changed: Vec<&Path> = events
+                    .iter()
+                    .map(|e| e.path.as_path())
+                    .filter(|path| is_relevant(&watched, path))
+                    .collect();
+                let Some(scope) = changed
+                    .iter()
+                    .map(|path| scope_of(&watched, path))
+                    .reduce(Scope::merge)
+                else {
+                    return;
+                };
+
+                let payload = ConfigChanged {
+                    path: event_root.clone(),
+                    changed: changed.iter().map(|path| path.to_string_lossy().to_string()).collect(),
+                };
+                if let Err(e) = handle.emit("config-changed", payload) {
+                    log::warn!("Failed to emit config-changed: {}", e);
+                }
+                queue(&handle, watched.clone(), scope);
+            }
+            Err(e) => log::warn!("Config watcher error for {}: {}", event_root, e),
+        }
+    })
+    .map_err(|e| format!("Failed to create watcher: {}", e))?;
+
+    debouncer
+        .watcher()
+        .watch(&root, RecursiveMode::Recursive)
+        .map_err(|e| format!("Failed to watch {}: {}", root.display(), e))?;
+
+    watchers.insert(root.clone(), debouncer);
+    drop(watchers);
+    queue(&app_handle, root.clone(), Scope::All);
+
+    Ok(root.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub fn unwatch_config_path(app_handle: tauri::AppHandle, path: String) -> bool {
+    // The directory may already be gone, so fall back to the path as given.
+    let root = canonical_path(&path).unwrap_or_else(|_| PathBuf::from(&path));
+    let state = app_handle.state::<WatchState>();
+    let removed = state.watchers.lock().unwrap().remove(&root);
+    // A re-analysis already running finishes, but nothing queued after it starts
+    state.reanalyses.lock().unwrap().remove(&root);
+
+    removed.is_some()
+}
+
+/// Drops every active watcher; called on app exit.
+pub fn unwatch_all(app_handle: &tauri::AppHandle) {
+    let state = app_handle.state::<WatchState>();
+    state.watchers.lock().unwrap().clear();
+    state.reanalyses.lock().unwrap().clear();
+}
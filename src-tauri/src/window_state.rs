@@ -0,0 +1,214 @@
+use serde::{Deserialize, Serialize};
+use tauri::{Manager, Monitor, PhysicalPosition, PhysicalSize, WebviewWindow};
+
+use crate::settings::{self, SettingsStore};
+
+const MAIN_WINDOW: &str = "main";
+/// Results windows are labelled with this and the run they show.
+const RESULTS_WINDOW_PREFIX: &str = "results-";
+/// Each further results window opens this far down and right of the last.
+const CASCADE_OFFSET: i32 = 30;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WindowState {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub maximized: bool,
+}
+
+fn main_window(app_handle: &tauri::AppHandle) -> Result<WebviewWindow, String> {
+    app_handle
+        .get_webview_window(MAIN_WINDOW)
+        .ok_or_else(|| "Main window not found".to_string())
+}
+
+fn intersects(state: &WindowState, monitor: &Monitor) -> bool {
+    let pos = monitor.position();
+    let size = monitor.size();
+
+    state.x < pos.x + size.width as i32
+        && state.x + state.width as i32 > pos.x
+        && state.y < pos.y + size.height as i32
+        && state.y + state.height as i32 > pos.y
+}
+
+/// Keeps the window fully on a connected monitor, moving it to `fallback`
+/// when the monitor it was saved on is gone.
+fn clamp_to_monitors(state: &WindowState, monitors: &[Monitor], fallback: Option<&Monitor>) -> WindowState {
+    let Some(monitor) = monitors.iter().find(|m| intersects(state, m)).or(fallback) else {
+        return state.clone();
+    };
+
+    let pos = monitor.position();
+    let size = monitor.size();
+    let width = state.width.min(size.width);
+    let height = state.height.min(size.height);
+
+    WindowState {
+        x: state.x.clamp(pos.x, pos.x + (size.width - width) as i32),
+        y: state.y.clamp(pos.y, pos.y + (size.height - height) as i32),
+        width,
+        height,
+        maximized: state.maximized,
+    }
+}
+
+pub fn save(app_handle: &tauri::AppHandle) -> Result<(), String> {
+    let window = main_window(app_handle)?;
+    let maximized = window.is_maximized().map_err(|e| format!("Failed to read window state: {}", e))?;
+
+    let store = app_handle.state::<SettingsStore>();
+    let mut current = store.settings.lock().unwrap();
+
+    // A maximized window's geometry is the monitor's, so keep the last normal geometry instead.
+    let state = match (&current.window, maximized) {
+        (Some(previous), true) => WindowState {
+            maximized: true,
+            ..previous.clone()
+        },
+        _ => {
+            let position = window
+                .outer_position()
+                .map_err(|e| format!("Failed to read window position: {}", e))?;
+            let size = window
+                .inner_size()
+                .map_err(|e| format!("Failed to read window size: {}", e))?;
+
+            WindowState {
+                x: position.x,
+                y: position.y,
+                width: size.width,
+                height: size.height,
+                maximized,
+            }
+        }
+    };
+
+    current.window = Some(state);
+    settings::save(app_handle, &current)
+}
+
+pub fn restore(app_handle: &tauri::AppHandle) -> Result<(), String> {
+    let Some(saved) = app_handle.state::<SettingsStore>().settings.lock().unwrap().window.clone() else {
+        return Ok(());
+    };
+
+    let window = main_window(app_handle)?;
+    let monitors = window
+        .available_monitors()
+        .map_err(|e| format!("Failed to list monitors: {}", e))?;
+    let primary = window.primary_monitor().ok().flatten();
+    let state = clamp_to_monitors(&saved, &monitors, primary.as_ref().or(monitors.first()));
+
+    window
+        .set_size(PhysicalSize::new(state.width, state.height))
+        .and_then(|_| window.set_position(PhysicalPosition::new(state.x, state.y)))
+        .map_err(|e| format!("Failed to restore window geometry: {}", e))?;
+
+    if state.maximized {
+        window.maximize().map_err(|e| format!("Failed to maximize window: {}", e))?;
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn save_window_state(app_handle: tauri::AppHandle) -> Result<(), String> {
+    save(&app_handle)
+}
+
+#[tauri::command]
+pub fn restore_window_state(app_handle: tauri::AppHandle) -> Result<(), String> {
+    restore(&app_handle)
+}
+
+pub fn is_results_window(label: &str) -> bool {
+    label.starts_with(RESULTS_WINDOW_PREFIX)
+}
+
+/// Remembers a results window's geometry for the next one opened.
+pub fn save_results_window(window: &tauri::Window) -> Result<(), String> {
+    let position = window
+        .outer_position()
+        .map_err(|e| format!("Failed to read window position: {}", e))?;
+    let size = window
+        .inner_size()
+        .map_err(|e| format!("Failed to read window size: {}", e))?;
+    let maximized = window.is_maximized().map_err(|e| format!("Failed to read window state: {}", e))?;
+
+    let app_handle = window.app_handle();
+    let store = app_handle.state::<SettingsStore>();
+    let mut current = store.settings.lock().unwrap();
+    current.results_window = Some(match (&current.results_window, maximized) {
+        (Some(previous), true) => WindowState {
+            maximized: true,
+            ..previous.clone()
+        },
+        _ => WindowState {
+            x: position.x,
+            y: position.y,
+            width: size.width,
+            height: size.height,
+            maximized,
+        },
+    });
+    settings::save(app_handle, &current)
+}
+
+/// Opens the results of `run_id` (an analysis id) in a window of its own, so an
+/// earlier report can stay open beside a new analysis. Focuses the window if that
+/// run is already open. New windows take the size the last one closed at.
+#[tauri::command]
+pub fn open_results_window(app_handle: tauri::AppHandle, run_id: String) -> Result<(), String> {
+    let valid = !run_id.is_empty()
+        && run_id.len() <= 128
+        && run_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(format!("Invalid run id: {:?}", run_id));
+    }
+
+    let label = format!("{}{}", RESULTS_WINDOW_PREFIX, run_id);
+    if let Some(window) = app_handle.get_webview_window(&label) {
+        let _ = window.unminimize();
+        return window.set_focus().map_err(|e| format!("Failed to focus results window: {}", e));
+    }
+
+    let url = tauri::WebviewUrl::App(format!("results/{}", run_id).into());
+    let window = tauri::WebviewWindowBuilder::new(&app_handle, &label, url)
+        .title(format!("Analysis results - {}", run_id))
+        .inner_size(1100.0, 850.0)
+        .min_inner_size(640.0, 480.0)
+        .visible(false)
+        .build()
+        .map_err(|e| format!("Failed to open results window: {}", e))?;
+
+    let saved = app_handle.state::<SettingsStore>().settings.lock().unwrap().results_window.clone();
+    if let Some(saved) = saved {
+        let others = app_handle
+            .webview_windows()
+            .keys()
+            .filter(|l| is_results_window(l) && **l != label)
+            .count() as i32;
+        let cascaded = WindowState {
+            x: saved.x + others * CASCADE_OFFSET,
+            y: saved.y + others * CASCADE_OFFSET,
+            ..saved
+        };
+        let monitors = window.available_monitors().unwrap_or_default();
+        let primary = window.primary_monitor().ok().flatten();
+        let state = clamp_to_monitors(&cascaded, &monitors, primary.as_ref().or(monitors.first()));
+        if let Err(e) = window
+            .set_size(PhysicalSize::new(state.width, state.height))
+            .and_then(|_| window.set_position(PhysicalPosition::new(state.x, state.y)))
+        {
+            log::warn!("Failed to place results window: {}", e);
+        }
+        if state.maximized {
+            let _ = window.maximize();
+        }
+    }
+
+    window.show().map_err(|e| format!("Failed to show results window: {}", e))
+}
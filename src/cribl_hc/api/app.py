@@ -8,6 +8,9 @@ Provides REST API endpoints for:
 - WebSocket live updates
 """
 
+import hmac
+import os
+import time
 from contextlib import asynccontextmanager
 from typing import Dict
 
@@ -68,6 +71,45 @@ app.add_middleware(
     allow_headers=["*"],
 )
 
+# Set by the desktop app, whose gateway adds it to every request it forwards
+_API_TOKEN_ENV = "CRIBL_HC_API_TOKEN"
+_API_TOKEN_HEADER = b"x-cribl-hc-token"
+# Guarded by its own token so the app can always stop the backend
+_UNAUTHENTICATED_PATHS = {"/api/v1/shutdown"}
+
+
+class APITokenMiddleware:
+    """Reject HTTP and WebSocket requests without the API token, when one is configured."""
+
+    def __init__(self, app):
+        self.app = app
+
+    async def __call__(self, scope, receive, send):
+        expected = os.environ.get(_API_TOKEN_ENV)
+        if (
+            not expected
+            or scope["type"] not in ("http", "websocket")
+            or scope["path"] in _UNAUTHENTICATED_PATHS
+        ):
+            await self.app(scope, receive, send)
+            return
+
+        provided = dict(scope["headers"]).get(_API_TOKEN_HEADER, b"")
+        if hmac.compare_digest(provided, expected.encode()):
+            await self.app(scope, receive, send)
+            return
+
+        if scope["type"] == "websocket":
+            # Closing before accepting makes the server answer the handshake with 403
+            await send({"type": "websocket.close", "code": 1008})
+            return
+        response = JSONResponse({"detail": "Missing or invalid API token"}, status_code=401)
+        await response(scope, receive, send)
+
+
+# Added last so it runs first, before CORS answers anything
+app.add_middleware(APITokenMiddleware)
+
 # Include routers
 app.include_router(system.router, prefix="/api/v1", tags=["system"])
 app.include_router(credentials.router, prefix="/api/v1/credentials", tags=["credentials"])
@@ -93,5 +135,6 @@ async def health_check():
     return {
         "status": "healthy",
         "version": __version__,
-        "service": "cribl-health-check"
+        "service": "cribl-health-check",
+        "timestamp_ms": int(time.time() * 1000),
     }
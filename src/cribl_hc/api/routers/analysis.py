@@ -6,6 +6,7 @@ and real-time status updates via WebSocket.
 """
 
 import asyncio
+import os
 import uuid
 from datetime import datetime
 from typing import Dict, List, Optional
@@ -20,7 +21,8 @@ from json import JSONEncoder
 from cribl_hc.analyzers import get_global_registry
 from cribl_hc.cli.commands.config import load_credentials
 from cribl_hc.core.api_client import CriblAPIClient
-from cribl_hc.core.orchestrator import AnalyzerOrchestrator
+from cribl_hc.core.bundle_client import DiagBundleClient
+from cribl_hc.core.orchestrator import AnalysisProgress, AnalyzerOrchestrator
 from cribl_hc.models.analysis import AnalysisRun
 from cribl_hc.models.finding import Finding
 from cribl_hc.models.health import HealthScore
@@ -43,6 +45,8 @@ class CustomJSONEncoder(JSONEncoder):
 # In-memory storage for analysis results (will be replaced with database in v2)
 analysis_results: Dict[str, Dict] = {}
 active_websockets: Dict[str, List[WebSocket]] = {}
+# Tasks of running analyses, so they can be cancelled
+running_tasks: Dict[str, asyncio.Task] = {}
 
 
 class AnalysisStatus(str, Enum):
@@ -51,6 +55,7 @@ class AnalysisStatus(str, Enum):
     RUNNING = "running"
     COMPLETED = "completed"
     FAILED = "failed"
+    CANCELLED = "cancelled"
 
 
 class AnalysisRequest(BaseModel):
@@ -60,6 +65,13 @@ class AnalysisRequest(BaseModel):
         None,
         description="List of analyzers to run. If not specified, all analyzers run."
     )
+    bundle_path: Optional[str] = Field(
+        None,
+        description=(
+            "Directory of an extracted `cribl diag` bundle to analyze offline. "
+            "deployment_name then only labels the results."
+        )
+    )
 
     class Config:
         json_schema_extra = {
@@ -82,6 +94,7 @@ class AnalysisResponse(BaseModel):
     progress_percent: int = 0
     current_step: Optional[str] = None
     api_calls_used: int = 0
+    error: Optional[str] = None
 
 
 class AnalysisResultResponse(BaseModel):
@@ -100,7 +113,8 @@ class AnalysisResultResponse(BaseModel):
 async def run_analysis_task(
     analysis_id: str,
     deployment_name: str,
-    analyzers_to_run: Optional[List[str]]
+    analyzers_to_run: Optional[List[str]],
+    bundle_path: Optional[str] = None,
 ):
     """
     Background task to run the analysis.
@@ -109,7 +123,16 @@ async def run_analysis_task(
         analysis_id: Unique analysis identifier
         deployment_name: Name of the deployment to analyze
         analyzers_to_run: List of analyzer names to run
+        bundle_path: Extracted diag bundle to read instead of a live deployment
     """
+    # Cancelled before it got to run
+    if analysis_results[analysis_id]["status"] == AnalysisStatus.CANCELLED:
+        return
+
+    task = asyncio.current_task()
+    if task is not None:
+        running_tasks[analysis_id] = task
+
     try:
         # Update status to running
         analysis_results[analysis_id]["status"] = AnalysisStatus.RUNNING
@@ -117,33 +140,40 @@ async def run_analysis_task(
 
         log.info("analysis_started", analysis_id=analysis_id, deployment=deployment_name)
 
-        # Load credentials
-        credentials = load_credentials()
-        if deployment_name not in credentials:
-            raise ValueError(f"Deployment '{deployment_name}' not found")
-
-        cred = credentials[deployment_name]
-        auth_type = cred.get("auth_type", "bearer")
-
-        # Create API client
-        if auth_type == "oauth":
-            client = CriblAPIClient(
-                base_url=cred["url"],
-                client_id=cred["client_id"],
-                client_secret=cred["client_secret"],
-            )
+        if bundle_path:
+            client = DiagBundleClient(bundle_path)
         else:
-            client = CriblAPIClient(
-                base_url=cred["url"],
-                auth_token=cred["token"],
-            )
+            # Load credentials
+            credentials = load_credentials()
+            if deployment_name not in credentials:
+                raise ValueError(f"Deployment '{deployment_name}' not found")
+
+            cred = credentials[deployment_name]
+            auth_type = cred.get("auth_type", "bearer")
+
+            # Create API client
+            if auth_type == "oauth":
+                client = CriblAPIClient(
+                    base_url=cred["url"],
+                    client_id=cred["client_id"],
+                    client_secret=cred["client_secret"],
+                )
+            else:
+                client = CriblAPIClient(
+                    base_url=cred["url"],
+                    auth_token=cred["token"],
+                )
 
         # Create orchestrator and run analysis
         async with client:
             orchestrator = AnalyzerOrchestrator(client=client)
 
             # Run analysis with specified analyzers (or None for all)
-            results = await orchestrator.run_analysis(objectives=analyzers_to_run)
+            results = await orchestrator.run_analysis(
+                objectives=analyzers_to_run,
+                progress_callback=lambda progress: _report_progress(analysis_id, progress),
+                stage_callback=lambda objective, stage: _report_stage(analysis_id, objective, stage),
+            )
 
             # Create analysis run from results
             analysis_run = orchestrator.create_analysis_run(results, deployment_name)
@@ -173,6 +203,19 @@ async def run_analysis_task(
             health_score=analysis_run.health_score.overall_score if analysis_run.health_score else None,
         )
 
+    except asyncio.CancelledError:
+        log.info("analysis_cancelled", analysis_id=analysis_id)
+        analysis_results[analysis_id].update({
+            "status": AnalysisStatus.CANCELLED,
+            "completed_at": datetime.utcnow(),
+            "error": "Analysis was cancelled",
+        })
+        await notify_websocket_clients(analysis_id, {
+            "type": "error",
+            "analysis_id": analysis_id,
+            "error": "Analysis was cancelled",
+        })
+
     except Exception as e:
         log.error("analysis_failed", analysis_id=analysis_id, error=str(e))
         analysis_results[analysis_id].update({
@@ -188,6 +231,44 @@ async def run_analysis_task(
             "error": str(e),
         })
 
+    finally:
+        running_tasks.pop(analysis_id, None)
+
+
+# The event loop only keeps weak references to tasks
+_pending_notifications: set = set()
+
+
+def _push(analysis_id: str, message: dict):
+    """Send a message to WebSocket clients from synchronous orchestrator callbacks."""
+    task = asyncio.get_running_loop().create_task(notify_websocket_clients(analysis_id, message))
+    _pending_notifications.add(task)
+    task.add_done_callback(_pending_notifications.discard)
+
+
+def _report_progress(analysis_id: str, progress: AnalysisProgress):
+    """Record orchestrator progress and push it to WebSocket clients."""
+    percent = int(progress.get_percentage())
+    analysis_results[analysis_id]["progress_percent"] = percent
+    analysis_results[analysis_id]["api_calls_used"] = progress.api_calls_used
+    _push(analysis_id, {
+        "type": "progress",
+        "percent": percent,
+        "step": analysis_results[analysis_id].get("current_step"),
+        "completed": progress.completed_objectives,
+        "total": progress.total_objectives,
+    })
+
+
+def _report_stage(analysis_id: str, objective: str, stage: str):
+    """Record which analyzer is running and push status changes to WebSocket clients."""
+    analysis_results[analysis_id]["current_step"] = objective if stage == "running" else None
+    _push(analysis_id, {
+        "type": "stage",
+        "objective": objective,
+        "status": stage,
+    })
+
 
 async def notify_websocket_clients(analysis_id: str, message: dict):
     """
@@ -225,6 +306,12 @@ async def start_analysis(request: AnalysisRequest, background_tasks: BackgroundT
 
     Returns immediately with analysis_id and status 'pending'.
     """
+    if request.bundle_path and not os.path.isdir(request.bundle_path):
+        raise HTTPException(
+            status_code=status.HTTP_400_BAD_REQUEST,
+            detail=f"Diag bundle not found: {request.bundle_path}"
+        )
+
     try:
         # Generate unique analysis ID
         analysis_id = str(uuid.uuid4())
@@ -250,6 +337,7 @@ async def start_analysis(request: AnalysisRequest, background_tasks: BackgroundT
             "api_calls_used": 0,
             "findings": [],
             "recommendations": [],
+            "bundle_path": request.bundle_path,
         }
 
         # Start background task
@@ -257,7 +345,8 @@ async def start_analysis(request: AnalysisRequest, background_tasks: BackgroundT
             run_analysis_task,
             analysis_id,
             request.deployment_name,
-            request.analyzers
+            request.analyzers,
+            request.bundle_path,
         )
 
         log.info("analysis_queued", analysis_id=analysis_id, deployment=request.deployment_name)
@@ -334,6 +423,7 @@ async def get_analysis(analysis_id: str):
         progress_percent=data.get("progress_percent", 0),
         current_step=data.get("current_step"),
         api_calls_used=data.get("api_calls_used", 0),
+        error=data.get("error"),
     )
 
 
@@ -353,7 +443,7 @@ async def get_analysis_results(analysis_id: str):
 
     data = analysis_results[analysis_id]
 
-    if data["status"] not in [AnalysisStatus.COMPLETED, AnalysisStatus.FAILED]:
+    if data["status"] not in [AnalysisStatus.COMPLETED, AnalysisStatus.FAILED, AnalysisStatus.CANCELLED]:
         raise HTTPException(
             status_code=status.HTTP_409_CONFLICT,
             detail=f"Analysis is still {data['status']}. Results not available yet."
@@ -404,6 +494,38 @@ async def delete_analysis(analysis_id: str):
     return None
 
 
+@router.post("/{analysis_id}/cancel", response_model=AnalysisResponse)
+async def cancel_analysis(analysis_id: str):
+    """
+    Cancel a pending or running analysis.
+
+    Cancelling one that has already finished is a no-op; the response shows how it ended.
+    """
+    if analysis_id not in analysis_results:
+        raise HTTPException(
+            status_code=status.HTTP_404_NOT_FOUND,
+            detail=f"Analysis '{analysis_id}' not found"
+        )
+
+    data = analysis_results[analysis_id]
+
+    if data["status"] == AnalysisStatus.PENDING:
+        data.update({
+            "status": AnalysisStatus.CANCELLED,
+            "completed_at": datetime.utcnow(),
+            "error": "Analysis was cancelled",
+        })
+    elif data["status"] == AnalysisStatus.RUNNING and analysis_id in running_tasks:
+        task = running_tasks[analysis_id]
+        task.cancel()
+        # Let the task record the cancellation before answering
+        await asyncio.wait([task], timeout=5)
+
+    log.info("analysis_cancel_requested", analysis_id=analysis_id, status=data["status"])
+
+    return await get_analysis(analysis_id)
+
+
 @router.get("/{analysis_id}/export/{format}")
 async def export_analysis(analysis_id: str, format: str):
     """
@@ -422,7 +544,7 @@ async def export_analysis(analysis_id: str, format: str):
 
     data = analysis_results[analysis_id]
 
-    if data["status"] not in [AnalysisStatus.COMPLETED, AnalysisStatus.FAILED]:
+    if data["status"] not in [AnalysisStatus.COMPLETED, AnalysisStatus.FAILED, AnalysisStatus.CANCELLED]:
         raise HTTPException(
             status_code=status.HTTP_409_CONFLICT,
             detail=f"Analysis is still {data['status']}. Cannot export incomplete analysis."
@@ -649,7 +771,8 @@ async def websocket_analysis_updates(websocket: WebSocket, analysis_id: str):
     - Completion notification
 
     Messages format:
-    - {"type": "progress", "percent": 45, "step": "Analyzing workers..."}
+    - {"type": "progress", "percent": 45, "step": "health", "completed": 1, "total": 3}
+    - {"type": "stage", "objective": "health", "status": "running"}
     - {"type": "finding", "finding": {...}}
     - {"type": "complete", "health_score": 87}
     - {"type": "error", "error": "..."}
@@ -4,12 +4,31 @@ System-level API endpoints.
 Provides version info, health checks, and system metadata.
 """
 
-from fastapi import APIRouter
+import asyncio
+import hmac
+import os
+import platform
+import signal
+import sys
+from typing import Optional
+
+from fastapi import APIRouter, Header, HTTPException, Request
 
 from cribl_hc import __version__
 
 router = APIRouter()
 
+# Environment variables worth showing in the runtime config
+_CONFIG_ENV_PREFIXES = ("CRIBL_", "PYTHON")
+_SECRET_MARKERS = ("TOKEN", "SECRET", "PASSWORD", "KEY", "CREDENTIAL")
+
+# Signals this process handles by reloading config in place. SIGHUP's default
+# action is to terminate, so the desktop app only sends signals listed here.
+_RELOAD_SIGNALS: list = []
+
+# Set by the desktop app for each launch; only a caller holding it may stop the server
+_SHUTDOWN_TOKEN_ENV = "CRIBL_HC_SHUTDOWN_TOKEN"
+
 
 @router.get("/version")
 async def get_version():
@@ -28,3 +47,58 @@ async def get_version():
             "real_time_analysis": True,
         }
     }
+
+
+@router.get("/config")
+async def get_runtime_config(request: Request):
+    """
+    Get the configuration this process is actually running with.
+
+    Reports how the server was launched, the relevant environment (secrets
+    redacted) and the CORS origins in effect.
+    """
+    environment = {
+        name: "***" if any(marker in name for marker in _SECRET_MARKERS) else value
+        for name, value in sorted(os.environ.items())
+        if name.startswith(_CONFIG_ENV_PREFIXES)
+    }
+
+    cors_origins = []
+    for middleware in request.app.user_middleware:
+        options = getattr(middleware, "kwargs", None) or getattr(middleware, "options", {})
+        cors_origins.extend(options.get("allow_origins", []))
+
+    return {
+        "version": __version__,
+        "python_version": platform.python_version(),
+        "pid": os.getpid(),
+        "frozen": bool(getattr(sys, "frozen", False)),
+        "argv": sys.argv[1:],
+        "environment": environment,
+        "cors_origins": cors_origins,
+        "reload_signals": _RELOAD_SIGNALS,
+    }
+
+
+def _schedule_exit():
+    """Stop the server the way Ctrl+C would, once the response has gone out."""
+    asyncio.get_running_loop().call_later(0.1, signal.raise_signal, signal.SIGINT)
+
+
+@router.post("/shutdown", status_code=202)
+async def request_shutdown(x_shutdown_token: Optional[str] = Header(default=None)):
+    """
+    Shut the server down gracefully.
+
+    Only available when launched by the desktop app, which passes the token it
+    expects in the environment; uvicorn then finishes in-flight requests and
+    runs shutdown handlers before exiting.
+    """
+    expected = os.environ.get(_SHUTDOWN_TOKEN_ENV)
+    if not expected:
+        raise HTTPException(status_code=404, detail="Not Found")
+    if not x_shutdown_token or not hmac.compare_digest(x_shutdown_token, expected):
+        raise HTTPException(status_code=403, detail="Invalid shutdown token")
+
+    _schedule_exit()
+    return {"status": "shutting_down"}
@@ -3,10 +3,15 @@ Cribl API client with rate limiting, error handling, and connection testing.
 """
 
 import asyncio
+import json
+import os
+import ssl
+import tempfile
 from datetime import datetime
 from typing import Any, Dict, List, Optional
 from urllib.parse import urljoin
 
+import certifi
 import httpx
 from pydantic import BaseModel, Field
 
@@ -16,6 +21,48 @@ from cribl_hc.utils.rate_limiter import RateLimiter
 
 log = get_logger(__name__)
 
+# Set by the desktop app: {"<leader url>": {"cert": "<PEM>", "key": "<PEM>"}}
+CLIENT_IDENTITIES_ENV = "CRIBL_HC_CLIENT_IDENTITIES"
+
+
+def client_ssl_context(base_url: str) -> Optional[ssl.SSLContext]:
+    """
+    Build an SSL context presenting the client certificate registered for a leader.
+
+    Args:
+        base_url: Leader URL, matched against the keys of CRIBL_HC_CLIENT_IDENTITIES
+
+    Returns:
+        SSL context with the client certificate loaded, or None if the leader has none
+    """
+    raw = os.environ.get(CLIENT_IDENTITIES_ENV)
+    if not raw:
+        return None
+    try:
+        identity = json.loads(raw).get(base_url.rstrip("/"))
+    except (ValueError, AttributeError):
+        log.warning("client_identities_invalid", env=CLIENT_IDENTITIES_ENV)
+        return None
+    if not identity:
+        return None
+
+    # Same trust store httpx would use, including SSL_CERT_FILE from the app's CA setting
+    context = ssl.create_default_context(cafile=os.environ.get("SSL_CERT_FILE") or certifi.where())
+
+    # load_cert_chain only reads files; keep the key on disk just long enough to load it
+    paths = []
+    try:
+        for pem in (identity["cert"], identity["key"]):
+            fd, path = tempfile.mkstemp(suffix=".pem")
+            paths.append(path)
+            with os.fdopen(fd, "w") as f:
+                f.write(pem)
+        context.load_cert_chain(certfile=paths[0], keyfile=paths[1])
+    finally:
+        for path in paths:
+            os.unlink(path)
+    return context
+
 
 class ConnectionTestResult(BaseModel):
     """
@@ -112,11 +159,17 @@ class CriblAPIClient:
             "User-Agent": "cribl-health-check/1.0",
         }
 
+        client_options = {}
+        ssl_context = client_ssl_context(self.base_url)
+        if ssl_context is not None:
+            client_options["verify"] = ssl_context
+
         self._client = httpx.AsyncClient(
             base_url=self.base_url,
             headers=headers,
             timeout=self.timeout,
             follow_redirects=True,
+            **client_options,
         )
 
         # Auto-detect worker group for Cribl Cloud deployments
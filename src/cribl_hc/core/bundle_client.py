@@ -0,0 +1,181 @@
+"""
+Offline API client that reads an extracted `cribl diag` bundle.
+
+Air-gapped customers can't point the tool at a live leader, but they can hand
+over a diag bundle. DiagBundleClient answers the same reads as CriblAPIClient
+from the configuration files inside the bundle, so every analyzer runs
+unchanged. Endpoints the bundle has no data for return 404, which the client
+methods already treat as "not available".
+"""
+
+from pathlib import Path
+from typing import Any, Dict, List, Optional
+
+import httpx
+import yaml
+
+from cribl_hc.core.api_client import CriblAPIClient
+from cribl_hc.utils.logger import get_logger
+
+
+log = get_logger(__name__)
+
+# How deep below the bundle root to look for `local/cribl` config directories
+MAX_SEARCH_DEPTH = 6
+
+
+class DiagBundleClient(CriblAPIClient):
+    """
+    CriblAPIClient stand-in backed by an extracted diag bundle.
+
+    Example:
+        >>> async with DiagBundleClient("/tmp/diag-leader01") as client:
+        ...     pipelines = await client.get_pipelines()
+    """
+
+    def __init__(self, bundle_path: str, worker_group: Optional[str] = None):
+        """
+        Initialize the offline client.
+
+        Args:
+            bundle_path: Directory the diag bundle was extracted to
+            worker_group: Group whose configuration to analyze (default: the
+                first group in the bundle, or the node's own configuration)
+        """
+        super().__init__(base_url="http://diag-bundle", auth_token="", worker_group=worker_group)
+        self.bundle_path = Path(bundle_path)
+        if not self.bundle_path.is_dir():
+            raise ValueError(f"Diag bundle not found: {bundle_path}")
+
+        self._groups = self._find_config_dirs()
+        if worker_group and worker_group not in self._groups:
+            raise ValueError(f"Worker group '{worker_group}' not found in diag bundle")
+        if not self._worker_group and self._groups:
+            self._worker_group = next(iter(self._groups))
+
+        # Diag bundles come from self-hosted Stream; Edge and Lake aren't supported offline
+        self._product_type = "stream"
+        self._deployment_detected = True
+
+    def _find_config_dirs(self) -> Dict[str, Path]:
+        """
+        Map each worker group in the bundle to its `local/cribl` directory.
+
+        Leader bundles keep group configuration under `groups/<name>/local/cribl`;
+        a worker's bundle has only its own `local/cribl`, listed as "default".
+        """
+        groups: Dict[str, Path] = {}
+        node_config: Optional[Path] = None
+
+        pending = [(self.bundle_path, 0)]
+        while pending:
+            directory, depth = pending.pop()
+            try:
+                children = sorted(p for p in directory.iterdir() if p.is_dir() and not p.is_symlink())
+            except OSError:
+                continue
+            for child in children:
+                if child.name == "cribl" and child.parent.name == "local":
+                    owner = child.parent.parent
+                    if owner.parent.name == "groups":
+                        groups.setdefault(owner.name, child)
+                    elif node_config is None:
+                        node_config = child
+                elif depth < MAX_SEARCH_DEPTH:
+                    pending.append((child, depth + 1))
+
+        if not groups and node_config is not None:
+            groups["default"] = node_config
+        return dict(sorted(groups.items()))
+
+    @property
+    def worker_groups(self) -> List[str]:
+        """Worker groups whose configuration the bundle contains."""
+        return list(self._groups)
+
+    def _config_dir(self) -> Optional[Path]:
+        return self._groups.get(self._worker_group or "default")
+
+    def _load_yaml(self, path: Path) -> Dict[str, Any]:
+        """A config file's top-level mapping; empty if it can't be read."""
+        try:
+            data = yaml.safe_load(path.read_text(encoding="utf-8"))
+        except (OSError, yaml.YAMLError) as e:
+            log.warning("diag_bundle_file_unreadable", path=str(path), error=str(e))
+            return {}
+        return data if isinstance(data, dict) else {}
+
+    def _keyed_items(self, filename: str, key: str) -> Optional[List[Dict[str, Any]]]:
+        """Items from files like `inputs.yml`, which map ids to configurations."""
+        config_dir = self._config_dir()
+        if config_dir is None or not (config_dir / filename).is_file():
+            return None
+        entries = self._load_yaml(config_dir / filename).get(key)
+        if not isinstance(entries, dict):
+            return []
+        return [
+            {"id": item_id, **(config if isinstance(config, dict) else {})}
+            for item_id, config in entries.items()
+        ]
+
+    def _pipelines(self) -> Optional[List[Dict[str, Any]]]:
+        config_dir = self._config_dir()
+        if config_dir is None or not (config_dir / "pipelines").is_dir():
+            return None
+        pipelines = []
+        for conf in sorted((config_dir / "pipelines").glob("*/conf.yml")):
+            pipelines.append({"id": conf.parent.name, "conf": self._load_yaml(conf)})
+        return pipelines
+
+    def _routes(self) -> Optional[List[Dict[str, Any]]]:
+        config_dir = self._config_dir()
+        route_file = config_dir / "pipelines" / "route.yml" if config_dir else None
+        if route_file is None or not route_file.is_file():
+            return None
+        table = self._load_yaml(route_file)
+        return [{"id": table.get("id", "default"), "routes": table.get("routes") or []}]
+
+    def _resolve(self, endpoint: str) -> Optional[Dict[str, Any]]:
+        """The JSON a leader would return for `endpoint`, or None when the bundle can't answer."""
+        path = endpoint.split("?", 1)[0].rstrip("/")
+        resource = path.rsplit("/", 1)[-1]
+
+        if path == "/api/v1/master/groups":
+            return {"items": [{"id": name} for name in self._groups]}
+
+        items = None
+        if resource == "pipelines":
+            items = self._pipelines()
+        elif resource == "routes":
+            items = self._routes()
+        elif resource == "inputs":
+            items = self._keyed_items("inputs.yml", "inputs")
+        elif resource == "outputs":
+            items = self._keyed_items("outputs.yml", "outputs")
+        return None if items is None else {"items": items, "count": len(items)}
+
+    async def __aenter__(self):
+        """Nothing to connect to."""
+        return self
+
+    async def __aexit__(self, exc_type, exc_val, exc_tb):
+        pass
+
+    async def get(self, endpoint: str, **kwargs) -> httpx.Response:
+        """
+        Answer a GET from the bundle.
+
+        Returns:
+            httpx.Response with status 200 and the data, or 404
+        """
+        request = httpx.Request("GET", f"{self.base_url}{endpoint}")
+        data = self._resolve(endpoint)
+        log.debug("diag_bundle_request", endpoint=endpoint, found=data is not None)
+        if data is None:
+            return httpx.Response(404, json={"message": "Not in diag bundle"}, request=request)
+        return httpx.Response(200, json=data, request=request)
+
+    async def post(self, endpoint: str, **kwargs) -> httpx.Response:
+        """Bundles are read-only."""
+        request = httpx.Request("POST", f"{self.base_url}{endpoint}")
+        return httpx.Response(405, json={"message": "Diag bundles are read-only"}, request=request)
@@ -117,6 +117,7 @@ class AnalyzerOrchestrator:
         self,
         objectives: list[str] | None = None,
         progress_callback: Callable[[Any], None] | None = None,
+        stage_callback: Callable[[str, str], None] | None = None,
     ) -> dict[str, AnalyzerResult]:
         """
         Run health check analysis for specified objectives.
@@ -126,6 +127,9 @@ class AnalyzerOrchestrator:
                        If None, runs all registered analyzers
             progress_callback: Optional callback function(progress: AnalysisProgress)
                               called after each objective completes
+            stage_callback: Optional callback function(objective, status) called
+                           with "running" as each objective starts and "completed"
+                           or "failed" as it ends
 
         Returns:
             Dictionary mapping objective names to AnalyzerResult objects
@@ -200,6 +204,7 @@ class AnalyzerOrchestrator:
             # Update progress
             self.progress.start_objective(objective)
             self.progress.update_api_calls(api_calls_used, api_calls_remaining)
+            self._notify_stage(stage_callback, objective, "running")
 
             # Run analyzer
             try:
@@ -226,6 +231,11 @@ class AnalyzerOrchestrator:
 
             # Mark objective complete
             self.progress.complete_objective()
+            self._notify_stage(
+                stage_callback,
+                objective,
+                "completed" if results[objective].success else "failed",
+            )
 
             # Update API call tracking
             api_calls_used = self.client.get_api_calls_used()
@@ -501,6 +511,20 @@ class AnalyzerOrchestrator:
             components=component_scores,
         )
 
+    def _notify_stage(
+        self,
+        stage_callback: Callable[[str, str], None] | None,
+        objective: str,
+        status: str,
+    ) -> None:
+        """Report an objective's status, never letting the callback fail the analysis."""
+        if not stage_callback:
+            return
+        try:
+            stage_callback(objective, status)
+        except Exception as e:
+            self.log.warning("stage_callback_failed", error=str(e))
+
     def get_progress(self) -> AnalysisProgress | None:
         """
         Get current analysis progress.
@@ -5,6 +5,7 @@ This module provides functionality to load, filter, and evaluate BestPracticeRul
 from YAML configuration files.
 """
 
+import os
 import re
 from pathlib import Path
 from typing import Any, Dict, List, Optional
@@ -18,6 +19,37 @@ from cribl_hc.utils.version import parse_version
 
 log = get_logger(__name__)
 
+# Set by the desktop app to the directory it installs rule packs into. Its
+# "active" file names the pack to use, so switching packs needs no restart.
+RULEPACKS_DIR_ENV = "CRIBL_HC_RULEPACKS_DIR"
+ACTIVE_PACK_FILE = "active"
+
+
+def installed_rulepack_dir() -> Optional[Path]:
+    """
+    Find the rule pack the desktop app has made active, if any.
+
+    Returns:
+        Directory holding the active pack's cribl_rules.yaml, or None to use
+        the rules bundled with the package
+    """
+    root = os.environ.get(RULEPACKS_DIR_ENV)
+    if not root:
+        return None
+
+    try:
+        version = (Path(root) / ACTIVE_PACK_FILE).read_text().strip()
+    except OSError:
+        return None
+    if not version or "/" in version or "\\" in version or version.startswith("."):
+        return None
+
+    pack_dir = Path(root) / version
+    if not (pack_dir / "cribl_rules.yaml").is_file():
+        log.warning("rulepack_missing", version=version, path=str(pack_dir))
+        return None
+    return pack_dir
+
 
 class RuleLoader:
     """
@@ -35,11 +67,11 @@ class RuleLoader:
 
         Args:
             rules_dir: Directory containing rule YAML files.
-                      Defaults to package rules directory.
+                      Defaults to the active rule pack, else the package
+                      rules directory.
         """
         if rules_dir is None:
-            # Default to package rules directory
-            rules_dir = Path(__file__).parent
+            rules_dir = installed_rulepack_dir() or Path(__file__).parent
 
         self.rules_dir = Path(rules_dir)
         self._rules_cache: Optional[List[BestPracticeRule]] = None
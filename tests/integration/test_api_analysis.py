@@ -12,6 +12,7 @@ Tests the full analysis workflow including:
 import pytest
 import asyncio
 import json
+from datetime import datetime
 from httpx import AsyncClient, ASGITransport
 from unittest.mock import AsyncMock, patch, MagicMock
 
@@ -200,6 +201,70 @@ class TestAnalysisWorkflow:
         assert response.status_code in [204, 404, 405]
 
 
+class TestAnalysisCancel:
+    """Test cancelling analyses."""
+
+    @pytest.mark.asyncio
+    async def test_cancel_pending_analysis(self, async_client, test_credential):
+        """A pending analysis is marked cancelled and never starts."""
+        analysis_request = {
+            "deployment_name": test_credential["name"],
+            "analyzers": ["health"]
+        }
+
+        with patch('cribl_hc.api.routers.analysis.run_analysis_task'):
+            start_response = await async_client.post("/api/v1/analysis", json=analysis_request)
+            analysis_id = start_response.json()["analysis_id"]
+
+        response = await async_client.post(f"/api/v1/analysis/{analysis_id}/cancel")
+
+        assert response.status_code == 200
+        data = response.json()
+        assert data["status"] == "cancelled"
+        assert data["error"] == "Analysis was cancelled"
+
+    @pytest.mark.asyncio
+    async def test_cancel_running_analysis(self):
+        """Cancelling a running analysis stops its task and records the cancellation."""
+        from cribl_hc.api.routers import analysis
+
+        analysis_id = "cancel-running-test"
+        analysis.analysis_results[analysis_id] = {
+            "analysis_id": analysis_id,
+            "deployment_name": "test",
+            "status": analysis.AnalysisStatus.PENDING,
+            "created_at": datetime.utcnow(),
+            "analyzers": ["health"],
+        }
+        started = asyncio.Event()
+
+        async def never_finishes(*args, **kwargs):
+            started.set()
+            await asyncio.sleep(3600)
+
+        with patch('cribl_hc.api.routers.analysis.load_credentials',
+                   return_value={"test": {"url": "https://cribl.example.com", "token": "t"}}), \
+             patch('cribl_hc.api.routers.analysis.CriblAPIClient'), \
+             patch('cribl_hc.api.routers.analysis.AnalyzerOrchestrator') as orchestrator:
+            orchestrator.return_value.run_analysis = never_finishes
+            task = asyncio.create_task(analysis.run_analysis_task(analysis_id, "test", ["health"]))
+            await asyncio.wait_for(started.wait(), timeout=5)
+
+            response = await analysis.cancel_analysis(analysis_id)
+
+        assert task.done()
+        assert response.status == analysis.AnalysisStatus.CANCELLED
+        assert analysis_id not in analysis.running_tasks
+        del analysis.analysis_results[analysis_id]
+
+    @pytest.mark.asyncio
+    async def test_cancel_nonexistent_analysis(self, async_client):
+        """Cancelling an unknown analysis returns 404."""
+        response = await async_client.post("/api/v1/analysis/nonexistent-id-12345/cancel")
+
+        assert response.status_code == 404
+
+
 class TestAnalysisExport:
     """Test analysis export functionality."""
 
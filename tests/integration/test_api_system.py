@@ -0,0 +1,141 @@
+"""
+Integration tests for system API router.
+
+Tests version, health, runtime configuration and shutdown endpoints, and the
+API token the desktop app's gateway adds to requests.
+"""
+
+import time
+
+import pytest
+from httpx import AsyncClient, ASGITransport
+
+from cribl_hc import __version__
+from cribl_hc.api.app import app
+from cribl_hc.api.routers import system
+
+
+@pytest.fixture
+async def async_client():
+    """Create async test client for the API."""
+    transport = ASGITransport(app=app)
+    async with AsyncClient(transport=transport, base_url="http://test") as client:
+        yield client
+
+
+class TestSystemAPI:
+    """Test system API endpoints."""
+
+    @pytest.mark.asyncio
+    async def test_get_version(self, async_client):
+        """Test version information."""
+        response = await async_client.get("/api/v1/version")
+
+        assert response.status_code == 200
+        data = response.json()
+        assert data["version"] == __version__
+        assert data["api_version"] == "v1"
+
+    @pytest.mark.asyncio
+    async def test_health_reports_server_time(self, async_client):
+        """Test health check includes the server clock for skew checks."""
+        before = int(time.time() * 1000)
+        response = await async_client.get("/health")
+        after = int(time.time() * 1000)
+
+        assert response.status_code == 200
+        assert before <= response.json()["timestamp_ms"] <= after
+
+    @pytest.mark.asyncio
+    async def test_get_runtime_config(self, async_client):
+        """Test runtime configuration reports the process and CORS origins."""
+        response = await async_client.get("/api/v1/config")
+
+        assert response.status_code == 200
+        data = response.json()
+        assert data["version"] == __version__
+        assert isinstance(data["pid"], int)
+        assert isinstance(data["argv"], list)
+        assert "http://localhost:5173" in data["cors_origins"]
+        assert isinstance(data["reload_signals"], list)
+
+    @pytest.mark.asyncio
+    async def test_runtime_config_redacts_secrets(self, async_client, monkeypatch):
+        """Test secret-looking environment variables are redacted."""
+        monkeypatch.setenv("CRIBL_AUTH_TOKEN", "super-secret")
+        monkeypatch.setenv("CRIBL_LOG_LEVEL", "debug")
+
+        response = await async_client.get("/api/v1/config")
+
+        environment = response.json()["environment"]
+        assert environment["CRIBL_AUTH_TOKEN"] == "***"
+        assert environment["CRIBL_LOG_LEVEL"] == "debug"
+
+    @pytest.mark.asyncio
+    async def test_shutdown_unavailable_without_token(self, async_client, monkeypatch):
+        """Test shutdown is hidden unless the desktop app configured a token."""
+        monkeypatch.delenv("CRIBL_HC_SHUTDOWN_TOKEN", raising=False)
+
+        response = await async_client.post("/api/v1/shutdown")
+
+        assert response.status_code == 404
+
+    @pytest.mark.asyncio
+    async def test_shutdown_rejects_wrong_token(self, async_client, monkeypatch):
+        """Test shutdown requires the launch token."""
+        monkeypatch.setenv("CRIBL_HC_SHUTDOWN_TOKEN", "expected")
+        monkeypatch.setattr(system, "_schedule_exit", lambda: pytest.fail("should not exit"))
+
+        response = await async_client.post(
+            "/api/v1/shutdown", headers={"X-Shutdown-Token": "wrong"}
+        )
+
+        assert response.status_code == 403
+
+    @pytest.mark.asyncio
+    async def test_shutdown_with_token(self, async_client, monkeypatch):
+        """Test a valid token schedules a graceful exit."""
+        scheduled = []
+        monkeypatch.setenv("CRIBL_HC_SHUTDOWN_TOKEN", "expected")
+        monkeypatch.setattr(system, "_schedule_exit", lambda: scheduled.append(True))
+
+        response = await async_client.post(
+            "/api/v1/shutdown", headers={"X-Shutdown-Token": "expected"}
+        )
+
+        assert response.status_code == 202
+        assert scheduled == [True]
+
+    @pytest.mark.asyncio
+    async def test_api_token_required_when_configured(self, async_client, monkeypatch):
+        """Test requests without the gateway's token are rejected."""
+        monkeypatch.setenv("CRIBL_HC_API_TOKEN", "session")
+
+        response = await async_client.get("/api/v1/version")
+
+        assert response.status_code == 401
+
+    @pytest.mark.asyncio
+    async def test_api_token_accepted(self, async_client, monkeypatch):
+        """Test requests carrying the gateway's token go through."""
+        monkeypatch.setenv("CRIBL_HC_API_TOKEN", "session")
+
+        response = await async_client.get(
+            "/api/v1/version", headers={"X-Cribl-HC-Token": "session"}
+        )
+
+        assert response.status_code == 200
+
+    @pytest.mark.asyncio
+    async def test_shutdown_does_not_need_api_token(self, async_client, monkeypatch):
+        """Test shutdown stays reachable with only its own token."""
+        scheduled = []
+        monkeypatch.setenv("CRIBL_HC_API_TOKEN", "session")
+        monkeypatch.setenv("CRIBL_HC_SHUTDOWN_TOKEN", "expected")
+        monkeypatch.setattr(system, "_schedule_exit", lambda: scheduled.append(True))
+
+        response = await async_client.post(
+            "/api/v1/shutdown", headers={"X-Shutdown-Token": "expected"}
+        )
+
+        assert response.status_code == 202
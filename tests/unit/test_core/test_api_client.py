@@ -2,12 +2,21 @@
 Unit tests for CriblAPIClient and connection testing.
 """
 
+import json
+import os
+import ssl
+
 import pytest
 import httpx
 import respx
 from datetime import datetime
 
-from cribl_hc.core.api_client import CriblAPIClient, ConnectionTestResult
+from cribl_hc.core.api_client import (
+    CLIENT_IDENTITIES_ENV,
+    ConnectionTestResult,
+    CriblAPIClient,
+    client_ssl_context,
+)
 
 
 class TestConnectionTestResult:
@@ -85,6 +94,52 @@ class TestCriblAPIClient:
         assert client.max_retries == 5
 
 
+class TestClientIdentities:
+    """Test mTLS client certificates passed in by the desktop app."""
+
+    def test_no_identities(self, monkeypatch):
+        """Test leaders get no client certificate when none are registered."""
+        monkeypatch.delenv(CLIENT_IDENTITIES_ENV, raising=False)
+
+        assert client_ssl_context("https://cribl.example.com") is None
+
+    def test_invalid_identities_are_ignored(self, monkeypatch):
+        """Test malformed identities don't break connecting."""
+        monkeypatch.setenv(CLIENT_IDENTITIES_ENV, "not json")
+
+        assert client_ssl_context("https://cribl.example.com") is None
+
+    def test_other_leader_gets_no_identity(self, monkeypatch):
+        """Test identities only apply to the leader they were registered for."""
+        monkeypatch.setenv(
+            CLIENT_IDENTITIES_ENV,
+            json.dumps({"https://other.example.com": {"cert": "CERT", "key": "KEY"}}),
+        )
+
+        assert client_ssl_context("https://cribl.example.com") is None
+
+    def test_registered_identity_is_loaded(self, monkeypatch):
+        """Test the registered certificate is loaded and its temp files removed."""
+        monkeypatch.setenv(
+            CLIENT_IDENTITIES_ENV,
+            json.dumps({"https://cribl.example.com": {"cert": "CERT", "key": "KEY"}}),
+        )
+        loaded = {}
+
+        def fake_load_cert_chain(self, certfile, keyfile=None, password=None):
+            with open(certfile) as cert, open(keyfile) as key:
+                loaded.update(cert=cert.read(), key=key.read(), paths=(certfile, keyfile))
+
+        monkeypatch.setattr(ssl.SSLContext, "load_cert_chain", fake_load_cert_chain)
+
+        context = client_ssl_context("https://cribl.example.com/")
+
+        assert isinstance(context, ssl.SSLContext)
+        assert loaded["cert"] == "CERT"
+        assert loaded["key"] == "KEY"
+        assert not any(os.path.exists(path) for path in loaded["paths"])
+
+
 class TestConnectionTesting:
     """Test connection testing functionality with mocked HTTP responses."""
 
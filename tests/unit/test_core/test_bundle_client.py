@@ -0,0 +1,75 @@
+"""
+Unit tests for DiagBundleClient.
+"""
+
+import pytest
+
+from cribl_hc.core.bundle_client import DiagBundleClient
+
+
+@pytest.fixture
+def leader_bundle(tmp_path):
+    """A leader diag bundle with two worker groups."""
+    for group in ("default", "edge-collectors"):
+        config = tmp_path / "diag" / "groups" / group / "local" / "cribl"
+        (config / "pipelines" / "main").mkdir(parents=True)
+        (config / "pipelines" / "main" / "conf.yml").write_text(
+            "functions:\n  - id: eval\n    conf: {}\n"
+        )
+        (config / "pipelines" / "route.yml").write_text(
+            "id: default\nroutes:\n  - id: r1\n    pipeline: main\n    final: true\n"
+        )
+        (config / "inputs.yml").write_text(f"inputs:\n  {group}-syslog:\n    type: syslog\n")
+        (config / "outputs.yml").write_text("outputs:\n  devnull:\n    type: devnull\n")
+    return tmp_path
+
+
+class TestDiagBundleClient:
+    """Test suite for DiagBundleClient."""
+
+    def test_missing_bundle(self, tmp_path):
+        """Test that a missing directory is rejected."""
+        with pytest.raises(ValueError):
+            DiagBundleClient(str(tmp_path / "missing"))
+
+    def test_finds_worker_groups(self, leader_bundle):
+        """Test that group configs are found and the first group is selected."""
+        client = DiagBundleClient(str(leader_bundle))
+
+        assert client.worker_groups == ["default", "edge-collectors"]
+        assert client.worker_group == "default"
+        assert client.product_type == "stream"
+
+    def test_unknown_worker_group(self, leader_bundle):
+        """Test that asking for a group the bundle lacks fails."""
+        with pytest.raises(ValueError):
+            DiagBundleClient(str(leader_bundle), worker_group="missing")
+
+    @pytest.mark.asyncio
+    async def test_reads_configuration(self, leader_bundle):
+        """Test that config reads are answered from the bundle."""
+        async with DiagBundleClient(str(leader_bundle), worker_group="edge-collectors") as client:
+            pipelines = await client.get_pipelines()
+            routes = await client.get_routes()
+            inputs = await client.get_inputs()
+            outputs = await client.get_outputs()
+            groups = await client.get_worker_groups()
+
+        assert pipelines == [{"id": "main", "conf": {"functions": [{"id": "eval", "conf": {}}]}}]
+        assert [route["id"] for route in routes] == ["r1"]
+        assert inputs == [{"id": "edge-collectors-syslog", "type": "syslog"}]
+        assert outputs == [{"id": "devnull", "type": "devnull"}]
+        assert [group["id"] for group in groups] == ["default", "edge-collectors"]
+
+    @pytest.mark.asyncio
+    async def test_unavailable_data(self, tmp_path):
+        """Test that endpoints the bundle can't answer look unavailable."""
+        (tmp_path / "local" / "cribl").mkdir(parents=True)
+
+        async with DiagBundleClient(str(tmp_path)) as client:
+            assert client.worker_groups == ["default"]
+            assert await client.get_workers() == []
+            response = await client.get("/api/v1/system/status")
+
+        assert response.status_code == 404
+        assert client.get_api_calls_used() == 0
@@ -187,6 +187,21 @@ class TestAnalyzerOrchestrator:
                 assert progress_updates[0]["total"] == 1
                 assert progress_updates[0]["percentage"] == 100.0
 
+    @pytest.mark.asyncio
+    async def test_run_analysis_stage_callback(self, orchestrator, mock_client):
+        """Test each objective reports running, then its outcome."""
+        mock_analyzer = MockHealthAnalyzer()
+        stages = []
+
+        with patch("cribl_hc.core.orchestrator.get_analyzer", return_value=mock_analyzer):
+            with patch("cribl_hc.core.orchestrator.list_objectives", return_value=["health"]):
+                await orchestrator.run_analysis(
+                    ["health"],
+                    stage_callback=lambda objective, status: stages.append((objective, status)),
+                )
+
+        assert stages == [("health", "running"), ("health", "completed")]
+
     @pytest.mark.asyncio
     async def test_run_analysis_api_budget_tracking(self, orchestrator, mock_client):
         """Test API call budget tracking."""
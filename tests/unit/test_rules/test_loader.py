@@ -8,7 +8,7 @@ import pytest
 from pathlib import Path
 from typing import Dict, Any
 
-from cribl_hc.rules.loader import RuleLoader, RuleEvaluator
+from cribl_hc.rules.loader import RULEPACKS_DIR_ENV, RuleLoader, RuleEvaluator
 from cribl_hc.models.rule import BestPracticeRule
 
 
@@ -29,6 +29,25 @@ class TestRuleLoader:
         loader = RuleLoader(rules_dir=custom_dir)
         assert loader.rules_dir == custom_dir
 
+    def test_initialization_active_rulepack(self, tmp_path, monkeypatch):
+        """Test loader uses the rule pack the app marked active."""
+        pack_dir = tmp_path / "2026.10.1"
+        pack_dir.mkdir()
+        (pack_dir / "cribl_rules.yaml").write_text("rules: []\n")
+        (tmp_path / "active").write_text("2026.10.1\n")
+        monkeypatch.setenv(RULEPACKS_DIR_ENV, str(tmp_path))
+
+        loader = RuleLoader()
+        assert loader.rules_dir == pack_dir
+
+    def test_initialization_missing_rulepack_falls_back(self, tmp_path, monkeypatch):
+        """Test loader falls back to bundled rules when the active pack is gone."""
+        (tmp_path / "active").write_text("2026.10.1")
+        monkeypatch.setenv(RULEPACKS_DIR_ENV, str(tmp_path))
+
+        loader = RuleLoader()
+        assert loader.rules_dir.name == "rules"
+
     def test_load_rules_from_yaml(self):
         """Test loading rules from the default cribl_rules.yaml."""
         loader = RuleLoader()